@@ -0,0 +1,222 @@
+//! permessage-deflate (RFC 7692) for the outgoing side of `/ws`. The actor
+//! context writes plain frames; [`Deflater`] re-encodes each complete text
+//! or binary frame compressed, with RSV1 set, before it reaches the socket.
+//! Client frames are only control frames here and are never compressed.
+
+use actix_web::http::header::HeaderMap;
+use actix_web::web::Bytes;
+use flate2::{Compress, Compression, FlushCompress};
+
+pub const EXTENSION: &str = "permessage-deflate";
+const SYNC_TAIL: [u8; 4] = [0x00, 0x00, 0xff, 0xff];
+
+/// The accepted offer: whether the compressor restarts for every message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Negotiated {
+    pub no_context_takeover: bool,
+}
+
+impl Negotiated {
+    pub fn response_header(&self) -> String {
+        if self.no_context_takeover {
+            format!("{EXTENSION}; server_no_context_takeover")
+        } else {
+            EXTENSION.to_string()
+        }
+    }
+}
+
+/// The first `permessage-deflate` offer in `Sec-WebSocket-Extensions` we
+/// can honour. Offers that shrink the server window are declined, as the
+/// compressor always uses the full 15 bits.
+pub fn negotiate(headers: &HeaderMap) -> Option<Negotiated> {
+    let offers = headers
+        .get_all("sec-websocket-extensions")
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','));
+    for offer in offers {
+        let mut params = offer.split(';').map(str::trim);
+        if params.next() != Some(EXTENSION) {
+            continue;
+        }
+        let mut n = Negotiated {
+            no_context_takeover: false,
+        };
+        let mut ok = true;
+        for p in params {
+            let (name, value) = p.split_once('=').unwrap_or((p, ""));
+            match name.trim() {
+                "server_no_context_takeover" => n.no_context_takeover = true,
+                "server_max_window_bits" => ok &= value.trim().trim_matches('"') == "15",
+                "client_no_context_takeover" | "client_max_window_bits" => {}
+                _ => ok = false,
+            }
+        }
+        if ok {
+            return Some(n);
+        }
+    }
+    None
+}
+
+/// Compresses the data frames in a server-to-client byte stream.
+pub struct Deflater {
+    compress: Compress,
+    no_context_takeover: bool,
+    pending: Vec<u8>,
+}
+
+impl Deflater {
+    pub fn new(n: Negotiated) -> Self {
+        Self {
+            compress: Compress::new(Compression::default(), false),
+            no_context_takeover: n.no_context_takeover,
+            pending: Vec::new(),
+        }
+    }
+
+    /// Takes the next chunk of encoded frames and returns what can be sent,
+    /// holding back any trailing partial frame.
+    pub fn feed(&mut self, chunk: &[u8]) -> Bytes {
+        let mut pending = std::mem::take(&mut self.pending);
+        pending.extend_from_slice(chunk);
+        let mut out = Vec::with_capacity(pending.len());
+        let mut at = 0;
+        while let Some((header_len, payload_len)) = frame_len(&pending[at..]) {
+            let frame = &pending[at..at + header_len + payload_len];
+            let (fin, opcode) = (frame[0] & 0x80 != 0, frame[0] & 0x0f);
+            if fin && (opcode == 1 || opcode == 2) {
+                let body = self.deflate(&frame[header_len..]);
+                write_header(&mut out, 0x80 | 0x40 | opcode, body.len());
+                out.extend_from_slice(&body);
+            } else {
+                // Fragments and control frames go out as they are.
+                out.extend_from_slice(frame);
+            }
+            at += header_len + payload_len;
+        }
+        pending.drain(..at);
+        self.pending = pending;
+        Bytes::from(out)
+    }
+
+    fn deflate(&mut self, data: &[u8]) -> Vec<u8> {
+        let mut out = Vec::with_capacity(data.len() / 2 + 64);
+        let start = self.compress.total_in();
+        loop {
+            let consumed = (self.compress.total_in() - start) as usize;
+            if out.len() == out.capacity() {
+                out.reserve(256);
+            }
+            self.compress
+                .compress_vec(&data[consumed..], &mut out, FlushCompress::Sync)
+                .expect("deflate of an in-memory buffer");
+            let done = (self.compress.total_in() - start) as usize == data.len();
+            if done && out.len() < out.capacity() {
+                break;
+            }
+        }
+        if out.ends_with(&SYNC_TAIL) {
+            out.truncate(out.len() - SYNC_TAIL.len());
+        }
+        if self.no_context_takeover {
+            self.compress.reset();
+        }
+        out
+    }
+}
+
+/// Header and payload length of the unmasked frame at the start of `buf`,
+/// once all of it is there.
+fn frame_len(buf: &[u8]) -> Option<(usize, usize)> {
+    if buf.len() < 2 {
+        return None;
+    }
+    let (header, payload) = match buf[1] & 0x7f {
+        126 if buf.len() >= 4 => (4, u16::from_be_bytes([buf[2], buf[3]]) as usize),
+        127 if buf.len() >= 10 => {
+            let mut n = [0u8; 8];
+            n.copy_from_slice(&buf[2..10]);
+            (10, u64::from_be_bytes(n) as usize)
+        }
+        126 | 127 => return None,
+        n => (2, n as usize),
+    };
+    (buf.len() >= header + payload).then_some((header, payload))
+}
+
+fn write_header(out: &mut Vec<u8>, first: u8, len: usize) {
+    out.push(first);
+    match len {
+        0..=125 => out.push(len as u8),
+        126..=0xffff => {
+            out.push(126);
+            out.extend_from_slice(&(len as u16).to_be_bytes());
+        }
+        _ => {
+            out.push(127);
+            out.extend_from_slice(&(len as u64).to_be_bytes());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::http::header::HeaderValue;
+    use flate2::{Decompress, FlushDecompress};
+
+    fn text_frame(s: &str) -> Vec<u8> {
+        let mut f = Vec::new();
+        write_header(&mut f, 0x81, s.len());
+        f.extend_from_slice(s.as_bytes());
+        f
+    }
+
+    fn inflate(d: &mut Decompress, payload: &[u8]) -> String {
+        let mut input = payload.to_vec();
+        input.extend_from_slice(&SYNC_TAIL);
+        let mut out = Vec::with_capacity(4096);
+        d.decompress_vec(&input, &mut out, FlushDecompress::Sync)
+            .unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn offers_are_negotiated_and_frames_compressed_across_chunks() {
+        let mut h = HeaderMap::new();
+        h.insert(
+            actix_web::http::header::SEC_WEBSOCKET_EXTENSIONS,
+            HeaderValue::from_static("x-custom, permessage-deflate; server_max_window_bits=10, permessage-deflate; client_max_window_bits"),
+        );
+        let n = negotiate(&h).unwrap();
+        assert_eq!(n.response_header(), "permessage-deflate");
+        assert_eq!(negotiate(&HeaderMap::new()), None);
+
+        let tick = r#"{"pair":"BTC/USDT","price":100000.5,"bid":99990.5,"ask":100010.5,"ts_ms":1}"#;
+        let mut wire = text_frame(tick);
+        wire.extend(text_frame(tick));
+        wire.extend([0x89, 0x00]); // ping
+        let mut d = Deflater::new(n);
+        let mut sent = d.feed(&wire[..5]).to_vec();
+        assert!(sent.is_empty());
+        sent.extend_from_slice(&d.feed(&wire[5..]));
+
+        let mut inflater = Decompress::new(false);
+        let mut at = 0;
+        let mut texts = Vec::new();
+        while let Some((hl, pl)) = frame_len(&sent[at..]) {
+            let f = &sent[at..at + hl + pl];
+            if f[0] & 0x0f == 1 {
+                assert_eq!(f[0] & 0x40, 0x40);
+                texts.push(inflate(&mut inflater, &f[hl..]));
+            } else {
+                assert_eq!(f, [0x89, 0x00]);
+            }
+            at += hl + pl;
+        }
+        assert_eq!(texts, vec![tick, tick]);
+        // The second copy back-references the first.
+        assert!(sent.len() < wire.len());
+    }
+}
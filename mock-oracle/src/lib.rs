@@ -0,0 +1,500 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    net::TcpListener,
+    time::Duration,
+};
+
+mod deflate;
+mod market;
+mod push;
+pub mod scenario;
+
+use actix::prelude::*;
+use actix::Actor;
+use actix::AsyncContext;
+use actix_web::dev::Server;
+use actix_web::get;
+use actix_web::web;
+use actix_web::App;
+use actix_web::HttpRequest;
+use actix_web::HttpResponse;
+use actix_web::HttpServer;
+use actix_web_actors::ws;
+use futures_util::StreamExt;
+use rand::Rng;
+use serde::Deserialize;
+use serde::Serialize;
+
+use market::{synth_trades, CandleBuilder, Channel};
+use push::{PushWs, Pushed, SharedHub};
+use scenario::{Scenario, Script};
+
+/// How the oracle runs; [`Config::from_env`] reads what the binary uses.
+#[derive(Clone)]
+pub struct Config {
+    pub pairs: Vec<String>,
+    pub interval: Duration,
+    pub bands: HashMap<String, PriceBand>,
+    /// Required as `?token=` on `/ws/push` when set.
+    pub push_token: Option<String>,
+    /// Required as `Authorization: Bearer` on `/ws` when set.
+    pub auth_token: Option<String>,
+    pub gap_every: Option<u64>,
+    /// Scripted prices replacing the walk for the pairs it names.
+    pub scenario: Scenario,
+}
+
+impl Config {
+    pub fn from_env() -> Self {
+        let pairs_env =
+            std::env::var("PAIRS").unwrap_or_else(|_| "BTC/USDT,ETH/USDT,SOL/USDT".into());
+        let pairs: Vec<String> = pairs_env
+            .split(',')
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .collect();
+
+        let interval_ms: u64 = std::env::var("INTERVAL_MS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(1000);
+
+        let mut bands: HashMap<String, PriceBand> = HashMap::new();
+        bands.insert(
+            "ETH/USDT".into(),
+            PriceBand {
+                min: 3500.0,
+                max: 3501.0,
+            },
+        );
+        bands.insert(
+            "BTC/USDT".into(),
+            PriceBand {
+                min: 100_000.0,
+                max: 110_000.0,
+            },
+        );
+        bands.insert(
+            "SOL/USDT".into(),
+            PriceBand {
+                min: 200.0,
+                max: 201.0,
+            },
+        );
+
+        let scenario = match std::env::var("SCENARIO") {
+            Ok(path) => Scenario::load(&path).unwrap_or_else(|e| panic!("SCENARIO {path}: {e}")),
+            Err(_) => Scenario::default(),
+        };
+
+        Self {
+            pairs,
+            interval: Duration::from_millis(interval_ms),
+            bands,
+            push_token: std::env::var("PUSH_TOKEN").ok().filter(|t| !t.is_empty()),
+            auth_token: std::env::var("AUTH_TOKEN").ok().filter(|t| !t.is_empty()),
+            gap_every: std::env::var("GAP_EVERY").ok().and_then(|s| s.parse().ok()),
+            scenario,
+        }
+    }
+}
+
+#[derive(Clone)]
+struct AppState {
+    config: Config,
+    hub: SharedHub,
+}
+
+struct PriceWs {
+    pairs: Vec<String>,
+    interval: Duration,
+    baselines: HashMap<String, f64>,
+    bands: HashMap<String, PriceBand>,
+    hub: SharedHub,
+    id: usize,
+    /// Last `seq` handed out on this connection.
+    seq: u64,
+    /// Every `gap_every`-th message is withheld, to exercise gap handling.
+    gap_every: Option<u64>,
+    script: Script,
+    /// Recent messages by `seq`, sent or withheld, for resend requests.
+    history: VecDeque<(u64, String)>,
+    channel: Channel,
+    candles: HashMap<String, CandleBuilder>,
+}
+
+/// A message with its place in the connection's stream.
+#[derive(Serialize)]
+struct Sequenced<'a, T> {
+    #[serde(flatten)]
+    msg: &'a T,
+    seq: u64,
+}
+
+/// Messages kept per connection for resends.
+const HISTORY: usize = 1_000;
+
+impl PriceWs {
+    fn new(
+        pairs: Vec<String>,
+        interval: Duration,
+        bands: HashMap<String, PriceBand>,
+        hub: SharedHub,
+        channel: Channel,
+        gap_every: Option<u64>,
+        script: Script,
+    ) -> Self {
+        let baselines = pairs
+            .iter()
+            .map(|p| {
+                let seed = if let Some(b) = bands.get(p) {
+                    seed_price_in_band(*b)
+                } else {
+                    seed_price(p)
+                };
+                (p.clone(), seed)
+            })
+            .collect::<HashMap<_, _>>();
+
+        Self {
+            pairs,
+            interval,
+            baselines,
+            bands,
+            hub,
+            id: 0,
+            seq: 0,
+            gap_every: gap_every.filter(|n| *n > 1),
+            script,
+            history: VecDeque::with_capacity(HISTORY),
+            channel,
+            candles: HashMap::new(),
+        }
+    }
+
+    /// Numbers `msg` and sends it, unless it is one the gap option skips.
+    fn emit<T: Serialize>(&mut self, msg: &T, ctx: &mut ws::WebsocketContext<Self>) {
+        self.seq += 1;
+        let seq = self.seq;
+        let Ok(text) = serde_json::to_string(&Sequenced { msg, seq }) else {
+            return;
+        };
+        if self.history.len() == HISTORY {
+            self.history.pop_front();
+        }
+        self.history.push_back((seq, text.clone()));
+        if self.gap_every.is_some_and(|n| seq.is_multiple_of(n)) {
+            tracing::debug!(seq, "withholding message");
+            return;
+        }
+        ctx.text(text);
+    }
+
+    /// Replays the kept messages numbered `from..=to` as they were.
+    fn resend(&self, from: u64, to: u64, ctx: &mut ws::WebsocketContext<Self>) {
+        for (_, text) in self.history.iter().filter(|(s, _)| (from..=to).contains(s)) {
+            ctx.text(text.clone());
+        }
+    }
+
+    /// The connection's channel at the walk's latest price for `pair`.
+    fn publish(&mut self, pair: &str, price: f64, ctx: &mut ws::WebsocketContext<Self>) {
+        let now = now_ms();
+        match self.channel {
+            Channel::Ticks => {
+                let half_spread = price * QUOTE_HALF_SPREAD;
+                let tick = Tick {
+                    pair: pair.to_string(),
+                    price,
+                    bid: price - half_spread,
+                    ask: price + half_spread,
+                    ts_ms: now,
+                };
+                self.emit(&tick, ctx);
+            }
+            Channel::Trades => {
+                for t in synth_trades(pair, price, now) {
+                    self.emit(&t, ctx);
+                }
+            }
+            Channel::Candles => {
+                let builder = self
+                    .candles
+                    .entry(pair.to_string())
+                    .or_insert_with(|| CandleBuilder::new(pair, price, now));
+                let closed = builder.roll(now);
+                for t in synth_trades(pair, price, now) {
+                    builder.add(&t);
+                }
+                if let Some(c) = closed {
+                    self.emit(&c, ctx);
+                }
+            }
+        }
+    }
+}
+
+impl Actor for PriceWs {
+    type Context = ws::WebsocketContext<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        let addr = ctx.address().recipient();
+        self.id = self.hub.lock().unwrap().subscribe(self.pairs.clone(), addr);
+        let interval = self.interval;
+        ctx.run_interval(interval, |actor, ctx| {
+            let walking: Vec<String> = {
+                let hub = actor.hub.lock().unwrap();
+                actor
+                    .pairs
+                    .iter()
+                    .filter(|p| !hub.is_pushed(p))
+                    .cloned()
+                    .collect()
+            };
+            for pair in walking {
+                let prev = *actor.baselines.get(&pair).unwrap_or(&100.0);
+
+                let next = if let Some(px) = actor.script.next(&pair) {
+                    px
+                } else if let Some(b) = actor.bands.get(&pair) {
+                    step_price_in_band(prev, *b)
+                } else {
+                    step_price(prev)
+                };
+
+                actor.baselines.insert(pair.clone(), next);
+                actor.publish(&pair, next, ctx);
+            }
+        });
+    }
+
+    fn stopped(&mut self, _: &mut Self::Context) {
+        self.hub.lock().unwrap().unsubscribe(self.id);
+    }
+}
+
+/// Pushed prices replace the walk's; it carries on from the last one once
+/// the pair is released. Trades and candles are printed around them.
+impl Handler<Pushed> for PriceWs {
+    type Result = ();
+
+    fn handle(&mut self, Pushed(tick): Pushed, ctx: &mut Self::Context) {
+        self.baselines.insert(tick.pair.clone(), tick.price);
+        match self.channel {
+            Channel::Ticks => self.emit(&tick, ctx),
+            _ => self.publish(&tick.pair, tick.price, ctx),
+        }
+    }
+}
+
+const QUOTE_HALF_SPREAD: f64 = 0.0001;
+
+#[derive(Debug, Clone, Serialize)]
+struct Tick {
+    pair: String,
+    price: f64,
+    bid: f64,
+    ask: f64,
+    ts_ms: i64,
+}
+
+/// The oracle's HTTP server on `listener`, ready to be awaited or spawned.
+pub fn serve(config: Config, listener: TcpListener) -> std::io::Result<Server> {
+    let state = AppState {
+        config,
+        hub: SharedHub::default(),
+    };
+    Ok(HttpServer::new(move || {
+        App::new()
+            .app_data(web::Data::new(state.clone()))
+            .service(ws_endpoint)
+            .service(push_endpoint)
+    })
+    .listen(listener)?
+    .run())
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct WsQuery {
+    pair: Option<String>,
+    /// Overrides `GAP_EVERY` for this connection.
+    gap_every: Option<u64>,
+    #[serde(default)]
+    channel: Channel,
+}
+
+#[derive(Debug, Deserialize)]
+struct ResendRequest {
+    resend: SeqRange,
+}
+
+#[derive(Debug, Deserialize)]
+struct SeqRange {
+    from: u64,
+    to: u64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct PushQuery {
+    token: Option<String>,
+}
+
+impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for PriceWs {
+    fn handle(&mut self, msg: Result<ws::Message, ws::ProtocolError>, ctx: &mut Self::Context) {
+        match msg {
+            Ok(ws::Message::Ping(bytes)) => ctx.pong(&bytes),
+            Ok(ws::Message::Close(reason)) => ctx.close(reason),
+            Ok(ws::Message::Text(text)) => match serde_json::from_str::<ResendRequest>(&text) {
+                Ok(ResendRequest { resend }) => self.resend(resend.from, resend.to, ctx),
+                Err(e) => tracing::debug!("ignoring client message: {e}"),
+            },
+            Ok(ws::Message::Binary(_)) => {}
+            _ => {}
+        }
+    }
+}
+
+/// Bounds the random walk keeps a pair's price within.
+#[derive(Clone, Copy)]
+pub struct PriceBand {
+    pub min: f64,
+    pub max: f64,
+}
+
+#[get("/ws")]
+async fn ws_endpoint(
+    req: HttpRequest,
+    stream: web::Payload,
+    state: web::Data<AppState>,
+    q: web::Query<WsQuery>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let cfg = &state.config;
+    if !authorized(&req, cfg.auth_token.as_deref()) {
+        tracing::warn!("rejected /ws connection without a valid bearer token");
+        return Ok(HttpResponse::Unauthorized()
+            .insert_header(("WWW-Authenticate", "Bearer"))
+            .finish());
+    }
+    let pairs = match &q.pair {
+        Some(p) => vec![p.clone()],
+        None => cfg.pairs.clone(),
+    };
+
+    let mut selected_bands = HashMap::new();
+    for p in &pairs {
+        if let Some(b) = cfg.bands.get(p) {
+            selected_bands.insert(p.clone(), *b);
+        }
+    }
+
+    let actor = PriceWs::new(
+        pairs,
+        cfg.interval,
+        selected_bands,
+        state.hub.clone(),
+        q.channel,
+        q.gap_every.or(cfg.gap_every),
+        cfg.scenario.script(),
+    );
+    let Some(negotiated) = deflate::negotiate(req.headers()) else {
+        return ws::start(actor, &req, stream);
+    };
+    let mut res = ws::handshake(&req)?;
+    res.insert_header(("Sec-WebSocket-Extensions", negotiated.response_header()));
+    let mut deflater = deflate::Deflater::new(negotiated);
+    let frames = ws::WebsocketContext::create(actor, stream)
+        .map(move |chunk| chunk.map(|b| deflater.feed(&b)));
+    Ok(res.streaming(frames))
+}
+
+/// Whether `req` carries `token` as its bearer credential; anything goes
+/// when no token is configured.
+fn authorized(req: &HttpRequest, token: Option<&str>) -> bool {
+    let Some(token) = token else {
+        return true;
+    };
+    req.headers()
+        .get("Authorization")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .is_some_and(|given| given.trim() == token)
+}
+
+/// Client mode: prices sent here go to every `/ws` subscriber of the pair
+/// in place of the random walk.
+#[get("/ws/push")]
+async fn push_endpoint(
+    req: HttpRequest,
+    stream: web::Payload,
+    state: web::Data<AppState>,
+    q: web::Query<PushQuery>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let token = &state.config.push_token;
+    if token.is_some() && q.token != *token {
+        return Ok(HttpResponse::Unauthorized().finish());
+    }
+    ws::start(PushWs::new(state.hub.clone()), &req, stream)
+}
+
+fn seed_price(pair: &str) -> f64 {
+    let h = fxhash(pair) as f64;
+    50.0 + (h % 500.0)
+}
+
+fn fxhash(s: &str) -> u64 {
+    let mut h: u64 = 0xcbf29ce484222325;
+    for b in s.as_bytes() {
+        h ^= *b as u64;
+        h = h.wrapping_mul(0x100000001b3);
+    }
+    h
+}
+
+fn step_price_in_band(prev: f64, b: PriceBand) -> f64 {
+    let mut rng = rand::thread_rng();
+    let noise: f64 = rng.gen_range(-0.0005..0.0005);
+    let drift_towards_mid = ((b.min + b.max) / 2.0 - prev) * 0.001;
+    let next = prev * (1.0 + noise) + drift_towards_mid;
+    next.clamp(b.min, b.max)
+}
+
+fn now_ms() -> i64 {
+    use std::time::{SystemTime, UNIX_EPOCH};
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0)
+}
+
+fn seed_price_in_band(b: PriceBand) -> f64 {
+    (b.min + b.max) / 2.0
+}
+
+fn step_price(prev: f64) -> f64 {
+    let mut rng = rand::thread_rng();
+    let drift = 0.0002;
+    let noise: f64 = rng.gen_range(-0.003..0.003);
+    let next = prev * (1.0 + drift + noise);
+    next.clamp(0.01, 1_000_000.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test::TestRequest;
+
+    #[test]
+    fn ws_needs_the_configured_bearer_token() {
+        let bearer = |v: &str| {
+            TestRequest::default()
+                .insert_header(("Authorization", v.to_string()))
+                .to_http_request()
+        };
+        let anonymous = TestRequest::default().to_http_request();
+        assert!(authorized(&anonymous, None));
+        assert!(!authorized(&anonymous, Some("s3cret")));
+        assert!(!authorized(&bearer("Bearer wrong"), Some("s3cret")));
+        assert!(!authorized(&bearer("s3cret"), Some("s3cret")));
+        assert!(authorized(&bearer("Bearer s3cret"), Some("s3cret")));
+    }
+}
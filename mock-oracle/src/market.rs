@@ -0,0 +1,171 @@
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+/// What a `/ws` connection streams, picked with `?channel=`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Channel {
+    #[default]
+    Ticks,
+    Trades,
+    Candles,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Aggressor {
+    Buy,
+    Sell,
+}
+
+/// A synthetic execution on the pair's market.
+#[derive(Debug, Clone, Serialize)]
+pub struct TradePrint {
+    pub pair: String,
+    pub price: f64,
+    pub size: f64,
+    pub side: Aggressor,
+    pub ts_ms: i64,
+}
+
+pub const CANDLE_MS: i64 = 1_000;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Candle {
+    pub pair: String,
+    pub start_ms: i64,
+    pub interval_ms: i64,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+    pub volume: f64,
+    pub trades: u32,
+}
+
+/// Up to three prints around `mid`. Buys lift above it and sells hit
+/// below; sizes are worth roughly 10 to 10,000 of the quote currency.
+pub fn synth_trades(pair: &str, mid: f64, ts_ms: i64) -> Vec<TradePrint> {
+    let mut rng = rand::thread_rng();
+    (0..rng.gen_range(0..=3))
+        .map(|_| {
+            let side = if rng.gen_bool(0.5) {
+                Aggressor::Buy
+            } else {
+                Aggressor::Sell
+            };
+            let offset = mid * rng.gen_range(0.0..0.0002);
+            let price = match side {
+                Aggressor::Buy => mid + offset,
+                Aggressor::Sell => mid - offset,
+            };
+            let notional = 10f64.powf(rng.gen_range(1.0..4.0));
+            TradePrint {
+                pair: pair.to_string(),
+                price,
+                size: notional / price,
+                side,
+                ts_ms,
+            }
+        })
+        .collect()
+}
+
+/// Folds prints into consecutive one-second candles for one pair.
+#[derive(Debug, Clone)]
+pub struct CandleBuilder {
+    current: Candle,
+}
+
+impl CandleBuilder {
+    /// Starts the candle for the second holding `ts_ms`, flat at `price`.
+    pub fn new(pair: &str, price: f64, ts_ms: i64) -> Self {
+        Self {
+            current: flat(pair, price, ts_ms - ts_ms.rem_euclid(CANDLE_MS)),
+        }
+    }
+
+    /// Closes the candle once `now_ms` has left its second; the next one
+    /// opens at its close. A second without prints is a flat candle with
+    /// zero volume.
+    pub fn roll(&mut self, now_ms: i64) -> Option<Candle> {
+        let start = now_ms - now_ms.rem_euclid(CANDLE_MS);
+        if start <= self.current.start_ms {
+            return None;
+        }
+        let next = flat(&self.current.pair, self.current.close, start);
+        Some(std::mem::replace(&mut self.current, next))
+    }
+
+    pub fn add(&mut self, t: &TradePrint) {
+        let c = &mut self.current;
+        if c.trades == 0 {
+            c.open = t.price;
+            c.high = t.price;
+            c.low = t.price;
+        }
+        c.high = c.high.max(t.price);
+        c.low = c.low.min(t.price);
+        c.close = t.price;
+        c.volume += t.size;
+        c.trades += 1;
+    }
+}
+
+fn flat(pair: &str, price: f64, start_ms: i64) -> Candle {
+    Candle {
+        pair: pair.to_string(),
+        start_ms,
+        interval_ms: CANDLE_MS,
+        open: price,
+        high: price,
+        low: price,
+        close: price,
+        volume: 0.0,
+        trades: 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn print(price: f64, size: f64) -> TradePrint {
+        TradePrint {
+            pair: "BTC/USDT".into(),
+            price,
+            size,
+            side: Aggressor::Buy,
+            ts_ms: 0,
+        }
+    }
+
+    #[test]
+    fn candles_close_each_second_and_carry_the_close() {
+        let mut b = CandleBuilder::new("BTC/USDT", 100.0, 5_400);
+        assert_eq!(b.roll(5_999), None);
+        b.add(&print(101.0, 1.0));
+        b.add(&print(99.0, 2.0));
+        b.add(&print(100.5, 0.5));
+        let c = b.roll(6_000).unwrap();
+        assert_eq!((c.start_ms, c.trades), (5_000, 3));
+        assert_eq!(
+            (c.open, c.high, c.low, c.close),
+            (101.0, 101.0, 99.0, 100.5)
+        );
+        assert_eq!(c.volume, 3.5);
+
+        let empty = b.roll(7_200).unwrap();
+        assert_eq!((empty.start_ms, empty.trades), (6_000, 0));
+        assert_eq!((empty.open, empty.close, empty.volume), (100.5, 100.5, 0.0));
+
+        for t in synth_trades("ETH/USDT", 3500.0, 1) {
+            assert!((t.price - 3500.0).abs() <= 3500.0 * 0.0002);
+            match t.side {
+                Aggressor::Buy => assert!(t.price >= 3500.0),
+                Aggressor::Sell => assert!(t.price <= 3500.0),
+            }
+            assert!(t.size > 0.0);
+        }
+    }
+}
@@ -0,0 +1,240 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use actix::prelude::*;
+use actix_web_actors::ws;
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::{now_ms, Tick, QUOTE_HALF_SPREAD};
+
+/// A pushed tick on its way to a subscriber.
+#[derive(Message, Clone)]
+#[rtype(result = "()")]
+pub struct Pushed(pub Tick);
+
+/// A price sent on `/ws/push`. Without `bid`/`ask` the quote gets the usual
+/// spread around `price`; without `ts_ms` it is stamped now, after the
+/// previous tick for the pair.
+#[derive(Debug, Deserialize)]
+pub struct PushTick {
+    pub pair: String,
+    pub price: f64,
+    pub bid: Option<f64>,
+    pub ask: Option<f64>,
+    pub ts_ms: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum PushMessage {
+    Tick(PushTick),
+    /// Hands the pair back to the random walk.
+    Release {
+        release: String,
+    },
+}
+
+struct Subscriber {
+    pairs: Vec<String>,
+    addr: Recipient<Pushed>,
+}
+
+/// Connects pushing clients to subscribers. A pair someone has pushed to
+/// stays under push control, with no random-walk ticks, until that client
+/// releases it or disconnects.
+#[derive(Default)]
+pub struct Hub {
+    next_id: usize,
+    subscribers: HashMap<usize, Subscriber>,
+    /// Pushed pairs, by the push session holding them.
+    overrides: HashMap<String, usize>,
+    last_ts: HashMap<String, i64>,
+}
+
+pub type SharedHub = Arc<Mutex<Hub>>;
+
+impl Hub {
+    pub fn session(&mut self) -> usize {
+        self.next_id += 1;
+        self.next_id
+    }
+
+    pub fn subscribe(&mut self, pairs: Vec<String>, addr: Recipient<Pushed>) -> usize {
+        let id = self.session();
+        self.subscribers.insert(id, Subscriber { pairs, addr });
+        id
+    }
+
+    pub fn unsubscribe(&mut self, id: usize) {
+        self.subscribers.remove(&id);
+    }
+
+    pub fn is_pushed(&self, pair: &str) -> bool {
+        self.overrides.contains_key(pair)
+    }
+
+    /// Takes `t.pair` over for `session` and sends the tick to its
+    /// subscribers; how many it reached.
+    pub fn push(&mut self, session: usize, t: PushTick) -> Result<usize, String> {
+        let tick = self.stamp(t)?;
+        self.overrides.insert(tick.pair.clone(), session);
+        let mut delivered = 0;
+        for s in self.subscribers.values() {
+            if s.pairs.contains(&tick.pair) {
+                s.addr.do_send(Pushed(tick.clone()));
+                delivered += 1;
+            }
+        }
+        Ok(delivered)
+    }
+
+    fn stamp(&mut self, t: PushTick) -> Result<Tick, String> {
+        if t.pair.trim().is_empty() {
+            return Err("pair is required".into());
+        }
+        let positive = |v: f64| v.is_finite() && v > 0.0;
+        if !positive(t.price) {
+            return Err("price must be a positive number".into());
+        }
+        let half_spread = t.price * QUOTE_HALF_SPREAD;
+        let bid = t.bid.unwrap_or(t.price - half_spread);
+        let ask = t.ask.unwrap_or(t.price + half_spread);
+        if !positive(bid) || !positive(ask) || bid > ask {
+            return Err("bid and ask must be positive with bid <= ask".into());
+        }
+        let last = self.last_ts.get(&t.pair).copied();
+        let ts_ms = t
+            .ts_ms
+            .unwrap_or_else(|| last.map_or(now_ms(), |l| now_ms().max(l + 1)));
+        self.last_ts
+            .insert(t.pair.clone(), last.map_or(ts_ms, |l| l.max(ts_ms)));
+        Ok(Tick {
+            pair: t.pair,
+            price: t.price,
+            bid,
+            ask,
+            ts_ms,
+        })
+    }
+
+    /// Returns `session`'s pairs, or just `pair`, to the random walk.
+    pub fn release(&mut self, session: usize, pair: Option<&str>) -> Vec<String> {
+        let released: Vec<String> = self
+            .overrides
+            .iter()
+            .filter(|(p, s)| **s == session && pair.is_none_or(|want| want == p.as_str()))
+            .map(|(p, _)| p.clone())
+            .collect();
+        for p in &released {
+            self.overrides.remove(p);
+        }
+        released
+    }
+}
+
+/// A client pushing prices: each text frame is a [`PushMessage`], answered
+/// with `{"delivered": n}`, `{"released": [...]}` or `{"error": "..."}`.
+pub struct PushWs {
+    hub: SharedHub,
+    id: usize,
+}
+
+impl PushWs {
+    pub fn new(hub: SharedHub) -> Self {
+        let id = hub.lock().unwrap().session();
+        Self { hub, id }
+    }
+
+    fn answer(&self, text: &str) -> serde_json::Value {
+        let msg = match serde_json::from_str::<PushMessage>(text) {
+            Ok(m) => m,
+            Err(_) => {
+                return json!({
+                    "error": "expected {pair, price, bid?, ask?, ts_ms?} or {release}"
+                })
+            }
+        };
+        let mut hub = self.hub.lock().unwrap();
+        match msg {
+            PushMessage::Tick(t) => match hub.push(self.id, t) {
+                Ok(n) => json!({ "delivered": n }),
+                Err(e) => json!({ "error": e }),
+            },
+            PushMessage::Release { release } => {
+                json!({ "released": hub.release(self.id, Some(&release)) })
+            }
+        }
+    }
+}
+
+impl Actor for PushWs {
+    type Context = ws::WebsocketContext<Self>;
+
+    fn stopped(&mut self, _: &mut Self::Context) {
+        let released = self.hub.lock().unwrap().release(self.id, None);
+        if !released.is_empty() {
+            tracing::info!(?released, "push client left; pairs back on the random walk");
+        }
+    }
+}
+
+impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for PushWs {
+    fn handle(&mut self, msg: Result<ws::Message, ws::ProtocolError>, ctx: &mut Self::Context) {
+        match msg {
+            Ok(ws::Message::Ping(bytes)) => ctx.pong(&bytes),
+            Ok(ws::Message::Close(reason)) => ctx.close(reason),
+            Ok(ws::Message::Text(text)) => ctx.text(self.answer(&text).to_string()),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Sink;
+
+    impl Actor for Sink {
+        type Context = Context<Self>;
+    }
+
+    impl Handler<Pushed> for Sink {
+        type Result = ();
+        fn handle(&mut self, _: Pushed, _: &mut Self::Context) {}
+    }
+
+    fn tick(pair: &str, price: f64, ts_ms: Option<i64>) -> PushTick {
+        PushTick {
+            pair: pair.into(),
+            price,
+            bid: None,
+            ask: None,
+            ts_ms,
+        }
+    }
+
+    #[actix::test]
+    async fn pushed_pairs_stay_overridden_until_released() {
+        let mut hub = Hub::default();
+        let sink = Sink.start().recipient();
+        hub.subscribe(vec!["BTC/USDT".into()], sink.clone());
+        hub.subscribe(vec!["ETH/USDT".into()], sink);
+        let (a, b) = (hub.session(), hub.session());
+
+        assert_eq!(hub.push(a, tick("BTC/USDT", 100.0, Some(5))), Ok(1));
+        assert!(hub.is_pushed("BTC/USDT"));
+        assert!(!hub.is_pushed("ETH/USDT"));
+        // Unstamped ticks follow the last one for the pair.
+        let t = hub.stamp(tick("BTC/USDT", 101.0, None)).unwrap();
+        assert!(t.ts_ms > 5 && t.bid < t.price && t.price < t.ask);
+        assert!(hub.push(a, tick("BTC/USDT", -1.0, None)).is_err());
+
+        assert_eq!(hub.push(b, tick("SOL/USDT", 1.0, None)), Ok(0));
+        assert!(hub.release(a, Some("SOL/USDT")).is_empty());
+        assert_eq!(hub.release(a, None), vec!["BTC/USDT".to_string()]);
+        assert!(!hub.is_pushed("BTC/USDT"));
+        assert!(hub.is_pushed("SOL/USDT"));
+    }
+}
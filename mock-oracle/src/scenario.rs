@@ -0,0 +1,70 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Prices to play instead of the random walk, per pair, one per interval,
+/// e.g. `{"BTC/USDT": [105, 103, 99.5]}`. Each connection plays the script
+/// from the start and then holds the last price, so a test sees the same
+/// path however late it connects. Pairs the script does not name walk as
+/// usual.
+#[derive(Debug, Clone, Default)]
+pub struct Scenario(Arc<HashMap<String, Vec<f64>>>);
+
+impl Scenario {
+    pub fn new(prices: impl IntoIterator<Item = (String, Vec<f64>)>) -> Self {
+        Self(Arc::new(
+            prices.into_iter().filter(|(_, v)| !v.is_empty()).collect(),
+        ))
+    }
+
+    pub fn from_json(s: &str) -> Result<Self, String> {
+        let prices: HashMap<String, Vec<f64>> =
+            serde_json::from_str(s).map_err(|e| e.to_string())?;
+        Ok(Self::new(prices))
+    }
+
+    /// Reads a scenario file, such as the one named by `SCENARIO`.
+    pub fn load(path: &str) -> Result<Self, String> {
+        Self::from_json(&std::fs::read_to_string(path).map_err(|e| e.to_string())?)
+    }
+
+    /// A fresh cursor over the script, for one connection.
+    pub(crate) fn script(&self) -> Script {
+        Script {
+            scenario: self.clone(),
+            played: HashMap::new(),
+        }
+    }
+}
+
+/// One connection's place in a [`Scenario`].
+pub(crate) struct Script {
+    scenario: Scenario,
+    played: HashMap<String, usize>,
+}
+
+impl Script {
+    /// The pair's next scripted price, `None` if it is not scripted.
+    pub(crate) fn next(&mut self, pair: &str) -> Option<f64> {
+        let prices = self.scenario.0.get(pair)?;
+        let i = self.played.entry(pair.to_string()).or_default();
+        let px = prices[(*i).min(prices.len() - 1)];
+        *i += 1;
+        Some(px)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scripts_play_in_order_then_hold_the_last_price() {
+        let s = Scenario::from_json(r#"{"BTC/USDT": [3, 2, 1], "ETH/USDT": []}"#).unwrap();
+        let mut a = s.script();
+        let played: Vec<_> = (0..5).map(|_| a.next("BTC/USDT").unwrap()).collect();
+        assert_eq!(played, [3.0, 2.0, 1.0, 1.0, 1.0]);
+        assert_eq!(a.next("ETH/USDT"), None);
+        assert_eq!(s.script().next("BTC/USDT"), Some(3.0));
+        assert!(Scenario::from_json("[1]").is_err());
+    }
+}
@@ -0,0 +1,54 @@
+//! Throughput of one tick's condition evaluation by `ENGINE_EVAL_THREADS`.
+//! Run with `cargo bench --bench eval_threads`; each thread count reports
+//! orders evaluated per second, so scaling shows as the ratio between them
+//! on a machine with that many cores.
+
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+use rust_decimal::Decimal;
+
+use conditional_orderbook::engine::evaluate_batch;
+use conditional_orderbook::engine::policy::OraclePolicy;
+use conditional_orderbook::engine::MarketView;
+use conditional_orderbook::entities::order::{Order, OrderSide};
+
+const BOOK: usize = 50_000;
+
+fn book() -> Vec<Order> {
+    (0..BOOK)
+        .map(|i| {
+            let side = if i % 2 == 0 {
+                OrderSide::Buy
+            } else {
+                OrderSide::Sell
+            };
+            let price = Decimal::from(90 + i % 21);
+            Order::new("BTC/USDT".into(), side, price, Decimal::ONE)
+        })
+        .collect()
+}
+
+fn eval_threads(c: &mut Criterion) {
+    let rt = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+    let orders = book();
+    let view = MarketView::from_last(Decimal::from(100), 1_700_000_000_000);
+    let policy = OraclePolicy::default();
+
+    let mut group = c.benchmark_group("evaluate_batch");
+    group.throughput(Throughput::Elements(BOOK as u64));
+    for threads in [1, 2, 4, 8] {
+        group.bench_with_input(BenchmarkId::from_parameter(threads), &threads, |b, &n| {
+            b.iter_batched(
+                || orders.clone(),
+                |orders| rt.block_on(evaluate_batch(orders, &view, &policy, n)),
+                BatchSize::LargeInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, eval_threads);
+criterion_main!(benches);
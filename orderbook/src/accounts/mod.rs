@@ -0,0 +1,321 @@
+pub mod purge;
+pub mod secrets;
+pub mod statements;
+pub mod usage;
+
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::utils::now_ms;
+use secrets::{hash_secret, random_hex, verify_secret, SecretBox};
+use statements::StatementLedger;
+use usage::AccountUsage;
+
+const KEY_PREFIX: &str = "cob";
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct Account {
+    pub id: String,
+    pub name: String,
+    pub created: i64,
+}
+
+/// API key metadata. The secret half is only ever stored as a hash.
+#[derive(Debug, Clone, Serialize)]
+pub struct ApiKey {
+    pub id: String,
+    pub account_id: String,
+    pub created: i64,
+    pub revoked: Option<i64>,
+    #[serde(skip)]
+    hash: String,
+}
+
+/// What a caller sees exactly once when a key is issued.
+#[derive(Debug, Clone, Serialize)]
+pub struct IssuedKey {
+    pub key: ApiKey,
+    pub api_key: String,
+}
+
+#[derive(Debug, Clone)]
+struct Webhook {
+    url: String,
+    sealed_secret: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WebhookInfo {
+    pub url: String,
+    pub signing_secret: String,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct ResealReport {
+    pub active_key_id: String,
+    pub resealed: usize,
+}
+
+#[derive(Default)]
+struct Inner {
+    accounts: HashMap<String, Account>,
+    keys: HashMap<String, ApiKey>,
+    webhooks: HashMap<String, Webhook>,
+}
+
+/// In-memory accounts with hashed API keys and webhook signing secrets
+/// sealed under the master keyring, plus each account's usage against its
+/// risk limits and its statements.
+#[derive(Clone)]
+pub struct AccountStore {
+    vault: SecretBox,
+    inner: Arc<RwLock<Inner>>,
+    usage: AccountUsage,
+    statements: StatementLedger,
+}
+
+impl AccountStore {
+    pub fn new(vault: SecretBox) -> Self {
+        Self {
+            vault,
+            inner: Arc::default(),
+            usage: AccountUsage::default(),
+            statements: StatementLedger::default(),
+        }
+    }
+
+    /// Tracks usage (and enforces limits) with `usage`, which the caller
+    /// attaches to the change stream.
+    pub fn with_usage(self, usage: AccountUsage) -> Self {
+        Self { usage, ..self }
+    }
+
+    pub fn usage(&self) -> &AccountUsage {
+        &self.usage
+    }
+
+    /// Reads statements off `statements`, which the caller attaches to the
+    /// change stream.
+    pub fn with_statements(self, statements: StatementLedger) -> Self {
+        Self { statements, ..self }
+    }
+
+    pub fn statements(&self) -> &StatementLedger {
+        &self.statements
+    }
+
+    pub fn create(&self, name: &str) -> (Account, IssuedKey) {
+        let account = Account {
+            id: crate::utils::ids::next_id(),
+            name: name.to_string(),
+            created: now_ms(),
+        };
+        let mut w = self.inner.write().unwrap();
+        w.accounts.insert(account.id.clone(), account.clone());
+        let issued = issue(&mut w, &account.id);
+        (account, issued)
+    }
+
+    pub fn get(&self, id: &str) -> Option<(Account, Vec<ApiKey>)> {
+        let r = self.inner.read().unwrap();
+        let account = r.accounts.get(id)?.clone();
+        let mut keys: Vec<ApiKey> = r
+            .keys
+            .values()
+            .filter(|k| k.account_id == id)
+            .cloned()
+            .collect();
+        keys.sort_by_key(|k| k.created);
+        Some((account, keys))
+    }
+
+    pub fn issue_key(&self, account_id: &str) -> Option<IssuedKey> {
+        let mut w = self.inner.write().unwrap();
+        if !w.accounts.contains_key(account_id) {
+            return None;
+        }
+        Some(issue(&mut w, account_id))
+    }
+
+    /// Issues a replacement and revokes `key_id` in one step.
+    pub fn rotate_key(&self, account_id: &str, key_id: &str) -> Option<IssuedKey> {
+        let mut w = self.inner.write().unwrap();
+        match w.keys.get_mut(key_id) {
+            Some(k) if k.account_id == account_id && k.revoked.is_none() => {
+                k.revoked = Some(now_ms())
+            }
+            _ => return None,
+        }
+        Some(issue(&mut w, account_id))
+    }
+
+    pub fn revoke_key(&self, account_id: &str, key_id: &str) -> bool {
+        let mut w = self.inner.write().unwrap();
+        match w.keys.get_mut(key_id) {
+            Some(k) if k.account_id == account_id => {
+                k.revoked.get_or_insert_with(now_ms);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Resolves a presented `cob_<key id>_<secret>` token to its account.
+    pub fn authenticate(&self, token: &str) -> Option<Account> {
+        let rest = token.strip_prefix(KEY_PREFIX)?.strip_prefix('_')?;
+        let (key_id, secret) = rest.split_once('_')?;
+        let r = self.inner.read().unwrap();
+        let key = r.keys.get(key_id).filter(|k| k.revoked.is_none())?;
+        if !verify_secret(secret, &key.hash) {
+            return None;
+        }
+        r.accounts.get(&key.account_id).cloned()
+    }
+
+    /// Sets the account's webhook URL with a fresh signing secret, replacing
+    /// any previous one.
+    pub fn set_webhook(&self, account_id: &str, url: &str) -> Option<WebhookInfo> {
+        let mut w = self.inner.write().unwrap();
+        if !w.accounts.contains_key(account_id) {
+            return None;
+        }
+        let secret = format!("whsec_{}", random_hex(32));
+        w.webhooks.insert(
+            account_id.to_string(),
+            Webhook {
+                url: url.to_string(),
+                sealed_secret: self.vault.seal(&secret),
+            },
+        );
+        Some(WebhookInfo {
+            url: url.to_string(),
+            signing_secret: secret,
+        })
+    }
+
+    pub fn rotate_webhook_secret(&self, account_id: &str) -> Option<WebhookInfo> {
+        let url = self
+            .inner
+            .read()
+            .unwrap()
+            .webhooks
+            .get(account_id)?
+            .url
+            .clone();
+        self.set_webhook(account_id, &url)
+    }
+
+    /// Decrypted webhook URL and signing secret, for delivery.
+    pub fn webhook(&self, account_id: &str) -> Result<Option<WebhookInfo>, String> {
+        let r = self.inner.read().unwrap();
+        let Some(hook) = r.webhooks.get(account_id) else {
+            return Ok(None);
+        };
+        Ok(Some(WebhookInfo {
+            url: hook.url.clone(),
+            signing_secret: self.vault.open(&hook.sealed_secret)?,
+        }))
+    }
+
+    /// Re-encrypts every sealed secret under the active master key so older
+    /// keys can be dropped from the ring.
+    pub fn reseal(&self) -> Result<ResealReport, String> {
+        let mut w = self.inner.write().unwrap();
+        let mut resealed = 0;
+        for hook in w.webhooks.values_mut() {
+            if self.vault.is_sealed_with_active(&hook.sealed_secret) {
+                continue;
+            }
+            hook.sealed_secret = self.vault.seal(&self.vault.open(&hook.sealed_secret)?);
+            resealed += 1;
+        }
+        Ok(ResealReport {
+            active_key_id: self.vault.active_key_id().to_string(),
+            resealed,
+        })
+    }
+}
+
+fn issue(w: &mut Inner, account_id: &str) -> IssuedKey {
+    let id = Uuid::new_v4().simple().to_string();
+    let secret = random_hex(32);
+    let key = ApiKey {
+        id: id.clone(),
+        account_id: account_id.to_string(),
+        created: now_ms(),
+        revoked: None,
+        hash: hash_secret(&secret),
+    };
+    w.keys.insert(id.clone(), key.clone());
+    IssuedKey {
+        key,
+        api_key: format!("{KEY_PREFIX}_{id}_{secret}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rotated_and_revoked_keys_stop_authenticating() {
+        let store = AccountStore::new(SecretBox::ephemeral());
+        let (account, first) = store.create("desk");
+        assert_eq!(store.authenticate(&first.api_key), Some(account.clone()));
+        assert!(store
+            .authenticate(&first.api_key.replace("cob_", ""))
+            .is_none());
+
+        let second = store.rotate_key(&account.id, &first.key.id).unwrap();
+        assert!(store.authenticate(&first.api_key).is_none());
+        assert_eq!(store.authenticate(&second.api_key), Some(account.clone()));
+        assert!(store.rotate_key(&account.id, &first.key.id).is_none());
+
+        assert!(store.revoke_key(&account.id, &second.key.id));
+        assert!(store.authenticate(&second.api_key).is_none());
+        assert_eq!(store.get(&account.id).unwrap().1.len(), 2);
+    }
+
+    #[test]
+    fn webhook_secrets_are_sealed_and_resealed_on_key_rollover() {
+        let k1 = format!("k1={}", "11".repeat(32));
+        let k2 = format!("k2={}", "22".repeat(32));
+        let old = AccountStore::new(SecretBox::from_keyring(&k1).unwrap());
+        let (account, _) = old.create("desk");
+        let info = old
+            .set_webhook(&account.id, "https://example.test/hook")
+            .unwrap();
+        let sealed = old.inner.read().unwrap().webhooks[&account.id]
+            .sealed_secret
+            .clone();
+        assert!(!sealed.contains(&info.signing_secret));
+
+        // Same data, restarted with k2 in front of k1.
+        let store = AccountStore {
+            vault: SecretBox::from_keyring(&format!("{k2},{k1}")).unwrap(),
+            inner: old.inner.clone(),
+            usage: AccountUsage::default(),
+            statements: StatementLedger::default(),
+        };
+        assert_eq!(
+            store.webhook(&account.id).unwrap().unwrap().signing_secret,
+            info.signing_secret
+        );
+        assert_eq!(
+            store.reseal().unwrap(),
+            ResealReport {
+                active_key_id: "k2".into(),
+                resealed: 1
+            }
+        );
+        assert_eq!(store.reseal().unwrap().resealed, 0);
+        assert!(old.webhook(&account.id).is_err());
+
+        let rotated = store.rotate_webhook_secret(&account.id).unwrap();
+        assert_ne!(rotated.signing_secret, info.signing_secret);
+        assert_eq!(rotated.url, "https://example.test/hook");
+    }
+}
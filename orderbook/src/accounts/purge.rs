@@ -0,0 +1,313 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+use futures_util::StreamExt;
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::entities::order::CANCEL_ACCOUNT_PURGE;
+use crate::repositories::audit::AuditLog;
+use crate::repositories::baskets::BasketStore;
+use crate::repositories::journal::Journal;
+use crate::repositories::{ListOrdersQuery, OrderRepository};
+use crate::utils::now_ms;
+
+/// Finished jobs kept for their status.
+const JOB_HISTORY: usize = 100;
+
+/// `purge` deletes an account's orders and every record of them;
+/// `anonymize` keeps them, with prices and fills, but without the account,
+/// tags or metadata.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PurgeMode {
+    #[default]
+    Anonymize,
+    Purge,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    Running,
+    Completed,
+    Failed,
+}
+
+/// Progress of one account's purge. Counts grow as the job goes.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct PurgeJob {
+    pub id: String,
+    pub account_id: String,
+    pub mode: PurgeMode,
+    pub status: JobStatus,
+    /// The account's orders found when the job started.
+    pub orders_found: usize,
+    /// Active orders cancelled before being purged or anonymized.
+    pub orders_cancelled: usize,
+    /// Orders deleted or anonymized so far.
+    pub orders: usize,
+    pub journal_entries: usize,
+    pub audit_entries: usize,
+    pub baskets: usize,
+    pub started_ms: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub finished_ms: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Purges or anonymizes an account's data across the order store, the
+/// journal, the audit log and baskets, one background job per request.
+/// Listeners on the change stream (search, open orders, usage) follow the
+/// store's writes. The account itself and its keys are left alone.
+#[derive(Clone)]
+pub struct AccountPurger {
+    orders: Arc<dyn OrderRepository>,
+    journal: Journal,
+    audit: AuditLog,
+    baskets: BasketStore,
+    jobs: Arc<Mutex<VecDeque<PurgeJob>>>,
+}
+
+impl AccountPurger {
+    pub fn new(
+        orders: Arc<dyn OrderRepository>,
+        journal: Journal,
+        audit: AuditLog,
+        baskets: BasketStore,
+    ) -> Self {
+        Self {
+            orders,
+            journal,
+            audit,
+            baskets,
+            jobs: Arc::default(),
+        }
+    }
+
+    /// Starts a job on `account_id` in the background and returns it as
+    /// first recorded. Errs while another job on the account is running.
+    pub fn start(&self, account_id: &str, mode: PurgeMode) -> Result<PurgeJob, String> {
+        let job = self.register(account_id, mode)?;
+        let purger = self.clone();
+        let id = job.id.clone();
+        tokio::spawn(async move { purger.run(&id).await });
+        Ok(job)
+    }
+
+    fn register(&self, account_id: &str, mode: PurgeMode) -> Result<PurgeJob, String> {
+        let job = PurgeJob {
+            id: crate::utils::ids::next_id(),
+            account_id: account_id.to_string(),
+            mode,
+            status: JobStatus::Running,
+            orders_found: 0,
+            orders_cancelled: 0,
+            orders: 0,
+            journal_entries: 0,
+            audit_entries: 0,
+            baskets: 0,
+            started_ms: now_ms(),
+            finished_ms: None,
+            error: None,
+        };
+        {
+            let mut jobs = self.jobs.lock().unwrap();
+            if let Some(running) = jobs
+                .iter()
+                .find(|j| j.account_id == account_id && j.status == JobStatus::Running)
+            {
+                return Err(format!(
+                    "job {} is already running on {account_id}",
+                    running.id
+                ));
+            }
+            jobs.push_back(job.clone());
+            while jobs.len() > JOB_HISTORY {
+                jobs.pop_front();
+            }
+        }
+        Ok(job)
+    }
+
+    pub fn job(&self, id: &str) -> Option<PurgeJob> {
+        let jobs = self.jobs.lock().unwrap();
+        jobs.iter().find(|j| j.id == id).cloned()
+    }
+
+    /// Runs job `id` to the end. The store goes first, so the stores
+    /// after it also drop the records its writes just published. The
+    /// first error stops the job; running it again picks up what is left.
+    pub async fn run(&self, id: &str) {
+        let Some(job) = self.job(id) else {
+            return;
+        };
+        let result = self.work(&job).await;
+        self.update(id, |j| {
+            j.finished_ms = Some(now_ms());
+            match result {
+                Ok(()) => j.status = JobStatus::Completed,
+                Err(e) => {
+                    j.status = JobStatus::Failed;
+                    j.error = Some(e);
+                }
+            }
+        });
+        match self.job(id) {
+            Some(j) if j.status == JobStatus::Completed => {
+                info!(job = %id, account = %j.account_id, mode = ?j.mode, orders = j.orders, "account data purged")
+            }
+            Some(j) => {
+                warn!(job = %id, account = %j.account_id, err = ?j.error, "account purge failed")
+            }
+            None => {}
+        }
+    }
+
+    async fn work(&self, job: &PurgeJob) -> Result<(), String> {
+        let account = job.account_id.as_str();
+        // Ids first: deleting while paging by offset would skip orders.
+        let mut ids = Vec::new();
+        let mut all = self.orders.clone().stream(ListOrdersQuery::default());
+        while let Some(o) = all.next().await {
+            let o = o?;
+            if o.account_id.as_deref() == Some(account) {
+                ids.push((o.id, o.status.is_active()));
+            }
+        }
+        self.update(&job.id, |j| j.orders_found = ids.len());
+
+        for (order_id, active) in ids {
+            // One that filled or went away meanwhile cannot be cancelled.
+            if active
+                && self
+                    .orders
+                    .cancel(&order_id, CANCEL_ACCOUNT_PURGE)
+                    .await
+                    .is_ok()
+            {
+                self.update(&job.id, |j| j.orders_cancelled += 1);
+            }
+            let written = match job.mode {
+                PurgeMode::Purge => self.orders.delete(&order_id).await,
+                PurgeMode::Anonymize => self.orders.anonymize(&order_id).await.map(drop),
+            };
+            match written {
+                Ok(()) => self.update(&job.id, |j| j.orders += 1),
+                Err(_) if self.orders.get_by_id(&order_id).await.is_err() => {}
+                Err(e) => return Err(e),
+            }
+        }
+
+        let journal_entries = self.journal.forget_account(account, job.mode);
+        let audit_entries = self.audit.forget_account(account, job.mode);
+        let baskets = self.baskets.forget_account(account, job.mode).await;
+        self.update(&job.id, |j| {
+            j.journal_entries = journal_entries;
+            j.audit_entries = audit_entries;
+            j.baskets = baskets;
+        });
+        Ok(())
+    }
+
+    fn update(&self, id: &str, f: impl FnOnce(&mut PurgeJob)) {
+        let mut jobs = self.jobs.lock().unwrap();
+        if let Some(j) = jobs.iter_mut().find(|j| j.id == id) {
+            f(j);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entities::order::OrderStatus;
+    use crate::entities::test_support::OrderBuilder;
+    use crate::repositories::audit::AuditAction;
+    use crate::repositories::changes::{ChangeStream, Observed};
+    use crate::repositories::in_memory::InMemoryOrderRepository;
+
+    async fn setup() -> (
+        AccountPurger,
+        Observed<InMemoryOrderRepository>,
+        Journal,
+        AuditLog,
+    ) {
+        let changes = ChangeStream::default();
+        let journal = Journal::default();
+        journal.attach(&changes);
+        let repo = Observed::new(InMemoryOrderRepository::default(), changes);
+        for (account, status) in [
+            ("acct-1", OrderStatus::Open),
+            ("acct-1", OrderStatus::Filled),
+            ("acct-2", OrderStatus::Open),
+        ] {
+            let mut new = OrderBuilder::buy("BTC/USDT").limit(100).new_order();
+            new.account_id = Some(account.into());
+            new.tags = vec!["desk".into()];
+            let o = repo.create(new).await.unwrap();
+            repo.set_status(&o.id, status).await.unwrap();
+        }
+        let audit = AuditLog::default();
+        for o in repo.list(ListOrdersQuery::default()).await.unwrap() {
+            audit.record(
+                AuditAction::Cancel,
+                &o,
+                OrderStatus::Cancelled,
+                "test",
+                None,
+            );
+        }
+        let purger = AccountPurger::new(
+            Arc::new(repo.clone()),
+            journal.clone(),
+            audit.clone(),
+            BasketStore::default(),
+        );
+        (purger, repo, journal, audit)
+    }
+
+    fn owned_by(orders: &[crate::entities::order::Order], account: &str) -> usize {
+        orders
+            .iter()
+            .filter(|o| o.account_id.as_deref() == Some(account))
+            .count()
+    }
+
+    #[tokio::test]
+    async fn purge_drops_an_accounts_orders_and_records_everywhere() {
+        let (purger, repo, journal, audit) = setup().await;
+        let job = purger.register("acct-1", PurgeMode::Purge).unwrap();
+        assert!(purger.register("acct-1", PurgeMode::Purge).is_err());
+        purger.run(&job.id).await;
+
+        let job = purger.job(&job.id).unwrap();
+        assert_eq!(job.status, JobStatus::Completed);
+        assert_eq!(
+            (job.orders_found, job.orders_cancelled, job.orders),
+            (2, 1, 2)
+        );
+        assert_eq!(job.audit_entries, 2);
+        let left = repo.list(ListOrdersQuery::default()).await.unwrap();
+        assert_eq!((left.len(), owned_by(&left, "acct-2")), (1, 1));
+        assert_eq!(journal.forget_account("acct-1", PurgeMode::Purge), 0);
+        assert_eq!(audit.list(None).len(), 1);
+    }
+
+    #[tokio::test]
+    async fn anonymize_keeps_orders_without_the_account() {
+        let (purger, repo, journal, audit) = setup().await;
+        let job = purger.register("acct-1", PurgeMode::Anonymize).unwrap();
+        purger.run(&job.id).await;
+
+        assert_eq!(purger.job(&job.id).unwrap().status, JobStatus::Completed);
+        let all = repo.list(ListOrdersQuery::default()).await.unwrap();
+        assert_eq!((all.len(), owned_by(&all, "acct-1")), (3, 0));
+        let stripped = all.iter().find(|o| o.account_id.is_none()).unwrap();
+        assert!(stripped.tags.is_empty());
+        assert_eq!(journal.forget_account("acct-1", PurgeMode::Anonymize), 0);
+        assert_eq!(audit.list(None).len(), 3);
+        assert_eq!(audit.forget_account("acct-1", PurgeMode::Purge), 0);
+    }
+}
@@ -0,0 +1,199 @@
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::{Algorithm, Argon2, Params, Version};
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{Key, XChaCha20Poly1305, XNonce};
+use rand::RngCore;
+
+/// Argon2id cost for API key hashes, in KiB of memory and passes. Keys are
+/// 256-bit random tokens, so the work factor only has to make a leaked
+/// hash table expensive to use, not protect a guessable password; it is
+/// kept low because every authenticated request verifies one.
+const KDF_MEMORY_KIB: u32 = 4_096;
+const KDF_PASSES: u32 = 1;
+
+pub fn random_hex(bytes: usize) -> String {
+    let mut buf = vec![0u8; bytes];
+    rand::thread_rng().fill_bytes(&mut buf);
+    hex::encode(buf)
+}
+
+fn kdf() -> Argon2<'static> {
+    let params = Params::new(KDF_MEMORY_KIB, KDF_PASSES, 1, None).expect("valid argon2 params");
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+}
+
+/// Salted Argon2id hash as a PHC string (`$argon2id$v=19$...`).
+pub fn hash_secret(secret: &str) -> String {
+    let salt = SaltString::generate(&mut rand::thread_rng());
+    kdf()
+        .hash_password(secret.as_bytes(), &salt)
+        .expect("argon2 hashes any secret")
+        .to_string()
+}
+
+/// Checks `secret` against a hash from [`hash_secret`], with the cost the
+/// hash records.
+pub fn verify_secret(secret: &str, stored: &str) -> bool {
+    PasswordHash::new(stored).is_ok_and(|h| {
+        Argon2::default()
+            .verify_password(secret.as_bytes(), &h)
+            .is_ok()
+    })
+}
+
+#[derive(Clone)]
+struct MasterKey {
+    id: String,
+    cipher: XChaCha20Poly1305,
+}
+
+impl MasterKey {
+    fn new(id: &str, key: &[u8; 32]) -> Self {
+        Self {
+            id: id.to_string(),
+            cipher: XChaCha20Poly1305::new(Key::from_slice(key)),
+        }
+    }
+}
+
+/// Authenticated encryption of secrets at rest under a keyring of master
+/// keys. The first key seals; any key in the ring opens, so a new master
+/// key can be rolled out before old ciphertexts are resealed.
+#[derive(Clone)]
+pub struct SecretBox {
+    keys: Vec<MasterKey>,
+}
+
+impl SecretBox {
+    /// Parses `id=hex,id=hex`, active key first. Keys must be 32 bytes,
+    /// and ids may not contain `:`, which separates them from the sealed
+    /// value.
+    pub fn from_keyring(spec: &str) -> Result<Self, String> {
+        let keys = spec
+            .split(',')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(|entry| {
+                let (id, hex_key) = entry
+                    .split_once('=')
+                    .ok_or_else(|| format!("master key {entry:?} is not id=hex"))?;
+                let id = id.trim();
+                if id.is_empty() || id.contains(':') {
+                    return Err(format!(
+                        "master key id {id:?} must be non-empty and without ':'"
+                    ));
+                }
+                let key = hex::decode(hex_key.trim())
+                    .map_err(|_| format!("master key {id} is not hex"))?;
+                let key: [u8; 32] = key
+                    .try_into()
+                    .map_err(|_| format!("master key {id} must be 32 bytes"))?;
+                Ok(MasterKey::new(id, &key))
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        if keys.is_empty() {
+            return Err("no master keys".into());
+        }
+        Ok(Self { keys })
+    }
+
+    /// Random single-key ring for when no master key is configured; sealed
+    /// values do not survive a restart.
+    pub fn ephemeral() -> Self {
+        let mut key = [0u8; 32];
+        rand::thread_rng().fill_bytes(&mut key);
+        Self {
+            keys: vec![MasterKey::new("ephemeral", &key)],
+        }
+    }
+
+    pub fn active_key_id(&self) -> &str {
+        &self.keys[0].id
+    }
+
+    /// `<key id>:<nonce>:<ciphertext>`, the last two hex encoded. The
+    /// secret is sealed with XChaCha20-Poly1305 under a random nonce; the
+    /// ciphertext carries the tag, and the key id is authenticated with it.
+    pub fn seal(&self, plaintext: &str) -> String {
+        let key = &self.keys[0];
+        let mut nonce = [0u8; 24];
+        rand::thread_rng().fill_bytes(&mut nonce);
+        let payload = Payload {
+            msg: plaintext.as_bytes(),
+            aad: key.id.as_bytes(),
+        };
+        let ct = key
+            .cipher
+            .encrypt(XNonce::from_slice(&nonce), payload)
+            .expect("xchacha20poly1305 seals any secret");
+        format!("{}:{}:{}", key.id, hex::encode(nonce), hex::encode(ct))
+    }
+
+    pub fn open(&self, sealed: &str) -> Result<String, String> {
+        let malformed = || "malformed sealed secret".to_string();
+        let (id, rest) = sealed.split_once(':').ok_or_else(malformed)?;
+        let (nonce, ct) = rest.split_once(':').ok_or_else(malformed)?;
+        let key = self
+            .keys
+            .iter()
+            .find(|k| k.id == id)
+            .ok_or_else(|| format!("unknown master key {id}"))?;
+        let nonce = hex::decode(nonce).map_err(|_| malformed())?;
+        let ct = hex::decode(ct).map_err(|_| malformed())?;
+        if nonce.len() != 24 {
+            return Err(malformed());
+        }
+        let payload = Payload {
+            msg: &ct,
+            aad: id.as_bytes(),
+        };
+        let plain = key
+            .cipher
+            .decrypt(XNonce::from_slice(&nonce), payload)
+            .map_err(|_| "sealed secret failed authentication".to_string())?;
+        String::from_utf8(plain).map_err(|_| "sealed secret is not utf-8".into())
+    }
+
+    pub fn is_sealed_with_active(&self, sealed: &str) -> bool {
+        sealed.split(':').next() == Some(self.active_key_id())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hashes_verify_only_the_original_secret() {
+        let h = hash_secret("s3cret");
+        assert!(h.starts_with("$argon2id$v=19$m=4096,t=1,p=1$"));
+        assert!(verify_secret("s3cret", &h));
+        assert!(!verify_secret("s3creT", &h));
+        assert!(!verify_secret("s3cret", "plain"));
+        assert_ne!(h, hash_secret("s3cret"));
+    }
+
+    #[test]
+    fn sealed_secrets_round_trip_across_a_key_rollover() {
+        let k1 = format!("k1={}", "11".repeat(32));
+        let k2 = format!("k2={}", "22".repeat(32));
+        let old = SecretBox::from_keyring(&k1).unwrap();
+        let sealed = old.seal("whsec_abc");
+        assert!(!sealed.contains("whsec_abc"));
+        assert_eq!(old.open(&sealed).unwrap(), "whsec_abc");
+
+        let ring = SecretBox::from_keyring(&format!("{k2},{k1}")).unwrap();
+        assert_eq!(ring.open(&sealed).unwrap(), "whsec_abc");
+        assert!(!ring.is_sealed_with_active(&sealed));
+        assert!(ring.is_sealed_with_active(&ring.seal("x")));
+
+        let flip = if sealed.ends_with('0') { "1" } else { "0" };
+        let tampered = format!("{}{flip}", &sealed[..sealed.len() - 1]);
+        assert!(old.open(&tampered).is_err());
+        assert!(SecretBox::from_keyring(&k2).unwrap().open(&sealed).is_err());
+        assert!(SecretBox::from_keyring("k1=abcd")
+            .is_err_and(|e| e == "master key k1 must be 32 bytes"));
+        assert!(SecretBox::from_keyring(&format!("k:1={}", "11".repeat(32)))
+            .is_err_and(|e| e.contains("without ':'")));
+    }
+}
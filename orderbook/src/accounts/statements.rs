@@ -0,0 +1,472 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::sync::{Arc, RwLock};
+
+use rust_decimal::Decimal;
+use serde::Serialize;
+
+use crate::entities::events::OrderEvent;
+use crate::entities::order::{Fill, Order, OrderSide, OrderStatus};
+use crate::instruments::symbol::Symbol;
+use crate::repositories::changes::{ChangeListener, ChangeStream, OrderChange};
+use crate::repositories::{ListOrdersQuery, OrderRepository};
+
+const DAY_MS: i64 = 86_400_000;
+
+/// A calendar month in UTC, written `2024-06`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Period {
+    year: i64,
+    month: u32,
+}
+
+impl Period {
+    pub fn parse(s: &str) -> Option<Self> {
+        let (year, month) = s.trim().split_once('-')?;
+        if year.len() != 4 || month.len() != 2 {
+            return None;
+        }
+        let (year, month) = (year.parse().ok()?, month.parse().ok()?);
+        ((1970..=9999).contains(&year) && (1..=12).contains(&month)).then_some(Self { year, month })
+    }
+
+    /// The month `ts_ms` falls in.
+    pub fn of(ts_ms: i64) -> Self {
+        let (year, month) = civil_from_days(ts_ms.div_euclid(DAY_MS));
+        Self { year, month }
+    }
+
+    pub fn start_ms(self) -> i64 {
+        days_from_civil(self.year, self.month) * DAY_MS
+    }
+
+    /// The start of the next month; the period excludes it.
+    pub fn end_ms(self) -> i64 {
+        let next = match self.month {
+            12 => Self {
+                year: self.year + 1,
+                month: 1,
+            },
+            m => Self {
+                month: m + 1,
+                ..self
+            },
+        };
+        next.start_ms()
+    }
+}
+
+impl fmt::Display for Period {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04}-{:02}", self.year, self.month)
+    }
+}
+
+/// Days from 1970-01-01 to the first of the month, proleptic Gregorian.
+fn days_from_civil(year: i64, month: u32) -> i64 {
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let m = i64::from(month);
+    let doy = (153 * (if m > 2 { m - 3 } else { m + 9 }) + 2) / 5;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+/// Year and month of the day `days` after 1970-01-01.
+fn civil_from_days(days: i64) -> (i64, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    (yoe + era * 400 + i64::from(month <= 2), month)
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct StatementFill {
+    pub order_id: String,
+    pub pair: Symbol,
+    pub side: OrderSide,
+    #[serde(serialize_with = "crate::utils::decimal::serialize")]
+    pub price: Decimal,
+    #[serde(serialize_with = "crate::utils::decimal::serialize")]
+    pub quantity: Decimal,
+    pub ts_ms: i64,
+}
+
+/// Net change in one asset from the account's fills: buying adds the base
+/// asset and spends the quote, selling the reverse.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct AssetBalance {
+    pub asset: String,
+    #[serde(serialize_with = "crate::utils::decimal::serialize")]
+    pub opening: Decimal,
+    #[serde(serialize_with = "crate::utils::decimal::serialize")]
+    pub change: Decimal,
+    #[serde(serialize_with = "crate::utils::decimal::serialize")]
+    pub closing: Decimal,
+}
+
+/// One account's activity over a period. Orders count in the period they
+/// were placed, filled or cancelled in; balances start from the first
+/// fill the ledger holds.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct Statement {
+    pub account_id: String,
+    pub period: String,
+    pub from_ms: i64,
+    pub to_ms: i64,
+    pub orders_placed: usize,
+    pub orders_filled: usize,
+    pub orders_cancelled: usize,
+    pub orders_expired: usize,
+    pub fills: Vec<StatementFill>,
+    pub balances: Vec<AssetBalance>,
+}
+
+/// What a statement needs of one order, as last written.
+#[derive(Debug, Clone)]
+struct Activity {
+    pair: Symbol,
+    side: OrderSide,
+    price: Decimal,
+    quantity: Decimal,
+    placed_ms: i64,
+    fills: Vec<Fill>,
+    /// `Filled` or `Cancelled`, and when the order got there.
+    closed: Option<(OrderStatus, i64)>,
+}
+
+#[derive(Default)]
+struct Inner {
+    accounts: HashMap<String, HashMap<String, Activity>>,
+    owners: HashMap<String, String>,
+}
+
+impl Inner {
+    fn record(&mut self, o: &Order, ts_ms: i64) {
+        let Some(account) = &o.account_id else {
+            // Anonymized: no longer the account's.
+            self.forget(&o.id);
+            return;
+        };
+        if self.owners.get(&o.id).is_some_and(|a| a != account) {
+            self.forget(&o.id);
+        }
+        self.owners.insert(o.id.clone(), account.clone());
+        let a = self
+            .accounts
+            .entry(account.clone())
+            .or_default()
+            .entry(o.id.clone())
+            .or_insert_with(|| Activity {
+                pair: o.pair.clone(),
+                side: o.side.clone(),
+                price: o.price,
+                quantity: o.quantity,
+                placed_ms: o.created,
+                fills: Vec::new(),
+                closed: None,
+            });
+        a.pair = o.pair.clone();
+        a.price = o.price;
+        a.quantity = o.quantity;
+        a.fills.clone_from(&o.fills);
+        if a.closed.is_none()
+            && matches!(
+                o.status,
+                OrderStatus::Filled | OrderStatus::Cancelled | OrderStatus::Expired
+            )
+        {
+            a.closed = Some((o.status.clone(), ts_ms));
+        }
+    }
+
+    fn forget(&mut self, id: &str) {
+        let Some(account) = self.owners.remove(id) else {
+            return;
+        };
+        if let Some(orders) = self.accounts.get_mut(&account) {
+            orders.remove(id);
+            if orders.is_empty() {
+                self.accounts.remove(&account);
+            }
+        }
+    }
+}
+
+/// Per-account record of orders and fills kept from the change stream, so
+/// statements are read off it instead of scanning the order store. An
+/// order deleted or anonymized by an account purge leaves it too.
+#[derive(Clone, Default)]
+pub struct StatementLedger {
+    inner: Arc<RwLock<Inner>>,
+}
+
+impl StatementLedger {
+    /// Subscribes to `stream` and records the orders already in `repo`,
+    /// as closed when they were last written.
+    pub async fn attach<R: OrderRepository + ?Sized>(
+        &self,
+        repo: &R,
+        stream: &ChangeStream,
+    ) -> Result<(), String> {
+        stream.listen(Arc::new(self.clone()));
+        let orders = repo.list(ListOrdersQuery::default()).await?;
+        let mut w = self.inner.write().unwrap();
+        for o in &orders {
+            w.record(o, o.updated);
+        }
+        Ok(())
+    }
+
+    /// Like [`attach`](Self::attach) for a store known to be empty.
+    pub fn follow(&self, stream: &ChangeStream) {
+        stream.listen(Arc::new(self.clone()));
+    }
+
+    pub fn statement(&self, account_id: &str, period: Period) -> Statement {
+        let (from, to) = (period.start_ms(), period.end_ms());
+        let within = |ts: i64| (from..to).contains(&ts);
+        let r = self.inner.read().unwrap();
+        let orders: Vec<(&String, &Activity)> = r
+            .accounts
+            .get(account_id)
+            .map(|m| m.iter().collect())
+            .unwrap_or_default();
+        let closed = |status: OrderStatus| {
+            orders
+                .iter()
+                .filter(|(_, a)| {
+                    a.closed
+                        .as_ref()
+                        .is_some_and(|(s, ts)| *s == status && within(*ts))
+                })
+                .count()
+        };
+
+        let mut fills = Vec::new();
+        let mut balances: BTreeMap<&str, (Decimal, Decimal)> = BTreeMap::new();
+        for (id, a) in &orders {
+            let Some((base, quote)) = a.pair.split_once('/') else {
+                continue;
+            };
+            for f in a.fills.iter().filter(|f| f.local_ms() < to) {
+                let (ts, notional) = (f.local_ms(), f.price * f.quantity);
+                let (base_delta, quote_delta) = match a.side {
+                    OrderSide::Buy => (f.quantity, -notional),
+                    OrderSide::Sell => (-f.quantity, notional),
+                };
+                for (asset, delta) in [(base, base_delta), (quote, quote_delta)] {
+                    let (opening, change) = balances.entry(asset).or_default();
+                    if ts < from {
+                        *opening += delta;
+                    } else {
+                        *change += delta;
+                    }
+                }
+                if within(ts) {
+                    fills.push(StatementFill {
+                        order_id: id.to_string(),
+                        pair: a.pair.clone(),
+                        side: a.side.clone(),
+                        price: f.price,
+                        quantity: f.quantity,
+                        ts_ms: ts,
+                    });
+                }
+            }
+        }
+        fills.sort_by(|a, b| (a.ts_ms, &a.order_id).cmp(&(b.ts_ms, &b.order_id)));
+
+        Statement {
+            account_id: account_id.to_string(),
+            period: period.to_string(),
+            from_ms: from,
+            to_ms: to,
+            orders_placed: orders.iter().filter(|(_, a)| within(a.placed_ms)).count(),
+            orders_filled: closed(OrderStatus::Filled),
+            orders_cancelled: closed(OrderStatus::Cancelled),
+            orders_expired: closed(OrderStatus::Expired),
+            fills,
+            balances: balances
+                .into_iter()
+                .map(|(asset, (opening, change))| AssetBalance {
+                    asset: asset.to_string(),
+                    opening,
+                    change,
+                    closing: opening + change,
+                })
+                .collect(),
+        }
+    }
+
+    /// The period's activity as CSV, one row per placement, fill, fill
+    /// completion or cancel, oldest first.
+    pub fn activity_csv(&self, account_id: &str, period: Period) -> String {
+        let (from, to) = (period.start_ms(), period.end_ms());
+        let within = |ts: i64| (from..to).contains(&ts);
+        let r = self.inner.read().unwrap();
+        let mut rows: Vec<(i64, &str, &str, &Activity, Decimal, Decimal)> = Vec::new();
+        for (id, a) in r.accounts.get(account_id).into_iter().flatten() {
+            if within(a.placed_ms) {
+                rows.push((a.placed_ms, "placed", id, a, a.price, a.quantity));
+            }
+            for f in a.fills.iter().filter(|f| within(f.local_ms())) {
+                rows.push((f.local_ms(), "fill", id, a, f.price, f.quantity));
+            }
+            if let Some((status, ts)) = a.closed.as_ref().filter(|(_, ts)| within(*ts)) {
+                let event = match status {
+                    OrderStatus::Filled => "filled",
+                    OrderStatus::Expired => "expired",
+                    _ => "cancelled",
+                };
+                rows.push((*ts, event, id, a, a.price, a.quantity));
+            }
+        }
+        // Placement before fills before close when they share a millisecond.
+        let rank = |event: &str| match event {
+            "placed" => 0,
+            "fill" => 1,
+            _ => 2,
+        };
+        rows.sort_by(|a, b| (a.0, a.2, rank(a.1)).cmp(&(b.0, b.2, rank(b.1))));
+
+        let mut csv = String::from("ts_ms,event,order_id,pair,side,price,quantity\n");
+        for (ts, event, id, a, price, quantity) in rows {
+            let side = match a.side {
+                OrderSide::Buy => "buy",
+                OrderSide::Sell => "sell",
+            };
+            csv.push_str(&format!(
+                "{ts},{event},{id},{},{side},{},{}\n",
+                a.pair,
+                price.normalize(),
+                quantity.normalize()
+            ));
+        }
+        csv
+    }
+}
+
+impl ChangeListener for StatementLedger {
+    fn on_change(&self, c: &OrderChange) {
+        let mut w = self.inner.write().unwrap();
+        match &c.event {
+            OrderEvent::Created { order } | OrderEvent::Updated { order } => {
+                w.record(order, c.ts_ms)
+            }
+            OrderEvent::Deleted { order } => w.forget(&order.id),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entities::test_support::OrderBuilder;
+    use crate::repositories::changes::Observed;
+    use crate::repositories::in_memory::InMemoryOrderRepository;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn periods_are_utc_calendar_months() {
+        let june = Period::parse("2024-06").unwrap();
+        assert_eq!(june.start_ms(), 1_717_200_000_000);
+        assert_eq!(june.end_ms(), 1_719_792_000_000);
+        assert_eq!(Period::of(1_719_791_999_999), june);
+        assert_eq!(Period::of(1_719_792_000_000).to_string(), "2024-07");
+        let dec = Period::parse("2023-12").unwrap();
+        assert_eq!(dec.end_ms(), Period::parse("2024-01").unwrap().start_ms());
+        // 2024 is a leap year.
+        let feb = Period::parse("2024-02").unwrap();
+        assert_eq!(feb.end_ms() - feb.start_ms(), 29 * DAY_MS);
+        for bad in ["2024-13", "2024-6", "24-06", "june", ""] {
+            assert_eq!(Period::parse(bad), None, "{bad}");
+        }
+    }
+
+    fn fill(price: Decimal, quantity: Decimal, at_ms: i64) -> Fill {
+        Fill {
+            price,
+            quantity,
+            ts_ms: at_ms,
+            received_ms: Some(at_ms),
+            decided_ms: Some(at_ms),
+            recovered_trigger: false,
+            interpolated_trigger: false,
+            legs: Vec::new(),
+            trigger_price: None,
+            fair_sources: Vec::new(),
+            counterparty: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn statements_sum_fills_into_balances_and_forget_anonymized_orders() {
+        let changes = ChangeStream::default();
+        let ledger = StatementLedger::default();
+        ledger.follow(&changes);
+        let repo = Observed::new(InMemoryOrderRepository::default(), changes);
+        let place = |side: OrderBuilder| {
+            let repo = repo.clone();
+            async move {
+                let new = side.limit(100).qty(2).account("acct-1").new_order();
+                repo.create(new).await.unwrap()
+            }
+        };
+        let buy = place(OrderBuilder::buy("BTC/USDT")).await;
+        let sell = place(OrderBuilder::sell("BTC/USDT")).await;
+        let this = Period::of(buy.created);
+        let (from, to) = (this.start_ms(), this.end_ms());
+        // A fill dated last month opens the balances.
+        repo.record_fills(&buy.id, &[fill(dec!(90), dec!(1), from - 1)])
+            .await
+            .unwrap();
+        repo.record_fills(&buy.id, &[fill(dec!(100), dec!(1), from)])
+            .await
+            .unwrap();
+        repo.record_fills(&sell.id, &[fill(dec!(110), dec!(0.5), to - 1)])
+            .await
+            .unwrap();
+        repo.cancel(&sell.id, "test").await.unwrap();
+
+        let s = ledger.statement("acct-1", this);
+        assert_eq!(s.period, this.to_string());
+        assert_eq!(
+            (s.orders_placed, s.orders_filled, s.orders_cancelled),
+            (2, 1, 1)
+        );
+        assert_eq!(s.fills.len(), 2);
+        assert_eq!(s.fills[0].order_id, buy.id);
+        let balance = |asset: &str| {
+            let b = s.balances.iter().find(|b| b.asset == asset).unwrap();
+            (b.opening, b.change, b.closing)
+        };
+        assert_eq!(balance("BTC"), (dec!(1), dec!(0.5), dec!(1.5)));
+        assert_eq!(balance("USDT"), (dec!(-90), dec!(-45), dec!(-135)));
+
+        let csv = ledger.activity_csv("acct-1", this);
+        let events: Vec<&str> = csv
+            .lines()
+            .skip(1)
+            .map(|l| l.split(',').nth(1).unwrap())
+            .collect();
+        assert_eq!(events.len(), 6);
+        assert_eq!(events.iter().filter(|e| **e == "placed").count(), 2);
+        assert!(csv.contains(&format!("{from},fill,{},BTC/USDT,buy,100,1\n", buy.id)));
+
+        // Last month saw only the one fill.
+        let before = ledger.statement("acct-1", Period::of(from - 1));
+        assert_eq!((before.orders_placed, before.fills.len()), (0, 1));
+        assert!(ledger.statement("acct-2", this).fills.is_empty());
+
+        repo.anonymize(&buy.id).await.unwrap();
+        repo.delete(&sell.id).await.unwrap();
+        let s = ledger.statement("acct-1", this);
+        assert_eq!((s.orders_placed, s.balances.len()), (0, 0));
+    }
+}
@@ -0,0 +1,515 @@
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::sync::{Arc, RwLock};
+
+use rust_decimal::Decimal;
+use serde::Serialize;
+
+use crate::entities::events::OrderEvent;
+use crate::entities::order::{Order, OrderStatus, CANCEL_BY_OWNER, CANCEL_REPLACED};
+use crate::instruments::symbol::Symbol;
+use crate::repositories::changes::{ChangeListener, ChangeStream, OrderChange};
+use crate::repositories::{ListOrdersQuery, OrderRepository};
+use crate::utils::now_ms;
+
+/// Span of the rolling submission rate.
+pub const RATE_WINDOW_MS: i64 = 60_000;
+
+/// Span over which an account's cancels are weighed against its fills.
+pub const CANCEL_RATIO_WINDOW_MS: i64 = 300_000;
+
+/// Error code of a cancel sooner than `min_resting_ms` after placement.
+pub const MIN_RESTING_TIME: &str = "min_resting_time";
+
+/// Error code of a cancel past `max_cancel_fill_ratio`.
+pub const CANCEL_RATE: &str = "cancel_rate";
+
+/// Per-account risk limits; `None` is unlimited. The same limits apply to
+/// every account.
+#[derive(Debug, Clone, Copy, Default, Serialize, PartialEq)]
+pub struct AccountLimits {
+    pub max_orders_per_min: Option<usize>,
+    pub max_open_orders: Option<usize>,
+    #[serde(serialize_with = "crate::utils::decimal::serialize_opt")]
+    pub max_open_notional: Option<Decimal>,
+    /// How long an order must rest before its owner may cancel it.
+    pub min_resting_ms: Option<i64>,
+    /// Owner cancels allowed per fill over [`CANCEL_RATIO_WINDOW_MS`],
+    /// counting an account without fills as having one.
+    #[serde(serialize_with = "crate::utils::decimal::serialize_opt")]
+    pub max_cancel_fill_ratio: Option<Decimal>,
+}
+
+impl AccountLimits {
+    pub fn from_env() -> Self {
+        fn var<T: std::str::FromStr>(k: &str) -> Option<T> {
+            std::env::var(k).ok().and_then(|s| s.trim().parse().ok())
+        }
+        Self {
+            max_orders_per_min: var("ACCOUNT_MAX_ORDERS_PER_MIN"),
+            max_open_orders: var("ACCOUNT_MAX_OPEN_ORDERS"),
+            max_open_notional: var("ACCOUNT_MAX_OPEN_NOTIONAL"),
+            min_resting_ms: var("ACCOUNT_MIN_RESTING_MS"),
+            max_cancel_fill_ratio: var("ACCOUNT_MAX_CANCEL_FILL_RATIO"),
+        }
+    }
+}
+
+/// What is left under each limit; `None` where there is no limit.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct Headroom {
+    pub orders_per_min: Option<usize>,
+    pub open_orders: Option<usize>,
+    #[serde(serialize_with = "crate::utils::decimal::serialize_opt")]
+    pub open_notional: Option<Decimal>,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct PairExposure {
+    pub pair: Symbol,
+    pub open_orders: usize,
+    #[serde(serialize_with = "crate::utils::decimal::serialize")]
+    pub open_notional: Decimal,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct UsageReport {
+    pub account_id: String,
+    /// Orders submitted in the last [`RATE_WINDOW_MS`].
+    pub orders_last_minute: usize,
+    pub open_orders: usize,
+    #[serde(serialize_with = "crate::utils::decimal::serialize")]
+    pub open_notional: Decimal,
+    /// Owner cancels and fills in the last [`CANCEL_RATIO_WINDOW_MS`].
+    pub recent_cancels: usize,
+    pub recent_fills: usize,
+    /// Pairs with open orders, by name.
+    pub pairs: Vec<PairExposure>,
+    pub limits: AccountLimits,
+    pub headroom: Headroom,
+}
+
+#[derive(Default)]
+struct Tally {
+    /// Submission times, oldest first, within the rate window.
+    submitted: VecDeque<i64>,
+    /// Owner cancel and fill times, oldest first, within the cancel
+    /// ratio window.
+    cancels: VecDeque<i64>,
+    fills: VecDeque<i64>,
+    /// Active orders by id: pair, remaining quantity at the limit price
+    /// and quantity filled so far.
+    open: HashMap<String, (Symbol, Decimal, Decimal)>,
+}
+
+fn trim(times: &mut VecDeque<i64>, window_ms: i64, now_ms: i64) {
+    while times.front().is_some_and(|ts| *ts <= now_ms - window_ms) {
+        times.pop_front();
+    }
+}
+
+fn within(times: &VecDeque<i64>, window_ms: i64, now_ms: i64) -> usize {
+    times.iter().filter(|ts| **ts > now_ms - window_ms).count()
+}
+
+impl Tally {
+    fn trim(&mut self, now_ms: i64) {
+        trim(&mut self.submitted, RATE_WINDOW_MS, now_ms);
+        trim(&mut self.cancels, CANCEL_RATIO_WINDOW_MS, now_ms);
+        trim(&mut self.fills, CANCEL_RATIO_WINDOW_MS, now_ms);
+    }
+
+    fn recent(&self, now_ms: i64) -> usize {
+        within(&self.submitted, RATE_WINDOW_MS, now_ms)
+    }
+
+    fn open_notional(&self) -> Decimal {
+        self.open.values().map(|(_, n, _)| *n).sum()
+    }
+}
+
+/// Per-account submission rate and open exposure, folded from the change
+/// stream. Open notional is the remaining quantity at the order's limit
+/// price, so market orders count toward open orders only.
+#[derive(Clone, Default)]
+pub struct AccountUsage {
+    limits: AccountLimits,
+    inner: Arc<RwLock<HashMap<String, Tally>>>,
+}
+
+impl AccountUsage {
+    pub fn new(limits: AccountLimits) -> Self {
+        Self {
+            limits,
+            inner: Arc::default(),
+        }
+    }
+
+    /// Subscribes to `stream` and seeds open exposure and the submission
+    /// rate from the orders already in `repo`, so limits hold across a
+    /// restart.
+    pub async fn attach<R: OrderRepository + ?Sized>(
+        &self,
+        repo: &R,
+        stream: &ChangeStream,
+    ) -> Result<(), String> {
+        stream.listen(Arc::new(self.clone()));
+        let mut orders = repo.list(ListOrdersQuery::default()).await?;
+        orders.sort_by_key(|o| o.created);
+        let now = now_ms();
+        for o in &orders {
+            let recent = o.created > now - RATE_WINDOW_MS;
+            self.apply(o, recent, false, if recent { o.created } else { now });
+        }
+        self.seed_activity(&orders, now);
+        Ok(())
+    }
+
+    /// Owner cancels and fills still inside the ratio window, from the
+    /// stored orders. Fills count by their time; cancels by the order's
+    /// last update.
+    fn seed_activity(&self, orders: &[Order], now: i64) {
+        let since = now - CANCEL_RATIO_WINDOW_MS;
+        let mut w = self.inner.write().unwrap();
+        for o in orders {
+            let Some(account_id) = &o.account_id else {
+                continue;
+            };
+            let t = w.entry(account_id.clone()).or_default();
+            t.fills.extend(
+                o.fills
+                    .iter()
+                    .map(|f| f.local_ms())
+                    .filter(|ts| *ts > since),
+            );
+            if is_owner_cancel(o) && o.updated > since {
+                t.cancels.push_back(o.updated);
+            }
+        }
+        for t in w.values_mut() {
+            t.fills.make_contiguous().sort_unstable();
+            t.cancels.make_contiguous().sort_unstable();
+        }
+    }
+
+    /// Like [`attach`](Self::attach) for a store known to be empty.
+    pub fn follow(&self, stream: &ChangeStream) {
+        stream.listen(Arc::new(self.clone()));
+    }
+
+    pub fn report(&self, account_id: &str, now_ms: i64) -> UsageReport {
+        let r = self.inner.read().unwrap();
+        let empty = Tally::default();
+        let t = r.get(account_id).unwrap_or(&empty);
+        let mut pairs: BTreeMap<&str, PairExposure> = BTreeMap::new();
+        for (pair, notional, _) in t.open.values() {
+            let p = pairs.entry(pair).or_insert_with(|| PairExposure {
+                pair: pair.clone(),
+                open_orders: 0,
+                open_notional: Decimal::ZERO,
+            });
+            p.open_orders += 1;
+            p.open_notional += notional;
+        }
+        let (recent, open, notional) = (t.recent(now_ms), t.open.len(), t.open_notional());
+        UsageReport {
+            account_id: account_id.to_string(),
+            orders_last_minute: recent,
+            open_orders: open,
+            open_notional: notional,
+            recent_cancels: within(&t.cancels, CANCEL_RATIO_WINDOW_MS, now_ms),
+            recent_fills: within(&t.fills, CANCEL_RATIO_WINDOW_MS, now_ms),
+            pairs: pairs.into_values().collect(),
+            limits: self.limits,
+            headroom: Headroom {
+                orders_per_min: self
+                    .limits
+                    .max_orders_per_min
+                    .map(|m| m.saturating_sub(recent)),
+                open_orders: self.limits.max_open_orders.map(|m| m.saturating_sub(open)),
+                open_notional: self
+                    .limits
+                    .max_open_notional
+                    .map(|m| (m - notional).max(Decimal::ZERO)),
+            },
+        }
+    }
+
+    /// Whether the account may submit `orders` more orders adding
+    /// `notional`; otherwise which limit they would break.
+    pub fn admit(
+        &self,
+        account_id: &str,
+        orders: usize,
+        notional: Decimal,
+        now_ms: i64,
+    ) -> Result<(), String> {
+        let h = self.report(account_id, now_ms).headroom;
+        if h.orders_per_min.is_some_and(|left| orders > left) {
+            return Err(format!(
+                "more than {} orders per minute",
+                self.limits.max_orders_per_min.unwrap_or_default()
+            ));
+        }
+        if h.open_orders.is_some_and(|left| orders > left) {
+            return Err(format!(
+                "more than {} open orders",
+                self.limits.max_open_orders.unwrap_or_default()
+            ));
+        }
+        if h.open_notional.is_some_and(|left| notional > left) {
+            return Err(format!(
+                "open notional above {}",
+                self.limits.max_open_notional.unwrap_or_default()
+            ));
+        }
+        Ok(())
+    }
+
+    /// Whether the owner may cancel `order` now; otherwise the error code
+    /// and which policy the cancel would break. Orders without an account
+    /// are not policed.
+    pub fn admit_cancel(&self, order: &Order, now_ms: i64) -> Result<(), (&'static str, String)> {
+        let Some(account_id) = &order.account_id else {
+            return Ok(());
+        };
+        if let Some(min) = self.limits.min_resting_ms {
+            let rested = now_ms - order.created;
+            if rested < min {
+                return Err((
+                    MIN_RESTING_TIME,
+                    format!("order has rested {rested} ms of the required {min} ms"),
+                ));
+            }
+        }
+        if let Some(ratio) = self.limits.max_cancel_fill_ratio {
+            let r = self.report(account_id, now_ms);
+            let allowed = ratio * Decimal::from(r.recent_fills.max(1));
+            if Decimal::from(r.recent_cancels + 1) > allowed {
+                return Err((
+                    CANCEL_RATE,
+                    format!(
+                        "{} cancels against {} fills in {} s; at most {ratio} per fill",
+                        r.recent_cancels,
+                        r.recent_fills,
+                        CANCEL_RATIO_WINDOW_MS / 1000
+                    ),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn apply(&self, o: &Order, created: bool, deleted: bool, ts_ms: i64) {
+        let Some(account_id) = &o.account_id else {
+            return;
+        };
+        let mut w = self.inner.write().unwrap();
+        let t = w.entry(account_id.clone()).or_default();
+        if created {
+            t.submitted.push_back(ts_ms);
+        }
+        t.trim(ts_ms);
+        // Only a change seen from an open order is new activity.
+        if let Some((_, _, filled)) = t.open.get(&o.id) {
+            if o.filled_quantity > *filled {
+                t.fills.push_back(ts_ms);
+            }
+            if !deleted && is_owner_cancel(o) {
+                t.cancels.push_back(ts_ms);
+            }
+        }
+        if o.status.is_active() && !deleted {
+            let notional = (o.quantity - o.filled_quantity) * o.price;
+            t.open
+                .insert(o.id.clone(), (o.pair.clone(), notional, o.filled_quantity));
+        } else {
+            t.open.remove(&o.id);
+        }
+    }
+}
+
+/// Cancelled by its owner, directly or by replacing it.
+fn is_owner_cancel(o: &Order) -> bool {
+    o.status == OrderStatus::Cancelled
+        && matches!(
+            o.cancel_reason.as_deref(),
+            Some(CANCEL_BY_OWNER | CANCEL_REPLACED)
+        )
+}
+
+impl ChangeListener for AccountUsage {
+    fn on_change(&self, c: &OrderChange) {
+        match &c.event {
+            OrderEvent::Created { order } => self.apply(order, true, false, c.ts_ms),
+            OrderEvent::Updated { order } => self.apply(order, false, false, c.ts_ms),
+            OrderEvent::Deleted { order } => self.apply(order, false, true, c.ts_ms),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entities::events::Envelope;
+    use crate::entities::order::{OrderSide, OrderStatus};
+    use rust_decimal_macros::dec;
+
+    fn order(account: &str, pair: &str, price: Decimal, qty: Decimal) -> Order {
+        Order {
+            account_id: Some(account.into()),
+            ..Order::new(pair.into(), OrderSide::Buy, price, qty)
+        }
+    }
+
+    #[tokio::test]
+    async fn attaching_seeds_exposure_and_rate_from_the_store() {
+        use crate::repositories::in_memory::InMemoryOrderRepository;
+
+        let repo = InMemoryOrderRepository::default();
+        for pair in ["BTC/USDT", "ETH/USDT"] {
+            let mut new = crate::entities::test_support::OrderBuilder::buy(pair)
+                .limit(100)
+                .new_order();
+            new.account_id = Some("acc".into());
+            repo.create(new).await.unwrap();
+        }
+        let usage = AccountUsage::default();
+        usage.attach(&repo, &ChangeStream::default()).await.unwrap();
+        let report = usage.report("acc", now_ms());
+        assert_eq!((report.orders_last_minute, report.open_orders), (2, 2));
+        assert_eq!(report.pairs.len(), 2);
+    }
+
+    #[test]
+    fn usage_tracks_rate_exposure_and_headroom() {
+        let usage = AccountUsage::new(AccountLimits {
+            max_orders_per_min: Some(3),
+            max_open_orders: None,
+            max_open_notional: Some(dec!(1000)),
+            ..AccountLimits::default()
+        });
+        let change = |event, ts_ms| usage.on_change(&Envelope::new(event, ts_ms));
+        let a = order("acc", "BTC/USDT", dec!(100), dec!(4));
+        let b = order("acc", "ETH/USDT", dec!(50), dec!(2));
+        change(OrderEvent::Created { order: a.clone() }, 1_000);
+        change(OrderEvent::Created { order: b.clone() }, 2_000);
+        change(
+            OrderEvent::Created {
+                order: order("other", "BTC/USDT", dec!(1), dec!(1)),
+            },
+            2_000,
+        );
+        change(
+            OrderEvent::Updated {
+                order: Order {
+                    status: OrderStatus::PartiallyFilled,
+                    filled_quantity: dec!(1),
+                    ..a.clone()
+                },
+            },
+            3_000,
+        );
+
+        let r = usage.report("acc", 3_000);
+        assert_eq!((r.orders_last_minute, r.open_orders), (2, 2));
+        assert_eq!(r.open_notional, dec!(400));
+        assert_eq!(r.pairs[0].pair, "BTC/USDT");
+        assert_eq!(r.pairs[0].open_notional, dec!(300));
+        assert_eq!(r.headroom.orders_per_min, Some(1));
+        assert_eq!(r.headroom.open_orders, None);
+        assert_eq!(r.headroom.open_notional, Some(dec!(600)));
+
+        assert!(usage.admit("acc", 1, dec!(600), 3_000).is_ok());
+        assert!(usage.admit("acc", 1, dec!(601), 3_000).is_err());
+        assert!(usage.admit("acc", 2, Decimal::ZERO, 3_000).is_err());
+        // The rate window rolls; the exposure stays.
+        assert_eq!(usage.report("acc", 61_500).orders_last_minute, 1);
+
+        change(OrderEvent::Deleted { order: b }, 4_000);
+        assert_eq!(usage.report("acc", 4_000).open_orders, 1);
+        assert_eq!(usage.report("nobody", 4_000).open_orders, 0);
+    }
+
+    #[test]
+    fn cancels_need_resting_time_and_fills_to_weigh_against() {
+        let usage = AccountUsage::new(AccountLimits {
+            min_resting_ms: Some(500),
+            max_cancel_fill_ratio: Some(dec!(2)),
+            ..AccountLimits::default()
+        });
+        let change = |event, ts_ms| usage.on_change(&Envelope::new(event, ts_ms));
+        let orders: Vec<Order> = (0..5)
+            .map(|_| Order {
+                created: 1_000,
+                ..order("acc", "BTC/USDT", dec!(100), dec!(1))
+            })
+            .collect();
+        for o in &orders {
+            change(OrderEvent::Created { order: o.clone() }, 1_000);
+        }
+        let cancelled = |o: &Order, reason: &str| Order {
+            status: OrderStatus::Cancelled,
+            cancel_reason: Some(reason.into()),
+            ..o.clone()
+        };
+
+        let (code, _) = usage.admit_cancel(&orders[0], 1_200).unwrap_err();
+        assert_eq!(code, MIN_RESTING_TIME);
+        assert!(usage.admit_cancel(&orders[0], 1_500).is_ok());
+
+        // No fills yet count as one: two cancels are allowed, and a
+        // replace is one of them.
+        for (o, reason) in orders[..2].iter().zip([CANCEL_BY_OWNER, CANCEL_REPLACED]) {
+            change(
+                OrderEvent::Updated {
+                    order: cancelled(o, reason),
+                },
+                2_000,
+            );
+        }
+        // The engine's own cancels do not count.
+        change(
+            OrderEvent::Updated {
+                order: cancelled(&orders[2], "cancel_after"),
+            },
+            2_000,
+        );
+        let (code, why) = usage.admit_cancel(&orders[3], 2_000).unwrap_err();
+        assert_eq!(code, CANCEL_RATE);
+        assert!(why.starts_with("2 cancels against 0 fills"), "{why}");
+
+        change(
+            OrderEvent::Updated {
+                order: Order {
+                    status: OrderStatus::Filled,
+                    filled_quantity: dec!(1),
+                    ..orders[4].clone()
+                },
+            },
+            2_100,
+        );
+        change(
+            OrderEvent::Updated {
+                order: Order {
+                    status: OrderStatus::Filled,
+                    filled_quantity: dec!(1),
+                    ..orders[4].clone()
+                },
+            },
+            2_200,
+        );
+        let r = usage.report("acc", 2_200);
+        assert_eq!((r.recent_cancels, r.recent_fills), (2, 1));
+        assert!(usage.admit_cancel(&orders[3], 2_200).is_err());
+        // Old cancels leave the window.
+        assert!(usage
+            .admit_cancel(&orders[3], 2_000 + CANCEL_RATIO_WINDOW_MS)
+            .is_ok());
+        assert!(usage
+            .admit_cancel(
+                &Order::new("BTC/USDT".into(), OrderSide::Buy, dec!(1), dec!(1)),
+                0
+            )
+            .is_ok());
+    }
+}
@@ -0,0 +1,627 @@
+//! A typed async client for the REST and websocket APIs, behind the
+//! `client` feature: place and cancel orders, follow the account's fills
+//! over `/ws/orders`, and follow a pair's ticks and trades over
+//! `/markets/{pair}/stream`. Both streams reconnect on their own.
+
+use std::collections::{HashMap, VecDeque};
+use std::time::Duration;
+
+use derive_more::Display;
+use futures_util::StreamExt;
+use rust_decimal::Decimal;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use tokio::net::TcpStream;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::http::{header::AUTHORIZATION, HeaderValue};
+use tokio_tungstenite::tungstenite::protocol::Message;
+use tokio_tungstenite::{connect_async, MaybeTlsStream, WebSocketStream};
+
+use crate::entities::events::{Envelope, OrderEvent};
+use crate::entities::order::{Fill, Order, OrderSide};
+use crate::limits::admin::ADMIN_TOKEN_HEADER;
+use crate::oracle_service::hub::MarketData;
+use crate::utils::http;
+
+/// A failed call. Errors the server tags with a `code` get their own
+/// variant, so callers can act on the cause; `error` is the server's
+/// message.
+#[derive(Debug, Display, Clone, PartialEq)]
+pub enum ClientError {
+    /// `rate_limited`: too many anonymous requests; try again after
+    /// `retry_after`, when the server said.
+    #[display("rate limited: {error}")]
+    RateLimited {
+        retry_after: Option<Duration>,
+        error: String,
+    },
+    /// `account_limit`: the order would break one of the account's limits.
+    #[display("risk limit: {_0}")]
+    RiskLimit(String),
+    /// `min_resting_time` or `cancel_rate`: an anti-churn policy refused
+    /// the cancel.
+    #[display("cancel refused ({code}): {error}")]
+    CancelRefused { code: String, error: String },
+    /// `invalid_transition`: the order's status does not allow the change,
+    /// e.g. cancelling a filled order.
+    #[display("invalid transition: {_0}")]
+    InvalidTransition(String),
+    /// `stale_oracle`: a market order needs a fresh price for its pair.
+    #[display("stale oracle: {_0}")]
+    StaleOracle(String),
+    /// `cancel_only`: the whole feed is down; only cancels are taken.
+    #[display("cancel only: {_0}")]
+    CancelOnly(String),
+    /// Any other error status, with its `code` if the server sent one.
+    #[display("{status}: {error}")]
+    Http {
+        status: u16,
+        code: Option<String>,
+        error: String,
+    },
+    /// The request never got an answer.
+    #[display("transport: {_0}")]
+    Transport(String),
+    /// The answer was not what the API promises.
+    #[display("decode: {_0}")]
+    Decode(String),
+}
+
+impl std::error::Error for ClientError {}
+
+impl ClientError {
+    /// The error a non-2xx response stands for, from its `code`.
+    fn from_response(status: u16, retry_after: Option<&str>, body: &Value) -> Self {
+        let error = body["error"]
+            .as_str()
+            .map_or_else(|| body.to_string(), str::to_string);
+        let code = body["code"].as_str();
+        match code {
+            Some("rate_limited") => Self::RateLimited {
+                retry_after: retry_after
+                    .and_then(|s| s.trim().parse().ok())
+                    .map(Duration::from_secs),
+                error,
+            },
+            Some("account_limit") => Self::RiskLimit(error),
+            Some(c @ ("min_resting_time" | "cancel_rate")) => Self::CancelRefused {
+                code: c.to_string(),
+                error,
+            },
+            Some("invalid_transition") => Self::InvalidTransition(error),
+            Some("stale_oracle") => Self::StaleOracle(error),
+            Some("cancel_only") => Self::CancelOnly(error),
+            _ => Self::Http {
+                status,
+                code: code.map(str::to_string),
+                error,
+            },
+        }
+    }
+
+    /// The HTTP status behind the error, if the server answered.
+    pub fn status(&self) -> Option<u16> {
+        match self {
+            Self::RateLimited { .. } | Self::RiskLimit(_) | Self::CancelRefused { .. } => Some(429),
+            Self::InvalidTransition(_) => Some(409),
+            Self::StaleOracle(_) | Self::CancelOnly(_) => Some(503),
+            Self::Http { status, .. } => Some(*status),
+            Self::Transport(_) | Self::Decode(_) => None,
+        }
+    }
+
+    /// Worth retrying: the server was unreachable or failing, not refusing.
+    /// A stale feed or cancel-only mode clears once ticks flow again.
+    fn is_transient(&self) -> bool {
+        match self {
+            Self::Transport(_) => true,
+            Self::Http { status, .. } => *status >= 500,
+            Self::StaleOracle(_) | Self::CancelOnly(_) => true,
+            _ => false,
+        }
+    }
+}
+
+/// An order to place. The common fields are typed; anything else
+/// `POST /orders` takes goes in `extra`, e.g.
+/// `OrderRequest::limit("BTC/USDT", OrderSide::Buy, price, qty).with("cancel_after_ms", 500)`.
+#[derive(Debug, Clone, Serialize)]
+pub struct OrderRequest {
+    pub pair: String,
+    pub side: OrderSide,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub price: Option<Decimal>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quantity: Option<Decimal>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl OrderRequest {
+    pub fn limit(pair: &str, side: OrderSide, price: Decimal, quantity: Decimal) -> Self {
+        Self {
+            pair: pair.to_string(),
+            side,
+            price: Some(price),
+            quantity: Some(quantity),
+            extra: Map::new(),
+        }
+    }
+
+    pub fn with(mut self, field: &str, value: impl Into<Value>) -> Self {
+        self.extra.insert(field.to_string(), value.into());
+        self
+    }
+}
+
+/// The orderbook at `base`, e.g. `http://127.0.0.1:8080`, optionally as an
+/// account's API key. Cheap to clone.
+#[derive(Debug, Clone)]
+pub struct Client {
+    host: String,
+    api_key: Option<String>,
+    admin_token: Option<String>,
+    /// Wait between reconnect attempts of a stream.
+    pub reconnect_backoff: Duration,
+    /// Failed attempts in a row after which a stream gives up and returns
+    /// the error.
+    pub max_reconnects: u32,
+}
+
+impl Client {
+    /// Only plain `http` is supported.
+    pub fn new(base: &str) -> Result<Self, ClientError> {
+        let url = url::Url::parse(base).map_err(|e| ClientError::Decode(e.to_string()))?;
+        if url.scheme() != "http" {
+            return Err(ClientError::Decode(format!(
+                "unsupported scheme {}",
+                url.scheme()
+            )));
+        }
+        let host = url
+            .host_str()
+            .ok_or_else(|| ClientError::Decode(format!("no host in {base}")))?;
+        Ok(Self {
+            host: format!("{host}:{}", url.port().unwrap_or(80)),
+            api_key: None,
+            admin_token: None,
+            reconnect_backoff: Duration::from_millis(500),
+            max_reconnects: 10,
+        })
+    }
+
+    pub fn with_api_key(mut self, key: impl Into<String>) -> Self {
+        self.api_key = Some(key.into());
+        self
+    }
+
+    /// Sends `token` to the `/admin` endpoints, as `ADMIN_TOKEN`.
+    pub fn with_admin_token(mut self, token: impl Into<String>) -> Self {
+        self.admin_token = Some(token.into());
+        self
+    }
+
+    fn headers(&self) -> Vec<(&'static str, String)> {
+        let mut h = vec![("Accept", "application/json".to_string())];
+        if let Some(k) = &self.api_key {
+            h.push(("Authorization", format!("Bearer {k}")));
+        }
+        if let Some(t) = &self.admin_token {
+            h.push((ADMIN_TOKEN_HEADER, t.clone()));
+        }
+        h
+    }
+
+    /// Any endpoint: the status and JSON body (`Null` when empty), with no
+    /// judgement on the status.
+    pub async fn call(
+        &self,
+        method: &str,
+        path: &str,
+        body: Option<&Value>,
+    ) -> Result<(u16, Value), ClientError> {
+        let (head, json) = self.exchange(method, path, body).await?;
+        Ok((head.status, json))
+    }
+
+    async fn exchange(
+        &self,
+        method: &str,
+        path: &str,
+        body: Option<&Value>,
+    ) -> Result<(http::Head, Value), ClientError> {
+        let body = body.map(Value::to_string);
+        let (head, rest) = http::send(
+            &self.host,
+            method,
+            path,
+            &self.headers(),
+            body.as_deref().map(str::as_bytes),
+        )
+        .await
+        .map_err(ClientError::Transport)?;
+        let raw = rest.read_all().await.map_err(ClientError::Transport)?;
+        let json = if raw.iter().all(u8::is_ascii_whitespace) {
+            Value::Null
+        } else {
+            serde_json::from_slice(&raw).map_err(|e| ClientError::Decode(e.to_string()))?
+        };
+        Ok((head, json))
+    }
+
+    /// Like [`call`](Self::call), with a non-2xx status as a typed error and
+    /// the body decoded as `T`.
+    pub async fn request<T: DeserializeOwned>(
+        &self,
+        method: &str,
+        path: &str,
+        body: Option<&Value>,
+    ) -> Result<T, ClientError> {
+        let (head, json) = self.exchange(method, path, body).await?;
+        if !(200..300).contains(&head.status) {
+            return Err(ClientError::from_response(
+                head.status,
+                head.header("retry-after"),
+                &json,
+            ));
+        }
+        serde_json::from_value(json).map_err(|e| ClientError::Decode(e.to_string()))
+    }
+
+    pub async fn place_order(&self, order: &OrderRequest) -> Result<Order, ClientError> {
+        let body = serde_json::to_value(order).map_err(|e| ClientError::Decode(e.to_string()))?;
+        self.request("POST", "/orders", Some(&body)).await
+    }
+
+    pub async fn get_order(&self, id: &str) -> Result<Order, ClientError> {
+        self.request("GET", &format!("/orders/{id}"), None).await
+    }
+
+    pub async fn cancel_order(&self, id: &str) -> Result<Order, ClientError> {
+        self.request("POST", &format!("/orders/{id}/cancel"), None)
+            .await
+    }
+
+    /// The pair's ticks and trade prints as they are published.
+    pub fn prices(&self, pair: &str) -> PriceStream {
+        PriceStream {
+            client: self.clone(),
+            path: format!("/markets/{pair}/stream"),
+            body: None,
+            buf: String::new(),
+        }
+    }
+
+    /// The account's order events, and the fills in them, over
+    /// `/ws/orders`. Needs an API key.
+    pub fn order_events(&self) -> OrderEvents {
+        OrderEvents {
+            client: self.clone(),
+            socket: None,
+            fills_seen: HashMap::new(),
+            fills: VecDeque::new(),
+            reconnects: 0,
+        }
+    }
+
+    /// Runs `attempt` until it succeeds, fails for good, or has failed
+    /// `max_reconnects` times in a row.
+    async fn retrying<T, F, Fut>(&self, mut attempt: F) -> Result<T, ClientError>
+    where
+        F: FnMut() -> Fut,
+        Fut: std::future::Future<Output = Result<T, ClientError>>,
+    {
+        let mut failures = 0;
+        loop {
+            match attempt().await {
+                Ok(v) => return Ok(v),
+                Err(e) if e.is_transient() && failures < self.max_reconnects => {
+                    failures += 1;
+                    tracing::debug!(err = %e, failures, "client: reconnecting");
+                    tokio::time::sleep(self.reconnect_backoff).await;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+/// A pair's market data over server-sent events. The stream reconnects, to
+/// the same pair, whenever the connection drops; messages published while
+/// it was down are missed.
+pub struct PriceStream {
+    client: Client,
+    path: String,
+    body: Option<http::Body>,
+    buf: String,
+}
+
+impl PriceStream {
+    pub async fn next(&mut self) -> Result<MarketData, ClientError> {
+        loop {
+            if let Some(data) = next_event_data(&mut self.buf) {
+                return serde_json::from_str(&data).map_err(|e| ClientError::Decode(e.to_string()));
+            }
+            if self.body.is_none() {
+                {
+                    let (client, path) = (&self.client, &self.path);
+                    let body = client
+                        .retrying(|| async move {
+                            let (head, body) =
+                                http::send(&client.host, "GET", path, &client.headers(), None)
+                                    .await
+                                    .map_err(ClientError::Transport)?;
+                            if head.status != 200 {
+                                let raw = body.read_all().await.unwrap_or_default();
+                                let json = serde_json::from_slice(&raw).unwrap_or_else(|_| {
+                                    Value::String(String::from_utf8_lossy(&raw).into_owned())
+                                });
+                                return Err(ClientError::from_response(
+                                    head.status,
+                                    head.header("retry-after"),
+                                    &json,
+                                ));
+                            }
+                            Ok(body)
+                        })
+                        .await?;
+                    self.buf.clear();
+                    self.body = Some(body);
+                }
+            }
+            let Some(body) = &mut self.body else {
+                continue;
+            };
+            match body.next().await {
+                Ok(Some(piece)) => self
+                    .buf
+                    .push_str(&String::from_utf8_lossy(&piece).replace("\r\n", "\n")),
+                Ok(None) | Err(_) => self.body = None,
+            }
+        }
+    }
+}
+
+/// Takes the first complete event off `buf` and returns its data lines.
+fn next_event_data(buf: &mut String) -> Option<String> {
+    loop {
+        let end = buf.find("\n\n")?;
+        let event: String = buf.drain(..end + 2).collect();
+        let data: Vec<&str> = event
+            .lines()
+            .filter_map(|l| l.strip_prefix("data:"))
+            .map(|d| d.strip_prefix(' ').unwrap_or(d))
+            .collect();
+        if !data.is_empty() {
+            return Some(data.join("\n"));
+        }
+    }
+}
+
+/// A fill on one of the account's orders, with the order after it.
+#[derive(Debug, Clone)]
+pub struct FillEvent {
+    pub order: Order,
+    pub fill: Fill,
+}
+
+type Socket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// The account's order events. On a dropped connection it reconnects and
+/// then catches up on the orders it has seen by reading them back, so
+/// [`next_fill`](Self::next_fill) misses no fill of those; events of
+/// orders never seen before the drop are only caught from then on.
+pub struct OrderEvents {
+    client: Client,
+    socket: Option<Socket>,
+    /// Fills already reported per order.
+    fills_seen: HashMap<String, usize>,
+    fills: VecDeque<FillEvent>,
+    reconnects: u32,
+}
+
+impl OrderEvents {
+    /// How many times the socket has been reopened.
+    pub fn reconnects(&self) -> u32 {
+        self.reconnects
+    }
+
+    /// Opens the socket now rather than on the first read, so no event
+    /// after this is missed. A no-op while connected.
+    pub async fn connect(&mut self) -> Result<(), ClientError> {
+        if self.socket.is_some() {
+            return Ok(());
+        }
+        let socket = self.client.retrying(|| self.open()).await?;
+        let resumed = !self.fills_seen.is_empty();
+        self.socket = Some(socket);
+        if resumed {
+            self.reconnects += 1;
+            self.catch_up().await;
+        }
+        Ok(())
+    }
+
+    pub async fn next(&mut self) -> Result<OrderEvent, ClientError> {
+        loop {
+            self.connect().await?;
+            let Some(socket) = &mut self.socket else {
+                continue;
+            };
+            let text = match socket.next().await {
+                Some(Ok(Message::Text(t))) => t.to_string(),
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => {
+                    self.socket = None;
+                    continue;
+                }
+                Some(Ok(_)) => continue,
+            };
+            let v: Value =
+                serde_json::from_str(&text).map_err(|e| ClientError::Decode(e.to_string()))?;
+            if v["type"] == "response" {
+                continue;
+            }
+            let envelope: Envelope<OrderEvent> =
+                serde_json::from_value(v).map_err(|e| ClientError::Decode(e.to_string()))?;
+            self.track(envelope.order());
+            return Ok(envelope.event);
+        }
+    }
+
+    /// The next fill on any of the account's orders, in the order
+    /// they arrive.
+    pub async fn next_fill(&mut self) -> Result<FillEvent, ClientError> {
+        loop {
+            if let Some(f) = self.fills.pop_front() {
+                return Ok(f);
+            }
+            self.next().await?;
+        }
+    }
+
+    fn track(&mut self, o: &Order) {
+        let seen = self.fills_seen.entry(o.id.clone()).or_default();
+        for fill in o.fills.iter().skip(*seen) {
+            self.fills.push_back(FillEvent {
+                order: o.clone(),
+                fill: fill.clone(),
+            });
+        }
+        *seen = (*seen).max(o.fills.len());
+    }
+
+    async fn catch_up(&mut self) {
+        let ids: Vec<String> = self.fills_seen.keys().cloned().collect();
+        for id in ids {
+            if let Ok(o) = self.client.get_order(&id).await {
+                self.track(&o);
+            }
+        }
+    }
+
+    async fn open(&self) -> Result<Socket, ClientError> {
+        let transport = |e: String| ClientError::Transport(e);
+        let mut req = format!("ws://{}/ws/orders", self.client.host)
+            .into_client_request()
+            .map_err(|e| transport(e.to_string()))?;
+        if let Some(k) = &self.client.api_key {
+            let value = HeaderValue::from_str(&format!("Bearer {k}"))
+                .map_err(|e| ClientError::Decode(e.to_string()))?;
+            req.headers_mut().insert(AUTHORIZATION, value);
+        }
+        match connect_async(req).await {
+            Ok((socket, _)) => Ok(socket),
+            Err(tokio_tungstenite::tungstenite::Error::Http(resp)) => Err(ClientError::Http {
+                status: resp.status().as_u16(),
+                code: None,
+                error: "upgrade refused".into(),
+            }),
+            Err(e) => Err(transport(e.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+    use serde_json::json;
+
+    #[test]
+    fn sse_events_are_split_and_comments_skipped() {
+        let mut buf =
+            ": keep-alive\n\nevent: tick\ndata: {\"a\":\ndata: 1}\n\nevent: tr".to_string();
+        assert_eq!(next_event_data(&mut buf).as_deref(), Some("{\"a\":\n1}"));
+        assert_eq!(next_event_data(&mut buf), None);
+        assert_eq!(buf, "event: tr");
+    }
+
+    #[test]
+    fn order_requests_flatten_extra_fields() {
+        let req = OrderRequest::limit("BTC/USDT", OrderSide::Buy, dec!(100), dec!(1))
+            .with("cancel_after_ms", 500);
+        let v = serde_json::to_value(&req).unwrap();
+        assert_eq!(v["side"], "buy");
+        assert_eq!(v["cancel_after_ms"], 500);
+        assert!(Client::new("https://example.com").is_err());
+        assert_eq!(
+            Client::new("http://localhost").unwrap().host,
+            "localhost:80"
+        );
+    }
+
+    #[test]
+    fn error_codes_map_to_their_own_variants() {
+        let err = |status, retry_after, body: Value| {
+            ClientError::from_response(status, retry_after, &body)
+        };
+        assert_eq!(
+            err(
+                429,
+                Some("7"),
+                json!({"error": "slow down", "code": "rate_limited"})
+            ),
+            ClientError::RateLimited {
+                retry_after: Some(Duration::from_secs(7)),
+                error: "slow down".into()
+            }
+        );
+        assert_eq!(
+            err(429, None, json!({"error": "x", "code": "cancel_rate"})),
+            ClientError::CancelRefused {
+                code: "cancel_rate".into(),
+                error: "x".into()
+            }
+        );
+        let e = err(
+            409,
+            None,
+            json!({"error": "x", "code": "invalid_transition"}),
+        );
+        assert_eq!(e, ClientError::InvalidTransition("x".into()));
+        assert_eq!(e.status(), Some(409));
+        assert!(!e.is_transient());
+        let e = err(503, None, json!({"error": "x", "code": "stale_oracle"}));
+        assert!(matches!(e, ClientError::StaleOracle(_)) && e.is_transient());
+        assert_eq!(
+            err(404, None, json!({"error": "not found"})),
+            ClientError::Http {
+                status: 404,
+                code: None,
+                error: "not found".into()
+            }
+        );
+        assert!(matches!(
+            err(502, None, json!("bad gateway")),
+            ClientError::Http { status: 502, ref error, .. } if error == "\"bad gateway\""
+        ));
+    }
+
+    #[test]
+    fn fills_are_reported_once_each() {
+        let mut events = Client::new("http://localhost").unwrap().order_events();
+        let mut o = Order::new("BTC/USDT".into(), OrderSide::Buy, dec!(100), dec!(2));
+        events.track(&o);
+        let fill = Fill {
+            price: dec!(100),
+            quantity: dec!(1),
+            ts_ms: 1,
+            received_ms: None,
+            decided_ms: None,
+            recovered_trigger: false,
+            interpolated_trigger: false,
+            legs: Vec::new(),
+            trigger_price: None,
+            fair_sources: Vec::new(),
+            counterparty: None,
+        };
+        o.fills.push(fill.clone());
+        events.track(&o);
+        events.track(&o);
+        o.fills.push(Fill { ts_ms: 2, ..fill });
+        events.track(&o);
+        let ts: Vec<i64> = events.fills.iter().map(|f| f.fill.ts_ms).collect();
+        assert_eq!(ts, [1, 2]);
+    }
+}
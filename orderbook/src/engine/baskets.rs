@@ -0,0 +1,250 @@
+use std::time::Duration;
+
+use rust_decimal::Decimal;
+use tokio::time::{interval, MissedTickBehavior};
+use tracing::{debug, info, warn};
+
+use super::halts::HaltRegistry;
+use super::market_view;
+use crate::entities::basket::{Basket, BasketMode, BasketStatus, LegStatus, CANCEL_BASKET_FAILED};
+use crate::entities::order::{Condition, NewOrder, OrderType, TimeInForce, TriggerPriceType};
+use crate::instruments::session::SessionState;
+use crate::instruments::InstrumentRegistry;
+use crate::oracle_service::OracleCache;
+use crate::repositories::baskets::BasketStore;
+use crate::repositories::OrderRepository;
+use crate::utils::now_ms;
+
+/// What a basket watcher needs besides the store and the repository.
+#[derive(Clone)]
+pub struct BasketMarkets {
+    pub oracle: OracleCache,
+    pub instruments: InstrumentRegistry,
+    pub halts: HaltRegistry,
+}
+
+impl BasketMarkets {
+    /// Why `pair` cannot take a market order now, if it cannot.
+    async fn blocker(&self, pair: &str, now: i64) -> Option<&'static str> {
+        if self.halts.is_halted(pair) {
+            Some("pair halted")
+        } else if self.instruments.session_state(pair, now) != SessionState::Open {
+            Some("market not in session")
+        } else if !self.oracle.has_fresh_price(pair, now).await {
+            Some("no fresh oracle price")
+        } else {
+            None
+        }
+    }
+
+    /// The trigger reference of a pending basket when its condition holds
+    /// on a fresh tick.
+    async fn triggered(&self, b: &Basket) -> Option<Decimal> {
+        let view = market_view(&self.oracle, &self.instruments, &b.trigger.pair).await?;
+        let ref_px = view.reference(b.trigger.trigger_price_type)?;
+        (!view.stale && b.trigger.holds(ref_px)).then_some(ref_px)
+    }
+}
+
+fn leg_order(b: &Basket, i: usize) -> NewOrder {
+    let leg = &b.legs[i];
+    NewOrder {
+        pair: leg.pair.clone(),
+        side: leg.side.clone(),
+        order_type: OrderType::Market,
+        price: Decimal::ZERO,
+        quantity: leg.quantity,
+        trigger_price_type: TriggerPriceType::Last,
+        price_source: None,
+        condition: Condition::Price,
+        sustained_for_ms: None,
+        time_in_force: TimeInForce::Gtc,
+        max_slippage_bps: None,
+        exec_pricing: None,
+        account_id: b.account_id.clone(),
+        quantity_quote: None,
+        cancel_after_ms: None,
+        valid_for_after_armed_ms: None,
+        expires_at: None,
+        tags: Vec::new(),
+        metadata: Default::default(),
+        callback_url: None,
+        callback_template: None,
+        trigger_price: None,
+        trailing: None,
+        oco_id: None,
+    }
+}
+
+async fn cancel_placed<R: OrderRepository>(repo: &R, b: &Basket) {
+    for id in b.legs.iter().filter_map(|l| l.order_id.as_deref()) {
+        if let Err(e) = repo.cancel(id, CANCEL_BASKET_FAILED).await {
+            warn!(basket_id = %b.id, order_id = %id, err = %e, "failed to cancel basket leg");
+        }
+    }
+}
+
+/// Sends out the legs of every pending basket whose trigger holds, each as
+/// a market order on its own pair. Returns how many baskets fired. Nothing
+/// fires in cancel-only mode.
+pub async fn fire_triggered<R: OrderRepository>(
+    store: &BasketStore,
+    repo: &R,
+    markets: &BasketMarkets,
+) -> usize {
+    if markets.oracle.dead_man().is_cancel_only() {
+        return 0;
+    }
+    let mut fired = 0;
+    for mut b in store.list(Some(BasketStatus::Pending)).await {
+        let Some(ref_px) = markets.triggered(&b).await else {
+            continue;
+        };
+        let now = now_ms();
+        let mut blockers = Vec::with_capacity(b.legs.len());
+        for leg in &b.legs {
+            blockers.push(markets.blocker(&leg.pair, now).await);
+        }
+        if b.mode == BasketMode::Atomic {
+            if let Some(reason) = blockers.iter().flatten().next() {
+                debug!(basket_id = %b.id, reason, "basket triggered but a leg cannot trade; holding");
+                continue;
+            }
+        }
+        b.triggered_px = Some(ref_px);
+        for (i, blocker) in blockers.into_iter().enumerate() {
+            let outcome = match blocker {
+                Some(reason) => Err(reason.to_string()),
+                None => repo.create(leg_order(&b, i)).await.map(|o| o.id),
+            };
+            let leg = &mut b.legs[i];
+            match outcome {
+                Ok(id) => {
+                    leg.status = LegStatus::Placed;
+                    leg.order_id = Some(id);
+                }
+                Err(reason) => {
+                    leg.status = LegStatus::Rejected;
+                    leg.reason = Some(reason);
+                }
+            }
+        }
+        let all_placed = b.legs.iter().all(|l| l.status == LegStatus::Placed);
+        b.status = if b.mode == BasketMode::Atomic && !all_placed {
+            cancel_placed(repo, &b).await;
+            BasketStatus::Failed
+        } else {
+            BasketStatus::Triggered
+        };
+        let status = b.status;
+        if !store.settle(b.clone()).await {
+            // Cancelled while the legs went out.
+            cancel_placed(repo, &b).await;
+            continue;
+        }
+        info!(basket_id = %b.id, ?status, ref_px = %ref_px, legs = b.legs.len(), "basket fired");
+        fired += 1;
+    }
+    fired
+}
+
+/// Checks pending baskets every `every`.
+pub fn start_basket_watcher<R: OrderRepository + 'static>(
+    store: BasketStore,
+    repo: R,
+    markets: BasketMarkets,
+    every: Duration,
+) {
+    tokio::spawn(async move {
+        let mut t = interval(every);
+        t.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            t.tick().await;
+            fire_triggered(&store, &repo, &markets).await;
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entities::basket::{BasketTrigger, NewBasket, NewBasketLeg};
+    use crate::entities::order::{OrderSide, OrderStatus};
+    use crate::oracle_service::Tick;
+    use crate::repositories::in_memory::InMemoryOrderRepository;
+    use rust_decimal_macros::dec;
+
+    fn basket(mode: BasketMode) -> Basket {
+        let leg = |pair: &str, quantity| NewBasketLeg {
+            pair: pair.into(),
+            side: OrderSide::Buy,
+            quantity,
+        };
+        Basket::from_new(
+            NewBasket {
+                trigger: BasketTrigger {
+                    pair: "BTC/USDT".into(),
+                    trigger_price_type: TriggerPriceType::Last,
+                    below: Some(dec!(60000)),
+                    above: None,
+                },
+                mode,
+                legs: vec![leg("ETH/USDT", dec!(2)), leg("SOL/USDT", dec!(30))],
+            },
+            None,
+        )
+    }
+
+    async fn tick(oracle: &OracleCache, pair: &str, px: Decimal, seq: i64) {
+        oracle.set(Tick::new(pair, px, now_ms() + seq)).await;
+    }
+
+    #[tokio::test]
+    async fn legs_go_out_together_once_every_leg_can_trade() {
+        let store = BasketStore::default();
+        let repo = InMemoryOrderRepository::default();
+        let markets = BasketMarkets {
+            oracle: OracleCache::default(),
+            instruments: InstrumentRegistry::default(),
+            halts: HaltRegistry::default(),
+        };
+        let atomic = store.create(basket(BasketMode::Atomic)).await;
+        let best = store.create(basket(BasketMode::BestEffort)).await;
+
+        tick(&markets.oracle, "BTC/USDT", dec!(61000), 0).await;
+        tick(&markets.oracle, "ETH/USDT", dec!(3000), 0).await;
+        assert_eq!(fire_triggered(&store, &repo, &markets).await, 0);
+
+        // Triggered, but SOL has no price: only best effort goes, with SOL
+        // rejected.
+        tick(&markets.oracle, "BTC/USDT", dec!(59000), 1).await;
+        assert_eq!(fire_triggered(&store, &repo, &markets).await, 1);
+        let b = store.get(&best.id).await.unwrap();
+        assert_eq!(b.status, BasketStatus::Triggered);
+        assert_eq!(b.triggered_px, Some(dec!(59000)));
+        assert_eq!(b.legs[0].status, LegStatus::Placed);
+        assert_eq!(b.legs[1].status, LegStatus::Rejected);
+        assert_eq!(
+            store.get(&atomic.id).await.unwrap().status,
+            BasketStatus::Pending
+        );
+
+        tick(&markets.oracle, "SOL/USDT", dec!(150), 0).await;
+        assert_eq!(fire_triggered(&store, &repo, &markets).await, 1);
+        let b = store.get(&atomic.id).await.unwrap();
+        assert_eq!(b.status, BasketStatus::Triggered);
+        for leg in &b.legs {
+            let o = repo
+                .get_by_id(leg.order_id.as_ref().unwrap())
+                .await
+                .unwrap();
+            assert_eq!(
+                (o.pair.as_str(), o.quantity),
+                (leg.pair.as_str(), leg.quantity)
+            );
+            assert_eq!(o.order_type, OrderType::Market);
+            assert_eq!(o.status, OrderStatus::New);
+        }
+        assert_eq!(fire_triggered(&store, &repo, &markets).await, 0);
+    }
+}
@@ -0,0 +1,305 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use rand::Rng;
+use rust_decimal::{Decimal, RoundingStrategy};
+use rust_decimal_macros::dec;
+use tokio::time::{interval, MissedTickBehavior};
+use tracing::{debug, warn};
+
+use crate::entities::order::{
+    Condition, NewOrder, OrderSide, OrderType, TimeInForce, TriggerPriceType, CANCEL_BOT_REQUOTE,
+};
+use crate::instruments::{InstrumentRegistry, TickPolicy};
+use crate::oracle_service::OracleCache;
+use crate::repositories::OrderRepository;
+use crate::utils::now_ms;
+
+/// Account the bots' orders are placed under.
+pub const BOT_ACCOUNT: &str = "liquidity-bot";
+
+/// Tag on every bot order, so they are easy to filter out of listings.
+pub const BOT_TAG: &str = "bot";
+
+/// How the bots quote: `levels` bids and asks per pair, the nth
+/// `n * step_bps` from the oracle price, each for around `quantity`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BotConfig {
+    pub levels: usize,
+    pub step_bps: Decimal,
+    pub quantity: Decimal,
+    pub refresh: Duration,
+}
+
+impl Default for BotConfig {
+    fn default() -> Self {
+        Self {
+            levels: 3,
+            step_bps: dec!(10),
+            quantity: Decimal::ONE,
+            refresh: Duration::from_secs(5),
+        }
+    }
+}
+
+impl BotConfig {
+    /// Parses `levels:step_bps:quantity`, e.g. `3:10:0.5`, or `on` for
+    /// the defaults.
+    pub fn parse(spec: &str) -> Option<Self> {
+        let spec = spec.trim();
+        if matches!(spec, "on" | "true" | "1") {
+            return Some(Self::default());
+        }
+        let mut parts = spec.split(':').map(str::trim);
+        let cfg = Self {
+            levels: parts.next()?.parse().ok()?,
+            step_bps: parts.next()?.parse().ok()?,
+            quantity: parts.next()?.parse().ok()?,
+            ..Self::default()
+        };
+        let valid = parts.next().is_none()
+            && cfg.levels > 0
+            && cfg.step_bps > Decimal::ZERO
+            && cfg.quantity > Decimal::ZERO;
+        valid.then_some(cfg)
+    }
+
+    /// Bots from `LIQUIDITY_BOTS`; none if it is unset or `off`. The
+    /// refresh is left at the default for the caller to set.
+    pub fn from_env() -> Option<Self> {
+        let spec = std::env::var("LIQUIDITY_BOTS").ok()?;
+        if spec.trim() == "off" {
+            return None;
+        }
+        let cfg = Self::parse(&spec);
+        if cfg.is_none() {
+            warn!(spec, "ignoring malformed LIQUIDITY_BOTS");
+        }
+        cfg
+    }
+}
+
+/// What one requote of a pair did.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Requote {
+    pub placed: usize,
+    pub cancelled: usize,
+    pub kept: usize,
+}
+
+/// One quote slot: a pair, a side and how many steps out.
+type Slot = (String, bool, usize);
+
+/// Simulated counterparties for demos. They keep a ladder of resting
+/// limit orders around each pair's oracle price, so the book endpoints
+/// show depth and price moves fill some of them. A quote stays while it
+/// is within half a step of where it belongs; filled or drifted ones are
+/// replaced with a fresh, randomly sized order. Pairs without a fresh
+/// price are left alone.
+pub struct LiquidityBots {
+    cfg: BotConfig,
+    oracle: OracleCache,
+    instruments: InstrumentRegistry,
+    quotes: HashMap<Slot, (String, Decimal)>,
+}
+
+impl LiquidityBots {
+    pub fn new(cfg: BotConfig, oracle: OracleCache, instruments: InstrumentRegistry) -> Self {
+        Self {
+            cfg,
+            oracle,
+            instruments,
+            quotes: HashMap::new(),
+        }
+    }
+
+    pub async fn requote<R: OrderRepository + ?Sized>(
+        &mut self,
+        repo: &R,
+        pair: &str,
+        now: i64,
+    ) -> Result<Requote, String> {
+        let mut out = Requote::default();
+        let Some(inst) = self.instruments.get(pair) else {
+            return Ok(out);
+        };
+        if !self.oracle.has_fresh_price(pair, now).await {
+            return Ok(out);
+        }
+        let Some((px, _)) = self.oracle.get_price(pair).await else {
+            return Ok(out);
+        };
+        // Quotes never cross, so round away from the price whatever the
+        // pair's tick policy.
+        let inst = crate::instruments::Instrument {
+            tick_policy: TickPolicy::Passive,
+            ..inst
+        };
+        let tolerance = px * self.cfg.step_bps / dec!(20000);
+        for level in 1..=self.cfg.levels {
+            for bid in [true, false] {
+                let (side, sign, strategy) = if bid {
+                    (
+                        OrderSide::Buy,
+                        -Decimal::ONE,
+                        RoundingStrategy::ToNegativeInfinity,
+                    )
+                } else {
+                    (
+                        OrderSide::Sell,
+                        Decimal::ONE,
+                        RoundingStrategy::ToPositiveInfinity,
+                    )
+                };
+                let offset = Decimal::from(level) * self.cfg.step_bps / dec!(10000);
+                let target = (px * (Decimal::ONE + sign * offset))
+                    .round_dp_with_strategy(inst.price_scale, strategy);
+                let Ok(target) = inst.fit_price(target, &side) else {
+                    continue;
+                };
+                if target <= Decimal::ZERO {
+                    continue;
+                }
+                let slot = (pair.to_string(), bid, level);
+                if let Some((id, price)) = self.quotes.get(&slot) {
+                    let active = repo.get_by_id(id).await.is_ok_and(|o| o.status.is_active());
+                    if active && (*price - target).abs() <= tolerance {
+                        out.kept += 1;
+                        continue;
+                    }
+                    if active && repo.cancel(id, CANCEL_BOT_REQUOTE).await.is_ok() {
+                        out.cancelled += 1;
+                    }
+                    self.quotes.remove(&slot);
+                }
+                let scale: u32 = rand::thread_rng().gen_range(50..=150);
+                let quantity = (self.cfg.quantity * Decimal::from(scale) / dec!(100))
+                    .round_dp_with_strategy(inst.qty_scale, RoundingStrategy::ToZero);
+                if quantity <= Decimal::ZERO {
+                    continue;
+                }
+                let order = repo.create(quote(pair, side, target, quantity)).await?;
+                self.quotes.insert(slot, (order.id, target));
+                out.placed += 1;
+            }
+        }
+        Ok(out)
+    }
+}
+
+fn quote(pair: &str, side: OrderSide, price: Decimal, quantity: Decimal) -> NewOrder {
+    NewOrder {
+        pair: pair.to_string(),
+        side,
+        order_type: OrderType::Limit,
+        price,
+        quantity,
+        trigger_price_type: TriggerPriceType::Last,
+        price_source: None,
+        condition: Condition::Price,
+        sustained_for_ms: None,
+        time_in_force: TimeInForce::Gtc,
+        max_slippage_bps: None,
+        exec_pricing: None,
+        account_id: Some(BOT_ACCOUNT.to_string()),
+        quantity_quote: None,
+        cancel_after_ms: None,
+        valid_for_after_armed_ms: None,
+        expires_at: None,
+        tags: vec![BOT_TAG.to_string()],
+        metadata: Default::default(),
+        callback_url: None,
+        callback_template: None,
+        trigger_price: None,
+        trailing: None,
+        oco_id: None,
+    }
+}
+
+/// Requotes every registered pair each `cfg.refresh`.
+pub fn start_liquidity_bots<R: OrderRepository + 'static>(mut bots: LiquidityBots, repo: R) {
+    tokio::spawn(async move {
+        let mut t = interval(bots.cfg.refresh);
+        t.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            t.tick().await;
+            for pair in bots.instruments.symbols() {
+                match bots.requote(&repo, &pair, now_ms()).await {
+                    Ok(r) if r.placed > 0 => {
+                        debug!(pair = %pair, placed = r.placed, cancelled = r.cancelled, "liquidity bot requoted")
+                    }
+                    Ok(_) => {}
+                    Err(e) => warn!(pair = %pair, err = %e, "liquidity bot failed to quote"),
+                }
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entities::order::OrderStatus;
+    use crate::oracle_service::Tick;
+    use crate::repositories::in_memory::InMemoryOrderRepository;
+    use crate::repositories::ListOrdersQuery;
+
+    #[test]
+    fn parses_ladder_specs() {
+        assert_eq!(BotConfig::parse("on"), Some(BotConfig::default()));
+        let cfg = BotConfig::parse("2:25:0.5").unwrap();
+        assert_eq!(
+            (cfg.levels, cfg.step_bps, cfg.quantity),
+            (2, dec!(25), dec!(0.5))
+        );
+        for bad in ["0:10:1", "2:10", "2:x:1", "2:10:1:4", "2:10:-1"] {
+            assert_eq!(BotConfig::parse(bad), None, "{bad}");
+        }
+    }
+
+    #[tokio::test]
+    async fn quotes_around_the_price_and_follow_it() {
+        let oracle = OracleCache::default();
+        let mut bots = LiquidityBots::new(
+            BotConfig::parse("2:10:1").unwrap(),
+            oracle.clone(),
+            InstrumentRegistry::default(),
+        );
+        let repo = InMemoryOrderRepository::default();
+        // Nothing to quote around yet.
+        let r = bots.requote(&repo, "BTC/USDT", now_ms()).await.unwrap();
+        assert_eq!(r, Requote::default());
+
+        let t0 = now_ms();
+        oracle.set(Tick::new("BTC/USDT", dec!(10000), t0)).await;
+        let r = bots.requote(&repo, "BTC/USDT", now_ms()).await.unwrap();
+        assert_eq!((r.placed, r.cancelled), (4, 0));
+        let orders = repo.list(ListOrdersQuery::default()).await.unwrap();
+        let mut prices: Vec<_> = orders.iter().map(|o| o.price).collect();
+        prices.sort();
+        assert_eq!(prices, [dec!(9980), dec!(9990), dec!(10010), dec!(10020)]);
+        assert!(orders
+            .iter()
+            .all(|o| o.account_id.as_deref() == Some(BOT_ACCOUNT) && o.tags == [BOT_TAG]));
+
+        // A small move keeps the ladder.
+        oracle.set(Tick::new("BTC/USDT", dec!(10001), t0 + 1)).await;
+        let r = bots.requote(&repo, "BTC/USDT", now_ms()).await.unwrap();
+        assert_eq!((r.placed, r.kept), (0, 4));
+
+        // A filled quote is replaced; a big move moves everything.
+        let filled = orders.iter().find(|o| o.price == dec!(9990)).unwrap();
+        repo.set_status(&filled.id, OrderStatus::Filled)
+            .await
+            .unwrap();
+        oracle.set(Tick::new("BTC/USDT", dec!(10100), t0 + 2)).await;
+        let r = bots.requote(&repo, "BTC/USDT", now_ms()).await.unwrap();
+        assert_eq!((r.placed, r.cancelled, r.kept), (4, 3, 0));
+        let active = repo.list(ListOrdersQuery::default()).await.unwrap();
+        let cancelled = active
+            .iter()
+            .filter(|o| o.cancel_reason.as_deref() == Some(CANCEL_BOT_REQUOTE))
+            .count();
+        assert_eq!(cancelled, 3);
+    }
+}
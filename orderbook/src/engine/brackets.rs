@@ -0,0 +1,204 @@
+use std::collections::HashMap;
+
+use rust_decimal::Decimal;
+use tracing::{error, info, warn};
+
+use crate::entities::order::{Order, OrderStatus, CANCEL_BRACKET_ENTRY};
+use crate::repositories::{ListOrdersQuery, OrderRepository};
+
+/// Replacements followed from a cancelled entry to the order standing in
+/// for it.
+const MAX_REPLACEMENTS: usize = 16;
+
+/// What becomes of a pending child given its entry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Release {
+    /// The entry is still working.
+    Wait,
+    /// The entry is done with this much filled; the child goes live for it.
+    Live(Decimal),
+    /// The entry ended without a fill, or is gone.
+    Withdraw,
+}
+
+/// `entry` is the child's entry, or what replaced it, as last read.
+pub fn release(entry: Option<&Order>) -> Release {
+    let Some(e) = entry else {
+        return Release::Withdraw;
+    };
+    match e.status {
+        OrderStatus::Filled => Release::Live(e.filled_quantity),
+        OrderStatus::Cancelled | OrderStatus::Expired if e.filled_quantity > Decimal::ZERO => {
+            Release::Live(e.filled_quantity)
+        }
+        OrderStatus::Cancelled | OrderStatus::Expired => Release::Withdraw,
+        _ => Release::Wait,
+    }
+}
+
+/// The order standing in for entry `id`: itself, or the last of its
+/// cancel/replace chain. `None` when it is gone.
+async fn entry<R: OrderRepository + ?Sized>(repo: &R, id: &str) -> Option<Order> {
+    let mut o = repo.get_by_id(id).await.ok()?;
+    for _ in 0..MAX_REPLACEMENTS {
+        match (&o.status, &o.replaced_by) {
+            (OrderStatus::Cancelled, Some(next)) if o.filled_quantity.is_zero() => {
+                o = repo.get_by_id(next).await.ok()?;
+            }
+            _ => break,
+        }
+    }
+    Some(o)
+}
+
+/// Settles `asset`'s pending bracket children against their entries: once
+/// an entry is filled, or cancelled or expired after filling some, its
+/// children are released as new orders sized to what it filled; once it
+/// ends without a fill, or is deleted, they are cancelled. Returns how many were
+/// released and withdrawn.
+pub async fn run<R: OrderRepository + ?Sized>(asset: &str, repo: &R) -> (usize, usize) {
+    let pending = match repo
+        .list(ListOrdersQuery {
+            pair: Some(asset.to_string()),
+            status: Some(OrderStatus::Pending),
+            limit: None,
+            offset: None,
+        })
+        .await
+    {
+        Ok(v) => v,
+        Err(e) => {
+            error!(%asset, err = %e, "failed to list pending bracket orders");
+            return (0, 0);
+        }
+    };
+    let (mut released, mut withdrawn) = (0, 0);
+    let mut entries: HashMap<String, Option<Order>> = HashMap::new();
+    for child in pending {
+        let Some(parent) = child.parent_id.clone() else {
+            continue;
+        };
+        if !entries.contains_key(&parent) {
+            let e = entry(repo, &parent).await;
+            entries.insert(parent.clone(), e);
+        }
+        let e = entries[&parent].as_ref();
+        match release(e) {
+            Release::Wait => {}
+            Release::Live(qty) => {
+                if qty != child.quantity {
+                    if let Err(e) = repo.amend(&child.id, child.price, qty).await {
+                        warn!(%asset, order_id = %child.id, err = %e, "failed to size bracket child");
+                        continue;
+                    }
+                }
+                match repo.set_status(&child.id, OrderStatus::New).await {
+                    Ok(_) => {
+                        released += 1;
+                        info!(%asset, order_id = %child.id, entry = %parent, qty = %qty, "bracket child released");
+                    }
+                    Err(e) => {
+                        warn!(%asset, order_id = %child.id, err = %e, "failed to release bracket child")
+                    }
+                }
+            }
+            Release::Withdraw => match repo.cancel(&child.id, CANCEL_BRACKET_ENTRY).await {
+                Ok(_) => {
+                    withdrawn += 1;
+                    info!(%asset, order_id = %child.id, entry = %parent, "bracket child withdrawn");
+                }
+                Err(e) => {
+                    warn!(%asset, order_id = %child.id, err = %e, "failed to withdraw bracket child")
+                }
+            },
+        }
+    }
+    (released, withdrawn)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entities::order::{Fill, NewOrder};
+    use crate::entities::test_support::OrderBuilder;
+    use crate::repositories::in_memory::InMemoryOrderRepository;
+    use rust_decimal_macros::dec;
+
+    fn fill(qty: Decimal) -> Fill {
+        Fill {
+            price: dec!(100),
+            quantity: qty,
+            ts_ms: 1,
+            received_ms: None,
+            decided_ms: None,
+            recovered_trigger: false,
+            interpolated_trigger: false,
+            legs: Vec::new(),
+            trigger_price: None,
+            fair_sources: Vec::new(),
+            counterparty: None,
+        }
+    }
+
+    async fn bracket(repo: &InMemoryOrderRepository) -> (Order, Vec<Order>) {
+        let children: Vec<NewOrder> = [
+            OrderBuilder::sell("BTC/USDT").limit(110).qty(2),
+            OrderBuilder::sell("BTC/USDT").qty(2).stop(95),
+        ]
+        .into_iter()
+        .map(|b| NewOrder {
+            oco_id: Some("x".into()),
+            ..b.new_order()
+        })
+        .collect();
+        let entry = OrderBuilder::buy("BTC/USDT").limit(100).qty(2).new_order();
+        repo.create_bracket(entry, children).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn children_go_live_for_what_the_entry_filled_or_are_withdrawn() {
+        let repo = InMemoryOrderRepository::default();
+        let (entry, children) = bracket(&repo).await;
+        assert!(children.iter().all(|c| c.status == OrderStatus::Pending
+            && c.parent_id.as_deref() == Some(entry.id.as_str())));
+        assert_eq!(run("BTC/USDT", &repo).await, (0, 0));
+
+        repo.record_fills(&entry.id, &[fill(dec!(1))])
+            .await
+            .unwrap();
+        assert_eq!(run("BTC/USDT", &repo).await, (0, 0));
+        repo.cancel(&entry.id, "owner_request").await.unwrap();
+        assert_eq!(run("BTC/USDT", &repo).await, (2, 0));
+        for c in &children {
+            let c = repo.get_by_id(&c.id).await.unwrap();
+            assert_eq!((c.status, c.quantity), (OrderStatus::New, dec!(1)));
+        }
+
+        let (entry, children) = bracket(&repo).await;
+        repo.cancel(&entry.id, "owner_request").await.unwrap();
+        assert_eq!(run("BTC/USDT", &repo).await, (0, 2));
+        let c = repo.get_by_id(&children[0].id).await.unwrap();
+        assert_eq!(c.cancel_reason.as_deref(), Some(CANCEL_BRACKET_ENTRY));
+    }
+
+    #[tokio::test]
+    async fn a_replaced_entry_hands_its_children_to_the_replacement() {
+        let repo = InMemoryOrderRepository::default();
+        let (entry, children) = bracket(&repo).await;
+        let (_, next) = repo
+            .replace(
+                &entry.id,
+                NewOrder {
+                    price: dec!(99),
+                    ..entry.to_new()
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(run("BTC/USDT", &repo).await, (0, 0));
+        repo.record_fills(&next.id, &[fill(dec!(2))]).await.unwrap();
+        assert_eq!(run("BTC/USDT", &repo).await, (2, 0));
+        let c = repo.get_by_id(&children[1].id).await.unwrap();
+        assert_eq!((c.status, c.quantity), (OrderStatus::New, dec!(2)));
+    }
+}
@@ -0,0 +1,413 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU16, AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use hmac::{Hmac, Mac};
+use rust_decimal::Decimal;
+use serde::Serialize;
+use serde_json::Value;
+use sha2::Sha256;
+use tokio::sync::Notify;
+use tracing::warn;
+
+use crate::accounts::AccountStore;
+use crate::engine::retry::RetryPolicy;
+use crate::entities::callback::{render, CallbackEvent};
+use crate::entities::events::OrderEvent;
+use crate::entities::order::{Fill, Order, OrderStatus};
+use crate::repositories::changes::{ChangeListener, ChangeStream, OrderChange};
+use crate::utils::{http, now_ms};
+
+/// Deliveries kept for `GET /orders/{id}/callbacks`, newest last, across
+/// all orders.
+const MAX_LOG: usize = 10_000;
+
+/// How long one POST may take, from connecting to the end of the reply.
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Retries for a callback whose endpoint errored or answered non-2xx.
+pub const CALLBACK_RETRY: RetryPolicy = RetryPolicy {
+    attempts: 4,
+    base_delay: Duration::from_millis(250),
+    max_delay: Duration::from_secs(5),
+};
+
+/// One callback POST, as it went.
+#[derive(Debug, Clone, Serialize)]
+pub struct CallbackDelivery {
+    pub order_id: String,
+    pub event: CallbackEvent,
+    pub url: String,
+    pub delivered: bool,
+    pub attempts: u32,
+    /// The last response status, when one came back.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub ts_ms: i64,
+}
+
+/// What a callback order looked like at its last change.
+#[derive(Clone)]
+struct Seen {
+    status: OrderStatus,
+    fills: usize,
+    price: Decimal,
+    quantity: Decimal,
+}
+
+impl Seen {
+    fn of(o: &Order) -> Self {
+        Self {
+            status: o.status.clone(),
+            fills: o.fills.len(),
+            price: o.price,
+            quantity: o.quantity,
+        }
+    }
+}
+
+struct Pending {
+    order_id: String,
+    account_id: Option<String>,
+    url: String,
+    event: CallbackEvent,
+    body: Result<Value, String>,
+}
+
+#[derive(Default)]
+struct Inner {
+    seen: HashMap<String, Seen>,
+    queue: VecDeque<Pending>,
+    log: VecDeque<CallbackDelivery>,
+}
+
+/// The lifecycle events of orders placed with a `callback_url`, read off
+/// the change stream and queued in order for [`start_callbacks`] to post.
+/// Only the order's own events go to its URL: creation, amendments, each
+/// fill, cancellation, suspension and deletion.
+#[derive(Clone, Default)]
+pub struct OrderCallbacks {
+    inner: Arc<Mutex<Inner>>,
+    wake: Arc<Notify>,
+}
+
+impl OrderCallbacks {
+    pub fn follow(&self, stream: &ChangeStream) {
+        stream.listen(Arc::new(self.clone()));
+    }
+
+    /// Deliveries for `order_id`, oldest first.
+    pub fn deliveries(&self, order_id: &str) -> Vec<CallbackDelivery> {
+        let r = self.inner.lock().unwrap();
+        r.log
+            .iter()
+            .filter(|d| d.order_id == order_id)
+            .cloned()
+            .collect()
+    }
+
+    fn queue(&self, order: &Order, events: Vec<(CallbackEvent, Option<&Fill>)>, ts_ms: i64) {
+        let Some(url) = &order.callback_url else {
+            return;
+        };
+        let pending = events.into_iter().map(|(event, fill)| Pending {
+            order_id: order.id.clone(),
+            account_id: order.account_id.clone(),
+            url: url.clone(),
+            event,
+            body: render(order.callback_template.as_ref(), event, order, fill, ts_ms),
+        });
+        self.inner.lock().unwrap().queue.extend(pending);
+    }
+
+    fn record(&self, d: CallbackDelivery) {
+        let mut w = self.inner.lock().unwrap();
+        if w.log.len() == MAX_LOG {
+            w.log.pop_front();
+        }
+        w.log.push_back(d);
+    }
+}
+
+impl ChangeListener for OrderCallbacks {
+    fn on_change(&self, c: &OrderChange) {
+        let order = c.order();
+        if order.callback_url.is_none() {
+            return;
+        }
+        let mut w = self.inner.lock().unwrap();
+        let before = w.seen.get(&order.id).cloned();
+        let mut events = Vec::new();
+        match &c.event {
+            OrderEvent::Created { .. } => events.push((CallbackEvent::Created, None)),
+            OrderEvent::Updated { .. } => {}
+            OrderEvent::Deleted { .. } => events.push((CallbackEvent::Deleted, None)),
+        }
+        if !matches!(c.event, OrderEvent::Deleted { .. }) {
+            let prev = before.unwrap_or(Seen {
+                fills: 0,
+                ..Seen::of(order)
+            });
+            if (prev.price, prev.quantity) != (order.price, order.quantity)
+                && order.fills.len() == prev.fills
+            {
+                events.push((CallbackEvent::Amended, None));
+            }
+            for fill in order.fills.iter().skip(prev.fills) {
+                events.push((CallbackEvent::Fill, Some(fill)));
+            }
+            if prev.status != order.status {
+                match order.status {
+                    OrderStatus::Cancelled => events.push((CallbackEvent::Cancelled, None)),
+                    OrderStatus::Suspended => events.push((CallbackEvent::Suspended, None)),
+                    OrderStatus::Expired => events.push((CallbackEvent::Expired, None)),
+                    _ => {}
+                }
+            }
+        }
+        let done = matches!(c.event, OrderEvent::Deleted { .. })
+            || matches!(
+                order.status,
+                OrderStatus::Filled | OrderStatus::Cancelled | OrderStatus::Expired
+            );
+        if done {
+            w.seen.remove(&order.id);
+        } else {
+            w.seen.insert(order.id.clone(), Seen::of(order));
+        }
+        drop(w);
+        if !events.is_empty() {
+            self.queue(order, events, c.ts_ms);
+            self.wake.notify_one();
+        }
+    }
+}
+
+/// `sha256=<hex>` HMAC of `"<timestamp>.<body>"` under `secret`.
+pub fn signature(secret: &str, ts_ms: i64, body: &str) -> String {
+    let mut mac =
+        <Hmac<Sha256> as Mac>::new_from_slice(secret.as_bytes()).expect("hmac accepts any key");
+    mac.update(ts_ms.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body.as_bytes());
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+/// Posts `body` and returns the response status with up to 200 bytes of
+/// the response body, for the log.
+async fn post(url: &str, headers: &[(&str, String)], body: &str) -> Result<(u16, String), String> {
+    let u = url::Url::parse(url).map_err(|e| e.to_string())?;
+    let host = format!(
+        "{}:{}",
+        u.host_str().unwrap_or_default(),
+        u.port_or_known_default().unwrap_or(80)
+    );
+    let path = match u.query() {
+        Some(q) => format!("{}?{q}", u.path()),
+        None => u.path().to_string(),
+    };
+    let exchange = async {
+        let (head, reply) =
+            http::send(&host, "POST", &path, headers, Some(body.as_bytes())).await?;
+        let mut text = String::from_utf8_lossy(&reply.read_all().await?).into_owned();
+        text.truncate(text.floor_char_boundary(200));
+        Ok::<_, String>((head.status, text))
+    };
+    tokio::time::timeout(DELIVERY_TIMEOUT, exchange)
+        .await
+        .map_err(|_| "timed out".to_string())?
+}
+
+/// Posts everything queued, each delivery retried under `retry`, and logs
+/// how each went. Bodies are signed with the order's account's webhook
+/// secret; an order whose account has none is not delivered. Returns how
+/// many were delivered.
+pub async fn deliver_pending(
+    callbacks: &OrderCallbacks,
+    accounts: &AccountStore,
+    retry: &RetryPolicy,
+) -> usize {
+    let mut delivered = 0;
+    loop {
+        let Some(p) = callbacks.inner.lock().unwrap().queue.pop_front() else {
+            return delivered;
+        };
+        // `status` stays 0 until a response comes back.
+        let (attempts, status) = (AtomicU32::new(0), AtomicU16::new(0));
+        let secret = match p.account_id.as_deref().map(|a| accounts.webhook(a)) {
+            Some(Ok(Some(hook))) => Ok(hook.signing_secret),
+            Some(Err(e)) => Err(e),
+            _ => Err("the order's account has no webhook signing secret".to_string()),
+        };
+        let result = match (secret, &p.body) {
+            (Ok(secret), Ok(body)) => {
+                let body = body.to_string();
+                let ts = now_ms();
+                let headers = [
+                    ("X-Callback-Event", p.event.name().to_string()),
+                    ("X-Callback-Timestamp", ts.to_string()),
+                    ("X-Callback-Signature", signature(&secret, ts, &body)),
+                ];
+                retry
+                    .run(|| {
+                        attempts.fetch_add(1, Ordering::Relaxed);
+                        let (url, headers, body, status) = (&p.url, &headers, &body, &status);
+                        async move {
+                            let (code, reply) = post(url, headers, body).await?;
+                            status.store(code, Ordering::Relaxed);
+                            if (200..300).contains(&code) {
+                                Ok(())
+                            } else {
+                                Err(format!("endpoint answered {code}: {reply}"))
+                            }
+                        }
+                    })
+                    .await
+            }
+            (Err(e), _) => Err(e),
+            (_, Err(e)) => Err(e.clone()),
+        };
+        if let Err(e) = &result {
+            warn!(order_id = %p.order_id, event = p.event.name(), err = %e, "callback not delivered");
+        }
+        delivered += usize::from(result.is_ok());
+        callbacks.record(CallbackDelivery {
+            order_id: p.order_id,
+            event: p.event,
+            url: p.url,
+            delivered: result.is_ok(),
+            attempts: attempts.into_inner(),
+            status: Some(status.into_inner()).filter(|&s| s != 0),
+            error: result.err(),
+            ts_ms: now_ms(),
+        });
+    }
+}
+
+/// Posts callbacks as their events arrive, one at a time so each order's
+/// arrive in order.
+pub fn start_callbacks(callbacks: OrderCallbacks, accounts: AccountStore) {
+    tokio::spawn(async move {
+        loop {
+            callbacks.wake.notified().await;
+            deliver_pending(&callbacks, &accounts, &CALLBACK_RETRY).await;
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::accounts::secrets::SecretBox;
+    use crate::entities::test_support::OrderBuilder;
+    use crate::repositories::changes::Observed;
+    use crate::repositories::in_memory::InMemoryOrderRepository;
+    use crate::repositories::OrderRepository;
+    use rust_decimal_macros::dec;
+    use serde_json::json;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// Answers `statuses` in turn, one per connection, and hands back each
+    /// raw request.
+    async fn endpoint(statuses: Vec<u16>) -> (String, tokio::sync::mpsc::Receiver<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook?src=ob", listener.local_addr().unwrap());
+        let (tx, rx) = tokio::sync::mpsc::channel(16);
+        tokio::spawn(async move {
+            for code in statuses {
+                let (mut s, _) = listener.accept().await.unwrap();
+                let mut req = Vec::new();
+                let mut buf = [0; 4096];
+                // Head and body arrive in at most two writes.
+                while !String::from_utf8_lossy(&req).contains("}") {
+                    let n = s.read(&mut buf).await.unwrap();
+                    req.extend_from_slice(&buf[..n]);
+                }
+                let reply = format!("HTTP/1.1 {code} X\r\nContent-Length: 0\r\n\r\n");
+                s.write_all(reply.as_bytes()).await.unwrap();
+                tx.send(String::from_utf8(req).unwrap()).await.unwrap();
+            }
+        });
+        (url, rx)
+    }
+
+    #[tokio::test]
+    async fn an_orders_own_events_are_signed_and_posted_in_order() {
+        let accounts = AccountStore::new(SecretBox::ephemeral());
+        let (account, _) = accounts.create("desk");
+        let secret = accounts
+            .set_webhook(&account.id, "http://unused.test/")
+            .unwrap()
+            .signing_secret;
+        // The fill's first attempt is refused with a 503.
+        let (url, mut requests) = endpoint(vec![200, 503, 200]).await;
+
+        let changes = ChangeStream::default();
+        let callbacks = OrderCallbacks::default();
+        callbacks.follow(&changes);
+        let repo = Observed::new(InMemoryOrderRepository::default(), changes);
+        let mut new = OrderBuilder::buy("BTC/USDT").limit(100).qty(1).new_order();
+        new.account_id = Some(account.id.clone());
+        new.callback_url = Some(url.clone());
+        new.callback_template = Some(json!({"text": "{{event}} {{order_id}} @ {{fill_price}}"}));
+        let order = repo.create(new).await.unwrap();
+        // Not a callback order: nothing is posted for it.
+        repo.create(OrderBuilder::buy("BTC/USDT").limit(90).qty(1).new_order())
+            .await
+            .unwrap();
+        let fill = Fill {
+            price: dec!(99),
+            quantity: dec!(1),
+            ts_ms: 1,
+            received_ms: None,
+            decided_ms: None,
+            recovered_trigger: false,
+            interpolated_trigger: false,
+            legs: Vec::new(),
+            trigger_price: None,
+            fair_sources: Vec::new(),
+            counterparty: None,
+        };
+        repo.record_fills(&order.id, &[fill]).await.unwrap();
+
+        let retry = RetryPolicy {
+            base_delay: Duration::from_millis(1),
+            ..CALLBACK_RETRY
+        };
+        assert_eq!(deliver_pending(&callbacks, &accounts, &retry).await, 2);
+        let log = callbacks.deliveries(&order.id);
+        let events: Vec<_> = log.iter().map(|d| (d.event, d.attempts)).collect();
+        assert_eq!(
+            events,
+            vec![(CallbackEvent::Created, 1), (CallbackEvent::Fill, 2)]
+        );
+        assert!(log.iter().all(|d| d.delivered && d.status == Some(200)));
+
+        let created = requests.recv().await.unwrap();
+        assert!(created.starts_with("POST /hook?src=ob HTTP/1.1"));
+        let body = created.split("\r\n\r\n").nth(1).unwrap();
+        assert_eq!(
+            body,
+            json!({"text": format!("created {} @ ", order.id)}).to_string()
+        );
+        let header = |req: &str, name: &str| {
+            req.lines()
+                .find_map(|l| l.strip_prefix(&format!("{name}: ")))
+                .unwrap()
+                .to_string()
+        };
+        let ts: i64 = header(&created, "X-Callback-Timestamp").parse().unwrap();
+        assert_eq!(
+            header(&created, "X-Callback-Signature"),
+            signature(&secret, ts, body)
+        );
+        requests.recv().await.unwrap();
+        let filled = requests.recv().await.unwrap();
+        assert_eq!(header(&filled, "X-Callback-Event"), "fill");
+        assert!(filled.ends_with(&format!("fill {} @ 99\"}}", order.id)));
+    }
+}
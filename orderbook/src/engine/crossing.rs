@@ -0,0 +1,286 @@
+use rust_decimal::Decimal;
+use tracing::{error, info, warn};
+
+use super::Controls;
+use crate::entities::order::{Condition, Fill, Order, OrderSide, OrderType};
+use crate::flags::{Feature, FeatureFlags};
+use crate::repositories::OrderRepository;
+
+/// Whether `o` rests on the pair's book to be crossed: a GTC or GTD limit order
+/// outside any OCO pair, with a plain price condition on the aggregate
+/// feed, sized in base units, with quantity left and the `crossing` flag
+/// on for it.
+pub fn rests(o: &Order, flags: &FeatureFlags) -> bool {
+    o.status.is_active()
+        && o.order_type == OrderType::Limit
+        && o.condition == Condition::Price
+        && o.time_in_force.rests()
+        && o.price_source.is_none()
+        && o.oco_id.is_none()
+        && o.quantity_quote.is_none()
+        && o.quantity > o.filled_quantity
+        && flags.is_enabled(Feature::Crossing, &o.pair, o.account_id.as_deref())
+}
+
+/// One match between a resting buy and sell, at the price of the `maker`,
+/// whichever of the two was placed first.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cross {
+    pub buy: String,
+    pub sell: String,
+    pub maker: String,
+    pub price: Decimal,
+    pub quantity: Decimal,
+}
+
+fn placed(o: &Order) -> (i64, &str) {
+    (o.created, o.id.as_str())
+}
+
+/// Crosses `orders` by price-time priority: the highest bid meets the
+/// lowest ask for as long as the bid is at or above it, the earlier order
+/// at a price going first. Two orders of one account never cross; the
+/// later of them sits out the pass.
+pub fn plan(orders: &[&Order]) -> Vec<Cross> {
+    let side = |s: OrderSide| {
+        let mut v: Vec<(&Order, Decimal)> = orders
+            .iter()
+            .filter(|o| o.side == s)
+            .map(|o| (*o, o.quantity - o.filled_quantity))
+            .collect();
+        v.sort_by(|(a, _), (b, _)| {
+            match s {
+                OrderSide::Buy => b.price.cmp(&a.price),
+                OrderSide::Sell => a.price.cmp(&b.price),
+            }
+            .then_with(|| placed(a).cmp(&placed(b)))
+        });
+        v
+    };
+    let (mut bids, mut asks) = (side(OrderSide::Buy), side(OrderSide::Sell));
+    let (mut i, mut j) = (0, 0);
+    let mut crosses = Vec::new();
+    while i < bids.len() && j < asks.len() {
+        let ((bid, left_b), (ask, left_a)) = (bids[i], asks[j]);
+        if bid.price < ask.price {
+            break;
+        }
+        let bid_first = placed(bid) <= placed(ask);
+        if bid.account_id.is_some() && bid.account_id == ask.account_id {
+            if bid_first {
+                j += 1;
+            } else {
+                i += 1;
+            }
+            continue;
+        }
+        let maker = if bid_first { bid } else { ask };
+        let quantity = left_b.min(left_a);
+        crosses.push(Cross {
+            buy: bid.id.clone(),
+            sell: ask.id.clone(),
+            maker: maker.id.clone(),
+            price: maker.price,
+            quantity,
+        });
+        bids[i].1 -= quantity;
+        asks[j].1 -= quantity;
+        if bids[i].1.is_zero() {
+            i += 1;
+        }
+        if asks[j].1.is_zero() {
+            j += 1;
+        }
+    }
+    crosses
+}
+
+/// Each crossed order's fills, in the order it first crossed, stamped
+/// with `now_ms` as one trigger event.
+pub fn fills(crosses: &[Cross], now_ms: i64) -> Vec<(String, Vec<Fill>)> {
+    let mut out: Vec<(String, Vec<Fill>)> = Vec::new();
+    for c in crosses {
+        for (id, other) in [(&c.buy, &c.sell), (&c.sell, &c.buy)] {
+            let fill = Fill {
+                price: c.price,
+                quantity: c.quantity,
+                ts_ms: now_ms,
+                received_ms: None,
+                decided_ms: Some(now_ms),
+                recovered_trigger: false,
+                interpolated_trigger: false,
+                legs: Vec::new(),
+                trigger_price: None,
+                fair_sources: Vec::new(),
+                counterparty: Some(other.clone()),
+            };
+            match out.iter_mut().find(|(o, _)| o == id) {
+                Some((_, f)) => f.push(fill),
+                None => out.push((id.clone(), vec![fill])),
+            }
+        }
+    }
+    out
+}
+
+/// Crosses the resting orders among `active` and writes their fills in one
+/// [`OrderRepository::cross`], each order's claimed in the ledger first.
+/// `active` is left holding the orders as written, without those now
+/// filled, for the oracle pass. Returns how many crosses were written.
+pub async fn run<R: OrderRepository>(
+    asset: &str,
+    repo: &R,
+    ctl: &Controls,
+    active: &mut Vec<Order>,
+    now_ms: i64,
+) -> usize {
+    let resting: Vec<&Order> = active
+        .iter()
+        .filter(|o| rests(o, &ctl.flags) && !ctl.dead_letters.is_suspended(&o.id))
+        .collect();
+    let crosses = plan(&resting);
+    if crosses.is_empty() {
+        return 0;
+    }
+    let fills = fills(&crosses, now_ms);
+    let mut claimed = Vec::new();
+    for (id, f) in &fills {
+        let Some(o) = active.iter().find(|o| &o.id == id) else {
+            continue;
+        };
+        let seq = o.trigger_seq();
+        let qty = f.iter().map(|f| f.quantity).sum();
+        if let Err(prior) = ctl.ledger.claim(id, seq, asset, now_ms, qty, now_ms) {
+            warn!(%asset, order_id = %id, trigger_seq = seq, owner = %prior.owner, state = ?prior.state, "trigger already claimed; not crossing");
+            for (id, seq) in claimed {
+                ctl.ledger.abort(id, seq);
+            }
+            return 0;
+        }
+        claimed.push((id.as_str(), seq));
+    }
+    let crossed = match repo.cross(&fills).await {
+        Ok(crossed) => crossed,
+        Err(e) => {
+            error!(%asset, orders = fills.len(), err = %e, "failed to cross orders");
+            for (id, seq) in claimed {
+                ctl.ledger.abort(id, seq);
+            }
+            return 0;
+        }
+    };
+    for (id, seq) in claimed {
+        ctl.ledger.commit(id, seq, crate::utils::now_ms());
+        ctl.dead_letters.record_success(id);
+    }
+    for c in &crosses {
+        info!(%asset, buy = %c.buy, sell = %c.sell, maker = %c.maker, qty = %c.quantity, exec_px = %c.price, "CROSS");
+    }
+    for o in crossed {
+        if let Some(slot) = active.iter_mut().find(|a| a.id == o.id) {
+            *slot = o;
+        }
+    }
+    active.retain(|o| o.status.is_active());
+    crosses.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entities::order::OrderStatus;
+    use crate::entities::test_support::OrderBuilder;
+    use crate::flags::Scope;
+    use crate::repositories::in_memory::InMemoryOrderRepository;
+    use rust_decimal_macros::dec;
+
+    fn order(b: OrderBuilder, id: &str, created: i64) -> Order {
+        Order {
+            created,
+            ..b.id(id).build()
+        }
+    }
+
+    #[test]
+    fn the_best_prices_cross_first_and_the_earlier_order_sets_the_price() {
+        let orders = [
+            order(OrderBuilder::buy("BTC/USDT").limit(101).qty(1), "b1", 3),
+            order(OrderBuilder::buy("BTC/USDT").limit(102).qty(1), "b2", 4),
+            order(OrderBuilder::buy("BTC/USDT").limit(101).qty(2), "b3", 1),
+            order(OrderBuilder::sell("BTC/USDT").limit(100).qty(3), "s1", 2),
+            order(OrderBuilder::sell("BTC/USDT").limit(103).qty(1), "s2", 0),
+        ];
+        let crosses = plan(&orders.iter().collect::<Vec<_>>());
+        let got: Vec<_> = crosses
+            .iter()
+            .map(|c| (c.buy.as_str(), c.maker.as_str(), c.price, c.quantity))
+            .collect();
+        // b2 has the best bid; b3 was at 101 before b1, which gets no
+        // quantity once s1 is gone, and 101 does not reach s2.
+        assert_eq!(
+            got,
+            [
+                ("b2", "s1", dec!(100), dec!(1)),
+                ("b3", "b3", dec!(101), dec!(2)),
+            ]
+        );
+
+        let mine = order(
+            OrderBuilder::sell("BTC/USDT").limit(90).qty(1).account("a"),
+            "s",
+            1,
+        );
+        let own = order(
+            OrderBuilder::buy("BTC/USDT").limit(95).qty(1).account("a"),
+            "b",
+            2,
+        );
+        let other = order(
+            OrderBuilder::buy("BTC/USDT").limit(94).qty(1).account("z"),
+            "o",
+            3,
+        );
+        let crosses = plan(&[&mine, &own, &other]);
+        assert_eq!(
+            crosses
+                .iter()
+                .map(|c| (c.buy.as_str(), c.price))
+                .collect::<Vec<_>>(),
+            [("o", dec!(90))]
+        );
+    }
+
+    #[tokio::test]
+    async fn flagged_orders_cross_in_one_write_and_leave_the_rest_active() {
+        let repo = InMemoryOrderRepository::default();
+        let ctl = Controls::default();
+        let mut active = Vec::new();
+        for b in [
+            OrderBuilder::buy("BTC/USDT").limit(101).qty(2),
+            OrderBuilder::sell("BTC/USDT").limit(100).qty(1),
+            OrderBuilder::sell("BTC/USDT").limit(100).qty(1).market(),
+        ] {
+            active.push(repo.create(b.new_order()).await.unwrap());
+            // The buy rests first, so it is the maker.
+            tokio::time::sleep(std::time::Duration::from_millis(2)).await;
+        }
+        assert_eq!(run("BTC/USDT", &repo, &ctl, &mut active, 1_000).await, 0);
+
+        ctl.flags
+            .set(Feature::Crossing, Scope::Pair("BTC/USDT".into()), true);
+        let (buy, sell) = (active[0].id.clone(), active[1].id.clone());
+        assert_eq!(run("BTC/USDT", &repo, &ctl, &mut active, 1_000).await, 1);
+        assert_eq!(active.len(), 2);
+        let b = repo.get_by_id(&buy).await.unwrap();
+        assert_eq!(b.status, OrderStatus::PartiallyFilled);
+        assert_eq!(b.filled_quantity, dec!(1));
+        assert_eq!(b.fills[0].counterparty.as_deref(), Some(sell.as_str()));
+        assert_eq!(active[0].filled_quantity, dec!(1));
+        let s = repo.get_by_id(&sell).await.unwrap();
+        assert_eq!(
+            (s.status, s.exec_price),
+            (OrderStatus::Filled, Some(dec!(101)))
+        );
+        assert!(ctl.ledger.unresolved().is_empty());
+    }
+}
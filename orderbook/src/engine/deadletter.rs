@@ -0,0 +1,154 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use serde::Serialize;
+
+const DEFAULT_MAX_FAILURES: u32 = 5;
+
+/// An order the engine could not write, with its consecutive failures.
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct DeadLetter {
+    pub order_id: String,
+    pub pair: String,
+    pub operation: &'static str,
+    pub failures: u32,
+    pub last_error: String,
+    pub first_failed_ms: i64,
+    pub last_failed_ms: i64,
+    /// Set once `failures` reaches the limit; the engine stops evaluating
+    /// the order from then on.
+    pub suspended: bool,
+}
+
+struct Inner {
+    max_failures: u32,
+    entries: HashMap<String, DeadLetter>,
+}
+
+/// Consecutive engine write failures per order, shared by the matcher
+/// workers and the admin API.
+#[derive(Clone)]
+pub struct DeadLetters {
+    inner: Arc<Mutex<Inner>>,
+}
+
+impl Default for DeadLetters {
+    fn default() -> Self {
+        Self::with_max_failures(DEFAULT_MAX_FAILURES)
+    }
+}
+
+impl DeadLetters {
+    pub fn with_max_failures(max_failures: u32) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(Inner {
+                max_failures: max_failures.max(1),
+                entries: HashMap::new(),
+            })),
+        }
+    }
+
+    /// Counts a failed write. Returns `true` exactly once, when the order
+    /// reaches the limit and should be suspended.
+    pub fn record_failure(
+        &self,
+        order_id: &str,
+        pair: &str,
+        operation: &'static str,
+        err: &str,
+        now_ms: i64,
+    ) -> bool {
+        let mut g = self.inner.lock().unwrap();
+        let max = g.max_failures;
+        let e = g
+            .entries
+            .entry(order_id.to_string())
+            .or_insert_with(|| DeadLetter {
+                order_id: order_id.to_string(),
+                pair: pair.to_string(),
+                operation,
+                failures: 0,
+                last_error: String::new(),
+                first_failed_ms: now_ms,
+                last_failed_ms: now_ms,
+                suspended: false,
+            });
+        e.failures += 1;
+        e.operation = operation;
+        e.last_error = err.to_string();
+        e.last_failed_ms = now_ms;
+        if e.suspended || e.failures < max {
+            return false;
+        }
+        e.suspended = true;
+        true
+    }
+
+    /// Forgets failures of an order whose write went through, unless it is
+    /// already suspended.
+    pub fn record_success(&self, order_id: &str) {
+        let mut g = self.inner.lock().unwrap();
+        if g.entries.get(order_id).is_some_and(|e| !e.suspended) {
+            g.entries.remove(order_id);
+        }
+    }
+
+    /// Forgets an order an operator took out of suspension, so the engine
+    /// evaluates it again. Returns whether it was tracked.
+    pub fn release(&self, order_id: &str) -> bool {
+        self.inner
+            .lock()
+            .unwrap()
+            .entries
+            .remove(order_id)
+            .is_some()
+    }
+
+    pub fn is_suspended(&self, order_id: &str) -> bool {
+        let g = self.inner.lock().unwrap();
+        g.entries.get(order_id).is_some_and(|e| e.suspended)
+    }
+
+    /// Suspended orders, most recently failed first.
+    pub fn list(&self) -> Vec<DeadLetter> {
+        let g = self.inner.lock().unwrap();
+        let mut v: Vec<DeadLetter> = g
+            .entries
+            .values()
+            .filter(|e| e.suspended)
+            .cloned()
+            .collect();
+        v.sort_by_key(|e| std::cmp::Reverse(e.last_failed_ms));
+        v
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn suspends_after_consecutive_failures_only() {
+        let d = DeadLetters::with_max_failures(3);
+        assert!(!d.record_failure("a", "BTC/USDT", "record_fill", "e1", 1));
+        assert!(!d.record_failure("a", "BTC/USDT", "record_fill", "e2", 2));
+        d.record_success("a");
+        assert!(!d.record_failure("a", "BTC/USDT", "record_fill", "e3", 3));
+        assert!(!d.record_failure("a", "BTC/USDT", "record_fill", "e4", 4));
+        assert!(d.record_failure("a", "BTC/USDT", "cancel", "e5", 5));
+        assert!(!d.record_failure("a", "BTC/USDT", "cancel", "e6", 6));
+        d.record_success("a");
+        assert!(d.is_suspended("a"));
+
+        let list = d.list();
+        assert_eq!(list.len(), 1);
+        assert_eq!(list[0].failures, 4);
+        assert_eq!(list[0].first_failed_ms, 3);
+        assert_eq!(list[0].last_error, "e6");
+        assert_eq!(list[0].operation, "cancel");
+
+        assert!(d.release("a"));
+        assert!(!d.is_suspended("a"));
+        assert!(d.list().is_empty());
+    }
+}
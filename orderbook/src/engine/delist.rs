@@ -0,0 +1,357 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use serde::Serialize;
+use tracing::{info, warn};
+
+use super::halts::{HaltRegistry, HaltSource};
+use super::Matchers;
+use crate::accounts::purge::JobStatus;
+use crate::entities::events::MarketEvent;
+use crate::entities::order::CANCEL_DELISTED;
+use crate::instruments::InstrumentRegistry;
+use crate::oracle_service::OracleCache;
+use crate::repositories::changes::ChangeStream;
+use crate::repositories::{ListOrdersQuery, OrderRepository};
+use crate::utils::now_ms;
+
+/// Finished delistings kept for their status.
+const JOB_HISTORY: usize = 100;
+
+/// How long a delisting waits for the pair's matcher to notice.
+pub const MATCHER_STOP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Where a delisting is, in the order the steps run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DelistStep {
+    Halt,
+    /// Take the pair out of the registry: ticks, orders and list filters
+    /// naming it are refused from here on, and the instrument is archived.
+    Unlist,
+    CancelOrders,
+    StopMatcher,
+    /// Drop its cached prices, index, history and market data topic.
+    ClearMarketData,
+    Done,
+}
+
+/// Progress of one pair's delisting. Counts grow as the job goes.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct DelistJob {
+    pub id: String,
+    pub pair: String,
+    pub status: JobStatus,
+    pub step: DelistStep,
+    /// Whether this job halted the pair, rather than finding it halted.
+    pub halted: bool,
+    /// Active orders found once the pair stopped taking new ones.
+    pub orders_found: usize,
+    pub orders_cancelled: usize,
+    pub matcher_stopped: bool,
+    /// Latest, venue and history ticks dropped from the oracle cache.
+    pub oracle_ticks: usize,
+    pub started_ms: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub finished_ms: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Delists pairs, one background job per request: halt, unlist, cancel
+/// what rests, wait for the matcher to stop, then clear the market data.
+/// The oracle connection is shared by every pair, so unlisting is what
+/// unsubscribes: the client drops ticks for symbols that do not resolve.
+/// A failed job can be started again on the archived pair and resumes
+/// where it stopped.
+#[derive(Clone)]
+pub struct PairDelister {
+    instruments: InstrumentRegistry,
+    orders: Arc<dyn OrderRepository>,
+    oracle: OracleCache,
+    halts: HaltRegistry,
+    matchers: Matchers,
+    events: ChangeStream,
+    stop_timeout: Duration,
+    jobs: Arc<Mutex<VecDeque<DelistJob>>>,
+}
+
+impl PairDelister {
+    pub fn new(
+        instruments: InstrumentRegistry,
+        orders: Arc<dyn OrderRepository>,
+        oracle: OracleCache,
+        halts: HaltRegistry,
+        matchers: Matchers,
+        events: ChangeStream,
+    ) -> Self {
+        Self {
+            instruments,
+            orders,
+            oracle,
+            halts,
+            matchers,
+            events,
+            stop_timeout: MATCHER_STOP_TIMEOUT,
+            jobs: Arc::default(),
+        }
+    }
+
+    pub fn with_stop_timeout(mut self, timeout: Duration) -> Self {
+        self.stop_timeout = timeout;
+        self
+    }
+
+    /// Starts delisting `pair` in the background and returns the job as
+    /// first recorded.
+    pub fn start(&self, pair: &str) -> Result<DelistJob, String> {
+        let job = self.register(pair)?;
+        let delister = self.clone();
+        let id = job.id.clone();
+        tokio::spawn(async move { delister.run(&id).await });
+        Ok(job)
+    }
+
+    /// Records a job on `pair`, listed or archived by an unfinished
+    /// delisting. Errs on an unknown pair, one already delisted, or while
+    /// another job on it is running.
+    fn register(&self, pair: &str) -> Result<DelistJob, String> {
+        let pair = match self.instruments.normalize(pair) {
+            Ok(p) => p,
+            Err(e) => {
+                let retry = self.instruments.archived(pair).is_some()
+                    && !self
+                        .jobs
+                        .lock()
+                        .unwrap()
+                        .iter()
+                        .any(|j| j.pair == pair && j.status == JobStatus::Completed);
+                if !retry {
+                    return Err(e.to_string());
+                }
+                pair.to_string()
+            }
+        };
+        let job = DelistJob {
+            id: crate::utils::ids::next_id(),
+            pair: pair.clone(),
+            status: JobStatus::Running,
+            step: DelistStep::Halt,
+            halted: false,
+            orders_found: 0,
+            orders_cancelled: 0,
+            matcher_stopped: false,
+            oracle_ticks: 0,
+            started_ms: now_ms(),
+            finished_ms: None,
+            error: None,
+        };
+        let mut jobs = self.jobs.lock().unwrap();
+        if let Some(running) = jobs
+            .iter()
+            .find(|j| j.pair == pair && j.status == JobStatus::Running)
+        {
+            return Err(format!("job {} is already delisting {pair}", running.id));
+        }
+        jobs.push_back(job.clone());
+        while jobs.len() > JOB_HISTORY {
+            jobs.pop_front();
+        }
+        Ok(job)
+    }
+
+    pub fn job(&self, id: &str) -> Option<DelistJob> {
+        let jobs = self.jobs.lock().unwrap();
+        jobs.iter().find(|j| j.id == id).cloned()
+    }
+
+    /// Runs job `id` to the end; the first error stops it.
+    pub async fn run(&self, id: &str) {
+        let Some(job) = self.job(id) else {
+            return;
+        };
+        let result = self.work(&job).await;
+        self.update(id, |j| {
+            j.finished_ms = Some(now_ms());
+            match result {
+                Ok(()) => {
+                    j.status = JobStatus::Completed;
+                    j.step = DelistStep::Done;
+                }
+                Err(e) => {
+                    j.status = JobStatus::Failed;
+                    j.error = Some(e);
+                }
+            }
+        });
+        match self.job(id) {
+            Some(j) if j.status == JobStatus::Completed => {
+                info!(job = %id, pair = %j.pair, cancelled = j.orders_cancelled, "pair delisted");
+                self.events.publish_market(MarketEvent::PairDelisted {
+                    pair: j.pair,
+                    cancelled: j.orders_cancelled,
+                });
+            }
+            Some(j) => {
+                warn!(job = %id, pair = %j.pair, step = ?j.step, err = ?j.error, "pair delisting failed")
+            }
+            None => {}
+        }
+    }
+
+    async fn work(&self, job: &DelistJob) -> Result<(), String> {
+        let pair = job.pair.as_str();
+        if self
+            .halts
+            .halt(pair, HaltSource::Admin, "delisting", now_ms())
+        {
+            self.events.publish_market(MarketEvent::PairHalted {
+                pair: pair.to_string(),
+                source: HaltSource::Admin,
+                reason: "delisting".into(),
+            });
+            self.update(&job.id, |j| j.halted = true);
+        }
+
+        self.update(&job.id, |j| j.step = DelistStep::Unlist);
+        if self.instruments.archived(pair).is_none() {
+            self.instruments.delist(pair)?;
+        }
+
+        self.update(&job.id, |j| j.step = DelistStep::CancelOrders);
+        let active: Vec<String> = self
+            .orders
+            .list(ListOrdersQuery {
+                pair: Some(pair.to_string()),
+                ..ListOrdersQuery::default()
+            })
+            .await?
+            .into_iter()
+            .filter(|o| o.status.is_active())
+            .map(|o| o.id)
+            .collect();
+        self.update(&job.id, |j| j.orders_found = active.len());
+        for order_id in active {
+            match self.orders.cancel(&order_id, CANCEL_DELISTED).await {
+                Ok(_) => self.update(&job.id, |j| j.orders_cancelled += 1),
+                // One that filled or closed meanwhile is fine.
+                Err(_) if !self.still_active(&order_id).await => {}
+                Err(e) => return Err(format!("cancelling {order_id}: {e}")),
+            }
+        }
+
+        self.update(&job.id, |j| j.step = DelistStep::StopMatcher);
+        let deadline = tokio::time::Instant::now() + self.stop_timeout;
+        while self.matchers.is_running(pair) && tokio::time::Instant::now() < deadline {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        let stopped = !self.matchers.is_running(pair);
+        if !stopped {
+            warn!(%pair, "matcher still running after delisting; it stops on its next tick");
+        }
+        self.update(&job.id, |j| j.matcher_stopped = stopped);
+
+        self.update(&job.id, |j| j.step = DelistStep::ClearMarketData);
+        let ticks = self.oracle.forget_pair(pair).await;
+        self.update(&job.id, |j| j.oracle_ticks = ticks);
+        Ok(())
+    }
+
+    async fn still_active(&self, order_id: &str) -> bool {
+        self.orders
+            .get_by_id(order_id)
+            .await
+            .is_ok_and(|o| o.status.is_active())
+    }
+
+    fn update(&self, id: &str, f: impl FnOnce(&mut DelistJob)) {
+        let mut jobs = self.jobs.lock().unwrap();
+        if let Some(j) = jobs.iter_mut().find(|j| j.id == id) {
+            f(j);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::{start_matchers, Controls, EngineConfig};
+    use crate::entities::order::OrderStatus;
+    use crate::entities::test_support::OrderBuilder;
+    use crate::oracle_service::Tick;
+    use crate::repositories::changes::Observed;
+    use crate::repositories::in_memory::InMemoryOrderRepository;
+    use rust_decimal::Decimal;
+
+    #[tokio::test]
+    async fn delisting_halts_cancels_stops_and_archives() {
+        let changes = ChangeStream::default();
+        let repo = Observed::new(InMemoryOrderRepository::default(), changes.clone());
+        let open = repo
+            .create(OrderBuilder::buy("ETH/USDT").limit(1).new_order())
+            .await
+            .unwrap();
+        let done = repo
+            .create(OrderBuilder::sell("ETH/USDT").limit(1).new_order())
+            .await
+            .unwrap();
+        repo.set_status(&done.id, OrderStatus::Filled)
+            .await
+            .unwrap();
+        let other = repo
+            .create(OrderBuilder::buy("BTC/USDT").limit(1).new_order())
+            .await
+            .unwrap();
+        let oracle = OracleCache::default();
+        oracle
+            .set(Tick::new("ETH/USDT", Decimal::from(2_000), now_ms()))
+            .await;
+        let instruments = InstrumentRegistry::default();
+        let ctl = Controls::default();
+        start_matchers(
+            vec!["ETH/USDT".into()],
+            repo.clone(),
+            oracle.clone(),
+            instruments.clone(),
+            ctl.clone(),
+            EngineConfig {
+                tick_every: Duration::from_millis(20),
+                ..EngineConfig::default()
+            },
+        );
+        while !ctl.matchers.is_running("ETH/USDT") {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        let delister = PairDelister::new(
+            instruments.clone(),
+            Arc::new(repo.clone()),
+            oracle.clone(),
+            ctl.halts.clone(),
+            ctl.matchers.clone(),
+            changes,
+        );
+
+        assert!(delister.register("DOGE/USDT").is_err());
+        let job = delister.register("eth-usdt").unwrap();
+        assert!(delister.register("ETH/USDT").is_err());
+        delister.run(&job.id).await;
+
+        let job = delister.job(&job.id).unwrap();
+        assert_eq!(
+            (job.status, job.step),
+            (JobStatus::Completed, DelistStep::Done)
+        );
+        assert!(job.halted && job.matcher_stopped);
+        assert_eq!((job.orders_found, job.orders_cancelled), (1, 1));
+        assert!(job.oracle_ticks > 0);
+        let cancelled = repo.get_by_id(&open.id).await.unwrap();
+        assert_eq!(cancelled.cancel_reason.as_deref(), Some(CANCEL_DELISTED));
+        assert!(repo.get_by_id(&other.id).await.unwrap().status.is_active());
+        assert!(instruments.normalize("ETH/USDT").is_err());
+        assert_eq!(instruments.list_archived()[0].symbol, "ETH/USDT");
+        assert!(oracle.get_tick("ETH/USDT").await.is_none());
+        // Done once; it cannot be delisted again.
+        assert!(delister.register("ETH/USDT").is_err());
+    }
+}
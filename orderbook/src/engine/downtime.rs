@@ -0,0 +1,318 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+use super::policy::{Decision, MatchPolicy};
+use super::MarketView;
+use crate::entities::order::Order;
+
+/// What happens to an order whose trigger holds on the first tick after
+/// the engine was down or not evaluating, when the price may have gapped
+/// through it unseen. `TriggerImmediately` fills it at the current price;
+/// `RequireFreshCrossing` holds it until the trigger has been seen not to
+/// hold and then crosses again; `ManualReview` holds it until an operator
+/// releases it under one of the other two.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MissedTriggerPolicy {
+    #[default]
+    TriggerImmediately,
+    RequireFreshCrossing,
+    ManualReview,
+}
+
+impl MissedTriggerPolicy {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "trigger_immediately" | "immediate" => Some(Self::TriggerImmediately),
+            "require_fresh_crossing" | "fresh" => Some(Self::RequireFreshCrossing),
+            "manual_review" | "review" => Some(Self::ManualReview),
+            _ => None,
+        }
+    }
+}
+
+/// When one worker last evaluated its pair. A worker that has not
+/// evaluated since it started, or not for longer than `gap_ms`, was blind
+/// and the orders it knew of may have missed their triggers.
+#[derive(Debug)]
+pub struct Downtime {
+    gap_ms: i64,
+    started_ms: i64,
+    last_ms: Option<i64>,
+}
+
+impl Downtime {
+    pub fn new(gap_ms: i64, started_ms: i64) -> Self {
+        Self {
+            gap_ms,
+            started_ms,
+            last_ms: None,
+        }
+    }
+
+    /// Records a tick the worker is up for and, if it ends a blind spell,
+    /// since when it was blind: the worker's start, or its last evaluation.
+    pub fn observe(&mut self, now_ms: i64) -> Option<i64> {
+        match self.last_ms.replace(now_ms) {
+            None => Some(self.started_ms),
+            Some(last) if now_ms - last > self.gap_ms => Some(last),
+            Some(_) => None,
+        }
+    }
+
+    /// A tick the worker deliberately skipped, such as a closed session;
+    /// not downtime.
+    pub fn touch(&mut self, now_ms: i64) {
+        self.last_ms = Some(now_ms);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Rule {
+    /// Judged on the next evaluation.
+    FirstTick(MissedTriggerPolicy),
+    FireNow,
+    AwaitReset,
+    Flagged,
+}
+
+#[derive(Debug, Clone)]
+struct Entry {
+    pair: String,
+    blind_since_ms: i64,
+    rule: Rule,
+}
+
+/// An order held for an operator after downtime.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct FlaggedTrigger {
+    pub order_id: String,
+    pub pair: String,
+    #[serde(serialize_with = "crate::utils::decimal::serialize")]
+    pub ref_px: Decimal,
+    pub blind_since_ms: i64,
+    pub flagged_ms: i64,
+}
+
+#[derive(Default)]
+struct Inner {
+    entries: HashMap<String, Entry>,
+    flagged: BTreeMap<String, FlaggedTrigger>,
+}
+
+/// Orders caught by a worker's downtime and the rule each is under, shared
+/// by the matcher workers and the admin API.
+#[derive(Clone, Default)]
+pub struct MissedTriggers {
+    inner: Arc<Mutex<Inner>>,
+}
+
+impl MissedTriggers {
+    /// Puts `ids` under `policy` for their next evaluation. Orders already
+    /// under a rule keep it.
+    pub fn mark<'a>(
+        &self,
+        pair: &str,
+        ids: impl IntoIterator<Item = &'a str>,
+        policy: MissedTriggerPolicy,
+        blind_since_ms: i64,
+    ) {
+        let mut g = self.inner.lock().unwrap();
+        for id in ids {
+            g.entries.entry(id.to_string()).or_insert_with(|| Entry {
+                pair: pair.to_string(),
+                blind_since_ms,
+                rule: Rule::FirstTick(policy),
+            });
+        }
+    }
+
+    /// Applies the order's rule, if any, on top of its normal evaluation.
+    /// The flag is set on a fill that honours a missed trigger.
+    pub fn apply(
+        &self,
+        o: &Order,
+        view: &MarketView,
+        policy: &dyn MatchPolicy,
+        decision: Decision,
+        now_ms: i64,
+    ) -> (Decision, bool) {
+        let mut g = self.inner.lock().unwrap();
+        let Some(entry) = g.entries.get(&o.id).cloned() else {
+            return (decision, false);
+        };
+        let triggered = matches!(decision, Decision::Fill { .. });
+        match entry.rule {
+            Rule::FirstTick(_) | Rule::AwaitReset if !triggered => {
+                g.entries.remove(&o.id);
+                (decision, false)
+            }
+            Rule::FirstTick(MissedTriggerPolicy::TriggerImmediately) => {
+                g.entries.remove(&o.id);
+                (decision, true)
+            }
+            Rule::FirstTick(MissedTriggerPolicy::RequireFreshCrossing) | Rule::AwaitReset => {
+                g.entries.get_mut(&o.id).unwrap().rule = Rule::AwaitReset;
+                (
+                    Decision::waiting(o, "awaiting fresh crossing after downtime"),
+                    false,
+                )
+            }
+            Rule::FirstTick(MissedTriggerPolicy::ManualReview) => {
+                let Decision::Fill { ref_px, .. } = decision else {
+                    unreachable!("triggered");
+                };
+                g.entries.get_mut(&o.id).unwrap().rule = Rule::Flagged;
+                g.flagged.insert(
+                    o.id.clone(),
+                    FlaggedTrigger {
+                        order_id: o.id.clone(),
+                        pair: entry.pair,
+                        ref_px,
+                        blind_since_ms: entry.blind_since_ms,
+                        flagged_ms: now_ms,
+                    },
+                );
+                (
+                    Decision::waiting(o, "missed trigger flagged for review"),
+                    false,
+                )
+            }
+            Rule::Flagged => (
+                Decision::waiting(o, "missed trigger flagged for review"),
+                false,
+            ),
+            Rule::FireNow => {
+                g.entries.remove(&o.id);
+                let decision = if decision.is_waiting() {
+                    let ref_px = view.reference(o.trigger_price_type).unwrap_or(view.last);
+                    policy.execute(o, view, ref_px)
+                } else {
+                    decision
+                };
+                let filled = matches!(decision, Decision::Fill { .. });
+                (decision, filled)
+            }
+        }
+    }
+
+    /// Drops entries for `pair`'s orders that are no longer active.
+    pub fn retain(&self, pair: &str, active: &HashSet<&str>) {
+        let mut g = self.inner.lock().unwrap();
+        g.entries
+            .retain(|id, e| e.pair != pair || active.contains(id.as_str()));
+        let Inner { entries, flagged } = &mut *g;
+        flagged.retain(|id, _| entries.contains_key(id));
+    }
+
+    pub fn flagged(&self) -> Vec<FlaggedTrigger> {
+        self.inner
+            .lock()
+            .unwrap()
+            .flagged
+            .values()
+            .cloned()
+            .collect()
+    }
+
+    /// Orders under a rule that are not flagged.
+    pub fn pending(&self) -> usize {
+        let g = self.inner.lock().unwrap();
+        g.entries.len() - g.flagged.len()
+    }
+
+    /// Releases a flagged order under `policy`: fill it on the next tick
+    /// whatever the price, or wait for a fresh crossing. `false` if the
+    /// order is not flagged.
+    pub fn release(&self, order_id: &str, policy: MissedTriggerPolicy) -> bool {
+        let mut g = self.inner.lock().unwrap();
+        if g.flagged.remove(order_id).is_none() {
+            return false;
+        }
+        let rule = match policy {
+            MissedTriggerPolicy::RequireFreshCrossing => Rule::AwaitReset,
+            _ => Rule::FireNow,
+        };
+        if let Some(e) = g.entries.get_mut(order_id) {
+            e.rule = rule;
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::policy::OraclePolicy;
+    use crate::entities::test_support::OrderBuilder;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn restarts_and_long_gaps_end_a_blind_spell() {
+        let mut d = Downtime::new(1_000, 50);
+        assert_eq!(d.observe(100), Some(50));
+        assert_eq!(d.observe(1_100), None);
+        d.touch(5_000);
+        assert_eq!(d.observe(5_900), None);
+        assert_eq!(d.observe(7_000), Some(5_900));
+    }
+
+    #[test]
+    fn each_policy_decides_what_the_first_tick_after_downtime_does() {
+        let reg = MissedTriggers::default();
+        let policy = OraclePolicy::default();
+        let o = |id: &str| OrderBuilder::buy("BTC/USDT").id(id).limit(100).build();
+        let (now, fresh, review, quiet) = (o("now"), o("fresh"), o("review"), o("quiet"));
+        reg.mark(
+            "BTC/USDT",
+            ["now"],
+            MissedTriggerPolicy::TriggerImmediately,
+            1,
+        );
+        reg.mark(
+            "BTC/USDT",
+            ["fresh", "quiet"],
+            MissedTriggerPolicy::RequireFreshCrossing,
+            1,
+        );
+        reg.mark("BTC/USDT", ["review"], MissedTriggerPolicy::ManualReview, 1);
+
+        let gapped = MarketView::from_last(dec!(90), now.created);
+        let calm = MarketView::from_last(dec!(110), now.created);
+        let eval =
+            |o: &Order, view: &MarketView| reg.apply(o, view, &policy, policy.evaluate(o, view), 9);
+        let (d, recovered) = eval(&now, &gapped);
+        assert!(matches!(d, Decision::Fill { .. }) && recovered);
+        // Nothing missed for an order not triggering after downtime.
+        assert_eq!(
+            eval(&quiet, &calm),
+            (Decision::Promote("not crossing"), false)
+        );
+        assert_eq!(
+            eval(&fresh, &gapped).0,
+            Decision::Promote("awaiting fresh crossing after downtime")
+        );
+        assert!(eval(&fresh, &gapped).0.is_waiting());
+        assert!(eval(&fresh, &calm).0.is_waiting());
+        assert!(matches!(
+            eval(&fresh, &gapped),
+            (Decision::Fill { .. }, false)
+        ));
+
+        assert!(eval(&review, &gapped).0.is_waiting());
+        assert!(eval(&review, &calm).0.is_waiting());
+        let flagged = reg.flagged();
+        assert_eq!(flagged.len(), 1);
+        assert_eq!((flagged[0].ref_px, flagged[0].flagged_ms), (dec!(90), 9));
+        assert!(!reg.release("now", MissedTriggerPolicy::TriggerImmediately));
+        assert!(reg.release("review", MissedTriggerPolicy::TriggerImmediately));
+        // Released to fire, it fills at the current price even if no longer
+        // crossing.
+        let (d, recovered) = eval(&review, &calm);
+        assert!(matches!(d, Decision::Fill { exec_px, .. } if exec_px == dec!(110)) && recovered);
+        assert_eq!(reg.pending(), 0);
+    }
+}
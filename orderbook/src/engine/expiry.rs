@@ -0,0 +1,139 @@
+use std::time::Duration;
+
+use tokio::time::{interval, MissedTickBehavior};
+use tracing::{error, info, warn};
+
+use crate::entities::order::OrderStatus;
+use crate::metrics::Metrics;
+use crate::repositories::{ListOrdersQuery, OrderRepository};
+use crate::utils::now_ms;
+
+/// Orders moved to `expired`, by pair.
+pub const EXPIRED_TOTAL: &str = "orders_expired_total";
+
+/// What one sweep did.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ExpiryReport {
+    pub expired: Vec<String>,
+    /// Orders due whose write the store refused; the next sweep tries
+    /// again.
+    pub failed: usize,
+}
+
+/// Moves every active or suspended order whose `expires_at` is at or
+/// before `now_ms` to `expired`. An order that fills or is cancelled
+/// between the listing and its write is left as it is.
+pub async fn sweep<R: OrderRepository + ?Sized>(
+    repo: &R,
+    metrics: &Metrics,
+    now_ms: i64,
+) -> Result<ExpiryReport, String> {
+    let mut report = ExpiryReport::default();
+    for status in OrderStatus::ACTIVE
+        .into_iter()
+        .chain([OrderStatus::Suspended])
+    {
+        let orders = repo
+            .list(ListOrdersQuery {
+                status: Some(status),
+                ..ListOrdersQuery::default()
+            })
+            .await?;
+        for o in orders {
+            if o.expires_at.is_none_or(|at| at > now_ms) {
+                continue;
+            }
+            match repo.expire(&o.id, now_ms).await {
+                Ok(_) => {
+                    info!(pair = %o.pair, order_id = %o.id, expires_at = ?o.expires_at, "order expired");
+                    metrics.incr(EXPIRED_TOTAL, &[("pair", o.pair.as_str())], 1.0);
+                    report.expired.push(o.id);
+                }
+                Err(e) => match repo.get_by_id(&o.id).await {
+                    Ok(now) if !now.status.can_transition_to(&OrderStatus::Expired) => {}
+                    _ => {
+                        warn!(pair = %o.pair, order_id = %o.id, err = %e, "failed to expire order");
+                        report.failed += 1;
+                    }
+                },
+            }
+        }
+    }
+    Ok(report)
+}
+
+/// Runs [`sweep`] every `every`.
+pub fn start_expiry_sweep<R: OrderRepository + 'static>(
+    repo: R,
+    metrics: Metrics,
+    every: Duration,
+) {
+    tokio::spawn(async move {
+        let mut t = interval(every);
+        t.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            t.tick().await;
+            if let Err(e) = sweep(&repo, &metrics, now_ms()).await {
+                error!(err = %e, "expiry sweep failed");
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entities::order::TimeInForce;
+    use crate::entities::test_support::OrderBuilder;
+    use crate::repositories::in_memory::InMemoryOrderRepository;
+
+    #[tokio::test]
+    async fn orders_past_expires_at_expire_and_the_rest_are_left_alone() {
+        let repo = InMemoryOrderRepository::default();
+        let metrics = Metrics::default();
+        let now = now_ms();
+        let mut ids = Vec::new();
+        for at in [Some(now - 1), Some(now), Some(now + 60_000), None] {
+            let mut new = OrderBuilder::buy("BTC/USDT").limit(100).new_order();
+            new.time_in_force = TimeInForce::Gtd;
+            new.expires_at = at;
+            ids.push(repo.create(new).await.unwrap().id);
+        }
+        repo.set_status(&ids[1], OrderStatus::Suspended)
+            .await
+            .unwrap();
+        let filled = {
+            let mut new = OrderBuilder::buy("BTC/USDT").limit(100).new_order();
+            new.expires_at = Some(now - 1);
+            repo.create(new).await.unwrap().id
+        };
+        repo.set_status(&filled, OrderStatus::Filled).await.unwrap();
+
+        let report = sweep(&repo, &metrics, now).await.unwrap();
+        let mut expired = report.expired.clone();
+        expired.sort();
+        let mut want = vec![ids[0].clone(), ids[1].clone()];
+        want.sort();
+        assert_eq!((expired, report.failed), (want, 0));
+        for (id, status) in [
+            (&ids[0], OrderStatus::Expired),
+            (&ids[1], OrderStatus::Expired),
+            (&ids[2], OrderStatus::New),
+            (&ids[3], OrderStatus::New),
+            (&filled, OrderStatus::Filled),
+        ] {
+            assert_eq!(repo.get_by_id(id).await.unwrap().status, status);
+        }
+        assert_eq!(
+            metrics.get(EXPIRED_TOTAL, &[("pair", "BTC/USDT")]),
+            Some(2.0)
+        );
+        // An expired order is finished.
+        assert!(repo.expire(&ids[0], now).await.is_err());
+        assert!(sweep(&repo, &metrics, now)
+            .await
+            .unwrap()
+            .expired
+            .is_empty());
+    }
+}
@@ -0,0 +1,145 @@
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+
+use super::policy::Decision;
+use super::MarketView;
+use crate::entities::order::SourcePrice;
+use crate::oracle_service::OracleCache;
+
+/// The range of prices the healthy configured venues quote for a pair on
+/// this tick, widened by `tolerance_bps` on each side. A trigger whose
+/// reference lies outside it was set off by a price no healthy venue
+/// agrees with, so it is held.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FairBand {
+    /// Each healthy venue's latest price, in `ORACLE_SOURCES` order.
+    pub sources: Vec<SourcePrice>,
+    pub tolerance_bps: Decimal,
+}
+
+impl FairBand {
+    /// The band's bounds, tolerance included; `None` without a healthy
+    /// venue.
+    pub fn bounds(&self) -> Option<(Decimal, Decimal)> {
+        let prices = self.sources.iter().map(|s| s.price);
+        let low = prices.clone().min()?;
+        let high = prices.max()?;
+        let slack = |px: Decimal| px * self.tolerance_bps / dec!(10000);
+        Some((low - slack(low), high + slack(high)))
+    }
+
+    pub fn contains(&self, px: Decimal) -> bool {
+        self.bounds()
+            .is_some_and(|(low, high)| low <= px && px <= high)
+    }
+}
+
+/// The pair's band from the configured venues whose latest tick is fresh,
+/// prices rounded to `price_scale`. `None` when no venues are configured,
+/// for then there is only the one feed to go by.
+pub async fn gather(
+    oracle: &OracleCache,
+    asset: &str,
+    price_scale: u32,
+    tolerance_bps: Decimal,
+    now_ms: i64,
+) -> Option<FairBand> {
+    if oracle.sources().is_empty() {
+        return None;
+    }
+    let mut sources = Vec::new();
+    for source in oracle.sources() {
+        let Some(tick) = oracle.get_venue_tick(asset, source).await else {
+            continue;
+        };
+        if oracle.is_fresh(&tick, now_ms) {
+            sources.push(SourcePrice {
+                source: source.clone(),
+                price: tick.price.round_dp(price_scale),
+                ts_ms: tick.ts_ms,
+            });
+        }
+    }
+    Some(FairBand {
+        sources,
+        tolerance_bps,
+    })
+}
+
+/// Holds a fill whose trigger reference lies outside the view's band. A
+/// view without a band passes every decision through.
+pub fn gate(view: &MarketView, decision: Decision) -> Decision {
+    let (Some(band), Decision::Fill { ref_px, .. }) = (&view.fair_band, &decision) else {
+        return decision;
+    };
+    if band.sources.is_empty() {
+        return Decision::Hold("no healthy source for the fair band");
+    }
+    if !band.contains(*ref_px) {
+        return Decision::Hold("reference outside the fair band");
+    }
+    decision
+}
+
+/// The venue prices a fill was let through on; empty without a band.
+pub fn quotes(view: &MarketView) -> Vec<SourcePrice> {
+    view.fair_band
+        .as_ref()
+        .map_or_else(Vec::new, |b| b.sources.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::oracle_service::Tick;
+    use crate::utils::now_ms;
+
+    #[tokio::test]
+    async fn fills_outside_the_band_of_healthy_venues_are_held() {
+        let oracle = OracleCache::default().with_sources(vec!["a".into(), "b".into(), "c".into()]);
+        let now = now_ms();
+        for (source, px, ts) in [
+            ("a", dec!(99), now),
+            ("b", dec!(101), now),
+            ("c", dec!(80), 1),
+        ] {
+            oracle
+                .set(Tick {
+                    source: Some(source.into()),
+                    ..Tick::new("BTC/USDT", px, ts)
+                })
+                .await;
+        }
+        // `c` is stale, so it does not widen the band.
+        let band = gather(&oracle, "BTC/USDT", 2, dec!(50), now).await.unwrap();
+        assert_eq!(
+            band.sources
+                .iter()
+                .map(|s| s.source.as_str())
+                .collect::<Vec<_>>(),
+            ["a", "b"]
+        );
+        assert_eq!(band.bounds(), Some((dec!(98.505), dec!(101.505))));
+        assert!(gather(&OracleCache::default(), "BTC/USDT", 2, dec!(0), now)
+            .await
+            .is_none());
+
+        let fill = |ref_px| Decision::Fill {
+            ref_px,
+            exec_px: ref_px,
+            qty: dec!(1),
+            levels: Vec::new(),
+        };
+        let mut view = MarketView::from_last(dec!(100), now);
+        assert_eq!(gate(&view, fill(dec!(80))), fill(dec!(80)));
+        view.fair_band = Some(band);
+        assert_eq!(gate(&view, fill(dec!(101.5))), fill(dec!(101.5)));
+        assert!(gate(&view, fill(dec!(97))).is_waiting());
+        assert_eq!(quotes(&view).len(), 2);
+        view.fair_band = Some(FairBand {
+            sources: Vec::new(),
+            tolerance_bps: dec!(0),
+        });
+        assert!(gate(&view, fill(dec!(100))).is_waiting());
+    }
+}
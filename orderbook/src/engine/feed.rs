@@ -0,0 +1,116 @@
+use std::collections::{HashMap, HashSet};
+
+use tokio::sync::broadcast::{error::TryRecvError, Receiver};
+use tracing::warn;
+
+use super::volume::VolumeBank;
+use crate::instruments::symbol::Symbol;
+use crate::oracle_service::hub::MarketData;
+use crate::oracle_service::{OracleCache, Tick};
+
+struct Topic {
+    rx: Receiver<MarketData>,
+    tick: Option<Tick>,
+}
+
+/// A matcher's subscriptions on the market data hub: its own pair, whose
+/// trade prints feed the volume windows, and every spread leg its orders
+/// read. Each topic keeps the newest tick seen on it. The hub does not
+/// replay, so a topic starts from the cache's latest tick when followed.
+#[derive(Default)]
+pub struct MarketFeed {
+    topics: HashMap<Symbol, Topic>,
+}
+
+impl MarketFeed {
+    /// Subscribes to `pair` unless it is already followed.
+    pub async fn follow(&mut self, oracle: &OracleCache, pair: &str) {
+        if self.topics.contains_key(pair) {
+            return;
+        }
+        // Subscribed before the snapshot, so no tick falls between them.
+        let rx = oracle.hub().subscribe(pair);
+        let tick = oracle.get_tick(pair).await;
+        self.topics.insert(Symbol::intern(pair), Topic { rx, tick });
+    }
+
+    /// Stops following every pair not in `keep`.
+    pub fn retain(&mut self, keep: &HashSet<Symbol>) {
+        self.topics.retain(|pair, _| keep.contains(pair));
+    }
+
+    pub fn forget(&mut self, pair: &str) {
+        self.topics.remove(pair);
+    }
+
+    /// Takes what was published since the last call: the newest tick on
+    /// each topic, and `asset`'s trade prints into `volumes`. A closed
+    /// topic is dropped, to be followed afresh.
+    pub fn drain(&mut self, asset: &str, volumes: &mut VolumeBank) {
+        self.topics.retain(|pair, topic| loop {
+            match topic.rx.try_recv() {
+                Ok(MarketData::Tick(t)) => topic.tick = Some(t),
+                Ok(MarketData::Trade(t)) if pair == asset => volumes.record(t.ts_ms, t.size),
+                Ok(MarketData::Trade(_)) => {}
+                Err(TryRecvError::Lagged(skipped)) if pair == asset => {
+                    warn!(%asset, skipped, "market topic overran; volume windows undercount");
+                }
+                Err(TryRecvError::Lagged(_)) => {}
+                Err(TryRecvError::Empty) => break true,
+                Err(TryRecvError::Closed) => break false,
+            }
+        });
+    }
+
+    /// The newest tick seen for `pair`, if it is followed.
+    pub fn tick(&self, pair: &str) -> Option<&Tick> {
+        self.topics.get(pair)?.tick.as_ref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::oracle_service::trades::{Aggressor, TradePrint};
+    use rust_decimal_macros::dec;
+
+    #[tokio::test]
+    async fn ticks_come_from_the_hub_after_a_cache_snapshot() {
+        let oracle = OracleCache::default();
+        oracle.set(Tick::new("BTC/USDT", dec!(100), 1_000)).await;
+        let mut feed = MarketFeed::default();
+        feed.follow(&oracle, "BTC/USDT").await;
+        feed.follow(&oracle, "ETH/USDT").await;
+        assert_eq!(feed.tick("BTC/USDT").unwrap().price, dec!(100));
+        assert!(feed.tick("ETH/USDT").is_none());
+
+        oracle.set(Tick::new("BTC/USDT", dec!(101), 2_000)).await;
+        oracle.set(Tick::new("BTC/USDT", dec!(102), 3_000)).await;
+        oracle.set(Tick::new("ETH/USDT", dec!(5), 3_000)).await;
+        for pair in ["BTC/USDT", "ETH/USDT"] {
+            oracle.record_trade(TradePrint {
+                pair: pair.into(),
+                price: dec!(1),
+                size: dec!(2),
+                side: Aggressor::Buy,
+                ts_ms: 3_000,
+                seq: None,
+            });
+        }
+        // Still the snapshot until the topics are drained.
+        assert_eq!(feed.tick("BTC/USDT").unwrap().price, dec!(100));
+        let mut volumes = VolumeBank::default();
+        volumes.require(60_000);
+        feed.drain("BTC/USDT", &mut volumes);
+        assert_eq!(feed.tick("BTC/USDT").unwrap().price, dec!(102));
+        assert_eq!(feed.tick("ETH/USDT").unwrap().price, dec!(5));
+        // Only the matcher's own pair feeds its volume windows.
+        assert_eq!(volumes.volumes(3_000)[&60_000], dec!(2));
+
+        feed.retain(&HashSet::from([Symbol::intern("BTC/USDT")]));
+        assert!(feed.tick("ETH/USDT").is_none());
+        oracle.hub().close("BTC/USDT");
+        feed.drain("BTC/USDT", &mut volumes);
+        assert!(feed.tick("BTC/USDT").is_none());
+    }
+}
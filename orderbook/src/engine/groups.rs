@@ -0,0 +1,223 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use tokio::sync::Notify;
+use tracing::{info, warn};
+
+use crate::entities::events::OrderEvent;
+use crate::entities::group::{OrderGroup, CANCEL_GROUP_BUDGET};
+use crate::entities::order::{Order, OrderStatus};
+use crate::repositories::changes::{ChangeListener, ChangeStream, OrderChange};
+use crate::repositories::OrderRepository;
+
+#[derive(Default)]
+struct Inner {
+    groups: HashMap<String, OrderGroup>,
+    /// Group of each member order.
+    by_order: HashMap<String, String>,
+    /// Members of exhausted groups still to cancel.
+    to_cancel: Vec<String>,
+}
+
+impl Inner {
+    fn observe(&mut self, o: &Order) {
+        let Some(g) = self
+            .by_order
+            .get(&o.id)
+            .and_then(|id| self.groups.get_mut(id))
+        else {
+            return;
+        };
+        let left = g.observe(o);
+        if !left.is_empty() {
+            info!(group = %g.id, spent = %g.spent, budget = %g.budget, cancelling = left.len(), "GROUP BUDGET SPENT");
+            self.to_cancel.extend(left);
+        }
+    }
+}
+
+/// Order groups with a shared quote budget, kept up to date from the
+/// change stream. When a member's fill takes its group to the budget, the
+/// members still active are queued for [`start_group_budgets`] to cancel.
+/// A member belongs to one group at most.
+#[derive(Clone, Default)]
+pub struct OrderGroups {
+    inner: Arc<Mutex<Inner>>,
+    wake: Arc<Notify>,
+}
+
+impl OrderGroups {
+    pub fn follow(&self, stream: &ChangeStream) {
+        stream.listen(Arc::new(self.clone()));
+    }
+
+    /// Stores `g` unless one of its orders is already in a group, which is
+    /// named in the error.
+    pub fn create(&self, g: OrderGroup) -> Result<OrderGroup, String> {
+        let mut w = self.inner.lock().unwrap();
+        if let Some((order, group)) = g
+            .members
+            .iter()
+            .find_map(|m| w.by_order.get_key_value(&m.order_id))
+        {
+            return Err(format!("order {order} is already in group {group}"));
+        }
+        for m in &g.members {
+            w.by_order.insert(m.order_id.clone(), g.id.clone());
+        }
+        w.groups.insert(g.id.clone(), g.clone());
+        Ok(g)
+    }
+
+    /// Catches a group up on `orders`, for fills that landed while it was
+    /// being created.
+    pub fn refresh(&self, orders: &[Order]) {
+        let mut w = self.inner.lock().unwrap();
+        for o in orders {
+            w.observe(o);
+        }
+        if !w.to_cancel.is_empty() {
+            self.wake.notify_one();
+        }
+    }
+
+    pub fn get(&self, id: &str) -> Option<OrderGroup> {
+        self.inner.lock().unwrap().groups.get(id).cloned()
+    }
+
+    /// `account_id`'s groups oldest first; with `None`, those created
+    /// without one.
+    pub fn list(&self, account_id: Option<&str>) -> Vec<OrderGroup> {
+        let r = self.inner.lock().unwrap();
+        let mut v: Vec<OrderGroup> = r
+            .groups
+            .values()
+            .filter(|g| g.account_id.as_deref() == account_id)
+            .cloned()
+            .collect();
+        v.sort_by_key(|g| g.created);
+        v
+    }
+
+    fn take_cancels(&self) -> Vec<String> {
+        std::mem::take(&mut self.inner.lock().unwrap().to_cancel)
+    }
+}
+
+impl ChangeListener for OrderGroups {
+    fn on_change(&self, c: &OrderChange) {
+        let mut w = self.inner.lock().unwrap();
+        match &c.event {
+            OrderEvent::Created { order } | OrderEvent::Updated { order } => w.observe(order),
+            OrderEvent::Deleted { order } => {
+                let mut gone = order.clone();
+                gone.status = OrderStatus::Cancelled;
+                w.observe(&gone);
+            }
+        }
+        let wake = !w.to_cancel.is_empty();
+        drop(w);
+        if wake {
+            self.wake.notify_one();
+        }
+    }
+}
+
+/// Cancels queued members that are still active with reason
+/// [`CANCEL_GROUP_BUDGET`]. Ones the store fails to cancel are logged and
+/// left. Returns how many were cancelled.
+pub async fn cancel_exhausted<R: OrderRepository + ?Sized>(
+    repo: &R,
+    groups: &OrderGroups,
+) -> usize {
+    let mut cancelled = 0;
+    for id in groups.take_cancels() {
+        match repo.get_by_id(&id).await {
+            Ok(o) if !o.status.is_active() => continue,
+            Ok(_) => {}
+            Err(e) => {
+                warn!(order_id = %id, err = %e, "group member not found");
+                continue;
+            }
+        }
+        match repo.cancel(&id, CANCEL_GROUP_BUDGET).await {
+            Ok(_) => cancelled += 1,
+            Err(e) => warn!(order_id = %id, err = %e, "group budget cancel failed"),
+        }
+    }
+    cancelled
+}
+
+/// Cancels the rest of each group as soon as its budget is spent.
+pub fn start_group_budgets<R: OrderRepository + 'static>(groups: OrderGroups, repo: R) {
+    tokio::spawn(async move {
+        loop {
+            groups.wake.notified().await;
+            cancel_exhausted(&repo, &groups).await;
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entities::group::GroupStatus;
+    use crate::entities::order::Fill;
+    use crate::entities::test_support::OrderBuilder;
+    use crate::repositories::changes::Observed;
+    use crate::repositories::in_memory::InMemoryOrderRepository;
+    use rust_decimal::Decimal;
+    use rust_decimal_macros::dec;
+
+    #[tokio::test]
+    async fn spending_the_budget_cancels_the_rest_of_the_group() {
+        let changes = ChangeStream::default();
+        let groups = OrderGroups::default();
+        groups.follow(&changes);
+        let repo = Observed::new(InMemoryOrderRepository::default(), changes);
+        let mut ladder = Vec::new();
+        for px in [100, 95, 90] {
+            let new = OrderBuilder::buy("BTC/USDT").limit(px).qty(1).new_order();
+            ladder.push(repo.create(new).await.unwrap());
+        }
+        let g = groups
+            .create(OrderGroup::new(dec!(150), &ladder, None))
+            .unwrap();
+        assert!(groups
+            .create(OrderGroup::new(dec!(10), &ladder[2..], None))
+            .is_err());
+
+        let fill = Fill {
+            price: dec!(100),
+            quantity: Decimal::ONE,
+            ts_ms: 1,
+            received_ms: None,
+            decided_ms: None,
+            recovered_trigger: false,
+            interpolated_trigger: false,
+            legs: Vec::new(),
+            trigger_price: None,
+            fair_sources: Vec::new(),
+            counterparty: None,
+        };
+        repo.record_fills(&ladder[0].id, std::slice::from_ref(&fill))
+            .await
+            .unwrap();
+        assert_eq!(cancel_exhausted(&repo, &groups).await, 0);
+        assert_eq!(groups.get(&g.id).unwrap().spent, dec!(100));
+
+        let fill = Fill {
+            price: dec!(95),
+            ts_ms: 2,
+            ..fill
+        };
+        repo.record_fills(&ladder[1].id, &[fill]).await.unwrap();
+        assert_eq!(cancel_exhausted(&repo, &groups).await, 1);
+        let g = groups.get(&g.id).unwrap();
+        assert_eq!((g.status, g.spent), (GroupStatus::Exhausted, dec!(195)));
+        let last = repo.get_by_id(&ladder[2].id).await.unwrap();
+        assert_eq!(last.status, OrderStatus::Cancelled);
+        assert_eq!(last.cancel_reason.as_deref(), Some(CANCEL_GROUP_BUDGET));
+        assert!(g.members.iter().all(|m| !m.active));
+    }
+}
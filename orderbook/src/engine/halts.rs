@@ -0,0 +1,242 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+/// What happens to orders that would have triggered while their pair was
+/// halted. `TriggerImmediately` fills them on the first tick after resume
+/// whatever the price is then; `RequireFreshCrossing` holds them until the
+/// trigger has been seen not to hold and then crosses again.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResumePolicy {
+    TriggerImmediately,
+    #[default]
+    RequireFreshCrossing,
+}
+
+impl ResumePolicy {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "trigger_immediately" | "immediate" => Some(Self::TriggerImmediately),
+            "require_fresh_crossing" | "fresh" => Some(Self::RequireFreshCrossing),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HaltSource {
+    Admin,
+    CircuitBreaker,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WouldTrigger {
+    pub order_id: String,
+    #[serde(serialize_with = "crate::utils::decimal::serialize")]
+    pub ref_px: Decimal,
+    pub first_seen_ms: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Halt {
+    pub pair: String,
+    pub source: HaltSource,
+    pub reason: String,
+    pub since_ms: i64,
+    pub would_trigger: Vec<WouldTrigger>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ResumeReport {
+    pub pair: String,
+    pub policy: ResumePolicy,
+    pub halted_ms: i64,
+    pub would_trigger: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct HaltStats {
+    pub default_policy: ResumePolicy,
+    pub halted: Vec<Halt>,
+    pub pending_immediate: usize,
+    pub pending_fresh_crossing: usize,
+    pub last_resumes: Vec<ResumeReport>,
+}
+
+/// Rule applied to a parked order by the matcher after resume.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pending {
+    FireNow,
+    AwaitReset,
+}
+
+const RESUME_HISTORY: usize = 20;
+
+#[derive(Default)]
+struct Inner {
+    default_policy: ResumePolicy,
+    halted: BTreeMap<String, Halt>,
+    pending: HashMap<String, Pending>,
+    resumes: Vec<ResumeReport>,
+}
+
+/// Per-pair halts shared by the admin API and the matcher workers.
+#[derive(Clone, Default)]
+pub struct HaltRegistry {
+    inner: Arc<Mutex<Inner>>,
+}
+
+impl HaltRegistry {
+    pub fn with_default_policy(policy: ResumePolicy) -> Self {
+        let reg = Self::default();
+        reg.inner.lock().unwrap().default_policy = policy;
+        reg
+    }
+
+    /// Policy used when a resume request does not name one.
+    pub fn default_policy(&self) -> ResumePolicy {
+        self.inner.lock().unwrap().default_policy
+    }
+
+    /// Returns `false` if the pair was already halted.
+    pub fn halt(&self, pair: &str, source: HaltSource, reason: &str, now_ms: i64) -> bool {
+        let mut g = self.inner.lock().unwrap();
+        if g.halted.contains_key(pair) {
+            return false;
+        }
+        g.halted.insert(
+            pair.to_string(),
+            Halt {
+                pair: pair.to_string(),
+                source,
+                reason: reason.to_string(),
+                since_ms: now_ms,
+                would_trigger: Vec::new(),
+            },
+        );
+        true
+    }
+
+    /// Lifts a halt and parks every order that would have triggered under
+    /// `policy`. `None` if the pair was not halted.
+    pub fn resume(&self, pair: &str, policy: ResumePolicy, now_ms: i64) -> Option<ResumeReport> {
+        let mut g = self.inner.lock().unwrap();
+        let halt = g.halted.remove(pair)?;
+        let rule = match policy {
+            ResumePolicy::TriggerImmediately => Pending::FireNow,
+            ResumePolicy::RequireFreshCrossing => Pending::AwaitReset,
+        };
+        let ids: Vec<String> = halt.would_trigger.into_iter().map(|w| w.order_id).collect();
+        for id in &ids {
+            g.pending.insert(id.clone(), rule);
+        }
+        let report = ResumeReport {
+            pair: pair.to_string(),
+            policy,
+            halted_ms: now_ms - halt.since_ms,
+            would_trigger: ids,
+        };
+        g.resumes.push(report.clone());
+        if g.resumes.len() > RESUME_HISTORY {
+            g.resumes.remove(0);
+        }
+        Some(report)
+    }
+
+    /// Carries a halt on `from` over to `to`. Returns whether there was
+    /// one.
+    pub fn rename(&self, from: &str, to: &str) -> bool {
+        let mut g = self.inner.lock().unwrap();
+        let Some(mut halt) = g.halted.remove(from) else {
+            return false;
+        };
+        halt.pair = to.to_string();
+        g.halted.insert(to.to_string(), halt);
+        true
+    }
+
+    pub fn is_halted(&self, pair: &str) -> bool {
+        self.inner.lock().unwrap().halted.contains_key(pair)
+    }
+
+    /// Notes that `order_id` would have triggered during the current halt.
+    /// Only the first sighting is kept.
+    pub fn record_would_trigger(&self, pair: &str, order_id: &str, ref_px: Decimal, ts_ms: i64) {
+        let mut g = self.inner.lock().unwrap();
+        if let Some(h) = g.halted.get_mut(pair) {
+            if !h.would_trigger.iter().any(|w| w.order_id == order_id) {
+                h.would_trigger.push(WouldTrigger {
+                    order_id: order_id.to_string(),
+                    ref_px,
+                    first_seen_ms: ts_ms,
+                });
+            }
+        }
+    }
+
+    pub fn pending(&self, order_id: &str) -> Option<Pending> {
+        self.inner.lock().unwrap().pending.get(order_id).copied()
+    }
+
+    pub fn clear_pending(&self, order_id: &str) {
+        self.inner.lock().unwrap().pending.remove(order_id);
+    }
+
+    pub fn stats(&self) -> HaltStats {
+        let g = self.inner.lock().unwrap();
+        let count = |p| g.pending.values().filter(|v| **v == p).count();
+        HaltStats {
+            default_policy: g.default_policy,
+            halted: g.halted.values().cloned().collect(),
+            pending_immediate: count(Pending::FireNow),
+            pending_fresh_crossing: count(Pending::AwaitReset),
+            last_resumes: g.resumes.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn would_trigger_is_recorded_once_and_parked_on_resume() {
+        let h = HaltRegistry::default();
+        assert!(h.halt("BTC/USDT", HaltSource::Admin, "ops", 1_000));
+        assert!(!h.halt("BTC/USDT", HaltSource::Admin, "again", 1_001));
+        h.record_would_trigger("BTC/USDT", "o1", dec!(100), 1_100);
+        h.record_would_trigger("BTC/USDT", "o1", dec!(99), 1_200);
+        h.record_would_trigger("ETH/USDT", "o2", dec!(1), 1_200);
+        assert_eq!(h.stats().halted[0].would_trigger.len(), 1);
+
+        let report = h
+            .resume("BTC/USDT", ResumePolicy::TriggerImmediately, 2_000)
+            .unwrap();
+        assert_eq!(report.would_trigger, vec!["o1".to_string()]);
+        assert_eq!(report.halted_ms, 1_000);
+        assert!(!h.is_halted("BTC/USDT"));
+        assert_eq!(h.pending("o1"), Some(Pending::FireNow));
+        assert_eq!(h.stats().pending_immediate, 1);
+        assert!(h
+            .resume("BTC/USDT", ResumePolicy::TriggerImmediately, 2_000)
+            .is_none());
+    }
+
+    #[test]
+    fn resume_policy_parses_long_and_short_names() {
+        assert_eq!(
+            ResumePolicy::parse("fresh"),
+            Some(ResumePolicy::RequireFreshCrossing)
+        );
+        assert_eq!(
+            ResumePolicy::parse("Trigger_Immediately"),
+            Some(ResumePolicy::TriggerImmediately)
+        );
+        assert_eq!(ResumePolicy::parse("later"), None);
+    }
+}
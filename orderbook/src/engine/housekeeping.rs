@@ -0,0 +1,709 @@
+use std::collections::{HashMap, VecDeque};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use serde::{Serialize, Serializer};
+use tokio::time::{interval, MissedTickBehavior};
+use tracing::{info, warn};
+
+use crate::accounts::purge::JobStatus;
+use crate::entities::order::{OrderStatus, CANCEL_HOUSEKEEPING};
+use crate::repositories::open_orders::OpenOrdersView;
+use crate::repositories::search::OrderSearch;
+use crate::repositories::vacuum::archive_filled;
+use crate::repositories::{ListOrdersQuery, OrderRepository};
+use crate::utils::now_ms;
+
+/// Runs kept for the admin API, across every policy.
+const RUN_HISTORY: usize = 200;
+
+/// How often the scheduler looks for policies due this minute.
+const CHECK_EVERY: Duration = Duration::from_secs(15);
+
+const MINUTE_MS: i64 = 60_000;
+const DAY_MINUTES: i64 = 24 * 60;
+
+/// A five-field cron expression, `minute hour day-of-month month
+/// day-of-week`, read in UTC. Each field is `*`, a value, a range `a-b`,
+/// any of those with a `/step`, or a comma list of them. Day of week runs
+/// from 0 (Sunday) to 6, with 7 also Sunday. As in cron, when both day
+/// fields are restricted a day matching either will do; a field that
+/// admits every value, such as `*/1` or `1-31`, is not restricted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Schedule {
+    spec: String,
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    any_day: bool,
+    any_weekday: bool,
+}
+
+fn field(spec: &str, lo: u32, hi: u32) -> Option<u64> {
+    let mut bits = 0u64;
+    for part in spec.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((r, s)) => (r, s.parse::<u32>().ok().filter(|&s| s > 0)?),
+            None => (part, 1),
+        };
+        let (from, to) = match range {
+            "*" => (lo, hi),
+            r => match r.split_once('-') {
+                Some((a, b)) => (a.parse().ok()?, b.parse().ok()?),
+                // `a/step` runs from `a` to the end of the field.
+                None if step > 1 => (r.parse().ok()?, hi),
+                None => {
+                    let v = r.parse().ok()?;
+                    (v, v)
+                }
+            },
+        };
+        if from < lo || to > hi || from > to {
+            return None;
+        }
+        for v in (from..=to).step_by(step as usize) {
+            bits |= 1 << v;
+        }
+    }
+    Some(bits)
+}
+
+/// Month (1-12) and day of month of a count of days since 1970-01-01.
+fn month_and_day(days: i64) -> (u32, u32) {
+    let z = days + 719_468;
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    (month as u32, day as u32)
+}
+
+impl Schedule {
+    pub fn parse(spec: &str) -> Option<Self> {
+        let fields: Vec<&str> = spec.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = fields[..] else {
+            return None;
+        };
+        let mut weekdays = field(weekday, 0, 7)?;
+        if weekdays & (1 << 7) != 0 {
+            weekdays = (weekdays | 1) & !(1 << 7);
+        }
+        let days = field(day, 1, 31)?;
+        Some(Self {
+            spec: fields.join(" "),
+            minutes: field(minute, 0, 59)?,
+            hours: field(hour, 0, 23)?,
+            days,
+            months: field(month, 1, 12)?,
+            weekdays,
+            any_day: days == field("*", 1, 31)?,
+            any_weekday: weekdays == field("*", 0, 6)?,
+        })
+    }
+
+    fn day_matches(&self, days: i64) -> bool {
+        let (month, day) = month_and_day(days);
+        // 1970-01-01 was a Thursday.
+        let weekday = (days + 4).rem_euclid(7);
+        let by_day = self.days & (1 << day) != 0;
+        let by_weekday = self.weekdays & (1 << weekday) != 0;
+        let day_ok = match (self.any_day, self.any_weekday) {
+            (false, false) => by_day || by_weekday,
+            _ => by_day && by_weekday,
+        };
+        self.months & (1 << month) != 0 && day_ok
+    }
+
+    fn minute_matches(&self, minute_of_day: i64) -> bool {
+        self.hours & (1 << (minute_of_day / 60)) != 0
+            && self.minutes & (1 << (minute_of_day % 60)) != 0
+    }
+
+    /// Whether the minute `ts_ms` falls in is one the schedule fires on.
+    pub fn matches(&self, ts_ms: i64) -> bool {
+        let minute = ts_ms.div_euclid(MINUTE_MS);
+        self.day_matches(minute.div_euclid(DAY_MINUTES))
+            && self.minute_matches(minute.rem_euclid(DAY_MINUTES))
+    }
+
+    /// The start of the first minute after `ts_ms`'s that the schedule
+    /// fires on, looking a little over a year ahead.
+    pub fn next_after(&self, ts_ms: i64) -> Option<i64> {
+        let start = ts_ms.div_euclid(MINUTE_MS) + 1;
+        let first_day = start.div_euclid(DAY_MINUTES);
+        for day in first_day..=first_day + 366 {
+            if !self.day_matches(day) {
+                continue;
+            }
+            let from = if day == first_day {
+                start.rem_euclid(DAY_MINUTES)
+            } else {
+                0
+            };
+            if let Some(m) = (from..DAY_MINUTES).find(|&m| self.minute_matches(m)) {
+                return Some((day * DAY_MINUTES + m) * MINUTE_MS);
+            }
+        }
+        None
+    }
+}
+
+impl Serialize for Schedule {
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        s.serialize_str(&self.spec)
+    }
+}
+
+/// Parses `30d`, `12h`, `15m` or `45s` into milliseconds.
+fn parse_age(s: &str) -> Option<i64> {
+    let (n, unit_ms) = [
+        ('s', 1_000),
+        ('m', MINUTE_MS),
+        ('h', 60 * MINUTE_MS),
+        ('d', DAY_MINUTES * MINUTE_MS),
+    ]
+    .into_iter()
+    .find_map(|(unit, ms)| Some((s.strip_suffix(unit)?, ms)))?;
+    let n: i64 = n.parse().ok().filter(|&n| n > 0)?;
+    n.checked_mul(unit_ms)
+}
+
+/// What a policy does when it runs. `CancelOlderThan` cancels active
+/// orders placed more than `older_than_ms` ago that carry no deadline of
+/// their own (`cancel_after_ms`, `valid_for_after_armed_ms`, `expires_at`).
+/// `ArchiveFilled` archives and deletes filled orders last updated that
+/// long ago, as the vacuum does. `RebuildIndexes` re-seeds the open orders
+/// view and the search index from the store.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum HousekeepingAction {
+    CancelOlderThan { older_than_ms: i64 },
+    ArchiveFilled { older_than_ms: i64 },
+    RebuildIndexes,
+}
+
+impl HousekeepingAction {
+    /// Parses `cancel_older_than=30d`, `archive_filled=7d` or
+    /// `rebuild_indexes`.
+    pub fn parse(spec: &str) -> Option<Self> {
+        match spec.split_once('=') {
+            Some(("cancel_older_than", age)) => Some(Self::CancelOlderThan {
+                older_than_ms: parse_age(age)?,
+            }),
+            Some(("archive_filled", age)) => Some(Self::ArchiveFilled {
+                older_than_ms: parse_age(age)?,
+            }),
+            None if spec == "rebuild_indexes" => Some(Self::RebuildIndexes),
+            _ => None,
+        }
+    }
+}
+
+/// A named action run on one pair, or on every pair when `pair` is unset,
+/// each minute its schedule fires on.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct HousekeepingPolicy {
+    pub name: String,
+    pub pair: Option<String>,
+    pub schedule: Schedule,
+    #[serde(flatten)]
+    pub action: HousekeepingAction,
+}
+
+impl HousekeepingPolicy {
+    /// Parses one `HOUSEKEEPING` entry, `NAME|PAIR|SCHEDULE|ACTION`, with
+    /// `*` for every pair; e.g. `stale|*|0 3 * * *|cancel_older_than=30d`.
+    /// `rebuild_indexes` covers every pair, so it takes `*` only.
+    pub fn parse(entry: &str) -> Result<Self, String> {
+        let parts: Vec<&str> = entry.split('|').map(str::trim).collect();
+        let [name, pair, schedule, action] = parts[..] else {
+            return Err("expected NAME|PAIR|SCHEDULE|ACTION".into());
+        };
+        if name.is_empty() {
+            return Err("policy name is empty".into());
+        }
+        let schedule = Schedule::parse(schedule).ok_or("bad schedule")?;
+        let action = HousekeepingAction::parse(action).ok_or("bad action")?;
+        let pair = (pair != "*").then(|| pair.to_string());
+        if pair.is_some() && action == HousekeepingAction::RebuildIndexes {
+            return Err("rebuild_indexes runs on every pair; use *".into());
+        }
+        Ok(Self {
+            name: name.to_string(),
+            pair,
+            schedule,
+            action,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RunTrigger {
+    Schedule,
+    Manual,
+}
+
+/// One run of a policy. `affected` counts the orders cancelled or archived,
+/// or those indexed by a rebuild; `failed` those an action had to leave.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct HousekeepingRun {
+    pub id: String,
+    pub policy: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pair: Option<String>,
+    #[serde(flatten)]
+    pub action: HousekeepingAction,
+    pub trigger: RunTrigger,
+    pub status: JobStatus,
+    pub affected: usize,
+    pub failed: usize,
+    pub started_ms: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub finished_ms: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// A policy as the admin API lists it, with when it fires next and how its
+/// latest run went.
+#[derive(Debug, Clone, Serialize)]
+pub struct PolicyStatus {
+    #[serde(flatten)]
+    pub policy: HousekeepingPolicy,
+    pub next_run_ms: Option<i64>,
+    pub last_run: Option<HousekeepingRun>,
+}
+
+/// Runs the configured housekeeping policies and keeps their history.
+/// Orders are cancelled and deleted through `orders`, so listeners on the
+/// change stream see those writes like any other. `archive` is the file
+/// `archive_filled` appends orders to; without one it fails rather than
+/// delete what it was meant to keep.
+#[derive(Clone)]
+pub struct Housekeeper {
+    orders: Arc<dyn OrderRepository>,
+    open_orders: OpenOrdersView,
+    search: OrderSearch,
+    archive: Option<PathBuf>,
+    policies: Arc<Vec<HousekeepingPolicy>>,
+    runs: Arc<Mutex<VecDeque<HousekeepingRun>>>,
+}
+
+impl Housekeeper {
+    pub fn new(
+        orders: Arc<dyn OrderRepository>,
+        open_orders: OpenOrdersView,
+        search: OrderSearch,
+        archive: Option<PathBuf>,
+        policies: Vec<HousekeepingPolicy>,
+    ) -> Self {
+        Self {
+            orders,
+            open_orders,
+            search,
+            archive,
+            policies: Arc::new(policies),
+            runs: Arc::default(),
+        }
+    }
+
+    /// The policies in `HOUSEKEEPING`, `;`-separated. Malformed entries
+    /// and repeated names are logged and skipped.
+    pub fn policies_from_env() -> Vec<HousekeepingPolicy> {
+        let mut policies: Vec<HousekeepingPolicy> = Vec::new();
+        let Ok(list) = std::env::var("HOUSEKEEPING") else {
+            return policies;
+        };
+        for entry in list.split(';').filter(|e| !e.trim().is_empty()) {
+            match HousekeepingPolicy::parse(entry) {
+                Ok(p) if policies.iter().any(|q| q.name == p.name) => {
+                    warn!(entry, "ignoring housekeeping policy with a repeated name")
+                }
+                Ok(p) => policies.push(p),
+                Err(err) => warn!(entry, %err, "ignoring housekeeping policy"),
+            }
+        }
+        policies
+    }
+
+    pub fn policies(&self) -> &[HousekeepingPolicy] {
+        &self.policies
+    }
+
+    pub fn status(&self, now_ms: i64) -> Vec<PolicyStatus> {
+        let runs = self.runs.lock().unwrap();
+        self.policies
+            .iter()
+            .map(|p| PolicyStatus {
+                policy: p.clone(),
+                next_run_ms: p.schedule.next_after(now_ms),
+                last_run: runs.iter().rev().find(|r| r.policy == p.name).cloned(),
+            })
+            .collect()
+    }
+
+    /// Runs, newest first, of `policy` or of every policy.
+    pub fn runs(&self, policy: Option<&str>) -> Vec<HousekeepingRun> {
+        let runs = self.runs.lock().unwrap();
+        runs.iter()
+            .rev()
+            .filter(|r| policy.is_none_or(|p| r.policy == p))
+            .cloned()
+            .collect()
+    }
+
+    /// Runs policy `name` to the end, ages taken from `now_ms`, and
+    /// returns the run. `None` for an unknown policy; errs while another
+    /// run of it is going.
+    pub async fn run(
+        &self,
+        name: &str,
+        trigger: RunTrigger,
+        now_ms: i64,
+    ) -> Option<Result<HousekeepingRun, String>> {
+        let policy = self.policies.iter().find(|p| p.name == name)?;
+        let run = match self.register(policy, trigger) {
+            Ok(run) => run,
+            Err(e) => return Some(Err(e)),
+        };
+        let result = self.work(policy, now_ms).await;
+        self.update(&run.id, |r| {
+            r.finished_ms = Some(crate::utils::now_ms());
+            match result {
+                Ok((affected, failed)) => {
+                    r.status = JobStatus::Completed;
+                    (r.affected, r.failed) = (affected, failed);
+                }
+                Err(e) => {
+                    r.status = JobStatus::Failed;
+                    r.error = Some(e);
+                }
+            }
+        });
+        let run = self.run_by_id(&run.id)?;
+        match run.status {
+            JobStatus::Completed => {
+                info!(policy = %run.policy, affected = run.affected, failed = run.failed, "housekeeping ran")
+            }
+            _ => warn!(policy = %run.policy, err = ?run.error, "housekeeping failed"),
+        }
+        Some(Ok(run))
+    }
+
+    fn register(
+        &self,
+        policy: &HousekeepingPolicy,
+        trigger: RunTrigger,
+    ) -> Result<HousekeepingRun, String> {
+        let run = HousekeepingRun {
+            id: crate::utils::ids::next_id(),
+            policy: policy.name.clone(),
+            pair: policy.pair.clone(),
+            action: policy.action.clone(),
+            trigger,
+            status: JobStatus::Running,
+            affected: 0,
+            failed: 0,
+            started_ms: now_ms(),
+            finished_ms: None,
+            error: None,
+        };
+        let mut runs = self.runs.lock().unwrap();
+        if let Some(running) = runs
+            .iter()
+            .find(|r| r.policy == policy.name && r.status == JobStatus::Running)
+        {
+            return Err(format!(
+                "run {} of {} is still going",
+                running.id, policy.name
+            ));
+        }
+        runs.push_back(run.clone());
+        while runs.len() > RUN_HISTORY {
+            runs.pop_front();
+        }
+        Ok(run)
+    }
+
+    fn run_by_id(&self, id: &str) -> Option<HousekeepingRun> {
+        let runs = self.runs.lock().unwrap();
+        runs.iter().find(|r| r.id == id).cloned()
+    }
+
+    fn update(&self, id: &str, f: impl FnOnce(&mut HousekeepingRun)) {
+        let mut runs = self.runs.lock().unwrap();
+        if let Some(r) = runs.iter_mut().find(|r| r.id == id) {
+            f(r);
+        }
+    }
+
+    async fn work(&self, policy: &HousekeepingPolicy, now: i64) -> Result<(usize, usize), String> {
+        let pair = policy.pair.as_deref();
+        match policy.action {
+            HousekeepingAction::CancelOlderThan { older_than_ms } => {
+                let cutoff = now - older_than_ms;
+                let mut stale = Vec::new();
+                for status in OrderStatus::ACTIVE {
+                    let orders = self
+                        .orders
+                        .list(ListOrdersQuery {
+                            pair: pair.map(str::to_string),
+                            status: Some(status),
+                            ..ListOrdersQuery::default()
+                        })
+                        .await?;
+                    stale.extend(orders.into_iter().filter(|o| {
+                        o.created < cutoff
+                            && o.cancel_after_ms.is_none()
+                            && o.valid_for_after_armed_ms.is_none()
+                            && o.expires_at.is_none()
+                    }));
+                }
+                let (mut cancelled, mut failed) = (0, 0);
+                for o in stale {
+                    // One that filled meanwhile is left as it is.
+                    match self.orders.cancel(&o.id, CANCEL_HOUSEKEEPING).await {
+                        Ok(_) => cancelled += 1,
+                        Err(err) => {
+                            warn!(policy = %policy.name, order_id = %o.id, %err, "housekeeping: cancel failed");
+                            failed += 1;
+                        }
+                    }
+                }
+                Ok((cancelled, failed))
+            }
+            HousekeepingAction::ArchiveFilled { older_than_ms } => {
+                let archive = self
+                    .archive
+                    .as_deref()
+                    .ok_or("no archive file configured (REPO_VACUUM_ARCHIVE)")?;
+                let report =
+                    archive_filled(&*self.orders, pair, older_than_ms, Some(archive), now).await?;
+                Ok((report.vacuumed, report.failed))
+            }
+            HousekeepingAction::RebuildIndexes => {
+                self.open_orders.rebuild(&*self.orders).await?;
+                let indexed = self.search.rebuild(&*self.orders).await?;
+                Ok((indexed, 0))
+            }
+        }
+    }
+
+    /// The policies whose schedule fires on the minute of `now_ms` and that
+    /// have not yet run for it, marking them as run in `last`.
+    fn due(&self, now_ms: i64, last: &mut HashMap<String, i64>) -> Vec<String> {
+        let minute = now_ms.div_euclid(MINUTE_MS);
+        self.policies
+            .iter()
+            .filter(|p| p.schedule.matches(now_ms))
+            .filter(|p| last.insert(p.name.clone(), minute) != Some(minute))
+            .map(|p| p.name.clone())
+            .collect()
+    }
+}
+
+/// Checks every few seconds for policies due this minute and runs them one
+/// after another.
+pub fn start_housekeeping(keeper: Housekeeper) {
+    if keeper.policies().is_empty() {
+        return;
+    }
+    tokio::spawn(async move {
+        let mut t = interval(CHECK_EVERY);
+        t.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        let mut last = HashMap::new();
+        loop {
+            t.tick().await;
+            let now = now_ms();
+            for name in keeper.due(now, &mut last) {
+                if let Some(Err(err)) = keeper.run(&name, RunTrigger::Schedule, now).await {
+                    warn!(policy = %name, %err, "housekeeping run skipped");
+                }
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entities::order::Order;
+    use crate::entities::test_support::{FakeRepo, OrderBuilder};
+
+    // Monday 2024-01-01 00:00:00 UTC.
+    const MONDAY: i64 = 1_704_067_200_000;
+    const HOUR: i64 = 60 * MINUTE_MS;
+    const DAY: i64 = 24 * HOUR;
+
+    #[test]
+    fn schedules_read_like_cron_in_utc() {
+        let nightly = Schedule::parse("30 3 * * *").unwrap();
+        assert!(nightly.matches(MONDAY + 3 * HOUR + 30 * MINUTE_MS + 59_000));
+        assert!(!nightly.matches(MONDAY + 3 * HOUR + 31 * MINUTE_MS));
+        assert_eq!(
+            nightly.next_after(MONDAY + 4 * HOUR),
+            Some(MONDAY + DAY + 3 * HOUR + 30 * MINUTE_MS)
+        );
+
+        let quarter = Schedule::parse("*/15 9-17 * * 1-5").unwrap();
+        assert!(quarter.matches(MONDAY + 9 * HOUR + 45 * MINUTE_MS));
+        assert!(!quarter.matches(MONDAY + 9 * HOUR + 50 * MINUTE_MS));
+        // Saturday the 6th is skipped for Monday the 8th.
+        assert_eq!(
+            quarter.next_after(MONDAY + 4 * DAY + 17 * HOUR + 45 * MINUTE_MS),
+            Some(MONDAY + 7 * DAY + 9 * HOUR)
+        );
+
+        // Either day field will do once both are set: the 15th, or Sundays.
+        let days = Schedule::parse("0 0 15 * 0,7").unwrap();
+        assert!(days.matches(MONDAY + 6 * DAY));
+        assert!(days.matches(MONDAY + 14 * DAY));
+        assert!(!days.matches(MONDAY + 15 * DAY));
+        // A step over every day still leaves the day of month open, so
+        // only Mondays match; `*/2` restricts it to odd days.
+        let mondays = Schedule::parse("0 0 */1 * 1").unwrap();
+        assert!(mondays.matches(MONDAY + 7 * DAY));
+        assert!(!mondays.matches(MONDAY + DAY));
+        let odd = Schedule::parse("0 0 */2 * 1").unwrap();
+        assert!(odd.matches(MONDAY + 2 * DAY));
+        assert!(odd.matches(MONDAY + 7 * DAY));
+        assert!(!odd.matches(MONDAY + 3 * DAY));
+        // February 29th 2024, then nothing before it comes round again.
+        let leap = Schedule::parse("0 12 29 2 *").unwrap();
+        assert_eq!(leap.next_after(MONDAY), Some(MONDAY + 59 * DAY + 12 * HOUR));
+        assert_eq!(
+            Schedule::parse("0 0 30 2 *").unwrap().next_after(MONDAY),
+            None
+        );
+
+        for bad in [
+            "* * * *",
+            "60 * * * *",
+            "*/0 * * * *",
+            "5-1 * * * *",
+            "0 0 0 * *",
+        ] {
+            assert!(Schedule::parse(bad).is_none(), "{bad}");
+        }
+    }
+
+    #[test]
+    fn policies_parse_from_config_entries() {
+        let p = HousekeepingPolicy::parse("stale | BTC/USDT | 0 3 * * * | cancel_older_than=30d")
+            .unwrap();
+        assert_eq!(
+            (p.name.as_str(), p.pair.as_deref()),
+            ("stale", Some("BTC/USDT"))
+        );
+        assert_eq!(
+            p.action,
+            HousekeepingAction::CancelOlderThan {
+                older_than_ms: 30 * DAY
+            }
+        );
+        let p = HousekeepingPolicy::parse("idx|*|0 * * * *|rebuild_indexes").unwrap();
+        assert_eq!(p.pair, None);
+        for bad in [
+            "x|*|0 3 * * *",
+            "|*|0 3 * * *|rebuild_indexes",
+            "x|*|0 3 * *|rebuild_indexes",
+            "x|*|0 3 * * *|archive_filled=7w",
+            "x|*|0 3 * * *|cancel_older_than=0d",
+            "x|*|0 3 * * *|cancel_older_than=30é",
+            "x|*|0 3 * * *|cancel_older_than=d",
+            "x|BTC/USDT|0 3 * * *|rebuild_indexes",
+        ] {
+            assert!(HousekeepingPolicy::parse(bad).is_err(), "{bad}");
+        }
+    }
+
+    fn keeper(repo: &FakeRepo, policies: &[&str]) -> Housekeeper {
+        Housekeeper::new(
+            Arc::new(repo.clone()),
+            OpenOrdersView::default(),
+            OrderSearch::default(),
+            None,
+            policies
+                .iter()
+                .map(|p| HousekeepingPolicy::parse(p).unwrap())
+                .collect(),
+        )
+    }
+
+    #[tokio::test]
+    async fn cancel_policies_leave_recent_and_self_expiring_orders() {
+        let repo = FakeRepo::default();
+        let keeper = keeper(&repo, &["stale|BTC/USDT|0 3 * * *|cancel_older_than=1h"]);
+        let now = MONDAY + 10 * DAY;
+        let order = |id: &str, pair: &str, created: i64| Order {
+            created,
+            ..OrderBuilder::buy(pair).limit(100).qty(1).id(id).build()
+        };
+        repo.seed([
+            order("old", "BTC/USDT", now - 2 * HOUR),
+            Order {
+                cancel_after_ms: Some(DAY),
+                ..order("deadline", "BTC/USDT", now - 2 * HOUR)
+            },
+            order("recent", "BTC/USDT", now - MINUTE_MS),
+            order("other-pair", "ETH/USDT", now - 2 * HOUR),
+        ])
+        .await;
+
+        let run = keeper
+            .run("stale", RunTrigger::Manual, now)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            (run.status, run.affected, run.failed),
+            (JobStatus::Completed, 1, 0)
+        );
+        let cancelled = repo.get_by_id("old").await.unwrap();
+        assert_eq!(
+            cancelled.cancel_reason.as_deref(),
+            Some(CANCEL_HOUSEKEEPING)
+        );
+        for id in ["deadline", "recent", "other-pair"] {
+            assert!(repo.get_by_id(id).await.unwrap().status.is_active(), "{id}");
+        }
+        assert!(keeper
+            .run("missing", RunTrigger::Manual, now)
+            .await
+            .is_none());
+        assert_eq!(keeper.runs(Some("stale")).first(), Some(&run));
+        assert!(keeper.runs(Some("other")).is_empty());
+        let status = keeper.status(MONDAY);
+        assert_eq!(status[0].last_run, Some(run));
+        assert_eq!(status[0].next_run_ms, Some(MONDAY + 3 * HOUR));
+    }
+
+    #[tokio::test]
+    async fn archiving_needs_a_file_and_policies_run_once_a_minute() {
+        let keeper = keeper(
+            &FakeRepo::default(),
+            &[
+                "archive|*|* * * * *|archive_filled=1d",
+                "idx|*|0 0 * * *|rebuild_indexes",
+            ],
+        );
+        let run = keeper
+            .run("archive", RunTrigger::Schedule, MONDAY)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(run.status, JobStatus::Failed);
+        assert!(run.error.unwrap().contains("REPO_VACUUM_ARCHIVE"));
+
+        let mut last = HashMap::new();
+        assert_eq!(keeper.due(MONDAY, &mut last), ["archive", "idx"]);
+        assert!(keeper.due(MONDAY + 30_000, &mut last).is_empty());
+        assert_eq!(keeper.due(MONDAY + MINUTE_MS, &mut last), ["archive"]);
+    }
+}
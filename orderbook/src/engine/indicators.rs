@@ -0,0 +1,347 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+
+use crate::entities::order::{Condition, CrossDirection, Indicator};
+
+/// Prices kept per worker so indicators requested by a new order can be
+/// warmed up from recent history instead of starting cold.
+const HISTORY: usize = 1_000;
+
+/// Longest period an order may ask for; bounded by the kept history.
+pub const MAX_PERIOD: u32 = 500;
+
+/// Indicator value after the latest tick and the one before it, which is
+/// what crossover detection needs. `None` while warming up.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Reading {
+    pub prev: Option<Decimal>,
+    pub current: Option<Decimal>,
+}
+
+pub type Readings = HashMap<Indicator, Reading>;
+
+#[derive(Debug, Clone)]
+enum Calc {
+    Sma {
+        period: usize,
+        window: VecDeque<Decimal>,
+        sum: Decimal,
+    },
+    Ema {
+        period: usize,
+        alpha: Decimal,
+        seed: Vec<Decimal>,
+        value: Option<Decimal>,
+    },
+    Rsi {
+        period: usize,
+        last_px: Option<Decimal>,
+        seed: Vec<(Decimal, Decimal)>,
+        avg: Option<(Decimal, Decimal)>,
+    },
+}
+
+impl Calc {
+    fn new(ind: Indicator) -> Self {
+        match ind {
+            Indicator::Sma { period } => Calc::Sma {
+                period: period as usize,
+                window: VecDeque::new(),
+                sum: Decimal::ZERO,
+            },
+            Indicator::Ema { period } => Calc::Ema {
+                period: period as usize,
+                alpha: dec!(2) / Decimal::from(period + 1),
+                seed: Vec::new(),
+                value: None,
+            },
+            Indicator::Rsi { period } => Calc::Rsi {
+                period: period as usize,
+                last_px: None,
+                seed: Vec::new(),
+                avg: None,
+            },
+        }
+    }
+
+    /// Folds one price in and returns the updated value, if warmed up.
+    /// EMA is seeded with the SMA of its first `period` prices; RSI uses
+    /// Wilder smoothing seeded the same way.
+    fn push(&mut self, px: Decimal) -> Option<Decimal> {
+        match self {
+            Calc::Sma {
+                period,
+                window,
+                sum,
+            } => {
+                window.push_back(px);
+                *sum += px;
+                if window.len() > *period {
+                    *sum -= window.pop_front().unwrap_or_default();
+                }
+                (window.len() == *period).then(|| *sum / Decimal::from(*period))
+            }
+            Calc::Ema {
+                period,
+                alpha,
+                seed,
+                value,
+            } => {
+                match value {
+                    Some(v) => *v = *alpha * px + (Decimal::ONE - *alpha) * *v,
+                    None => {
+                        seed.push(px);
+                        if seed.len() == *period {
+                            *value = Some(seed.iter().sum::<Decimal>() / Decimal::from(*period));
+                            seed.clear();
+                        }
+                    }
+                }
+                *value
+            }
+            Calc::Rsi {
+                period,
+                last_px,
+                seed,
+                avg,
+            } => {
+                let prev = last_px.replace(px)?;
+                let change = px - prev;
+                let (gain, loss) = if change > Decimal::ZERO {
+                    (change, Decimal::ZERO)
+                } else {
+                    (Decimal::ZERO, -change)
+                };
+                let n = Decimal::from(*period);
+                match avg {
+                    Some((g, l)) => {
+                        *g = (*g * (n - Decimal::ONE) + gain) / n;
+                        *l = (*l * (n - Decimal::ONE) + loss) / n;
+                    }
+                    None => {
+                        seed.push((gain, loss));
+                        if seed.len() < *period {
+                            return None;
+                        }
+                        let g: Decimal = seed.iter().map(|s| s.0).sum();
+                        let l: Decimal = seed.iter().map(|s| s.1).sum();
+                        *avg = Some((g / n, l / n));
+                        seed.clear();
+                    }
+                }
+                avg.map(|(g, l)| rsi(g, l))
+            }
+        }
+    }
+}
+
+fn rsi(avg_gain: Decimal, avg_loss: Decimal) -> Decimal {
+    if avg_loss.is_zero() {
+        return dec!(100);
+    }
+    dec!(100) - dec!(100) / (Decimal::ONE + avg_gain / avg_loss)
+}
+
+/// Incrementally maintained indicators for one pair. Each distinct oracle
+/// tick is observed once; series are added when an order first needs them
+/// and dropped when no active order does.
+#[derive(Debug, Default)]
+pub struct IndicatorBank {
+    history: VecDeque<Decimal>,
+    last_ts: Option<i64>,
+    series: HashMap<Indicator, (Calc, Reading)>,
+}
+
+impl IndicatorBank {
+    /// Returns `false` if the tick was already observed.
+    pub fn observe(&mut self, px: Decimal, ts_ms: i64) -> bool {
+        if self.last_ts == Some(ts_ms) {
+            return false;
+        }
+        self.last_ts = Some(ts_ms);
+        self.history.push_back(px);
+        if self.history.len() > HISTORY {
+            self.history.pop_front();
+        }
+        for (calc, reading) in self.series.values_mut() {
+            reading.prev = reading.current;
+            reading.current = calc.push(px);
+        }
+        true
+    }
+
+    pub fn require(&mut self, ind: Indicator) {
+        let history = &self.history;
+        self.series.entry(ind).or_insert_with(|| {
+            let mut calc = Calc::new(ind);
+            let mut reading = Reading::default();
+            for px in history {
+                reading.prev = reading.current;
+                reading.current = calc.push(*px);
+            }
+            (calc, reading)
+        });
+    }
+
+    /// Keeps only the series in `needed` and makes sure all of them exist.
+    pub fn track(&mut self, needed: &HashSet<Indicator>) {
+        self.series.retain(|i, _| needed.contains(i));
+        for i in needed {
+            self.require(*i);
+        }
+    }
+
+    pub fn readings(&self) -> Readings {
+        self.series.iter().map(|(i, (_, r))| (*i, *r)).collect()
+    }
+}
+
+/// Indicators referenced by a condition.
+pub fn required(cond: &Condition) -> Vec<Indicator> {
+    match cond {
+        Condition::Crossover { fast, slow, .. } => vec![*fast, *slow],
+        Condition::Level { indicator, .. } => vec![*indicator],
+        Condition::Price
+        | Condition::Notional { .. }
+        | Condition::Volume { .. }
+        | Condition::Spread { .. } => Vec::new(),
+    }
+}
+
+/// Whether an indicator condition holds on the latest readings. Errs with a
+/// hold reason while a series is still warming up.
+pub fn holds(cond: &Condition, readings: &Readings) -> Result<bool, &'static str> {
+    let get = |i: &Indicator| readings.get(i).copied().unwrap_or_default();
+    match cond {
+        Condition::Crossover {
+            fast,
+            slow,
+            direction,
+        } => {
+            let (f, s) = (get(fast), get(slow));
+            let (Some(pf), Some(cf), Some(ps), Some(cs)) = (f.prev, f.current, s.prev, s.current)
+            else {
+                return Err("indicators warming up");
+            };
+            Ok(match direction {
+                CrossDirection::Above => pf <= ps && cf > cs,
+                CrossDirection::Below => pf >= ps && cf < cs,
+            })
+        }
+        Condition::Level {
+            indicator,
+            below,
+            above,
+        } => {
+            let v = get(indicator).current.ok_or("indicator warming up")?;
+            Ok(below.is_none_or(|b| v < b) && above.is_none_or(|a| v > a))
+        }
+        Condition::Price
+        | Condition::Notional { .. }
+        | Condition::Volume { .. }
+        | Condition::Spread { .. } => Ok(false),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn feed(bank: &mut IndicatorBank, prices: &[i64]) {
+        let start = bank.last_ts.map_or(0, |t| t + 1);
+        for (i, px) in prices.iter().enumerate() {
+            bank.observe(Decimal::from(*px), start + i as i64);
+        }
+    }
+
+    #[test]
+    fn sma_and_ema_warm_up_then_track() {
+        let sma = Indicator::Sma { period: 3 };
+        let ema = Indicator::Ema { period: 3 };
+        let mut bank = IndicatorBank::default();
+        bank.require(sma);
+        bank.require(ema);
+        feed(&mut bank, &[1, 2]);
+        assert_eq!(bank.readings()[&sma].current, None);
+        feed(&mut bank, &[3]);
+        assert_eq!(bank.readings()[&sma].current, Some(dec!(2)));
+        assert_eq!(bank.readings()[&ema].current, Some(dec!(2)));
+        feed(&mut bank, &[6]);
+        let r = bank.readings();
+        assert_eq!(r[&sma].prev, Some(dec!(2)));
+        assert_eq!(r[&sma].current, Some(dec!(11) / dec!(3)));
+        assert_eq!(r[&ema].current, Some(dec!(4)));
+    }
+
+    #[test]
+    fn repeated_tick_is_observed_once_and_late_series_replay_history() {
+        let mut bank = IndicatorBank::default();
+        feed(&mut bank, &[1, 2, 3]);
+        assert!(!bank.observe(dec!(100), 2));
+        bank.require(Indicator::Sma { period: 2 });
+        let r = bank.readings()[&Indicator::Sma { period: 2 }];
+        assert_eq!(r.prev, Some(dec!(1.5)));
+        assert_eq!(r.current, Some(dec!(2.5)));
+    }
+
+    #[test]
+    fn rsi_is_100_without_losses_and_low_after_a_selloff() {
+        let rsi = Indicator::Rsi { period: 3 };
+        let mut bank = IndicatorBank::default();
+        bank.require(rsi);
+        feed(&mut bank, &[10, 11, 12, 13]);
+        assert_eq!(bank.readings()[&rsi].current, Some(dec!(100)));
+        feed(&mut bank, &[9, 6, 4]);
+        let v = bank.readings()[&rsi].current.unwrap();
+        assert!(v < dec!(30), "rsi {v}");
+    }
+
+    #[test]
+    fn crossover_fires_only_on_the_crossing_tick() {
+        let fast = Indicator::Sma { period: 1 };
+        let slow = Indicator::Sma { period: 3 };
+        let cond = Condition::Crossover {
+            fast,
+            slow,
+            direction: CrossDirection::Above,
+        };
+        let mut bank = IndicatorBank::default();
+        bank.track(&required(&cond).into_iter().collect());
+        feed(&mut bank, &[5, 5]);
+        assert_eq!(holds(&cond, &bank.readings()), Err("indicators warming up"));
+        feed(&mut bank, &[5, 4]);
+        assert_eq!(holds(&cond, &bank.readings()), Ok(false));
+        feed(&mut bank, &[8]);
+        assert_eq!(holds(&cond, &bank.readings()), Ok(true));
+        feed(&mut bank, &[9]);
+        assert_eq!(holds(&cond, &bank.readings()), Ok(false));
+    }
+
+    #[test]
+    fn level_checks_all_given_bounds() {
+        let ind = Indicator::Sma { period: 1 };
+        let mut readings = Readings::new();
+        readings.insert(
+            ind,
+            Reading {
+                prev: None,
+                current: Some(dec!(25)),
+            },
+        );
+        let below = |b| Condition::Level {
+            indicator: ind,
+            below: Some(b),
+            above: None,
+        };
+        assert_eq!(holds(&below(dec!(30)), &readings), Ok(true));
+        assert_eq!(holds(&below(dec!(20)), &readings), Ok(false));
+        let band = Condition::Level {
+            indicator: ind,
+            below: Some(dec!(30)),
+            above: Some(dec!(26)),
+        };
+        assert_eq!(holds(&band, &readings), Ok(false));
+    }
+}
@@ -0,0 +1,222 @@
+use std::collections::HashMap;
+
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use tracing::{debug, warn};
+
+use super::policy::MatchPolicy;
+use super::sustain::SustainTimers;
+use super::{process_active_orders, stops, Controls, EngineConfig, MarketView};
+use crate::entities::order::{Order, OrderType, TimeInForce, TriggerPriceType};
+use crate::repositories::OrderRepository;
+
+/// At most this many prices are filled in between two ticks; the levels
+/// nearest the previous tick go first.
+pub const MAX_STEPS: usize = 32;
+
+/// What the engine does when the price moves past orders' trigger levels
+/// between two ticks. `None` evaluates each tick on its own. `Linear`
+/// first walks the straight line from the previous tick to the new one,
+/// stopping at each level on the way.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Interpolation {
+    #[default]
+    None,
+    Linear,
+}
+
+impl Interpolation {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "none" | "off" => Some(Self::None),
+            "linear" => Some(Self::Linear),
+            _ => None,
+        }
+    }
+}
+
+/// Whether `o` is evaluated at filled-in prices: it must trigger on
+/// `last`, and plain market orders and IOC orders, which get one
+/// evaluation, wait for a tick's own price.
+fn walks(o: &Order) -> bool {
+    o.trigger_price_type == TriggerPriceType::Last
+        && o.price_source.is_none()
+        && o.order_type != OrderType::Market
+        && o.time_in_force != TimeInForce::Ioc
+}
+
+/// The price `o` waits for: a stop's trigger, or the level its condition
+/// reduces to.
+fn level(o: &Order) -> Option<Decimal> {
+    if o.order_type.is_stop() {
+        o.trigger_price
+    } else {
+        o.trigger_level()
+    }
+}
+
+/// The views between `prev`, the previous tick's last price and time, and
+/// `view`: one at each level of `orders` strictly between the two prices,
+/// in the direction of travel, stamped when the line from one tick to the
+/// other reaches it. They are `view` re-priced with no bid or ask. Empty
+/// when the price did not move or time did not advance.
+pub fn path(prev: (Decimal, i64), view: &MarketView, orders: &[Order]) -> Vec<MarketView> {
+    let ((from, from_ms), (to, to_ms)) = (prev, (view.last, view.ts_ms));
+    if from == to || from_ms >= to_ms {
+        return Vec::new();
+    }
+    let (lo, hi) = (from.min(to), from.max(to));
+    let mut levels: Vec<Decimal> = orders
+        .iter()
+        .filter(|o| walks(o))
+        .filter_map(level)
+        .filter(|l| lo < *l && *l < hi)
+        .collect();
+    levels.sort_by_key(|l| (l - from).abs());
+    levels.dedup();
+    levels.truncate(MAX_STEPS);
+    let span = Decimal::from(to_ms - from_ms);
+    levels
+        .into_iter()
+        .map(|l| {
+            let at = (span * (l - from) / (to - from))
+                .to_i64()
+                .unwrap_or_default();
+            MarketView {
+                last: l,
+                bid: None,
+                ask: None,
+                ts_ms: from_ms + at.clamp(0, to_ms - from_ms),
+                interpolated: true,
+                ..view.clone()
+            }
+        })
+        .collect()
+}
+
+/// Evaluates the orders that [walk](walks) on each of `steps` in turn,
+/// activating stops and filling as on a tick. A stop that becomes a
+/// market order waits for the tick's own price. Returns `orders` as they
+/// stand afterwards, those no longer active left out, with how many stops
+/// were activated and orders filled and promoted on the way.
+#[allow(clippy::too_many_arguments)]
+pub async fn run<R: OrderRepository, P: MatchPolicy + Clone + 'static>(
+    asset: &str,
+    repo: &R,
+    ctl: &Controls,
+    policy: &P,
+    cfg: &EngineConfig,
+    timers: &mut SustainTimers,
+    mut orders: Vec<Order>,
+    steps: &[MarketView],
+) -> (Vec<Order>, (usize, usize, usize)) {
+    let (mut activated, mut matched, mut promoted) = (0, 0, 0);
+    for step in steps {
+        let (walking, mut rest): (Vec<Order>, Vec<Order>) = orders.into_iter().partition(walks);
+        if walking.is_empty() {
+            orders = rest;
+            break;
+        }
+        debug!(%asset, interpolated_px = %step.last, at_ms = step.ts_ms, orders = walking.len(), "evaluating between ticks");
+        let before: HashMap<String, Order> =
+            walking.iter().map(|o| (o.id.clone(), o.clone())).collect();
+        let (walking, a) = stops::run(asset, repo, ctl, walking, step).await;
+        let walking = walking.into_iter().filter(walks).collect();
+        let (m, p) =
+            process_active_orders(asset, repo, walking, step, policy, cfg, ctl, timers).await;
+        (activated, matched, promoted) = (activated + a, matched + m, promoted + p);
+        for (id, o) in before {
+            match repo.get_by_id(&id).await {
+                Ok(now) if now.status.is_active() => rest.push(now),
+                Ok(_) => {}
+                Err(e) => {
+                    warn!(%asset, order_id = %id, err = %e, "failed to re-read order between ticks");
+                    rest.push(o);
+                }
+            }
+        }
+        orders = rest;
+    }
+    (orders, (activated, matched, promoted))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::policy::OraclePolicy;
+    use crate::entities::order::{OrderStatus, Trail};
+    use crate::entities::test_support::OrderBuilder;
+    use crate::repositories::in_memory::InMemoryOrderRepository;
+    use crate::utils::now_ms;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn the_path_stops_at_each_level_passed_in_the_direction_of_travel() {
+        let orders = [
+            OrderBuilder::sell("BTC/USDT").qty(1).stop(95).build(),
+            OrderBuilder::buy("BTC/USDT").limit(92).qty(1).build(),
+            OrderBuilder::sell("BTC/USDT").limit(105).qty(1).build(),
+            OrderBuilder::sell("BTC/USDT").qty(1).stop(95).build(),
+            OrderBuilder::buy("BTC/USDT").qty(1).market().build(),
+        ];
+        let view = MarketView::from_last(dec!(90), 2_000);
+        let steps = path((dec!(100), 1_000), &view, &orders);
+        assert_eq!(
+            steps
+                .iter()
+                .map(|v| (v.last, v.ts_ms, v.interpolated))
+                .collect::<Vec<_>>(),
+            [(dec!(95), 1_500, true), (dec!(92), 1_800, true)]
+        );
+        assert!(path((dec!(90), 1_000), &view, &orders).is_empty());
+        assert!(path((dec!(100), 2_000), &view, &orders).is_empty());
+    }
+
+    #[tokio::test]
+    async fn a_gap_through_a_trailed_stop_fills_it_at_its_level() {
+        let repo = InMemoryOrderRepository::default();
+        let ctl = Controls::default();
+        let policy = OraclePolicy::default();
+        let mut timers = SustainTimers::default();
+        let trailing = OrderBuilder::sell("BTC/USDT")
+            .qty(1)
+            .trailing_stop(Trail::Amount(dec!(5)));
+        let trailing = repo.create(trailing.new_order()).await.unwrap();
+        let limit = OrderBuilder::sell("BTC/USDT").qty(1).stop_limit(110, 115);
+        let limit = repo.create(limit.new_order()).await.unwrap();
+
+        // The trail was at 120 on the previous tick; the next comes in at 90.
+        let trailing = repo
+            .trail_stop(&trailing.id, dec!(120), dec!(115))
+            .await
+            .unwrap();
+        let view = MarketView::from_last(dec!(90), now_ms());
+        let orders = vec![trailing, limit.clone()];
+        let steps = path((dec!(120), view.ts_ms - 1_000), &view, &orders);
+        assert_eq!(steps.len(), 1);
+        let (left, (activated, matched, _)) = run(
+            "BTC/USDT",
+            &repo,
+            &ctl,
+            &policy,
+            &EngineConfig::default(),
+            &mut timers,
+            orders,
+            &steps,
+        )
+        .await;
+        assert_eq!((activated, matched), (2, 1));
+
+        // The stop-limit sold at the filled-in 115; the stop is a market
+        // order waiting for the tick itself.
+        let sold = repo.get_by_id(&limit.id).await.unwrap();
+        assert_eq!(sold.status, OrderStatus::Filled);
+        let fill = &sold.fills[0];
+        assert_eq!((fill.price, fill.interpolated_trigger), (dec!(115), true));
+        assert_eq!(
+            left.iter().map(|o| o.order_type).collect::<Vec<_>>(),
+            [OrderType::Market]
+        );
+        assert!(left[0].stop_triggered_ms.is_some());
+    }
+}
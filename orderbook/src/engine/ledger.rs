@@ -0,0 +1,207 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+
+use rust_decimal::Decimal;
+use serde::Serialize;
+
+/// Committed intents kept so a late duplicate is still refused; older ones
+/// are for orders that have long moved past those triggers.
+const DEFAULT_RETAIN_COMMITTED: usize = 100_000;
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum IntentState {
+    /// Claimed but the fill not yet known to be written.
+    Claimed,
+    Committed,
+}
+
+/// The engine's claim on one trigger event of an order, written before its
+/// fills. `trigger_seq` is how many trigger events the order had already
+/// filled on, so every engine looking at the same state of the order asks
+/// for the same key.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct ExecutionIntent {
+    pub order_id: String,
+    pub trigger_seq: u64,
+    pub pair: String,
+    pub owner: String,
+    pub ts_ms: i64,
+    #[serde(serialize_with = "crate::utils::decimal::serialize")]
+    pub quantity: Decimal,
+    pub state: IntentState,
+    pub claimed_ms: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub committed_ms: Option<i64>,
+}
+
+type Key = (String, u64);
+
+struct Inner {
+    retain_committed: usize,
+    intents: HashMap<Key, ExecutionIntent>,
+    committed: VecDeque<Key>,
+}
+
+/// Execution intents keyed by `(order_id, trigger_seq)`, at most one per
+/// key, so a retry from a stale copy of an order or a second matcher
+/// worker on the same pair cannot fill the same trigger twice. Shared by
+/// the matcher workers and the admin API of one process; each handle
+/// claims as its `owner`. The ledger lives in process memory, like the
+/// in-memory store, so it is neither shared with other processes nor kept
+/// across a restart; a durable store would need these claims in it, unique
+/// on the key.
+#[derive(Clone)]
+pub struct ExecutionLedger {
+    owner: Arc<str>,
+    inner: Arc<Mutex<Inner>>,
+}
+
+impl Default for ExecutionLedger {
+    fn default() -> Self {
+        Self::with_retention(DEFAULT_RETAIN_COMMITTED)
+    }
+}
+
+impl ExecutionLedger {
+    pub fn with_retention(retain_committed: usize) -> Self {
+        Self {
+            owner: Arc::from("local"),
+            inner: Arc::new(Mutex::new(Inner {
+                retain_committed,
+                intents: HashMap::new(),
+                committed: VecDeque::new(),
+            })),
+        }
+    }
+
+    /// A handle on the same ledger that claims as `owner`.
+    pub fn for_owner(&self, owner: &str) -> Self {
+        Self {
+            owner: Arc::from(owner),
+            inner: self.inner.clone(),
+        }
+    }
+
+    /// Writes an intent for the key unless one exists, which is returned
+    /// instead.
+    pub fn claim(
+        &self,
+        order_id: &str,
+        trigger_seq: u64,
+        pair: &str,
+        ts_ms: i64,
+        quantity: Decimal,
+        now_ms: i64,
+    ) -> Result<(), Box<ExecutionIntent>> {
+        let mut g = self.inner.lock().unwrap();
+        let key = (order_id.to_string(), trigger_seq);
+        if let Some(prior) = g.intents.get(&key) {
+            return Err(Box::new(prior.clone()));
+        }
+        g.intents.insert(
+            key,
+            ExecutionIntent {
+                order_id: order_id.to_string(),
+                trigger_seq,
+                pair: pair.to_string(),
+                owner: self.owner.to_string(),
+                ts_ms,
+                quantity,
+                state: IntentState::Claimed,
+                claimed_ms: now_ms,
+                committed_ms: None,
+            },
+        );
+        Ok(())
+    }
+
+    /// Marks the claim's fills as written. `false` if there was no such
+    /// claim.
+    pub fn commit(&self, order_id: &str, trigger_seq: u64, now_ms: i64) -> bool {
+        let mut g = self.inner.lock().unwrap();
+        let key = (order_id.to_string(), trigger_seq);
+        let Some(intent) = g.intents.get_mut(&key) else {
+            return false;
+        };
+        if intent.state == IntentState::Committed {
+            return false;
+        }
+        intent.state = IntentState::Committed;
+        intent.committed_ms = Some(now_ms);
+        g.committed.push_back(key);
+        while g.committed.len() > g.retain_committed {
+            if let Some(old) = g.committed.pop_front() {
+                g.intents.remove(&old);
+            }
+        }
+        true
+    }
+
+    /// Drops a claim whose fills were not written, so the trigger may be
+    /// filled again. Committed intents stay. `false` if there was no such
+    /// claim.
+    pub fn abort(&self, order_id: &str, trigger_seq: u64) -> bool {
+        let mut g = self.inner.lock().unwrap();
+        let key = (order_id.to_string(), trigger_seq);
+        if g.intents
+            .get(&key)
+            .is_some_and(|i| i.state == IntentState::Claimed)
+        {
+            g.intents.remove(&key);
+            return true;
+        }
+        false
+    }
+
+    /// Claims never committed or aborted, oldest first: fills that may or
+    /// may not have been written, which hold their orders until resolved.
+    pub fn unresolved(&self) -> Vec<ExecutionIntent> {
+        let g = self.inner.lock().unwrap();
+        let mut v: Vec<ExecutionIntent> = g
+            .intents
+            .values()
+            .filter(|i| i.state == IntentState::Claimed)
+            .cloned()
+            .collect();
+        v.sort_by(|a, b| {
+            (a.claimed_ms, &a.order_id, a.trigger_seq).cmp(&(
+                b.claimed_ms,
+                &b.order_id,
+                b.trigger_seq,
+            ))
+        });
+        v
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn one_intent_per_trigger_event() {
+        let ledger = ExecutionLedger::with_retention(1);
+        let other = ledger.for_owner("standby");
+        ledger.claim("a", 0, "BTC/USDT", 1, dec!(1), 10).unwrap();
+        let prior = other.claim("a", 0, "BTC/USDT", 1, dec!(1), 11).unwrap_err();
+        assert_eq!(
+            (prior.owner.as_str(), prior.state),
+            ("local", IntentState::Claimed)
+        );
+        other.claim("a", 1, "BTC/USDT", 2, dec!(1), 12).unwrap();
+        assert_eq!(ledger.unresolved().len(), 2);
+
+        assert!(ledger.commit("a", 0, 20));
+        assert!(!ledger.commit("a", 0, 20));
+        assert!(!ledger.abort("a", 0));
+        assert!(ledger.claim("a", 0, "BTC/USDT", 1, dec!(1), 21).is_err());
+        assert!(other.abort("a", 1));
+        other.claim("a", 1, "BTC/USDT", 3, dec!(1), 22).unwrap();
+        // Past the retention, the oldest committed intent is forgotten.
+        other.commit("a", 1, 23);
+        assert!(ledger.claim("a", 0, "BTC/USDT", 1, dec!(1), 24).is_ok());
+        assert!(ledger.claim("a", 1, "BTC/USDT", 3, dec!(1), 24).is_err());
+    }
+}
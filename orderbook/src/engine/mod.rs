@@ -1,34 +1,212 @@
 use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use std::collections::{BTreeMap, HashSet};
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 use tokio::time::{interval, MissedTickBehavior};
-use tracing::{debug, error, info, instrument};
+use tracing::{debug, error, info, instrument, warn};
 
-use crate::entities::order::{Order, OrderSide, OrderStatus};
-use crate::oracle_service::OracleCache;
+use crate::entities::events::MarketEvent;
+use crate::entities::order::{
+    Condition, Fill, Order, OrderSide, OrderStatus, TimeInForce, TriggerPriceType, CANCEL_IOC,
+    CANCEL_QUOTE_TOO_SMALL,
+};
+use crate::flags::FeatureFlags;
+use crate::instruments::liquidity::LiquidityCurve;
+use crate::instruments::session::SessionState;
+use crate::instruments::symbol::Symbol;
+use crate::instruments::{base_for_quote, InstrumentRegistry, DEFAULT_SCALE};
+use crate::oracle_service::{OracleCache, Tick};
+use crate::repositories::changes::ChangeStream;
 use crate::repositories::{ListOrdersQuery, OrderRepository};
+use deadletter::DeadLetters;
+use downtime::{Downtime, MissedTriggerPolicy, MissedTriggers};
+use fairband::FairBand;
+use feed::MarketFeed;
+use halts::{HaltRegistry, HaltSource, Pending};
+use indicators::{IndicatorBank, Readings};
+use interpolate::Interpolation;
+use ledger::ExecutionLedger;
+use policy::{Decision, FlaggedPolicy, MatchPolicy, OraclePolicy, PolicyKind};
+use retry::{ErrorBudget, ErrorBudgetConfig, Resilient, RetryPolicy};
+use spread::Legs;
+use sustain::SustainTimers;
+use throttle::FillThrottle;
+use volume::{VolumeBank, Volumes};
+
+pub mod baskets;
+pub mod bots;
+pub mod brackets;
+pub mod callbacks;
+pub mod crossing;
+pub mod deadletter;
+pub mod delist;
+pub mod downtime;
+pub mod expiry;
+pub mod fairband;
+pub mod feed;
+pub mod groups;
+pub mod halts;
+pub mod housekeeping;
+pub mod indicators;
+pub mod interpolate;
+pub mod ledger;
+pub mod orphans;
+pub mod policy;
+pub mod reconcile;
+pub mod reevaluate;
+pub mod rename;
+pub mod retry;
+pub mod spread;
+pub mod stops;
+pub mod sustain;
+pub mod throttle;
+pub mod timers;
+pub mod volume;
+
+/// Matcher tuning shared by every worker. `policy` picks the
+/// [`MatchPolicy`]. `market_band_bps` is the widest gap between last and
+/// index at which a market order may still execute (and, under
+/// `band_protected`, any order); `fok_max_slippage_bps` bounds how deep
+/// into the simulated book an order may sweep unless it sets its own
+/// bound. When
+/// `circuit_breaker_bps` is set, a last price further than that from the
+/// index halts the pair. Repository calls are retried per `retry`, and a
+/// pair whose calls keep failing pauses per `error_budget`. A worker that
+/// has just started, or has not evaluated for over `downtime_gap`, applies
+/// `missed_trigger` to the orders that were already resting. With
+/// `fair_band_bps` set and venues configured, fills are only let through
+/// while their trigger reference lies within that many bps of the range
+/// the healthy venues quote.
+#[derive(Debug, Clone)]
+pub struct EngineConfig {
+    pub tick_every: Duration,
+    pub policy: PolicyKind,
+    pub market_band_bps: Decimal,
+    pub fok_max_slippage_bps: Decimal,
+    pub circuit_breaker_bps: Option<Decimal>,
+    pub retry: RetryPolicy,
+    pub error_budget: ErrorBudgetConfig,
+    pub missed_trigger: MissedTriggerPolicy,
+    pub downtime_gap: Duration,
+    pub fair_band_bps: Option<Decimal>,
+    /// Whether to fill in prices between ticks; never across a gap longer
+    /// than `downtime_gap`.
+    pub interpolation: Interpolation,
+    /// How many threads may evaluate one pair's orders on a tick; 1 keeps
+    /// evaluation on the matcher worker. Only large books are split.
+    pub eval_threads: usize,
+    /// How many orders one tick may fill before the rest log at DEBUG and
+    /// the tick is summed up in one `BulkFill` log and event instead.
+    pub bulk_fill_threshold: usize,
+}
+
+impl Default for EngineConfig {
+    fn default() -> Self {
+        Self {
+            tick_every: Duration::from_secs(1),
+            policy: PolicyKind::default(),
+            market_band_bps: dec!(100),
+            fok_max_slippage_bps: dec!(50),
+            circuit_breaker_bps: None,
+            retry: RetryPolicy::default(),
+            error_budget: ErrorBudgetConfig::default(),
+            missed_trigger: MissedTriggerPolicy::default(),
+            downtime_gap: Duration::from_secs(30),
+            fair_band_bps: None,
+            interpolation: Interpolation::None,
+            eval_threads: 1,
+            bulk_fill_threshold: DEFAULT_BULK_FILL_THRESHOLD,
+        }
+    }
+}
+
+impl EngineConfig {
+    pub fn match_policy(&self) -> Box<dyn MatchPolicy> {
+        self.policy
+            .build(self.oracle_policy(), self.market_band_bps)
+    }
+
+    /// [`match_policy`](Self::match_policy), with the features `flags`
+    /// turns on for an order's pair or account taking over for it.
+    pub fn flagged_policy(&self, flags: &FeatureFlags) -> FlaggedPolicy {
+        FlaggedPolicy::new(self.match_policy(), self.oracle_policy(), flags.clone())
+    }
+
+    fn oracle_policy(&self) -> OraclePolicy {
+        OraclePolicy {
+            market_band_bps: self.market_band_bps,
+            fok_max_slippage_bps: self.fok_max_slippage_bps,
+        }
+    }
+}
+
+/// State the matcher workers share with the admin API.
+#[derive(Clone, Default)]
+pub struct Controls {
+    pub halts: HaltRegistry,
+    pub dead_letters: DeadLetters,
+    /// Where the engine announces market events such as breaker halts.
+    pub events: ChangeStream,
+    /// Orders whose triggers may have been missed while a worker was down.
+    pub missed: MissedTriggers,
+    /// One claim per trigger event, written before its fills.
+    pub ledger: ExecutionLedger,
+    /// Features turned on for some pairs or accounts.
+    pub flags: FeatureFlags,
+    /// Pairs whose worker is running.
+    pub matchers: Matchers,
+    /// The cap on fills per pair per second, and the triggers over it.
+    pub throttle: FillThrottle,
+}
+
+/// Which pairs have a matcher worker running. A worker stops once its
+/// pair no longer resolves in the registry, as after a delisting.
+#[derive(Clone, Default)]
+pub struct Matchers {
+    running: Arc<Mutex<HashSet<String>>>,
+}
+
+impl Matchers {
+    pub fn is_running(&self, pair: &str) -> bool {
+        self.running.lock().unwrap().contains(pair)
+    }
+
+    fn started(&self, pair: &str) {
+        self.running.lock().unwrap().insert(pair.to_string());
+    }
+
+    fn stopped(&self, pair: &str) {
+        self.running.lock().unwrap().remove(pair);
+    }
+}
 
 pub fn start_matchers<R: OrderRepository + Clone + 'static>(
     assets: Vec<String>,
     repo: R,
     oracle: OracleCache,
-    tick_every: Duration,
+    instruments: InstrumentRegistry,
+    ctl: Controls,
+    cfg: EngineConfig,
 ) {
     for asset in assets {
         let repo_cloned = repo.clone();
         let oracle_cloned = oracle.clone();
+        let instruments = instruments.clone();
+        let ctl = ctl.clone();
+        let cfg = cfg.clone();
         tokio::spawn(async move {
-            run_worker(asset, repo_cloned, oracle_cloned, tick_every).await;
+            run_worker(asset, repo_cloned, oracle_cloned, instruments, ctl, cfg).await;
         });
     }
 }
 
-async fn collect_active_orders<R: OrderRepository>(asset: &str, repo: &R) -> Vec<Order> {
+pub(crate) async fn collect_active_orders<R: OrderRepository + ?Sized>(
+    asset: &str,
+    repo: &R,
+) -> Vec<Order> {
     let mut active: Vec<Order> = Vec::new();
-    for status in [
-        OrderStatus::New,
-        OrderStatus::Open,
-        OrderStatus::PartiallyFilled,
-    ] {
+    for status in OrderStatus::ACTIVE {
         match repo
             .list(ListOrdersQuery {
                 pair: Some(asset.to_string()),
@@ -47,192 +225,880 @@ async fn collect_active_orders<R: OrderRepository>(asset: &str, repo: &R) -> Vec
     active
 }
 
-async fn process_active_orders<R: OrderRepository>(
+/// Prices available to the matcher on a tick. `last` is the raw oracle
+/// price and the default execution price; `bid`/`ask` are only present
+/// when the feed quotes them, and `index`/`mark` come from the index module
+/// and may be missing until it has seen the pair. `stale` is set when the
+/// latest tick is older than the cache's staleness window, and
+/// `received_ms` when the cache accepted it. `liquidity` is
+/// the pair's simulated depth, if configured, `indicators` the readings
+/// active orders' indicator conditions need, and `volumes` the traded
+/// volume over the windows their volume conditions read, and `legs` the
+/// prices of the pairs their spread conditions read. `qty_scale` is
+/// the pair's quantity precision, to which quote-sized orders are sized,
+/// and `price_scale` its price precision. Prices are rounded to it, so feed noise past
+/// it (`100.000000001` from a float) cannot cross an order at `100`.
+#[derive(Debug, Clone)]
+pub struct MarketView {
+    pub last: Decimal,
+    pub bid: Option<Decimal>,
+    pub ask: Option<Decimal>,
+    pub index: Option<Decimal>,
+    pub mark: Option<Decimal>,
+    pub ts_ms: i64,
+    pub received_ms: Option<i64>,
+    pub stale: bool,
+    pub liquidity: Option<LiquidityCurve>,
+    pub indicators: Readings,
+    pub volumes: Volumes,
+    pub legs: Legs,
+    pub qty_scale: u32,
+    pub price_scale: u32,
+    /// Set when the engine bands fills by the healthy venues' prices.
+    pub fair_band: Option<FairBand>,
+    /// A price filled in between two ticks (see [`interpolate`]), not a
+    /// tick's own.
+    pub interpolated: bool,
+}
+
+impl MarketView {
+    pub fn from_last(last: Decimal, ts_ms: i64) -> Self {
+        Self {
+            last,
+            bid: None,
+            ask: None,
+            index: None,
+            mark: None,
+            ts_ms,
+            received_ms: None,
+            stale: false,
+            liquidity: None,
+            indicators: Readings::new(),
+            volumes: Volumes::new(),
+            legs: Legs::new(),
+            qty_scale: DEFAULT_SCALE,
+            price_scale: DEFAULT_SCALE,
+            fair_band: None,
+            interpolated: false,
+        }
+    }
+
+    fn within_band(&self, band_bps: Decimal) -> bool {
+        match self.index {
+            Some(idx) if !idx.is_zero() => {
+                ((self.last - idx) / idx).abs() * dec!(10000) <= band_bps
+            }
+            _ => true,
+        }
+    }
+
+    pub fn reference(&self, r: TriggerPriceType) -> Option<Decimal> {
+        match r {
+            TriggerPriceType::Last => Some(self.last),
+            TriggerPriceType::Bid => self.bid,
+            TriggerPriceType::Ask => self.ask,
+            TriggerPriceType::Index => self.index,
+            TriggerPriceType::Mark => self.mark,
+        }
+    }
+}
+
+/// The view from the cache's latest tick, for callers outside a
+/// matcher's loop.
+async fn market_view(
+    oracle: &OracleCache,
+    instruments: &InstrumentRegistry,
+    asset: &str,
+) -> Option<MarketView> {
+    let tick = oracle.get_tick(asset).await?;
+    Some(tick_view(oracle, instruments, asset, &tick).await)
+}
+
+async fn tick_view(
+    oracle: &OracleCache,
+    instruments: &InstrumentRegistry,
+    asset: &str,
+    tick: &Tick,
+) -> MarketView {
+    let idx = oracle.get_index(asset).await;
+    let instrument = instruments.get(asset);
+    let scale = instrument.as_ref().map_or(DEFAULT_SCALE, |i| i.price_scale);
+    let px = |d: Decimal| d.round_dp(scale);
+    MarketView {
+        last: px(tick.price),
+        bid: tick.bid.map(px),
+        ask: tick.ask.map(px),
+        ts_ms: tick.ts_ms,
+        received_ms: tick.received_ms,
+        stale: !oracle.is_fresh(tick, crate::utils::now_ms()),
+        index: idx.as_ref().map(|i| px(i.index_price)),
+        mark: idx.as_ref().map(|i| px(i.mark_price)),
+        liquidity: instrument.as_ref().and_then(|i| i.liquidity.clone()),
+        indicators: Readings::new(),
+        volumes: Volumes::new(),
+        legs: Legs::new(),
+        qty_scale: instrument.map_or(DEFAULT_SCALE, |i| i.qty_scale),
+        price_scale: scale,
+        fair_band: None,
+        interpolated: false,
+    }
+}
+
+/// `base` re-quoted from `source`'s latest tick; the index, depth,
+/// indicators and volumes stay the aggregate's. `None` until the venue has ticked.
+async fn venue_view(
+    oracle: &OracleCache,
+    base: &MarketView,
+    asset: &str,
+    source: &str,
+) -> Option<MarketView> {
+    let tick = oracle.get_venue_tick(asset, source).await?;
+    let px = |d: Decimal| d.round_dp(base.price_scale);
+    Some(MarketView {
+        last: px(tick.price),
+        bid: tick.bid.map(px),
+        ask: tick.ask.map(px),
+        ts_ms: tick.ts_ms,
+        received_ms: tick.received_ms,
+        stale: !oracle.is_fresh(&tick, crate::utils::now_ms()),
+        ..base.clone()
+    })
+}
+
+/// Fixes the base quantity of a quote-sized order on its first trigger:
+/// the quote amount over the price it will fill at, rounded down to the
+/// pair's precision, with the fill re-executed for that quantity. The
+/// quantity to store comes back with the decision. A sweep's VWAP for the
+/// new quantity may differ slightly from the price it was sized at.
+fn size_by_quote(
+    o: &Order,
+    view: &MarketView,
+    policy: &dyn MatchPolicy,
+    decision: Decision,
+) -> (Decision, Option<Decimal>) {
+    let (
+        Some(quote),
+        Decision::Fill {
+            ref_px, exec_px, ..
+        },
+    ) = (o.quantity_quote, &decision)
+    else {
+        return (decision, None);
+    };
+    if !o.fills.is_empty() {
+        return (decision, None);
+    }
+    let px = o
+        .exec_pricing
+        .map_or(*exec_px, |p| p.price(&o.side, *exec_px, o.price));
+    let qty = base_for_quote(quote, px, view.qty_scale);
+    if qty.is_zero() {
+        return (Decision::Cancel(CANCEL_QUOTE_TOO_SMALL), None);
+    }
+    let sized = Order {
+        quantity: qty,
+        ..o.clone()
+    };
+    match policy.execute(&sized, view, *ref_px) {
+        d @ Decision::Fill { .. } => (d, Some(qty)),
+        d => (d, None),
+    }
+}
+
+/// Active orders grouped by the price source they trigger on, aggregate
+/// (`None`) first.
+fn by_source(active: Vec<Order>) -> BTreeMap<Option<String>, Vec<Order>> {
+    let mut groups: BTreeMap<_, Vec<_>> = BTreeMap::new();
+    for o in active {
+        groups.entry(o.price_source.clone()).or_default().push(o);
+    }
+    groups
+}
+
+/// Applies the resume rule of an order parked by a halt on top of its
+/// normal evaluation.
+fn after_halt(
+    halts: &HaltRegistry,
+    o: &Order,
+    view: &MarketView,
+    policy: &dyn MatchPolicy,
+    decision: Decision,
+) -> Decision {
+    match halts.pending(&o.id) {
+        None => decision,
+        Some(Pending::FireNow) => {
+            halts.clear_pending(&o.id);
+            if decision.is_waiting() {
+                let ref_px = view.reference(o.trigger_price_type).unwrap_or(view.last);
+                policy.execute(o, view, ref_px)
+            } else {
+                decision
+            }
+        }
+        Some(Pending::AwaitReset) => match decision {
+            Decision::Fill { .. } => Decision::waiting(o, "awaiting fresh crossing after halt"),
+            other => {
+                halts.clear_pending(&o.id);
+                other
+            }
+        },
+    }
+}
+
+/// Evaluates orders of a halted pair without acting on them, recording the
+/// ones that would have triggered. Returns how many did.
+fn record_halted(
+    asset: &str,
+    halts: &HaltRegistry,
+    orders: &[Order],
+    view: &MarketView,
+    policy: &dyn MatchPolicy,
+) -> usize {
+    let mut n = 0;
+    for o in orders {
+        if let Decision::Fill { ref_px, .. } = policy.evaluate(o, view) {
+            halts.record_would_trigger(asset, &o.id, ref_px, view.ts_ms);
+            n += 1;
+        }
+    }
+    n
+}
+
+/// Counts a failed engine write toward the order's dead-letter limit and
+/// parks the order as `Suspended` once the limit is reached.
+async fn write_failed<R: OrderRepository>(
+    repo: &R,
+    ctl: &Controls,
+    o: &Order,
+    operation: &'static str,
+    err: &str,
+) {
+    let now = crate::utils::now_ms();
+    if !ctl
+        .dead_letters
+        .record_failure(&o.id, &o.pair, operation, err, now)
+    {
+        return;
+    }
+    match repo.set_status(&o.id, OrderStatus::Suspended).await {
+        Ok(_) => {
+            warn!(pair = %o.pair, order_id = %o.id, operation, "order suspended after repeated write failures")
+        }
+        Err(e) => {
+            error!(pair = %o.pair, order_id = %o.id, err = %e, "failed to suspend; the engine skips the order from now on")
+        }
+    }
+}
+
+/// An IOC order gets one evaluation: one still waiting after it is
+/// cancelled.
+fn immediate_or_cancel(o: &Order, decision: Decision) -> Decision {
+    if o.time_in_force == TimeInForce::Ioc && decision.is_waiting() {
+        Decision::Cancel(CANCEL_IOC)
+    } else {
+        decision
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn process_active_orders<R: OrderRepository, P: MatchPolicy + Clone + 'static>(
     asset: &str,
     repo: &R,
     orders: Vec<Order>,
-    px: Decimal,
-    ts_ms: i64,
+    view: &MarketView,
+    policy: &P,
+    cfg: &EngineConfig,
+    ctl: &Controls,
+    timers: &mut SustainTimers,
 ) -> (usize, usize) {
+    let px = view.last;
+    let ts_ms = view.ts_ms;
     let mut matched = 0usize;
     let mut promoted = 0usize;
-    for o in orders {
-        if crosses(&o, px) {
-            match repo.set_status(&o.id, OrderStatus::Filled).await {
-                Ok(filled) => {
-                    matched += 1;
-                    log_exec(&filled, px, ts_ms);
+    let threshold = cfg.bulk_fill_threshold;
+    let mut bulk = BulkFill::default();
+    let mut orders: Vec<Order> = orders
+        .into_iter()
+        .filter(|o| !ctl.dead_letters.is_suspended(&o.id))
+        .collect();
+    ctl.throttle.prioritise(asset, &mut orders);
+    let mut throttled = 0usize;
+    let (orders, decisions) = evaluate_batch(orders, view, policy, cfg.eval_threads).await;
+    // Depth one order takes on this tick is gone for the next on its side,
+    // so once a fill has taken some, the side's later orders are evaluated
+    // again against what is left.
+    let (mut bids, mut asks) = ((view.clone(), false), (view.clone(), false));
+    // OCO legs cancelled by a fill earlier in this pass.
+    let mut withdrawn: HashSet<String> = HashSet::new();
+    for (o, decision) in orders.into_iter().zip(decisions) {
+        if withdrawn.contains(&o.id) {
+            continue;
+        }
+        let (view, consumed) = match o.side {
+            OrderSide::Buy => (&mut asks.0, &mut asks.1),
+            OrderSide::Sell => (&mut bids.0, &mut bids.1),
+        };
+        let decision = if *consumed {
+            policy.evaluate(&o, view)
+        } else {
+            decision
+        };
+        let decision = timers.gate(&o, ts_ms, decision);
+        let now = crate::utils::now_ms();
+        let decided_ms = Some(now);
+        let decision = after_halt(&ctl.halts, &o, view, policy, decision);
+        let (decision, recovered) = ctl.missed.apply(&o, view, policy, decision, now);
+        let (decision, sized) = size_by_quote(&o, view, policy, decision);
+        let decision = fairband::gate(view, decision);
+        let decision = immediate_or_cancel(&o, decision);
+        let reason = match decision {
+            Decision::Fill {
+                ref_px,
+                exec_px,
+                qty,
+                levels,
+            } => {
+                if !ctl.throttle.admit(asset, &o.id, now) {
+                    throttled += 1;
+                    debug!(%asset, order_id = %o.id, "fill throttled; queued for a later tick");
+                    continue;
                 }
-                Err(e) => {
-                    error!(%asset, order_id = %o.id, err = %e, "failed to set status=Filled");
+                let seq = o.trigger_seq();
+                if let Err(prior) = ctl.ledger.claim(&o.id, seq, asset, ts_ms, qty, now) {
+                    warn!(%asset, order_id = %o.id, trigger_seq = seq, owner = %prior.owner, state = ?prior.state, "trigger already claimed; not filling");
+                    ctl.throttle.refund(asset, now);
+                    continue;
                 }
-            }
-        } else if matches!(o.status, OrderStatus::New) {
-            match repo.set_status(&o.id, OrderStatus::Open).await {
-                Ok(_) => {
-                    promoted += 1;
-                    debug!(%asset, order_id = %o.id, limit_px = o.price.to_string(), oracle_px = px.to_string(), "promoted NEW -> OPEN (not crossing)");
+                if let Some(qty) = sized {
+                    if let Err(e) = repo.amend(&o.id, o.price, qty).await {
+                        error!(%asset, order_id = %o.id, err = %e, "failed to size quote order");
+                        ctl.ledger.abort(&o.id, seq);
+                        ctl.throttle.refund(asset, now);
+                        write_failed(repo, ctl, &o, "amend", &e).await;
+                        continue;
+                    }
+                }
+                if let Some(curve) = &mut view.liquidity {
+                    curve.consume(levels.iter().map(|l| l.quantity).sum());
+                    *consumed = true;
                 }
-                Err(e) => {
-                    error!(%asset, order_id = %o.id, err = %e, "failed to promote NEW -> OPEN");
+                let legs = spread::quotes(&o.condition, &view.legs);
+                let fair_sources = fairband::quotes(view);
+                let fill = |price, quantity| Fill {
+                    price,
+                    quantity,
+                    ts_ms,
+                    received_ms: view.received_ms,
+                    decided_ms,
+                    recovered_trigger: recovered,
+                    interpolated_trigger: view.interpolated,
+                    legs: legs.clone(),
+                    trigger_price: Some(ref_px),
+                    fair_sources: fair_sources.clone(),
+                    counterparty: None,
+                };
+                let fills = match o.exec_pricing {
+                    Some(p) => vec![fill(p.price(&o.side, exec_px, o.price), qty)],
+                    None if levels.is_empty() => vec![fill(exec_px, qty)],
+                    None => levels
+                        .into_iter()
+                        .map(|l| fill(l.price, l.quantity))
+                        .collect(),
+                };
+                let written = match o.oco_id {
+                    None => repo.record_fills(&o.id, &fills).await,
+                    Some(_) => repo.fill_oco(&o.id, &fills).await.map(|(leg, cancelled)| {
+                        for c in cancelled {
+                            info!(%asset, order_id = %c.id, filled_leg = %leg.id, "OCO leg cancelled");
+                            withdrawn.insert(c.id);
+                        }
+                        leg
+                    }),
+                };
+                match written {
+                    Ok(filled) => {
+                        matched += 1;
+                        ctl.ledger.commit(&o.id, seq, crate::utils::now_ms());
+                        ctl.dead_letters.record_success(&o.id);
+                        let quiet = bulk.orders >= threshold;
+                        bulk.add(&fills);
+                        log_exec(&filled, ref_px, &fills, ts_ms, quiet);
+                        if o.time_in_force == TimeInForce::Ioc && filled.status.is_active() {
+                            match repo.cancel(&o.id, CANCEL_IOC).await {
+                                Ok(_) => {
+                                    info!(%asset, order_id = %o.id, left = %(filled.quantity - filled.filled_quantity), "IOC remainder cancelled")
+                                }
+                                Err(e) => {
+                                    error!(%asset, order_id = %o.id, err = %e, "failed to cancel IOC remainder");
+                                    write_failed(repo, ctl, &o, "cancel", &e).await;
+                                }
+                            }
+                        }
+                    }
+                    Err(e) => {
+                        error!(%asset, order_id = %o.id, err = %e, "failed to record fills");
+                        ctl.ledger.abort(&o.id, seq);
+                        ctl.throttle.refund(asset, now);
+                        let op = if o.oco_id.is_some() {
+                            "fill_oco"
+                        } else {
+                            "record_fills"
+                        };
+                        write_failed(repo, ctl, &o, op, &e).await;
+                    }
                 }
+                continue;
             }
-        } else {
-            debug!(%asset, order_id = %o.id, status = ?o.status, limit_px = o.price.to_string(), oracle_px = px.to_string(), "not crossing");
+            Decision::Cancel(reason) => {
+                match repo.cancel(&o.id, reason).await {
+                    Ok(_) => {
+                        ctl.dead_letters.record_success(&o.id);
+                        info!(%asset, order_id = %o.id, reason, "cancelled on trigger")
+                    }
+                    Err(e) => {
+                        error!(%asset, order_id = %o.id, err = %e, "failed to cancel");
+                        write_failed(repo, ctl, &o, "cancel", &e).await;
+                    }
+                }
+                continue;
+            }
+            Decision::Hold(reason) => {
+                debug!(%asset, order_id = %o.id, status = ?o.status, limit_px = o.price.to_string(), oracle_px = px.to_string(), reason, "holding");
+                continue;
+            }
+            Decision::Promote(reason) => reason,
+        };
+        match repo.set_status(&o.id, OrderStatus::Open).await {
+            Ok(_) => {
+                promoted += 1;
+                ctl.dead_letters.record_success(&o.id);
+                debug!(%asset, order_id = %o.id, limit_px = o.price.to_string(), oracle_px = px.to_string(), reason, "promoted NEW -> OPEN");
+            }
+            Err(e) => {
+                error!(%asset, order_id = %o.id, err = %e, "failed to promote NEW -> OPEN");
+                write_failed(repo, ctl, &o, "set_status", &e).await;
+            }
+        }
+    }
+    if throttled > 0 {
+        info!(%asset, oracle_ts = ts_ms, throttled, queued = ctl.throttle.queued(asset), max_per_sec = ?ctl.throttle.max_per_sec(), "fill throttle reached");
+    }
+    if bulk.orders > threshold {
+        if let Some(event) = bulk.event(asset, ts_ms) {
+            info!(%asset, oracle_ts = ts_ms, orders = bulk.orders, qty = %bulk.quantity, px_range = ?bulk.range, "BULK EXECUTE");
+            ctl.events.publish_market(event);
         }
     }
     (matched, promoted)
 }
 
-#[instrument(name = "matcher_worker", skip(repo, oracle), fields(%asset, tick_ms = %tick_every.as_millis()))]
+/// Readies a worker's indicator bank before its first tick: it replays the
+/// pair's kept ticks, then builds the series the active orders in `repo`
+/// read, so a fresh worker does not hold indicator conditions as
+/// warming up while the cache already has the prices. Returns how many
+/// active orders and ticks it found.
+async fn prime<R: OrderRepository + ?Sized>(
+    asset: &str,
+    repo: &R,
+    oracle: &OracleCache,
+    instruments: &InstrumentRegistry,
+    bank: &mut IndicatorBank,
+) -> (usize, usize) {
+    let scale = instruments
+        .get(asset)
+        .map_or(DEFAULT_SCALE, |i| i.price_scale);
+    let ticks = oracle
+        .history()
+        .before(asset, i64::MAX, reevaluate::WARMUP_TICKS)
+        .await;
+    for t in &ticks {
+        bank.observe(t.price.round_dp(scale), t.ts_ms);
+    }
+    let active = collect_active_orders(asset, repo).await;
+    bank.track(
+        &active
+            .iter()
+            .flat_map(|o| indicators::required(&o.condition))
+            .collect(),
+    );
+    (active.len(), ticks.len())
+}
+
+#[instrument(name = "matcher_worker", skip(repo, oracle, instruments, ctl, cfg), fields(%asset, tick_ms = %cfg.tick_every.as_millis()))]
 async fn run_worker<R: OrderRepository>(
-    asset: String,
+    mut asset: String,
     repo: R,
     oracle: OracleCache,
-    tick_every: Duration,
+    instruments: InstrumentRegistry,
+    ctl: Controls,
+    cfg: EngineConfig,
 ) {
-    let mut t = interval(tick_every);
+    let halts = &ctl.halts;
+    let policy = Arc::new(cfg.flagged_policy(&ctl.flags));
+    let repo = Resilient::new(repo, &asset, cfg.retry, ErrorBudget::new(cfg.error_budget));
+    let mut t = interval(cfg.tick_every);
     t.set_missed_tick_behavior(MissedTickBehavior::Delay);
     let mut ticks: u64 = 0;
+    let mut bank = IndicatorBank::default();
+    let mut volumes = VolumeBank::default();
+    let mut feed = MarketFeed::default();
+    let mut timers = SustainTimers::default();
+    let mut downtime = Downtime::new(cfg.downtime_gap.as_millis() as i64, crate::utils::now_ms());
+    // The last price and time of the latest tick read, to interpolate from.
+    let mut prev: Option<(Decimal, i64)> = None;
+    let (active, warm) = prime(&asset, &repo, &oracle, &instruments, &mut bank).await;
+    info!(%asset, active, ticks = warm, "matcher primed");
+    ctl.matchers.started(&asset);
     loop {
         t.tick().await;
         ticks += 1;
-        let Some((px, ts)) = oracle.get_price(&asset).await else {
+        // A renamed pair resolves to its new name; keep matching it there.
+        // One that resolves to nothing was delisted.
+        match instruments.normalize(&asset) {
+            Ok(current) if current != asset => {
+                info!(from = %asset, to = %current, "matcher follows pair rename");
+                feed.forget(&asset);
+                ctl.matchers.stopped(&asset);
+                ctl.matchers.started(&current);
+                asset = current;
+            }
+            Ok(_) => {}
+            Err(_) => {
+                info!(%asset, "pair delisted; matcher stopped");
+                ctl.matchers.stopped(&asset);
+                return;
+            }
+        }
+        feed.follow(&oracle, &asset).await;
+        feed.drain(&asset, &mut volumes);
+        let session = instruments.session_state(&asset, crate::utils::now_ms());
+        if session != SessionState::Open {
+            debug!(%asset, tick = ticks, ?session, "market not in session; skipping this tick");
+            downtime.touch(crate::utils::now_ms());
+            continue;
+        }
+        if oracle.dead_man().is_cancel_only() {
+            debug!(%asset, tick = ticks, "cancel-only: oracle lost; skipping this tick");
+            continue;
+        }
+        if repo.budget().is_paused(crate::utils::now_ms()) {
+            debug!(%asset, tick = ticks, "error budget exhausted; matcher paused");
+            continue;
+        }
+        let Some(tick) = feed.tick(&asset).cloned() else {
             debug!(%asset, tick = ticks, "no oracle price yet; skipping this tick");
             continue;
         };
+        let mut view = tick_view(&oracle, &instruments, &asset, &tick).await;
+        bank.observe(view.last, view.ts_ms);
+        let gap_ms = cfg.downtime_gap.as_millis() as i64;
+        let from = prev.filter(|&(_, ts)| {
+            cfg.interpolation == Interpolation::Linear && view.ts_ms - ts <= gap_ms
+        });
+        if prev.is_none_or(|(_, ts)| view.ts_ms > ts) {
+            prev = Some((view.last, view.ts_ms));
+        }
+        let blind_since = downtime.observe(crate::utils::now_ms());
+        if let Some(bps) = cfg.circuit_breaker_bps {
+            if !view.within_band(bps)
+                && halts.halt(
+                    &asset,
+                    HaltSource::CircuitBreaker,
+                    "last outside circuit breaker band",
+                    crate::utils::now_ms(),
+                )
+            {
+                warn!(%asset, oracle_px = view.last.to_string(), index_px = ?view.index, "circuit breaker halted pair");
+                ctl.events.publish_market(MarketEvent::PairHalted {
+                    pair: asset.clone(),
+                    source: HaltSource::CircuitBreaker,
+                    reason: "last outside circuit breaker band".into(),
+                });
+            }
+        }
+        let (released, withdrawn) = brackets::run(&asset, &repo).await;
+        if released + withdrawn > 0 {
+            debug!(%asset, tick = ticks, released, withdrawn, "bracket children settled");
+        }
         let active = collect_active_orders(&asset, &repo).await;
-        info!(%asset, tick = ticks, oracle_px = px.to_string(), oracle_ts = ts, active = active.len(), "tick");
+        info!(%asset, tick = ticks, oracle_px = view.last.to_string(), oracle_ts = view.ts_ms, active = active.len(), "tick");
         if active.is_empty() {
             debug!(%asset, tick = ticks, "no active orders");
             continue;
         }
-        let (matched, promoted) = process_active_orders(&asset, &repo, active, px, ts).await;
-        info!(%asset, tick = ticks, matched, promoted, "tick summary");
+        bank.track(
+            &active
+                .iter()
+                .flat_map(|o| indicators::required(&o.condition))
+                .collect(),
+        );
+        view.indicators = bank.readings();
+        volumes.track(
+            &active
+                .iter()
+                .filter_map(|o| volume::required(&o.condition))
+                .collect(),
+        );
+        view.volumes = volumes.volumes(view.ts_ms);
+        let mut followed: HashSet<Symbol> = active
+            .iter()
+            .flat_map(|o| spread::required(&o.condition))
+            .cloned()
+            .collect();
+        for pair in &followed {
+            feed.follow(&oracle, pair).await;
+        }
+        view.legs = spread::gather(&oracle, &feed, &followed, crate::utils::now_ms());
+        followed.insert(Symbol::intern(&asset));
+        feed.retain(&followed);
+        if let Some(bps) = cfg.fair_band_bps {
+            view.fair_band = fairband::gather(
+                &oracle,
+                &asset,
+                view.price_scale,
+                bps,
+                crate::utils::now_ms(),
+            )
+            .await;
+        }
+        let ids: HashSet<&str> = active.iter().map(|o| o.id.as_str()).collect();
+        timers.retain(&ids);
+        ctl.missed.retain(&asset, &ids);
+        ctl.throttle.retain(&asset, &ids);
+        if let Some(since) = blind_since {
+            let resting: Vec<&str> = active
+                .iter()
+                .filter(|o| o.created < since)
+                .map(|o| o.id.as_str())
+                .collect();
+            if !resting.is_empty() {
+                warn!(%asset, blind_since_ms = since, orders = resting.len(), policy = ?cfg.missed_trigger, "evaluating after downtime");
+                ctl.missed.mark(&asset, resting, cfg.missed_trigger, since);
+            }
+        }
+        let halted = halts.is_halted(&asset);
+        let mut active = active;
+        let crossed = if halted {
+            0
+        } else {
+            crossing::run(&asset, &repo, &ctl, &mut active, crate::utils::now_ms()).await
+        };
+        let (mut activated, mut matched, mut promoted, mut would) = (0, 0, 0, 0);
+        for (source, orders) in by_source(active) {
+            let view = match &source {
+                None => view.clone(),
+                Some(src) => match venue_view(&oracle, &view, &asset, src).await {
+                    Some(v) => v,
+                    None => {
+                        debug!(%asset, tick = ticks, source = %src, held = orders.len(), "no price from source yet");
+                        continue;
+                    }
+                },
+            };
+            if halted {
+                would += record_halted(&asset, halts, &orders, &view, &policy);
+                continue;
+            }
+            let steps = match (&source, from) {
+                (None, Some(from)) => interpolate::path(from, &view, &orders),
+                _ => Vec::new(),
+            };
+            let orders = if steps.is_empty() {
+                orders
+            } else {
+                let (orders, (a, m, p)) = interpolate::run(
+                    &asset,
+                    &repo,
+                    &ctl,
+                    &policy,
+                    &cfg,
+                    &mut timers,
+                    orders,
+                    &steps,
+                )
+                .await;
+                debug!(%asset, tick = ticks, steps = steps.len(), activated = a, matched = m, "interpolated between ticks");
+                (activated, matched, promoted) = (activated + a, matched + m, promoted + p);
+                orders
+            };
+            let (orders, a) = stops::run(&asset, &repo, &ctl, orders, &view).await;
+            activated += a;
+            let (m, p) = process_active_orders(
+                &asset,
+                &repo,
+                orders,
+                &view,
+                &policy,
+                &cfg,
+                &ctl,
+                &mut timers,
+            )
+            .await;
+            matched += m;
+            promoted += p;
+        }
+        if halted {
+            info!(%asset, tick = ticks, would_trigger = would, "pair halted; evaluation frozen");
+            continue;
+        }
+        info!(%asset, tick = ticks, crossed, activated, matched, promoted, "tick summary");
     }
 }
 
+/// Folds the prints on `asset`'s topic since the last tick into its volume
+/// windows, whether or not the pair is evaluated this tick. Ticks are read
+/// from the cache instead.
 fn crosses(o: &Order, oracle_px: Decimal) -> bool {
+    let (level, threshold) = match &o.condition {
+        Condition::Price
+        | Condition::Crossover { .. }
+        | Condition::Level { .. }
+        | Condition::Volume { .. }
+        | Condition::Spread { .. } => (oracle_px, o.price),
+        Condition::Notional {
+            threshold,
+            quantity,
+        } => (oracle_px * quantity.unwrap_or(o.quantity), *threshold),
+    };
     match o.side {
-        OrderSide::Buy => o.price >= oracle_px,
-        OrderSide::Sell => o.price <= oracle_px,
+        OrderSide::Buy => threshold >= level,
+        OrderSide::Sell => threshold <= level,
     }
 }
 
-fn log_exec(o: &Order, px: Decimal, ts_ms: i64) {
-    info!(
-        pair      = %o.pair,
-        side      = ?o.side,
-        order_id  = %o.id,
-        qty       = %o.quantity,
-        limit_px  = %o.price,
-        exec_px   = %px,
-        oracle_ts = ts_ms,
-        "EXECUTE"
-    );
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use rust_decimal_macros::dec;
-    use std::collections::{HashMap, HashSet};
-    use std::sync::Arc;
-    use tokio::sync::RwLock;
-
-    use crate::entities::order::{NewOrder, Order, OrderSide, OrderStatus};
-    use crate::repositories::{ListOrdersQuery, OrderRepository};
-    use crate::utils::now_ms;
+/// Orders each thread takes at least, so small books stay on the worker.
+const MIN_EVAL_CHUNK: usize = 512;
 
-    #[derive(Clone, Default)]
-    struct FakeRepo {
-        inner: Arc<RwLock<HashMap<String, Order>>>,
-        fail_list_on_status: Arc<RwLock<Option<OrderStatus>>>,
-        fail_set_for_ids: Arc<RwLock<HashSet<String>>>,
+/// `policy`'s decision for each of `orders` against the tick's `view`, in
+/// order, handed back with the orders. With `threads` above 1 and enough
+/// orders, chunks are evaluated on scoped threads and merged back in order
+/// before anything is written. The threads are joined on the blocking
+/// pool, so the runtime's thread keeps serving other tasks meanwhile.
+pub async fn evaluate_batch<P: MatchPolicy + Clone + 'static>(
+    orders: Vec<Order>,
+    view: &MarketView,
+    policy: &P,
+    threads: usize,
+) -> (Vec<Order>, Vec<Decision>) {
+    let threads = threads.min(orders.len() / MIN_EVAL_CHUNK).max(1);
+    if threads == 1 {
+        let decisions = orders.iter().map(|o| policy.evaluate(o, view)).collect();
+        return (orders, decisions);
     }
-
-    impl FakeRepo {
-        async fn set_fail_list_on(&self, s: Option<OrderStatus>) {
-            *self.fail_list_on_status.write().await = s;
-        }
-        async fn fail_set_for(&self, id: &str) {
-            self.fail_set_for_ids.write().await.insert(id.to_string());
-        }
+    let (view, policy) = (view.clone(), policy.clone());
+    let batch = tokio::task::spawn_blocking(move || {
+        let decisions = evaluate_in_threads(&orders, &view, &policy, threads);
+        (orders, decisions)
+    });
+    match batch.await {
+        Ok(done) => done,
+        Err(e) => std::panic::resume_unwind(e.into_panic()),
     }
+}
 
-    #[async_trait::async_trait]
-    impl OrderRepository for FakeRepo {
-        async fn list(&self, q: ListOrdersQuery) -> Result<Vec<Order>, String> {
-            if let Some(status) = &q.status {
-                if Some(status.clone()) == *self.fail_list_on_status.read().await {
-                    return Err(format!("boom listing {:?}", status));
-                }
-            }
-            let map = self.inner.read().await;
-            let mut v: Vec<Order> = map
-                .values()
-                .cloned()
-                .filter(|o| {
-                    q.pair.as_ref().map_or(true, |p| &o.pair == p)
-                        && q.status.as_ref().map_or(true, |s| &o.status == s)
+fn evaluate_in_threads(
+    orders: &[Order],
+    view: &MarketView,
+    policy: &dyn MatchPolicy,
+    threads: usize,
+) -> Vec<Decision> {
+    let chunk = orders.len().div_ceil(threads);
+    std::thread::scope(|s| {
+        let parts: Vec<_> = orders
+            .chunks(chunk)
+            .map(|c| {
+                s.spawn(move || {
+                    c.iter()
+                        .map(|o| policy.evaluate(o, view))
+                        .collect::<Vec<_>>()
                 })
-                .collect();
-            if let Some(off) = q.offset {
-                if off < v.len() as i64 {
-                    v.drain(0..off as usize);
-                } else {
-                    v.clear();
-                }
-            }
-            if let Some(lim) = q.limit {
-                if v.len() > lim as usize {
-                    v.truncate(lim as usize);
-                }
-            }
-            Ok(v)
-        }
+            })
+            .collect();
+        parts
+            .into_iter()
+            .flat_map(|h| h.join().unwrap_or_else(|e| std::panic::resume_unwind(e)))
+            .collect()
+    })
+}
 
-        async fn set_status(&self, id: &str, to: OrderStatus) -> Result<Order, String> {
-            if self.fail_set_for_ids.read().await.contains(id) {
-                return Err("boom set_status".into());
-            }
-            let mut map = self.inner.write().await;
-            let o = map.get_mut(id).ok_or_else(|| "not found".to_string())?;
-            o.status = to;
-            o.updated = now_ms();
-            Ok(o.clone())
-        }
+pub const DEFAULT_BULK_FILL_THRESHOLD: usize = 100;
 
-        async fn create(&self, n: NewOrder) -> Result<Order, String> {
-            let id = uuid::Uuid::new_v4().to_string();
-            let o = Order {
-                id,
-                pair: n.pair,
-                side: n.side,
-                price: n.price,
-                quantity: n.quantity,
-                status: OrderStatus::New,
-                created: now_ms(),
-                updated: now_ms(),
-            };
-            let mut map = self.inner.write().await;
-            map.insert(o.id.clone(), o.clone());
-            Ok(o)
-        }
+/// The fills of one tick's orders, summed up.
+#[derive(Debug, Default)]
+struct BulkFill {
+    orders: usize,
+    quantity: Decimal,
+    notional: Decimal,
+    range: Option<(Decimal, Decimal)>,
+}
 
-        async fn get_by_id(&self, id: &str) -> Result<Order, String> {
-            let map = self.inner.read().await;
-            map.get(id).cloned().ok_or_else(|| "not found".to_string())
+impl BulkFill {
+    fn add(&mut self, fills: &[Fill]) {
+        self.orders += 1;
+        for f in fills {
+            self.quantity += f.quantity;
+            self.notional += f.price * f.quantity;
+            let (lo, hi) = self.range.get_or_insert((f.price, f.price));
+            *lo = (*lo).min(f.price);
+            *hi = (*hi).max(f.price);
         }
+    }
 
-        async fn delete(&self, id: &str) -> Result<(), String> {
-            let mut map = self.inner.write().await;
-            match map.remove(id) {
-                Some(_) => Ok(()),
-                None => Err("not found".to_string()),
-            }
+    fn event(&self, pair: &str, oracle_ts: i64) -> Option<MarketEvent> {
+        let (min_px, max_px) = self.range?;
+        Some(MarketEvent::BulkFill {
+            pair: pair.to_string(),
+            oracle_ts,
+            orders: self.orders,
+            quantity: self.quantity,
+            avg_px: (self.notional / self.quantity).normalize(),
+            min_px,
+            max_px,
+        })
+    }
+}
+
+fn log_exec(o: &Order, ref_px: Decimal, fills: &[Fill], ts_ms: i64, quiet: bool) {
+    for f in fills {
+        if quiet {
+            debug!(pair = %o.pair, order_id = %o.id, qty = %f.quantity, exec_px = %f.price, oracle_ts = ts_ms, "EXECUTE");
+            continue;
         }
+        info!(
+            pair = %o.pair,
+            side = ?o.side,
+            order_id = %o.id,
+            qty = %f.quantity,
+            status = ?o.status,
+            limit_px = %o.price,
+            trigger_type = ?o.trigger_price_type,
+            ref_px = %ref_px,
+            exec_px = %f.price,
+            avg_px = ?o.exec_price,
+            exec_pricing = ?o.exec_pricing,
+            oracle_ts = ts_ms,
+            e2e_ms = ?f.end_to_end_ms(),
+            "EXECUTE"
+        );
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::policy::PartialFillPolicy;
+    use super::*;
+    use rust_decimal_macros::dec;
+    use std::collections::HashSet;
+
+    use crate::entities::order::{
+        ExecPricing, LegPrice, Order, OrderSide, OrderStatus, OrderType, SpreadMeasure,
+        TimeInForce, CANCEL_FOK_UNFILLABLE,
+    };
+    use crate::entities::test_support::{FakeRepo, OrderBuilder};
+    use crate::instruments::Instrument;
+    use crate::oracle_service::Tick;
+    use crate::repositories::OrderRepository;
+    use crate::utils::now_ms;
 
     fn mk_order(
         id: &str,
@@ -242,23 +1108,15 @@ mod tests {
         qty: &str,
         status: OrderStatus,
     ) -> Order {
-        Order {
-            id: id.to_string(),
-            pair: pair.to_string(),
-            side,
-            price: Decimal::from_str_exact(price).unwrap(),
-            quantity: Decimal::from_str_exact(qty).unwrap(),
-            status,
-            created: now_ms(),
-            updated: now_ms(),
-        }
-    }
-
-    async fn seed(repo: &FakeRepo, orders: Vec<Order>) {
-        let mut w = repo.inner.write().await;
-        for o in orders {
-            w.insert(o.id.clone(), o);
-        }
+        let b = match side {
+            OrderSide::Buy => OrderBuilder::buy(pair),
+            OrderSide::Sell => OrderBuilder::sell(pair),
+        };
+        b.id(id)
+            .limit(Decimal::from_str_exact(price).unwrap())
+            .qty(Decimal::from_str_exact(qty).unwrap())
+            .status(status)
+            .build()
     }
 
     #[test]
@@ -294,51 +1152,48 @@ mod tests {
     #[tokio::test]
     async fn collect_active_gathers_new_open_partial_for_asset() {
         let repo = FakeRepo::default();
-        seed(
-            &repo,
-            vec![
-                mk_order(
-                    "n1",
-                    "BTC/USDT",
-                    OrderSide::Buy,
-                    "100",
-                    "1",
-                    OrderStatus::New,
-                ),
-                mk_order(
-                    "o1",
-                    "BTC/USDT",
-                    OrderSide::Buy,
-                    "100",
-                    "1",
-                    OrderStatus::Open,
-                ),
-                mk_order(
-                    "p1",
-                    "BTC/USDT",
-                    OrderSide::Buy,
-                    "100",
-                    "1",
-                    OrderStatus::PartiallyFilled,
-                ),
-                mk_order(
-                    "x1",
-                    "BTC/USDT",
-                    OrderSide::Buy,
-                    "100",
-                    "1",
-                    OrderStatus::Filled,
-                ),
-                mk_order(
-                    "e1",
-                    "ETH/USDT",
-                    OrderSide::Buy,
-                    "100",
-                    "1",
-                    OrderStatus::New,
-                ),
-            ],
-        )
+        repo.seed(vec![
+            mk_order(
+                "n1",
+                "BTC/USDT",
+                OrderSide::Buy,
+                "100",
+                "1",
+                OrderStatus::New,
+            ),
+            mk_order(
+                "o1",
+                "BTC/USDT",
+                OrderSide::Buy,
+                "100",
+                "1",
+                OrderStatus::Open,
+            ),
+            mk_order(
+                "p1",
+                "BTC/USDT",
+                OrderSide::Buy,
+                "100",
+                "1",
+                OrderStatus::PartiallyFilled,
+            ),
+            mk_order(
+                "x1",
+                "BTC/USDT",
+                OrderSide::Buy,
+                "100",
+                "1",
+                OrderStatus::Filled,
+            ),
+            mk_order(
+                "e1",
+                "ETH/USDT",
+                OrderSide::Buy,
+                "100",
+                "1",
+                OrderStatus::New,
+            ),
+        ])
         .await;
         let v = super::collect_active_orders("BTC/USDT", &repo).await;
         let ids: HashSet<_> = v.into_iter().map(|o| o.id).collect();
@@ -351,35 +1206,32 @@ mod tests {
     #[tokio::test]
     async fn collect_active_skips_on_list_error_but_keeps_others() {
         let repo = FakeRepo::default();
-        seed(
-            &repo,
-            vec![
-                mk_order(
-                    "n1",
-                    "BTC/USDT",
-                    OrderSide::Buy,
-                    "100",
-                    "1",
-                    OrderStatus::New,
-                ),
-                mk_order(
-                    "o1",
-                    "BTC/USDT",
-                    OrderSide::Buy,
-                    "100",
-                    "1",
-                    OrderStatus::Open,
-                ),
-                mk_order(
-                    "p1",
-                    "BTC/USDT",
-                    OrderSide::Buy,
-                    "100",
-                    "1",
-                    OrderStatus::PartiallyFilled,
-                ),
-            ],
-        )
+        repo.seed(vec![
+            mk_order(
+                "n1",
+                "BTC/USDT",
+                OrderSide::Buy,
+                "100",
+                "1",
+                OrderStatus::New,
+            ),
+            mk_order(
+                "o1",
+                "BTC/USDT",
+                OrderSide::Buy,
+                "100",
+                "1",
+                OrderStatus::Open,
+            ),
+            mk_order(
+                "p1",
+                "BTC/USDT",
+                OrderSide::Buy,
+                "100",
+                "1",
+                OrderStatus::PartiallyFilled,
+            ),
+        ])
         .await;
         repo.set_fail_list_on(Some(OrderStatus::Open)).await;
         let v = super::collect_active_orders("BTC/USDT", &repo).await;
@@ -390,24 +1242,24 @@ mod tests {
     #[tokio::test]
     async fn promotes_new_to_open_when_not_crossing() {
         let repo = FakeRepo::default();
-        seed(
-            &repo,
-            vec![mk_order(
-                "o1",
-                "BTC/USDT",
-                OrderSide::Buy,
-                "100.0",
-                "1",
-                OrderStatus::New,
-            )],
-        )
+        repo.seed(vec![mk_order(
+            "o1",
+            "BTC/USDT",
+            OrderSide::Buy,
+            "100.0",
+            "1",
+            OrderStatus::New,
+        )])
         .await;
         let (matched, promoted) = super::process_active_orders(
             "BTC/USDT",
             &repo,
             vec![repo.get_by_id("o1").await.unwrap()],
-            dec!(101.0),
-            1_700_000_000_000,
+            &MarketView::from_last(dec!(101.0), 1_700_000_000_000),
+            &OraclePolicy::default(),
+            &EngineConfig::default(),
+            &Controls::default(),
+            &mut SustainTimers::default(),
         )
         .await;
         assert_eq!(matched, 0);
@@ -419,46 +1271,156 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn executes_when_crossing_buy_for_all_statuses() {
-        let repo = FakeRepo::default();
-        seed(
+    async fn filling_one_oco_leg_cancels_the_other_in_the_same_pass() {
+        use crate::entities::order::{NewOrder, CANCEL_OCO};
+        use crate::entities::test_support::OrderBuilder;
+        use crate::repositories::in_memory::InMemoryOrderRepository;
+
+        let repo = InMemoryOrderRepository::default();
+        let mut orders = Vec::new();
+        // Both legs would fill at 100; only the first does.
+        for b in [
+            OrderBuilder::buy("BTC/USDT").limit(100).qty(1),
+            OrderBuilder::sell("BTC/USDT").limit(95).qty(1),
+        ] {
+            let new = NewOrder {
+                oco_id: Some("pair-1".into()),
+                ..b.new_order()
+            };
+            orders.push(repo.create(new).await.unwrap());
+        }
+        let (buy, sell) = (orders[0].id.clone(), orders[1].id.clone());
+        // One failed write would suspend the cancelled leg.
+        let ctl = Controls {
+            dead_letters: DeadLetters::with_max_failures(1),
+            ..Controls::default()
+        };
+        let (matched, _) = super::process_active_orders(
+            "BTC/USDT",
             &repo,
-            vec![
-                mk_order(
-                    "n",
-                    "BTC/USDT",
-                    OrderSide::Buy,
-                    "100",
-                    "1",
-                    OrderStatus::New,
-                ),
-                mk_order(
-                    "o",
-                    "BTC/USDT",
-                    OrderSide::Buy,
-                    "100",
-                    "1",
-                    OrderStatus::Open,
-                ),
-                mk_order(
-                    "p",
-                    "BTC/USDT",
-                    OrderSide::Buy,
-                    "100",
-                    "1",
-                    OrderStatus::PartiallyFilled,
-                ),
-            ],
+            orders,
+            &MarketView::from_last(dec!(100), 1_700_000_000_000),
+            &OraclePolicy::default(),
+            &EngineConfig::default(),
+            &ctl,
+            &mut SustainTimers::default(),
         )
         .await;
+        assert_eq!(matched, 1);
+        assert_eq!(
+            repo.get_by_id(&buy).await.unwrap().status,
+            OrderStatus::Filled
+        );
+        let s = repo.get_by_id(&sell).await.unwrap();
+        assert_eq!(
+            (s.status, s.cancel_reason.as_deref()),
+            (OrderStatus::Cancelled, Some(CANCEL_OCO))
+        );
+        assert!(ctl.dead_letters.list().is_empty());
+        // A leg no longer active cannot be filled after the fact.
+        assert!(repo.fill_oco(&sell, &[]).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn throttled_triggers_fill_first_once_the_second_has_passed() {
+        use crate::entities::test_support::OrderBuilder;
+        use crate::repositories::in_memory::InMemoryOrderRepository;
+
+        let repo = InMemoryOrderRepository::default();
+        let mut ids = Vec::new();
+        for _ in 0..4 {
+            let new = OrderBuilder::buy("BTC/USDT").limit(100).qty(1).new_order();
+            ids.push(repo.create(new).await.unwrap().id);
+        }
+        let ctl = Controls {
+            throttle: FillThrottle::new(Some(1)),
+            ..Controls::default()
+        };
+        let pass = |order: [usize; 3]| {
+            let (repo, ctl, ids) = (&repo, &ctl, &ids);
+            async move {
+                let mut orders = Vec::new();
+                for i in order {
+                    orders.push(repo.get_by_id(&ids[i]).await.unwrap());
+                }
+                let view = MarketView::from_last(dec!(100), crate::utils::now_ms());
+                let policy = OraclePolicy::default();
+                let mut timers = SustainTimers::default();
+                process_active_orders(
+                    "BTC/USDT",
+                    repo,
+                    orders,
+                    &view,
+                    &policy,
+                    &EngineConfig::default(),
+                    ctl,
+                    &mut timers,
+                )
+                .await
+                .0
+            }
+        };
+        let status = |i: usize| {
+            let (repo, ids) = (&repo, &ids);
+            async move { repo.get_by_id(&ids[i]).await.unwrap().status }
+        };
+        assert_eq!(pass([0, 1, 2]).await, 1);
+        assert_eq!(ctl.throttle.queued("BTC/USDT"), 2);
+        tokio::time::sleep(Duration::from_millis(1_001)).await;
+        // The queue goes ahead of the fresh trigger listed first.
+        assert_eq!(pass([3, 2, 1]).await, 1);
+        assert_eq!(status(1).await, OrderStatus::Filled);
+        assert_eq!(status(2).await, OrderStatus::New);
+        assert_eq!(status(3).await, OrderStatus::New);
+        assert_eq!(ctl.throttle.queued("BTC/USDT"), 2);
+    }
+
+    #[tokio::test]
+    async fn executes_when_crossing_buy_for_all_statuses() {
+        let repo = FakeRepo::default();
+        repo.seed(vec![
+            mk_order(
+                "n",
+                "BTC/USDT",
+                OrderSide::Buy,
+                "100",
+                "1",
+                OrderStatus::New,
+            ),
+            mk_order(
+                "o",
+                "BTC/USDT",
+                OrderSide::Buy,
+                "100",
+                "1",
+                OrderStatus::Open,
+            ),
+            mk_order(
+                "p",
+                "BTC/USDT",
+                OrderSide::Buy,
+                "100",
+                "1",
+                OrderStatus::PartiallyFilled,
+            ),
+        ])
+        .await;
         let orders = vec![
             repo.get_by_id("n").await.unwrap(),
             repo.get_by_id("o").await.unwrap(),
             repo.get_by_id("p").await.unwrap(),
         ];
-        let (matched, promoted) =
-            super::process_active_orders("BTC/USDT", &repo, orders, dec!(100.0), 1_700_000_000_000)
-                .await;
+        let (matched, promoted) = super::process_active_orders(
+            "BTC/USDT",
+            &repo,
+            orders,
+            &MarketView::from_last(dec!(100.0), 1_700_000_000_000),
+            &OraclePolicy::default(),
+            &EngineConfig::default(),
+            &Controls::default(),
+            &mut SustainTimers::default(),
+        )
+        .await;
         assert_eq!(matched, 3);
         assert_eq!(promoted, 0);
         for id in ["n", "o", "p"] {
@@ -472,24 +1434,24 @@ mod tests {
     #[tokio::test]
     async fn leaves_open_unchanged_when_not_crossing() {
         let repo = FakeRepo::default();
-        seed(
-            &repo,
-            vec![mk_order(
-                "o1",
-                "BTC/USDT",
-                OrderSide::Buy,
-                "100",
-                "1",
-                OrderStatus::Open,
-            )],
-        )
+        repo.seed(vec![mk_order(
+            "o1",
+            "BTC/USDT",
+            OrderSide::Buy,
+            "100",
+            "1",
+            OrderStatus::Open,
+        )])
         .await;
         let (matched, promoted) = super::process_active_orders(
             "BTC/USDT",
             &repo,
             vec![repo.get_by_id("o1").await.unwrap()],
-            dec!(101.0),
-            1_700_000_000_000,
+            &MarketView::from_last(dec!(101.0), 1_700_000_000_000),
+            &OraclePolicy::default(),
+            &EngineConfig::default(),
+            &Controls::default(),
+            &mut SustainTimers::default(),
         )
         .await;
         assert_eq!(matched, 0);
@@ -503,35 +1465,40 @@ mod tests {
     #[tokio::test]
     async fn respects_sell_crossing_direction() {
         let repo = FakeRepo::default();
-        seed(
-            &repo,
-            vec![
-                mk_order(
-                    "s1",
-                    "BTC/USDT",
-                    OrderSide::Sell,
-                    "100",
-                    "1",
-                    OrderStatus::New,
-                ),
-                mk_order(
-                    "s2",
-                    "BTC/USDT",
-                    OrderSide::Sell,
-                    "100",
-                    "1",
-                    OrderStatus::Open,
-                ),
-            ],
-        )
+        repo.seed(vec![
+            mk_order(
+                "s1",
+                "BTC/USDT",
+                OrderSide::Sell,
+                "100",
+                "1",
+                OrderStatus::New,
+            ),
+            mk_order(
+                "s2",
+                "BTC/USDT",
+                OrderSide::Sell,
+                "100",
+                "1",
+                OrderStatus::Open,
+            ),
+        ])
         .await;
         let orders = vec![
             repo.get_by_id("s1").await.unwrap(),
             repo.get_by_id("s2").await.unwrap(),
         ];
-        let (matched, promoted) =
-            super::process_active_orders("BTC/USDT", &repo, orders, dec!(100.5), 1_700_000_000_000)
-                .await;
+        let (matched, promoted) = super::process_active_orders(
+            "BTC/USDT",
+            &repo,
+            orders,
+            &MarketView::from_last(dec!(100.5), 1_700_000_000_000),
+            &OraclePolicy::default(),
+            &EngineConfig::default(),
+            &Controls::default(),
+            &mut SustainTimers::default(),
+        )
+        .await;
         assert_eq!(matched, 2);
         assert_eq!(promoted, 0);
         for id in ["s1", "s2"] {
@@ -545,36 +1512,41 @@ mod tests {
     #[tokio::test]
     async fn set_status_error_does_not_increment_counters() {
         let repo = FakeRepo::default();
-        seed(
-            &repo,
-            vec![
-                mk_order(
-                    "ok",
-                    "BTC/USDT",
-                    OrderSide::Buy,
-                    "100",
-                    "1",
-                    OrderStatus::Open,
-                ),
-                mk_order(
-                    "bad",
-                    "BTC/USDT",
-                    OrderSide::Buy,
-                    "100",
-                    "1",
-                    OrderStatus::Open,
-                ),
-            ],
-        )
+        repo.seed(vec![
+            mk_order(
+                "ok",
+                "BTC/USDT",
+                OrderSide::Buy,
+                "100",
+                "1",
+                OrderStatus::Open,
+            ),
+            mk_order(
+                "bad",
+                "BTC/USDT",
+                OrderSide::Buy,
+                "100",
+                "1",
+                OrderStatus::Open,
+            ),
+        ])
         .await;
         repo.fail_set_for("bad").await;
         let orders = vec![
             repo.get_by_id("ok").await.unwrap(),
             repo.get_by_id("bad").await.unwrap(),
         ];
-        let (matched, promoted) =
-            super::process_active_orders("BTC/USDT", &repo, orders, dec!(100.0), 1_700_000_000_000)
-                .await;
+        let (matched, promoted) = super::process_active_orders(
+            "BTC/USDT",
+            &repo,
+            orders,
+            &MarketView::from_last(dec!(100.0), 1_700_000_000_000),
+            &OraclePolicy::default(),
+            &EngineConfig::default(),
+            &Controls::default(),
+            &mut SustainTimers::default(),
+        )
+        .await;
         assert_eq!(matched, 1);
         assert_eq!(promoted, 0);
         assert_eq!(
@@ -590,25 +1562,1313 @@ mod tests {
     #[tokio::test]
     async fn promotion_error_does_not_increment_promoted() {
         let repo = FakeRepo::default();
-        seed(
-            &repo,
-            vec![mk_order(
-                "n",
-                "BTC/USDT",
-                OrderSide::Buy,
-                "100",
-                "1",
-                OrderStatus::New,
-            )],
-        )
+        repo.seed(vec![mk_order(
+            "n",
+            "BTC/USDT",
+            OrderSide::Buy,
+            "100",
+            "1",
+            OrderStatus::New,
+        )])
         .await;
         repo.fail_set_for("n").await;
         let orders = vec![repo.get_by_id("n").await.unwrap()];
-        let (matched, promoted) =
-            super::process_active_orders("BTC/USDT", &repo, orders, dec!(101.0), 1_700_000_000_000)
-                .await;
+        let (matched, promoted) = super::process_active_orders(
+            "BTC/USDT",
+            &repo,
+            orders,
+            &MarketView::from_last(dec!(101.0), 1_700_000_000_000),
+            &OraclePolicy::default(),
+            &EngineConfig::default(),
+            &Controls::default(),
+            &mut SustainTimers::default(),
+        )
+        .await;
         assert_eq!(matched, 0);
         assert_eq!(promoted, 0);
         assert_eq!(repo.get_by_id("n").await.unwrap().status, OrderStatus::New);
     }
+
+    #[tokio::test]
+    async fn repeated_write_failures_dead_letter_the_order() {
+        let repo = FakeRepo::default();
+        repo.seed(vec![mk_order(
+            "bad",
+            "BTC/USDT",
+            OrderSide::Buy,
+            "100",
+            "1",
+            OrderStatus::Open,
+        )])
+        .await;
+        repo.fail_set_for("bad").await;
+        let ctl = Controls {
+            dead_letters: DeadLetters::with_max_failures(3),
+            ..Controls::default()
+        };
+        let view = MarketView::from_last(dec!(99), 1_700_000_000_000);
+        for _ in 0..3 {
+            assert!(!ctl.dead_letters.is_suspended("bad"));
+            let orders = vec![repo.get_by_id("bad").await.unwrap()];
+            super::process_active_orders(
+                "BTC/USDT",
+                &repo,
+                orders,
+                &view,
+                &OraclePolicy::default(),
+                &EngineConfig::default(),
+                &ctl,
+                &mut SustainTimers::default(),
+            )
+            .await;
+        }
+        assert!(ctl.dead_letters.is_suspended("bad"));
+        let list = ctl.dead_letters.list();
+        assert_eq!(list.len(), 1);
+        assert_eq!(list[0].operation, "record_fills");
+        assert_eq!(list[0].failures, 3);
+        assert_eq!(list[0].last_error, "boom set_status");
+
+        repo.clear_set_failures().await;
+        let orders = vec![repo.get_by_id("bad").await.unwrap()];
+        super::process_active_orders(
+            "BTC/USDT",
+            &repo,
+            orders,
+            &view,
+            &OraclePolicy::default(),
+            &EngineConfig::default(),
+            &ctl,
+            &mut SustainTimers::default(),
+        )
+        .await;
+        assert_eq!(
+            repo.get_by_id("bad").await.unwrap().status,
+            OrderStatus::Open
+        );
+    }
+
+    #[tokio::test]
+    async fn index_triggered_order_uses_index_and_holds_without_it() {
+        let repo = FakeRepo::default();
+        let mut o = mk_order(
+            "i1",
+            "BTC/USDT",
+            OrderSide::Buy,
+            "100",
+            "1",
+            OrderStatus::Open,
+        );
+        o.trigger_price_type = TriggerPriceType::Index;
+        repo.seed(vec![o.clone()]).await;
+
+        let (matched, _) = super::process_active_orders(
+            "BTC/USDT",
+            &repo,
+            vec![o.clone()],
+            &MarketView::from_last(dec!(99.0), 1_700_000_000_000),
+            &OraclePolicy::default(),
+            &EngineConfig::default(),
+            &Controls::default(),
+            &mut SustainTimers::default(),
+        )
+        .await;
+        assert_eq!(matched, 0);
+
+        let view = MarketView {
+            index: Some(dec!(100.5)),
+            mark: Some(dec!(99.5)),
+            ..MarketView::from_last(dec!(99.0), 1_700_000_000_000)
+        };
+        let (matched, _) = super::process_active_orders(
+            "BTC/USDT",
+            &repo,
+            vec![o.clone()],
+            &view,
+            &OraclePolicy::default(),
+            &EngineConfig::default(),
+            &Controls::default(),
+            &mut SustainTimers::default(),
+        )
+        .await;
+        assert_eq!(matched, 0);
+
+        let view = MarketView {
+            index: Some(dec!(99.9)),
+            ..view
+        };
+        let (matched, _) = super::process_active_orders(
+            "BTC/USDT",
+            &repo,
+            vec![o],
+            &view,
+            &OraclePolicy::default(),
+            &EngineConfig::default(),
+            &Controls::default(),
+            &mut SustainTimers::default(),
+        )
+        .await;
+        assert_eq!(matched, 1);
+        assert_eq!(
+            repo.get_by_id("i1").await.unwrap().status,
+            OrderStatus::Filled
+        );
+    }
+
+    #[tokio::test]
+    async fn bid_and_ask_triggers_compare_against_quote_sides() {
+        let repo = FakeRepo::default();
+        let mut sell = mk_order(
+            "s",
+            "BTC/USDT",
+            OrderSide::Sell,
+            "100",
+            "1",
+            OrderStatus::Open,
+        );
+        sell.trigger_price_type = TriggerPriceType::Bid;
+        let mut buy = mk_order(
+            "b",
+            "BTC/USDT",
+            OrderSide::Buy,
+            "100",
+            "1",
+            OrderStatus::Open,
+        );
+        buy.trigger_price_type = TriggerPriceType::Ask;
+        repo.seed(vec![sell.clone(), buy.clone()]).await;
+
+        let view = MarketView {
+            bid: Some(dec!(99.9)),
+            ask: Some(dec!(100.1)),
+            ..MarketView::from_last(dec!(100.0), 1_700_000_000_000)
+        };
+        let (matched, _) = super::process_active_orders(
+            "BTC/USDT",
+            &repo,
+            vec![sell, buy],
+            &view,
+            &OraclePolicy::default(),
+            &EngineConfig::default(),
+            &Controls::default(),
+            &mut SustainTimers::default(),
+        )
+        .await;
+        assert_eq!(matched, 0);
+        assert_eq!(repo.get_by_id("s").await.unwrap().status, OrderStatus::Open);
+        assert_eq!(repo.get_by_id("b").await.unwrap().status, OrderStatus::Open);
+    }
+
+    #[tokio::test]
+    async fn market_orders_fill_at_next_fresh_tick_within_band() {
+        let repo = FakeRepo::default();
+        let mut o = mk_order("m", "BTC/USDT", OrderSide::Buy, "0", "1", OrderStatus::New);
+        o.order_type = OrderType::Market;
+        repo.seed(vec![o.clone()]).await;
+        let policy = OraclePolicy::default();
+
+        let before = MarketView::from_last(dec!(100), o.created - 1);
+        assert_eq!(
+            policy.evaluate(&o, &before),
+            Decision::Promote("waiting for a fresh tick")
+        );
+
+        let stale = MarketView {
+            stale: true,
+            ..MarketView::from_last(dec!(100), o.created + 1)
+        };
+        assert!(policy.evaluate(&o, &stale).is_waiting());
+
+        let gapped = MarketView {
+            index: Some(dec!(90)),
+            ..MarketView::from_last(dec!(100), o.created + 1)
+        };
+        assert!(policy.evaluate(&o, &gapped).is_waiting());
+
+        let fresh = MarketView {
+            index: Some(dec!(100.5)),
+            ..MarketView::from_last(dec!(100), o.created + 1)
+        };
+        let (matched, _) = super::process_active_orders(
+            "BTC/USDT",
+            &repo,
+            vec![o],
+            &fresh,
+            &policy,
+            &EngineConfig::default(),
+            &Controls::default(),
+            &mut SustainTimers::default(),
+        )
+        .await;
+        assert_eq!(matched, 1);
+        let filled = repo.get_by_id("m").await.unwrap();
+        assert_eq!(filled.status, OrderStatus::Filled);
+        assert_eq!(filled.exec_price, Some(dec!(100)));
+    }
+
+    #[tokio::test]
+    async fn fok_fills_at_sweep_vwap_or_is_cancelled() {
+        let repo = FakeRepo::default();
+        let mut ok = mk_order(
+            "ok",
+            "BTC/USDT",
+            OrderSide::Buy,
+            "100",
+            "2",
+            OrderStatus::Open,
+        );
+        ok.time_in_force = TimeInForce::Fok;
+        let mut big = mk_order(
+            "big",
+            "BTC/USDT",
+            OrderSide::Buy,
+            "100",
+            "5",
+            OrderStatus::Open,
+        );
+        big.time_in_force = TimeInForce::Fok;
+        repo.seed(vec![ok.clone(), big.clone()]).await;
+
+        let view = MarketView {
+            liquidity: LiquidityCurve::parse("10:1,20:2"),
+            ..MarketView::from_last(dec!(100), 1_700_000_000_000)
+        };
+        let (matched, _) = super::process_active_orders(
+            "BTC/USDT",
+            &repo,
+            vec![ok, big],
+            &view,
+            &OraclePolicy::default(),
+            &EngineConfig::default(),
+            &Controls::default(),
+            &mut SustainTimers::default(),
+        )
+        .await;
+        assert_eq!(matched, 1);
+
+        let ok = repo.get_by_id("ok").await.unwrap();
+        assert_eq!(ok.status, OrderStatus::Filled);
+        assert_eq!(ok.exec_price, Some(dec!(100.15)));
+
+        let big = repo.get_by_id("big").await.unwrap();
+        assert_eq!(big.status, OrderStatus::Cancelled);
+        assert_eq!(big.cancel_reason.as_deref(), Some(CANCEL_FOK_UNFILLABLE));
+    }
+
+    #[test]
+    fn fok_respects_per_order_slippage_bound() {
+        let mut o = mk_order(
+            "f",
+            "BTC/USDT",
+            OrderSide::Sell,
+            "100",
+            "2",
+            OrderStatus::Open,
+        );
+        o.time_in_force = TimeInForce::Fok;
+        o.max_slippage_bps = Some(dec!(15));
+        let view = MarketView {
+            liquidity: LiquidityCurve::parse("10:1,20:2"),
+            ..MarketView::from_last(dec!(100), 1_700_000_000_000)
+        };
+        assert_eq!(
+            OraclePolicy::default().evaluate(&o, &view),
+            Decision::Cancel(CANCEL_FOK_UNFILLABLE)
+        );
+    }
+
+    #[tokio::test]
+    async fn halted_pair_records_would_trigger_and_resumes_by_policy() {
+        let repo = FakeRepo::default();
+        let now = mk_order(
+            "now",
+            "BTC/USDT",
+            OrderSide::Buy,
+            "100",
+            "1",
+            OrderStatus::Open,
+        );
+        let fresh = mk_order(
+            "fresh",
+            "BTC/USDT",
+            OrderSide::Buy,
+            "100",
+            "1",
+            OrderStatus::Open,
+        );
+        repo.seed(vec![now.clone(), fresh.clone()]).await;
+        let policy = OraclePolicy::default();
+        let ctl = Controls::default();
+        let halts = &ctl.halts;
+        halts.halt("BTC/USDT", HaltSource::Admin, "test", 0);
+
+        let crossing = MarketView::from_last(dec!(99), 1_700_000_000_000);
+        let orders = vec![now.clone(), fresh.clone()];
+        assert_eq!(
+            record_halted("BTC/USDT", halts, &orders, &crossing, &policy),
+            2
+        );
+        assert_eq!(halts.stats().halted[0].would_trigger.len(), 2);
+
+        // Park both for a fresh crossing, then re-halt to switch `now` to immediate.
+        halts.resume("BTC/USDT", halts::ResumePolicy::RequireFreshCrossing, 1);
+        halts.halt("BTC/USDT", HaltSource::Admin, "test", 2);
+        halts.record_would_trigger("BTC/USDT", "now", dec!(99), 3);
+        halts.resume("BTC/USDT", halts::ResumePolicy::TriggerImmediately, 4);
+
+        // Price moved away: the immediate order fills anyway, the other holds.
+        let away = MarketView::from_last(dec!(101), 1_700_000_000_001);
+        let (matched, _) = super::process_active_orders(
+            "BTC/USDT",
+            &repo,
+            orders,
+            &away,
+            &policy,
+            &EngineConfig::default(),
+            &ctl,
+            &mut SustainTimers::default(),
+        )
+        .await;
+        assert_eq!(matched, 1);
+        assert_eq!(
+            repo.get_by_id("now").await.unwrap().exec_price,
+            Some(dec!(101))
+        );
+        assert_eq!(halts.pending("fresh"), None);
+
+        // `fresh` saw a non-crossing tick, so the next crossing fills it.
+        let (matched, _) = super::process_active_orders(
+            "BTC/USDT",
+            &repo,
+            vec![repo.get_by_id("fresh").await.unwrap()],
+            &crossing,
+            &policy,
+            &EngineConfig::default(),
+            &ctl,
+            &mut SustainTimers::default(),
+        )
+        .await;
+        assert_eq!(matched, 1);
+    }
+
+    #[tokio::test]
+    async fn fresh_crossing_policy_holds_an_order_still_crossing_at_resume() {
+        let repo = FakeRepo::default();
+        let o = mk_order(
+            "o",
+            "BTC/USDT",
+            OrderSide::Buy,
+            "100",
+            "1",
+            OrderStatus::Open,
+        );
+        repo.seed(vec![o.clone()]).await;
+        let ctl = Controls::default();
+        let halts = &ctl.halts;
+        halts.halt("BTC/USDT", HaltSource::CircuitBreaker, "band", 0);
+        halts.record_would_trigger("BTC/USDT", "o", dec!(99), 1);
+        halts.resume("BTC/USDT", halts::ResumePolicy::RequireFreshCrossing, 2);
+
+        let (matched, _) = super::process_active_orders(
+            "BTC/USDT",
+            &repo,
+            vec![o],
+            &MarketView::from_last(dec!(99), 1_700_000_000_000),
+            &OraclePolicy::default(),
+            &EngineConfig::default(),
+            &ctl,
+            &mut SustainTimers::default(),
+        )
+        .await;
+        assert_eq!(matched, 0);
+        assert_eq!(halts.pending("o"), Some(Pending::AwaitReset));
+    }
+
+    #[test]
+    fn notional_condition_compares_reference_times_quantity() {
+        let mut buy = mk_order("b", "BTC/USDT", OrderSide::Buy, "0", "2", OrderStatus::Open);
+        buy.condition = Condition::Notional {
+            threshold: dec!(100000),
+            quantity: None,
+        };
+        assert!(super::crosses(&buy, dec!(50000)));
+        assert!(!super::crosses(&buy, dec!(50000.01)));
+
+        let mut sell = mk_order(
+            "s",
+            "BTC/USDT",
+            OrderSide::Sell,
+            "0",
+            "2",
+            OrderStatus::Open,
+        );
+        sell.condition = Condition::Notional {
+            threshold: dec!(30000),
+            quantity: Some(dec!(0.5)),
+        };
+        assert!(super::crosses(&sell, dec!(60000)));
+        assert!(!super::crosses(&sell, dec!(59999)));
+    }
+
+    #[test]
+    fn indicator_conditions_read_view_readings() {
+        use crate::entities::order::{CrossDirection, Indicator};
+        use indicators::Reading;
+
+        let rsi = Indicator::Rsi { period: 14 };
+        let mut o = mk_order("r", "BTC/USDT", OrderSide::Buy, "0", "1", OrderStatus::Open);
+        o.condition = Condition::Level {
+            indicator: rsi,
+            below: Some(dec!(30)),
+            above: None,
+        };
+        let mut view = MarketView::from_last(dec!(100), o.created);
+        let policy = OraclePolicy::default();
+        assert_eq!(
+            policy.evaluate(&o, &view),
+            Decision::Hold("indicator warming up")
+        );
+        view.indicators.insert(
+            rsi,
+            Reading {
+                prev: Some(dec!(35)),
+                current: Some(dec!(28)),
+            },
+        );
+        assert_eq!(
+            policy.evaluate(&o, &view),
+            Decision::Fill {
+                ref_px: dec!(100),
+                exec_px: dec!(100),
+                qty: dec!(1),
+                levels: Vec::new(),
+            }
+        );
+
+        // A crossover seen on a tick older than the order does not count.
+        o.condition = Condition::Crossover {
+            fast: rsi,
+            slow: Indicator::Sma { period: 1 },
+            direction: CrossDirection::Below,
+        };
+        view.indicators.insert(
+            Indicator::Sma { period: 1 },
+            Reading {
+                prev: Some(dec!(30)),
+                current: Some(dec!(30)),
+            },
+        );
+        assert_eq!(
+            policy.evaluate(&o, &view),
+            Decision::Fill {
+                ref_px: dec!(100),
+                exec_px: dec!(100),
+                qty: dec!(1),
+                levels: Vec::new(),
+            }
+        );
+        view.ts_ms = o.created - 1;
+        assert_eq!(policy.evaluate(&o, &view), Decision::Hold("not crossing"));
+    }
+
+    #[tokio::test]
+    async fn priming_warms_indicators_from_kept_ticks_before_the_first_tick() {
+        use crate::entities::order::Indicator;
+
+        let sma = Indicator::Sma { period: 3 };
+        let mut o = mk_order("i", "BTC/USDT", OrderSide::Buy, "0", "1", OrderStatus::Open);
+        o.condition = Condition::Level {
+            indicator: sma,
+            below: Some(dec!(50)),
+            above: None,
+        };
+        let repo = FakeRepo::default();
+        repo.seed(vec![o]).await;
+        let oracle = OracleCache::default();
+        for (ts, px) in [(1, 10), (2, 20), (3, 30), (4, 40)] {
+            oracle
+                .set(crate::oracle_service::Tick::new(
+                    "BTC/USDT",
+                    Decimal::from(px),
+                    ts,
+                ))
+                .await;
+        }
+
+        let mut bank = IndicatorBank::default();
+        let found = prime(
+            "BTC/USDT",
+            &repo,
+            &oracle,
+            &InstrumentRegistry::default(),
+            &mut bank,
+        )
+        .await;
+        assert_eq!(found, (1, 4));
+        let reading = bank.readings()[&sma];
+        assert_eq!(
+            (reading.prev, reading.current),
+            (Some(dec!(20)), Some(dec!(30)))
+        );
+        // The worker's first tick then dedupes the latest kept one.
+        assert!(!bank.observe(dec!(40), 4));
+    }
+
+    #[tokio::test]
+    async fn spread_orders_fill_on_the_cross_price_and_record_both_legs() {
+        let repo = FakeRepo::default();
+        let mut o = mk_order("s", "ETH/BTC", OrderSide::Buy, "0", "1", OrderStatus::Open);
+        o.condition = Condition::Spread {
+            legs: ["ETH/USDT".into(), "BTC/USDT".into()],
+            measure: SpreadMeasure::Ratio,
+            below: Some(dec!(0.05)),
+            above: None,
+        };
+        repo.seed(vec![o.clone()]).await;
+        let leg = |pair: &str, price| LegPrice {
+            pair: pair.into(),
+            price,
+            ts_ms: 7,
+        };
+        let mut view = MarketView::from_last(dec!(0.049), 1_700_000_000_000);
+        let policy = OraclePolicy::default();
+        assert_eq!(
+            policy.evaluate(&o, &view),
+            Decision::Hold("spread leg price unavailable")
+        );
+        view.legs
+            .insert("ETH/USDT".into(), leg("ETH/USDT", dec!(3100)));
+        view.legs
+            .insert("BTC/USDT".into(), leg("BTC/USDT", dec!(60000)));
+        assert_eq!(policy.evaluate(&o, &view), Decision::Hold("not crossing"));
+        view.legs
+            .insert("ETH/USDT".into(), leg("ETH/USDT", dec!(2900)));
+
+        let (matched, _) = super::process_active_orders(
+            "ETH/BTC",
+            &repo,
+            vec![o],
+            &view,
+            &policy,
+            &EngineConfig::default(),
+            &Controls::default(),
+            &mut SustainTimers::default(),
+        )
+        .await;
+        assert_eq!(matched, 1);
+        let filled = repo.get_by_id("s").await.unwrap();
+        assert_eq!(filled.exec_price, Some(dec!(0.049)));
+        let legs: Vec<_> = filled.fills[0].legs.iter().map(|l| l.price).collect();
+        assert_eq!(legs, [dec!(2900), dec!(60000)]);
+    }
+
+    #[test]
+    fn volume_conditions_read_view_volumes_and_optionally_the_price() {
+        let mut o = mk_order(
+            "v",
+            "BTC/USDT",
+            OrderSide::Buy,
+            "90",
+            "1",
+            OrderStatus::Open,
+        );
+        o.condition = Condition::Volume {
+            window_ms: 60_000,
+            min_volume: dec!(10),
+            with_price: false,
+        };
+        let mut view = MarketView::from_last(dec!(100), o.created);
+        let policy = OraclePolicy::default();
+        assert_eq!(policy.evaluate(&o, &view), Decision::Hold("not crossing"));
+        view.volumes.insert(60_000, dec!(10));
+        let fill = Decision::Fill {
+            ref_px: dec!(100),
+            exec_px: dec!(100),
+            qty: dec!(1),
+            levels: Vec::new(),
+        };
+        assert_eq!(policy.evaluate(&o, &view), fill);
+
+        // Combined with the price, the buy also waits for last <= 90.
+        o.condition = Condition::Volume {
+            window_ms: 60_000,
+            min_volume: dec!(10),
+            with_price: true,
+        };
+        assert_eq!(policy.evaluate(&o, &view), Decision::Hold("not crossing"));
+        let view = MarketView {
+            volumes: view.volumes.clone(),
+            ..MarketView::from_last(dec!(90), o.created)
+        };
+        assert!(matches!(
+            policy.evaluate(&o, &view),
+            Decision::Fill { ref_px, .. } if ref_px == dec!(90)
+        ));
+    }
+
+    #[tokio::test]
+    async fn sustained_order_fills_once_the_trigger_held_long_enough() {
+        let repo = FakeRepo::default();
+        let o = Order {
+            sustained_for_ms: Some(5_000),
+            ..mk_order(
+                "s",
+                "BTC/USDT",
+                OrderSide::Buy,
+                "100",
+                "1",
+                OrderStatus::Open,
+            )
+        };
+        repo.seed(vec![o.clone()]).await;
+        let policy = OraclePolicy::default();
+        let ctl = Controls::default();
+        let mut timers = SustainTimers::default();
+
+        for (ts, want) in [(1_000, 0), (4_000, 0), (6_000, 1)] {
+            let (matched, _) = super::process_active_orders(
+                "BTC/USDT",
+                &repo,
+                vec![repo.get_by_id("s").await.unwrap()],
+                &MarketView::from_last(dec!(99), ts),
+                &policy,
+                &EngineConfig::default(),
+                &ctl,
+                &mut timers,
+            )
+            .await;
+            assert_eq!(matched, want, "at ts {ts}");
+        }
+    }
+
+    #[tokio::test]
+    async fn partial_fill_policy_works_an_order_over_several_ticks() {
+        let repo = FakeRepo::default();
+        let o = mk_order(
+            "p",
+            "BTC/USDT",
+            OrderSide::Buy,
+            "100",
+            "3",
+            OrderStatus::Open,
+        );
+        repo.seed(vec![o]).await;
+        let policy = PartialFillPolicy::default();
+        let view = MarketView {
+            liquidity: LiquidityCurve::parse("10:2"),
+            ..MarketView::from_last(dec!(99), 1_700_000_000_000)
+        };
+        let mut timers = SustainTimers::default();
+        for want in [OrderStatus::PartiallyFilled, OrderStatus::Filled] {
+            let (matched, _) = super::process_active_orders(
+                "BTC/USDT",
+                &repo,
+                vec![repo.get_by_id("p").await.unwrap()],
+                &view,
+                &policy,
+                &EngineConfig::default(),
+                &Controls::default(),
+                &mut timers,
+            )
+            .await;
+            assert_eq!(matched, 1);
+            assert_eq!(repo.get_by_id("p").await.unwrap().status, want);
+        }
+        assert_eq!(repo.get_by_id("p").await.unwrap().filled_quantity, dec!(3));
+    }
+
+    #[tokio::test]
+    async fn ioc_orders_fill_what_they_can_on_their_tick_and_cancel_the_rest() {
+        use crate::entities::order::TimeInForce;
+        use crate::entities::test_support::OrderBuilder;
+        use crate::repositories::in_memory::InMemoryOrderRepository;
+
+        let repo = InMemoryOrderRepository::default();
+        let mut orders = Vec::new();
+        for (px, qty) in [(100, 3), (90, 1)] {
+            let mut new = OrderBuilder::buy("BTC/USDT").limit(px).qty(qty).new_order();
+            new.time_in_force = TimeInForce::Ioc;
+            orders.push(repo.create(new).await.unwrap());
+        }
+        let (partial, missed) = (orders[0].id.clone(), orders[1].id.clone());
+        let view = MarketView {
+            liquidity: LiquidityCurve::parse("10:2"),
+            ..MarketView::from_last(dec!(99), 1_700_000_000_000)
+        };
+        let (matched, promoted) = super::process_active_orders(
+            "BTC/USDT",
+            &repo,
+            orders,
+            &view,
+            &PartialFillPolicy::default(),
+            &EngineConfig::default(),
+            &Controls::default(),
+            &mut SustainTimers::default(),
+        )
+        .await;
+        assert_eq!((matched, promoted), (1, 0));
+        let o = repo.get_by_id(&partial).await.unwrap();
+        assert_eq!(
+            (o.status, o.filled_quantity, o.cancel_reason.as_deref()),
+            (OrderStatus::Cancelled, dec!(2), Some(CANCEL_IOC))
+        );
+        let o = repo.get_by_id(&missed).await.unwrap();
+        assert_eq!(
+            (o.status, o.filled_quantity, o.cancel_reason.as_deref()),
+            (OrderStatus::Cancelled, dec!(0), Some(CANCEL_IOC))
+        );
+    }
+
+    #[tokio::test]
+    async fn orders_on_one_tick_sweep_depth_the_earlier_ones_left() {
+        let repo = FakeRepo::default();
+        let mut orders = Vec::new();
+        for (id, side) in [
+            ("b1", OrderSide::Buy),
+            ("b2", OrderSide::Buy),
+            ("s1", OrderSide::Sell),
+        ] {
+            orders.push(mk_order(
+                id,
+                "BTC/USDT",
+                side,
+                "100",
+                "2",
+                OrderStatus::Open,
+            ));
+        }
+        repo.seed(orders.clone()).await;
+        let view = MarketView {
+            liquidity: LiquidityCurve::parse("10:1,20:2"),
+            ..MarketView::from_last(dec!(100), 1_700_000_000_000)
+        };
+        let (matched, _) = super::process_active_orders(
+            "BTC/USDT",
+            &repo,
+            orders,
+            &view,
+            &PartialFillPolicy::default(),
+            &EngineConfig::default(),
+            &Controls::default(),
+            &mut SustainTimers::default(),
+        )
+        .await;
+        assert_eq!(matched, 3);
+
+        let b1 = repo.get_by_id("b1").await.unwrap();
+        let prices: Vec<_> = b1.fills.iter().map(|f| f.price).collect();
+        assert_eq!(prices, vec![dec!(100.1), dec!(100.2)]);
+        assert_eq!(b1.exec_price, Some(dec!(100.15)));
+        assert_eq!(b1.status, OrderStatus::Filled);
+
+        // Only one unit was left within the default slippage bound, further
+        // out; the sell side was untouched.
+        let b2 = repo.get_by_id("b2").await.unwrap();
+        assert_eq!(b2.status, OrderStatus::PartiallyFilled);
+        assert_eq!(b2.fills.len(), 1);
+        assert_eq!(
+            (b2.fills[0].price, b2.filled_quantity),
+            (dec!(100.2), dec!(1))
+        );
+        let s1 = repo.get_by_id("s1").await.unwrap();
+        assert_eq!(s1.fills[0].price, dec!(99.9));
+        assert_eq!(s1.status, OrderStatus::Filled);
+    }
+
+    #[tokio::test]
+    async fn gapped_fills_are_priced_by_the_order_exec_pricing() {
+        let repo = FakeRepo::default();
+        let mut orders = Vec::new();
+        for (id, pricing) in [
+            ("oracle", None),
+            ("limit", Some(ExecPricing::Limit)),
+            ("better", Some(ExecPricing::BetterOf)),
+        ] {
+            let mut o = mk_order(
+                id,
+                "BTC/USDT",
+                OrderSide::Sell,
+                "100",
+                "1",
+                OrderStatus::Open,
+            );
+            o.exec_pricing = pricing;
+            orders.push(o);
+        }
+        repo.seed(orders.clone()).await;
+        // Gapped up through the sell limits, then down.
+        let (matched, _) = super::process_active_orders(
+            "BTC/USDT",
+            &repo,
+            orders,
+            &MarketView::from_last(dec!(120), 1_700_000_000_000),
+            &OraclePolicy::default(),
+            &EngineConfig::default(),
+            &Controls::default(),
+            &mut SustainTimers::default(),
+        )
+        .await;
+        assert_eq!(matched, 3);
+        let px = |o: Order| o.exec_price.unwrap();
+        assert_eq!(px(repo.get_by_id("oracle").await.unwrap()), dec!(120));
+        assert_eq!(px(repo.get_by_id("limit").await.unwrap()), dec!(100));
+        assert_eq!(px(repo.get_by_id("better").await.unwrap()), dec!(120));
+    }
+
+    #[tokio::test]
+    async fn quote_sized_orders_get_their_quantity_at_the_fill_price() {
+        let repo = FakeRepo::default();
+        let mut spend = mk_order(
+            "spend",
+            "BTC/USDT",
+            OrderSide::Buy,
+            "100",
+            "5",
+            OrderStatus::Open,
+        );
+        spend.quantity_quote = Some(dec!(500));
+        let mut dust = mk_order(
+            "dust",
+            "BTC/USDT",
+            OrderSide::Buy,
+            "100",
+            "0.001",
+            OrderStatus::Open,
+        );
+        dust.quantity_quote = Some(dec!(0.01));
+        repo.seed(vec![spend.clone(), dust.clone()]).await;
+        let view = MarketView {
+            qty_scale: 3,
+            ..MarketView::from_last(dec!(80.3), 1_700_000_000_000)
+        };
+        let (matched, _) = super::process_active_orders(
+            "BTC/USDT",
+            &repo,
+            vec![spend, dust],
+            &view,
+            &OraclePolicy::default(),
+            &EngineConfig::default(),
+            &Controls::default(),
+            &mut SustainTimers::default(),
+        )
+        .await;
+        assert_eq!(matched, 1);
+        let o = repo.get_by_id("spend").await.unwrap();
+        // 500 / 80.3 = 6.2266..., rounded down to the pair's 3 places.
+        assert_eq!((o.quantity, o.filled_quantity), (dec!(6.226), dec!(6.226)));
+        assert_eq!(
+            (o.status, o.quantity_quote),
+            (OrderStatus::Filled, Some(dec!(500)))
+        );
+        let o = repo.get_by_id("dust").await.unwrap();
+        assert_eq!(o.cancel_reason.as_deref(), Some(CANCEL_QUOTE_TOO_SMALL));
+    }
+
+    #[tokio::test]
+    async fn banded_fills_record_the_venue_prices_they_went_through_on() {
+        let oracle = OracleCache::default().with_sources(vec!["a".into(), "b".into()]);
+        let now = now_ms();
+        for (source, px) in [("a", dec!(99)), ("b", dec!(98))] {
+            oracle
+                .set(Tick {
+                    source: Some(source.into()),
+                    ..Tick::new("BTC/USDT", px, now)
+                })
+                .await;
+        }
+        let mut view = MarketView {
+            index: Some(dec!(90)),
+            ..MarketView::from_last(dec!(99), now)
+        };
+        view.fair_band = fairband::gather(&oracle, "BTC/USDT", 8, dec!(0), now).await;
+        let mut orders = vec![
+            mk_order(
+                "last",
+                "BTC/USDT",
+                OrderSide::Buy,
+                "100",
+                "1",
+                OrderStatus::Open,
+            ),
+            mk_order(
+                "index",
+                "BTC/USDT",
+                OrderSide::Buy,
+                "95",
+                "1",
+                OrderStatus::Open,
+            ),
+        ];
+        orders[1].trigger_price_type = TriggerPriceType::Index;
+        let repo = FakeRepo::default();
+        repo.seed(orders.clone()).await;
+        let (matched, _) = super::process_active_orders(
+            "BTC/USDT",
+            &repo,
+            orders,
+            &view,
+            &OraclePolicy::default(),
+            &EngineConfig::default(),
+            &Controls::default(),
+            &mut SustainTimers::default(),
+        )
+        .await;
+        // The index at 90 is below every healthy venue, so its order holds.
+        assert_eq!(matched, 1);
+        assert!(repo.get_by_id("index").await.unwrap().fills.is_empty());
+        let f = repo.get_by_id("last").await.unwrap().fills[0].clone();
+        let sources: Vec<_> = f
+            .fair_sources
+            .iter()
+            .map(|s| (s.source.as_str(), s.price))
+            .collect();
+        assert_eq!(sources, [("a", dec!(99)), ("b", dec!(98))]);
+    }
+
+    #[tokio::test]
+    async fn fills_carry_oracle_receive_and_decision_times() {
+        let oracle = OracleCache::default();
+        let tick_ts = now_ms() - 250;
+        oracle.set(Tick::new("BTC/USDT", dec!(99), tick_ts)).await;
+        let view = market_view(&oracle, &InstrumentRegistry::default(), "BTC/USDT")
+            .await
+            .unwrap();
+        let o = mk_order(
+            "t",
+            "BTC/USDT",
+            OrderSide::Buy,
+            "100",
+            "1",
+            OrderStatus::Open,
+        );
+        let repo = FakeRepo::default();
+        repo.seed(vec![o.clone()]).await;
+        super::process_active_orders(
+            "BTC/USDT",
+            &repo,
+            vec![o],
+            &view,
+            &OraclePolicy::default(),
+            &EngineConfig::default(),
+            &Controls::default(),
+            &mut SustainTimers::default(),
+        )
+        .await;
+        let f = repo.get_by_id("t").await.unwrap().fills[0].clone();
+        assert_eq!(f.ts_ms, tick_ts);
+        assert_eq!(f.received_ms, view.received_ms);
+        assert!(f.clock_skew_ms().is_some_and(|ms| ms >= 250));
+        assert!(f.processing_ms().is_some_and(|ms| ms >= 0));
+        assert!(f.end_to_end_ms() >= f.clock_skew_ms());
+    }
+
+    #[tokio::test]
+    async fn market_prices_are_compared_at_the_pair_precision() {
+        let instruments = InstrumentRegistry::empty();
+        instruments.register(Instrument::parse("BTC/USDT:2:6").unwrap());
+        let oracle = OracleCache::default();
+        let now = now_ms();
+        oracle
+            .set(Tick {
+                bid: Some(dec!(99.996)),
+                ..Tick::new("BTC/USDT", dec!(100.000000001), now)
+            })
+            .await;
+        let view = market_view(&oracle, &instruments, "BTC/USDT")
+            .await
+            .unwrap();
+        assert_eq!((view.last, view.bid), (dec!(100.00), Some(dec!(100.00))));
+        assert_eq!(view.price_scale, 2);
+
+        // Float noise above 100 no longer keeps a buy at 100 from filling.
+        let o = mk_order(
+            "p",
+            "BTC/USDT",
+            OrderSide::Buy,
+            "100",
+            "1",
+            OrderStatus::Open,
+        );
+        let repo = FakeRepo::default();
+        repo.seed(vec![o.clone()]).await;
+        let (matched, _) = super::process_active_orders(
+            "BTC/USDT",
+            &repo,
+            vec![o],
+            &view,
+            &OraclePolicy::default(),
+            &EngineConfig::default(),
+            &Controls::default(),
+            &mut SustainTimers::default(),
+        )
+        .await;
+        assert_eq!(matched, 1);
+    }
+
+    #[tokio::test]
+    async fn fills_for_triggers_missed_while_down_are_tagged() {
+        let view = MarketView::from_last(dec!(99), now_ms());
+        let mk = |id| {
+            mk_order(
+                id,
+                "BTC/USDT",
+                OrderSide::Buy,
+                "100",
+                "1",
+                OrderStatus::Open,
+            )
+        };
+        let (missed, fresh) = (mk("missed"), mk("fresh"));
+        let repo = FakeRepo::default();
+        repo.seed(vec![missed.clone(), fresh.clone()]).await;
+        let ctl = Controls::default();
+        ctl.missed.mark(
+            "BTC/USDT",
+            ["missed"],
+            MissedTriggerPolicy::TriggerImmediately,
+            0,
+        );
+        let (matched, _) = super::process_active_orders(
+            "BTC/USDT",
+            &repo,
+            vec![missed, fresh],
+            &view,
+            &OraclePolicy::default(),
+            &EngineConfig::default(),
+            &ctl,
+            &mut SustainTimers::default(),
+        )
+        .await;
+        assert_eq!(matched, 2);
+        for (id, tagged) in [("missed", true), ("fresh", false)] {
+            let o = repo.get_by_id(id).await.unwrap();
+            assert_eq!(o.fills[0].recovered_trigger, tagged);
+        }
+    }
+
+    #[tokio::test]
+    async fn a_trigger_event_is_filled_at_most_once() {
+        let view = MarketView::from_last(dec!(99), now_ms());
+        let o = mk_order(
+            "t",
+            "BTC/USDT",
+            OrderSide::Buy,
+            "100",
+            "1",
+            OrderStatus::Open,
+        );
+        let held = mk_order(
+            "h",
+            "BTC/USDT",
+            OrderSide::Buy,
+            "100",
+            "1",
+            OrderStatus::Open,
+        );
+        let repo = FakeRepo::default();
+        repo.seed(vec![o.clone(), held.clone()]).await;
+        let ctl = Controls::default();
+        // Another engine already claimed `h`'s trigger.
+        ctl.ledger
+            .for_owner("standby")
+            .claim("h", 0, "BTC/USDT", view.ts_ms, dec!(1), 0)
+            .unwrap();
+        let run = |orders| {
+            let (repo, ctl, view) = (&repo, &ctl, &view);
+            async move {
+                super::process_active_orders(
+                    "BTC/USDT",
+                    repo,
+                    orders,
+                    view,
+                    &OraclePolicy::default(),
+                    &EngineConfig::default(),
+                    ctl,
+                    &mut SustainTimers::default(),
+                )
+                .await
+                .0
+            }
+        };
+        assert_eq!(run(vec![o.clone(), held]).await, 1);
+        assert!(repo.get_by_id("h").await.unwrap().fills.is_empty());
+        // A retry working from the order as it was before the fill.
+        assert_eq!(run(vec![o]).await, 0);
+        assert_eq!(repo.get_by_id("t").await.unwrap().fills.len(), 1);
+        assert_eq!(ctl.ledger.unresolved().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn parallel_evaluation_matches_sequential_and_keeps_order() {
+        let view = MarketView {
+            liquidity: LiquidityCurve::parse("10:1,40:1,90:5"),
+            ..MarketView::from_last(dec!(100), now_ms())
+        };
+        let orders: Vec<Order> = (0..MIN_EVAL_CHUNK * 4 + 7)
+            .map(|i| {
+                let side = if i % 3 == 0 {
+                    OrderSide::Sell
+                } else {
+                    OrderSide::Buy
+                };
+                let px = (95 + i % 11).to_string();
+                mk_order(
+                    &format!("o{i}"),
+                    "BTC/USDT",
+                    side,
+                    &px,
+                    "1",
+                    OrderStatus::Open,
+                )
+            })
+            .collect();
+        let policy = PartialFillPolicy::default();
+        let (_, sequential) = evaluate_batch(orders.clone(), &view, &policy, 1).await;
+        assert_eq!(sequential.len(), orders.len());
+        assert!(sequential
+            .iter()
+            .any(|d| matches!(d, Decision::Fill { .. })));
+        assert!(sequential.iter().any(|d| matches!(d, Decision::Hold(_))));
+        for threads in [2, 4, 64] {
+            let (back, decisions) = evaluate_batch(orders.clone(), &view, &policy, threads).await;
+            assert_eq!(decisions, sequential);
+            assert!(back.iter().zip(&orders).all(|(a, b)| a.id == b.id));
+        }
+    }
+
+    #[tokio::test]
+    async fn a_tick_filling_past_the_bulk_threshold_is_summed_up_once() {
+        use crate::entities::events::DomainEvent;
+
+        let view = MarketView::from_last(dec!(99), now_ms());
+        let orders: Vec<Order> = (0..=DEFAULT_BULK_FILL_THRESHOLD)
+            .map(|i| {
+                let qty = if i == 0 { "2" } else { "1" };
+                let id = format!("o{i}");
+                mk_order(
+                    &id,
+                    "BTC/USDT",
+                    OrderSide::Buy,
+                    "100",
+                    qty,
+                    OrderStatus::Open,
+                )
+            })
+            .collect();
+        let repo = FakeRepo::default();
+        repo.seed(orders.clone()).await;
+        let ctl = Controls::default();
+        let mut events = ctl.events.subscribe();
+        let (matched, _) = super::process_active_orders(
+            "BTC/USDT",
+            &repo,
+            orders,
+            &view,
+            &OraclePolicy::default(),
+            &EngineConfig::default(),
+            &ctl,
+            &mut SustainTimers::default(),
+        )
+        .await;
+        assert_eq!(matched, DEFAULT_BULK_FILL_THRESHOLD + 1);
+        match events.try_recv().unwrap().event {
+            DomainEvent::Market(MarketEvent::BulkFill {
+                orders,
+                quantity,
+                avg_px,
+                min_px,
+                max_px,
+                ..
+            }) => {
+                assert_eq!(orders, DEFAULT_BULK_FILL_THRESHOLD + 1);
+                assert_eq!(quantity, Decimal::from(DEFAULT_BULK_FILL_THRESHOLD + 2));
+                assert_eq!((avg_px, min_px, max_px), (dec!(99), dec!(99), dec!(99)));
+            }
+            e => panic!("got {e:?}"),
+        }
+        assert!(events.try_recv().is_err());
+    }
+
+    #[test]
+    fn bulk_fills_weigh_prices_by_quantity() {
+        let fill = |price, quantity| Fill {
+            price,
+            quantity,
+            ts_ms: 1,
+            received_ms: None,
+            decided_ms: None,
+            recovered_trigger: false,
+            interpolated_trigger: false,
+            legs: Vec::new(),
+            trigger_price: None,
+            fair_sources: Vec::new(),
+            counterparty: None,
+        };
+        let mut bulk = BulkFill::default();
+        assert!(bulk.event("BTC/USDT", 1).is_none());
+        bulk.add(&[fill(dec!(100), dec!(1)), fill(dec!(103), dec!(2))]);
+        bulk.add(&[fill(dec!(98), dec!(1))]);
+        match bulk.event("BTC/USDT", 1) {
+            Some(MarketEvent::BulkFill {
+                orders,
+                avg_px,
+                min_px,
+                max_px,
+                ..
+            }) => assert_eq!(
+                (orders, avg_px, min_px, max_px),
+                (2, dec!(101), dec!(98), dec!(103))
+            ),
+            e => panic!("got {e:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn venue_orders_are_evaluated_on_their_venue_price() {
+        let oracle = OracleCache::default().with_sources(vec!["a".into()]);
+        let now = now_ms();
+        oracle
+            .set(Tick {
+                source: Some("a".into()),
+                ..Tick::new("BTC/USDT", dec!(95), now)
+            })
+            .await;
+        oracle.set(Tick::new("BTC/USDT", dec!(105), now + 1)).await;
+        let base = MarketView {
+            index: Some(dec!(100)),
+            ..MarketView::from_last(dec!(105), now + 1)
+        };
+        let view = venue_view(&oracle, &base, "BTC/USDT", "a").await.unwrap();
+        assert_eq!((view.last, view.ts_ms), (dec!(95), now));
+        assert_eq!(view.index, Some(dec!(100)));
+        assert!(venue_view(&oracle, &base, "BTC/USDT", "b").await.is_none());
+
+        let mut venue = mk_order(
+            "v",
+            "BTC/USDT",
+            OrderSide::Buy,
+            "100",
+            "1",
+            OrderStatus::Open,
+        );
+        venue.price_source = Some("a".into());
+        let agg = mk_order(
+            "g",
+            "BTC/USDT",
+            OrderSide::Buy,
+            "100",
+            "1",
+            OrderStatus::Open,
+        );
+        let groups = by_source(vec![venue, agg]);
+        let keys: Vec<_> = groups.keys().cloned().collect();
+        assert_eq!(keys, vec![None, Some("a".to_string())]);
+        // Only the venue order crosses: its venue is below the limit.
+        let policy = OraclePolicy::default();
+        assert!(matches!(
+            policy.evaluate(&groups[&Some("a".to_string())][0], &view),
+            Decision::Fill { .. }
+        ));
+        assert!(policy.evaluate(&groups[&None][0], &base).is_waiting());
+    }
 }
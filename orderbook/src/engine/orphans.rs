@@ -0,0 +1,214 @@
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use serde::Serialize;
+use tokio::time::{interval, MissedTickBehavior};
+use tracing::{error, info, warn};
+
+use crate::entities::order::{Order, OrderStatus};
+use crate::instruments::symbol::Symbol;
+use crate::metrics::Metrics;
+use crate::oracle_service::OracleCache;
+use crate::repositories::{ListOrdersQuery, OrderRepository};
+use crate::utils::now_ms;
+
+pub const ORPHANED_GAUGE: &str = "orphaned_orders";
+pub const ORPHANED_CANCELLED: &str = "orphaned_orders_cancelled_total";
+
+#[derive(Debug, Serialize)]
+pub struct OrphanReport {
+    pub min_age_ms: i64,
+    pub count: usize,
+    pub by_pair: BTreeMap<Symbol, usize>,
+    pub orders: Vec<Order>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct OrphanCancelReport {
+    pub cancelled: Vec<String>,
+    pub failed: Vec<String>,
+}
+
+/// Active orders older than `min_age_ms` whose pair has never had an oracle
+/// price. They can never be evaluated, so they are reported (and optionally
+/// cancelled) instead of silently resting forever.
+pub async fn find_orphaned<R: OrderRepository + ?Sized>(
+    repo: &R,
+    oracle: &OracleCache,
+    min_age_ms: i64,
+    now: i64,
+) -> Result<OrphanReport, String> {
+    let priced = oracle.pairs().await;
+    let mut orders: Vec<Order> = repo
+        .list(ListOrdersQuery::default())
+        .await?
+        .into_iter()
+        .filter(|o| o.status.is_active())
+        .filter(|o| now - o.created >= min_age_ms)
+        .filter(|o| !priced.contains(&o.pair))
+        .collect();
+    orders.sort_by_key(|o| o.created);
+
+    let mut by_pair = BTreeMap::new();
+    for o in &orders {
+        *by_pair.entry(o.pair.clone()).or_insert(0) += 1;
+    }
+    Ok(OrphanReport {
+        min_age_ms,
+        count: orders.len(),
+        by_pair,
+        orders,
+    })
+}
+
+pub fn record_orphans(metrics: &Metrics, report: &OrphanReport) {
+    metrics.clear(ORPHANED_GAUGE);
+    metrics.set(ORPHANED_GAUGE, &[], report.count as f64);
+    for (pair, n) in &report.by_pair {
+        metrics.set(ORPHANED_GAUGE, &[("pair", pair)], *n as f64);
+    }
+}
+
+pub async fn cancel_orphaned<R: OrderRepository + ?Sized>(
+    repo: &R,
+    metrics: &Metrics,
+    report: &OrphanReport,
+) -> OrphanCancelReport {
+    let mut out = OrphanCancelReport::default();
+    for o in &report.orders {
+        match repo.set_status(&o.id, OrderStatus::Cancelled).await {
+            Ok(_) => {
+                metrics.incr(ORPHANED_CANCELLED, &[("pair", &o.pair)], 1.0);
+                out.cancelled.push(o.id.clone());
+            }
+            Err(e) => {
+                error!(order_id = %o.id, err = %e, "failed to cancel orphaned order");
+                out.failed.push(o.id.clone());
+            }
+        }
+    }
+    info!(
+        cancelled = out.cancelled.len(),
+        failed = out.failed.len(),
+        "orphaned orders cancelled"
+    );
+    out
+}
+
+/// Periodically refreshes the orphaned-order gauges.
+pub fn start_orphan_sweep<R: OrderRepository + 'static>(
+    repo: R,
+    oracle: OracleCache,
+    metrics: Metrics,
+    min_age_ms: i64,
+    every: Duration,
+) {
+    tokio::spawn(async move {
+        let mut t = interval(every);
+        t.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            t.tick().await;
+            match find_orphaned(&repo, &oracle, min_age_ms, now_ms()).await {
+                Ok(report) => {
+                    if report.count > 0 {
+                        warn!(count = report.count, by_pair = ?report.by_pair, "orphaned orders");
+                    }
+                    record_orphans(&metrics, &report);
+                }
+                Err(e) => error!(err = %e, "orphan sweep failed"),
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entities::order::{
+        Condition, NewOrder, OrderSide, OrderType, TimeInForce, TriggerPriceType,
+    };
+    use crate::oracle_service::Tick;
+    use crate::repositories::in_memory::InMemoryOrderRepository;
+    use rust_decimal_macros::dec;
+
+    const HOUR_MS: i64 = 3_600_000;
+
+    async fn place(repo: &InMemoryOrderRepository, pair: &str) -> Order {
+        repo.create(NewOrder {
+            pair: pair.into(),
+            side: OrderSide::Buy,
+            order_type: OrderType::Limit,
+            price: dec!(1),
+            quantity: dec!(1),
+            trigger_price_type: TriggerPriceType::Last,
+            condition: Condition::Price,
+            sustained_for_ms: None,
+            time_in_force: TimeInForce::Gtc,
+            max_slippage_bps: None,
+            exec_pricing: None,
+            price_source: None,
+            account_id: None,
+            quantity_quote: None,
+            cancel_after_ms: None,
+            valid_for_after_armed_ms: None,
+            expires_at: None,
+            tags: Vec::new(),
+            metadata: Default::default(),
+            callback_url: None,
+            callback_template: None,
+            trigger_price: None,
+            trailing: None,
+            oco_id: None,
+        })
+        .await
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn reports_only_old_active_orders_on_unpriced_pairs() {
+        let repo = InMemoryOrderRepository::default();
+        let oracle = OracleCache::default();
+        oracle.set(Tick::new("BTC/USDT", dec!(100), 1)).await;
+
+        let orphan = place(&repo, "DOGE/USDT").await;
+        place(&repo, "BTC/USDT").await;
+        let done = place(&repo, "DOGE/USDT").await;
+        repo.set_status(&done.id, OrderStatus::Filled)
+            .await
+            .unwrap();
+
+        let later = now_ms() + 2 * HOUR_MS;
+        let report = find_orphaned(&repo, &oracle, HOUR_MS, later).await.unwrap();
+        assert_eq!(report.count, 1);
+        assert_eq!(report.orders[0].id, orphan.id);
+        assert_eq!(report.by_pair.get("DOGE/USDT"), Some(&1));
+
+        let fresh = find_orphaned(&repo, &oracle, HOUR_MS, now_ms())
+            .await
+            .unwrap();
+        assert_eq!(fresh.count, 0);
+    }
+
+    #[tokio::test]
+    async fn cancel_updates_orders_and_metrics() {
+        let repo = InMemoryOrderRepository::default();
+        let oracle = OracleCache::default();
+        let metrics = Metrics::default();
+        let o = place(&repo, "DOGE/USDT").await;
+
+        let report = find_orphaned(&repo, &oracle, 0, now_ms()).await.unwrap();
+        record_orphans(&metrics, &report);
+        assert_eq!(metrics.get(ORPHANED_GAUGE, &[]), Some(1.0));
+
+        let out = cancel_orphaned(&repo, &metrics, &report).await;
+        assert_eq!(out.cancelled, vec![o.id.clone()]);
+        assert_eq!(
+            repo.get_by_id(&o.id).await.unwrap().status,
+            OrderStatus::Cancelled
+        );
+        assert_eq!(
+            metrics.get(ORPHANED_CANCELLED, &[("pair", "DOGE/USDT")]),
+            Some(1.0)
+        );
+    }
+}
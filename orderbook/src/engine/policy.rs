@@ -0,0 +1,477 @@
+use std::fmt::Debug;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+
+use super::{crosses, indicators, spread, volume, MarketView};
+use crate::entities::order::{
+    Condition, Order, OrderSide, OrderStatus, OrderType, TimeInForce, CANCEL_FOK_UNFILLABLE,
+};
+use crate::flags::{Feature, FeatureFlags};
+use crate::instruments::liquidity::SimulatedFill;
+
+/// What the matcher does with an active order on a tick.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Decision {
+    /// Execute `qty` of the order at `exec_px`; `ref_px` is the trigger
+    /// reference that crossed. Less than the remaining quantity is a
+    /// partial fill. `levels` are the simulated depth levels the fill
+    /// walked, nearest first, with `exec_px` their VWAP; empty when it
+    /// does not touch the depth.
+    Fill {
+        ref_px: Decimal,
+        exec_px: Decimal,
+        qty: Decimal,
+        levels: Vec<SimulatedFill>,
+    },
+    /// Not triggered; open the `New` order so it rests.
+    Promote(&'static str),
+    /// Not triggered; leave the order as it is.
+    Hold(&'static str),
+    Cancel(&'static str),
+}
+
+impl Decision {
+    /// Not triggered, for `reason`: `New` orders are promoted, the rest
+    /// held.
+    pub fn waiting(o: &Order, reason: &'static str) -> Self {
+        if o.status == OrderStatus::New {
+            Self::Promote(reason)
+        } else {
+            Self::Hold(reason)
+        }
+    }
+
+    pub fn is_waiting(&self) -> bool {
+        matches!(self, Self::Promote(_) | Self::Hold(_))
+    }
+}
+
+/// The crossing decision. `trigger` says whether an order's condition
+/// holds, `execute` prices a triggered order; halts use `execute` alone to
+/// fire parked orders. Policies are picked per process with
+/// `MATCH_POLICY`.
+pub trait MatchPolicy: Send + Sync + Debug {
+    fn name(&self) -> &'static str;
+
+    /// The trigger reference price when the order's condition holds, or
+    /// why it does not.
+    fn trigger(&self, o: &Order, view: &MarketView) -> Result<Decimal, &'static str>;
+
+    fn execute(&self, o: &Order, view: &MarketView, ref_px: Decimal) -> Decision;
+
+    fn evaluate(&self, o: &Order, view: &MarketView) -> Decision {
+        match self.trigger(o, view) {
+            Ok(ref_px) => self.execute(o, view, ref_px),
+            Err(reason) => Decision::waiting(o, reason),
+        }
+    }
+}
+
+/// A worker's policy shared with the threads that evaluate a large book.
+impl<P: MatchPolicy + ?Sized> MatchPolicy for Arc<P> {
+    fn name(&self) -> &'static str {
+        (**self).name()
+    }
+
+    fn trigger(&self, o: &Order, view: &MarketView) -> Result<Decimal, &'static str> {
+        (**self).trigger(o, view)
+    }
+
+    fn execute(&self, o: &Order, view: &MarketView, ref_px: Decimal) -> Decision {
+        (**self).execute(o, view, ref_px)
+    }
+
+    fn evaluate(&self, o: &Order, view: &MarketView) -> Decision {
+        (**self).evaluate(o, view)
+    }
+}
+
+fn remaining(o: &Order) -> Decimal {
+    o.quantity - o.filled_quantity
+}
+
+/// The default: triggers on the order's reference price and executes the
+/// whole remainder at the oracle last. FOK orders must find their quantity
+/// in the simulated depth within their slippage bound and execute at the
+/// sweep's VWAP, or are cancelled. `market_band_bps` is the widest gap
+/// between last and index at which a market order may still execute.
+#[derive(Debug, Clone, Copy)]
+pub struct OraclePolicy {
+    pub market_band_bps: Decimal,
+    pub fok_max_slippage_bps: Decimal,
+}
+
+impl Default for OraclePolicy {
+    fn default() -> Self {
+        Self {
+            market_band_bps: dec!(100),
+            fok_max_slippage_bps: dec!(50),
+        }
+    }
+}
+
+impl MatchPolicy for OraclePolicy {
+    fn name(&self) -> &'static str {
+        "oracle"
+    }
+
+    fn trigger(&self, o: &Order, view: &MarketView) -> Result<Decimal, &'static str> {
+        match o.order_type {
+            OrderType::Market => {
+                if view.stale || view.ts_ms < o.created {
+                    return Err("waiting for a fresh tick");
+                }
+                if !view.within_band(self.market_band_bps) {
+                    return Err("last outside band around index");
+                }
+                Ok(view.last)
+            }
+            OrderType::Limit => {
+                let ref_px = view
+                    .reference(o.trigger_price_type)
+                    .ok_or("reference price unavailable")?;
+                let hit = match &o.condition {
+                    Condition::Price | Condition::Notional { .. } => crosses(o, ref_px),
+                    Condition::Crossover { .. } if view.ts_ms < o.created => false,
+                    Condition::Volume { with_price, .. } => {
+                        volume::holds(&o.condition, &view.volumes)
+                            && (!with_price || crosses(o, ref_px))
+                    }
+                    cond @ Condition::Spread { .. } => spread::holds(cond, &view.legs)?,
+                    cond => indicators::holds(cond, &view.indicators)?,
+                };
+                if hit {
+                    Ok(ref_px)
+                } else {
+                    Err("not crossing")
+                }
+            }
+            // The matcher activates stops before the policy sees them.
+            OrderType::Stop | OrderType::StopLimit => Err("stop not triggered"),
+        }
+    }
+
+    fn execute(&self, o: &Order, view: &MarketView, ref_px: Decimal) -> Decision {
+        let (exec_px, levels) = match (o.time_in_force, &view.liquidity) {
+            (TimeInForce::Fok, Some(curve)) => {
+                let max_bps = o.max_slippage_bps.unwrap_or(self.fok_max_slippage_bps);
+                let sweep = curve.sweep(&o.side, view.last, remaining(o), max_bps);
+                match sweep.vwap() {
+                    Some(vwap) if sweep.filled >= remaining(o) => (vwap, sweep.fills),
+                    _ => return Decision::Cancel(CANCEL_FOK_UNFILLABLE),
+                }
+            }
+            _ => (view.last, Vec::new()),
+        };
+        Decision::Fill {
+            ref_px,
+            exec_px,
+            qty: remaining(o),
+            levels,
+        }
+    }
+}
+
+/// Like [`OraclePolicy`], but a buy executes at the ask and a sell at the
+/// bid when the feed quotes them, instead of at last. FOK orders keep
+/// their sweep.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BidAskPolicy(pub OraclePolicy);
+
+impl MatchPolicy for BidAskPolicy {
+    fn name(&self) -> &'static str {
+        "bid_ask"
+    }
+
+    fn trigger(&self, o: &Order, view: &MarketView) -> Result<Decimal, &'static str> {
+        self.0.trigger(o, view)
+    }
+
+    fn execute(&self, o: &Order, view: &MarketView, ref_px: Decimal) -> Decision {
+        let quote = match o.side {
+            OrderSide::Buy => view.ask,
+            OrderSide::Sell => view.bid,
+        };
+        match (self.0.execute(o, view, ref_px), quote) {
+            (Decision::Fill { qty, .. }, Some(px)) if o.time_in_force != TimeInForce::Fok => {
+                Decision::Fill {
+                    ref_px,
+                    exec_px: px,
+                    qty,
+                    levels: Vec::new(),
+                }
+            }
+            (d, _) => d,
+        }
+    }
+}
+
+/// Like [`OraclePolicy`], but limit orders, too, only trigger while last
+/// is within `band_bps` of the index.
+#[derive(Debug, Clone, Copy)]
+pub struct BandProtectedPolicy {
+    pub inner: OraclePolicy,
+    pub band_bps: Decimal,
+}
+
+impl MatchPolicy for BandProtectedPolicy {
+    fn name(&self) -> &'static str {
+        "band_protected"
+    }
+
+    fn trigger(&self, o: &Order, view: &MarketView) -> Result<Decimal, &'static str> {
+        if !view.within_band(self.band_bps) {
+            return Err("last outside band around index");
+        }
+        self.inner.trigger(o, view)
+    }
+
+    fn execute(&self, o: &Order, view: &MarketView, ref_px: Decimal) -> Decision {
+        self.inner.execute(o, view, ref_px)
+    }
+}
+
+/// Like [`OraclePolicy`], but a triggered order other than FOK on a pair
+/// with simulated depth fills only what the depth offers within
+/// `fok_max_slippage_bps` per tick, one fill per level walked, and keeps
+/// the rest working; an IOC order's rest is cancelled.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PartialFillPolicy(pub OraclePolicy);
+
+impl MatchPolicy for PartialFillPolicy {
+    fn name(&self) -> &'static str {
+        "partial_fill"
+    }
+
+    fn trigger(&self, o: &Order, view: &MarketView) -> Result<Decimal, &'static str> {
+        self.0.trigger(o, view)
+    }
+
+    fn execute(&self, o: &Order, view: &MarketView, ref_px: Decimal) -> Decision {
+        let Some(curve) = view
+            .liquidity
+            .as_ref()
+            .filter(|_| o.time_in_force != TimeInForce::Fok)
+        else {
+            return self.0.execute(o, view, ref_px);
+        };
+        let max_bps = o.max_slippage_bps.unwrap_or(self.0.fok_max_slippage_bps);
+        let sweep = curve.sweep(&o.side, view.last, remaining(o), max_bps);
+        match sweep.vwap() {
+            Some(exec_px) => Decision::Fill {
+                ref_px,
+                exec_px,
+                qty: sweep.filled,
+                levels: sweep.fills,
+            },
+            None => Decision::waiting(o, "no simulated depth within slippage bound"),
+        }
+    }
+}
+
+/// Runs `base`, except for orders whose pair or account has a [`Feature`]
+/// turned on, which get that feature's policy instead. With both on,
+/// partial fills win. Flags are read once per order per tick.
+#[derive(Debug)]
+pub struct FlaggedPolicy {
+    base: Box<dyn MatchPolicy>,
+    partial: PartialFillPolicy,
+    bid_ask: BidAskPolicy,
+    flags: FeatureFlags,
+}
+
+impl FlaggedPolicy {
+    pub fn new(base: Box<dyn MatchPolicy>, oracle: OraclePolicy, flags: FeatureFlags) -> Self {
+        Self {
+            base,
+            partial: PartialFillPolicy(oracle),
+            bid_ask: BidAskPolicy(oracle),
+            flags,
+        }
+    }
+
+    fn pick(&self, o: &Order) -> &dyn MatchPolicy {
+        let on = |f| self.flags.is_enabled(f, &o.pair, o.account_id.as_deref());
+        if on(Feature::PartialFill) {
+            &self.partial
+        } else if on(Feature::BidAsk) {
+            &self.bid_ask
+        } else {
+            self.base.as_ref()
+        }
+    }
+}
+
+impl MatchPolicy for FlaggedPolicy {
+    fn name(&self) -> &'static str {
+        self.base.name()
+    }
+
+    fn trigger(&self, o: &Order, view: &MarketView) -> Result<Decimal, &'static str> {
+        self.pick(o).trigger(o, view)
+    }
+
+    fn execute(&self, o: &Order, view: &MarketView, ref_px: Decimal) -> Decision {
+        self.pick(o).execute(o, view, ref_px)
+    }
+
+    fn evaluate(&self, o: &Order, view: &MarketView) -> Decision {
+        self.pick(o).evaluate(o, view)
+    }
+}
+
+/// Which [`MatchPolicy`] the matchers run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PolicyKind {
+    #[default]
+    Oracle,
+    BidAsk,
+    BandProtected,
+    PartialFill,
+}
+
+impl FromStr for PolicyKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "oracle" => Ok(Self::Oracle),
+            "bid_ask" => Ok(Self::BidAsk),
+            "band_protected" => Ok(Self::BandProtected),
+            "partial_fill" => Ok(Self::PartialFill),
+            other => Err(format!("unknown match policy '{other}'")),
+        }
+    }
+}
+
+impl PolicyKind {
+    /// Builds the policy. `band_bps` bounds limit orders under
+    /// `band_protected`; the other parameters are the oracle policy's.
+    pub fn build(self, base: OraclePolicy, band_bps: Decimal) -> Box<dyn MatchPolicy> {
+        match self {
+            Self::Oracle => Box::new(base),
+            Self::BidAsk => Box::new(BidAskPolicy(base)),
+            Self::BandProtected => Box::new(BandProtectedPolicy {
+                inner: base,
+                band_bps,
+            }),
+            Self::PartialFill => Box::new(PartialFillPolicy(base)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::flags::Scope;
+    use crate::instruments::liquidity::LiquidityCurve;
+
+    fn open_buy(px: Decimal, qty: Decimal) -> Order {
+        Order {
+            status: OrderStatus::Open,
+            ..Order::new("BTC/USDT".into(), OrderSide::Buy, px, qty)
+        }
+    }
+
+    #[test]
+    fn policies_share_triggers_and_differ_in_execution() {
+        let o = open_buy(dec!(100), dec!(3));
+        let mut view = MarketView {
+            bid: Some(dec!(98.9)),
+            ask: Some(dec!(99.1)),
+            index: Some(dec!(90)),
+            ..MarketView::from_last(dec!(99), o.created)
+        };
+        let fill = |exec_px, qty| Decision::Fill {
+            ref_px: dec!(99),
+            exec_px,
+            qty,
+            levels: Vec::new(),
+        };
+        assert_eq!(
+            OraclePolicy::default().evaluate(&o, &view),
+            fill(dec!(99), dec!(3))
+        );
+        assert_eq!(
+            BidAskPolicy::default().evaluate(&o, &view),
+            fill(dec!(99.1), dec!(3))
+        );
+        let banded = PolicyKind::BandProtected.build(OraclePolicy::default(), dec!(500));
+        assert_eq!(
+            banded.evaluate(&o, &view),
+            Decision::Hold("last outside band around index")
+        );
+
+        view.liquidity = LiquidityCurve::parse("10:1,40:1,90:5");
+        let partial = PartialFillPolicy::default().evaluate(&o, &view);
+        let Decision::Fill {
+            qty,
+            exec_px,
+            levels,
+            ..
+        } = partial
+        else {
+            panic!("expected a partial fill, got {partial:?}");
+        };
+        assert_eq!(qty, dec!(2));
+        assert!(exec_px > dec!(99));
+        assert_eq!(levels.len(), 2);
+        assert!(levels[1].price > levels[0].price);
+    }
+
+    #[test]
+    fn flags_swap_the_policy_per_pair_and_account() {
+        let flags = FeatureFlags::parse("partial_fill=BTC/USDT;bid_ask=account:desk").unwrap();
+        let policy = FlaggedPolicy::new(
+            Box::new(OraclePolicy::default()),
+            OraclePolicy::default(),
+            flags.clone(),
+        );
+        let btc = open_buy(dec!(100), dec!(3));
+        let eth = Order {
+            pair: "ETH/USDT".into(),
+            ..btc.clone()
+        };
+        let desk = Order {
+            account_id: Some("desk".into()),
+            ..eth.clone()
+        };
+        let view = MarketView {
+            bid: Some(dec!(98.9)),
+            ask: Some(dec!(99.1)),
+            liquidity: LiquidityCurve::parse("10:1,40:1,90:5"),
+            ..MarketView::from_last(dec!(99), btc.created)
+        };
+        let filled = |o: &Order| match policy.evaluate(o, &view) {
+            Decision::Fill { exec_px, qty, .. } => (exec_px, qty),
+            d => panic!("expected a fill, got {d:?}"),
+        };
+        assert_eq!(policy.name(), "oracle");
+        assert_eq!(filled(&eth), (dec!(99), dec!(3)));
+        assert_eq!(filled(&desk), (dec!(99.1), dec!(3)));
+        assert_eq!(filled(&btc).1, dec!(2));
+
+        flags.set(Feature::PartialFill, Scope::Pair("BTC/USDT".into()), false);
+        assert_eq!(filled(&btc), (dec!(99), dec!(3)));
+    }
+
+    #[test]
+    fn untriggered_new_orders_are_promoted() {
+        let mut o = open_buy(dec!(100), dec!(1));
+        let view = MarketView::from_last(dec!(101), o.created);
+        assert_eq!(
+            OraclePolicy::default().evaluate(&o, &view),
+            Decision::Hold("not crossing")
+        );
+        o.status = OrderStatus::New;
+        assert_eq!(
+            OraclePolicy::default().evaluate(&o, &view),
+            Decision::Promote("not crossing")
+        );
+        assert_eq!("Bid_Ask".parse(), Ok(PolicyKind::BidAsk));
+        assert!("best".parse::<PolicyKind>().is_err());
+    }
+}
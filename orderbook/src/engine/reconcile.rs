@@ -0,0 +1,237 @@
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::entities::order::OrderStatus;
+use crate::repositories::OrderRepository;
+
+/// What a downstream settlement system says happened to an order.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ExternalState {
+    pub order_id: String,
+    pub external_status: OrderStatus,
+    pub external_fill_qty: Decimal,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Correction {
+    pub order_id: String,
+    pub from: OrderStatus,
+    pub to: OrderStatus,
+    #[serde(serialize_with = "crate::utils::decimal::serialize")]
+    pub filled_quantity: Decimal,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Conflict {
+    pub order_id: String,
+    pub reason: &'static str,
+    pub local_status: Option<OrderStatus>,
+    pub external_status: OrderStatus,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct ReconcileReport {
+    pub corrected: Vec<Correction>,
+    pub in_sync: Vec<String>,
+    pub conflicts: Vec<Conflict>,
+}
+
+/// Whether a fill quantity is consistent with a status on an order of
+/// `quantity`.
+fn fill_matches(status: &OrderStatus, fill: Decimal, quantity: Decimal) -> bool {
+    match status {
+        OrderStatus::New | OrderStatus::Open | OrderStatus::Pending => fill.is_zero(),
+        OrderStatus::PartiallyFilled => fill > Decimal::ZERO && fill < quantity,
+        OrderStatus::Filled => fill == quantity,
+        OrderStatus::Cancelled | OrderStatus::Suspended | OrderStatus::Expired => fill < quantity,
+    }
+}
+
+/// Brings local orders in line with settled state. Each entry is either
+/// applied, already in sync, or reported as a conflict and left untouched:
+/// unknown orders, fills outside `0..=quantity` or smaller than what is
+/// already recorded, a status that contradicts the fill, and transitions
+/// the lifecycle does not allow.
+pub async fn reconcile<R: OrderRepository + ?Sized>(
+    repo: &R,
+    entries: &[ExternalState],
+) -> ReconcileReport {
+    let mut out = ReconcileReport::default();
+    for e in entries {
+        let conflict = |reason, local_status| Conflict {
+            order_id: e.order_id.clone(),
+            reason,
+            local_status,
+            external_status: e.external_status.clone(),
+        };
+        let Ok(local) = repo.get_by_id(&e.order_id).await else {
+            out.conflicts.push(conflict("unknown_order", None));
+            continue;
+        };
+        let status = Some(local.status.clone());
+        let fill = e.external_fill_qty;
+        if fill < Decimal::ZERO || fill > local.quantity {
+            out.conflicts.push(conflict("fill_out_of_range", status));
+            continue;
+        }
+        if !fill_matches(&e.external_status, fill, local.quantity) {
+            out.conflicts.push(conflict("status_fill_mismatch", status));
+            continue;
+        }
+        if local.status == e.external_status && local.filled_quantity == fill {
+            out.in_sync.push(e.order_id.clone());
+            continue;
+        }
+        if fill < local.filled_quantity {
+            out.conflicts.push(conflict("fill_regression", status));
+            continue;
+        }
+        if local.status != e.external_status && !local.status.can_transition_to(&e.external_status)
+            || local.status == e.external_status && !local.status.is_active()
+        {
+            out.conflicts.push(conflict("illegal_transition", status));
+            continue;
+        }
+        match repo
+            .set_fill(&e.order_id, e.external_status.clone(), fill)
+            .await
+        {
+            Ok(o) => out.corrected.push(Correction {
+                order_id: o.id,
+                from: local.status,
+                to: o.status,
+                filled_quantity: o.filled_quantity,
+            }),
+            Err(_) => out.conflicts.push(conflict("update_failed", status)),
+        }
+    }
+    if !out.conflicts.is_empty() {
+        warn!(conflicts = out.conflicts.len(), "reconciliation conflicts");
+    }
+    info!(
+        corrected = out.corrected.len(),
+        in_sync = out.in_sync.len(),
+        conflicts = out.conflicts.len(),
+        "reconciliation applied"
+    );
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entities::order::{
+        Condition, NewOrder, Order, OrderSide, OrderType, TimeInForce, TriggerPriceType,
+    };
+    use crate::repositories::in_memory::InMemoryOrderRepository;
+    use crate::repositories::ListOrdersQuery;
+    use rust_decimal_macros::dec;
+
+    fn ext(id: &str, status: OrderStatus, fill: Decimal) -> ExternalState {
+        ExternalState {
+            order_id: id.into(),
+            external_status: status,
+            external_fill_qty: fill,
+        }
+    }
+
+    async fn place(repo: &InMemoryOrderRepository, status: OrderStatus) -> Order {
+        let created = repo
+            .create(NewOrder {
+                pair: "BTC/USDT".into(),
+                side: OrderSide::Buy,
+                order_type: OrderType::Limit,
+                price: dec!(1),
+                quantity: dec!(2),
+                trigger_price_type: TriggerPriceType::Last,
+                condition: Condition::Price,
+                sustained_for_ms: None,
+                time_in_force: TimeInForce::Gtc,
+                max_slippage_bps: None,
+                exec_pricing: None,
+                price_source: None,
+                account_id: None,
+                quantity_quote: None,
+                cancel_after_ms: None,
+                valid_for_after_armed_ms: None,
+                expires_at: None,
+                tags: Vec::new(),
+                metadata: Default::default(),
+                callback_url: None,
+                callback_template: None,
+                trigger_price: None,
+                trailing: None,
+                oco_id: None,
+            })
+            .await
+            .unwrap();
+        repo.set_status(&created.id, status).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn applies_forward_corrections_and_reports_conflicts() {
+        let repo = InMemoryOrderRepository::default();
+        let open = place(&repo, OrderStatus::Open).await;
+        let partial = place(&repo, OrderStatus::Open).await;
+        let done = place(&repo, OrderStatus::Cancelled).await;
+        let same = place(&repo, OrderStatus::Open).await;
+
+        let report = reconcile(
+            &repo,
+            &[
+                ext(&open.id, OrderStatus::Filled, dec!(2)),
+                ext(&partial.id, OrderStatus::PartiallyFilled, dec!(0.5)),
+                ext(&done.id, OrderStatus::Filled, dec!(2)),
+                ext(&same.id, OrderStatus::Open, dec!(0)),
+                ext(&same.id, OrderStatus::Filled, dec!(1)),
+                ext(&same.id, OrderStatus::Filled, dec!(3)),
+                ext("missing", OrderStatus::Filled, dec!(1)),
+            ],
+        )
+        .await;
+
+        let corrected: Vec<_> = report.corrected.iter().map(|c| &c.order_id).collect();
+        assert_eq!(corrected, vec![&open.id, &partial.id]);
+        assert_eq!(report.in_sync, vec![same.id.clone()]);
+        let reasons: Vec<_> = report.conflicts.iter().map(|c| c.reason).collect();
+        assert_eq!(
+            reasons,
+            vec![
+                "illegal_transition",
+                "status_fill_mismatch",
+                "fill_out_of_range",
+                "unknown_order"
+            ]
+        );
+        let p = repo.get_by_id(&partial.id).await.unwrap();
+        assert_eq!(p.status, OrderStatus::PartiallyFilled);
+        assert_eq!(p.filled_quantity, dec!(0.5));
+        assert_eq!(
+            repo.list(ListOrdersQuery::default()).await.unwrap().len(),
+            4
+        );
+    }
+
+    #[tokio::test]
+    async fn fill_may_grow_but_not_shrink() {
+        let repo = InMemoryOrderRepository::default();
+        let o = place(&repo, OrderStatus::Open).await;
+        reconcile(
+            &repo,
+            &[ext(&o.id, OrderStatus::PartiallyFilled, dec!(1.5))],
+        )
+        .await;
+
+        let report = reconcile(
+            &repo,
+            &[
+                ext(&o.id, OrderStatus::PartiallyFilled, dec!(1)),
+                ext(&o.id, OrderStatus::PartiallyFilled, dec!(1.8)),
+            ],
+        )
+        .await;
+        assert_eq!(report.conflicts[0].reason, "fill_regression");
+        assert_eq!(report.corrected[0].filled_quantity, dec!(1.8));
+    }
+}
@@ -0,0 +1,206 @@
+use std::sync::Arc;
+
+use serde::Serialize;
+use tokio::sync::Mutex;
+use tracing::{info, warn};
+
+use crate::engine::halts::HaltRegistry;
+use crate::entities::events::MarketEvent;
+use crate::instruments::symbol::Symbol;
+use crate::instruments::InstrumentRegistry;
+use crate::oracle_service::OracleCache;
+use crate::repositories::baskets::BasketStore;
+use crate::repositories::changes::ChangeStream;
+use crate::repositories::journal::Journal;
+use crate::repositories::{ListOrdersQuery, OrderRepository};
+
+/// What a pair rename touches. A dry run reports what would change; an
+/// applied rename what did.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct RenameReport {
+    pub from: String,
+    pub to: String,
+    pub dry_run: bool,
+    /// Orders on the pair in any status.
+    pub orders: usize,
+    pub active_orders: usize,
+    /// Journal entries and snapshot orders naming the pair.
+    pub journal_entries: usize,
+    /// Latest, per-venue and history ticks cached for the pair.
+    pub oracle_ticks: usize,
+    /// Pending baskets triggering on or trading the pair.
+    pub baskets: usize,
+    pub halted: bool,
+}
+
+/// Renames a pair everywhere it lives: the instrument registry, the order
+/// store, the journal, the oracle cache, halts and pending baskets. The old
+/// spellings stay as aliases of the new name, so ticks and requests still
+/// quoting the old symbol reach the renamed pair. Renames run one at a
+/// time.
+#[derive(Clone)]
+pub struct PairRenamer {
+    instruments: InstrumentRegistry,
+    orders: Arc<dyn OrderRepository>,
+    journal: Journal,
+    oracle: OracleCache,
+    halts: HaltRegistry,
+    baskets: BasketStore,
+    events: ChangeStream,
+    lock: Arc<Mutex<()>>,
+}
+
+impl PairRenamer {
+    pub fn new(
+        instruments: InstrumentRegistry,
+        orders: Arc<dyn OrderRepository>,
+        journal: Journal,
+        oracle: OracleCache,
+        halts: HaltRegistry,
+        baskets: BasketStore,
+        events: ChangeStream,
+    ) -> Self {
+        Self {
+            instruments,
+            orders,
+            journal,
+            oracle,
+            halts,
+            baskets,
+            events,
+            lock: Arc::default(),
+        }
+    }
+
+    /// Renames `from` to `to`, or with `dry_run` only reports what would
+    /// change. Errs if either name is unusable (see
+    /// [`InstrumentRegistry::check_rename`]) or the store cannot rename, in
+    /// which case nothing has changed.
+    pub async fn rename(
+        &self,
+        from: &str,
+        to: &str,
+        dry_run: bool,
+    ) -> Result<RenameReport, String> {
+        let _one_at_a_time = self.lock.lock().await;
+        let (from, to) = self.instruments.check_rename(from, to)?;
+        let orders = self
+            .orders
+            .list(ListOrdersQuery {
+                pair: Some(from.clone()),
+                ..ListOrdersQuery::default()
+            })
+            .await?;
+        let mut report = RenameReport {
+            orders: orders.len(),
+            active_orders: orders.iter().filter(|o| o.status.is_active()).count(),
+            journal_entries: self.journal.references(&from),
+            oracle_ticks: self.oracle.cached_ticks(&from).await,
+            baskets: self.baskets.references(&from).await,
+            halted: self.halts.is_halted(&from),
+            from,
+            to,
+            dry_run,
+        };
+        if dry_run {
+            return Ok(report);
+        }
+
+        // The registry goes first so new orders and ticks already land on
+        // the new name; the store is the only step that can fail, and
+        // undoing the registry leaves nothing else to roll back.
+        let (from, to) = (report.from.clone(), Symbol::intern(&report.to));
+        self.instruments.rename(&from, &to)?;
+        let renamed = match self.orders.rename_pair(&from, &to).await {
+            Ok(renamed) => renamed,
+            Err(e) => {
+                warn!(%from, %to, err = %e, "pair rename failed; restoring the old name");
+                self.instruments.rename(&to, &from)?;
+                return Err(e);
+            }
+        };
+        report.orders = renamed.len();
+        report.active_orders = renamed.iter().filter(|o| o.status.is_active()).count();
+        report.oracle_ticks = self.oracle.rename_pair(&from, &to).await;
+        report.halted = self.halts.rename(&from, &to);
+        report.journal_entries = self.journal.rename_pair(&from, &to);
+        report.baskets = self.baskets.rename_pair(&from, &to).await;
+        info!(%from, %to, orders = report.orders, "pair renamed");
+        self.events.publish_market(MarketEvent::PairRenamed {
+            from,
+            to: to.to_string(),
+        });
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::halts::HaltSource;
+    use crate::entities::order::OrderStatus;
+    use crate::entities::test_support::OrderBuilder;
+    use crate::oracle_service::Tick;
+    use crate::repositories::changes::Observed;
+    use crate::repositories::in_memory::InMemoryOrderRepository;
+    use rust_decimal::Decimal;
+
+    #[tokio::test]
+    async fn dry_run_reports_and_apply_moves_everything_to_the_new_name() {
+        let changes = ChangeStream::default();
+        let journal = Journal::default();
+        journal.attach(&changes);
+        let repo = Observed::new(InMemoryOrderRepository::default(), changes.clone());
+        let open = repo
+            .create(OrderBuilder::buy("BTC/USDT").limit(100).new_order())
+            .await
+            .unwrap();
+        let done = repo
+            .create(OrderBuilder::sell("BTC/USDT").limit(100).new_order())
+            .await
+            .unwrap();
+        repo.set_status(&done.id, OrderStatus::Filled)
+            .await
+            .unwrap();
+        let oracle = OracleCache::default();
+        oracle
+            .set(Tick::new("BTC/USDT", Decimal::from(100), 1))
+            .await;
+        let halts = HaltRegistry::default();
+        halts.halt("BTC/USDT", HaltSource::Admin, "vendor change", 1);
+        let renamer = PairRenamer::new(
+            InstrumentRegistry::default(),
+            Arc::new(repo.clone()),
+            journal.clone(),
+            oracle.clone(),
+            halts.clone(),
+            BasketStore::default(),
+            changes,
+        );
+
+        let dry = renamer.rename("btc-usdt", "xbt/usdt", true).await.unwrap();
+        assert_eq!(
+            (dry.from.as_str(), dry.to.as_str()),
+            ("BTC/USDT", "XBT/USDT")
+        );
+        assert_eq!(
+            (dry.orders, dry.active_orders, dry.journal_entries),
+            (2, 1, 3)
+        );
+        assert!(dry.oracle_ticks > 0 && dry.halted);
+        assert_eq!(repo.get_by_id(&open.id).await.unwrap().pair, "BTC/USDT");
+
+        let applied = renamer.rename("BTC/USDT", "XBT/USDT", false).await.unwrap();
+        assert_eq!((applied.orders, applied.active_orders), (2, 1));
+        assert!(!applied.dry_run);
+        assert_eq!(repo.get_by_id(&open.id).await.unwrap().pair, "XBT/USDT");
+        assert_eq!(journal.references("BTC/USDT"), 0);
+        assert_eq!(
+            oracle.get_tick("XBT/USDT").await.unwrap().price,
+            Decimal::from(100)
+        );
+        assert!(oracle.get_tick("BTC/USDT").await.is_none());
+        assert!(halts.is_halted("XBT/USDT") && !halts.is_halted("BTC/USDT"));
+        assert!(renamer.rename("BTC/USDT", "XBT/USDT", true).await.is_err());
+    }
+}
@@ -0,0 +1,369 @@
+use std::collections::VecDeque;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use rand::Rng;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use tracing::warn;
+
+use crate::entities::order::{Fill, NewOrder, Order, OrderStatus};
+use crate::instruments::symbol::Symbol;
+use crate::repositories::{ListOrdersQuery, OrderRepository};
+use crate::utils::now_ms;
+
+/// Bounded retries with full jitter: attempt `n` waits a random time up to
+/// `base_delay * 2^n`, capped at `max_delay`.
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    /// Total tries per call, including the first.
+    pub attempts: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            attempts: 3,
+            base_delay: Duration::from_millis(20),
+            max_delay: Duration::from_millis(500),
+        }
+    }
+}
+
+impl RetryPolicy {
+    fn delay(&self, attempt: u32) -> Duration {
+        let cap = self
+            .base_delay
+            .saturating_mul(1 << attempt.min(16))
+            .min(self.max_delay);
+        let ms = cap.as_millis() as u64;
+        Duration::from_millis(rand::thread_rng().gen_range(0..=ms))
+    }
+
+    pub async fn run<T, F, Fut>(&self, mut call: F) -> Result<T, String>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, String>>,
+    {
+        let mut attempt = 0;
+        loop {
+            match call().await {
+                Err(e) if attempt + 1 < self.attempts && is_transient(&e) => {
+                    tokio::time::sleep(self.delay(attempt)).await;
+                    attempt += 1;
+                }
+                r => return r,
+            }
+        }
+    }
+}
+
+/// Whether retrying could help. A missing order stays missing.
+fn is_transient(err: &str) -> bool {
+    err != "not found"
+}
+
+/// When a pair's matcher pauses: more than `max_error_rate` of at least
+/// `min_calls` repository calls within `window` failed.
+#[derive(Debug, Clone, Copy)]
+pub struct ErrorBudgetConfig {
+    pub window: Duration,
+    pub min_calls: usize,
+    pub max_error_rate: Decimal,
+    pub pause: Duration,
+}
+
+impl Default for ErrorBudgetConfig {
+    fn default() -> Self {
+        Self {
+            window: Duration::from_secs(60),
+            min_calls: 10,
+            max_error_rate: dec!(0.5),
+            pause: Duration::from_secs(30),
+        }
+    }
+}
+
+struct BudgetInner {
+    calls: VecDeque<(i64, bool)>,
+    paused_until: Option<i64>,
+}
+
+/// Sliding-window error rate of one pair's repository calls, with a
+/// breaker that opens for `pause` once the budget is spent.
+#[derive(Clone)]
+pub struct ErrorBudget {
+    cfg: ErrorBudgetConfig,
+    inner: Arc<Mutex<BudgetInner>>,
+}
+
+impl ErrorBudget {
+    pub fn new(cfg: ErrorBudgetConfig) -> Self {
+        Self {
+            cfg,
+            inner: Arc::new(Mutex::new(BudgetInner {
+                calls: VecDeque::new(),
+                paused_until: None,
+            })),
+        }
+    }
+
+    /// Records a call outcome; returns `true` when it opens the breaker.
+    pub fn record(&self, ok: bool, now_ms: i64) -> bool {
+        let mut g = self.inner.lock().unwrap();
+        let horizon = now_ms - self.cfg.window.as_millis() as i64;
+        while g.calls.front().is_some_and(|(ts, _)| *ts < horizon) {
+            g.calls.pop_front();
+        }
+        g.calls.push_back((now_ms, ok));
+        if ok || g.paused_until.is_some() || g.calls.len() < self.cfg.min_calls {
+            return false;
+        }
+        let failed = g.calls.iter().filter(|(_, ok)| !ok).count();
+        if Decimal::from(failed) <= self.cfg.max_error_rate * Decimal::from(g.calls.len()) {
+            return false;
+        }
+        g.paused_until = Some(now_ms + self.cfg.pause.as_millis() as i64);
+        true
+    }
+
+    /// Whether the breaker is open. Once the pause is over it closes with a
+    /// fresh window.
+    pub fn is_paused(&self, now_ms: i64) -> bool {
+        let mut g = self.inner.lock().unwrap();
+        match g.paused_until {
+            Some(until) if now_ms < until => true,
+            Some(_) => {
+                g.paused_until = None;
+                g.calls.clear();
+                false
+            }
+            None => false,
+        }
+    }
+}
+
+/// Repository wrapper for a matcher worker: retries transient failures of
+/// the engine's calls and charges their final outcome to the pair's budget.
+#[derive(Clone)]
+pub struct Resilient<R> {
+    inner: R,
+    policy: RetryPolicy,
+    budget: ErrorBudget,
+    pair: String,
+}
+
+impl<R: OrderRepository> Resilient<R> {
+    pub fn new(inner: R, pair: &str, policy: RetryPolicy, budget: ErrorBudget) -> Self {
+        Self {
+            inner,
+            policy,
+            budget,
+            pair: pair.to_string(),
+        }
+    }
+
+    pub fn budget(&self) -> &ErrorBudget {
+        &self.budget
+    }
+
+    fn charge<T>(&self, r: Result<T, String>) -> Result<T, String> {
+        if self.budget.record(r.is_ok(), now_ms()) {
+            warn!(pair = %self.pair, pause_ms = self.budget.cfg.pause.as_millis() as u64, "error budget exhausted; pausing matcher");
+        }
+        r
+    }
+}
+
+#[async_trait]
+impl<R: OrderRepository> OrderRepository for Resilient<R> {
+    async fn create(&self, new: NewOrder) -> Result<Order, String> {
+        self.inner.create(new).await
+    }
+
+    async fn get_by_id(&self, id: &str) -> Result<Order, String> {
+        self.inner.get_by_id(id).await
+    }
+
+    async fn list(&self, q: ListOrdersQuery) -> Result<Vec<Order>, String> {
+        let r = self.policy.run(|| self.inner.list(q.clone())).await;
+        self.charge(r)
+    }
+
+    async fn set_status(&self, id: &str, status: OrderStatus) -> Result<Order, String> {
+        let r = self
+            .policy
+            .run(|| self.inner.set_status(id, status.clone()))
+            .await;
+        self.charge(r)
+    }
+
+    async fn record_fill(&self, id: &str, exec_price: Decimal) -> Result<Order, String> {
+        let r = self
+            .policy
+            .run(|| self.inner.record_fill(id, exec_price))
+            .await;
+        self.charge(r)
+    }
+
+    async fn record_fills(&self, id: &str, fills: &[Fill]) -> Result<Order, String> {
+        let r = self.policy.run(|| self.inner.record_fills(id, fills)).await;
+        self.charge(r)
+    }
+
+    async fn set_fill(
+        &self,
+        id: &str,
+        status: OrderStatus,
+        filled_quantity: Decimal,
+    ) -> Result<Order, String> {
+        self.inner.set_fill(id, status, filled_quantity).await
+    }
+
+    async fn cancel(&self, id: &str, reason: &str) -> Result<Order, String> {
+        let r = self.policy.run(|| self.inner.cancel(id, reason)).await;
+        self.charge(r)
+    }
+
+    async fn amend(&self, id: &str, price: Decimal, quantity: Decimal) -> Result<Order, String> {
+        self.inner.amend(id, price, quantity).await
+    }
+
+    async fn delete(&self, id: &str) -> Result<(), String> {
+        self.inner.delete(id).await
+    }
+
+    async fn rename_pair(&self, from: &str, to: &Symbol) -> Result<Vec<Order>, String> {
+        self.inner.rename_pair(from, to).await
+    }
+
+    async fn replace(&self, id: &str, new: NewOrder) -> Result<(Order, Order), String> {
+        self.inner.replace(id, new).await
+    }
+
+    async fn cross(&self, fills: &[(String, Vec<Fill>)]) -> Result<Vec<Order>, String> {
+        let r = self.inner.cross(fills).await;
+        self.charge(r)
+    }
+
+    async fn activate_stop(&self, id: &str) -> Result<Order, String> {
+        let r = self.policy.run(|| self.inner.activate_stop(id)).await;
+        self.charge(r)
+    }
+
+    async fn create_bracket(
+        &self,
+        entry: NewOrder,
+        children: Vec<NewOrder>,
+    ) -> Result<(Order, Vec<Order>), String> {
+        self.inner.create_bracket(entry, children).await
+    }
+
+    async fn fill_oco(&self, id: &str, fills: &[Fill]) -> Result<(Order, Vec<Order>), String> {
+        let r = self.policy.run(|| self.inner.fill_oco(id, fills)).await;
+        self.charge(r)
+    }
+
+    async fn trail_stop(
+        &self,
+        id: &str,
+        watermark: Decimal,
+        trigger_price: Decimal,
+    ) -> Result<Order, String> {
+        let r = self
+            .policy
+            .run(|| self.inner.trail_stop(id, watermark, trigger_price))
+            .await;
+        self.charge(r)
+    }
+
+    async fn expire(&self, id: &str, now_ms: i64) -> Result<Order, String> {
+        let r = self.policy.run(|| self.inner.expire(id, now_ms)).await;
+        self.charge(r)
+    }
+
+    async fn anonymize(&self, id: &str) -> Result<Order, String> {
+        self.inner.anonymize(id).await
+    }
+
+    fn replication_lag_ms(&self) -> Option<i64> {
+        self.inner.replication_lag_ms()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    #[tokio::test]
+    async fn retries_transient_errors_only() {
+        let policy = RetryPolicy {
+            attempts: 3,
+            base_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(2),
+        };
+        let calls = AtomicU32::new(0);
+        let r = policy
+            .run(|| async {
+                match calls.fetch_add(1, Ordering::SeqCst) {
+                    0 | 1 => Err("connection reset".to_string()),
+                    n => Ok(n),
+                }
+            })
+            .await;
+        assert_eq!(r, Ok(2));
+
+        calls.store(0, Ordering::SeqCst);
+        let r: Result<(), String> = policy
+            .run(|| async {
+                calls.fetch_add(1, Ordering::SeqCst);
+                Err("connection reset".to_string())
+            })
+            .await;
+        assert!(r.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+
+        calls.store(0, Ordering::SeqCst);
+        let r: Result<(), String> = policy
+            .run(|| async {
+                calls.fetch_add(1, Ordering::SeqCst);
+                Err("not found".to_string())
+            })
+            .await;
+        assert!(r.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn budget_trips_over_threshold_and_recovers_after_pause() {
+        let b = ErrorBudget::new(ErrorBudgetConfig {
+            window: Duration::from_secs(10),
+            min_calls: 4,
+            max_error_rate: dec!(0.5),
+            pause: Duration::from_secs(5),
+        });
+        assert!(!b.record(true, 0));
+        assert!(!b.record(false, 1));
+        assert!(!b.record(true, 2));
+        assert!(!b.record(false, 3));
+        assert!(!b.is_paused(3));
+        assert!(b.record(false, 4));
+        assert!(!b.record(false, 5));
+        assert!(b.is_paused(4_000));
+        assert!(!b.is_paused(5_004));
+
+        // Failures that aged out of the window no longer count.
+        for ts in [6_000, 7_000, 8_000] {
+            assert!(!b.record(false, ts));
+        }
+        for ts in [19_000, 19_001, 19_002] {
+            assert!(!b.record(true, ts));
+        }
+        assert!(!b.record(false, 19_003));
+    }
+}
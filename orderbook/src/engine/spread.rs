@@ -0,0 +1,138 @@
+use std::collections::{HashMap, HashSet};
+
+use rust_decimal::Decimal;
+
+use super::feed::MarketFeed;
+use crate::entities::order::{Condition, LegPrice, SpreadMeasure};
+use crate::instruments::symbol::Symbol;
+use crate::oracle_service::OracleCache;
+
+/// The latest fresh price of every pair a spread condition reads, as of
+/// this tick.
+pub type Legs = HashMap<Symbol, LegPrice>;
+
+/// The price of one pair in terms of another: `a / b` for a ratio (e.g.
+/// ETH/BTC from ETH/USDT and BTC/USDT), `a - b` for a difference. `None`
+/// for a ratio over zero.
+pub fn cross_price(measure: SpreadMeasure, a: Decimal, b: Decimal) -> Option<Decimal> {
+    match measure {
+        SpreadMeasure::Ratio if b.is_zero() => None,
+        SpreadMeasure::Ratio => Some(a / b),
+        SpreadMeasure::Difference => Some(a - b),
+    }
+}
+
+/// The pairs a condition reads, if it is a spread condition.
+pub fn required(cond: &Condition) -> &[Symbol] {
+    match cond {
+        Condition::Spread { legs, .. } => legs,
+        _ => &[],
+    }
+}
+
+/// Each leg's latest tick on the feed, skipping legs with no tick or a
+/// stale one.
+pub fn gather(
+    oracle: &OracleCache,
+    feed: &MarketFeed,
+    pairs: &HashSet<Symbol>,
+    now_ms: i64,
+) -> Legs {
+    let mut legs = Legs::new();
+    for pair in pairs {
+        let Some(tick) = feed.tick(pair) else {
+            continue;
+        };
+        if oracle.is_fresh(tick, now_ms) {
+            let leg = LegPrice {
+                pair: pair.clone(),
+                price: tick.price,
+                ts_ms: tick.ts_ms,
+            };
+            legs.insert(pair.clone(), leg);
+        }
+    }
+    legs
+}
+
+/// Whether a spread condition's cross price is strictly below and/or above
+/// its bounds. Errs with a hold reason while a leg has no fresh price.
+pub fn holds(cond: &Condition, legs: &Legs) -> Result<bool, &'static str> {
+    let Condition::Spread {
+        legs: [a, b],
+        measure,
+        below,
+        above,
+    } = cond
+    else {
+        return Ok(false);
+    };
+    let (Some(a), Some(b)) = (legs.get(a), legs.get(b)) else {
+        return Err("spread leg price unavailable");
+    };
+    let v = cross_price(*measure, a.price, b.price).ok_or("spread undefined")?;
+    Ok(below.is_none_or(|lo| v < lo) && above.is_none_or(|hi| v > hi))
+}
+
+/// The leg prices a spread order filled on, in leg order.
+pub fn quotes(cond: &Condition, legs: &Legs) -> Vec<LegPrice> {
+    required(cond)
+        .iter()
+        .filter_map(|p| legs.get(p).cloned())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn ratio_and_difference_spreads_compare_with_their_bounds() {
+        let leg = |pair: &str, price| {
+            let pair = Symbol::intern(pair);
+            let leg = LegPrice {
+                pair: pair.clone(),
+                price,
+                ts_ms: 1,
+            };
+            (pair, leg)
+        };
+        let legs: Legs = [leg("ETH/USDT", dec!(2900)), leg("BTC/USDT", dec!(60000))].into();
+        let spread = |measure, below, above| Condition::Spread {
+            legs: ["ETH/USDT".into(), "BTC/USDT".into()],
+            measure,
+            below,
+            above,
+        };
+
+        // 2900 / 60000 = 0.04833...
+        assert_eq!(
+            holds(&spread(SpreadMeasure::Ratio, Some(dec!(0.05)), None), &legs),
+            Ok(true)
+        );
+        assert_eq!(
+            holds(
+                &spread(SpreadMeasure::Ratio, Some(dec!(0.048)), None),
+                &legs
+            ),
+            Ok(false)
+        );
+        assert_eq!(
+            holds(
+                &spread(SpreadMeasure::Difference, None, Some(dec!(-57200))),
+                &legs
+            ),
+            Ok(true)
+        );
+        let one_leg: Legs = [leg("ETH/USDT", dec!(2900))].into();
+        let cond = spread(SpreadMeasure::Ratio, Some(dec!(1)), None);
+        assert!(holds(&cond, &one_leg).is_err());
+        assert_eq!(quotes(&cond, &legs).len(), 2);
+        assert_eq!(quotes(&cond, &legs)[1].price, dec!(60000));
+        assert_eq!(
+            cross_price(SpreadMeasure::Ratio, dec!(1), Decimal::ZERO),
+            None
+        );
+    }
+}
@@ -0,0 +1,153 @@
+use tracing::{debug, error, info};
+
+use super::{write_failed, Controls, MarketView};
+use crate::entities::order::Order;
+use crate::repositories::OrderRepository;
+
+/// Whether the view's reference for `o` has reached its stop level. Orders
+/// that are not stops, and views without the reference, never do.
+pub fn triggered(o: &Order, view: &MarketView) -> bool {
+    o.order_type.is_stop()
+        && view
+            .reference(o.trigger_price_type)
+            .is_some_and(|px| o.stop_hit(px))
+}
+
+/// Activates the stops among `orders` whose trigger price the view has
+/// reached, leaving out those still waiting for it. Trailing stops first
+/// have their watermark and trigger moved to the view's reference and
+/// written. Returns the orders for the oracle pass, activated ones as
+/// written, and how many were activated.
+pub async fn run<R: OrderRepository>(
+    asset: &str,
+    repo: &R,
+    ctl: &Controls,
+    orders: Vec<Order>,
+    view: &MarketView,
+) -> (Vec<Order>, usize) {
+    let mut out = Vec::with_capacity(orders.len());
+    let mut activated = 0;
+    for o in orders {
+        if !o.order_type.is_stop() {
+            out.push(o);
+            continue;
+        }
+        if ctl.dead_letters.is_suspended(&o.id) {
+            continue;
+        }
+        let moved = view
+            .reference(o.trigger_price_type)
+            .and_then(|px| o.trail(px));
+        let o = match moved {
+            None => o,
+            Some((wm, stop)) => match repo.trail_stop(&o.id, wm, stop).await {
+                Ok(t) => {
+                    debug!(%asset, order_id = %t.id, watermark = %wm, stop_px = %stop, "stop trailed");
+                    t
+                }
+                Err(e) => {
+                    error!(%asset, order_id = %o.id, err = %e, "failed to trail stop");
+                    write_failed(repo, ctl, &o, "trail_stop", &e).await;
+                    continue;
+                }
+            },
+        };
+        if !triggered(&o, view) {
+            continue;
+        }
+        match repo.activate_stop(&o.id).await {
+            Ok(a) => {
+                info!(%asset, order_id = %a.id, stop_px = ?a.trigger_price, order_type = ?a.order_type, "STOP");
+                ctl.dead_letters.record_success(&a.id);
+                activated += 1;
+                out.push(a);
+            }
+            Err(e) => {
+                error!(%asset, order_id = %o.id, err = %e, "failed to activate stop");
+                write_failed(repo, ctl, &o, "activate_stop", &e).await;
+            }
+        }
+    }
+    (out, activated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entities::order::{OrderType, Trail};
+    use crate::entities::test_support::OrderBuilder;
+    use crate::repositories::in_memory::InMemoryOrderRepository;
+    use crate::utils::now_ms;
+    use rust_decimal_macros::dec;
+
+    #[tokio::test]
+    async fn stops_activate_once_the_reference_reaches_them() {
+        let repo = InMemoryOrderRepository::default();
+        let ctl = Controls::default();
+        let mut orders = Vec::new();
+        for b in [
+            OrderBuilder::buy("BTC/USDT").qty(1).stop(105),
+            OrderBuilder::sell("BTC/USDT").qty(1).stop_limit(94, 95),
+            OrderBuilder::buy("BTC/USDT").limit(90).qty(1),
+        ] {
+            orders.push(repo.create(b.new_order()).await.unwrap());
+        }
+        let (ids, now) = (
+            orders.iter().map(|o| o.id.clone()).collect::<Vec<_>>(),
+            now_ms(),
+        );
+
+        let view = MarketView::from_last(dec!(100), now);
+        let (out, n) = run("BTC/USDT", &repo, &ctl, orders.clone(), &view).await;
+        assert_eq!((n, out.len()), (0, 1));
+        assert_eq!(out[0].id, ids[2]);
+
+        let view = MarketView::from_last(dec!(105), now);
+        let (out, n) = run("BTC/USDT", &repo, &ctl, orders.clone(), &view).await;
+        assert_eq!(n, 1);
+        assert_eq!(
+            out.iter().map(|o| o.order_type).collect::<Vec<_>>(),
+            [OrderType::Market, OrderType::Limit]
+        );
+
+        let view = MarketView::from_last(dec!(95), now);
+        let (out, n) = run("BTC/USDT", &repo, &ctl, orders, &view).await;
+        assert_eq!((n, out.len()), (1, 2));
+        let s = repo.get_by_id(&ids[1]).await.unwrap();
+        assert_eq!((s.order_type, s.price), (OrderType::Limit, dec!(94)));
+        assert!(s.stop_triggered_ms.is_some());
+        // An order that is no longer a stop cannot be activated again.
+        assert!(repo.activate_stop(&ids[1]).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn trailing_stops_follow_the_reference_and_go_off_on_a_reversal() {
+        let repo = InMemoryOrderRepository::default();
+        let ctl = Controls::default();
+        let b = OrderBuilder::sell("BTC/USDT")
+            .qty(1)
+            .trailing_stop(Trail::Amount(dec!(5)));
+        let id = repo.create(b.new_order()).await.unwrap().id;
+        let now = now_ms();
+
+        for (px, wm, stop) in [
+            (dec!(100), dec!(100), dec!(95)),
+            (dec!(110), dec!(110), dec!(105)),
+            (dec!(107), dec!(110), dec!(105)),
+        ] {
+            let orders = vec![repo.get_by_id(&id).await.unwrap()];
+            let view = MarketView::from_last(px, now);
+            let (out, n) = run("BTC/USDT", &repo, &ctl, orders, &view).await;
+            assert_eq!((n, out.len()), (0, 0));
+            let o = repo.get_by_id(&id).await.unwrap();
+            assert_eq!((o.watermark, o.trigger_price), (Some(wm), Some(stop)));
+        }
+
+        let orders = vec![repo.get_by_id(&id).await.unwrap()];
+        let view = MarketView::from_last(dec!(104), now);
+        let (out, n) = run("BTC/USDT", &repo, &ctl, orders, &view).await;
+        assert_eq!(n, 1);
+        assert_eq!(out[0].order_type, OrderType::Market);
+        assert_eq!(out[0].trigger_price, Some(dec!(105)));
+    }
+}
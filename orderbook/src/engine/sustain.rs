@@ -0,0 +1,94 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::entities::order::Order;
+
+use super::policy::Decision;
+
+/// When each order with a `sustained_for_ms` modifier was first seen
+/// triggering, in oracle time. Owned by one matcher worker.
+#[derive(Debug, Default)]
+pub struct SustainTimers {
+    since: HashMap<String, i64>,
+}
+
+impl SustainTimers {
+    /// Holds a triggering order until its trigger has held continuously for
+    /// `sustained_for_ms`. Any tick on which it stops holding resets the
+    /// timer.
+    pub(super) fn gate(&mut self, o: &Order, ts_ms: i64, decision: Decision) -> Decision {
+        let Some(need) = o.sustained_for_ms else {
+            return decision;
+        };
+        if decision.is_waiting() {
+            self.since.remove(&o.id);
+            return decision;
+        }
+        let first = *self.since.entry(o.id.clone()).or_insert(ts_ms);
+        if ts_ms - first < need {
+            return Decision::waiting(o, "sustaining trigger");
+        }
+        self.since.remove(&o.id);
+        decision
+    }
+
+    pub fn first_crossing(&self, order_id: &str) -> Option<i64> {
+        self.since.get(order_id).copied()
+    }
+
+    /// Drops timers of orders that are no longer active.
+    pub fn retain(&mut self, active: &HashSet<&str>) {
+        self.since.retain(|id, _| active.contains(id.as_str()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entities::order::OrderSide;
+    use rust_decimal_macros::dec;
+
+    fn fill() -> Decision {
+        Decision::Fill {
+            ref_px: dec!(1),
+            exec_px: dec!(1),
+            qty: dec!(1),
+            levels: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn fills_after_duration_and_resets_on_recross() {
+        let o = Order {
+            sustained_for_ms: Some(1_000),
+            ..Order::new("BTC/USDT".into(), OrderSide::Buy, dec!(1), dec!(1))
+        };
+        let mut t = SustainTimers::default();
+        assert_eq!(
+            t.gate(&o, 0, fill()),
+            Decision::Promote("sustaining trigger")
+        );
+        assert_eq!(
+            t.gate(&o, 999, fill()),
+            Decision::Promote("sustaining trigger")
+        );
+        assert_eq!(
+            t.gate(&o, 1_000, Decision::Promote("not crossing")),
+            Decision::Promote("not crossing")
+        );
+        assert_eq!(t.first_crossing(&o.id), None);
+        assert_eq!(
+            t.gate(&o, 1_500, fill()),
+            Decision::Promote("sustaining trigger")
+        );
+        assert_eq!(t.gate(&o, 2_500, fill()), fill());
+        assert_eq!(t.first_crossing(&o.id), None);
+    }
+
+    #[test]
+    fn orders_without_modifier_pass_through() {
+        let o = Order::new("BTC/USDT".into(), OrderSide::Buy, dec!(1), dec!(1));
+        let mut t = SustainTimers::default();
+        assert_eq!(t.gate(&o, 0, fill()), fill());
+        assert_eq!(t.first_crossing(&o.id), None);
+    }
+}
@@ -0,0 +1,152 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::{Arc, Mutex};
+
+use crate::entities::order::Order;
+
+/// The window fills are counted over.
+const WINDOW_MS: i64 = 1_000;
+
+#[derive(Debug, Default)]
+struct Pair {
+    /// When each fill in the last window was taken, oldest first.
+    taken: VecDeque<i64>,
+    /// Orders whose trigger was turned away, in the order they were.
+    queue: Vec<String>,
+}
+
+/// Caps the fills the matchers write per pair to `max_per_sec` in any one
+/// second, shared by a pair's tick and the prices filled in before it.
+/// An order triggering over the cap is queued; on later ticks queued
+/// orders are evaluated first, earliest turned away first, and fill as
+/// soon as their trigger holds and the window has room. Without a cap,
+/// every fill goes through.
+#[derive(Clone, Default)]
+pub struct FillThrottle {
+    max_per_sec: Option<usize>,
+    inner: Arc<Mutex<HashMap<String, Pair>>>,
+}
+
+impl FillThrottle {
+    pub fn new(max_per_sec: Option<usize>) -> Self {
+        Self {
+            max_per_sec: max_per_sec.filter(|&n| n > 0),
+            inner: Arc::default(),
+        }
+    }
+
+    pub fn max_per_sec(&self) -> Option<usize> {
+        self.max_per_sec
+    }
+
+    /// Moves `pair`'s queued orders to the front of `orders`, in queue
+    /// order, leaving the rest as they were.
+    pub fn prioritise(&self, pair: &str, orders: &mut [Order]) {
+        let g = self.inner.lock().unwrap();
+        let Some(p) = g.get(pair).filter(|p| !p.queue.is_empty()) else {
+            return;
+        };
+        orders.sort_by_key(|o| {
+            p.queue
+                .iter()
+                .position(|id| *id == o.id)
+                .unwrap_or(usize::MAX)
+        });
+    }
+
+    /// Takes a slot for a fill of `order_id` at `now_ms`, dropping it from
+    /// the queue. `false`, with the order queued if it was not, when
+    /// `pair` is at its cap for the last second.
+    pub fn admit(&self, pair: &str, order_id: &str, now_ms: i64) -> bool {
+        let Some(max) = self.max_per_sec else {
+            return true;
+        };
+        let mut g = self.inner.lock().unwrap();
+        let p = g.entry(pair.to_string()).or_default();
+        while p.taken.front().is_some_and(|&t| t <= now_ms - WINDOW_MS) {
+            p.taken.pop_front();
+        }
+        if p.taken.len() >= max {
+            if !p.queue.iter().any(|id| id == order_id) {
+                p.queue.push(order_id.to_string());
+            }
+            return false;
+        }
+        p.taken.push_back(now_ms);
+        p.queue.retain(|id| id != order_id);
+        true
+    }
+
+    /// Gives back the slot an admitted fill took at `now_ms` when its
+    /// write failed.
+    pub fn refund(&self, pair: &str, now_ms: i64) {
+        if let Some(p) = self.inner.lock().unwrap().get_mut(pair) {
+            if let Some(i) = p.taken.iter().rposition(|&t| t == now_ms) {
+                p.taken.remove(i);
+            }
+        }
+    }
+
+    /// Drops queued orders of `pair` that are no longer active.
+    pub fn retain(&self, pair: &str, active: &HashSet<&str>) {
+        if let Some(p) = self.inner.lock().unwrap().get_mut(pair) {
+            p.queue.retain(|id| active.contains(id.as_str()));
+        }
+    }
+
+    /// How many of `pair`'s triggers wait for room.
+    pub fn queued(&self, pair: &str) -> usize {
+        self.inner
+            .lock()
+            .unwrap()
+            .get(pair)
+            .map_or(0, |p| p.queue.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entities::test_support::OrderBuilder;
+
+    #[test]
+    fn fills_over_the_cap_wait_in_line_for_the_next_second() {
+        let t = FillThrottle::new(Some(2));
+        assert!(t.admit("BTC/USDT", "a", 1_000));
+        assert!(t.admit("BTC/USDT", "b", 1_200));
+        assert!(!t.admit("BTC/USDT", "c", 1_500));
+        assert!(!t.admit("BTC/USDT", "d", 1_500));
+        assert!(!t.admit("BTC/USDT", "c", 1_600));
+        assert!(t.admit("ETH/USDT", "e", 1_600), "pairs are capped apart");
+        assert_eq!(t.queued("BTC/USDT"), 2);
+
+        let mut orders: Vec<Order> = ["x", "d", "y", "c"]
+            .into_iter()
+            .map(|id| {
+                let mut o = OrderBuilder::buy("BTC/USDT").limit(100).qty(1).build();
+                o.id = id.into();
+                o
+            })
+            .collect();
+        t.prioritise("BTC/USDT", &mut orders);
+        let ids: Vec<&str> = orders.iter().map(|o| o.id.as_str()).collect();
+        assert_eq!(ids, ["c", "d", "x", "y"]);
+
+        // The first slot frees a second after it was taken.
+        assert!(t.admit("BTC/USDT", "c", 2_000));
+        assert!(!t.admit("BTC/USDT", "d", 2_100));
+        t.refund("BTC/USDT", 2_000);
+        assert!(t.admit("BTC/USDT", "d", 2_100));
+        assert_eq!(t.queued("BTC/USDT"), 0);
+
+        assert!(!t.admit("BTC/USDT", "z", 2_150));
+        t.retain("BTC/USDT", &HashSet::new());
+        assert_eq!(t.queued("BTC/USDT"), 0);
+    }
+
+    #[test]
+    fn no_cap_or_a_zero_cap_lets_everything_through() {
+        for t in [FillThrottle::default(), FillThrottle::new(Some(0))] {
+            assert!((0..100).all(|i| t.admit("BTC/USDT", &i.to_string(), 1_000)));
+        }
+    }
+}
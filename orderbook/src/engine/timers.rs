@@ -0,0 +1,275 @@
+use std::collections::{BTreeSet, HashMap};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tokio::sync::Notify;
+use tracing::{info, warn};
+
+use crate::entities::events::OrderEvent;
+use crate::entities::order::{Order, OrderStatus, CANCEL_AFTER};
+use crate::repositories::changes::{ChangeListener, ChangeStream, OrderChange};
+use crate::repositories::{ListOrdersQuery, OrderRepository};
+use crate::utils::now_ms;
+
+/// The longest the timer task sleeps between checks, so a clock step or a
+/// missed wake-up delays a cancel by at most this much.
+const MAX_SLEEP: Duration = Duration::from_secs(1);
+
+/// How long a cancel the store refused waits before it is tried again.
+const RETRY_AFTER_MS: i64 = 1_000;
+
+#[derive(Default)]
+struct Inner {
+    due: BTreeSet<(i64, String)>,
+    by_id: HashMap<String, i64>,
+}
+
+impl Inner {
+    fn schedule(&mut self, id: &str, at_ms: i64) {
+        self.clear(id);
+        self.due.insert((at_ms, id.to_string()));
+        self.by_id.insert(id.to_string(), at_ms);
+    }
+
+    fn clear(&mut self, id: &str) {
+        if let Some(at) = self.by_id.remove(id) {
+            self.due.remove(&(at, id.to_string()));
+        }
+    }
+
+    fn track(&mut self, o: &Order) {
+        match o.cancel_at_ms() {
+            Some(at) if o.status.is_active() => self.schedule(&o.id, at),
+            _ => self.clear(&o.id),
+        }
+    }
+}
+
+/// When each active order with `cancel_after_ms`, or armed with
+/// `valid_for_after_armed_ms`, is due to be cancelled, kept from the change
+/// stream. An order re-arms its deadline when its first fill lands. [`start_order_timers`] cancels them as
+/// they come due; an order that fills or is cancelled first drops out.
+#[derive(Clone, Default)]
+pub struct OrderTimers {
+    inner: Arc<Mutex<Inner>>,
+    wake: Arc<Notify>,
+}
+
+impl OrderTimers {
+    /// Subscribes the timers to `stream` and seeds them from the active
+    /// orders in `repo`; orders already past due are cancelled on the
+    /// first check.
+    pub async fn attach<R: OrderRepository + ?Sized>(
+        &self,
+        repo: &R,
+        stream: &ChangeStream,
+    ) -> Result<(), String> {
+        stream.listen(Arc::new(self.clone()));
+        for status in OrderStatus::ACTIVE {
+            let orders = repo
+                .list(ListOrdersQuery {
+                    status: Some(status),
+                    ..ListOrdersQuery::default()
+                })
+                .await?;
+            let mut w = self.inner.lock().unwrap();
+            for o in &orders {
+                w.track(o);
+            }
+        }
+        self.wake.notify_one();
+        Ok(())
+    }
+
+    /// Like [`attach`](Self::attach) for a store known to be empty.
+    pub fn follow(&self, stream: &ChangeStream) {
+        stream.listen(Arc::new(self.clone()));
+    }
+
+    /// The earliest deadline, if any order has one.
+    pub fn next_due(&self) -> Option<i64> {
+        let r = self.inner.lock().unwrap();
+        r.due.first().map(|(at, _)| *at)
+    }
+
+    /// Removes and returns the orders due by `now_ms`, earliest first.
+    pub fn take_due(&self, now_ms: i64) -> Vec<String> {
+        let mut w = self.inner.lock().unwrap();
+        let mut ids = Vec::new();
+        while let Some((at, id)) = w.due.first().cloned() {
+            if at > now_ms {
+                break;
+            }
+            w.clear(&id);
+            ids.push(id);
+        }
+        ids
+    }
+}
+
+impl ChangeListener for OrderTimers {
+    fn on_change(&self, c: &OrderChange) {
+        let mut w = self.inner.lock().unwrap();
+        match &c.event {
+            OrderEvent::Created { order } | OrderEvent::Updated { order } => w.track(order),
+            OrderEvent::Deleted { order } => w.clear(&order.id),
+        }
+        drop(w);
+        self.wake.notify_one();
+    }
+}
+
+/// Cancels the orders among `ids` that are still active, with reason
+/// [`CANCEL_AFTER`] or [`CANCEL_AFTER_ARMED`](crate::entities::order::CANCEL_AFTER_ARMED)
+/// for whichever deadline came first. Ones the store fails to cancel are put back to be
+/// retried. Returns how many were cancelled.
+pub async fn cancel_due<R: OrderRepository + ?Sized>(
+    repo: &R,
+    timers: &OrderTimers,
+    ids: Vec<String>,
+) -> usize {
+    let mut cancelled = 0;
+    for id in ids {
+        let result = match repo.get_by_id(&id).await {
+            Ok(o) if !o.status.is_active() => continue,
+            Ok(o) => {
+                let reason = o.cancel_deadline().map_or(CANCEL_AFTER, |(_, r)| r);
+                repo.cancel(&id, reason).await
+            }
+            Err(e) => Err(e),
+        };
+        match result {
+            Ok(o) => {
+                info!(order_id = %id, filled = %o.filled_quantity, "CANCEL AFTER");
+                cancelled += 1;
+            }
+            Err(e) => {
+                warn!(order_id = %id, err = %e, "cancel-after failed; retrying");
+                let retry_at = now_ms() + RETRY_AFTER_MS;
+                timers.inner.lock().unwrap().schedule(&id, retry_at);
+            }
+        }
+    }
+    cancelled
+}
+
+/// Cancels orders as their `cancel_after_ms` or
+/// `valid_for_after_armed_ms` runs out, sleeping until the
+/// next deadline or until the timers change.
+pub fn start_order_timers<R: OrderRepository + 'static>(timers: OrderTimers, repo: R) {
+    tokio::spawn(async move {
+        loop {
+            let now = now_ms();
+            let ids = timers.take_due(now);
+            if !ids.is_empty() {
+                cancel_due(&repo, &timers, ids).await;
+                continue;
+            }
+            let wait = timers.next_due().map_or(MAX_SLEEP, |at| {
+                Duration::from_millis((at - now).max(0) as u64).min(MAX_SLEEP)
+            });
+            tokio::select! {
+                _ = tokio::time::sleep(wait) => {}
+                _ = timers.wake.notified() => {}
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entities::order::{Fill, CANCEL_AFTER_ARMED};
+    use crate::entities::test_support::OrderBuilder;
+    use crate::repositories::changes::Observed;
+    use crate::repositories::in_memory::InMemoryOrderRepository;
+    use rust_decimal::Decimal;
+
+    #[tokio::test]
+    async fn cancels_orders_still_active_when_their_time_runs_out() {
+        let changes = ChangeStream::default();
+        let timers = OrderTimers::default();
+        timers.follow(&changes);
+        let repo = Observed::new(InMemoryOrderRepository::default(), changes);
+        let mut new = OrderBuilder::buy("BTC/USDT").limit(100).new_order();
+        new.cancel_after_ms = Some(60_000);
+        let timed = repo.create(new.clone()).await.unwrap();
+        let filled = repo.create(new).await.unwrap();
+        let plain = repo
+            .create(OrderBuilder::buy("BTC/USDT").limit(100).new_order())
+            .await
+            .unwrap();
+        let at = timed.cancel_at_ms().unwrap();
+        assert_eq!(at, timed.created + 60_000);
+        assert_eq!(
+            timers.next_due(),
+            Some(at.min(filled.cancel_at_ms().unwrap()))
+        );
+
+        // Filling takes an order off the timers.
+        repo.set_status(&filled.id, OrderStatus::Filled)
+            .await
+            .unwrap();
+        assert!(timers.take_due(at - 1).is_empty());
+        let due = timers.take_due(at);
+        assert_eq!(due, vec![timed.id.clone()]);
+        assert_eq!(cancel_due(&repo, &timers, due).await, 1);
+
+        let o = repo.get_by_id(&timed.id).await.unwrap();
+        assert_eq!(o.status, OrderStatus::Cancelled);
+        assert_eq!(o.cancel_reason.as_deref(), Some(CANCEL_AFTER));
+        assert_eq!(timers.next_due(), None);
+        assert!(repo.get_by_id(&plain.id).await.unwrap().status.is_active());
+    }
+
+    #[tokio::test]
+    async fn armed_orders_expire_relative_to_their_first_fill() {
+        let changes = ChangeStream::default();
+        let timers = OrderTimers::default();
+        timers.follow(&changes);
+        let repo = Observed::new(InMemoryOrderRepository::default(), changes);
+        let mut new = OrderBuilder::buy("BTC/USDT").limit(100).qty(2).new_order();
+        new.valid_for_after_armed_ms = Some(5_000);
+        new.cancel_after_ms = Some(3_600_000);
+        let o = repo.create(new).await.unwrap();
+        // Not armed yet: only the creation deadline counts.
+        assert_eq!(timers.next_due(), Some(o.created + 3_600_000));
+
+        let fill = Fill {
+            price: Decimal::from(100),
+            quantity: Decimal::ONE,
+            ts_ms: 1,
+            received_ms: Some(o.created + 10),
+            decided_ms: Some(o.created + 20),
+            recovered_trigger: false,
+            interpolated_trigger: false,
+            legs: Vec::new(),
+            trigger_price: None,
+            fair_sources: Vec::new(),
+            counterparty: None,
+        };
+        let armed = repo
+            .record_fills(&o.id, std::slice::from_ref(&fill))
+            .await
+            .unwrap();
+        assert_eq!(armed.status, OrderStatus::PartiallyFilled);
+        assert_eq!(armed.armed_ms, Some(o.created + 20));
+        let at = o.created + 5_020;
+        assert_eq!(timers.next_due(), Some(at));
+        // A later fill keeps the first arming time.
+        let again = Fill {
+            decided_ms: Some(o.created + 900),
+            ts_ms: 2,
+            quantity: Decimal::new(5, 1),
+            ..fill
+        };
+        repo.record_fills(&o.id, &[again]).await.unwrap();
+        assert_eq!(timers.next_due(), Some(at));
+
+        let due = timers.take_due(at);
+        assert_eq!(cancel_due(&repo, &timers, due).await, 1);
+        let o = repo.get_by_id(&o.id).await.unwrap();
+        assert_eq!(o.status, OrderStatus::Cancelled);
+        assert_eq!(o.cancel_reason.as_deref(), Some(CANCEL_AFTER_ARMED));
+    }
+}
@@ -0,0 +1,149 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use rust_decimal::Decimal;
+
+use crate::entities::order::Condition;
+
+/// Widest window an order may ask for; prints this old are kept so a new
+/// window starts with its full history.
+pub const MAX_WINDOW_MS: i64 = 3_600_000;
+
+/// Bound on the kept prints, whatever their age.
+const HISTORY: usize = 100_000;
+
+/// Traded volume per window length, as of the latest tick.
+pub type Volumes = HashMap<i64, Decimal>;
+
+#[derive(Debug, Default)]
+struct Window {
+    prints: VecDeque<(i64, Decimal)>,
+    sum: Decimal,
+}
+
+impl Window {
+    fn add(&mut self, ts_ms: i64, size: Decimal) {
+        self.prints.push_back((ts_ms, size));
+        self.sum += size;
+    }
+
+    /// Drops prints at or before `cutoff`.
+    fn evict(&mut self, cutoff: i64) {
+        while let Some((ts, size)) = self.prints.front().copied() {
+            if ts > cutoff {
+                break;
+            }
+            self.sum -= size;
+            self.prints.pop_front();
+        }
+    }
+}
+
+/// Rolling traded volume for one pair. Each window keeps a running sum,
+/// added to as prints arrive and subtracted from as they age out, so a
+/// reading costs only the prints that left since the last one. Windows are
+/// added when an order first needs them and dropped when no active order
+/// does.
+#[derive(Debug, Default)]
+pub struct VolumeBank {
+    history: VecDeque<(i64, Decimal)>,
+    windows: HashMap<i64, Window>,
+}
+
+impl VolumeBank {
+    pub fn record(&mut self, ts_ms: i64, size: Decimal) {
+        self.history.push_back((ts_ms, size));
+        while self.history.len() > HISTORY
+            || self
+                .history
+                .front()
+                .is_some_and(|(ts, _)| *ts <= ts_ms - MAX_WINDOW_MS)
+        {
+            self.history.pop_front();
+        }
+        for w in self.windows.values_mut() {
+            w.add(ts_ms, size);
+        }
+    }
+
+    pub fn require(&mut self, window_ms: i64) {
+        let history = &self.history;
+        self.windows.entry(window_ms).or_insert_with(|| {
+            let mut w = Window::default();
+            for (ts, size) in history {
+                w.add(*ts, *size);
+            }
+            w
+        });
+    }
+
+    /// Keeps only the windows in `needed` and makes sure all of them exist.
+    pub fn track(&mut self, needed: &HashSet<i64>) {
+        self.windows.retain(|w, _| needed.contains(w));
+        for w in needed {
+            self.require(*w);
+        }
+    }
+
+    /// Volume traded in `(now_ms - window, now_ms]` for every window.
+    pub fn volumes(&mut self, now_ms: i64) -> Volumes {
+        self.windows
+            .iter_mut()
+            .map(|(len, w)| {
+                w.evict(now_ms - len);
+                (*len, w.sum)
+            })
+            .collect()
+    }
+}
+
+/// The window a condition reads, if it is a volume condition.
+pub fn required(cond: &Condition) -> Option<i64> {
+    match cond {
+        Condition::Volume { window_ms, .. } => Some(*window_ms),
+        _ => None,
+    }
+}
+
+/// Whether a volume condition's window has traded at least `min_volume`.
+pub fn holds(cond: &Condition, volumes: &Volumes) -> bool {
+    match cond {
+        Condition::Volume {
+            window_ms,
+            min_volume,
+            ..
+        } => volumes.get(window_ms).is_some_and(|v| v >= min_volume),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn windows_roll_and_late_windows_start_from_history() {
+        let mut bank = VolumeBank::default();
+        bank.require(1_000);
+        bank.record(100, dec!(2));
+        bank.record(600, dec!(3));
+        assert_eq!(bank.volumes(1_000)[&1_000], dec!(5));
+        // The first print leaves the window once it is a full window old.
+        assert_eq!(bank.volumes(1_100)[&1_000], dec!(3));
+        bank.record(1_500, dec!(1));
+        assert_eq!(bank.volumes(1_600)[&1_000], dec!(1));
+
+        bank.track(&HashSet::from([5_000]));
+        assert_eq!(bank.volumes(1_600), Volumes::from([(5_000, dec!(6))]));
+
+        let cond = Condition::Volume {
+            window_ms: 5_000,
+            min_volume: dec!(6),
+            with_price: false,
+        };
+        assert_eq!(required(&cond), Some(5_000));
+        assert!(holds(&cond, &bank.volumes(1_600)));
+        assert!(!holds(&cond, &bank.volumes(5_200)));
+        assert!(!holds(&cond, &Volumes::new()));
+    }
+}
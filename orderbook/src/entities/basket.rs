@@ -0,0 +1,163 @@
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+use crate::entities::order::{Order, OrderSide, TriggerPriceType};
+use crate::utils::now_ms;
+
+/// The condition a basket shares: the reference price of `pair` at or
+/// below `below` and/or at or above `above`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BasketTrigger {
+    pub pair: String,
+    #[serde(default)]
+    pub trigger_price_type: TriggerPriceType,
+    #[serde(default, serialize_with = "crate::utils::decimal::serialize_opt")]
+    pub below: Option<Decimal>,
+    #[serde(default, serialize_with = "crate::utils::decimal::serialize_opt")]
+    pub above: Option<Decimal>,
+}
+
+impl BasketTrigger {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.below.is_none() && self.above.is_none() {
+            return Err("trigger needs below and/or above".into());
+        }
+        Ok(())
+    }
+
+    pub fn holds(&self, ref_px: Decimal) -> bool {
+        self.below.is_none_or(|b| ref_px <= b) && self.above.is_none_or(|a| ref_px >= a)
+    }
+}
+
+/// How legs go out once the trigger holds. `Atomic` waits until every
+/// leg's market can take an order and places them together; `BestEffort`
+/// places what it can and rejects the other legs.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum BasketMode {
+    #[default]
+    Atomic,
+    BestEffort,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum BasketStatus {
+    /// Waiting for the trigger.
+    Pending,
+    /// Legs placed; their orders carry the outcome.
+    Triggered,
+    Cancelled,
+    /// An atomic basket whose legs could not all be placed; placed legs
+    /// were cancelled again.
+    Failed,
+}
+
+/// `cancel_reason` of leg orders withdrawn because their atomic basket
+/// could not place every leg.
+pub const CANCEL_BASKET_FAILED: &str = "basket_failed";
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LegStatus {
+    Pending,
+    /// Sent to its pair's matcher as a market order.
+    Placed,
+    Rejected,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BasketLeg {
+    pub pair: String,
+    pub side: OrderSide,
+    #[serde(serialize_with = "crate::utils::decimal::serialize")]
+    pub quantity: Decimal,
+    pub status: LegStatus,
+    pub order_id: Option<String>,
+    pub reason: Option<String>,
+    /// The leg's order as of the read; filled in by the API, not stored.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub order: Option<Order>,
+}
+
+/// Several legs across pairs behind one shared trigger.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Basket {
+    pub id: String,
+    pub trigger: BasketTrigger,
+    pub mode: BasketMode,
+    pub legs: Vec<BasketLeg>,
+    pub status: BasketStatus,
+    /// The trigger reference price at which the legs went out.
+    #[serde(default, serialize_with = "crate::utils::decimal::serialize_opt")]
+    pub triggered_px: Option<Decimal>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub account_id: Option<String>,
+    pub created: i64,
+    pub updated: i64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct NewBasketLeg {
+    pub pair: String,
+    pub side: OrderSide,
+    pub quantity: Decimal,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct NewBasket {
+    pub trigger: BasketTrigger,
+    #[serde(default)]
+    pub mode: BasketMode,
+    pub legs: Vec<NewBasketLeg>,
+}
+
+impl Basket {
+    pub fn from_new(new: NewBasket, account_id: Option<String>) -> Self {
+        let now = now_ms();
+        Self {
+            id: crate::utils::ids::next_id(),
+            trigger: new.trigger,
+            mode: new.mode,
+            legs: new
+                .legs
+                .into_iter()
+                .map(|l| BasketLeg {
+                    pair: l.pair,
+                    side: l.side,
+                    quantity: l.quantity,
+                    status: LegStatus::Pending,
+                    order_id: None,
+                    reason: None,
+                    order: None,
+                })
+                .collect(),
+            status: BasketStatus::Pending,
+            triggered_px: None,
+            account_id,
+            created: now,
+            updated: now,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn trigger_bounds_are_inclusive_and_combine() {
+        let mut t: BasketTrigger =
+            serde_json::from_str(r#"{"pair":"BTC/USDT","below":"60000"}"#).unwrap();
+        assert!(t.holds(dec!(60000)));
+        assert!(!t.holds(dec!(60000.01)));
+        t.above = Some(dec!(55000));
+        assert!(t.holds(dec!(58000)));
+        assert!(!t.holds(dec!(54000)));
+        t.below = None;
+        t.above = None;
+        assert!(t.validate().is_err());
+    }
+}
@@ -0,0 +1,225 @@
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+
+use crate::entities::order::{Fill, Order};
+
+pub const MAX_CALLBACK_URL_LEN: usize = 2048;
+
+/// Template variables besides `metadata.<key>`.
+pub const CALLBACK_VARS: [&str; 14] = [
+    "event",
+    "order_id",
+    "account_id",
+    "pair",
+    "side",
+    "status",
+    "price",
+    "quantity",
+    "filled_quantity",
+    "exec_price",
+    "fill_price",
+    "fill_quantity",
+    "cancel_reason",
+    "ts_ms",
+];
+
+/// What happened to an order with a `callback_url`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CallbackEvent {
+    Created,
+    /// Price or quantity changed while the order rested.
+    Amended,
+    /// One per fill, the last one included.
+    Fill,
+    Cancelled,
+    Suspended,
+    Expired,
+    /// Removed from the store.
+    Deleted,
+}
+
+impl CallbackEvent {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Created => "created",
+            Self::Amended => "amended",
+            Self::Fill => "fill",
+            Self::Cancelled => "cancelled",
+            Self::Suspended => "suspended",
+            Self::Expired => "expired",
+            Self::Deleted => "deleted",
+        }
+    }
+}
+
+/// Checks a callback at placement: a plain `http://` URL, and a template
+/// whose `{{...}}` placeholders all name known variables.
+pub fn validate_callback(url: &str, template: Option<&Value>) -> Result<(), String> {
+    if url.len() > MAX_CALLBACK_URL_LEN {
+        return Err(format!(
+            "callback_url is longer than {MAX_CALLBACK_URL_LEN} characters"
+        ));
+    }
+    let parsed = url::Url::parse(url).map_err(|e| format!("callback_url: {e}"))?;
+    if parsed.scheme() != "http" || parsed.host_str().is_none() {
+        return Err("callback_url must be an http:// URL with a host".into());
+    }
+    if let Some(t) = template {
+        let known = |name: &str| {
+            (CALLBACK_VARS.contains(&name) || name.strip_prefix("metadata.").is_some())
+                .then_some(Value::Null)
+        };
+        walk(t, &known)?;
+    }
+    Ok(())
+}
+
+/// The body posted for `event`: the template with its variables filled in,
+/// or `{"event", "order", "fill"}` without one. A string that is a single
+/// placeholder takes the variable's JSON value, `null` when unset; inside
+/// longer strings unset variables are left empty.
+pub fn render(
+    template: Option<&Value>,
+    event: CallbackEvent,
+    order: &Order,
+    fill: Option<&Fill>,
+    ts_ms: i64,
+) -> Result<Value, String> {
+    let Some(t) = template else {
+        return Ok(json!({"event": event.name(), "order": order, "fill": fill}));
+    };
+    let amount = |d: Decimal| {
+        crate::utils::decimal::serialize(&d, serde_json::value::Serializer).unwrap_or(Value::Null)
+    };
+    let text = |s: &str| Value::String(s.to_string());
+    let lookup = |name: &str| {
+        let v = match name {
+            "event" => text(event.name()),
+            "order_id" => text(&order.id),
+            "account_id" => order.account_id.as_deref().map_or(Value::Null, text),
+            "pair" => text(&order.pair),
+            "side" => serde_json::to_value(&order.side).unwrap_or(Value::Null),
+            "status" => serde_json::to_value(&order.status).unwrap_or(Value::Null),
+            "price" => amount(order.price),
+            "quantity" => amount(order.quantity),
+            "filled_quantity" => amount(order.filled_quantity),
+            "exec_price" => order.exec_price.map_or(Value::Null, amount),
+            "fill_price" => fill.map_or(Value::Null, |f| amount(f.price)),
+            "fill_quantity" => fill.map_or(Value::Null, |f| amount(f.quantity)),
+            "cancel_reason" => order.cancel_reason.as_deref().map_or(Value::Null, text),
+            "ts_ms" => ts_ms.into(),
+            _ => {
+                let key = name.strip_prefix("metadata.")?;
+                order.metadata.get(key).map_or(Value::Null, |v| text(v))
+            }
+        };
+        Some(v)
+    };
+    walk(t, &lookup)
+}
+
+fn walk(v: &Value, lookup: &dyn Fn(&str) -> Option<Value>) -> Result<Value, String> {
+    match v {
+        Value::String(s) => fill_in(s, lookup),
+        Value::Array(items) => items
+            .iter()
+            .map(|v| walk(v, lookup))
+            .collect::<Result<_, _>>()
+            .map(Value::Array),
+        Value::Object(m) => m
+            .iter()
+            .map(|(k, v)| Ok((k.clone(), walk(v, lookup)?)))
+            .collect::<Result<Map<_, _>, String>>()
+            .map(Value::Object),
+        v => Ok(v.clone()),
+    }
+}
+
+fn fill_in(s: &str, lookup: &dyn Fn(&str) -> Option<Value>) -> Result<Value, String> {
+    let unknown = |name: &str| format!("unknown callback template variable {name:?}");
+    let whole = s
+        .strip_prefix("{{")
+        .and_then(|r| r.strip_suffix("}}"))
+        .filter(|r| !r.contains("{{") && !r.contains("}}"));
+    if let Some(name) = whole.map(str::trim) {
+        return lookup(name).ok_or_else(|| unknown(name));
+    }
+    let (mut out, mut rest) = (String::new(), s);
+    while let Some(start) = rest.find("{{") {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let end = after
+            .find("}}")
+            .ok_or_else(|| format!("unclosed {{{{ in callback template {s:?}"))?;
+        let name = after[..end].trim();
+        match lookup(name).ok_or_else(|| unknown(name))? {
+            Value::Null => {}
+            Value::String(v) => out.push_str(&v),
+            v => out.push_str(&v.to_string()),
+        }
+        rest = &after[end + 2..];
+    }
+    out.push_str(rest);
+    Ok(Value::String(out))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entities::test_support::OrderBuilder;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn templates_are_checked_and_filled_in() {
+        let template = json!({
+            "text": "{{side}} {{pair}} filled {{fill_quantity}} @ {{fill_price}}{{cancel_reason}}",
+            "order": "{{ order_id }}",
+            "at": "{{ts_ms}}",
+            "ref": ["{{metadata.ref}}", "{{metadata.missing}}", 7],
+        });
+        assert!(validate_callback("http://hooks.test/fills", Some(&template)).is_ok());
+        for bad in [json!("{{oops}}"), json!({"a": "x {{price"})] {
+            assert!(validate_callback("http://hooks.test/", Some(&bad)).is_err());
+        }
+        assert!(validate_callback("https://hooks.test/", None).is_err());
+        assert!(validate_callback("hooks.test/fills", None).is_err());
+
+        let mut order = OrderBuilder::buy("BTC/USDT").limit(100).qty(2).build();
+        order.metadata.insert("ref".into(), "desk-7".into());
+        let fill = Fill {
+            price: dec!(99.5),
+            quantity: dec!(1),
+            ts_ms: 5,
+            received_ms: None,
+            decided_ms: None,
+            recovered_trigger: false,
+            interpolated_trigger: false,
+            legs: Vec::new(),
+            trigger_price: None,
+            fair_sources: Vec::new(),
+            counterparty: None,
+        };
+        let body = render(
+            Some(&template),
+            CallbackEvent::Fill,
+            &order,
+            Some(&fill),
+            42,
+        )
+        .unwrap();
+        assert_eq!(
+            body,
+            json!({
+                "text": "buy BTC/USDT filled 1 @ 99.5",
+                "order": order.id,
+                "at": 42,
+                "ref": ["desk-7", null, 7],
+            })
+        );
+        let plain = render(None, CallbackEvent::Created, &order, None, 42).unwrap();
+        assert_eq!(plain["event"], "created");
+        assert_eq!(plain["order"]["id"], order.id.as_str());
+    }
+}
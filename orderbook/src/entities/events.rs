@@ -0,0 +1,150 @@
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+use crate::engine::halts::{HaltSource, ResumePolicy};
+use crate::entities::order::Order;
+
+/// Bumped whenever an event's serialized shape changes incompatibly.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// A write to an order. `Created` and `Updated` carry the order after the
+/// write, `Deleted` the order as it was.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum OrderEvent {
+    #[serde(rename = "order_created")]
+    Created { order: Order },
+    #[serde(rename = "order_updated")]
+    Updated { order: Order },
+    #[serde(rename = "order_deleted")]
+    Deleted { order: Order },
+}
+
+impl OrderEvent {
+    pub fn order(&self) -> &Order {
+        match self {
+            Self::Created { order } | Self::Updated { order } | Self::Deleted { order } => order,
+        }
+    }
+}
+
+/// A change to a pair's trading state.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum MarketEvent {
+    PairHalted {
+        pair: String,
+        source: HaltSource,
+        reason: String,
+    },
+    PairResumed {
+        pair: String,
+        policy: ResumePolicy,
+        parked: usize,
+    },
+    /// The pair now trades as `to`; `from` stays an alias of it.
+    PairRenamed { from: String, to: String },
+    /// The pair was delisted with its remaining orders cancelled.
+    PairDelisted { pair: String, cancelled: usize },
+    /// One tick filled more orders than the bulk threshold. Each order's
+    /// fill is still published as its own `order_updated`.
+    BulkFill {
+        pair: String,
+        oracle_ts: i64,
+        orders: usize,
+        #[serde(serialize_with = "crate::utils::decimal::serialize")]
+        quantity: Decimal,
+        #[serde(serialize_with = "crate::utils::decimal::serialize")]
+        avg_px: Decimal,
+        #[serde(serialize_with = "crate::utils::decimal::serialize")]
+        min_px: Decimal,
+        #[serde(serialize_with = "crate::utils::decimal::serialize")]
+        max_px: Decimal,
+    },
+}
+
+/// Any event a channel may carry. Variant tags are unique across both
+/// enums, so the `type` field alone identifies the payload.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum DomainEvent {
+    Order(Box<OrderEvent>),
+    Market(MarketEvent),
+}
+
+/// What every channel puts on the wire: the event flattened next to its
+/// schema version and the time it happened.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Envelope<E> {
+    pub schema_version: u32,
+    pub ts_ms: i64,
+    #[serde(flatten)]
+    pub event: E,
+}
+
+impl<E> Envelope<E> {
+    pub fn new(event: E, ts_ms: i64) -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            ts_ms,
+            event,
+        }
+    }
+}
+
+impl Envelope<OrderEvent> {
+    pub fn order(&self) -> &Order {
+        self.event.order()
+    }
+
+    pub fn is_delete(&self) -> bool {
+        matches!(self.event, OrderEvent::Deleted { .. })
+    }
+}
+
+impl From<Envelope<OrderEvent>> for Envelope<DomainEvent> {
+    fn from(e: Envelope<OrderEvent>) -> Self {
+        Self {
+            schema_version: e.schema_version,
+            ts_ms: e.ts_ms,
+            event: DomainEvent::Order(Box::new(e.event)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entities::order::OrderSide;
+    use rust_decimal_macros::dec;
+
+    fn round_trip(v: &serde_json::Value) -> serde_json::Value {
+        let e: Envelope<DomainEvent> = serde_json::from_value(v.clone()).unwrap();
+        serde_json::to_value(e).unwrap()
+    }
+
+    #[test]
+    fn wire_shape_is_flat_and_round_trips() {
+        let order = Order::new("BTC/USDT".into(), OrderSide::Buy, dec!(100), dec!(1));
+        let e: Envelope<DomainEvent> = Envelope::new(OrderEvent::Created { order }, 7).into();
+        let v = serde_json::to_value(&e).unwrap();
+        assert_eq!(v["schema_version"], SCHEMA_VERSION);
+        assert_eq!(v["ts_ms"], 7);
+        assert_eq!(v["type"], "order_created");
+        assert_eq!(v["order"]["status"], "new");
+        assert_eq!(round_trip(&v), v);
+
+        let m = Envelope::new(
+            DomainEvent::Market(MarketEvent::PairHalted {
+                pair: "BTC/USDT".into(),
+                source: HaltSource::Admin,
+                reason: "maintenance".into(),
+            }),
+            8,
+        );
+        let v = serde_json::to_value(&m).unwrap();
+        assert_eq!(v["type"], "pair_halted");
+        assert_eq!(v["source"], "admin");
+        assert_eq!(round_trip(&v), v);
+    }
+}
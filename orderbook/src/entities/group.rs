@@ -0,0 +1,170 @@
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+use crate::entities::order::Order;
+use crate::utils::now_ms;
+
+/// `cancel_reason` of group members withdrawn because the group spent its
+/// budget.
+pub const CANCEL_GROUP_BUDGET: &str = "group_budget";
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum GroupStatus {
+    /// Budget left and members still working.
+    Active,
+    /// Filled notional reached the budget; the members still active then
+    /// were cancelled.
+    Exhausted,
+    /// Every member finished before the budget ran out.
+    Completed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GroupMember {
+    pub order_id: String,
+    /// Quote notional filled so far.
+    #[serde(serialize_with = "crate::utils::decimal::serialize")]
+    pub spent: Decimal,
+    pub active: bool,
+    /// The member's order as of the read; filled in by the API, not stored.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub order: Option<Order>,
+}
+
+/// Buy orders sharing one quote budget, such as the levels of a laddered
+/// entry. Once their fills add up to `budget`, the rest are cancelled.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderGroup {
+    pub id: String,
+    #[serde(serialize_with = "crate::utils::decimal::serialize")]
+    pub budget: Decimal,
+    #[serde(serialize_with = "crate::utils::decimal::serialize")]
+    pub spent: Decimal,
+    pub members: Vec<GroupMember>,
+    pub status: GroupStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub account_id: Option<String>,
+    pub created: i64,
+    pub updated: i64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct NewOrderGroup {
+    pub budget: Decimal,
+    pub order_ids: Vec<String>,
+}
+
+impl OrderGroup {
+    pub fn new(budget: Decimal, orders: &[Order], account_id: Option<String>) -> Self {
+        let now = now_ms();
+        let mut g = Self {
+            id: crate::utils::ids::next_id(),
+            budget,
+            spent: Decimal::ZERO,
+            members: orders
+                .iter()
+                .map(|o| GroupMember {
+                    order_id: o.id.clone(),
+                    spent: Decimal::ZERO,
+                    active: true,
+                    order: None,
+                })
+                .collect(),
+            status: GroupStatus::Active,
+            account_id,
+            created: now,
+            updated: now,
+        };
+        for o in orders {
+            g.observe(o);
+        }
+        g
+    }
+
+    /// Brings member `o` up to date. When that takes an active group to
+    /// its budget, returns the members still active, to be cancelled.
+    pub fn observe(&mut self, o: &Order) -> Vec<String> {
+        let Some(m) = self.members.iter_mut().find(|m| m.order_id == o.id) else {
+            return Vec::new();
+        };
+        m.spent = o.fills.iter().map(|f| f.price * f.quantity).sum();
+        m.active = o.status.is_active();
+        self.spent = self.members.iter().map(|m| m.spent).sum();
+        self.updated = now_ms();
+        if self.status != GroupStatus::Active {
+            return Vec::new();
+        }
+        if self.spent >= self.budget {
+            self.status = GroupStatus::Exhausted;
+            return self
+                .members
+                .iter()
+                .filter(|m| m.active)
+                .map(|m| m.order_id.clone())
+                .collect();
+        }
+        if self.members.iter().all(|m| !m.active) {
+            self.status = GroupStatus::Completed;
+        }
+        Vec::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entities::order::{Fill, OrderStatus};
+    use crate::entities::test_support::OrderBuilder;
+    use rust_decimal_macros::dec;
+
+    fn fill(o: &mut Order, price: Decimal, quantity: Decimal) {
+        o.fills.push(Fill {
+            price,
+            quantity,
+            ts_ms: 1,
+            received_ms: None,
+            decided_ms: None,
+            recovered_trigger: false,
+            interpolated_trigger: false,
+            legs: Vec::new(),
+            trigger_price: None,
+            fair_sources: Vec::new(),
+            counterparty: None,
+        });
+        o.filled_quantity += quantity;
+        o.status = if o.filled_quantity >= o.quantity {
+            OrderStatus::Filled
+        } else {
+            OrderStatus::PartiallyFilled
+        };
+    }
+
+    #[test]
+    fn reaching_the_budget_returns_the_members_still_active() {
+        let mut a = OrderBuilder::buy("BTC/USDT").limit(100).qty(2).build();
+        let b = OrderBuilder::buy("BTC/USDT").limit(90).qty(2).build();
+        let c = OrderBuilder::buy("BTC/USDT").limit(80).qty(2).build();
+        let mut g = OrderGroup::new(dec!(150), &[a.clone(), b.clone(), c.clone()], None);
+        assert_eq!((g.status, g.spent), (GroupStatus::Active, Decimal::ZERO));
+
+        fill(&mut a, dec!(100), dec!(1));
+        assert!(g.observe(&a).is_empty());
+        fill(&mut a, dec!(100), dec!(1));
+        let mut left = g.observe(&a);
+        left.sort();
+        let mut want = vec![b.id.clone(), c.id.clone()];
+        want.sort();
+        assert_eq!(left, want);
+        assert_eq!((g.status, g.spent), (GroupStatus::Exhausted, dec!(200)));
+        // Only the first time.
+        assert!(g.observe(&a).is_empty());
+        // Below budget once everything has finished.
+        let solo = OrderBuilder::buy("BTC/USDT").limit(100).build();
+        let mut g = OrderGroup::new(dec!(1000), std::slice::from_ref(&solo), None);
+        let mut solo = solo;
+        solo.status = OrderStatus::Cancelled;
+        g.observe(&solo);
+        assert_eq!(g.status, GroupStatus::Completed);
+    }
+}
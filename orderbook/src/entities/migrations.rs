@@ -0,0 +1,123 @@
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value};
+
+use crate::entities::events::SCHEMA_VERSION;
+
+type Object = Map<String, Value>;
+
+/// Upgrades a record, and every order it carries, by one version.
+struct Step {
+    record: fn(&mut Object),
+    order: fn(&mut Object),
+}
+
+/// `STEPS[n]` takes a record from version `n` to `n + 1`. Version 0 is
+/// anything written before records carried `schema_version`.
+const STEPS: [Step; SCHEMA_VERSION as usize] = [Step {
+    record: v1_record,
+    order: v1_order,
+}];
+
+/// Change records once were `{"kind": "created", "order", "ts_ms"}`.
+fn v1_record(r: &mut Object) {
+    if let Some(Value::String(kind)) = r.remove("kind") {
+        r.insert("type".into(), Value::String(format!("order_{kind}")));
+    }
+}
+
+/// Orders once named the trigger reference `trigger_on` and did not track
+/// fills, so a filled order's fill is its whole quantity.
+fn v1_order(o: &mut Object) {
+    if let Some(v) = o.remove("trigger_on") {
+        o.entry("trigger_price_type").or_insert(v);
+    }
+    if !o.contains_key("filled_quantity")
+        && o.get("status").and_then(Value::as_str) == Some("filled")
+    {
+        if let Some(q) = o.get("quantity").cloned() {
+            o.insert("filled_quantity".into(), q);
+        }
+    }
+}
+
+/// Brings a stored record up to [`SCHEMA_VERSION`] and reports the version
+/// it was written with. Records from a newer build are refused.
+pub fn upgrade(mut record: Value) -> Result<(Value, u32), String> {
+    let r = record
+        .as_object_mut()
+        .ok_or("record is not a JSON object")?;
+    let from = match r.get("schema_version") {
+        None => 0,
+        Some(v) => v
+            .as_u64()
+            .map(|v| v as u32)
+            .ok_or("schema_version is not a number")?,
+    };
+    if from > SCHEMA_VERSION {
+        return Err(format!(
+            "schema_version {from} is newer than supported {SCHEMA_VERSION}"
+        ));
+    }
+    for step in &STEPS[from as usize..] {
+        (step.record)(r);
+        if let Some(Value::Object(o)) = r.get_mut("order") {
+            (step.order)(o);
+        }
+        if let Some(Value::Array(orders)) = r.get_mut("orders") {
+            for o in orders.iter_mut().filter_map(Value::as_object_mut) {
+                (step.order)(o);
+            }
+        }
+    }
+    r.insert("schema_version".into(), SCHEMA_VERSION.into());
+    Ok((record, from))
+}
+
+/// Upgrades and then deserializes a stored record.
+pub fn load<T: DeserializeOwned>(record: Value) -> Result<(T, u32), String> {
+    let (v, from) = upgrade(record)?;
+    let t = serde_json::from_value(v).map_err(|e| e.to_string())?;
+    Ok((t, from))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entities::events::{Envelope, OrderEvent};
+    use crate::entities::order::OrderStatus;
+    use rust_decimal_macros::dec;
+    use serde_json::json;
+
+    #[test]
+    fn upgrades_legacy_change_records() {
+        let legacy = json!({
+            "kind": "updated",
+            "ts_ms": 5,
+            "order": {
+                "id": "o1", "pair": "BTC/USDT", "side": "buy", "price": "100",
+                "quantity": "2", "status": "filled", "trigger_on": "mark",
+                "created": 1, "updated": 5
+            }
+        });
+        let (e, from): (Envelope<OrderEvent>, u32) = load(legacy).unwrap();
+        assert_eq!(from, 0);
+        assert_eq!(e.schema_version, SCHEMA_VERSION);
+        let OrderEvent::Updated { order } = e.event else {
+            panic!("expected an update");
+        };
+        assert_eq!(order.status, OrderStatus::Filled);
+        assert_eq!(order.filled_quantity, dec!(2));
+        assert_eq!(
+            serde_json::to_value(order.trigger_price_type).unwrap(),
+            "mark"
+        );
+    }
+
+    #[test]
+    fn current_records_pass_through_and_future_ones_are_refused() {
+        let current = json!({"schema_version": SCHEMA_VERSION, "type": "x"});
+        assert_eq!(upgrade(current.clone()).unwrap(), (current, SCHEMA_VERSION));
+        assert!(upgrade(json!({"schema_version": SCHEMA_VERSION + 1})).is_err());
+        assert!(upgrade(json!([1])).is_err());
+    }
+}
@@ -1,2 +1,10 @@
+pub mod basket;
+pub mod callback;
+pub mod events;
+pub mod group;
+pub mod migrations;
 pub mod order;
 pub mod orderbook;
+pub mod template;
+#[cfg(any(test, feature = "test-support"))]
+pub mod test_support;
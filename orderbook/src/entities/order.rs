@@ -1,7 +1,10 @@
+use std::collections::BTreeMap;
+
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
-use uuid::Uuid;
+use serde_json::Value;
 
+use crate::instruments::symbol::Symbol;
 use crate::utils::now_ms;
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -19,16 +22,574 @@ pub enum OrderStatus {
     PartiallyFilled,
     Filled,
     Cancelled,
+    /// Parked by the engine after repeated write failures; see the
+    /// dead-letter list.
+    Suspended,
+    /// A bracket child waiting for its entry to finish; the engine
+    /// releases it as `new` or cancels it.
+    Pending,
+    /// Still working at its `expires_at`; set by the expiry sweep.
+    Expired,
+}
+
+impl OrderStatus {
+    pub const ACTIVE: [OrderStatus; 3] = [
+        OrderStatus::New,
+        OrderStatus::Open,
+        OrderStatus::PartiallyFilled,
+    ];
+
+    pub fn is_active(&self) -> bool {
+        Self::ACTIVE.contains(self)
+    }
+
+    /// Lifecycle rules: orders only move forward, `Filled`, `Cancelled`
+    /// and `Expired` are terminal, and a partial fill may be followed by
+    /// further partial fills. Active orders may be suspended, and a
+    /// suspended order can be reopened or cancelled. Active and suspended
+    /// orders may expire.
+    pub fn can_transition_to(&self, to: &OrderStatus) -> bool {
+        use OrderStatus::*;
+        matches!(
+            (self, to),
+            (New, Open | PartiallyFilled | Filled | Cancelled)
+                | (Open, PartiallyFilled | Filled | Cancelled)
+                | (PartiallyFilled, PartiallyFilled | Filled | Cancelled)
+                | (New | Open | PartiallyFilled, Suspended)
+                | (Suspended, Open | Cancelled)
+                | (Pending, New | Cancelled)
+                | (New | Open | PartiallyFilled | Suspended, Expired)
+        )
+    }
+}
+
+/// Which price an order's trigger is compared against: the latest oracle
+/// tick, the top of book carried on the tick, the smoothed index, or the
+/// mark (last clamped around the index).
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum TriggerPriceType {
+    #[default]
+    Last,
+    Bid,
+    Ask,
+    Index,
+    Mark,
+}
+
+/// `Limit` orders wait for the trigger reference to cross `price`;
+/// `Market` orders ignore `price` and execute on the next fresh tick.
+/// `Stop` and `StopLimit` orders lie dormant until the reference reaches
+/// their `trigger_price`, rising for a buy and falling for a sell, and
+/// then become `Market` and `Limit` orders.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum OrderType {
+    #[default]
+    Limit,
+    Market,
+    Stop,
+    StopLimit,
+}
+
+impl OrderType {
+    pub fn is_stop(self) -> bool {
+        matches!(self, Self::Stop | Self::StopLimit)
+    }
+
+    /// What a stop becomes once triggered; other types stay as they are.
+    pub fn activated(self) -> Self {
+        match self {
+            Self::Stop => Self::Market,
+            Self::StopLimit => Self::Limit,
+            t => t,
+        }
+    }
+}
+
+/// How far a trailing stop's trigger sits from the best reference it has
+/// seen: a fixed `amount` in quote units or a `percent` of the reference.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Trail {
+    Amount(#[serde(serialize_with = "crate::utils::decimal::serialize")] Decimal),
+    Percent(#[serde(serialize_with = "crate::utils::decimal::serialize")] Decimal),
+}
+
+impl Trail {
+    /// The trigger for watermark `wm`: below it for a sell, above it for a
+    /// buy.
+    pub fn level(self, side: &OrderSide, wm: Decimal) -> Decimal {
+        let offset = match self {
+            Self::Amount(a) => a,
+            Self::Percent(p) => wm * p / Decimal::ONE_HUNDRED,
+        };
+        match side {
+            OrderSide::Buy => wm + offset,
+            OrderSide::Sell => wm - offset,
+        }
+        .normalize()
+    }
+}
+
+/// `Gtc` rests until filled or cancelled. `Gtd` rests the same way until
+/// its `expires_at`, then becomes [`OrderStatus::Expired`]. `Ioc`
+/// is evaluated once, on the first tick that sees it: it fills what it
+/// can, and is cancelled with reason [`CANCEL_IOC`] for the rest, or
+/// whole if it does not trigger. `Fok` must fill its whole quantity
+/// against the simulated depth when it triggers, otherwise it is
+/// cancelled with reason [`CANCEL_FOK_UNFILLABLE`].
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum TimeInForce {
+    #[default]
+    Gtc,
+    Ioc,
+    Fok,
+    Gtd,
+}
+
+impl TimeInForce {
+    /// Whether an order that is not filled keeps working.
+    pub fn rests(&self) -> bool {
+        matches!(self, Self::Gtc | Self::Gtd)
+    }
+}
+
+/// How a triggered limit order's fill is priced: at the engine's execution
+/// price (`oracle`), at the order's limit (`limit`), or at whichever of the
+/// two is better for the order (`better_of`).
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ExecPricing {
+    #[default]
+    Oracle,
+    Limit,
+    BetterOf,
+}
+
+impl ExecPricing {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "oracle" => Some(Self::Oracle),
+            "limit" => Some(Self::Limit),
+            "better_of" => Some(Self::BetterOf),
+            _ => None,
+        }
+    }
+
+    /// The fill price given what the engine would execute at.
+    pub fn price(self, side: &OrderSide, exec_px: Decimal, limit: Decimal) -> Decimal {
+        match (self, side) {
+            (Self::Oracle, _) => exec_px,
+            (Self::Limit, _) => limit,
+            (Self::BetterOf, OrderSide::Buy) => exec_px.min(limit),
+            (Self::BetterOf, OrderSide::Sell) => exec_px.max(limit),
+        }
+    }
+}
+
+/// A technical indicator computed over the last `period` oracle ticks.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Indicator {
+    Sma { period: u32 },
+    Ema { period: u32 },
+    Rsi { period: u32 },
+}
+
+impl Indicator {
+    pub fn period(&self) -> u32 {
+        match self {
+            Self::Sma { period } | Self::Ema { period } | Self::Rsi { period } => *period,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CrossDirection {
+    Above,
+    Below,
+}
+
+/// How a spread condition combines its two legs' prices.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SpreadMeasure {
+    #[default]
+    Ratio,
+    Difference,
+}
+
+/// What must cross for a limit order to trigger. `Price` compares the
+/// trigger reference with the order's `price`. `Notional` compares
+/// `reference * quantity` with `threshold` (in quote currency); `quantity`
+/// defaults to the order's own. Buys trigger at or below the threshold,
+/// sells at or above it. `Crossover` triggers on the tick where `fast`
+/// crosses `slow` in `direction`; `Level` while an indicator is strictly
+/// below and/or above the given bounds. `Volume` triggers once at least
+/// `min_volume` has traded on the pair within the last `window_ms`, and,
+/// `with_price`, only while the reference also crosses `price`. `Spread`
+/// triggers while `legs[0] / legs[1]` (or `legs[0] - legs[1]`) on each
+/// leg's latest fresh tick is strictly below and/or above the bounds.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Condition {
+    #[default]
+    Price,
+    Notional {
+        #[serde(serialize_with = "crate::utils::decimal::serialize")]
+        threshold: Decimal,
+        #[serde(default, serialize_with = "crate::utils::decimal::serialize_opt")]
+        quantity: Option<Decimal>,
+    },
+    Crossover {
+        fast: Indicator,
+        slow: Indicator,
+        direction: CrossDirection,
+    },
+    Level {
+        indicator: Indicator,
+        #[serde(default, serialize_with = "crate::utils::decimal::serialize_opt")]
+        below: Option<Decimal>,
+        #[serde(default, serialize_with = "crate::utils::decimal::serialize_opt")]
+        above: Option<Decimal>,
+    },
+    Volume {
+        window_ms: i64,
+        #[serde(serialize_with = "crate::utils::decimal::serialize")]
+        min_volume: Decimal,
+        #[serde(default)]
+        with_price: bool,
+    },
+    Spread {
+        legs: [Symbol; 2],
+        #[serde(default)]
+        measure: SpreadMeasure,
+        #[serde(default, serialize_with = "crate::utils::decimal::serialize_opt")]
+        below: Option<Decimal>,
+        #[serde(default, serialize_with = "crate::utils::decimal::serialize_opt")]
+        above: Option<Decimal>,
+    },
+}
+
+impl Condition {
+    /// The serde `type` tag, used to group analytics.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Condition::Price => "price",
+            Condition::Notional { .. } => "notional",
+            Condition::Crossover { .. } => "crossover",
+            Condition::Level { .. } => "level",
+            Condition::Volume { .. } => "volume",
+            Condition::Spread { .. } => "spread",
+        }
+    }
+
+    /// Whether the order's `price` takes part in the trigger.
+    pub fn uses_price(&self) -> bool {
+        match self {
+            Condition::Price => true,
+            Condition::Volume { with_price, .. } => *with_price,
+            _ => false,
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        let period_ok =
+            |i: &Indicator| (1..=crate::engine::indicators::MAX_PERIOD).contains(&i.period());
+        match self {
+            Condition::Price => Ok(()),
+            Condition::Notional {
+                threshold,
+                quantity,
+            } => {
+                if *threshold <= Decimal::ZERO || quantity.is_some_and(|q| q <= Decimal::ZERO) {
+                    return Err("notional threshold and quantity must be positive".into());
+                }
+                Ok(())
+            }
+            Condition::Crossover { fast, slow, .. } => {
+                if !period_ok(fast) || !period_ok(slow) {
+                    return Err(format!(
+                        "indicator periods must be 1..={}",
+                        crate::engine::indicators::MAX_PERIOD
+                    ));
+                }
+                if fast == slow {
+                    return Err("crossover needs two different indicators".into());
+                }
+                Ok(())
+            }
+            Condition::Level {
+                indicator,
+                below,
+                above,
+            } => {
+                if !period_ok(indicator) {
+                    return Err(format!(
+                        "indicator periods must be 1..={}",
+                        crate::engine::indicators::MAX_PERIOD
+                    ));
+                }
+                if below.is_none() && above.is_none() {
+                    return Err("level condition needs `below` or `above`".into());
+                }
+                Ok(())
+            }
+            Condition::Volume {
+                window_ms,
+                min_volume,
+                ..
+            } => {
+                let max = crate::engine::volume::MAX_WINDOW_MS;
+                if !(1..=max).contains(window_ms) {
+                    return Err(format!("volume window_ms must be 1..={max}"));
+                }
+                if *min_volume <= Decimal::ZERO {
+                    return Err("volume min_volume must be positive".into());
+                }
+                Ok(())
+            }
+            Condition::Spread {
+                legs, below, above, ..
+            } => {
+                if legs[0] == legs[1] {
+                    return Err("spread needs two different legs".into());
+                }
+                if below.is_none() && above.is_none() {
+                    return Err("spread condition needs `below` or `above`".into());
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+pub const CANCEL_FOK_UNFILLABLE: &str = "fok_unfillable";
+
+/// `cancel_reason` of an IOC order, or what was left of it, after its one
+/// evaluation.
+pub const CANCEL_IOC: &str = "ioc_unfilled";
+
+/// `cancel_reason` of a quote-sized order whose amount buys less than the
+/// smallest quantity the pair allows at the execution price.
+pub const CANCEL_QUOTE_TOO_SMALL: &str = "quote_too_small";
+
+/// `cancel_reason` of an order still active when its `cancel_after_ms`
+/// ran out.
+pub const CANCEL_AFTER: &str = "cancel_after";
+
+/// `cancel_reason` of an order still active `valid_for_after_armed_ms`
+/// after it armed.
+pub const CANCEL_AFTER_ARMED: &str = "expired_after_armed";
+
+/// One execution against the simulated book: a single depth level, or the
+/// whole quantity at one price where there is no depth. `ts_ms` is the
+/// oracle's timestamp on the tick that triggered it, `received_ms` when
+/// the cache accepted that tick and `decided_ms` when the engine decided
+/// to fill, both by the local clock; fills from before these were
+/// recorded lack them. `recovered_trigger` marks a fill for a trigger the
+/// engine may have missed while down, honoured after it came back, and
+/// `interpolated_trigger` one whose reference was a price filled in
+/// between two ticks rather than a tick's own. `legs`
+/// are the prices a spread condition triggered on. `trigger_price` is the
+/// reference price the order triggered at, against which fill quality is
+/// measured.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Fill {
+    #[serde(serialize_with = "crate::utils::decimal::serialize")]
+    pub price: Decimal,
+    #[serde(serialize_with = "crate::utils::decimal::serialize")]
+    pub quantity: Decimal,
+    pub ts_ms: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub received_ms: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub decided_ms: Option<i64>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub recovered_trigger: bool,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub interpolated_trigger: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub legs: Vec<LegPrice>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "crate::utils::decimal::serialize_opt"
+    )]
+    pub trigger_price: Option<Decimal>,
+    /// The healthy venues' prices the fair-price band was built from when
+    /// the fill went through it.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fair_sources: Vec<SourcePrice>,
+    /// The resting order on the other side, when the fill crossed two
+    /// orders instead of the oracle.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub counterparty: Option<String>,
+}
+
+/// One venue's price as the engine read it for the fair-price band.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SourcePrice {
+    pub source: String,
+    #[serde(serialize_with = "crate::utils::decimal::serialize")]
+    pub price: Decimal,
+    pub ts_ms: i64,
+}
+
+/// One leg of a spread as the engine read it: the pair's oracle price and
+/// that tick's timestamp.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct LegPrice {
+    pub pair: Symbol,
+    #[serde(serialize_with = "crate::utils::decimal::serialize")]
+    pub price: Decimal,
+    pub ts_ms: i64,
+}
+
+impl Fill {
+    /// Receive time minus oracle time: network delay plus the offset
+    /// between the two clocks, negative when the oracle's runs ahead.
+    pub fn clock_skew_ms(&self) -> Option<i64> {
+        self.received_ms.map(|r| r - self.ts_ms)
+    }
+
+    /// How long the tick sat in the cache before the engine acted on it.
+    pub fn processing_ms(&self) -> Option<i64> {
+        Some(self.decided_ms? - self.received_ms?)
+    }
+
+    pub fn end_to_end_ms(&self) -> Option<i64> {
+        self.decided_ms.map(|d| d - self.ts_ms)
+    }
+
+    /// When the fill happened by the local clock: the engine's decision,
+    /// else the tick's arrival, else the oracle's timestamp.
+    pub fn local_ms(&self) -> i64 {
+        self.decided_ms.or(self.received_ms).unwrap_or(self.ts_ms)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Order {
     pub id: String,
-    pub pair: String,
+    pub pair: Symbol,
     pub side: OrderSide,
+    #[serde(default)]
+    pub order_type: OrderType,
+    #[serde(serialize_with = "crate::utils::decimal::serialize")]
     pub price: Decimal,
+    #[serde(serialize_with = "crate::utils::decimal::serialize")]
     pub quantity: Decimal,
+    /// The quote-currency amount to spend (or raise, selling), when the
+    /// order was sized that way. Until the first fill `quantity` is only
+    /// an estimate at the limit price; the engine fixes it from the
+    /// execution price when the order triggers.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "crate::utils::decimal::serialize_opt"
+    )]
+    pub quantity_quote: Option<Decimal>,
     pub status: OrderStatus,
+    #[serde(default, alias = "trigger_on")]
+    pub trigger_price_type: TriggerPriceType,
+    /// The oracle venue the trigger reference is read from; absent for the
+    /// aggregate feed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub price_source: Option<String>,
+    #[serde(default)]
+    pub condition: Condition,
+    #[serde(default)]
+    pub sustained_for_ms: Option<i64>,
+    #[serde(default)]
+    pub time_in_force: TimeInForce,
+    /// Cancels whatever is left of the order this long after creation.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cancel_after_ms: Option<i64>,
+    /// Cancels whatever is left of the order this long after it armed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub valid_for_after_armed_ms: Option<i64>,
+    /// When a GTD order expires if still working, in epoch ms.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<i64>,
+    /// When the order first triggered, in local time: the decision time of
+    /// its first fill. An order that stays active after that is armed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub armed_ms: Option<i64>,
+    #[serde(default)]
+    pub max_slippage_bps: Option<Decimal>,
+    #[serde(default, serialize_with = "crate::utils::decimal::serialize")]
+    pub filled_quantity: Decimal,
+    #[serde(default, serialize_with = "crate::utils::decimal::serialize_opt")]
+    pub exec_price: Option<Decimal>,
+    /// How `exec_price` is set; fixed at placement for price-triggered
+    /// limit orders, absent (oracle pricing) for the rest.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exec_pricing: Option<ExecPricing>,
+    /// Every execution so far, oldest first; `exec_price` is their VWAP.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fills: Vec<Fill>,
+    /// The stop level of a `stop` or `stop_limit` order. It stays on the
+    /// order once the stop has gone off.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "crate::utils::decimal::serialize_opt"
+    )]
+    pub trigger_price: Option<Decimal>,
+    /// When the stop went off and the order became a market or limit one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stop_triggered_ms: Option<i64>,
+    /// Set on a trailing stop, whose `trigger_price` follows the reference.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trailing: Option<Trail>,
+    /// The best reference a trailing stop has seen while dormant: the
+    /// highest for a sell, the lowest for a buy.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "crate::utils::decimal::serialize_opt"
+    )]
+    pub watermark: Option<Decimal>,
+    /// Milliseconds from creation to the fill, set when the engine fills.
+    #[serde(default)]
+    pub triggered_after_ms: Option<i64>,
+    #[serde(default)]
+    pub cancel_reason: Option<String>,
+    /// The order a cancel/replace created in this one's place.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub replaced_by: Option<String>,
+    /// The order this one replaced.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub replaces: Option<String>,
+    /// Shared by the two legs of a one-cancels-other pair; the first fill
+    /// on one leg cancels the other.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub oco_id: Option<String>,
+    /// The entry of the bracket this order is a take-profit or stop-loss
+    /// child of.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent_id: Option<String>,
+    /// The account that placed the order, when it was placed with an API
+    /// key.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub account_id: Option<String>,
+    /// Free-form labels set at placement, for finding the order later.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub metadata: BTreeMap<String, String>,
+    /// Where the order's own lifecycle events are posted, signed with its
+    /// account's webhook secret.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub callback_url: Option<String>,
+    /// The JSON posted to `callback_url`, with `{{...}}` variables; see
+    /// [`render`](crate::entities::callback::render).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub callback_template: Option<Value>,
     pub created: i64,
     pub updated: i64,
 }
@@ -37,24 +598,325 @@ pub struct Order {
 pub struct NewOrder {
     pub pair: String,
     pub side: OrderSide,
+    pub order_type: OrderType,
     pub price: Decimal,
     pub quantity: Decimal,
+    #[serde(default)]
+    pub trigger_price: Option<Decimal>,
+    #[serde(default)]
+    pub trailing: Option<Trail>,
+    #[serde(default)]
+    pub oco_id: Option<String>,
+    #[serde(default)]
+    pub quantity_quote: Option<Decimal>,
+    #[serde(default)]
+    pub cancel_after_ms: Option<i64>,
+    #[serde(default)]
+    pub valid_for_after_armed_ms: Option<i64>,
+    #[serde(default)]
+    pub expires_at: Option<i64>,
+    pub trigger_price_type: TriggerPriceType,
+    #[serde(default)]
+    pub price_source: Option<String>,
+    pub condition: Condition,
+    pub sustained_for_ms: Option<i64>,
+    pub time_in_force: TimeInForce,
+    pub max_slippage_bps: Option<Decimal>,
+    #[serde(default)]
+    pub exec_pricing: Option<ExecPricing>,
+    #[serde(default)]
+    pub account_id: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub metadata: BTreeMap<String, String>,
+    #[serde(default)]
+    pub callback_url: Option<String>,
+    #[serde(default)]
+    pub callback_template: Option<Value>,
+}
+
+pub const MAX_TAGS: usize = 16;
+pub const MAX_METADATA: usize = 16;
+pub const MAX_LABEL_LEN: usize = 64;
+
+/// Tags and metadata keys are single words, so search terms and
+/// `key:value` filters can refer to them; metadata keys hold no `:`.
+pub fn validate_labels(tags: &[String], metadata: &BTreeMap<String, String>) -> Result<(), String> {
+    if tags.len() > MAX_TAGS || metadata.len() > MAX_METADATA {
+        return Err(format!(
+            "at most {MAX_TAGS} tags and {MAX_METADATA} metadata entries"
+        ));
+    }
+    let word =
+        |s: &str| !s.is_empty() && s.len() <= MAX_LABEL_LEN && !s.contains(char::is_whitespace);
+    if let Some(t) = tags.iter().find(|t| !word(t)) {
+        return Err(format!(
+            "tag {t:?} must be 1 to {MAX_LABEL_LEN} characters without spaces"
+        ));
+    }
+    for (k, v) in metadata {
+        if !word(k) || k.contains(':') {
+            return Err(format!(
+                "metadata key {k:?} must be 1 to {MAX_LABEL_LEN} characters without spaces or ':'"
+            ));
+        }
+        if !word(v) {
+            return Err(format!(
+                "metadata value for {k:?} must be 1 to {MAX_LABEL_LEN} characters without spaces"
+            ));
+        }
+    }
+    Ok(())
 }
 
+/// `cancel_reason` of an order its owner cancelled.
+pub const CANCEL_BY_OWNER: &str = "owner_request";
+
+/// `cancel_reason` of an order cancelled by a cancel/replace; its
+/// `replaced_by` names the replacement.
+pub const CANCEL_REPLACED: &str = "replaced";
+
+/// `cancel_reason` of an order an operator cancelled; the audit log has
+/// their reason.
+pub const CANCEL_BY_ADMIN: &str = "admin_intervention";
+
+/// `cancel_reason` of an order cancelled because its account's data was
+/// purged or anonymized.
+pub const CANCEL_ACCOUNT_PURGE: &str = "account_purge";
+
+/// `cancel_reason` of an order still active when its pair was delisted.
+pub const CANCEL_DELISTED: &str = "delisted";
+
+/// `cancel_reason` of a liquidity bot quote moved to follow the price.
+pub const CANCEL_BOT_REQUOTE: &str = "bot_requote";
+
+/// `cancel_reason` of an order cancelled by a housekeeping policy for its
+/// age.
+pub const CANCEL_HOUSEKEEPING: &str = "housekeeping";
+
+/// `cancel_reason` of an OCO leg withdrawn because the other leg filled.
+pub const CANCEL_OCO: &str = "oco";
+
+/// `cancel_reason` of a bracket child whose entry ended without a fill.
+pub const CANCEL_BRACKET_ENTRY: &str = "bracket_entry_unfilled";
+
 impl Order {
-    pub fn new(pair: String, side: OrderSide, price: Decimal, quantity: Decimal) -> Self {
+    pub fn new(pair: Symbol, side: OrderSide, price: Decimal, quantity: Decimal) -> Self {
         let now = now_ms();
         Self {
-            id: Uuid::new_v4().to_string(),
+            id: crate::utils::ids::next_id(),
             pair,
             side,
+            order_type: OrderType::Limit,
             price,
             quantity,
+            quantity_quote: None,
+            cancel_after_ms: None,
+            valid_for_after_armed_ms: None,
+            expires_at: None,
+            armed_ms: None,
             status: OrderStatus::New,
+            trigger_price_type: TriggerPriceType::Last,
+            price_source: None,
+            condition: Condition::Price,
+            sustained_for_ms: None,
+            time_in_force: TimeInForce::Gtc,
+            max_slippage_bps: None,
+            filled_quantity: Decimal::ZERO,
+            exec_price: None,
+            exec_pricing: None,
+            fills: Vec::new(),
+            trigger_price: None,
+            stop_triggered_ms: None,
+            trailing: None,
+            watermark: None,
+            triggered_after_ms: None,
+            cancel_reason: None,
+            replaced_by: None,
+            replaces: None,
+            oco_id: None,
+            parent_id: None,
+            account_id: None,
+            tags: Vec::new(),
+            metadata: BTreeMap::new(),
+            callback_url: None,
+            callback_template: None,
             created: now,
             updated: now,
         }
     }
+
+    pub fn from_new(new: NewOrder) -> Self {
+        Self {
+            order_type: new.order_type,
+            trigger_price: new.trigger_price,
+            trailing: new.trailing,
+            oco_id: new.oco_id,
+            trigger_price_type: new.trigger_price_type,
+            price_source: new.price_source,
+            condition: new.condition,
+            sustained_for_ms: new.sustained_for_ms,
+            time_in_force: new.time_in_force,
+            max_slippage_bps: new.max_slippage_bps,
+            exec_pricing: new.exec_pricing,
+            account_id: new.account_id,
+            quantity_quote: new.quantity_quote,
+            cancel_after_ms: new.cancel_after_ms,
+            valid_for_after_armed_ms: new.valid_for_after_armed_ms,
+            expires_at: new.expires_at,
+            tags: new.tags,
+            metadata: new.metadata,
+            callback_url: new.callback_url,
+            callback_template: new.callback_template,
+            ..Self::new(new.pair.into(), new.side, new.price, new.quantity)
+        }
+    }
+
+    /// Drops what ties the order to whoever placed it: the account, the
+    /// free-form tags and metadata and the callback. Prices, quantities and
+    /// fills stay.
+    pub fn anonymize(&mut self) {
+        self.account_id = None;
+        self.tags.clear();
+        self.metadata.clear();
+        self.callback_url = None;
+        self.callback_template = None;
+    }
+
+    /// The request that would place this order again: same pair, trigger,
+    /// execution settings, account and labels, without its fills.
+    pub fn to_new(&self) -> NewOrder {
+        NewOrder {
+            pair: self.pair.to_string(),
+            side: self.side.clone(),
+            order_type: self.order_type,
+            price: self.price,
+            quantity: self.quantity,
+            trigger_price: self.trigger_price,
+            trailing: self.trailing,
+            oco_id: self.oco_id.clone(),
+            quantity_quote: self.quantity_quote,
+            cancel_after_ms: self.cancel_after_ms,
+            valid_for_after_armed_ms: self.valid_for_after_armed_ms,
+            expires_at: self.expires_at,
+            trigger_price_type: self.trigger_price_type,
+            price_source: self.price_source.clone(),
+            condition: self.condition.clone(),
+            sustained_for_ms: self.sustained_for_ms,
+            time_in_force: self.time_in_force,
+            max_slippage_bps: self.max_slippage_bps,
+            exec_pricing: self.exec_pricing,
+            account_id: self.account_id.clone(),
+            tags: self.tags.clone(),
+            metadata: self.metadata.clone(),
+            callback_url: self.callback_url.clone(),
+            callback_template: self.callback_template.clone(),
+        }
+    }
+
+    /// The oracle price at which the order's condition holds, for
+    /// conditions that reduce to one: its price, or a notional threshold
+    /// over its quantity. Buys trigger at or below it, sells at or above.
+    pub fn trigger_level(&self) -> Option<Decimal> {
+        if self.order_type.is_stop() {
+            return None;
+        }
+        let level = match &self.condition {
+            Condition::Price => self.price,
+            Condition::Notional {
+                threshold,
+                quantity,
+            } => {
+                let q = quantity.unwrap_or(self.quantity);
+                if q <= Decimal::ZERO {
+                    return None;
+                }
+                threshold / q
+            }
+            _ => return None,
+        };
+        (level > Decimal::ZERO).then_some(level)
+    }
+
+    /// Whether a dormant stop's reference `px` has reached its trigger: at
+    /// or above it for a buy, at or below for a sell.
+    pub fn stop_hit(&self, px: Decimal) -> bool {
+        match (self.order_type.is_stop(), self.trigger_price) {
+            (true, Some(stop)) => match self.side {
+                OrderSide::Buy => px >= stop,
+                OrderSide::Sell => px <= stop,
+            },
+            _ => false,
+        }
+    }
+
+    /// Where a dormant trailing stop's watermark and trigger move to after
+    /// reference `px`, or `None` when neither moves. The trigger only ever
+    /// ratchets toward the price, never back.
+    pub fn trail(&self, px: Decimal) -> Option<(Decimal, Decimal)> {
+        let trail = self.trailing.filter(|_| self.order_type.is_stop())?;
+        let wm = match (&self.side, self.watermark) {
+            (_, None) => px,
+            (OrderSide::Buy, Some(wm)) => wm.min(px),
+            (OrderSide::Sell, Some(wm)) => wm.max(px),
+        };
+        if self.watermark == Some(wm) && self.trigger_price.is_some() {
+            return None;
+        }
+        let level = trail.level(&self.side, wm);
+        let stop = match (&self.side, self.trigger_price) {
+            (_, None) => level,
+            (OrderSide::Buy, Some(stop)) => stop.min(level),
+            (OrderSide::Sell, Some(stop)) => stop.max(level),
+        };
+        Some((wm, stop))
+    }
+
+    /// When the order is due to be cancelled, the earlier of its
+    /// `cancel_after_ms` and, once armed, its `valid_for_after_armed_ms`.
+    pub fn cancel_at_ms(&self) -> Option<i64> {
+        self.cancel_deadline().map(|(at, _)| at)
+    }
+
+    /// [`cancel_at_ms`](Self::cancel_at_ms) with the `cancel_reason` it
+    /// cancels with.
+    pub fn cancel_deadline(&self) -> Option<(i64, &'static str)> {
+        let after = self
+            .cancel_after_ms
+            .map(|ms| (self.created.saturating_add(ms), CANCEL_AFTER));
+        let armed = self
+            .armed_ms
+            .zip(self.valid_for_after_armed_ms)
+            .map(|(at, ms)| (at.saturating_add(ms), CANCEL_AFTER_ARMED));
+        after.into_iter().chain(armed).min_by_key(|(at, _)| *at)
+    }
+
+    /// How many trigger events have filled the order so far. Each writes
+    /// its fills together, stamped with the tick that triggered it.
+    pub fn trigger_seq(&self) -> u64 {
+        self.fills.chunk_by(|a, b| a.ts_ms == b.ts_ms).count() as u64
+    }
+
+    /// Appends `fills`, arms the order on its first, re-prices `exec_price` as the VWAP of every fill
+    /// and marks the order `Filled` once nothing remains, `PartiallyFilled`
+    /// before.
+    pub fn apply_fills(&mut self, fills: &[Fill]) {
+        if let (None, Some(f)) = (self.armed_ms, fills.first()) {
+            self.armed_ms = Some(f.local_ms());
+        }
+        self.fills.extend_from_slice(fills);
+        self.filled_quantity += fills.iter().map(|f| f.quantity).sum::<Decimal>();
+        if !self.filled_quantity.is_zero() {
+            let notional: Decimal = self.fills.iter().map(|f| f.price * f.quantity).sum();
+            self.exec_price = Some(notional / self.filled_quantity);
+        }
+        self.status = if self.filled_quantity >= self.quantity {
+            OrderStatus::Filled
+        } else {
+            OrderStatus::PartiallyFilled
+        };
+    }
 }
 
 #[cfg(test)]
@@ -75,6 +937,9 @@ mod tests {
         assert_eq!(o.price, dec!(100.5));
         assert_eq!(o.quantity, dec!(2.0));
         assert_eq!(o.status, OrderStatus::New);
+        assert_eq!(o.trigger_price_type, TriggerPriceType::Last);
+        assert_eq!(o.order_type, OrderType::Limit);
+        assert_eq!(o.exec_price, None);
         assert!(!o.id.is_empty());
         assert!(
             is_plausible_ms(o.created),
@@ -89,6 +954,32 @@ mod tests {
         assert!(o.updated >= o.created);
     }
 
+    #[test]
+    fn fills_accumulate_into_a_vwap() {
+        let mut o = Order::new("BTC/USDT".into(), OrderSide::Buy, dec!(100), dec!(3));
+        let fill = |price, quantity| Fill {
+            price,
+            quantity,
+            ts_ms: 1,
+            received_ms: None,
+            decided_ms: None,
+            recovered_trigger: false,
+            interpolated_trigger: false,
+            legs: Vec::new(),
+            trigger_price: None,
+            fair_sources: Vec::new(),
+            counterparty: None,
+        };
+        o.apply_fills(&[fill(dec!(100), dec!(1)), fill(dec!(101), dec!(1))]);
+        assert_eq!(o.status, OrderStatus::PartiallyFilled);
+        assert_eq!(o.exec_price, Some(dec!(100.5)));
+        o.apply_fills(&[fill(dec!(103), dec!(1))]);
+        assert_eq!(o.status, OrderStatus::Filled);
+        assert_eq!(o.filled_quantity, dec!(3));
+        assert_eq!(o.exec_price, Some(dec!(304) / dec!(3)));
+        assert_eq!(o.fills.len(), 3);
+    }
+
     #[test]
     fn order_side_serde_is_snake_case() {
         let s_buy = serde_json::to_string(&OrderSide::Buy).unwrap();
@@ -99,6 +990,22 @@ mod tests {
         assert_eq!(back, OrderSide::Buy);
     }
 
+    #[test]
+    fn status_transitions_only_move_forward() {
+        use OrderStatus::*;
+        assert!(New.can_transition_to(&Open));
+        assert!(Open.can_transition_to(&Filled));
+        assert!(PartiallyFilled.can_transition_to(&PartiallyFilled));
+        assert!(!Open.can_transition_to(&New));
+        assert!(!Open.can_transition_to(&Open));
+        assert!(!Filled.can_transition_to(&Cancelled));
+        assert!(!Cancelled.can_transition_to(&Open));
+        assert!(Open.can_transition_to(&Suspended));
+        assert!(Suspended.can_transition_to(&Open));
+        assert!(!Filled.can_transition_to(&Suspended));
+        assert!(!Suspended.is_active());
+    }
+
     #[test]
     fn order_status_serde_is_snake_case() {
         let s = serde_json::to_string(&OrderStatus::New).unwrap();
@@ -106,4 +1013,130 @@ mod tests {
         let back: OrderStatus = serde_json::from_str(&s).unwrap();
         assert_eq!(back, OrderStatus::New);
     }
+
+    #[test]
+    fn notional_condition_is_tagged_and_quantity_optional() {
+        let c: Condition =
+            serde_json::from_str(r#"{"type":"notional","threshold":"50000"}"#).unwrap();
+        assert_eq!(
+            c,
+            Condition::Notional {
+                threshold: dec!(50000),
+                quantity: None
+            }
+        );
+        let raw = r#"{"id":"a","pair":"BTC/USDT","side":"buy","price":"1","quantity":"1","status":"new","created":1,"updated":1}"#;
+        let o: Order = serde_json::from_str(raw).unwrap();
+        assert_eq!(o.condition, Condition::Price);
+    }
+
+    #[test]
+    fn volume_condition_defaults_to_volume_alone_and_bounds_its_window() {
+        let c: Condition =
+            serde_json::from_str(r#"{"type":"volume","window_ms":60000,"min_volume":"12.5"}"#)
+                .unwrap();
+        assert_eq!(
+            c,
+            Condition::Volume {
+                window_ms: 60_000,
+                min_volume: dec!(12.5),
+                with_price: false
+            }
+        );
+        assert!(c.validate().is_ok());
+        assert!(!c.uses_price());
+        let bad = |window_ms, min_volume| {
+            Condition::Volume {
+                window_ms,
+                min_volume,
+                with_price: true,
+            }
+            .validate()
+        };
+        assert!(bad(0, dec!(1)).is_err());
+        assert!(bad(crate::engine::volume::MAX_WINDOW_MS + 1, dec!(1)).is_err());
+        assert!(bad(1_000, dec!(0)).is_err());
+    }
+
+    #[test]
+    fn trigger_price_type_defaults_to_last_when_absent() {
+        let raw = r#"{"id":"a","pair":"BTC/USDT","side":"buy","price":"1","quantity":"1","status":"new","created":1,"updated":1}"#;
+        let o: Order = serde_json::from_str(raw).unwrap();
+        assert_eq!(o.trigger_price_type, TriggerPriceType::Last);
+        let s = serde_json::to_string(&TriggerPriceType::Mark).unwrap();
+        assert_eq!(s, "\"mark\"");
+    }
+
+    #[test]
+    fn trigger_price_type_accepts_legacy_trigger_on_key() {
+        let raw = r#"{"id":"a","pair":"BTC/USDT","side":"buy","price":"1","quantity":"1","status":"new","trigger_on":"bid","created":1,"updated":1}"#;
+        let o: Order = serde_json::from_str(raw).unwrap();
+        assert_eq!(o.trigger_price_type, TriggerPriceType::Bid);
+    }
+
+    #[test]
+    fn exec_pricing_never_fills_a_buy_above_its_limit_under_better_of() {
+        let buy = OrderSide::Buy;
+        assert_eq!(
+            ExecPricing::Oracle.price(&buy, dec!(90), dec!(100)),
+            dec!(90)
+        );
+        assert_eq!(
+            ExecPricing::Limit.price(&buy, dec!(90), dec!(100)),
+            dec!(100)
+        );
+        assert_eq!(
+            ExecPricing::BetterOf.price(&buy, dec!(90), dec!(100)),
+            dec!(90)
+        );
+        assert_eq!(
+            ExecPricing::BetterOf.price(&buy, dec!(101), dec!(100)),
+            dec!(100)
+        );
+        assert_eq!(ExecPricing::parse("Better_Of"), Some(ExecPricing::BetterOf));
+        assert_eq!(ExecPricing::parse("mid"), None);
+    }
+
+    #[test]
+    fn trailing_stops_ratchet_toward_the_price_and_never_back() {
+        let mut sell = Order {
+            order_type: OrderType::Stop,
+            trailing: Some(Trail::Percent(dec!(2))),
+            ..Order::new("BTC/USDT".into(), OrderSide::Sell, dec!(0), dec!(1))
+        };
+        fn step(o: &mut Order, px: Decimal) -> Option<(Decimal, Decimal)> {
+            let moved = o.trail(px);
+            if let Some((wm, stop)) = moved {
+                (o.watermark, o.trigger_price) = (Some(wm), Some(stop));
+            }
+            moved
+        }
+        assert_eq!(step(&mut sell, dec!(100)), Some((dec!(100), dec!(98))));
+        assert_eq!(step(&mut sell, dec!(110)), Some((dec!(110), dec!(107.8))));
+        assert_eq!(step(&mut sell, dec!(108)), None);
+        assert!(!sell.stop_hit(dec!(108)) && sell.stop_hit(dec!(107.8)));
+
+        // A buy trails the low from above; a trigger given at placement
+        // holds until the trail passes it.
+        let mut buy = Order {
+            order_type: OrderType::Stop,
+            trailing: Some(Trail::Amount(dec!(5))),
+            trigger_price: Some(dec!(103)),
+            ..Order::new("BTC/USDT".into(), OrderSide::Buy, dec!(0), dec!(1))
+        };
+        assert_eq!(step(&mut buy, dec!(100)), Some((dec!(100), dec!(103))));
+        assert_eq!(step(&mut buy, dec!(96)), Some((dec!(96), dec!(101))));
+        assert_eq!(step(&mut buy, dec!(99)), None);
+        assert_eq!(
+            Order::new("BTC/USDT".into(), OrderSide::Buy, dec!(1), dec!(1)).trail(dec!(1)),
+            None
+        );
+
+        let t: Trail = serde_json::from_str(r#"{"percent": "2.5"}"#).unwrap();
+        assert_eq!(t, Trail::Percent(dec!(2.5)));
+        assert_eq!(
+            serde_json::to_value(Trail::Amount(dec!(5))).unwrap(),
+            serde_json::json!({"amount": "5"})
+        );
+    }
 }
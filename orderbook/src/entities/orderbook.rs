@@ -8,7 +8,7 @@ pub struct OrderBook {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use serde_json::{json, Value};
+    use serde_json::Value;
 
     #[test]
     fn default_is_empty_pair() {
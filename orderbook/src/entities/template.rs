@@ -0,0 +1,87 @@
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+use crate::entities::order::{
+    Condition, OrderSide, OrderType, TimeInForce, Trail, TriggerPriceType,
+};
+use crate::utils::now_ms;
+
+/// A reusable order setup. `price` and `quantity` are optional so a
+/// template can leave them to be supplied when it is instantiated.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderTemplate {
+    pub id: String,
+    pub name: Option<String>,
+    pub pair: String,
+    pub side: OrderSide,
+    pub order_type: OrderType,
+    #[serde(serialize_with = "crate::utils::decimal::serialize_opt")]
+    pub price: Option<Decimal>,
+    #[serde(serialize_with = "crate::utils::decimal::serialize_opt")]
+    pub quantity: Option<Decimal>,
+    pub trigger_price_type: TriggerPriceType,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "crate::utils::decimal::serialize_opt"
+    )]
+    pub trigger_price: Option<Decimal>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trailing: Option<Trail>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub price_source: Option<String>,
+    pub condition: Condition,
+    pub sustained_for_ms: Option<i64>,
+    pub time_in_force: TimeInForce,
+    pub max_slippage_bps: Option<Decimal>,
+    pub tags: Vec<String>,
+    pub created: i64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct NewTemplate {
+    pub name: Option<String>,
+    pub pair: String,
+    pub side: OrderSide,
+    #[serde(default)]
+    pub order_type: OrderType,
+    pub price: Option<Decimal>,
+    pub quantity: Option<Decimal>,
+    #[serde(default, alias = "trigger_on")]
+    pub trigger_price_type: TriggerPriceType,
+    pub trigger_price: Option<Decimal>,
+    pub trailing: Option<Trail>,
+    pub price_source: Option<String>,
+    #[serde(default)]
+    pub condition: Condition,
+    pub sustained_for_ms: Option<i64>,
+    #[serde(default)]
+    pub time_in_force: TimeInForce,
+    pub max_slippage_bps: Option<Decimal>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+impl OrderTemplate {
+    pub fn from_new(new: NewTemplate) -> Self {
+        Self {
+            id: crate::utils::ids::next_id(),
+            name: new.name,
+            pair: new.pair,
+            side: new.side,
+            order_type: new.order_type,
+            price: new.price,
+            quantity: new.quantity,
+            trigger_price_type: new.trigger_price_type,
+            trigger_price: new.trigger_price,
+            trailing: new.trailing,
+            price_source: new.price_source,
+            condition: new.condition,
+            sustained_for_ms: new.sustained_for_ms,
+            time_in_force: new.time_in_force,
+            max_slippage_bps: new.max_slippage_bps,
+            tags: new.tags,
+            created: now_ms(),
+        }
+    }
+}
@@ -0,0 +1,446 @@
+//! Fixtures for tests of code built on this crate: order builders, an
+//! in-memory repository that can be told to fail, an oracle feed that
+//! plays back a fixed script, and a frozen clock and counting ID generator
+//! for output that must not change between runs. Compiled for this
+//! crate's own tests and, elsewhere, behind the `test-support` feature.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use rust_decimal::Decimal;
+use tokio::sync::RwLock;
+
+use crate::entities::order::{
+    ExecPricing, Fill, NewOrder, Order, OrderSide, OrderStatus, OrderType, TimeInForce, Trail,
+};
+use crate::instruments::symbol::Symbol;
+use crate::oracle_service::{OracleCache, Tick, TickOutcome};
+use crate::repositories::{ListOrdersQuery, OrderRepository};
+use crate::utils::clock::Clock;
+use crate::utils::ids::IdGenerator;
+use crate::utils::now_ms;
+
+/// Builds an [`Order`] or [`NewOrder`] from the fields a test cares about:
+/// `OrderBuilder::buy("BTC/USDT").limit(100).qty(1).build()`. Everything
+/// not set is what [`Order::new`] gives a fresh limit order.
+#[derive(Debug, Clone)]
+pub struct OrderBuilder {
+    order: Order,
+}
+
+impl OrderBuilder {
+    pub fn buy(pair: impl Into<Symbol>) -> Self {
+        Self::side(pair, OrderSide::Buy)
+    }
+
+    pub fn sell(pair: impl Into<Symbol>) -> Self {
+        Self::side(pair, OrderSide::Sell)
+    }
+
+    fn side(pair: impl Into<Symbol>, side: OrderSide) -> Self {
+        Self {
+            order: Order::new(pair.into(), side, Decimal::ZERO, Decimal::ONE),
+        }
+    }
+
+    pub fn id(mut self, id: impl Into<String>) -> Self {
+        self.order.id = id.into();
+        self
+    }
+
+    pub fn limit(mut self, price: impl Into<Decimal>) -> Self {
+        self.order.order_type = OrderType::Limit;
+        self.order.price = price.into();
+        self
+    }
+
+    /// A market order; the price is left at zero.
+    pub fn market(mut self) -> Self {
+        self.order.order_type = OrderType::Market;
+        self.order.price = Decimal::ZERO;
+        self
+    }
+
+    /// A stop order that becomes a market order at `trigger`.
+    pub fn stop(mut self, trigger: impl Into<Decimal>) -> Self {
+        self.order.order_type = OrderType::Stop;
+        self.order.price = Decimal::ZERO;
+        self.order.trigger_price = Some(trigger.into());
+        self
+    }
+
+    /// A stop order that becomes a limit order at `price` once `trigger`
+    /// is reached.
+    pub fn stop_limit(mut self, price: impl Into<Decimal>, trigger: impl Into<Decimal>) -> Self {
+        self.order.order_type = OrderType::StopLimit;
+        self.order.price = price.into();
+        self.order.trigger_price = Some(trigger.into());
+        self
+    }
+
+    /// A stop order that becomes a market order once the reference moves
+    /// `trail` back from its best.
+    pub fn trailing_stop(mut self, trail: Trail) -> Self {
+        self.order.order_type = OrderType::Stop;
+        self.order.price = Decimal::ZERO;
+        self.order.trailing = Some(trail);
+        self
+    }
+
+    pub fn qty(mut self, quantity: impl Into<Decimal>) -> Self {
+        self.order.quantity = quantity.into();
+        self
+    }
+
+    pub fn status(mut self, status: OrderStatus) -> Self {
+        self.order.status = status;
+        self
+    }
+
+    pub fn filled(mut self, quantity: impl Into<Decimal>) -> Self {
+        self.order.filled_quantity = quantity.into();
+        self
+    }
+
+    pub fn time_in_force(mut self, tif: TimeInForce) -> Self {
+        self.order.time_in_force = tif;
+        self
+    }
+
+    pub fn max_slippage_bps(mut self, bps: impl Into<Decimal>) -> Self {
+        self.order.max_slippage_bps = Some(bps.into());
+        self
+    }
+
+    pub fn exec_pricing(mut self, pricing: ExecPricing) -> Self {
+        self.order.exec_pricing = Some(pricing);
+        self
+    }
+
+    pub fn account(mut self, account_id: impl Into<String>) -> Self {
+        self.order.account_id = Some(account_id.into());
+        self
+    }
+
+    pub fn build(self) -> Order {
+        self.order
+    }
+
+    /// The request that would create this order; id, status and fill
+    /// state are dropped.
+    pub fn new_order(self) -> NewOrder {
+        let o = self.order;
+        NewOrder {
+            pair: o.pair.into(),
+            side: o.side,
+            order_type: o.order_type,
+            price: o.price,
+            quantity: o.quantity,
+            trigger_price_type: o.trigger_price_type,
+            price_source: o.price_source,
+            condition: o.condition,
+            sustained_for_ms: o.sustained_for_ms,
+            time_in_force: o.time_in_force,
+            max_slippage_bps: o.max_slippage_bps,
+            exec_pricing: o.exec_pricing,
+            account_id: o.account_id,
+            quantity_quote: o.quantity_quote,
+            cancel_after_ms: o.cancel_after_ms,
+            valid_for_after_armed_ms: o.valid_for_after_armed_ms,
+            expires_at: o.expires_at,
+            tags: o.tags,
+            metadata: o.metadata,
+            callback_url: o.callback_url,
+            callback_template: o.callback_template,
+            trigger_price: o.trigger_price,
+            trailing: o.trailing,
+            oco_id: o.oco_id,
+        }
+    }
+}
+
+/// A map-backed [`OrderRepository`] that fails on request: listing one
+/// status, or writing particular orders.
+#[derive(Clone, Default)]
+pub struct FakeRepo {
+    inner: Arc<RwLock<HashMap<String, Order>>>,
+    fail_list_on_status: Arc<RwLock<Option<OrderStatus>>>,
+    fail_set_for_ids: Arc<RwLock<HashSet<String>>>,
+}
+
+impl FakeRepo {
+    /// Stores `orders` as they are, ids included.
+    pub async fn seed(&self, orders: impl IntoIterator<Item = Order>) {
+        let mut w = self.inner.write().await;
+        for o in orders {
+            w.insert(o.id.clone(), o);
+        }
+    }
+
+    /// Makes `list` fail for queries on status `s`; `None` stops it.
+    pub async fn set_fail_list_on(&self, s: Option<OrderStatus>) {
+        *self.fail_list_on_status.write().await = s;
+    }
+
+    /// Makes every write to order `id` fail.
+    pub async fn fail_set_for(&self, id: &str) {
+        self.fail_set_for_ids.write().await.insert(id.to_string());
+    }
+
+    /// Lets writes through again.
+    pub async fn clear_set_failures(&self) {
+        self.fail_set_for_ids.write().await.clear();
+    }
+}
+
+#[async_trait]
+impl OrderRepository for FakeRepo {
+    async fn list(&self, q: ListOrdersQuery) -> Result<Vec<Order>, String> {
+        if let Some(status) = &q.status {
+            if Some(status.clone()) == *self.fail_list_on_status.read().await {
+                return Err(format!("boom listing {:?}", status));
+            }
+        }
+        let map = self.inner.read().await;
+        let mut v: Vec<Order> = map
+            .values()
+            .filter(|o| {
+                q.pair.as_ref().is_none_or(|p| &o.pair == p)
+                    && q.status.as_ref().is_none_or(|s| &o.status == s)
+            })
+            .cloned()
+            .collect();
+        if let Some(off) = q.offset {
+            if off < v.len() as i64 {
+                v.drain(0..off as usize);
+            } else {
+                v.clear();
+            }
+        }
+        if let Some(lim) = q.limit {
+            if v.len() > lim as usize {
+                v.truncate(lim as usize);
+            }
+        }
+        Ok(v)
+    }
+
+    async fn set_status(&self, id: &str, to: OrderStatus) -> Result<Order, String> {
+        if self.fail_set_for_ids.read().await.contains(id) {
+            return Err("boom set_status".into());
+        }
+        let mut map = self.inner.write().await;
+        let o = map.get_mut(id).ok_or_else(|| "not found".to_string())?;
+        o.status = to;
+        o.updated = now_ms();
+        Ok(o.clone())
+    }
+
+    async fn record_fill(&self, id: &str, exec_price: Decimal) -> Result<Order, String> {
+        let mut o = self.set_status(id, OrderStatus::Filled).await?;
+        o.filled_quantity = o.quantity;
+        o.exec_price = Some(exec_price);
+        self.inner.write().await.insert(o.id.clone(), o.clone());
+        Ok(o)
+    }
+
+    async fn record_fills(&self, id: &str, fills: &[Fill]) -> Result<Order, String> {
+        let mut o = self.set_status(id, OrderStatus::Filled).await?;
+        o.apply_fills(fills);
+        self.inner.write().await.insert(o.id.clone(), o.clone());
+        Ok(o)
+    }
+
+    async fn set_fill(
+        &self,
+        id: &str,
+        status: OrderStatus,
+        filled_quantity: Decimal,
+    ) -> Result<Order, String> {
+        let mut o = self.set_status(id, status).await?;
+        o.filled_quantity = filled_quantity;
+        self.inner.write().await.insert(o.id.clone(), o.clone());
+        Ok(o)
+    }
+
+    async fn cancel(&self, id: &str, reason: &str) -> Result<Order, String> {
+        let mut o = self.set_status(id, OrderStatus::Cancelled).await?;
+        o.cancel_reason = Some(reason.to_string());
+        self.inner.write().await.insert(o.id.clone(), o.clone());
+        Ok(o)
+    }
+
+    async fn amend(&self, id: &str, price: Decimal, qty: Decimal) -> Result<Order, String> {
+        let mut map = self.inner.write().await;
+        let o = map.get_mut(id).ok_or_else(|| "not found".to_string())?;
+        o.price = price;
+        o.quantity = qty;
+        Ok(o.clone())
+    }
+
+    async fn create(&self, n: NewOrder) -> Result<Order, String> {
+        let o = Order::from_new(n);
+        let mut map = self.inner.write().await;
+        map.insert(o.id.clone(), o.clone());
+        Ok(o)
+    }
+
+    async fn get_by_id(&self, id: &str) -> Result<Order, String> {
+        let map = self.inner.read().await;
+        map.get(id).cloned().ok_or_else(|| "not found".to_string())
+    }
+
+    async fn delete(&self, id: &str) -> Result<(), String> {
+        let mut map = self.inner.write().await;
+        match map.remove(id) {
+            Some(_) => Ok(()),
+            None => Err("not found".to_string()),
+        }
+    }
+}
+
+/// A fixed sequence of ticks fed into an [`OracleCache`] one step at a
+/// time. Each tick is stamped one millisecond after the one before, so none
+/// is dropped as a duplicate.
+#[derive(Debug, Clone)]
+pub struct ScriptedOracle {
+    script: VecDeque<Tick>,
+    next_ts_ms: i64,
+}
+
+impl Default for ScriptedOracle {
+    fn default() -> Self {
+        Self::starting_at(now_ms())
+    }
+}
+
+impl ScriptedOracle {
+    /// A script whose first tick is stamped `ts_ms`.
+    pub fn starting_at(ts_ms: i64) -> Self {
+        Self {
+            script: VecDeque::new(),
+            next_ts_ms: ts_ms,
+        }
+    }
+
+    /// Appends a last-price tick for `pair`.
+    pub fn then(self, pair: &str, price: impl Into<Decimal>) -> Self {
+        let ts_ms = self.next_ts_ms;
+        self.then_tick(Tick::new(pair, price.into(), ts_ms))
+    }
+
+    /// Appends `t` as given, restamped to follow the previous tick.
+    pub fn then_tick(mut self, t: Tick) -> Self {
+        self.script.push_back(Tick {
+            ts_ms: self.next_ts_ms,
+            ..t
+        });
+        self.next_ts_ms += 1;
+        self
+    }
+
+    pub fn remaining(&self) -> usize {
+        self.script.len()
+    }
+
+    /// Feeds the next tick into `cache`; `None` once the script is done.
+    pub async fn step(&mut self, cache: &OracleCache) -> Option<TickOutcome> {
+        let t = self.script.pop_front()?;
+        Some(cache.set(t).await)
+    }
+
+    /// Feeds every remaining tick into `cache`.
+    pub async fn play(mut self, cache: &OracleCache) {
+        while self.step(cache).await.is_some() {}
+    }
+}
+
+/// A [`Clock`] that stands still until moved.
+#[derive(Debug, Default)]
+pub struct FrozenClock(AtomicI64);
+
+impl FrozenClock {
+    pub fn at(ts_ms: i64) -> Self {
+        Self(AtomicI64::new(ts_ms))
+    }
+
+    pub fn advance(&self, ms: i64) {
+        self.0.fetch_add(ms, Ordering::SeqCst);
+    }
+
+    pub fn set(&self, ts_ms: i64) {
+        self.0.store(ts_ms, Ordering::SeqCst);
+    }
+}
+
+impl Clock for FrozenClock {
+    fn now_ms(&self) -> i64 {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+/// An [`IdGenerator`] counting up from a seed, as `{prefix}-000001` and so
+/// on, so IDs are the same on every run that makes them in the same order.
+#[derive(Debug)]
+pub struct CountingIds {
+    prefix: String,
+    next: AtomicU64,
+}
+
+impl CountingIds {
+    pub fn seeded(prefix: &str, first: u64) -> Self {
+        Self {
+            prefix: prefix.to_string(),
+            next: AtomicU64::new(first),
+        }
+    }
+}
+
+impl IdGenerator for CountingIds {
+    fn next_id(&self) -> String {
+        format!(
+            "{}-{:06}",
+            self.prefix,
+            self.next.fetch_add(1, Ordering::SeqCst)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn builder_fills_in_only_what_is_asked() {
+        let o = OrderBuilder::sell("BTC/USDT")
+            .id("s1")
+            .limit(dec!(100.5))
+            .qty(2)
+            .status(OrderStatus::Open)
+            .build();
+        assert_eq!((o.id.as_str(), o.side), ("s1", OrderSide::Sell));
+        assert_eq!((o.price, o.quantity), (dec!(100.5), dec!(2)));
+        assert_eq!(o.status, OrderStatus::Open);
+
+        let n = OrderBuilder::buy("ETH/USDT").market().qty(3).new_order();
+        assert_eq!(n.order_type, OrderType::Market);
+        assert_eq!((n.price, n.quantity), (Decimal::ZERO, dec!(3)));
+    }
+
+    #[tokio::test]
+    async fn script_plays_in_order_without_duplicates() {
+        let cache = OracleCache::default();
+        let mut feed = ScriptedOracle::starting_at(1_000)
+            .then("BTC/USDT", 100)
+            .then("BTC/USDT", 101)
+            .then("ETH/USDT", 5);
+        assert_eq!(feed.step(&cache).await, Some(TickOutcome::Accepted));
+        assert_eq!(feed.remaining(), 2);
+        feed.play(&cache).await;
+        assert_eq!(cache.get_price("BTC/USDT").await, Some((dec!(101), 1_001)));
+        assert_eq!(cache.get_price("ETH/USDT").await, Some((dec!(5), 1_002)));
+    }
+}
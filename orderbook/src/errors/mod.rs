@@ -8,6 +8,31 @@ pub enum ApiError {
     NotFound,
     #[display("bad request: {}", _0)]
     BadRequest(String),
+    #[display("unauthorized")]
+    Unauthorized,
+    #[display("conflict: {}", _0)]
+    Conflict(String),
+    /// The order's status does not allow the change, e.g. cancelling a
+    /// filled order.
+    #[display("invalid transition: {}", _0)]
+    InvalidTransition(String),
+    #[display("payload too large: limit is {} bytes", _0)]
+    PayloadTooLarge(usize),
+    #[display("service unavailable: {}", _0)]
+    Unavailable(String),
+    #[display("stale oracle: {}", _0)]
+    StaleOracle(String),
+    #[display("deadline of {} ms exceeded", _0)]
+    Timeout(u64),
+    #[display("cancel-only: no oracle tick for any pair within {} ms", _0)]
+    CancelOnly(i64),
+    #[display("account limit: {}", _0)]
+    LimitExceeded(String),
+    #[display("rate limited: retry in {} s", _0)]
+    RateLimited(u64),
+    /// A cancel refused by an anti-churn policy, with its code.
+    #[display("cancel refused: {}", _1)]
+    CancelRefused(&'static str, String),
     #[display("internal")]
     Internal,
 }
@@ -15,6 +40,23 @@ pub enum ApiError {
 #[derive(Serialize)]
 struct ErrBody {
     error: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    code: Option<&'static str>,
+}
+
+impl ApiError {
+    /// A stable machine-readable code, for errors clients act on.
+    pub fn code(&self) -> Option<&'static str> {
+        match self {
+            Self::InvalidTransition(_) => Some("invalid_transition"),
+            Self::StaleOracle(_) => Some("stale_oracle"),
+            Self::CancelOnly(_) => Some("cancel_only"),
+            Self::LimitExceeded(_) => Some("account_limit"),
+            Self::RateLimited(_) => Some("rate_limited"),
+            Self::CancelRefused(code, _) => Some(code),
+            _ => None,
+        }
+    }
 }
 
 impl ResponseError for ApiError {
@@ -22,13 +64,28 @@ impl ResponseError for ApiError {
         match self {
             Self::NotFound => StatusCode::NOT_FOUND,
             Self::BadRequest(_) => StatusCode::BAD_REQUEST,
+            Self::Unauthorized => StatusCode::UNAUTHORIZED,
+            Self::Conflict(_) | Self::InvalidTransition(_) => StatusCode::CONFLICT,
+            Self::PayloadTooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
+            Self::Unavailable(_) | Self::StaleOracle(_) | Self::CancelOnly(_) => {
+                StatusCode::SERVICE_UNAVAILABLE
+            }
+            Self::Timeout(_) => StatusCode::GATEWAY_TIMEOUT,
+            Self::LimitExceeded(_) | Self::RateLimited(_) | Self::CancelRefused(..) => {
+                StatusCode::TOO_MANY_REQUESTS
+            }
             Self::Internal => StatusCode::INTERNAL_SERVER_ERROR,
         }
     }
 
     fn error_response(&self) -> HttpResponse {
-        HttpResponse::build(self.status_code()).json(ErrBody {
+        let mut b = HttpResponse::build(self.status_code());
+        if let Self::RateLimited(secs) = self {
+            b.insert_header(("Retry-After", secs.to_string()));
+        }
+        b.json(ErrBody {
             error: self.to_string(),
+            code: self.code(),
         })
     }
 }
@@ -0,0 +1,201 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, RwLock};
+
+use serde::{Deserialize, Serialize};
+
+/// A behavior that can be turned on for some pairs or accounts before
+/// everyone, and off again without a redeploy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Feature {
+    /// Fill GTC orders only as far as the simulated depth allows, as the
+    /// `partial_fill` policy does.
+    PartialFill,
+    /// Execute triggered GTC orders at the quoted bid or ask, as the
+    /// `bid_ask` policy does.
+    BidAsk,
+    /// Cross resting limit orders against each other before they are
+    /// evaluated against the oracle.
+    Crossing,
+}
+
+impl Feature {
+    pub const ALL: [Feature; 3] = [Feature::PartialFill, Feature::BidAsk, Feature::Crossing];
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "partial_fill" => Some(Self::PartialFill),
+            "bid_ask" => Some(Self::BidAsk),
+            "crossing" => Some(Self::Crossing),
+            _ => None,
+        }
+    }
+}
+
+/// Where a flag is set: everywhere, or an override for one pair or
+/// account.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Scope {
+    All,
+    Pair(String),
+    Account(String),
+}
+
+/// One feature's setting. An account override beats a pair override,
+/// which beats `enabled`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct FlagRule {
+    pub enabled: bool,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub pairs: BTreeMap<String, bool>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub accounts: BTreeMap<String, bool>,
+}
+
+impl FlagRule {
+    fn is_enabled(&self, pair: &str, account: Option<&str>) -> bool {
+        account
+            .and_then(|a| self.accounts.get(a))
+            .or_else(|| self.pairs.get(pair))
+            .copied()
+            .unwrap_or(self.enabled)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FlagState {
+    pub feature: Feature,
+    #[serde(flatten)]
+    pub rule: FlagRule,
+}
+
+/// Runtime feature flags, read from `FEATURE_FLAGS` at boot and changed
+/// through the admin API. Every feature is off until set.
+#[derive(Debug, Clone, Default)]
+pub struct FeatureFlags {
+    inner: Arc<RwLock<HashMap<Feature, FlagRule>>>,
+}
+
+impl FeatureFlags {
+    /// Parses `feature=target,...;...`. A target is `*` for everywhere, a
+    /// pair, or `account:<id>`; a leading `!` turns the feature off there
+    /// instead. `partial_fill=BTC/USDT,account:desk-1;bid_ask=*`
+    /// enables partial fills for one pair and one account, and bid/ask
+    /// execution everywhere. Pairs are taken as written.
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let flags = Self::default();
+        for entry in spec.split(';').map(str::trim).filter(|e| !e.is_empty()) {
+            let (name, targets) = entry
+                .split_once('=')
+                .ok_or_else(|| format!("flag {entry:?} must be feature=targets"))?;
+            let feature =
+                Feature::parse(name).ok_or_else(|| format!("unknown feature {:?}", name.trim()))?;
+            for target in targets.split(',').map(str::trim).filter(|t| !t.is_empty()) {
+                let (on, target) = match target.strip_prefix('!') {
+                    Some(rest) => (false, rest),
+                    None => (true, target),
+                };
+                let scope = match target {
+                    "*" => Scope::All,
+                    t => match t.strip_prefix("account:") {
+                        Some(id) => Scope::Account(id.to_string()),
+                        None => Scope::Pair(t.to_string()),
+                    },
+                };
+                flags.set(feature, scope, on);
+            }
+        }
+        Ok(flags)
+    }
+
+    /// Flags from `FEATURE_FLAGS`; none if it is unset.
+    pub fn from_env() -> Result<Self, String> {
+        std::env::var("FEATURE_FLAGS").map_or_else(|_| Ok(Self::default()), |s| Self::parse(&s))
+    }
+
+    pub fn is_enabled(&self, feature: Feature, pair: &str, account: Option<&str>) -> bool {
+        let r = self.inner.read().unwrap();
+        r.get(&feature)
+            .is_some_and(|rule| rule.is_enabled(pair, account))
+    }
+
+    pub fn set(&self, feature: Feature, scope: Scope, on: bool) {
+        let mut w = self.inner.write().unwrap();
+        let rule = w.entry(feature).or_default();
+        match scope {
+            Scope::All => rule.enabled = on,
+            Scope::Pair(p) => {
+                rule.pairs.insert(p, on);
+            }
+            Scope::Account(a) => {
+                rule.accounts.insert(a, on);
+            }
+        }
+    }
+
+    /// Drops a pair or account override, or with [`Scope::All`] every
+    /// setting of the feature. Returns whether there was one.
+    pub fn clear(&self, feature: Feature, scope: Scope) -> bool {
+        let mut w = self.inner.write().unwrap();
+        match (scope, w.get_mut(&feature)) {
+            (_, None) => false,
+            (Scope::All, Some(_)) => w.remove(&feature).is_some(),
+            (Scope::Pair(p), Some(rule)) => rule.pairs.remove(&p).is_some(),
+            (Scope::Account(a), Some(rule)) => rule.accounts.remove(&a).is_some(),
+        }
+    }
+
+    /// Every feature's setting, unset ones as off.
+    pub fn list(&self) -> Vec<FlagState> {
+        let r = self.inner.read().unwrap();
+        Feature::ALL
+            .iter()
+            .map(|f| FlagState {
+                feature: *f,
+                rule: r.get(f).cloned().unwrap_or_default(),
+            })
+            .collect()
+    }
+
+    pub fn get(&self, feature: Feature) -> FlagState {
+        FlagState {
+            feature,
+            rule: self
+                .inner
+                .read()
+                .unwrap()
+                .get(&feature)
+                .cloned()
+                .unwrap_or_default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overrides_narrow_from_account_to_pair_to_everywhere() {
+        let flags =
+            FeatureFlags::parse("partial_fill=BTC/USDT,account:desk; bid_ask=*,!ETH/USDT").unwrap();
+        let on = |f, pair, account| flags.is_enabled(f, pair, account);
+        assert!(on(Feature::PartialFill, "BTC/USDT", None));
+        assert!(on(Feature::PartialFill, "ETH/USDT", Some("desk")));
+        assert!(!on(Feature::PartialFill, "ETH/USDT", Some("other")));
+        assert!(on(Feature::BidAsk, "SOL/USDT", None));
+        assert!(!on(Feature::BidAsk, "ETH/USDT", None));
+
+        // An account turned off keeps it off on a pair that has it on.
+        flags.set(Feature::PartialFill, Scope::Account("desk".into()), false);
+        assert!(!on(Feature::PartialFill, "BTC/USDT", Some("desk")));
+        assert!(flags.clear(Feature::PartialFill, Scope::Account("desk".into())));
+        assert!(on(Feature::PartialFill, "BTC/USDT", Some("desk")));
+        assert!(flags.clear(Feature::BidAsk, Scope::All));
+        assert!(!on(Feature::BidAsk, "SOL/USDT", None));
+        assert_eq!(flags.list().len(), Feature::ALL.len());
+
+        assert!(FeatureFlags::parse("nope=*").is_err());
+        assert!(FeatureFlags::parse("partial_fill").is_err());
+    }
+}
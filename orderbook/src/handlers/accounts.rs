@@ -0,0 +1,257 @@
+use actix_web::{web, HttpRequest, HttpResponse};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+use crate::accounts::purge::{AccountPurger, PurgeMode};
+use crate::accounts::statements::Period;
+use crate::accounts::{Account, AccountStore, ApiKey, IssuedKey};
+use crate::entities::order::Order;
+use crate::errors::ApiError;
+use crate::utils::now_ms;
+
+#[derive(Debug, Deserialize)]
+pub struct CreateAccountPayload {
+    pub name: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PurgeQuery {
+    #[serde(default)]
+    pub mode: PurgeMode,
+}
+
+/// `period` is a UTC month such as `2024-06`, the current one if absent;
+/// `format` is `json` (default) or `csv`.
+#[derive(Debug, Deserialize)]
+pub struct StatementQuery {
+    pub period: Option<String>,
+    pub format: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct WebhookPayload {
+    pub url: String,
+}
+
+#[derive(Debug, Serialize)]
+struct AccountResponse {
+    account: Account,
+    keys: Vec<ApiKey>,
+}
+
+#[derive(Debug, Serialize)]
+struct CreatedAccount {
+    account: Account,
+    #[serde(flatten)]
+    issued: IssuedKey,
+}
+
+/// Resolves the caller from `Authorization: Bearer <key>` or `X-Api-Key`.
+pub(crate) fn authenticate(
+    req: &HttpRequest,
+    accounts: &AccountStore,
+) -> Result<Account, ApiError> {
+    maybe_authenticate(req, accounts)?.ok_or(ApiError::Unauthorized)
+}
+
+/// Like [`authenticate`], but a request without credentials is anonymous
+/// rather than rejected. Credentials that do not resolve are still a 401.
+pub(crate) fn maybe_authenticate(
+    req: &HttpRequest,
+    accounts: &AccountStore,
+) -> Result<Option<Account>, ApiError> {
+    let headers = req.headers();
+    let Some(token) = headers
+        .get("Authorization")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .or_else(|| headers.get("X-Api-Key").and_then(|v| v.to_str().ok()))
+    else {
+        return Ok(None);
+    };
+    accounts
+        .authenticate(token.trim())
+        .map(Some)
+        .ok_or(ApiError::Unauthorized)
+}
+
+/// Refuses `orders` new orders adding `notional` that would take the
+/// account past its risk limits.
+pub(crate) fn within_limits(
+    accounts: &AccountStore,
+    account_id: &str,
+    orders: usize,
+    notional: Decimal,
+) -> Result<(), ApiError> {
+    accounts
+        .usage()
+        .admit(account_id, orders, notional, now_ms())
+        .map_err(ApiError::LimitExceeded)
+}
+
+/// Refuses a cancel of `order` that its account's anti-churn policies
+/// forbid.
+pub(crate) fn may_cancel(accounts: &AccountStore, order: &Order) -> Result<(), ApiError> {
+    accounts
+        .usage()
+        .admit_cancel(order, now_ms())
+        .map_err(|(code, why)| ApiError::CancelRefused(code, why))
+}
+
+pub async fn whoami(
+    req: HttpRequest,
+    accounts: web::Data<AccountStore>,
+) -> Result<HttpResponse, ApiError> {
+    Ok(HttpResponse::Ok().json(authenticate(&req, &accounts)?))
+}
+
+/// `GET /accounts/{id}/usage`: the caller's own rate, exposure and
+/// headroom; other accounts do not exist to it.
+pub async fn usage(
+    req: HttpRequest,
+    accounts: web::Data<AccountStore>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let account = authenticate(&req, &accounts)?;
+    if account.id != *path {
+        return Err(ApiError::NotFound);
+    }
+    Ok(HttpResponse::Ok().json(accounts.usage().report(&account.id, now_ms())))
+}
+
+/// `GET /accounts/{id}/statements`: the caller's own statement for a
+/// month, or with `format=csv` its activity rows.
+pub async fn statements(
+    req: HttpRequest,
+    accounts: web::Data<AccountStore>,
+    path: web::Path<String>,
+    q: web::Query<StatementQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let account = authenticate(&req, &accounts)?;
+    if account.id != *path {
+        return Err(ApiError::NotFound);
+    }
+    let period = match q.period.as_deref() {
+        None => Period::of(now_ms()),
+        Some(p) => Period::parse(p).ok_or_else(|| {
+            ApiError::BadRequest(format!("period {p:?} must be a month like 2024-06"))
+        })?,
+    };
+    let ledger = accounts.statements();
+    match q.format.as_deref().unwrap_or("json") {
+        "json" => Ok(HttpResponse::Ok().json(ledger.statement(&account.id, period))),
+        "csv" => Ok(HttpResponse::Ok()
+            .content_type("text/csv; charset=utf-8")
+            .insert_header((
+                "Content-Disposition",
+                format!("attachment; filename=\"statement-{period}.csv\""),
+            ))
+            .body(ledger.activity_csv(&account.id, period))),
+        other => Err(ApiError::BadRequest(format!(
+            "format {other:?} must be json or csv"
+        ))),
+    }
+}
+
+pub async fn create_account(
+    accounts: web::Data<AccountStore>,
+    payload: web::Json<CreateAccountPayload>,
+) -> Result<HttpResponse, ApiError> {
+    let name = payload.name.trim();
+    if name.is_empty() {
+        return Err(ApiError::BadRequest("name is required".into()));
+    }
+    let (account, issued) = accounts.create(name);
+    Ok(HttpResponse::Created().json(CreatedAccount { account, issued }))
+}
+
+pub async fn get_account(
+    accounts: web::Data<AccountStore>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let (account, keys) = accounts.get(&path).ok_or(ApiError::NotFound)?;
+    Ok(HttpResponse::Ok().json(AccountResponse { account, keys }))
+}
+
+pub async fn issue_key(
+    accounts: web::Data<AccountStore>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let issued = accounts.issue_key(&path).ok_or(ApiError::NotFound)?;
+    Ok(HttpResponse::Created().json(issued))
+}
+
+pub async fn rotate_key(
+    accounts: web::Data<AccountStore>,
+    path: web::Path<(String, String)>,
+) -> Result<HttpResponse, ApiError> {
+    let (account_id, key_id) = path.into_inner();
+    let issued = accounts
+        .rotate_key(&account_id, &key_id)
+        .ok_or(ApiError::NotFound)?;
+    Ok(HttpResponse::Ok().json(issued))
+}
+
+pub async fn revoke_key(
+    accounts: web::Data<AccountStore>,
+    path: web::Path<(String, String)>,
+) -> Result<HttpResponse, ApiError> {
+    let (account_id, key_id) = path.into_inner();
+    if !accounts.revoke_key(&account_id, &key_id) {
+        return Err(ApiError::NotFound);
+    }
+    Ok(HttpResponse::NoContent().finish())
+}
+
+pub async fn set_webhook(
+    accounts: web::Data<AccountStore>,
+    path: web::Path<String>,
+    payload: web::Json<WebhookPayload>,
+) -> Result<HttpResponse, ApiError> {
+    url::Url::parse(&payload.url).map_err(|e| ApiError::BadRequest(format!("url: {e}")))?;
+    let info = accounts
+        .set_webhook(&path, &payload.url)
+        .ok_or(ApiError::NotFound)?;
+    Ok(HttpResponse::Ok().json(info))
+}
+
+pub async fn rotate_webhook_secret(
+    accounts: web::Data<AccountStore>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let info = accounts
+        .rotate_webhook_secret(&path)
+        .ok_or(ApiError::NotFound)?;
+    Ok(HttpResponse::Ok().json(info))
+}
+
+/// Reseals stored secrets under the active master key.
+pub async fn reseal_secrets(accounts: web::Data<AccountStore>) -> Result<HttpResponse, ApiError> {
+    let report = accounts.reseal().map_err(|_| ApiError::Internal)?;
+    Ok(HttpResponse::Ok().json(report))
+}
+
+/// Starts purging or anonymizing the account's data; **202** with the job
+/// to poll.
+pub async fn purge_account_data(
+    accounts: web::Data<AccountStore>,
+    purger: web::Data<AccountPurger>,
+    path: web::Path<String>,
+    q: web::Query<PurgeQuery>,
+) -> Result<HttpResponse, ApiError> {
+    accounts.get(&path).ok_or(ApiError::NotFound)?;
+    let job = purger.start(&path, q.mode).map_err(ApiError::Conflict)?;
+    Ok(HttpResponse::Accepted().json(job))
+}
+
+pub async fn purge_job(
+    purger: web::Data<AccountPurger>,
+    path: web::Path<(String, String)>,
+) -> Result<HttpResponse, ApiError> {
+    let (account_id, job_id) = path.into_inner();
+    let job = purger
+        .job(&job_id)
+        .filter(|j| j.account_id == account_id)
+        .ok_or(ApiError::NotFound)?;
+    Ok(HttpResponse::Ok().json(job))
+}
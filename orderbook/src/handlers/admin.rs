@@ -0,0 +1,694 @@
+use actix_web::{web, HttpResponse};
+use serde::{Deserialize, Serialize};
+
+use crate::engine::deadletter::DeadLetters;
+use crate::engine::delist::PairDelister;
+use crate::engine::downtime::{FlaggedTrigger, MissedTriggerPolicy, MissedTriggers};
+use crate::engine::halts::{HaltRegistry, HaltSource, ResumePolicy};
+use crate::engine::housekeeping::{Housekeeper, RunTrigger};
+use crate::engine::ledger::ExecutionLedger;
+use crate::engine::orphans::{
+    cancel_orphaned, find_orphaned, record_orphans, OrphanCancelReport, OrphanReport,
+};
+use crate::engine::reconcile::{reconcile, ExternalState};
+use crate::engine::reevaluate::{reevaluate, Reevaluation, WARMUP_TICKS};
+use crate::engine::rename::PairRenamer;
+use crate::engine::{collect_active_orders, EngineConfig};
+use crate::entities::events::MarketEvent;
+use crate::entities::order::{OrderStatus, CANCEL_BY_ADMIN};
+use crate::errors::ApiError;
+use crate::flags::{Feature, FeatureFlags, Scope};
+use crate::instruments::InstrumentRegistry;
+use crate::limits::Limits;
+use crate::metrics::placement::PlacementTimings;
+use crate::metrics::Metrics;
+use crate::oracle_service::{OracleCache, Tick};
+use crate::repositories::audit::{AuditAction, AuditEntry, AuditLog};
+use crate::repositories::changes::ChangeStream;
+use crate::repositories::journal::Journal;
+use crate::state::AppState;
+use crate::utils::now_ms;
+
+const DEFAULT_ORPHAN_AGE_HOURS: i64 = 24;
+
+#[derive(Debug, Deserialize)]
+pub struct OrphanQuery {
+    pub min_age_hours: Option<i64>,
+}
+
+impl OrphanQuery {
+    fn min_age_ms(&self) -> Result<i64, ApiError> {
+        let hours = self.min_age_hours.unwrap_or(DEFAULT_ORPHAN_AGE_HOURS);
+        if hours < 0 {
+            return Err(ApiError::BadRequest("min_age_hours must be >= 0".into()));
+        }
+        hours
+            .checked_mul(3_600_000)
+            .ok_or_else(|| ApiError::BadRequest("min_age_hours is too large".into()))
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct OrphanCancelResponse {
+    report: OrphanReport,
+    result: OrphanCancelReport,
+}
+
+pub async fn orphaned_orders(
+    state: web::Data<AppState>,
+    oracle: web::Data<OracleCache>,
+    metrics: web::Data<Metrics>,
+    q: web::Query<OrphanQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let report = find_orphaned(&*state.orders, &oracle, q.min_age_ms()?, now_ms())
+        .await
+        .map_err(|_| ApiError::Internal)?;
+    record_orphans(&metrics, &report);
+    Ok(HttpResponse::Ok().json(report))
+}
+
+pub async fn cancel_orphaned_orders(
+    state: web::Data<AppState>,
+    oracle: web::Data<OracleCache>,
+    metrics: web::Data<Metrics>,
+    q: web::Query<OrphanQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let report = find_orphaned(&*state.orders, &oracle, q.min_age_ms()?, now_ms())
+        .await
+        .map_err(|_| ApiError::Internal)?;
+    let result = cancel_orphaned(&*state.orders, &metrics, &report).await;
+    Ok(HttpResponse::Ok().json(OrphanCancelResponse { report, result }))
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct HaltPayload {
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RenamePayload {
+    pub to: String,
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// Where a flag change applies: one pair, one account, or with neither
+/// everywhere.
+#[derive(Debug, Default, Deserialize)]
+pub struct FlagTarget {
+    pub pair: Option<String>,
+    pub account: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FlagPayload {
+    pub enabled: bool,
+    #[serde(flatten)]
+    pub target: FlagTarget,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ResumeQuery {
+    pub policy: Option<ResumePolicy>,
+}
+
+#[derive(Debug, Serialize)]
+struct StatsResponse {
+    halts: crate::engine::halts::HaltStats,
+}
+
+fn known_pair(instruments: &InstrumentRegistry, raw: &str) -> Result<String, ApiError> {
+    instruments.normalize(raw).map_err(|_| ApiError::NotFound)
+}
+
+pub async fn halt_pair(
+    instruments: web::Data<InstrumentRegistry>,
+    halts: web::Data<HaltRegistry>,
+    changes: web::Data<ChangeStream>,
+    path: web::Path<String>,
+    payload: Option<web::Json<HaltPayload>>,
+) -> Result<HttpResponse, ApiError> {
+    let pair = known_pair(&instruments, &path.into_inner())?;
+    let reason = payload
+        .and_then(|p| p.into_inner().reason)
+        .unwrap_or_else(|| "halted by admin".into());
+    if !halts.halt(&pair, HaltSource::Admin, &reason, now_ms()) {
+        return Err(ApiError::Conflict(format!("{pair} is already halted")));
+    }
+    tracing::warn!(%pair, %reason, "pair halted");
+    changes.publish_market(MarketEvent::PairHalted {
+        pair: pair.clone(),
+        source: HaltSource::Admin,
+        reason,
+    });
+    Ok(HttpResponse::Ok().json(halts.stats().halted.into_iter().find(|h| h.pair == pair)))
+}
+
+pub async fn resume_pair(
+    instruments: web::Data<InstrumentRegistry>,
+    halts: web::Data<HaltRegistry>,
+    changes: web::Data<ChangeStream>,
+    path: web::Path<String>,
+    q: web::Query<ResumeQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let pair = known_pair(&instruments, &path.into_inner())?;
+    let policy = q.policy.unwrap_or_else(|| halts.default_policy());
+    let report = halts
+        .resume(&pair, policy, now_ms())
+        .ok_or_else(|| ApiError::Conflict(format!("{pair} is not halted")))?;
+    tracing::info!(%pair, ?policy, parked = report.would_trigger.len(), "pair resumed");
+    changes.publish_market(MarketEvent::PairResumed {
+        pair,
+        policy,
+        parked: report.would_trigger.len(),
+    });
+    Ok(HttpResponse::Ok().json(report))
+}
+
+/// Renames a pair across the service, or with `dry_run` reports what
+/// would change. A target another pair already answers to is a conflict.
+pub async fn rename_pair(
+    instruments: web::Data<InstrumentRegistry>,
+    renamer: web::Data<PairRenamer>,
+    path: web::Path<String>,
+    payload: web::Json<RenamePayload>,
+) -> Result<HttpResponse, ApiError> {
+    let pair = known_pair(&instruments, &path.into_inner())?;
+    let RenamePayload { to, dry_run } = payload.into_inner();
+    if let Ok(taken) = instruments.normalize(&to) {
+        if taken != pair {
+            return Err(ApiError::Conflict(format!("{to} already names {taken}")));
+        }
+    }
+    let report = renamer
+        .rename(&pair, &to, dry_run)
+        .await
+        .map_err(ApiError::BadRequest)?;
+    Ok(HttpResponse::Ok().json(report))
+}
+
+/// Starts delisting a pair and returns the job to poll. A pair left
+/// archived by a failed delisting can be started again under its
+/// canonical name.
+pub async fn delist_pair(
+    instruments: web::Data<InstrumentRegistry>,
+    delister: web::Data<PairDelister>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let raw = path.into_inner();
+    if instruments.normalize(&raw).is_err() && instruments.archived(&raw).is_none() {
+        return Err(ApiError::NotFound);
+    }
+    let job = delister.start(&raw).map_err(ApiError::Conflict)?;
+    Ok(HttpResponse::Accepted().json(job))
+}
+
+pub async fn delist_job(
+    delister: web::Data<PairDelister>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let job = delister.job(&path).ok_or(ApiError::NotFound)?;
+    Ok(HttpResponse::Ok().json(job))
+}
+
+pub async fn delisted_pairs(
+    instruments: web::Data<InstrumentRegistry>,
+) -> Result<HttpResponse, ApiError> {
+    Ok(HttpResponse::Ok().json(instruments.list_archived()))
+}
+
+fn flag_scope(instruments: &InstrumentRegistry, target: FlagTarget) -> Result<Scope, ApiError> {
+    match (target.pair, target.account) {
+        (Some(_), Some(_)) => Err(ApiError::BadRequest(
+            "set either pair or account, not both".into(),
+        )),
+        (Some(pair), None) => Ok(Scope::Pair(known_pair(instruments, &pair)?)),
+        (None, Some(account)) => Ok(Scope::Account(account)),
+        (None, None) => Ok(Scope::All),
+    }
+}
+
+fn known_feature(raw: &str) -> Result<Feature, ApiError> {
+    Feature::parse(raw).ok_or(ApiError::NotFound)
+}
+
+/// Every feature flag with its pair and account overrides.
+pub async fn feature_flags(flags: web::Data<FeatureFlags>) -> Result<HttpResponse, ApiError> {
+    Ok(HttpResponse::Ok().json(flags.list()))
+}
+
+/// Turns a feature on or off everywhere, or for one pair or account. The
+/// matchers pick it up on their next tick.
+pub async fn set_feature_flag(
+    instruments: web::Data<InstrumentRegistry>,
+    flags: web::Data<FeatureFlags>,
+    path: web::Path<String>,
+    payload: web::Json<FlagPayload>,
+) -> Result<HttpResponse, ApiError> {
+    let feature = known_feature(&path.into_inner())?;
+    let FlagPayload { enabled, target } = payload.into_inner();
+    let scope = flag_scope(&instruments, target)?;
+    tracing::warn!(?feature, ?scope, enabled, "feature flag set");
+    flags.set(feature, scope, enabled);
+    Ok(HttpResponse::Ok().json(flags.get(feature)))
+}
+
+/// Drops a pair or account override, or with neither resets the feature
+/// to off everywhere.
+pub async fn clear_feature_flag(
+    instruments: web::Data<InstrumentRegistry>,
+    flags: web::Data<FeatureFlags>,
+    path: web::Path<String>,
+    q: web::Query<FlagTarget>,
+) -> Result<HttpResponse, ApiError> {
+    let feature = known_feature(&path.into_inner())?;
+    let scope = flag_scope(&instruments, q.into_inner())?;
+    if !flags.clear(feature, scope.clone()) {
+        return Err(ApiError::NotFound);
+    }
+    tracing::warn!(?feature, ?scope, "feature flag cleared");
+    Ok(HttpResponse::Ok().json(flags.get(feature)))
+}
+
+/// Orders the engine suspended after repeated write failures.
+pub async fn dead_letters(dead_letters: web::Data<DeadLetters>) -> Result<HttpResponse, ApiError> {
+    Ok(HttpResponse::Ok().json(dead_letters.list()))
+}
+
+#[derive(Debug, Serialize)]
+struct MissedTriggersResponse {
+    flagged: Vec<FlaggedTrigger>,
+    pending: usize,
+}
+
+/// Orders held for review after downtime, and how many more are still
+/// under a missed-trigger rule.
+pub async fn missed_triggers(missed: web::Data<MissedTriggers>) -> Result<HttpResponse, ApiError> {
+    Ok(HttpResponse::Ok().json(MissedTriggersResponse {
+        flagged: missed.flagged(),
+        pending: missed.pending(),
+    }))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReleasePayload {
+    pub policy: MissedTriggerPolicy,
+}
+
+/// Releases an order flagged for review after downtime: fill it on the
+/// next tick, or wait for a fresh crossing. Cancelling goes through
+/// `/admin/orders/{id}/cancel`.
+pub async fn release_missed_trigger(
+    missed: web::Data<MissedTriggers>,
+    path: web::Path<String>,
+    payload: web::Json<ReleasePayload>,
+) -> Result<HttpResponse, ApiError> {
+    let id = path.into_inner();
+    let policy = payload.into_inner().policy;
+    if policy == MissedTriggerPolicy::ManualReview {
+        return Err(ApiError::BadRequest(
+            "release under trigger_immediately or require_fresh_crossing".into(),
+        ));
+    }
+    if !missed.release(&id, policy) {
+        return Err(ApiError::NotFound);
+    }
+    tracing::info!(order_id = %id, ?policy, "missed trigger released");
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Execution intents whose fills may or may not have been written.
+pub async fn unresolved_executions(
+    ledger: web::Data<ExecutionLedger>,
+) -> Result<HttpResponse, ApiError> {
+    Ok(HttpResponse::Ok().json(ledger.unresolved()))
+}
+
+#[derive(Debug, Serialize)]
+struct ResolveResponse {
+    order_id: String,
+    trigger_seq: u64,
+    resolution: &'static str,
+}
+
+/// Settles an unresolved intent from the order itself: committed if the
+/// order has filled on that trigger, otherwise aborted so the trigger can
+/// fill again.
+pub async fn resolve_execution(
+    state: web::Data<AppState>,
+    ledger: web::Data<ExecutionLedger>,
+    path: web::Path<(String, u64)>,
+) -> Result<HttpResponse, ApiError> {
+    let (order_id, trigger_seq) = path.into_inner();
+    let order = state
+        .orders
+        .get_by_id(&order_id)
+        .await
+        .map_err(|_| ApiError::NotFound)?;
+    let resolution = if order.trigger_seq() > trigger_seq {
+        ledger
+            .commit(&order_id, trigger_seq, now_ms())
+            .then_some("committed")
+    } else {
+        ledger.abort(&order_id, trigger_seq).then_some("aborted")
+    }
+    .ok_or(ApiError::NotFound)?;
+    tracing::info!(%order_id, trigger_seq, resolution, "execution intent resolved");
+    Ok(HttpResponse::Ok().json(ResolveResponse {
+        order_id,
+        trigger_seq,
+        resolution,
+    }))
+}
+
+/// Per-stage latency of recent placements against the placement SLO.
+pub async fn placement_slo(timings: web::Data<PlacementTimings>) -> Result<HttpResponse, ApiError> {
+    Ok(HttpResponse::Ok().json(timings.summary()))
+}
+
+/// Each housekeeping policy with its next scheduled run and its latest.
+pub async fn housekeeping_policies(
+    keeper: web::Data<Housekeeper>,
+) -> Result<HttpResponse, ApiError> {
+    Ok(HttpResponse::Ok().json(keeper.status(now_ms())))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct HousekeepingRunsQuery {
+    pub policy: Option<String>,
+}
+
+pub async fn housekeeping_runs(
+    keeper: web::Data<Housekeeper>,
+    q: web::Query<HousekeepingRunsQuery>,
+) -> Result<HttpResponse, ApiError> {
+    Ok(HttpResponse::Ok().json(keeper.runs(q.policy.as_deref())))
+}
+
+/// Runs a policy now, outside its schedule, and returns the finished run.
+pub async fn run_housekeeping(
+    keeper: web::Data<Housekeeper>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let run = keeper
+        .run(&path, RunTrigger::Manual, now_ms())
+        .await
+        .ok_or(ApiError::NotFound)?
+        .map_err(ApiError::Conflict)?;
+    Ok(HttpResponse::Ok().json(run))
+}
+
+pub async fn stats(halts: web::Data<HaltRegistry>) -> Result<HttpResponse, ApiError> {
+    Ok(HttpResponse::Ok().json(StatsResponse {
+        halts: halts.stats(),
+    }))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReconcilePayload {
+    pub orders: Vec<ExternalState>,
+}
+
+pub async fn reconcile_orders(
+    state: web::Data<AppState>,
+    limits: web::Data<Limits>,
+    payload: web::Json<ReconcilePayload>,
+) -> Result<HttpResponse, ApiError> {
+    limits.check_batch(payload.orders.len())?;
+    Ok(HttpResponse::Ok().json(reconcile(&*state.orders, &payload.orders).await))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReevaluatePayload {
+    pub pair: String,
+    pub from_ms: i64,
+    pub to_ms: i64,
+    /// Captured ticks to replay instead of the cache's history; those
+    /// before `from_ms` only warm up indicators and the index.
+    #[serde(default)]
+    pub ticks: Option<Vec<Tick>>,
+}
+
+/// `history_starts_ms` is set when the kept history begins after
+/// `from_ms`, so the start of the range could not be replayed.
+#[derive(Debug, Serialize)]
+struct ReevaluateResponse {
+    from_ms: i64,
+    to_ms: i64,
+    source: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    history_starts_ms: Option<i64>,
+    #[serde(flatten)]
+    result: Reevaluation,
+}
+
+/// Dry-runs the pair's active orders over the ticks in `from_ms..=to_ms`
+/// and reports which would have triggered, without touching them.
+pub async fn reevaluate_orders(
+    state: web::Data<AppState>,
+    oracle: web::Data<OracleCache>,
+    instruments: web::Data<InstrumentRegistry>,
+    engine: web::Data<EngineConfig>,
+    flags: web::Data<FeatureFlags>,
+    payload: web::Json<ReevaluatePayload>,
+) -> Result<HttpResponse, ApiError> {
+    let p = payload.into_inner();
+    let pair = instruments
+        .normalize(&p.pair)
+        .map_err(|e| ApiError::BadRequest(e.to_string()))?;
+    if p.from_ms > p.to_ms {
+        return Err(ApiError::BadRequest("from_ms must be <= to_ms".into()));
+    }
+    let (source, warmup, ticks, history_starts_ms) = match p.ticks {
+        Some(supplied) => {
+            let mut mine: Vec<Tick> = supplied
+                .into_iter()
+                .filter(|t| instruments.normalize(&t.pair).is_ok_and(|n| n == pair))
+                .filter(|t| t.ts_ms <= p.to_ms)
+                .map(|t| Tick {
+                    pair: pair.as_str().into(),
+                    ..t
+                })
+                .collect();
+            mine.sort_by_key(|t| t.ts_ms);
+            let split = mine.partition_point(|t| t.ts_ms < p.from_ms);
+            let ticks = mine.split_off(split);
+            let warmup = mine.split_off(split.saturating_sub(WARMUP_TICKS));
+            ("supplied", warmup, ticks, None)
+        }
+        None => {
+            let history = oracle.history();
+            let starts = history.oldest(&pair).await.filter(|ts| *ts > p.from_ms);
+            (
+                "history",
+                history.before(&pair, p.from_ms, WARMUP_TICKS).await,
+                history.range(&pair, p.from_ms, p.to_ms).await,
+                starts,
+            )
+        }
+    };
+    let orders = collect_active_orders(&pair, state.orders.as_ref()).await;
+    let policy = engine.flagged_policy(&flags);
+    let result = reevaluate(&pair, orders, &warmup, &ticks, &policy, &instruments).await;
+    tracing::info!(
+        %pair,
+        source,
+        ticks = result.ticks,
+        would_trigger = result.would_trigger.len(),
+        "re-evaluated active orders"
+    );
+    Ok(HttpResponse::Ok().json(ReevaluateResponse {
+        from_ms: p.from_ms,
+        to_ms: p.to_ms,
+        source,
+        history_starts_ms,
+        result,
+    }))
+}
+
+/// The journal as newline-delimited JSON, for loading elsewhere later.
+pub async fn export_journal(journal: web::Data<Journal>) -> HttpResponse {
+    HttpResponse::Ok()
+        .content_type("application/x-ndjson")
+        .body(journal.export())
+}
+
+/// Replaces the journal with an exported capture, upgrading old records.
+pub async fn import_journal(
+    journal: web::Data<Journal>,
+    body: String,
+) -> Result<HttpResponse, ApiError> {
+    let report = journal.import(&body).map_err(ApiError::BadRequest)?;
+    tracing::info!(
+        entries = report.entries,
+        upgraded = report.upgraded,
+        "journal imported"
+    );
+    Ok(HttpResponse::Ok().json(report))
+}
+
+/// Why an operator intervenes; `reason` is required.
+#[derive(Debug, Deserialize)]
+pub struct InterventionPayload {
+    pub reason: String,
+    pub operator: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TransitionPayload {
+    pub status: OrderStatus,
+    #[serde(flatten)]
+    pub why: InterventionPayload,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ForceQuery {
+    #[serde(default)]
+    pub force: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AuditQuery {
+    pub order_id: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct InterventionResponse {
+    order: crate::entities::order::Order,
+    audit: AuditEntry,
+}
+
+/// Moves order `id` to `to` if the lifecycle allows it, as the operator
+/// rather than as the owner, and records the intervention. Leaving
+/// `Suspended` also clears the order's dead letter so the engine picks it
+/// up again. [`AuditAction::Force`] skips the lifecycle check.
+async fn intervene(
+    state: &AppState,
+    audit: &AuditLog,
+    dead_letters: &DeadLetters,
+    id: &str,
+    action: AuditAction,
+    to: OrderStatus,
+    why: InterventionPayload,
+) -> Result<HttpResponse, ApiError> {
+    let reason = why.reason.trim();
+    if reason.is_empty() {
+        return Err(ApiError::BadRequest("reason is required".into()));
+    }
+    let before = state
+        .orders
+        .get_by_id(id)
+        .await
+        .map_err(|_| ApiError::NotFound)?;
+    if action != AuditAction::Force && !before.status.can_transition_to(&to) {
+        return Err(ApiError::Conflict(
+            format!("order is {:?}; cannot move to {to:?}", before.status).to_lowercase(),
+        ));
+    }
+    let written = match to {
+        OrderStatus::Cancelled => state.orders.cancel(id, CANCEL_BY_ADMIN).await,
+        _ => state.orders.set_status(id, to.clone()).await,
+    };
+    let order = written.map_err(|_| ApiError::Internal)?;
+    if before.status == OrderStatus::Suspended {
+        dead_letters.release(id);
+    }
+    let entry = audit.record(action, &before, to, reason, why.operator.as_deref());
+    tracing::warn!(order_id = %id, ?action, from = ?entry.from, to = ?entry.to, reason, operator = ?entry.operator, "admin intervention");
+    Ok(HttpResponse::Ok().json(InterventionResponse {
+        order,
+        audit: entry,
+    }))
+}
+
+pub async fn admin_cancel_order(
+    state: web::Data<AppState>,
+    audit: web::Data<AuditLog>,
+    dead_letters: web::Data<DeadLetters>,
+    path: web::Path<String>,
+    payload: web::Json<InterventionPayload>,
+) -> Result<HttpResponse, ApiError> {
+    let to = OrderStatus::Cancelled;
+    let why = payload.into_inner();
+    intervene(
+        &state,
+        &audit,
+        &dead_letters,
+        &path,
+        AuditAction::Cancel,
+        to,
+        why,
+    )
+    .await
+}
+
+/// Parks an active order; the engine skips it until it is reopened.
+pub async fn admin_suspend_order(
+    state: web::Data<AppState>,
+    audit: web::Data<AuditLog>,
+    dead_letters: web::Data<DeadLetters>,
+    path: web::Path<String>,
+    payload: web::Json<InterventionPayload>,
+) -> Result<HttpResponse, ApiError> {
+    let to = OrderStatus::Suspended;
+    let why = payload.into_inner();
+    intervene(
+        &state,
+        &audit,
+        &dead_letters,
+        &path,
+        AuditAction::Suspend,
+        to,
+        why,
+    )
+    .await
+}
+
+/// Forces a stuck order along a lifecycle edge. Fills carry quantities
+/// and prices, so they go through reconciliation instead.
+pub async fn admin_transition_order(
+    state: web::Data<AppState>,
+    audit: web::Data<AuditLog>,
+    dead_letters: web::Data<DeadLetters>,
+    path: web::Path<String>,
+    payload: web::Json<TransitionPayload>,
+) -> Result<HttpResponse, ApiError> {
+    let TransitionPayload { status, why } = payload.into_inner();
+    if matches!(status, OrderStatus::PartiallyFilled | OrderStatus::Filled) {
+        return Err(ApiError::BadRequest(
+            "fills are applied through /admin/orders/reconcile".into(),
+        ));
+    }
+    let action = AuditAction::Transition;
+    intervene(&state, &audit, &dead_letters, &path, action, status, why).await
+}
+
+/// Sets an order's status outright, whatever its lifecycle says. Nothing
+/// else may write a raw status, as the engine relies on every order moving
+/// along its lifecycle, so this takes `force=true` and a reason and is
+/// audited.
+pub async fn admin_force_status(
+    state: web::Data<AppState>,
+    audit: web::Data<AuditLog>,
+    dead_letters: web::Data<DeadLetters>,
+    path: web::Path<String>,
+    q: web::Query<ForceQuery>,
+    payload: web::Json<TransitionPayload>,
+) -> Result<HttpResponse, ApiError> {
+    if !q.force {
+        return Err(ApiError::BadRequest(
+            "force=true is required; lifecycle moves go through /admin/orders/{id}/transition"
+                .into(),
+        ));
+    }
+    let TransitionPayload { status, why } = payload.into_inner();
+    let action = AuditAction::Force;
+    intervene(&state, &audit, &dead_letters, &path, action, status, why).await
+}
+
+pub async fn audit_log(
+    audit: web::Data<AuditLog>,
+    q: web::Query<AuditQuery>,
+) -> Result<HttpResponse, ApiError> {
+    Ok(HttpResponse::Ok().json(audit.list(q.order_id.as_deref())))
+}
@@ -0,0 +1,119 @@
+use actix_web::{web, HttpRequest, HttpResponse};
+use rust_decimal::Decimal;
+use serde::Deserialize;
+
+use crate::accounts::AccountStore;
+use crate::entities::basket::{Basket, BasketStatus, NewBasket};
+use crate::errors::ApiError;
+use crate::handlers::accounts::{maybe_authenticate, within_limits};
+use crate::handlers::orders::accepting_orders;
+use crate::instruments::InstrumentRegistry;
+use crate::limits::Limits;
+use crate::oracle_service::OracleCache;
+use crate::repositories::baskets::BasketStore;
+use crate::state::AppState;
+
+#[derive(Debug, Deserialize)]
+pub struct ListBasketsQuery {
+    pub status: Option<BasketStatus>,
+}
+
+/// Pairs normalized, legs bounded by the batch limit and each quantity
+/// positive and within the instrument's precision.
+fn validate_basket(
+    instruments: &InstrumentRegistry,
+    limits: &Limits,
+    mut new: NewBasket,
+) -> Result<NewBasket, ApiError> {
+    let bad = |e: String| ApiError::BadRequest(e);
+    new.trigger.validate().map_err(bad)?;
+    new.trigger.pair = instruments
+        .normalize(&new.trigger.pair)
+        .map_err(|e| bad(e.to_string()))?;
+    if new.legs.is_empty() || new.legs.len() > limits.max_batch {
+        return Err(bad(format!(
+            "a basket takes 1 to {} legs",
+            limits.max_batch
+        )));
+    }
+    for leg in &mut new.legs {
+        leg.pair = instruments
+            .normalize(&leg.pair)
+            .map_err(|e| bad(e.to_string()))?;
+        if leg.quantity <= Decimal::ZERO {
+            return Err(bad("leg quantity must be > 0".into()));
+        }
+        if let Some(instrument) = instruments.get(&leg.pair) {
+            instrument
+                .check_precision(Decimal::ZERO, leg.quantity)
+                .map_err(bad)?;
+        }
+    }
+    Ok(new)
+}
+
+/// Baskets placed with an API key belong to its account, as do their leg
+/// orders.
+pub async fn create_basket(
+    req: HttpRequest,
+    instruments: web::Data<InstrumentRegistry>,
+    oracle: web::Data<OracleCache>,
+    accounts: web::Data<AccountStore>,
+    baskets: web::Data<BasketStore>,
+    limits: web::Data<Limits>,
+    payload: web::Json<NewBasket>,
+) -> Result<HttpResponse, ApiError> {
+    let account_id = maybe_authenticate(&req, &accounts)?.map(|a| a.id);
+    accepting_orders(&oracle)?;
+    let new = validate_basket(&instruments, &limits, payload.into_inner())?;
+    if let Some(id) = &account_id {
+        // Legs go out as market orders, which carry no limit notional.
+        within_limits(&accounts, id, new.legs.len(), Decimal::ZERO)?;
+    }
+    let created = baskets.create(Basket::from_new(new, account_id)).await;
+    Ok(HttpResponse::Created().json(created))
+}
+
+pub async fn list_baskets(
+    baskets: web::Data<BasketStore>,
+    q: web::Query<ListBasketsQuery>,
+) -> Result<HttpResponse, ApiError> {
+    Ok(HttpResponse::Ok().json(baskets.list(q.status).await))
+}
+
+/// The basket with each placed leg's order as it stands now.
+pub async fn get_basket(
+    state: web::Data<AppState>,
+    baskets: web::Data<BasketStore>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let mut b = baskets.get(&path).await.ok_or(ApiError::NotFound)?;
+    for leg in &mut b.legs {
+        if let Some(id) = &leg.order_id {
+            leg.order = state.reads.get_by_id(id).await.ok();
+        }
+    }
+    Ok(HttpResponse::Ok().json(b))
+}
+
+/// Withdraws a basket whose trigger has not fired; like orders, only its
+/// owner may.
+pub async fn cancel_basket(
+    req: HttpRequest,
+    accounts: web::Data<AccountStore>,
+    baskets: web::Data<BasketStore>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let account = maybe_authenticate(&req, &accounts)?;
+    let b = baskets.get(&path).await.ok_or(ApiError::NotFound)?;
+    if b.account_id.as_deref() != account.as_ref().map(|a| a.id.as_str()) {
+        return Err(ApiError::NotFound);
+    }
+    let b = baskets.cancel(&b.id).await.ok_or(ApiError::NotFound)?;
+    if b.status != BasketStatus::Cancelled {
+        return Err(ApiError::Conflict(
+            format!("basket is {:?}", b.status).to_lowercase(),
+        ));
+    }
+    Ok(HttpResponse::Ok().json(b))
+}
@@ -0,0 +1,74 @@
+use actix_web::{web, HttpResponse};
+use rust_decimal::Decimal;
+use serde::Deserialize;
+
+use crate::errors::ApiError;
+use crate::instruments::InstrumentRegistry;
+use crate::repositories::journal::Journal;
+use crate::repositories::open_orders::OpenOrdersView;
+
+#[derive(Debug, Deserialize)]
+pub struct AsOfQuery {
+    /// Epoch ms; reconstructs state from the journal instead of the live view.
+    pub as_of: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BookQuery {
+    /// As in [`AsOfQuery`].
+    pub as_of: Option<i64>,
+    /// Bucket width for the levels; a multiple of the pair's tick size.
+    pub agg: Option<Decimal>,
+}
+
+/// Checks a bucket width against the pair's price grid: its tick size
+/// when it has one, else its price precision.
+fn check_agg(instruments: &InstrumentRegistry, pair: &str, agg: Decimal) -> Result<(), ApiError> {
+    if agg <= Decimal::ZERO {
+        return Err(ApiError::BadRequest("agg must be positive".into()));
+    }
+    let Some(i) = instruments.get(pair) else {
+        return Ok(());
+    };
+    match i.tick_size.filter(|t| *t > Decimal::ZERO) {
+        Some(tick) if !(agg % tick).is_zero() => Err(ApiError::BadRequest(format!(
+            "agg {agg} is not a multiple of the {pair} tick size {tick}"
+        ))),
+        None if agg.normalize().scale() > i.price_scale => Err(ApiError::BadRequest(format!(
+            "agg {agg} has more than {} decimal places",
+            i.price_scale
+        ))),
+        _ => Ok(()),
+    }
+}
+
+pub async fn get_book(
+    instruments: web::Data<InstrumentRegistry>,
+    view: web::Data<OpenOrdersView>,
+    journal: web::Data<Journal>,
+    path: web::Path<String>,
+    q: web::Query<BookQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let pair = instruments
+        .normalize(&path.into_inner())
+        .map_err(|_| ApiError::NotFound)?;
+    if let Some(agg) = q.agg {
+        check_agg(&instruments, &pair, agg)?;
+    }
+    let book = match q.as_of {
+        Some(as_of) => journal
+            .book_as_of(&pair, as_of)
+            .map_err(ApiError::BadRequest)?,
+        None if !view.is_live() => {
+            return Err(ApiError::Unavailable(
+                "open orders view is not ready".into(),
+            ))
+        }
+        None => view.book(&pair),
+    };
+    let book = match q.agg {
+        Some(agg) => book.bucketed(agg),
+        None => book,
+    };
+    Ok(HttpResponse::Ok().json(book))
+}
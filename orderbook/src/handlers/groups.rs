@@ -0,0 +1,93 @@
+use std::collections::HashSet;
+
+use actix_web::{web, HttpRequest, HttpResponse};
+use rust_decimal::Decimal;
+
+use crate::accounts::AccountStore;
+use crate::engine::groups::OrderGroups;
+use crate::entities::group::{NewOrderGroup, OrderGroup};
+use crate::entities::order::OrderSide;
+use crate::errors::ApiError;
+use crate::handlers::accounts::maybe_authenticate;
+use crate::handlers::orders::{owned_order, require_active};
+use crate::limits::Limits;
+use crate::state::AppState;
+
+/// Groups the caller's active buy orders under a shared quote budget.
+/// Orders reach the group as they stand, fills so far included, so a
+/// budget they have already spent cancels the rest straight away.
+pub async fn create_group(
+    req: HttpRequest,
+    state: web::Data<AppState>,
+    accounts: web::Data<AccountStore>,
+    groups: web::Data<OrderGroups>,
+    limits: web::Data<Limits>,
+    payload: web::Json<NewOrderGroup>,
+) -> Result<HttpResponse, ApiError> {
+    let account_id = maybe_authenticate(&req, &accounts)?.map(|a| a.id);
+    let NewOrderGroup { budget, order_ids } = payload.into_inner();
+    if budget <= Decimal::ZERO {
+        return Err(ApiError::BadRequest("budget must be > 0".into()));
+    }
+    if order_ids.is_empty() || order_ids.len() > limits.max_batch {
+        return Err(ApiError::BadRequest(format!(
+            "a group takes 1 to {} orders",
+            limits.max_batch
+        )));
+    }
+    if order_ids.iter().collect::<HashSet<_>>().len() != order_ids.len() {
+        return Err(ApiError::BadRequest("order_ids repeat an order".into()));
+    }
+    let mut orders = Vec::with_capacity(order_ids.len());
+    for id in &order_ids {
+        let order = owned_order(&state, id, account_id.as_deref()).await?;
+        require_active(&order)?;
+        if order.side != OrderSide::Buy {
+            return Err(ApiError::BadRequest(format!(
+                "order {id} is not a buy; only buys share a budget"
+            )));
+        }
+        orders.push(order);
+    }
+    let group = groups
+        .create(OrderGroup::new(budget, &orders, account_id))
+        .map_err(ApiError::Conflict)?;
+    // Fills that landed between the reads and the group being stored.
+    let mut current = Vec::with_capacity(order_ids.len());
+    for id in &order_ids {
+        current.extend(state.orders.get_by_id(id).await.ok());
+    }
+    groups.refresh(&current);
+    let group = groups.get(&group.id).unwrap_or(group);
+    Ok(HttpResponse::Created().json(group))
+}
+
+/// The caller's groups, oldest first.
+pub async fn list_groups(
+    req: HttpRequest,
+    accounts: web::Data<AccountStore>,
+    groups: web::Data<OrderGroups>,
+) -> Result<HttpResponse, ApiError> {
+    let account_id = maybe_authenticate(&req, &accounts)?.map(|a| a.id);
+    Ok(HttpResponse::Ok().json(groups.list(account_id.as_deref())))
+}
+
+/// The group with each member's order as it stands now; like orders, only
+/// its owner sees it.
+pub async fn get_group(
+    req: HttpRequest,
+    state: web::Data<AppState>,
+    accounts: web::Data<AccountStore>,
+    groups: web::Data<OrderGroups>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let account_id = maybe_authenticate(&req, &accounts)?.map(|a| a.id);
+    let mut g = groups
+        .get(&path)
+        .filter(|g| g.account_id == account_id)
+        .ok_or(ApiError::NotFound)?;
+    for m in &mut g.members {
+        m.order = state.reads.get_by_id(&m.order_id).await.ok();
+    }
+    Ok(HttpResponse::Ok().json(g))
+}
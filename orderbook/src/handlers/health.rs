@@ -1,9 +1,23 @@
-use actix_web::{HttpResponse, Responder};
+use actix_web::{web, HttpResponse, Responder};
+
+use crate::oracle_service::OracleCache;
 
 pub async fn ping() -> impl Responder {
     HttpResponse::Ok().body("pong")
 }
 
+/// `GET /health/ready`: **503** while the oracle dead-man switch holds the
+/// service cancel-only, with the switch state either way.
+pub async fn ready(oracle: web::Data<OracleCache>) -> impl Responder {
+    let status = oracle.dead_man().status();
+    let mut resp = if status.cancel_only {
+        HttpResponse::ServiceUnavailable()
+    } else {
+        HttpResponse::Ok()
+    };
+    resp.json(status)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,17 @@
+use actix_web::{web, HttpResponse};
+
+use crate::errors::ApiError;
+use crate::oracle_service::OracleCache;
+
+pub async fn list_index(cache: web::Data<OracleCache>) -> Result<HttpResponse, ApiError> {
+    Ok(HttpResponse::Ok().json(cache.index().all().await))
+}
+
+pub async fn get_index(
+    cache: web::Data<OracleCache>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let pair = path.into_inner();
+    let idx = cache.get_index(&pair).await.ok_or(ApiError::NotFound)?;
+    Ok(HttpResponse::Ok().json(idx))
+}
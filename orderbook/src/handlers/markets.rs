@@ -0,0 +1,176 @@
+use actix_web::{web, HttpResponse};
+use futures_util::stream;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::errors::ApiError;
+use crate::instruments::session::SessionState;
+use crate::instruments::{Instrument, InstrumentRegistry};
+use crate::oracle_service::OracleCache;
+use crate::repositories::open_orders::OpenOrdersView;
+use crate::repositories::tape::{TapeEntry, TradeTape};
+use crate::utils::now_ms;
+
+#[derive(Debug, Serialize)]
+struct MarketResponse {
+    #[serde(flatten)]
+    instrument: Instrument,
+    session_state: SessionState,
+    #[serde(serialize_with = "crate::utils::decimal::serialize_opt")]
+    last_price: Option<Decimal>,
+    last_ts_ms: Option<i64>,
+}
+
+async fn describe(
+    instrument: Instrument,
+    instruments: &InstrumentRegistry,
+    oracle: &OracleCache,
+) -> MarketResponse {
+    let px = oracle.get_price(&instrument.symbol).await;
+    MarketResponse {
+        session_state: instruments.session_state(&instrument.symbol, now_ms()),
+        last_price: px.map(|(p, _)| p),
+        last_ts_ms: px.map(|(_, ts)| ts),
+        instrument,
+    }
+}
+
+pub async fn list_markets(
+    instruments: web::Data<InstrumentRegistry>,
+    oracle: web::Data<OracleCache>,
+) -> Result<HttpResponse, ApiError> {
+    let mut out = Vec::new();
+    for symbol in instruments.symbols() {
+        if let Some(i) = instruments.get(&symbol) {
+            out.push(describe(i, &instruments, &oracle).await);
+        }
+    }
+    Ok(HttpResponse::Ok().json(out))
+}
+
+pub async fn get_market(
+    instruments: web::Data<InstrumentRegistry>,
+    oracle: web::Data<OracleCache>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let symbol = instruments
+        .normalize(&path.into_inner())
+        .map_err(|_| ApiError::NotFound)?;
+    let instrument = instruments.get(&symbol).ok_or(ApiError::NotFound)?;
+    Ok(HttpResponse::Ok().json(describe(instrument, &instruments, &oracle).await))
+}
+
+const DEFAULT_TRIGGER_DEPTH: usize = 10;
+const MAX_TRIGGER_DEPTH: usize = 100;
+
+#[derive(Debug, Deserialize)]
+pub struct TriggersQuery {
+    pub levels: Option<usize>,
+}
+
+/// How crowded the trigger levels near the last price are, without saying
+/// whose orders they are.
+pub async fn get_triggers(
+    instruments: web::Data<InstrumentRegistry>,
+    oracle: web::Data<OracleCache>,
+    view: web::Data<OpenOrdersView>,
+    path: web::Path<String>,
+    q: web::Query<TriggersQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let symbol = instruments
+        .normalize(&path.into_inner())
+        .map_err(|_| ApiError::NotFound)?;
+    let instrument = instruments.get(&symbol).ok_or(ApiError::NotFound)?;
+    let depth = q.levels.unwrap_or(DEFAULT_TRIGGER_DEPTH);
+    if depth == 0 || depth > MAX_TRIGGER_DEPTH {
+        return Err(ApiError::BadRequest(format!(
+            "levels must be 1 to {MAX_TRIGGER_DEPTH}"
+        )));
+    }
+    if !view.is_live() {
+        return Err(ApiError::Unavailable(
+            "open orders view is not ready".into(),
+        ));
+    }
+    let px = oracle.get_price(&symbol).await.map(|(p, _)| p);
+    Ok(HttpResponse::Ok().json(view.triggers(&symbol, px, instrument.price_scale, depth)))
+}
+
+const DEFAULT_TAPE_LIMIT: usize = 100;
+const MAX_TAPE_LIMIT: usize = 1000;
+
+#[derive(Debug, Deserialize)]
+pub struct TapeQuery {
+    pub from_seq: Option<u64>,
+    pub to_seq: Option<u64>,
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Serialize)]
+struct TapePage {
+    pair: String,
+    /// The pair's latest sequence number.
+    head: u64,
+    entries: Vec<TapeEntry>,
+}
+
+/// A range of the pair's trade tape, from `from_seq` (default 1). Ranges
+/// never change once written, so paging on `seq` is exact.
+pub async fn get_tape(
+    instruments: web::Data<InstrumentRegistry>,
+    tape: web::Data<TradeTape>,
+    path: web::Path<String>,
+    q: web::Query<TapeQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let symbol = instruments
+        .normalize(&path.into_inner())
+        .map_err(|_| ApiError::NotFound)?;
+    let limit = q.limit.unwrap_or(DEFAULT_TAPE_LIMIT);
+    if limit == 0 || limit > MAX_TAPE_LIMIT {
+        return Err(ApiError::BadRequest(format!(
+            "limit must be 1 to {MAX_TAPE_LIMIT}"
+        )));
+    }
+    let from = q.from_seq.unwrap_or(1);
+    if q.to_seq.is_some_and(|to| to < from) {
+        return Err(ApiError::BadRequest("to_seq is before from_seq".into()));
+    }
+    Ok(HttpResponse::Ok().json(TapePage {
+        head: tape.head(&symbol),
+        entries: tape.range(&symbol, from, q.to_seq, limit),
+        pair: symbol,
+    }))
+}
+
+/// Server-sent events relaying the pair's topic on the market data hub, as
+/// `event: tick` or `event: trade`. A consumer that falls behind skips
+/// what it missed rather than holding up the feed.
+pub async fn stream_market(
+    instruments: web::Data<InstrumentRegistry>,
+    oracle: web::Data<OracleCache>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let symbol = instruments
+        .normalize(&path.into_inner())
+        .map_err(|_| ApiError::NotFound)?;
+    let rx = oracle.hub().subscribe(&symbol);
+    let body = stream::unfold(rx, |mut rx| async move {
+        loop {
+            match rx.recv().await {
+                Ok(data) => {
+                    let json = serde_json::to_string(&data).unwrap_or_default();
+                    let frame =
+                        web::Bytes::from(format!("event: {}\ndata: {json}\n\n", data.name()));
+                    return Some((Ok::<_, actix_web::Error>(frame), rx));
+                }
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    });
+    Ok(HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header(("Cache-Control", "no-cache"))
+        .streaming(body))
+}
@@ -0,0 +1,9 @@
+use actix_web::{web, HttpResponse, Responder};
+
+use crate::metrics::Metrics;
+
+pub async fn render(metrics: web::Data<Metrics>) -> impl Responder {
+    HttpResponse::Ok()
+        .content_type("text/plain; version=0.0.4")
+        .body(metrics.render())
+}
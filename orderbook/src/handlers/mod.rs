@@ -1,2 +1,14 @@
+pub mod accounts;
+pub mod admin;
+pub mod baskets;
+pub mod book;
+pub mod groups;
 pub mod health;
+pub mod index;
+pub mod markets;
+pub mod metrics;
 pub mod orders;
+pub mod stats;
+pub mod templates;
+pub mod ui;
+pub mod ws;
@@ -1,18 +1,75 @@
-use actix_web::{web, HttpResponse};
+use std::collections::BTreeMap;
+
+use actix_web::{web, HttpRequest, HttpResponse, HttpResponseBuilder};
+use futures_util::StreamExt;
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tracing::{debug, debug_span, error, field, Instrument};
 
-use crate::entities::order::{NewOrder, Order, OrderSide, OrderStatus};
+use crate::accounts::AccountStore;
+use crate::engine::callbacks::OrderCallbacks;
+use crate::entities::callback::validate_callback;
+use crate::entities::order::{
+    validate_labels, Condition, ExecPricing, NewOrder, Order, OrderSide, OrderStatus, OrderType,
+    TimeInForce, Trail, TriggerPriceType, CANCEL_BY_OWNER, CANCEL_OCO,
+};
 use crate::errors::ApiError;
+use crate::handlers::accounts::{may_cancel, maybe_authenticate, within_limits};
+use crate::handlers::book::AsOfQuery;
+use crate::instruments::session::{OffSessionPolicy, SessionState};
+use crate::instruments::{self, base_for_quote, InstrumentRegistry, DEFAULT_SCALE};
+use crate::metrics::placement::{self, measure, PlacementTimings, Stage};
+use crate::oracle_service::OracleCache;
+use crate::repositories::journal::Journal;
+use crate::repositories::open_orders::OpenOrdersView;
+use crate::repositories::search::{OrderSearch, SearchQuery};
 use crate::repositories::ListOrdersQuery;
 use crate::state::AppState;
+use crate::utils::now_ms;
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize)]
 pub struct CreateOrderPayload {
     pub pair: String,
     pub side: OrderSide,
-    pub price: Decimal,
+    #[serde(default)]
+    pub order_type: OrderType,
+    pub price: Option<Decimal>,
+    /// The stop level of a `stop` or `stop_limit` order.
+    pub trigger_price: Option<Decimal>,
+    /// Makes a `stop` order trail the reference by this much.
+    pub trailing: Option<Trail>,
+    /// Zero when the order is sized by `quantity_quote` instead.
+    #[serde(default)]
     pub quantity: Decimal,
+    pub quantity_quote: Option<Decimal>,
+    #[serde(default, alias = "trigger_on")]
+    pub trigger_price_type: TriggerPriceType,
+    pub price_source: Option<String>,
+    #[serde(default)]
+    pub condition: Condition,
+    pub sustained_for_ms: Option<i64>,
+    #[serde(default)]
+    pub time_in_force: TimeInForce,
+    pub cancel_after_ms: Option<i64>,
+    pub valid_for_after_armed_ms: Option<i64>,
+    pub expires_at: Option<i64>,
+    pub max_slippage_bps: Option<Decimal>,
+    pub exec_pricing: Option<ExecPricing>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub metadata: BTreeMap<String, String>,
+    pub callback_url: Option<String>,
+    pub callback_template: Option<Value>,
+}
+
+impl CreateOrderPayload {
+    /// What the order commits in quote currency, for account limits.
+    pub fn notional(&self) -> Decimal {
+        self.quantity_quote
+            .unwrap_or(self.price.unwrap_or_default() * self.quantity)
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -24,84 +81,967 @@ pub struct ListQuery {
 }
 
 #[derive(Debug, Deserialize)]
-pub struct UpdateStatusPayload {
-    pub status: OrderStatus,
+pub struct SearchParams {
+    pub q: String,
+    pub limit: Option<usize>,
+}
+
+const DEFAULT_SEARCH_LIMIT: usize = 20;
+const MAX_SEARCH_LIMIT: usize = 100;
+
+#[derive(Debug, Serialize)]
+pub struct SearchResult {
+    pub score: u32,
+    pub order: Order,
+}
+
+/// New price and/or quantity for a resting order, or for its replacement
+/// on a cancel/replace.
+#[derive(Debug, Deserialize)]
+pub struct AmendPayload {
+    pub price: Option<Decimal>,
+    pub quantity: Option<Decimal>,
 }
 
 #[derive(Debug, Serialize)]
 struct OrderResponse(Order);
 
+/// Both sides of a cancel/replace: the cancelled order and the one that
+/// took its place.
+#[derive(Debug, Serialize)]
+pub struct ReplaceResponse {
+    pub replaced: Order,
+    pub order: Order,
+}
+
+/// Starts a response for data read through `state.reads`, tagged when it
+/// came from a replica so clients know it may lag the primary.
+fn read_response(state: &AppState) -> HttpResponseBuilder {
+    let mut b = HttpResponse::Ok();
+    if state.reads_from_replica {
+        b.insert_header(("X-Read-Source", "replica"));
+        if let Some(lag) = state.reads.replication_lag_ms() {
+            b.insert_header(("X-Replica-Lag-Ms", lag.to_string()));
+        }
+    }
+    b
+}
+
+/// Orders placed with an API key belong to its account.
 pub async fn create_order(
+    req: HttpRequest,
     state: web::Data<AppState>,
+    instruments: web::Data<InstrumentRegistry>,
+    oracle: web::Data<OracleCache>,
+    timings: web::Data<PlacementTimings>,
+    accounts: web::Data<AccountStore>,
     payload: web::Json<CreateOrderPayload>,
 ) -> Result<HttpResponse, ApiError> {
+    let account_id = maybe_authenticate(&req, &accounts)?.map(|a| a.id);
+    if let Some(id) = &account_id {
+        within_limits(&accounts, id, 1, payload.notional())?;
+    }
+    let created = place_order(
+        &state,
+        &instruments,
+        &oracle,
+        &timings,
+        payload.into_inner(),
+        account_id,
+    )
+    .await?;
+    Ok(HttpResponse::Created().json(OrderResponse(created)))
+}
+
+/// The two legs of a one-cancels-other pair.
+#[derive(Debug, Deserialize)]
+pub struct OcoPayload {
+    pub legs: Vec<CreateOrderPayload>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct OcoResponse {
+    pub oco_id: String,
+    pub orders: Vec<Order>,
+}
+
+/// Places two orders on one pair linked by a fresh `oco_id`: the engine's
+/// first fill on either leg cancels the other in the same write. Both legs
+/// are validated before either is stored, and each counts toward the
+/// account's limits.
+pub async fn create_oco(
+    req: HttpRequest,
+    state: web::Data<AppState>,
+    instruments: web::Data<InstrumentRegistry>,
+    oracle: web::Data<OracleCache>,
+    accounts: web::Data<AccountStore>,
+    payload: web::Json<OcoPayload>,
+) -> Result<HttpResponse, ApiError> {
+    let account_id = maybe_authenticate(&req, &accounts)?.map(|a| a.id);
+    let legs = payload.into_inner().legs;
+    if legs.len() != 2 {
+        return Err(ApiError::BadRequest(
+            "an OCO order takes exactly two legs".into(),
+        ));
+    }
+    if let Some(id) = &account_id {
+        within_limits(&accounts, id, 2, legs.iter().map(|l| l.notional()).sum())?;
+    }
+    let mut news = Vec::with_capacity(2);
+    for leg in legs {
+        let new = validate_new(&instruments, &oracle, leg, account_id.clone())?;
+        check_risk(&instruments, &oracle, &new).await?;
+        news.push(new);
+    }
+    if news[0].pair != news[1].pair {
+        return Err(ApiError::BadRequest(
+            "both legs of an OCO order must be on one pair".into(),
+        ));
+    }
+    let oco_id = crate::utils::ids::next_id();
+    let mut orders: Vec<Order> = Vec::with_capacity(2);
+    for mut new in news {
+        new.oco_id = Some(oco_id.clone());
+        match state.orders.create(new).await {
+            Ok(o) => orders.push(o),
+            Err(e) => {
+                error!(%oco_id, err = %e, "failed to place OCO leg; withdrawing the other");
+                for o in &orders {
+                    let _ = state.orders.cancel(&o.id, CANCEL_OCO).await;
+                }
+                return Err(ApiError::Internal);
+            }
+        }
+    }
+    Ok(HttpResponse::Created().json(OcoResponse { oco_id, orders }))
+}
+
+/// A bracket's take-profit: a limit order at `price`.
+#[derive(Debug, Deserialize)]
+pub struct TakeProfit {
+    pub price: Decimal,
+}
+
+/// A bracket's stop-loss: a stop order at `trigger_price`, or a
+/// stop-limit one when `price` is set.
+#[derive(Debug, Deserialize)]
+pub struct StopLoss {
+    pub trigger_price: Decimal,
+    pub price: Option<Decimal>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BracketPayload {
+    pub entry: CreateOrderPayload,
+    pub take_profit: TakeProfit,
+    pub stop_loss: StopLoss,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BracketResponse {
+    pub entry: Order,
+    pub take_profit: Order,
+    pub stop_loss: Order,
+}
+
+/// Places an entry with a take-profit and a stop-loss on the other side.
+/// The two exits are stored `pending` and linked as an OCO pair; the
+/// engine releases them for what the entry fills, or cancels them if it
+/// ends unfilled. All three are validated before any is stored, and each
+/// counts toward the account's limits.
+pub async fn create_bracket(
+    req: HttpRequest,
+    state: web::Data<AppState>,
+    instruments: web::Data<InstrumentRegistry>,
+    oracle: web::Data<OracleCache>,
+    accounts: web::Data<AccountStore>,
+    payload: web::Json<BracketPayload>,
+) -> Result<HttpResponse, ApiError> {
+    let account_id = maybe_authenticate(&req, &accounts)?.map(|a| a.id);
+    let BracketPayload {
+        entry,
+        take_profit,
+        stop_loss,
+    } = payload.into_inner();
+    if entry.quantity_quote.is_some() {
+        return Err(ApiError::BadRequest(
+            "a bracket entry must be sized by quantity".into(),
+        ));
+    }
+    let sl = stop_loss.trigger_price;
+    let ordered = match entry.side {
+        OrderSide::Buy => take_profit.price > sl,
+        OrderSide::Sell => take_profit.price < sl,
+    };
+    if !ordered {
+        return Err(ApiError::BadRequest(
+            "take_profit.price must be beyond stop_loss.trigger_price in the entry's favour".into(),
+        ));
+    }
+    if let Some(id) = &account_id {
+        within_limits(&accounts, id, 3, entry.notional() * Decimal::from(3))?;
+    }
+    let exit = |order_type, price, trigger_price| CreateOrderPayload {
+        side: match entry.side {
+            OrderSide::Buy => OrderSide::Sell,
+            OrderSide::Sell => OrderSide::Buy,
+        },
+        order_type,
+        price,
+        trigger_price,
+        trailing: None,
+        condition: Condition::Price,
+        sustained_for_ms: None,
+        time_in_force: TimeInForce::default(),
+        cancel_after_ms: None,
+        valid_for_after_armed_ms: None,
+        expires_at: None,
+        ..entry.clone()
+    };
+    let tp = exit(OrderType::Limit, Some(take_profit.price), None);
+    let sl = match stop_loss.price {
+        Some(px) => exit(OrderType::StopLimit, Some(px), Some(sl)),
+        None => exit(OrderType::Stop, None, Some(sl)),
+    };
+    let mut news = Vec::with_capacity(3);
+    for leg in [entry, tp, sl] {
+        let new = validate_new(&instruments, &oracle, leg, account_id.clone())?;
+        check_risk(&instruments, &oracle, &new).await?;
+        news.push(new);
+    }
+    let oco_id = crate::utils::ids::next_id();
+    let entry = news.remove(0);
+    for child in news.iter_mut() {
+        child.oco_id = Some(oco_id.clone());
+    }
+    let (entry, mut children) = state
+        .orders
+        .create_bracket(entry, news)
+        .await
+        .map_err(|e| {
+            error!(%oco_id, err = %e, "failed to place bracket");
+            ApiError::Internal
+        })?;
+    let stop_loss = children.pop().ok_or(ApiError::Internal)?;
+    let take_profit = children.pop().ok_or(ApiError::Internal)?;
+    Ok(HttpResponse::Created().json(BracketResponse {
+        entry,
+        take_profit,
+        stop_loss,
+    }))
+}
+
+/// Validates and stores a new order; shared by `POST /orders`, template
+/// instantiation and the order websocket. Each stage's time is recorded
+/// on the `place_order` span and in `timings`.
+pub(crate) async fn place_order(
+    state: &AppState,
+    instruments: &InstrumentRegistry,
+    oracle: &OracleCache,
+    timings: &PlacementTimings,
+    payload: CreateOrderPayload,
+    account_id: Option<String>,
+) -> Result<Order, ApiError> {
+    let span = debug_span!(
+        "place_order",
+        validation_us = field::Empty,
+        risk_us = field::Empty,
+        write_us = field::Empty,
+        publish_us = field::Empty,
+    );
+    let (placed, times) = placement::timed(async {
+        let new = measure(Stage::Validation, async {
+            validate_new(instruments, oracle, payload, account_id)
+        })
+        .await?;
+        measure(Stage::Risk, check_risk(instruments, oracle, &new)).await?;
+        measure(Stage::Write, state.orders.create(new))
+            .await
+            .map_err(|_| ApiError::Internal)
+    })
+    .instrument(span.clone())
+    .await;
+    if let Ok(order) = &placed {
+        for stage in Stage::ALL {
+            let field = format!("{}_us", stage.name());
+            span.record(field.as_str(), times.get(stage).as_micros() as u64);
+        }
+        debug!(parent: &span, order_id = %order.id, "order placed");
+        timings.record(times);
+    }
+    placed
+}
+
+/// Shape, symbol, price source and precision checks of a new order.
+fn validate_new(
+    instruments: &InstrumentRegistry,
+    oracle: &OracleCache,
+    mut payload: CreateOrderPayload,
+    account_id: Option<String>,
+) -> Result<NewOrder, ApiError> {
+    let pair = instruments
+        .normalize(&payload.pair)
+        .map_err(|e| ApiError::BadRequest(e.to_string()))?;
+    if let Condition::Spread { legs, .. } = &mut payload.condition {
+        for leg in legs.iter_mut() {
+            *leg = instruments
+                .normalize(leg)
+                .map_err(|e| ApiError::BadRequest(e.to_string()))?
+                .into();
+        }
+    }
+    if payload.condition != Condition::Price && payload.order_type != OrderType::Limit {
+        return Err(ApiError::BadRequest(
+            "conditions apply to limit orders only".into(),
+        ));
+    }
+    payload.condition.validate().map_err(ApiError::BadRequest)?;
+    validate_labels(&payload.tags, &payload.metadata).map_err(ApiError::BadRequest)?;
+    match (&payload.callback_url, &account_id) {
+        (Some(_), None) => {
+            return Err(ApiError::BadRequest(
+                "callback_url needs an API key: deliveries are signed with the account's webhook secret"
+                    .into(),
+            ))
+        }
+        (Some(url), Some(_)) => validate_callback(url, payload.callback_template.as_ref())
+            .map_err(ApiError::BadRequest)?,
+        (None, _) if payload.callback_template.is_some() => {
+            return Err(ApiError::BadRequest(
+                "callback_template needs a callback_url".into(),
+            ))
+        }
+        (None, _) => {}
+    }
+    oracle
+        .check_source(payload.price_source.as_deref())
+        .map_err(ApiError::BadRequest)?;
+    match payload.sustained_for_ms {
+        Some(ms) if ms <= 0 => {
+            return Err(ApiError::BadRequest("sustained_for_ms must be > 0".into()))
+        }
+        Some(_) if payload.order_type != OrderType::Limit => {
+            return Err(ApiError::BadRequest(
+                "sustained_for_ms applies to limit orders only".into(),
+            ))
+        }
+        // An IOC order gets one tick; there is nothing to sustain over.
+        Some(_) if payload.time_in_force == TimeInForce::Ioc => {
+            return Err(ApiError::BadRequest(
+                "sustained_for_ms does not apply to IOC orders".into(),
+            ))
+        }
+        _ => {}
+    }
+    match (payload.time_in_force, payload.expires_at) {
+        (TimeInForce::Gtd, None) => {
+            return Err(ApiError::BadRequest("GTD orders require expires_at".into()))
+        }
+        (TimeInForce::Gtd, Some(at)) if at <= now_ms() => {
+            return Err(ApiError::BadRequest(
+                "expires_at must be in the future".into(),
+            ))
+        }
+        (TimeInForce::Gtd, Some(_)) | (_, None) => {}
+        (_, Some(_)) => {
+            return Err(ApiError::BadRequest(
+                "expires_at applies to GTD orders only".into(),
+            ))
+        }
+    }
+    if payload.cancel_after_ms.is_some_and(|ms| ms <= 0) {
+        return Err(ApiError::BadRequest("cancel_after_ms must be > 0".into()));
+    }
+    match payload.valid_for_after_armed_ms {
+        Some(ms) if ms <= 0 => {
+            return Err(ApiError::BadRequest(
+                "valid_for_after_armed_ms must be > 0".into(),
+            ))
+        }
+        // FOK and IOC orders never rest once triggered.
+        Some(_) if !payload.time_in_force.rests() => {
+            return Err(ApiError::BadRequest(
+                "valid_for_after_armed_ms does not apply to FOK or IOC orders".into(),
+            ))
+        }
+        _ => {}
+    }
+    let price = match (payload.order_type, payload.price) {
+        (OrderType::Limit, Some(px)) => px,
+        // Non-price conditions carry the trigger, so the price is unused.
+        (OrderType::Limit, None) if !payload.condition.uses_price() => Decimal::ZERO,
+        (OrderType::Limit, None) => {
+            return Err(ApiError::BadRequest("limit orders require a price".into()))
+        }
+        (OrderType::StopLimit, Some(px)) => px,
+        (OrderType::StopLimit, None) => {
+            return Err(ApiError::BadRequest(
+                "stop_limit orders require a price".into(),
+            ))
+        }
+        (OrderType::Market | OrderType::Stop, _) => Decimal::ZERO,
+    };
+    let instrument = instruments.get(&pair);
+    let price = match &instrument {
+        Some(i) => i
+            .fit_price(price, &payload.side)
+            .map_err(ApiError::BadRequest)?,
+        None => price,
+    };
+    match payload.trailing {
+        Some(_) if payload.order_type != OrderType::Stop => {
+            return Err(ApiError::BadRequest(
+                "trailing applies to stop orders only".into(),
+            ))
+        }
+        Some(Trail::Amount(a)) if a <= Decimal::ZERO => {
+            return Err(ApiError::BadRequest("trailing amount must be > 0".into()))
+        }
+        Some(Trail::Percent(p)) if p <= Decimal::ZERO || p >= Decimal::ONE_HUNDRED => {
+            return Err(ApiError::BadRequest(
+                "trailing percent must be between 0 and 100".into(),
+            ))
+        }
+        _ => {}
+    }
+    let trigger_price = match (payload.order_type.is_stop(), payload.trigger_price) {
+        (true, Some(px)) if px > Decimal::ZERO => Some(match &instrument {
+            Some(i) => i
+                .fit_price(px, &payload.side)
+                .map_err(ApiError::BadRequest)?,
+            None => px,
+        }),
+        // A trailing stop without one takes its first from the reference.
+        (true, None) if payload.trailing.is_some() => None,
+        (true, _) => {
+            return Err(ApiError::BadRequest(
+                "stop orders require a trigger_price > 0".into(),
+            ))
+        }
+        (false, Some(_)) => {
+            return Err(ApiError::BadRequest(
+                "trigger_price applies to stop and stop_limit orders only".into(),
+            ))
+        }
+        (false, None) => None,
+    };
+    let quantity = match payload.quantity_quote {
+        None => payload.quantity,
+        Some(_) if !payload.quantity.is_zero() => {
+            return Err(ApiError::BadRequest(
+                "set quantity or quantity_quote, not both".into(),
+            ))
+        }
+        Some(q) if q <= Decimal::ZERO => {
+            return Err(ApiError::BadRequest("quantity_quote must be > 0".into()))
+        }
+        Some(_) if payload.order_type != OrderType::Limit || price.is_zero() => {
+            return Err(ApiError::BadRequest(
+                "quantity_quote needs a limit price to size against".into(),
+            ))
+        }
+        Some(q) => {
+            if let Some(instrument) = &instrument {
+                instrument
+                    .check_quote_precision(q)
+                    .map_err(ApiError::BadRequest)?;
+            }
+            let scale = instrument.as_ref().map_or(DEFAULT_SCALE, |i| i.qty_scale);
+            let estimate = base_for_quote(q, price, scale);
+            if estimate.is_zero() {
+                return Err(ApiError::BadRequest(format!(
+                    "quantity_quote {q} buys nothing at {price}"
+                )));
+            }
+            estimate
+        }
+    };
+    if let Some(instrument) = &instrument {
+        instrument
+            .check_precision(price, quantity)
+            .map_err(ApiError::BadRequest)?;
+        if let Some(px) = trigger_price {
+            instrument
+                .check_precision(px, quantity)
+                .map_err(ApiError::BadRequest)?;
+        }
+        if let Some(Trail::Amount(a)) = payload.trailing {
+            instrument
+                .check_precision(a, quantity)
+                .map_err(ApiError::BadRequest)?;
+        }
+    }
+    // Only a price-triggered limit order has a limit to protect.
+    let exec_pricing = match (payload.exec_pricing, payload.order_type, &payload.condition) {
+        (p, OrderType::Limit | OrderType::StopLimit, Condition::Price) => {
+            Some(p.unwrap_or(instrument.map_or(ExecPricing::default(), |i| i.exec_pricing)))
+        }
+        (None | Some(ExecPricing::Oracle), _, _) => None,
+        (Some(_), _, _) => {
+            return Err(ApiError::BadRequest(
+                "exec_pricing applies to price-triggered limit orders only".into(),
+            ))
+        }
+    };
+    Ok(NewOrder {
+        pair,
+        side: payload.side,
+        order_type: payload.order_type,
+        price,
+        quantity,
+        trigger_price_type: payload.trigger_price_type,
+        price_source: payload.price_source,
+        condition: payload.condition,
+        sustained_for_ms: payload.sustained_for_ms,
+        time_in_force: payload.time_in_force,
+        cancel_after_ms: payload.cancel_after_ms,
+        valid_for_after_armed_ms: payload.valid_for_after_armed_ms,
+        expires_at: payload.expires_at,
+        max_slippage_bps: payload.max_slippage_bps,
+        exec_pricing,
+        account_id,
+        quantity_quote: payload.quantity_quote,
+        tags: payload.tags,
+        metadata: payload.metadata,
+        callback_url: payload.callback_url,
+        callback_template: payload.callback_template,
+        trigger_price,
+        trailing: payload.trailing,
+        oco_id: None,
+    })
+}
+
+/// Refused while the oracle dead-man switch holds the service cancel-only.
+pub(crate) fn accepting_orders(oracle: &OracleCache) -> Result<(), ApiError> {
+    let status = oracle.dead_man().status();
+    if status.cancel_only {
+        return Err(ApiError::CancelOnly(status.timeout_ms.unwrap_or_default()));
+    }
+    Ok(())
+}
+
+/// Whether the market can take the order now: nothing is placed in
+/// cancel-only mode, a market order needs a fresh feed, and a closed
+/// session may refuse orders.
+async fn check_risk(
+    instruments: &InstrumentRegistry,
+    oracle: &OracleCache,
+    new: &NewOrder,
+) -> Result<(), ApiError> {
+    accepting_orders(oracle)?;
+    let pair = &new.pair;
+    if new.order_type == OrderType::Market && !oracle.has_fresh_price(pair, now_ms()).await {
+        return Err(ApiError::StaleOracle(format!(
+            "oracle feed for {pair} is stale"
+        )));
+    }
+    if let Some(session) = instruments.get(pair).and_then(|i| i.session) {
+        let state = session.state_at(now_ms());
+        if state != SessionState::Open && session.off_session == OffSessionPolicy::Reject {
+            return Err(ApiError::Conflict(
+                format!("market {pair} is {state:?}").to_lowercase(),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// The order, if `account_id` may change it: orders placed with a key
+/// belong to that account, the rest to unauthenticated callers. Anyone
+/// else is told it does not exist.
+pub(crate) async fn owned_order(
+    state: &AppState,
+    id: &str,
+    account_id: Option<&str>,
+) -> Result<Order, ApiError> {
+    let order = state
+        .orders
+        .get_by_id(id)
+        .await
+        .map_err(|_| ApiError::NotFound)?;
+    if order.account_id.as_deref() != account_id {
+        return Err(ApiError::NotFound);
+    }
+    Ok(order)
+}
+
+pub(crate) fn require_active(order: &Order) -> Result<(), ApiError> {
+    if order.status.is_active() {
+        return Ok(());
+    }
+    Err(ApiError::InvalidTransition(
+        format!("order is {:?}", order.status).to_lowercase(),
+    ))
+}
+
+/// Reprices or resizes an active order; shared by `PATCH /orders/{id}` and
+/// the order websocket. Like placement, refused in cancel-only mode.
+pub(crate) async fn amend_order(
+    state: &AppState,
+    instruments: &InstrumentRegistry,
+    oracle: &OracleCache,
+    id: &str,
+    payload: AmendPayload,
+    account_id: Option<&str>,
+) -> Result<Order, ApiError> {
+    let order = owned_order(state, id, account_id).await?;
+    require_active(&order)?;
+    accepting_orders(oracle)?;
+    if payload.price.is_none() && payload.quantity.is_none() {
+        return Err(ApiError::BadRequest(
+            "nothing to amend: set price and/or quantity".into(),
+        ));
+    }
+    let instrument = instruments.get(&order.pair);
+    let price = new_price(&order, instrument.as_ref(), payload.price)?;
+    let quantity = match order.quantity_quote {
+        Some(_) if payload.quantity.is_some() => {
+            return Err(ApiError::BadRequest(
+                "the order is sized by quantity_quote; amend its price".into(),
+            ))
+        }
+        // Re-estimated at the new price until the engine fixes it.
+        Some(q) if order.fills.is_empty() => {
+            let scale = instrument.as_ref().map_or(DEFAULT_SCALE, |i| i.qty_scale);
+            base_for_quote(q, price, scale)
+        }
+        _ => payload.quantity.unwrap_or(order.quantity),
+    };
+    if quantity <= order.filled_quantity {
+        return Err(ApiError::BadRequest(format!(
+            "quantity must exceed the filled {}",
+            order.filled_quantity
+        )));
+    }
+    if let Some(instrument) = &instrument {
+        instrument
+            .check_precision(price, quantity)
+            .map_err(ApiError::BadRequest)?;
+    }
+    state
+        .orders
+        .amend(id, price, quantity)
+        .await
+        .map_err(|_| ApiError::Internal)
+}
+
+/// `price` fitted to the pair's tick grid, or the order's own price when
+/// none is given. Market and stop orders have none to change.
+fn new_price(
+    order: &Order,
+    instrument: Option<&instruments::Instrument>,
+    price: Option<Decimal>,
+) -> Result<Decimal, ApiError> {
+    let price = match (price, instrument) {
+        (Some(_), _) if matches!(order.order_type, OrderType::Market | OrderType::Stop) => {
+            return Err(ApiError::BadRequest(
+                "market orders have no price to change".into(),
+            ))
+        }
+        (Some(px), _) if px < Decimal::ZERO => {
+            return Err(ApiError::BadRequest("price must be >= 0".into()))
+        }
+        (Some(px), Some(i)) => i.fit_price(px, &order.side).map_err(ApiError::BadRequest)?,
+        (Some(px), None) => px,
+        (None, _) => order.price,
+    };
+    Ok(price)
+}
+
+/// Cancels an active order and places a copy at a new price and/or
+/// quantity in one store write, so there is no moment with neither or
+/// both live. The copy keeps the order's trigger, execution settings,
+/// account, labels and `cancel_after_ms` deadline. Without a quantity it
+/// takes what was left unfilled. Like placement, refused in cancel-only
+/// mode and subject to the same market checks; the cancel half is held to
+/// the account's cancel limits.
+pub(crate) async fn replace_order(
+    state: &AppState,
+    accounts: &AccountStore,
+    instruments: &InstrumentRegistry,
+    oracle: &OracleCache,
+    id: &str,
+    payload: AmendPayload,
+    account_id: Option<&str>,
+) -> Result<(Order, Order), ApiError> {
+    let order = owned_order(state, id, account_id).await?;
+    require_active(&order)?;
+    may_cancel(accounts, &order)?;
+    let instrument = instruments.get(&order.pair);
+    let price = new_price(&order, instrument.as_ref(), payload.price)?;
+    let (quantity, quantity_quote) = match order.quantity_quote {
+        Some(_) if payload.quantity.is_some() => {
+            return Err(ApiError::BadRequest(
+                "the order is sized by quantity_quote; replace its price".into(),
+            ))
+        }
+        Some(_) if !order.fills.is_empty() => {
+            return Err(ApiError::Conflict(
+                "a partially filled quote-sized order cannot be replaced".into(),
+            ))
+        }
+        Some(q) => {
+            let scale = instrument.as_ref().map_or(DEFAULT_SCALE, |i| i.qty_scale);
+            (base_for_quote(q, price, scale), Some(q))
+        }
+        None => (
+            payload
+                .quantity
+                .unwrap_or(order.quantity - order.filled_quantity),
+            None,
+        ),
+    };
+    if quantity <= Decimal::ZERO {
+        return Err(ApiError::BadRequest("quantity must be > 0".into()));
+    }
+    if let Some(instrument) = &instrument {
+        instrument
+            .check_precision(price, quantity)
+            .map_err(ApiError::BadRequest)?;
+    }
+    let now = now_ms();
     let new = NewOrder {
-        pair: payload.pair.clone(),
-        side: payload.side.clone(),
-        price: payload.price,
-        quantity: payload.quantity,
+        price,
+        quantity,
+        quantity_quote,
+        cancel_after_ms: order
+            .cancel_after_ms
+            .map(|ms| (order.created.saturating_add(ms) - now).max(1)),
+        ..order.to_new()
     };
-    let created = state
+    check_risk(instruments, oracle, &new).await?;
+    match state.orders.replace(id, new).await {
+        Ok(both) => Ok(both),
+        // Filled or cancelled since it was read.
+        Err(_) => {
+            require_active(&owned_order(state, id, account_id).await?)?;
+            Err(ApiError::Internal)
+        }
+    }
+}
+
+/// Cancels an active order at its owner's request; shared by
+/// `POST /orders/{id}/cancel` and the order websocket.
+pub(crate) async fn cancel_order(
+    state: &AppState,
+    accounts: &AccountStore,
+    id: &str,
+    account_id: Option<&str>,
+) -> Result<Order, ApiError> {
+    let order = owned_order(state, id, account_id).await?;
+    require_active(&order)?;
+    may_cancel(accounts, &order)?;
+    state
         .orders
-        .create(new)
+        .cancel(id, CANCEL_BY_OWNER)
         .await
-        .map_err(|_| ApiError::Internal)?;
-    Ok(HttpResponse::Created().json(OrderResponse(created)))
+        .map_err(|_| ApiError::Internal)
+}
+
+pub async fn amend(
+    req: HttpRequest,
+    state: web::Data<AppState>,
+    instruments: web::Data<InstrumentRegistry>,
+    oracle: web::Data<OracleCache>,
+    accounts: web::Data<AccountStore>,
+    path: web::Path<String>,
+    payload: web::Json<AmendPayload>,
+) -> Result<HttpResponse, ApiError> {
+    let account = maybe_authenticate(&req, &accounts)?;
+    let amended = amend_order(
+        &state,
+        &instruments,
+        &oracle,
+        &path.into_inner(),
+        payload.into_inner(),
+        account.as_ref().map(|a| a.id.as_str()),
+    )
+    .await?;
+    Ok(HttpResponse::Ok().json(OrderResponse(amended)))
 }
 
+pub async fn replace(
+    req: HttpRequest,
+    state: web::Data<AppState>,
+    instruments: web::Data<InstrumentRegistry>,
+    oracle: web::Data<OracleCache>,
+    accounts: web::Data<AccountStore>,
+    path: web::Path<String>,
+    payload: web::Json<AmendPayload>,
+) -> Result<HttpResponse, ApiError> {
+    let account = maybe_authenticate(&req, &accounts)?;
+    let (replaced, order) = replace_order(
+        &state,
+        &accounts,
+        &instruments,
+        &oracle,
+        &path.into_inner(),
+        payload.into_inner(),
+        account.as_ref().map(|a| a.id.as_str()),
+    )
+    .await?;
+    Ok(HttpResponse::Created().json(ReplaceResponse { replaced, order }))
+}
+
+pub async fn cancel(
+    req: HttpRequest,
+    state: web::Data<AppState>,
+    accounts: web::Data<AccountStore>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let account = maybe_authenticate(&req, &accounts)?;
+    let cancelled = cancel_order(
+        &state,
+        &accounts,
+        &path.into_inner(),
+        account.as_ref().map(|a| a.id.as_str()),
+    )
+    .await?;
+    Ok(HttpResponse::Ok().json(OrderResponse(cancelled)))
+}
+
+fn list_query(
+    instruments: &InstrumentRegistry,
+    q: &ListQuery,
+) -> Result<ListOrdersQuery, ApiError> {
+    let pair = q
+        .pair
+        .as_deref()
+        .map(|p| instruments.normalize(p))
+        .transpose()
+        .map_err(|e| ApiError::BadRequest(e.to_string()))?;
+    Ok(ListOrdersQuery {
+        pair,
+        status: q.status.clone(),
+        limit: q.limit,
+        offset: q.offset,
+    })
+}
+
+/// Same filters as `list_orders`, written as NDJSON while the repository
+/// produces them. A failure mid-stream ends it with an `{"error": ...}`
+/// line.
+pub async fn stream_orders(
+    state: web::Data<AppState>,
+    instruments: web::Data<InstrumentRegistry>,
+    q: web::Query<ListQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let query = list_query(&instruments, &q)?;
+    let lines = state.reads.clone().stream(query).scan(false, |failed, r| {
+        if *failed {
+            return futures_util::future::ready(None);
+        }
+        let mut line = match r {
+            Ok(o) => serde_json::to_vec(&o).unwrap_or_default(),
+            Err(e) => {
+                tracing::error!(err = %e, "order stream failed");
+                *failed = true;
+                serde_json::to_vec(&serde_json::json!({ "error": "internal" })).unwrap_or_default()
+            }
+        };
+        line.push(b'\n');
+        futures_util::future::ready(Some(Ok::<_, actix_web::Error>(web::Bytes::from(line))))
+    });
+    Ok(read_response(&state)
+        .content_type("application/x-ndjson")
+        .streaming(lines))
+}
+
+/// Active-status queries are answered from the open orders view when it is
+/// live; everything else goes to the read handle.
 pub async fn list_orders(
     state: web::Data<AppState>,
+    instruments: web::Data<InstrumentRegistry>,
+    view: web::Data<OpenOrdersView>,
     q: web::Query<ListQuery>,
 ) -> Result<HttpResponse, ApiError> {
+    let query = list_query(&instruments, &q)?;
+    if view.is_live() && q.status.as_ref().is_some_and(|s| s.is_active()) {
+        return Ok(HttpResponse::Ok().json(view.list(&query)));
+    }
     let items = state
-        .orders
-        .list(ListOrdersQuery {
-            pair: q.pair.clone(),
-            status: q.status.clone(),
-            limit: q.limit,
-            offset: q.offset,
-        })
+        .reads
+        .list(query)
         .await
         .map_err(|_| ApiError::Internal)?;
-    Ok(HttpResponse::Ok().json(items))
+    Ok(read_response(&state).json(items))
+}
+
+/// Orders by ID prefix, tag and `key:value` metadata, best match first.
+pub async fn search_orders(
+    state: web::Data<AppState>,
+    index: web::Data<OrderSearch>,
+    q: web::Query<SearchParams>,
+) -> Result<HttpResponse, ApiError> {
+    let query = SearchQuery::parse(&q.q).map_err(ApiError::BadRequest)?;
+    let limit = q
+        .limit
+        .unwrap_or(DEFAULT_SEARCH_LIMIT)
+        .clamp(1, MAX_SEARCH_LIMIT);
+    let mut items = Vec::new();
+    for hit in index.search(&query, limit) {
+        // Deleted between the lookup and here.
+        if let Ok(order) = state.reads.get_by_id(&hit.order_id).await {
+            items.push(SearchResult {
+                score: hit.score,
+                order,
+            });
+        }
+    }
+    Ok(read_response(&state).json(items))
 }
 
+/// With `as_of`, returns the order as the journal recorded it at that time.
 pub async fn get_order(
     state: web::Data<AppState>,
+    journal: web::Data<Journal>,
     path: web::Path<String>,
+    q: web::Query<AsOfQuery>,
 ) -> Result<HttpResponse, ApiError> {
     let id = path.into_inner();
+    if let Some(as_of) = q.as_of {
+        let order = journal
+            .order_as_of(&id, as_of)
+            .map_err(ApiError::BadRequest)?
+            .ok_or(ApiError::NotFound)?;
+        return Ok(HttpResponse::Ok().json(OrderResponse(order)));
+    }
     let order = state
-        .orders
+        .reads
         .get_by_id(&id)
         .await
         .map_err(|_| ApiError::NotFound)?;
-    Ok(HttpResponse::Ok().json(OrderResponse(order)))
+    Ok(read_response(&state).json(OrderResponse(order)))
 }
 
-pub async fn update_status(
+/// The order's callback deliveries, oldest first; only its owner sees them.
+pub async fn callback_deliveries(
+    req: HttpRequest,
     state: web::Data<AppState>,
+    accounts: web::Data<AccountStore>,
+    callbacks: web::Data<OrderCallbacks>,
     path: web::Path<String>,
-    payload: web::Json<UpdateStatusPayload>,
 ) -> Result<HttpResponse, ApiError> {
-    let id = path.into_inner();
-    let updated = state
-        .orders
-        .set_status(&id, payload.status.clone())
-        .await
-        .map_err(|_| ApiError::NotFound)?;
-    Ok(HttpResponse::Ok().json(OrderResponse(updated)))
+    let account_id = maybe_authenticate(&req, &accounts)?.map(|a| a.id);
+    let order = owned_order(&state, &path, account_id.as_deref()).await?;
+    Ok(HttpResponse::Ok().json(callbacks.deliveries(&order.id)))
 }
 
+/// `DELETE /orders/{id}`: the same owner cancel as `POST
+/// /orders/{id}/cancel`, answered with no body. The order is kept.
 pub async fn delete_order(
+    req: HttpRequest,
     state: web::Data<AppState>,
+    accounts: web::Data<AccountStore>,
     path: web::Path<String>,
 ) -> Result<HttpResponse, ApiError> {
-    let id = path.into_inner();
-    state
-        .orders
-        .delete(&id)
-        .await
-        .map_err(|_| ApiError::NotFound)?;
+    let account = maybe_authenticate(&req, &accounts)?;
+    cancel_order(
+        &state,
+        &accounts,
+        &path.into_inner(),
+        account.as_ref().map(|a| a.id.as_str()),
+    )
+    .await?;
     Ok(HttpResponse::NoContent().finish())
 }
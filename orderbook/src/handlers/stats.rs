@@ -0,0 +1,68 @@
+use actix_web::{web, HttpResponse};
+use serde::Deserialize;
+
+use crate::errors::ApiError;
+use crate::instruments::InstrumentRegistry;
+use crate::repositories::stats::{FillQuality, TradingStats, TriggerLatency};
+use crate::utils::now_ms;
+
+#[derive(Debug, Deserialize)]
+pub struct StatsQuery {
+    pub pair: Option<String>,
+}
+
+pub async fn get_stats(
+    instruments: web::Data<InstrumentRegistry>,
+    stats: web::Data<TradingStats>,
+    q: web::Query<StatsQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let pair = q
+        .pair
+        .as_deref()
+        .map(|p| instruments.normalize(p))
+        .transpose()
+        .map_err(|e| ApiError::BadRequest(e.to_string()))?;
+    Ok(HttpResponse::Ok().json(stats.report(pair.as_deref(), now_ms())))
+}
+
+pub async fn time_to_trigger(
+    instruments: web::Data<InstrumentRegistry>,
+    latency: web::Data<TriggerLatency>,
+    q: web::Query<StatsQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let pair = q
+        .pair
+        .as_deref()
+        .map(|p| instruments.normalize(p))
+        .transpose()
+        .map_err(|e| ApiError::BadRequest(e.to_string()))?;
+    Ok(HttpResponse::Ok().json(latency.distributions(pair.as_deref())))
+}
+
+pub async fn fill_latency(
+    instruments: web::Data<InstrumentRegistry>,
+    latency: web::Data<TriggerLatency>,
+    q: web::Query<StatsQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let pair = q
+        .pair
+        .as_deref()
+        .map(|p| instruments.normalize(p))
+        .transpose()
+        .map_err(|e| ApiError::BadRequest(e.to_string()))?;
+    Ok(HttpResponse::Ok().json(latency.fill_latency(pair.as_deref())))
+}
+
+pub async fn slippage(
+    instruments: web::Data<InstrumentRegistry>,
+    quality: web::Data<FillQuality>,
+    q: web::Query<StatsQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let pair = q
+        .pair
+        .as_deref()
+        .map(|p| instruments.normalize(p))
+        .transpose()
+        .map_err(|e| ApiError::BadRequest(e.to_string()))?;
+    Ok(HttpResponse::Ok().json(quality.distributions(pair.as_deref())))
+}
@@ -0,0 +1,109 @@
+use actix_web::{web, HttpResponse};
+use rust_decimal::Decimal;
+use serde::Deserialize;
+
+use crate::entities::template::NewTemplate;
+use crate::errors::ApiError;
+use crate::handlers::orders::{place_order, CreateOrderPayload};
+use crate::instruments::InstrumentRegistry;
+use crate::metrics::placement::PlacementTimings;
+use crate::oracle_service::OracleCache;
+use crate::repositories::templates::TemplateStore;
+use crate::state::AppState;
+
+#[derive(Debug, Deserialize)]
+pub struct ListTemplatesQuery {
+    pub tag: Option<String>,
+}
+
+/// Per-instantiation overrides; anything not given comes from the template.
+#[derive(Debug, Deserialize)]
+pub struct InstantiateQuery {
+    pub price: Option<Decimal>,
+    pub quantity: Option<Decimal>,
+}
+
+pub async fn create_template(
+    instruments: web::Data<InstrumentRegistry>,
+    oracle: web::Data<OracleCache>,
+    templates: web::Data<TemplateStore>,
+    payload: web::Json<NewTemplate>,
+) -> Result<HttpResponse, ApiError> {
+    let mut new = payload.into_inner();
+    new.pair = instruments
+        .normalize(&new.pair)
+        .map_err(|e| ApiError::BadRequest(e.to_string()))?;
+    new.condition.validate().map_err(ApiError::BadRequest)?;
+    oracle
+        .check_source(new.price_source.as_deref())
+        .map_err(ApiError::BadRequest)?;
+    Ok(HttpResponse::Created().json(templates.create(new).await))
+}
+
+pub async fn list_templates(
+    templates: web::Data<TemplateStore>,
+    q: web::Query<ListTemplatesQuery>,
+) -> Result<HttpResponse, ApiError> {
+    Ok(HttpResponse::Ok().json(templates.list(q.tag.as_deref()).await))
+}
+
+pub async fn get_template(
+    templates: web::Data<TemplateStore>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let t = templates.get(&path).await.ok_or(ApiError::NotFound)?;
+    Ok(HttpResponse::Ok().json(t))
+}
+
+pub async fn delete_template(
+    templates: web::Data<TemplateStore>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    if !templates.delete(&path).await {
+        return Err(ApiError::NotFound);
+    }
+    Ok(HttpResponse::NoContent().finish())
+}
+
+pub async fn instantiate_template(
+    state: web::Data<AppState>,
+    instruments: web::Data<InstrumentRegistry>,
+    oracle: web::Data<OracleCache>,
+    timings: web::Data<PlacementTimings>,
+    templates: web::Data<TemplateStore>,
+    path: web::Path<String>,
+    q: web::Query<InstantiateQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let t = templates.get(&path).await.ok_or(ApiError::NotFound)?;
+    let quantity = q
+        .quantity
+        .or(t.quantity)
+        .ok_or_else(|| ApiError::BadRequest("template has no quantity; pass ?quantity=".into()))?;
+    let payload = CreateOrderPayload {
+        pair: t.pair,
+        side: t.side,
+        order_type: t.order_type,
+        price: q.price.or(t.price),
+        quantity,
+        quantity_quote: None,
+        cancel_after_ms: None,
+        valid_for_after_armed_ms: None,
+        expires_at: None,
+        trigger_price_type: t.trigger_price_type,
+        price_source: t.price_source,
+        condition: t.condition,
+        sustained_for_ms: t.sustained_for_ms,
+        time_in_force: t.time_in_force,
+        max_slippage_bps: t.max_slippage_bps,
+        exec_pricing: None,
+        tags: Vec::new(),
+        // Lets `template:<id>` find what a template placed.
+        metadata: [("template".to_string(), t.id)].into(),
+        callback_url: None,
+        callback_template: None,
+        trigger_price: t.trigger_price,
+        trailing: t.trailing,
+    };
+    let created = place_order(&state, &instruments, &oracle, &timings, payload, None).await?;
+    Ok(HttpResponse::Created().json(created))
+}
@@ -0,0 +1,49 @@
+use actix_web::{web, HttpResponse};
+use futures_util::stream;
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::entities::events::DomainEvent;
+use crate::repositories::changes::ChangeStream;
+
+const INDEX_HTML: &str = include_str!("../../static/admin/index.html");
+const APP_JS: &str = include_str!("../../static/admin/app.js");
+
+pub async fn index() -> HttpResponse {
+    HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(INDEX_HTML)
+}
+
+pub async fn app_js() -> HttpResponse {
+    HttpResponse::Ok()
+        .content_type("text/javascript; charset=utf-8")
+        .body(APP_JS)
+}
+
+/// Server-sent events carrying every domain event envelope, as
+/// `event: order` or `event: market`. A consumer that falls behind skips
+/// what it missed rather than stalling writers.
+pub async fn events(changes: web::Data<ChangeStream>) -> HttpResponse {
+    let rx = changes.subscribe();
+    let body = stream::unfold(rx, |mut rx| async move {
+        loop {
+            match rx.recv().await {
+                Ok(envelope) => {
+                    let name = match envelope.event {
+                        DomainEvent::Order(_) => "order",
+                        DomainEvent::Market(_) => "market",
+                    };
+                    let data = serde_json::to_string(&envelope).unwrap_or_default();
+                    let frame = web::Bytes::from(format!("event: {name}\ndata: {data}\n\n"));
+                    return Some((Ok::<_, actix_web::Error>(frame), rx));
+                }
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    });
+    HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header(("Cache-Control", "no-cache"))
+        .streaming(body)
+}
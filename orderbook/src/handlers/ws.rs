@@ -0,0 +1,409 @@
+use std::future::{ready, Ready};
+
+use actix::{Actor, ActorFutureExt, AsyncContext, StreamHandler, WrapFuture};
+use actix_web::dev::Payload;
+use actix_web::{web, FromRequest, HttpRequest, HttpResponse, ResponseError};
+use actix_web_actors::ws;
+use futures_util::stream;
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::accounts::AccountStore;
+use crate::entities::events::{DomainEvent, Envelope};
+use crate::entities::order::Order;
+use crate::errors::ApiError;
+use crate::handlers::accounts::{authenticate, within_limits};
+use crate::handlers::orders::{
+    amend_order, cancel_order, place_order, AmendPayload, CreateOrderPayload,
+};
+use crate::instruments::InstrumentRegistry;
+use crate::metrics::placement::PlacementTimings;
+use crate::oracle_service::OracleCache;
+use crate::repositories::changes::ChangeStream;
+use crate::repositories::tape::{TapeEntry, TradeTape};
+use crate::state::AppState;
+
+/// What the socket may ask for. Each runs the same validation as its REST
+/// counterpart.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum Command {
+    PlaceOrder {
+        order: Box<CreateOrderPayload>,
+    },
+    CancelOrder {
+        order_id: String,
+    },
+    AmendOrder {
+        order_id: String,
+        price: Option<Decimal>,
+        quantity: Option<Decimal>,
+    },
+}
+
+/// A command frame; `id` is echoed on its response so clients can match
+/// them up while several are in flight.
+#[derive(Debug, Deserialize)]
+struct Request {
+    #[serde(default)]
+    id: Value,
+    #[serde(flatten)]
+    command: Command,
+}
+
+/// The handles a command needs, cloned per connection.
+#[derive(Clone)]
+pub struct Handles {
+    state: web::Data<AppState>,
+    instruments: web::Data<InstrumentRegistry>,
+    oracle: web::Data<OracleCache>,
+    timings: web::Data<PlacementTimings>,
+    accounts: web::Data<AccountStore>,
+}
+
+impl FromRequest for Handles {
+    type Error = actix_web::Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        fn data<T: 'static>(req: &HttpRequest) -> Result<web::Data<T>, actix_web::Error> {
+            req.app_data::<web::Data<T>>()
+                .cloned()
+                .ok_or_else(|| ApiError::Internal.into())
+        }
+        ready((|| {
+            Ok(Handles {
+                state: data(req)?,
+                instruments: data(req)?,
+                oracle: data(req)?,
+                timings: data(req)?,
+                accounts: data(req)?,
+            })
+        })())
+    }
+}
+
+/// Answers one text frame for `account_id`. Frames that do not parse get
+/// a 400 response; its `id` is whatever could be read from the frame.
+async fn respond(h: &Handles, account_id: &str, frame: &str) -> String {
+    let parsed: Result<Request, _> = serde_json::from_str(frame);
+    let (id, result) = match parsed {
+        Ok(req) => (req.id, dispatch(h, account_id, req.command).await),
+        Err(e) => {
+            let id = serde_json::from_str::<Value>(frame)
+                .ok()
+                .and_then(|v| v.get("id").cloned())
+                .unwrap_or(Value::Null);
+            (id, Err(ApiError::BadRequest(e.to_string())))
+        }
+    };
+    let body = match result {
+        Ok(order) => json!({"type": "response", "id": id, "ok": true, "order": order}),
+        Err(e) => {
+            let mut body = json!({
+                "type": "response",
+                "id": id,
+                "ok": false,
+                "status": e.status_code().as_u16(),
+                "error": e.to_string(),
+            });
+            if let Some(code) = e.code() {
+                body["code"] = code.into();
+            }
+            body
+        }
+    };
+    body.to_string()
+}
+
+async fn dispatch(h: &Handles, account_id: &str, command: Command) -> Result<Order, ApiError> {
+    match command {
+        Command::PlaceOrder { order } => {
+            within_limits(&h.accounts, account_id, 1, order.notional())?;
+            place_order(
+                &h.state,
+                &h.instruments,
+                &h.oracle,
+                &h.timings,
+                *order,
+                Some(account_id.to_string()),
+            )
+            .await
+        }
+        Command::CancelOrder { order_id } => {
+            cancel_order(&h.state, &h.accounts, &order_id, Some(account_id)).await
+        }
+        Command::AmendOrder {
+            order_id,
+            price,
+            quantity,
+        } => {
+            let payload = AmendPayload { price, quantity };
+            amend_order(
+                &h.state,
+                &h.instruments,
+                &h.oracle,
+                &order_id,
+                payload,
+                Some(account_id),
+            )
+            .await
+        }
+    }
+}
+
+/// One authenticated connection: answers commands and pushes the
+/// account's own order events.
+struct OrderWs {
+    handles: Handles,
+    account_id: String,
+    changes: ChangeStream,
+}
+
+impl Actor for OrderWs {
+    type Context = ws::WebsocketContext<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        // A slow socket skips what it missed rather than stalling writers,
+        // as on the UI event stream.
+        let events = stream::unfold(self.changes.subscribe(), |mut rx| async move {
+            loop {
+                match rx.recv().await {
+                    Ok(e) => return Some((e, rx)),
+                    Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => return None,
+                }
+            }
+        });
+        ctx.add_stream(events);
+    }
+}
+
+impl StreamHandler<Envelope<DomainEvent>> for OrderWs {
+    fn handle(&mut self, envelope: Envelope<DomainEvent>, ctx: &mut Self::Context) {
+        let DomainEvent::Order(event) = &envelope.event else {
+            return;
+        };
+        if event.order().account_id.as_deref() != Some(self.account_id.as_str()) {
+            return;
+        }
+        if let Ok(s) = serde_json::to_string(&envelope) {
+            ctx.text(s);
+        }
+    }
+
+    /// The event feed ending must not close the socket.
+    fn finished(&mut self, _ctx: &mut Self::Context) {}
+}
+
+impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for OrderWs {
+    fn handle(&mut self, msg: Result<ws::Message, ws::ProtocolError>, ctx: &mut Self::Context) {
+        match msg {
+            Ok(ws::Message::Ping(bytes)) => ctx.pong(&bytes),
+            Ok(ws::Message::Close(reason)) => ctx.close(reason),
+            Ok(ws::Message::Text(text)) => {
+                let handles = self.handles.clone();
+                let account_id = self.account_id.clone();
+                let fut = async move { respond(&handles, &account_id, &text).await };
+                ctx.spawn(fut.into_actor(self).map(|reply, _, ctx| ctx.text(reply)));
+            }
+            _ => {}
+        }
+    }
+}
+
+/// `GET /ws/orders`: upgrades an API-key authenticated request to the
+/// order command socket.
+pub async fn orders(
+    req: HttpRequest,
+    body: web::Payload,
+    handles: Handles,
+    changes: web::Data<ChangeStream>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let account = authenticate(&req, &handles.accounts)?;
+    let actor = OrderWs {
+        handles,
+        account_id: account.id,
+        changes: changes.get_ref().clone(),
+    };
+    ws::start(actor, &req, body)
+}
+
+/// Tape entries read in one go while catching up.
+const TAPE_BATCH: usize = 1000;
+
+#[derive(Debug, Deserialize)]
+pub struct TapeStreamQuery {
+    pub from_seq: Option<u64>,
+}
+
+/// One pair's trade tape from `next` on: what is already there, then
+/// entries as they land. A socket that falls behind the live feed reads
+/// the gap back from the tape, so it sees every sequence number once and
+/// in order.
+struct TapeWs {
+    tape: TradeTape,
+    pair: String,
+    next: u64,
+}
+
+impl TapeWs {
+    fn catch_up(&mut self, ctx: &mut ws::WebsocketContext<Self>) {
+        loop {
+            let batch = self.tape.range(&self.pair, self.next, None, TAPE_BATCH);
+            if batch.is_empty() {
+                return;
+            }
+            for e in &batch {
+                self.send(e, ctx);
+            }
+        }
+    }
+
+    fn send(&mut self, e: &TapeEntry, ctx: &mut ws::WebsocketContext<Self>) {
+        if let Ok(s) = serde_json::to_string(e) {
+            ctx.text(s);
+        }
+        self.next = e.seq + 1;
+    }
+}
+
+impl Actor for TapeWs {
+    type Context = ws::WebsocketContext<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        // Subscribed before catching up, so nothing lands in between.
+        // `None` marks a lag.
+        let live = stream::unfold(self.tape.subscribe(), |mut rx| async move {
+            match rx.recv().await {
+                Ok(e) => Some((Some(e), rx)),
+                Err(RecvError::Lagged(_)) => Some((None, rx)),
+                Err(RecvError::Closed) => None,
+            }
+        });
+        self.catch_up(ctx);
+        ctx.add_stream(live);
+    }
+}
+
+impl StreamHandler<Option<TapeEntry>> for TapeWs {
+    fn handle(&mut self, entry: Option<TapeEntry>, ctx: &mut Self::Context) {
+        match entry {
+            Some(e) if e.pair != self.pair || e.seq < self.next => {}
+            Some(e) if e.seq == self.next => self.send(&e, ctx),
+            _ => self.catch_up(ctx),
+        }
+    }
+
+    fn finished(&mut self, _ctx: &mut Self::Context) {}
+}
+
+impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for TapeWs {
+    fn handle(&mut self, msg: Result<ws::Message, ws::ProtocolError>, ctx: &mut Self::Context) {
+        match msg {
+            Ok(ws::Message::Ping(bytes)) => ctx.pong(&bytes),
+            Ok(ws::Message::Close(reason)) => ctx.close(reason),
+            _ => {}
+        }
+    }
+}
+
+/// `GET /markets/{pair}/tape/ws?from_seq=`: upgrades to the pair's trade
+/// tape, from `from_seq` or, without it, from the next fill.
+pub async fn tape(
+    req: HttpRequest,
+    body: web::Payload,
+    instruments: web::Data<InstrumentRegistry>,
+    tape: web::Data<TradeTape>,
+    path: web::Path<String>,
+    q: web::Query<TapeStreamQuery>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let pair = instruments
+        .normalize(&path.into_inner())
+        .map_err(|_| ApiError::NotFound)?;
+    let next = q.from_seq.unwrap_or_else(|| tape.head(&pair) + 1).max(1);
+    let actor = TapeWs {
+        tape: tape.get_ref().clone(),
+        pair,
+        next,
+    };
+    ws::start(actor, &req, body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::accounts::secrets::SecretBox;
+    use crate::repositories::in_memory::InMemoryOrderRepository;
+
+    fn handles() -> Handles {
+        Handles {
+            state: AppState::new(InMemoryOrderRepository::default()),
+            instruments: web::Data::new(InstrumentRegistry::default()),
+            oracle: web::Data::new(OracleCache::default()),
+            timings: web::Data::new(PlacementTimings::default()),
+            accounts: web::Data::new(AccountStore::new(SecretBox::ephemeral())),
+        }
+    }
+
+    async fn call(h: &Handles, account: &str, frame: Value) -> Value {
+        serde_json::from_str(&respond(h, account, &frame.to_string()).await).unwrap()
+    }
+
+    #[tokio::test]
+    async fn commands_are_correlated_and_scoped_to_the_account() {
+        let h = handles();
+        let placed = call(
+            &h,
+            "acc-1",
+            json!({"id": "p1", "op": "place_order", "order": {
+                "pair": "BTC/USDT", "side": "buy", "price": "100", "quantity": "2"
+            }}),
+        )
+        .await;
+        assert_eq!(placed["id"], "p1");
+        assert_eq!(placed["ok"], true);
+        assert_eq!(placed["order"]["account_id"], "acc-1");
+        let order_id = placed["order"]["id"].clone();
+
+        let amended = call(
+            &h,
+            "acc-1",
+            json!({"id": 2, "op": "amend_order", "order_id": order_id, "price": "99.5"}),
+        )
+        .await;
+        assert_eq!(amended["id"], 2);
+        assert_eq!(amended["order"]["price"], "99.5");
+        assert_eq!(amended["order"]["quantity"], "2");
+
+        let foreign = call(
+            &h,
+            "acc-2",
+            json!({"id": "c0", "op": "cancel_order", "order_id": order_id}),
+        )
+        .await;
+        assert_eq!(foreign["ok"], false);
+        assert_eq!(foreign["status"], 404);
+
+        let cancelled = call(
+            &h,
+            "acc-1",
+            json!({"id": "c1", "op": "cancel_order", "order_id": order_id}),
+        )
+        .await;
+        assert_eq!(cancelled["order"]["status"], "cancelled");
+        let again = call(
+            &h,
+            "acc-1",
+            json!({"id": "c2", "op": "amend_order", "order_id": order_id, "quantity": "3"}),
+        )
+        .await;
+        assert_eq!(again["status"], 409);
+
+        let bad = call(&h, "acc-1", json!({"id": "x", "op": "launch"})).await;
+        assert_eq!(bad["id"], "x");
+        assert_eq!(bad["status"], 400);
+    }
+}
@@ -0,0 +1,249 @@
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::Serialize;
+use std::{collections::HashMap, sync::Arc};
+use tokio::sync::RwLock;
+
+use crate::instruments::symbol::Symbol;
+use crate::oracle_service::Tick;
+
+/// Tuning for the smoothed index: `alpha` is the EMA weight of a new tick,
+/// ticks further than `max_deviation_bps` from the current index are treated
+/// as outliers, and the mark price is the last price clamped to
+/// `mark_band_bps` around the index.
+#[derive(Debug, Clone)]
+pub struct IndexConfig {
+    pub alpha: Decimal,
+    pub max_deviation_bps: Decimal,
+    pub max_consecutive_outliers: u32,
+    pub mark_band_bps: Decimal,
+}
+
+impl Default for IndexConfig {
+    fn default() -> Self {
+        Self {
+            alpha: dec!(0.2),
+            max_deviation_bps: dec!(500),
+            max_consecutive_outliers: 5,
+            mark_band_bps: dec!(50),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct IndexPrice {
+    pub pair: String,
+    #[serde(serialize_with = "crate::utils::decimal::serialize")]
+    pub last_price: Decimal,
+    #[serde(serialize_with = "crate::utils::decimal::serialize")]
+    pub index_price: Decimal,
+    #[serde(serialize_with = "crate::utils::decimal::serialize")]
+    pub mark_price: Decimal,
+    #[serde(serialize_with = "crate::utils::decimal::serialize")]
+    pub funding_rate: Decimal,
+    pub samples: u64,
+    pub outliers: u64,
+    pub ts_ms: i64,
+}
+
+#[derive(Debug, Clone)]
+struct IndexState {
+    last: Decimal,
+    ema: Decimal,
+    samples: u64,
+    outliers: u64,
+    consecutive_outliers: u32,
+    ts_ms: i64,
+}
+
+impl IndexState {
+    fn seed(px: Decimal, ts_ms: i64) -> Self {
+        Self {
+            last: px,
+            ema: px,
+            samples: 1,
+            outliers: 0,
+            consecutive_outliers: 0,
+            ts_ms,
+        }
+    }
+
+    /// Folds a tick into the EMA. Returns `false` when the tick was dropped
+    /// as an outlier. A run of outliers longer than the configured limit is
+    /// taken as a genuine regime change and re-seeds the index.
+    fn update(&mut self, cfg: &IndexConfig, px: Decimal, ts_ms: i64) -> bool {
+        self.last = px;
+        self.ts_ms = ts_ms;
+
+        if deviation_bps(px, self.ema) > cfg.max_deviation_bps {
+            self.outliers += 1;
+            self.consecutive_outliers += 1;
+            if self.consecutive_outliers <= cfg.max_consecutive_outliers {
+                return false;
+            }
+            self.ema = px;
+            self.consecutive_outliers = 0;
+            self.samples += 1;
+            return true;
+        }
+
+        self.consecutive_outliers = 0;
+        self.ema = cfg.alpha * px + (Decimal::ONE - cfg.alpha) * self.ema;
+        self.samples += 1;
+        true
+    }
+
+    fn mark(&self, cfg: &IndexConfig) -> Decimal {
+        let band = self.ema * cfg.mark_band_bps / dec!(10000);
+        self.last.clamp(self.ema - band, self.ema + band)
+    }
+
+    fn snapshot(&self, pair: &str, cfg: &IndexConfig) -> IndexPrice {
+        let mark = self.mark(cfg);
+        let funding_rate = if self.ema.is_zero() {
+            Decimal::ZERO
+        } else {
+            (mark - self.ema) / self.ema
+        };
+        IndexPrice {
+            pair: pair.to_string(),
+            last_price: self.last,
+            index_price: self.ema,
+            mark_price: mark,
+            funding_rate,
+            samples: self.samples,
+            outliers: self.outliers,
+            ts_ms: self.ts_ms,
+        }
+    }
+}
+
+fn deviation_bps(px: Decimal, reference: Decimal) -> Decimal {
+    if reference.is_zero() {
+        return Decimal::ZERO;
+    }
+    ((px - reference) / reference).abs() * dec!(10000)
+}
+
+#[derive(Clone, Default)]
+pub struct IndexCache {
+    cfg: Arc<IndexConfig>,
+    inner: Arc<RwLock<HashMap<Symbol, IndexState>>>,
+}
+
+impl IndexCache {
+    pub fn new(cfg: IndexConfig) -> Self {
+        Self {
+            cfg: Arc::new(cfg),
+            inner: Arc::default(),
+        }
+    }
+
+    pub async fn observe(&self, t: &Tick) -> bool {
+        let mut w = self.inner.write().await;
+        match w.get_mut(&t.pair) {
+            Some(state) => state.update(&self.cfg, t.price, t.ts_ms),
+            None => {
+                w.insert(t.pair.clone(), IndexState::seed(t.price, t.ts_ms));
+                true
+            }
+        }
+    }
+
+    /// Carries `from`'s index over to `to` unless `to` has one already.
+    pub async fn rename(&self, from: &str, to: &Symbol) {
+        let mut w = self.inner.write().await;
+        if let Some(state) = w.remove(from) {
+            w.entry(to.clone()).or_insert(state);
+        }
+    }
+
+    pub async fn forget(&self, pair: &str) {
+        self.inner.write().await.remove(pair);
+    }
+
+    pub async fn get(&self, pair: &str) -> Option<IndexPrice> {
+        let r = self.inner.read().await;
+        r.get(pair).map(|s| s.snapshot(pair, &self.cfg))
+    }
+
+    pub async fn all(&self) -> Vec<IndexPrice> {
+        let r = self.inner.read().await;
+        let mut v: Vec<IndexPrice> = r
+            .iter()
+            .map(|(pair, s)| s.snapshot(pair, &self.cfg))
+            .collect();
+        v.sort_by(|a, b| a.pair.cmp(&b.pair));
+        v
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tick(px: Decimal, ts_ms: i64) -> Tick {
+        Tick::new("BTC/USDT", px, ts_ms)
+    }
+
+    #[tokio::test]
+    async fn first_tick_seeds_index() {
+        let idx = IndexCache::default();
+        assert!(idx.observe(&tick(dec!(100), 1)).await);
+        let p = idx.get("BTC/USDT").await.unwrap();
+        assert_eq!(p.index_price, dec!(100));
+        assert_eq!(p.mark_price, dec!(100));
+        assert_eq!(p.funding_rate, Decimal::ZERO);
+        assert_eq!(p.samples, 1);
+    }
+
+    #[tokio::test]
+    async fn ema_smooths_towards_new_prices() {
+        let idx = IndexCache::default();
+        idx.observe(&tick(dec!(100), 1)).await;
+        idx.observe(&tick(dec!(101), 2)).await;
+        let p = idx.get("BTC/USDT").await.unwrap();
+        assert_eq!(p.index_price, dec!(100.2));
+        assert_eq!(p.last_price, dec!(101));
+        assert_eq!(p.samples, 2);
+    }
+
+    #[tokio::test]
+    async fn outliers_are_dropped_then_reseed_after_a_run() {
+        let idx = IndexCache::new(IndexConfig {
+            max_consecutive_outliers: 2,
+            ..IndexConfig::default()
+        });
+        idx.observe(&tick(dec!(100), 1)).await;
+        assert!(!idx.observe(&tick(dec!(200), 2)).await);
+        assert!(!idx.observe(&tick(dec!(200), 3)).await);
+        let p = idx.get("BTC/USDT").await.unwrap();
+        assert_eq!(p.index_price, dec!(100));
+        assert_eq!(p.outliers, 2);
+
+        assert!(idx.observe(&tick(dec!(200), 4)).await);
+        let p = idx.get("BTC/USDT").await.unwrap();
+        assert_eq!(p.index_price, dec!(200));
+    }
+
+    #[tokio::test]
+    async fn mark_is_clamped_to_band_around_index() {
+        let idx = IndexCache::new(IndexConfig {
+            alpha: dec!(0),
+            ..IndexConfig::default()
+        });
+        idx.observe(&tick(dec!(100), 1)).await;
+        idx.observe(&tick(dec!(102), 2)).await;
+        let p = idx.get("BTC/USDT").await.unwrap();
+        assert_eq!(p.index_price, dec!(100));
+        assert_eq!(p.mark_price, dec!(100.5));
+        assert_eq!(p.funding_rate, dec!(0.005));
+    }
+
+    #[tokio::test]
+    async fn unknown_pair_is_none() {
+        let idx = IndexCache::default();
+        assert!(idx.get("ETH/USDT").await.is_none());
+        assert!(idx.all().await.is_empty());
+    }
+}
@@ -0,0 +1,190 @@
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::Serialize;
+
+use crate::entities::order::OrderSide;
+
+/// Quantity resting `bps` away from mid on each side of the simulated book.
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct LiquidityLevel {
+    pub bps: Decimal,
+    pub quantity: Decimal,
+}
+
+/// Per-pair synthetic depth used where there is no real counterparty book.
+/// Levels are kept sorted by distance from mid.
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct LiquidityCurve {
+    pub levels: Vec<LiquidityLevel>,
+}
+
+impl Default for LiquidityCurve {
+    fn default() -> Self {
+        Self::new(vec![
+            LiquidityLevel {
+                bps: dec!(5),
+                quantity: dec!(1),
+            },
+            LiquidityLevel {
+                bps: dec!(10),
+                quantity: dec!(5),
+            },
+            LiquidityLevel {
+                bps: dec!(25),
+                quantity: dec!(20),
+            },
+            LiquidityLevel {
+                bps: dec!(50),
+                quantity: dec!(100),
+            },
+        ])
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SimulatedFill {
+    pub price: Decimal,
+    pub quantity: Decimal,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Sweep {
+    pub fills: Vec<SimulatedFill>,
+    pub filled: Decimal,
+}
+
+impl Sweep {
+    pub fn vwap(&self) -> Option<Decimal> {
+        if self.filled.is_zero() {
+            return None;
+        }
+        let notional: Decimal = self.fills.iter().map(|f| f.price * f.quantity).sum();
+        Some(notional / self.filled)
+    }
+}
+
+impl LiquidityCurve {
+    pub fn new(mut levels: Vec<LiquidityLevel>) -> Self {
+        levels.sort_by_key(|l| l.bps);
+        Self { levels }
+    }
+
+    /// Parses `bps:qty` pairs, e.g. `5:1,10:5,25:20`.
+    pub fn parse(spec: &str) -> Option<Self> {
+        let levels = spec
+            .split(',')
+            .map(|lvl| {
+                let (bps, qty) = lvl.split_once(':')?;
+                Some(LiquidityLevel {
+                    bps: bps.trim().parse().ok()?,
+                    quantity: qty.trim().parse().ok()?,
+                })
+            })
+            .collect::<Option<Vec<_>>>()?;
+        if levels.is_empty() {
+            return None;
+        }
+        Some(Self::new(levels))
+    }
+
+    /// Walks the simulated book from `mid` for up to `quantity`, never going
+    /// further than `max_bps` away. Buys lift levels above mid, sells hit
+    /// levels below it.
+    pub fn sweep(
+        &self,
+        side: &OrderSide,
+        mid: Decimal,
+        quantity: Decimal,
+        max_bps: Decimal,
+    ) -> Sweep {
+        let mut out = Sweep::default();
+        for lvl in self.levels.iter().take_while(|l| l.bps <= max_bps) {
+            let remaining = quantity - out.filled;
+            if remaining <= Decimal::ZERO {
+                break;
+            }
+            if lvl.quantity.is_zero() {
+                continue;
+            }
+            let offset = mid * lvl.bps / dec!(10000);
+            let price = match side {
+                OrderSide::Buy => mid + offset,
+                OrderSide::Sell => mid - offset,
+            };
+            let take = remaining.min(lvl.quantity);
+            out.fills.push(SimulatedFill {
+                price,
+                quantity: take,
+            });
+            out.filled += take;
+        }
+        out
+    }
+
+    /// Takes `quantity` off the levels nearest mid, as a sweep that filled
+    /// it did, so later sweeps start further out.
+    pub fn consume(&mut self, mut quantity: Decimal) {
+        for lvl in &mut self.levels {
+            let take = quantity.min(lvl.quantity);
+            lvl.quantity -= take;
+            quantity -= take;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_sorts_levels() {
+        let c = LiquidityCurve::parse("10:5, 5:1").unwrap();
+        assert_eq!(c.levels[0].bps, dec!(5));
+        assert!(LiquidityCurve::parse("5").is_none());
+        assert!(LiquidityCurve::parse("").is_none());
+    }
+
+    #[test]
+    fn buy_sweep_walks_up_the_curve() {
+        let c = LiquidityCurve::parse("10:1,20:2").unwrap();
+        let s = c.sweep(&OrderSide::Buy, dec!(100), dec!(2), dec!(50));
+        assert_eq!(s.filled, dec!(2));
+        assert_eq!(
+            s.fills,
+            vec![
+                SimulatedFill {
+                    price: dec!(100.1),
+                    quantity: dec!(1)
+                },
+                SimulatedFill {
+                    price: dec!(100.2),
+                    quantity: dec!(1)
+                },
+            ]
+        );
+        assert_eq!(s.vwap(), Some(dec!(100.15)));
+    }
+
+    #[test]
+    fn consumed_depth_pushes_the_next_sweep_out() {
+        let mut c = LiquidityCurve::parse("10:1,20:2").unwrap();
+        c.consume(dec!(1.5));
+        let s = c.sweep(&OrderSide::Buy, dec!(100), dec!(2), dec!(50));
+        assert_eq!(s.filled, dec!(1.5));
+        assert_eq!(
+            s.fills,
+            vec![SimulatedFill {
+                price: dec!(100.2),
+                quantity: dec!(1.5)
+            }]
+        );
+    }
+
+    #[test]
+    fn sell_sweep_respects_slippage_bound() {
+        let c = LiquidityCurve::parse("10:1,20:2").unwrap();
+        let s = c.sweep(&OrderSide::Sell, dec!(100), dec!(2), dec!(15));
+        assert_eq!(s.filled, dec!(1));
+        assert_eq!(s.fills[0].price, dec!(99.9));
+    }
+}
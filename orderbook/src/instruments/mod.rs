@@ -0,0 +1,735 @@
+use rust_decimal::{Decimal, RoundingStrategy};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::sync::{Arc, RwLock};
+
+pub mod liquidity;
+pub mod session;
+pub mod symbol;
+pub mod synthetic;
+
+use crate::entities::order::{ExecPricing, OrderSide};
+use liquidity::LiquidityCurve;
+use session::{MaintenanceWindow, OffSessionPolicy, SessionState, TradingSession};
+use synthetic::Synthetic;
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct Instrument {
+    pub symbol: String,
+    pub base: String,
+    pub quote: String,
+    pub price_scale: u32,
+    pub qty_scale: u32,
+    pub liquidity: Option<LiquidityCurve>,
+    pub session: Option<TradingSession>,
+    /// How price-triggered limit orders that do not choose are priced.
+    pub exec_pricing: ExecPricing,
+    /// The price increment orders must be placed on, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tick_size: Option<Decimal>,
+    pub tick_policy: TickPolicy,
+    /// Set for a pair priced from two others rather than quoted by a feed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub synthetic: Option<Synthetic>,
+}
+
+/// What happens to an order price off the instrument's tick grid: it is
+/// rejected, moved to the nearest tick, or moved to the tick on the
+/// passive side (down for buys, up for sells), so the order never ends up
+/// more aggressive than asked.
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum TickPolicy {
+    #[default]
+    Reject,
+    Nearest,
+    Passive,
+}
+
+impl TickPolicy {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "reject" => Some(Self::Reject),
+            "nearest" => Some(Self::Nearest),
+            "passive" => Some(Self::Passive),
+            _ => None,
+        }
+    }
+}
+
+pub const DEFAULT_SCALE: u32 = 8;
+
+impl Instrument {
+    /// Parses `BASE/QUOTE` with optional `:price_scale:qty_scale` suffixes,
+    /// e.g. `BTC/USDT:2:6`. Missing scales default to [`DEFAULT_SCALE`].
+    pub fn parse(spec: &str) -> Option<Self> {
+        let mut parts = spec.split(':');
+        let (base, quote) = parts.next()?.split_once('/')?;
+        let (base, quote) = (base.trim().to_uppercase(), quote.trim().to_uppercase());
+        if base.is_empty() || quote.is_empty() {
+            return None;
+        }
+        let mut scale = || -> Option<u32> {
+            match parts.next() {
+                Some(p) => p.trim().parse().ok(),
+                None => Some(DEFAULT_SCALE),
+            }
+        };
+        let price_scale = scale()?;
+        let qty_scale = scale()?;
+        Some(Self {
+            symbol: format!("{base}/{quote}"),
+            base,
+            quote,
+            price_scale,
+            qty_scale,
+            liquidity: None,
+            session: None,
+            exec_pricing: ExecPricing::default(),
+            tick_size: None,
+            tick_policy: TickPolicy::default(),
+            synthetic: None,
+        })
+    }
+
+    /// `price` on the tick grid per [`TickPolicy`]; zero (no price) and
+    /// instruments without a tick size pass through.
+    pub fn fit_price(&self, price: Decimal, side: &OrderSide) -> Result<Decimal, String> {
+        let Some(tick) = self.tick_size.filter(|t| *t > Decimal::ZERO) else {
+            return Ok(price);
+        };
+        if price.is_zero() || (price % tick).is_zero() {
+            return Ok(price);
+        }
+        let strategy = match (self.tick_policy, side) {
+            (TickPolicy::Reject, _) => {
+                return Err(format!(
+                    "price {price} is not a multiple of the {} tick size {tick}",
+                    self.symbol
+                ))
+            }
+            (TickPolicy::Nearest, _) => RoundingStrategy::MidpointAwayFromZero,
+            (TickPolicy::Passive, OrderSide::Buy) => RoundingStrategy::ToNegativeInfinity,
+            (TickPolicy::Passive, OrderSide::Sell) => RoundingStrategy::ToPositiveInfinity,
+        };
+        let fitted = (price / tick).round_dp_with_strategy(0, strategy) * tick;
+        if fitted <= Decimal::ZERO {
+            return Err(format!(
+                "price {price} is below the {} tick size {tick}",
+                self.symbol
+            ));
+        }
+        Ok(fitted.normalize())
+    }
+
+    /// Rejects amounts carrying more decimal places than the instrument
+    /// allows; trailing zeros do not count.
+    pub fn check_precision(&self, price: Decimal, quantity: Decimal) -> Result<(), String> {
+        if price.normalize().scale() > self.price_scale {
+            return Err(format!(
+                "price {price} exceeds {} decimal places for {}",
+                self.price_scale, self.symbol
+            ));
+        }
+        if quantity.normalize().scale() > self.qty_scale {
+            return Err(format!(
+                "quantity {quantity} exceeds {} decimal places for {}",
+                self.qty_scale, self.symbol
+            ));
+        }
+        Ok(())
+    }
+
+    /// Quote-currency amounts carry the price's precision.
+    pub fn check_quote_precision(&self, quote: Decimal) -> Result<(), String> {
+        if quote.normalize().scale() > self.price_scale {
+            return Err(format!(
+                "quantity_quote {quote} exceeds {} decimal places for {}",
+                self.price_scale, self.symbol
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// The base quantity `quote` buys at `price`, rounded down to `qty_scale`
+/// places so the spend never exceeds `quote`. Zero for a zero price.
+pub fn base_for_quote(quote: Decimal, price: Decimal, qty_scale: u32) -> Decimal {
+    if price <= Decimal::ZERO {
+        return Decimal::ZERO;
+    }
+    (quote / price).round_dp_with_strategy(qty_scale, RoundingStrategy::ToZero)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownSymbol {
+    pub input: String,
+    pub suggestions: Vec<String>,
+}
+
+impl fmt::Display for UnknownSymbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown symbol '{}'", self.input)?;
+        if !self.suggestions.is_empty() {
+            write!(f, "; did you mean {}?", self.suggestions.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    instruments: BTreeMap<String, Instrument>,
+    lookup: HashMap<String, String>,
+    /// Delisted instruments, kept for reference; nothing resolves to them.
+    archived: BTreeMap<String, Instrument>,
+}
+
+impl Inner {
+    /// `from`'s instrument under the name `to`, if the rename is allowed.
+    fn renamed(&self, from: &str, to: &str) -> Result<Instrument, String> {
+        let old = self
+            .instruments
+            .get(from)
+            .ok_or_else(|| format!("unknown symbol '{from}'"))?;
+        let spec = Instrument::parse(to).ok_or_else(|| format!("'{to}' is not BASE/QUOTE"))?;
+        // Synthetic prices are derived under the names configured at start.
+        let is_leg = self
+            .instruments
+            .values()
+            .filter_map(|i| i.synthetic.as_ref())
+            .any(|s| s.legs.iter().any(|l| l == from));
+        if old.synthetic.is_some() || is_leg {
+            return Err(format!("{from} is or feeds a synthetic pair"));
+        }
+        if spec.symbol == old.symbol {
+            return Err(format!("{from} is already called {to}"));
+        }
+        match self.lookup.get(&compact(&spec.symbol)) {
+            Some(taken) if taken != from => {
+                return Err(format!("{} already names {taken}", spec.symbol))
+            }
+            _ => {}
+        }
+        Ok(Instrument {
+            symbol: spec.symbol,
+            base: spec.base,
+            quote: spec.quote,
+            ..old.clone()
+        })
+    }
+}
+
+/// The set of tradable pairs. Every symbol entering the service (order
+/// payloads, list filters, oracle ticks) goes through [`normalize`] so the
+/// canonical `BASE/QUOTE` string is the only one stored or compared.
+///
+/// [`normalize`]: InstrumentRegistry::normalize
+#[derive(Clone)]
+pub struct InstrumentRegistry {
+    inner: Arc<RwLock<Inner>>,
+}
+
+pub const DEFAULT_INSTRUMENTS: [&str; 3] = ["BTC/USDT", "ETH/USDT", "SOL/USDT"];
+
+impl Default for InstrumentRegistry {
+    fn default() -> Self {
+        let reg = Self::empty();
+        for s in DEFAULT_INSTRUMENTS {
+            let mut i = Instrument::parse(s).expect("valid default instrument");
+            i.liquidity = Some(LiquidityCurve::default());
+            reg.register(i);
+        }
+        reg.alias("XBT/USDT", "BTC/USDT");
+        reg
+    }
+}
+
+/// Uppercases and strips separators so `btc-usdt`, `BTCUSDT` and `BTC/USDT`
+/// share one lookup key.
+fn compact(s: &str) -> String {
+    s.chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_uppercase())
+        .collect()
+}
+
+impl InstrumentRegistry {
+    pub fn empty() -> Self {
+        Self {
+            inner: Arc::new(RwLock::new(Inner::default())),
+        }
+    }
+
+    /// Builds a registry from `INSTRUMENTS` (comma-separated
+    /// `BASE/QUOTE[:price_scale:qty_scale]`)
+    /// and `SYMBOL_ALIASES` (comma-separated `ALIAS=BASE/QUOTE`), falling back
+    /// to the defaults when `INSTRUMENTS` is unset. `LIQUIDITY` sets
+    /// simulated depth as `PAIR=bps:qty,bps:qty;PAIR=...`, `SESSIONS` sets
+    /// trading hours as `PAIR=mon-fri@08:00-16:00;PAIR=...` (with
+    /// `OFF_SESSION_POLICY=queue|reject`) and `MAINTENANCE` adds windows as
+    /// `PAIR=start_ms-end_ms;...`. `EXEC_PRICING` sets the default fill
+    /// pricing as `PAIR=oracle|limit|better_of;...`. `TICK_SIZES` sets
+    /// price increments as `PAIR=0.5;...`, fitted per `TICK_POLICY`
+    /// (`reject`, `nearest` or `passive`). `SYNTHETIC_PAIRS` adds pairs
+    /// priced from two registered ones as
+    /// `ETH/BTC = ETH/USDT / BTC/USDT;PAIR[:scales] = A - B;...`.
+    pub fn from_env() -> Self {
+        let reg = match std::env::var("INSTRUMENTS") {
+            Ok(list) => {
+                let reg = Self::empty();
+                for s in list.split(',') {
+                    match Instrument::parse(s) {
+                        Some(i) => reg.register(i),
+                        None => tracing::warn!(symbol = s, "ignoring malformed instrument"),
+                    }
+                }
+                reg
+            }
+            Err(_) => Self::default(),
+        };
+        if let Ok(aliases) = std::env::var("SYMBOL_ALIASES") {
+            for entry in aliases.split(',') {
+                if let Some((alias, target)) = entry.split_once('=') {
+                    if !reg.alias(alias, target) {
+                        tracing::warn!(alias, target, "alias targets unknown instrument");
+                    }
+                }
+            }
+        }
+        if let Ok(synthetics) = std::env::var("SYNTHETIC_PAIRS") {
+            for entry in synthetics.split(';').filter(|e| !e.trim().is_empty()) {
+                let added = entry
+                    .split_once('=')
+                    .ok_or_else(|| "expected PAIR = A / B".to_string())
+                    .and_then(|(pair, spec)| reg.add_synthetic(pair, spec));
+                if let Err(err) = added {
+                    tracing::warn!(entry, %err, "ignoring synthetic pair");
+                }
+            }
+        }
+        if let Ok(curves) = std::env::var("LIQUIDITY") {
+            for entry in curves.split(';') {
+                let applied = entry
+                    .split_once('=')
+                    .and_then(|(pair, spec)| Some((pair, LiquidityCurve::parse(spec)?)))
+                    .is_some_and(|(pair, curve)| reg.set_liquidity(pair, curve));
+                if !applied {
+                    tracing::warn!(entry, "ignoring liquidity entry");
+                }
+            }
+        }
+        let policy = match std::env::var("OFF_SESSION_POLICY").as_deref() {
+            Ok("reject") => OffSessionPolicy::Reject,
+            _ => OffSessionPolicy::Queue,
+        };
+        if let Ok(sessions) = std::env::var("SESSIONS") {
+            for entry in sessions.split(';') {
+                let applied = entry
+                    .split_once('=')
+                    .and_then(|(pair, spec)| Some((pair, TradingSession::parse(spec)?)))
+                    .is_some_and(|(pair, mut session)| {
+                        session.off_session = policy;
+                        reg.set_session(pair, session)
+                    });
+                if !applied {
+                    tracing::warn!(entry, "ignoring session entry");
+                }
+            }
+        }
+        if let Ok(windows) = std::env::var("MAINTENANCE") {
+            for entry in windows.split(';') {
+                let window = entry.split_once('=').and_then(|(pair, range)| {
+                    let (start, end) = range.split_once('-')?;
+                    Some((
+                        pair,
+                        MaintenanceWindow {
+                            start_ms: start.trim().parse().ok()?,
+                            end_ms: end.trim().parse().ok()?,
+                        },
+                    ))
+                });
+                let applied = window.is_some_and(|(pair, w)| reg.add_maintenance(pair, w));
+                if !applied {
+                    tracing::warn!(entry, "ignoring maintenance entry");
+                }
+            }
+        }
+        if let Ok(pricing) = std::env::var("EXEC_PRICING") {
+            for entry in pricing.split(';') {
+                let applied = entry
+                    .split_once('=')
+                    .and_then(|(pair, p)| Some((pair, ExecPricing::parse(p)?)))
+                    .is_some_and(|(pair, p)| reg.set_exec_pricing(pair, p));
+                if !applied {
+                    tracing::warn!(entry, "ignoring exec pricing entry");
+                }
+            }
+        }
+        let tick_policy = std::env::var("TICK_POLICY")
+            .ok()
+            .and_then(|s| TickPolicy::parse(&s))
+            .unwrap_or_default();
+        if let Ok(ticks) = std::env::var("TICK_SIZES") {
+            for entry in ticks.split(';') {
+                let applied = entry
+                    .split_once('=')
+                    .and_then(|(pair, t)| Some((pair, t.trim().parse::<Decimal>().ok()?)))
+                    .filter(|(_, t)| *t > Decimal::ZERO)
+                    .is_some_and(|(pair, t)| reg.set_tick_size(pair, t, tick_policy));
+                if !applied {
+                    tracing::warn!(entry, "ignoring tick size entry");
+                }
+            }
+        }
+        reg
+    }
+
+    fn update<F: FnOnce(&mut Instrument)>(&self, symbol: &str, f: F) -> bool {
+        let Ok(symbol) = self.normalize(symbol) else {
+            return false;
+        };
+        let mut w = self.inner.write().unwrap();
+        match w.instruments.get_mut(&symbol) {
+            Some(i) => {
+                f(i);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn set_session(&self, symbol: &str, session: TradingSession) -> bool {
+        self.update(symbol, |i| i.session = Some(session))
+    }
+
+    /// Adds a maintenance window, creating an always-open session for
+    /// instruments that had none.
+    pub fn add_maintenance(&self, symbol: &str, window: MaintenanceWindow) -> bool {
+        self.update(symbol, |i| {
+            i.session
+                .get_or_insert_with(TradingSession::default)
+                .maintenance
+                .push(window)
+        })
+    }
+
+    /// Session state of `symbol` at `now_ms`; instruments without a
+    /// configured session trade around the clock.
+    pub fn session_state(&self, symbol: &str, now_ms: i64) -> SessionState {
+        let r = self.inner.read().unwrap();
+        r.instruments
+            .get(symbol)
+            .and_then(|i| i.session.as_ref())
+            .map_or(SessionState::Open, |s| s.state_at(now_ms))
+    }
+
+    pub fn set_exec_pricing(&self, symbol: &str, pricing: ExecPricing) -> bool {
+        self.update(symbol, |i| i.exec_pricing = pricing)
+    }
+
+    pub fn set_tick_size(&self, symbol: &str, tick: Decimal, policy: TickPolicy) -> bool {
+        self.update(symbol, |i| {
+            i.tick_size = Some(tick);
+            i.tick_policy = policy;
+        })
+    }
+
+    pub fn set_liquidity(&self, symbol: &str, curve: LiquidityCurve) -> bool {
+        self.update(symbol, |i| i.liquidity = Some(curve))
+    }
+
+    pub fn register(&self, i: Instrument) {
+        let mut w = self.inner.write().unwrap();
+        w.lookup.insert(compact(&i.symbol), i.symbol.clone());
+        w.instruments.insert(i.symbol.clone(), i);
+    }
+
+    /// Registers `pair` (`BASE/QUOTE[:price_scale:qty_scale]`) as priced
+    /// by `spec` (`A / B` or `A - B`) from two registered, non-synthetic
+    /// pairs.
+    pub fn add_synthetic(&self, pair: &str, spec: &str) -> Result<Instrument, String> {
+        let mut i =
+            Instrument::parse(pair.trim()).ok_or_else(|| format!("'{pair}' is not BASE/QUOTE"))?;
+        let mut s =
+            Synthetic::parse(spec).ok_or_else(|| format!("'{spec}' is not A / B or A - B"))?;
+        if self.normalize(&i.symbol).is_ok() {
+            return Err(format!("{} is already listed", i.symbol));
+        }
+        for leg in &mut s.legs {
+            *leg = self.normalize(leg).map_err(|e| e.to_string())?;
+            if self.get(leg).is_some_and(|l| l.synthetic.is_some()) {
+                return Err(format!("{leg} is synthetic itself"));
+            }
+        }
+        if s.legs[0] == s.legs[1] {
+            return Err("the legs must differ".into());
+        }
+        i.synthetic = Some(s);
+        self.register(i.clone());
+        Ok(i)
+    }
+
+    /// The synthetic pairs, with their legs.
+    pub fn synthetics(&self) -> Vec<Instrument> {
+        let r = self.inner.read().unwrap();
+        r.instruments
+            .values()
+            .filter(|i| i.synthetic.is_some())
+            .cloned()
+            .collect()
+    }
+
+    /// Maps `alias` onto an already registered instrument. Returns `false`
+    /// when the target is unknown.
+    pub fn alias(&self, alias: &str, target: &str) -> bool {
+        let mut w = self.inner.write().unwrap();
+        let Some(canonical) = w.lookup.get(&compact(target)).cloned() else {
+            return false;
+        };
+        w.lookup.insert(compact(alias), canonical);
+        true
+    }
+
+    pub fn normalize(&self, raw: &str) -> Result<String, UnknownSymbol> {
+        let key = compact(raw);
+        let r = self.inner.read().unwrap();
+        if let Some(canonical) = r.lookup.get(&key) {
+            return Ok(canonical.clone());
+        }
+        let mut scored: Vec<(usize, &String)> = r
+            .instruments
+            .keys()
+            .map(|s| (levenshtein(&key, &compact(s)), s))
+            .filter(|(d, _)| *d <= 2)
+            .collect();
+        scored.sort();
+        Err(UnknownSymbol {
+            input: raw.to_string(),
+            suggestions: scored.into_iter().take(3).map(|(_, s)| s.clone()).collect(),
+        })
+    }
+
+    /// The canonical `(from, to)` symbols a rename of `from` to `to` would
+    /// use, or why it is refused.
+    pub fn check_rename(&self, from: &str, to: &str) -> Result<(String, String), String> {
+        let from = self.normalize(from).map_err(|e| e.to_string())?;
+        let r = self.inner.read().unwrap();
+        let renamed = r.renamed(&from, to)?;
+        Ok((from, renamed.symbol))
+    }
+
+    /// Re-registers `from` as `to` with the same settings. Every spelling
+    /// and alias of `from`, and `from` itself, then resolves to `to`, so
+    /// orders and ticks still using the old name keep working.
+    pub fn rename(&self, from: &str, to: &str) -> Result<Instrument, String> {
+        let from = self.normalize(from).map_err(|e| e.to_string())?;
+        let mut w = self.inner.write().unwrap();
+        let renamed = w.renamed(&from, to)?;
+        w.instruments.remove(&from);
+        for target in w.lookup.values_mut().filter(|t| **t == from) {
+            *target = renamed.symbol.clone();
+        }
+        w.lookup
+            .insert(compact(&renamed.symbol), renamed.symbol.clone());
+        w.instruments
+            .insert(renamed.symbol.clone(), renamed.clone());
+        Ok(renamed)
+    }
+
+    /// Moves `symbol` to the archive. It and its aliases stop resolving,
+    /// so orders, list filters and oracle ticks naming it are refused from
+    /// then on.
+    pub fn delist(&self, symbol: &str) -> Result<Instrument, String> {
+        let symbol = self.normalize(symbol).map_err(|e| e.to_string())?;
+        let mut w = self.inner.write().unwrap();
+        let i = w
+            .instruments
+            .remove(&symbol)
+            .ok_or_else(|| format!("unknown symbol '{symbol}'"))?;
+        w.lookup.retain(|_, target| *target != symbol);
+        w.archived.insert(symbol, i.clone());
+        Ok(i)
+    }
+
+    pub fn get(&self, symbol: &str) -> Option<Instrument> {
+        self.inner.read().unwrap().instruments.get(symbol).cloned()
+    }
+
+    pub fn archived(&self, symbol: &str) -> Option<Instrument> {
+        self.inner.read().unwrap().archived.get(symbol).cloned()
+    }
+
+    /// Delisted instruments by name.
+    pub fn list_archived(&self) -> Vec<Instrument> {
+        let r = self.inner.read().unwrap();
+        r.archived.values().cloned().collect()
+    }
+
+    pub fn symbols(&self) -> Vec<String> {
+        self.inner
+            .read()
+            .unwrap()
+            .instruments
+            .keys()
+            .cloned()
+            .collect()
+    }
+}
+
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut cur = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let cost = usize::from(ca != *cb);
+            cur[j + 1] = (prev[j] + cost).min(prev[j + 1] + 1).min(cur[j] + 1);
+        }
+        prev = cur;
+    }
+    prev[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn accepts_common_spellings() {
+        let reg = InstrumentRegistry::default();
+        for raw in ["BTC/USDT", "btc-usdt", "BTCUSDT", "btc_usdt", " btc/usdt "] {
+            assert_eq!(reg.normalize(raw).unwrap(), "BTC/USDT", "input {raw}");
+        }
+    }
+
+    #[test]
+    fn aliases_resolve_to_canonical() {
+        let reg = InstrumentRegistry::default();
+        assert_eq!(reg.normalize("xbt-usdt").unwrap(), "BTC/USDT");
+        assert!(reg.alias("ETHER", "eth/usdt"));
+        assert_eq!(reg.normalize("ether").unwrap(), "ETH/USDT");
+        assert!(!reg.alias("FOO", "FOO/BAR"));
+    }
+
+    #[test]
+    fn synthetic_pairs_need_two_listed_legs() {
+        let reg = InstrumentRegistry::default();
+        let i = reg
+            .add_synthetic("eth/btc:6:4", "eth-usdt / xbt/usdt")
+            .unwrap();
+        assert_eq!((i.price_scale, i.qty_scale), (6, 4));
+        let s = i.synthetic.unwrap();
+        assert_eq!(s.legs, ["ETH/USDT".to_string(), "BTC/USDT".to_string()]);
+        assert_eq!(reg.normalize("ethbtc").unwrap(), "ETH/BTC");
+        assert_eq!(reg.synthetics().len(), 1);
+        assert!(reg.check_rename("BTC/USDT", "XBT/USDT").is_err());
+        assert!(reg.check_rename("ETH/BTC", "ETH/XBT").is_err());
+        for (pair, spec) in [
+            ("ETH/BTC", "ETH/USDT / BTC/USDT"),
+            ("BTC/ETH", "BTC/USDT / NOPE/USDT"),
+            ("BTC/ETH", "ETH/BTC / BTC/USDT"),
+            ("SOL/SOL", "SOL/USDT / SOL/USDT"),
+        ] {
+            assert!(reg.add_synthetic(pair, spec).is_err(), "{pair} = {spec}");
+        }
+    }
+
+    #[test]
+    fn renames_keep_settings_and_old_spellings() {
+        let reg = InstrumentRegistry::default();
+        reg.set_tick_size("BTC/USDT", dec!(0.5), TickPolicy::Nearest);
+        assert_eq!(
+            reg.check_rename("btc-usdt", "xbt/usdt"),
+            Ok(("BTC/USDT".into(), "XBT/USDT".into()))
+        );
+        assert!(reg.check_rename("BTC/USDT", "ETH/USDT").is_err());
+        assert!(reg.check_rename("BTC/USDT", "BTCUSDT").is_err());
+
+        let renamed = reg.rename("BTC/USDT", "XBT/USDT").unwrap();
+        assert_eq!(
+            (renamed.base.as_str(), renamed.tick_size),
+            ("XBT", Some(dec!(0.5)))
+        );
+        assert!(reg.get("BTC/USDT").is_none());
+        for raw in ["BTC/USDT", "btcusdt", "XBT/USDT"] {
+            assert_eq!(reg.normalize(raw).unwrap(), "XBT/USDT", "input {raw}");
+        }
+        assert!(reg.rename("BTC/USDT", "XBT/USDT").is_err());
+    }
+
+    #[test]
+    fn unknown_symbols_come_with_suggestions() {
+        let reg = InstrumentRegistry::default();
+        let err = reg.normalize("BTCUSD").unwrap_err();
+        assert_eq!(err.suggestions, vec!["BTC/USDT".to_string()]);
+        assert_eq!(
+            err.to_string(),
+            "unknown symbol 'BTCUSD'; did you mean BTC/USDT?"
+        );
+
+        let err = reg.normalize("DOGE/JPY").unwrap_err();
+        assert!(err.suggestions.is_empty());
+    }
+
+    #[test]
+    fn precision_is_enforced_per_instrument() {
+        let i = Instrument::parse("BTC/USDT:2:4").unwrap();
+        assert_eq!((i.price_scale, i.qty_scale), (2, 4));
+        assert!(i.check_precision(dec!(100.10), dec!(0.0001)).is_ok());
+        assert!(i.check_precision(dec!(100.001), dec!(1)).is_err());
+        assert!(i.check_precision(dec!(100), dec!(0.00001)).is_err());
+        assert!(Instrument::parse("BTC/USDT:x").is_none());
+        assert_eq!(
+            Instrument::parse("BTC/USDT").unwrap().price_scale,
+            DEFAULT_SCALE
+        );
+    }
+
+    #[test]
+    fn prices_are_fitted_to_the_tick_grid_per_policy() {
+        let mut i = Instrument::parse("BTC/USDT:2:4").unwrap();
+        assert_eq!(i.fit_price(dec!(100.37), &OrderSide::Buy), Ok(dec!(100.37)));
+        i.tick_size = Some(dec!(0.25));
+        assert_eq!(i.fit_price(dec!(100.50), &OrderSide::Buy), Ok(dec!(100.5)));
+        assert_eq!(
+            i.fit_price(Decimal::ZERO, &OrderSide::Buy),
+            Ok(Decimal::ZERO)
+        );
+        assert!(i.fit_price(dec!(100.37), &OrderSide::Buy).is_err());
+
+        i.tick_policy = TickPolicy::Nearest;
+        assert_eq!(i.fit_price(dec!(100.37), &OrderSide::Buy), Ok(dec!(100.25)));
+        assert_eq!(i.fit_price(dec!(100.38), &OrderSide::Sell), Ok(dec!(100.5)));
+
+        i.tick_policy = TickPolicy::Passive;
+        assert_eq!(i.fit_price(dec!(100.49), &OrderSide::Buy), Ok(dec!(100.25)));
+        assert_eq!(i.fit_price(dec!(100.26), &OrderSide::Sell), Ok(dec!(100.5)));
+        assert!(i.fit_price(dec!(0.1), &OrderSide::Buy).is_err());
+    }
+
+    #[test]
+    fn session_state_defaults_to_open_and_follows_config() {
+        let reg = InstrumentRegistry::default();
+        assert_eq!(reg.session_state("BTC/USDT", 0), SessionState::Open);
+        assert!(reg.add_maintenance(
+            "btc-usdt",
+            MaintenanceWindow {
+                start_ms: 0,
+                end_ms: 10
+            }
+        ));
+        assert_eq!(reg.session_state("BTC/USDT", 5), SessionState::Maintenance);
+        assert_eq!(reg.session_state("BTC/USDT", 10), SessionState::Open);
+        assert!(!reg.set_session("FOO/BAR", TradingSession::default()));
+    }
+
+    #[test]
+    fn parse_rejects_malformed_symbols() {
+        assert!(Instrument::parse("BTCUSDT").is_none());
+        assert!(Instrument::parse("/USDT").is_none());
+        assert_eq!(Instrument::parse("eth/btc").unwrap().symbol, "ETH/BTC");
+    }
+}
@@ -0,0 +1,177 @@
+use serde::Serialize;
+
+const MINUTES_PER_DAY: u32 = 24 * 60;
+const DAY_NAMES: [&str; 7] = ["mon", "tue", "wed", "thu", "fri", "sat", "sun"];
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionState {
+    Open,
+    Closed,
+    Maintenance,
+}
+
+/// What order placement does while the session is not open: `Reject` fails
+/// the request, `Queue` accepts it and leaves it dormant until the open.
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum OffSessionPolicy {
+    #[default]
+    Queue,
+    Reject,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct MaintenanceWindow {
+    pub start_ms: i64,
+    pub end_ms: i64,
+}
+
+/// A weekly UTC trading window plus one-off maintenance windows. `days` is
+/// indexed Monday first; `open_minute..close_minute` is the minute-of-day
+/// range the market trades on those days.
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct TradingSession {
+    pub days: [bool; 7],
+    pub open_minute: u32,
+    pub close_minute: u32,
+    pub maintenance: Vec<MaintenanceWindow>,
+    pub off_session: OffSessionPolicy,
+}
+
+impl Default for TradingSession {
+    fn default() -> Self {
+        Self {
+            days: [true; 7],
+            open_minute: 0,
+            close_minute: MINUTES_PER_DAY,
+            maintenance: Vec::new(),
+            off_session: OffSessionPolicy::default(),
+        }
+    }
+}
+
+fn parse_day(s: &str) -> Option<usize> {
+    DAY_NAMES.iter().position(|d| *d == s)
+}
+
+fn parse_hhmm(s: &str) -> Option<u32> {
+    let (h, m) = s.split_once(':')?;
+    let (h, m): (u32, u32) = (h.parse().ok()?, m.parse().ok()?);
+    if m >= 60 || h * 60 + m > MINUTES_PER_DAY {
+        return None;
+    }
+    Some(h * 60 + m)
+}
+
+/// Monday-first weekday and minute-of-day (UTC) for a unix timestamp.
+fn weekday_and_minute(ts_ms: i64) -> (usize, u32) {
+    let minutes = ts_ms.div_euclid(60_000);
+    let days = minutes.div_euclid(MINUTES_PER_DAY as i64);
+    // 1970-01-01 was a Thursday.
+    let weekday = (days + 3).rem_euclid(7) as usize;
+    (weekday, minutes.rem_euclid(MINUTES_PER_DAY as i64) as u32)
+}
+
+impl TradingSession {
+    /// Parses `DAYS@HH:MM-HH:MM` where `DAYS` is `daily`, a range such as
+    /// `mon-fri`, or a list such as `mon,wed,fri`; e.g. `mon-fri@08:00-16:30`.
+    pub fn parse(spec: &str) -> Option<Self> {
+        let (days_spec, hours) = spec.trim().split_once('@')?;
+        let mut days = [false; 7];
+        let days_spec = days_spec.trim().to_ascii_lowercase();
+        if days_spec == "daily" {
+            days = [true; 7];
+        } else if let Some((from, to)) = days_spec.split_once('-') {
+            let (from, to) = (parse_day(from)?, parse_day(to)?);
+            if from > to {
+                return None;
+            }
+            days[from..=to].iter_mut().for_each(|d| *d = true);
+        } else {
+            for d in days_spec.split(',') {
+                days[parse_day(d.trim())?] = true;
+            }
+        }
+        let (open, close) = hours.split_once('-')?;
+        let (open_minute, close_minute) = (parse_hhmm(open.trim())?, parse_hhmm(close.trim())?);
+        if open_minute >= close_minute {
+            return None;
+        }
+        Some(Self {
+            days,
+            open_minute,
+            close_minute,
+            ..Self::default()
+        })
+    }
+
+    pub fn state_at(&self, now_ms: i64) -> SessionState {
+        if self
+            .maintenance
+            .iter()
+            .any(|w| (w.start_ms..w.end_ms).contains(&now_ms))
+        {
+            return SessionState::Maintenance;
+        }
+        let (weekday, minute) = weekday_and_minute(now_ms);
+        if self.days[weekday] && (self.open_minute..self.close_minute).contains(&minute) {
+            SessionState::Open
+        } else {
+            SessionState::Closed
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Monday 2024-01-01 00:00:00 UTC.
+    const MONDAY: i64 = 1_704_067_200_000;
+    const HOUR: i64 = 3_600_000;
+    const DAY: i64 = 24 * HOUR;
+
+    #[test]
+    fn weekday_arithmetic_matches_calendar() {
+        assert_eq!(weekday_and_minute(MONDAY), (0, 0));
+        assert_eq!(weekday_and_minute(MONDAY + 5 * DAY + 90 * 60_000), (5, 90));
+        assert_eq!(weekday_and_minute(0), (3, 0));
+    }
+
+    #[test]
+    fn weekday_session_is_closed_on_weekends_and_off_hours() {
+        let s = TradingSession::parse("mon-fri@08:00-16:30").unwrap();
+        assert_eq!(s.state_at(MONDAY + 9 * HOUR), SessionState::Open);
+        assert_eq!(s.state_at(MONDAY + 7 * HOUR), SessionState::Closed);
+        assert_eq!(s.state_at(MONDAY + 17 * HOUR), SessionState::Closed);
+        assert_eq!(
+            s.state_at(MONDAY + 5 * DAY + 9 * HOUR),
+            SessionState::Closed
+        );
+    }
+
+    #[test]
+    fn maintenance_overrides_open_session() {
+        let mut s = TradingSession::default();
+        s.maintenance.push(MaintenanceWindow {
+            start_ms: MONDAY,
+            end_ms: MONDAY + HOUR,
+        });
+        assert_eq!(s.state_at(MONDAY + 1), SessionState::Maintenance);
+        assert_eq!(s.state_at(MONDAY + HOUR), SessionState::Open);
+    }
+
+    #[test]
+    fn parse_accepts_lists_and_daily() {
+        let s = TradingSession::parse("mon,wed@00:00-24:00").unwrap();
+        assert_eq!(s.days, [true, false, true, false, false, false, false]);
+        assert_eq!(
+            TradingSession::parse("daily@00:00-24:00").unwrap().days,
+            [true; 7]
+        );
+        assert!(TradingSession::parse("fri-mon@00:00-01:00").is_none());
+        assert!(TradingSession::parse("mon@10:00-09:00").is_none());
+        assert!(TradingSession::parse("mon@25:00-26:00").is_none());
+    }
+}
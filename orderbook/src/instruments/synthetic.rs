@@ -0,0 +1,64 @@
+use rust_decimal::Decimal;
+use serde::Serialize;
+
+use crate::entities::order::SpreadMeasure;
+
+/// A pair no feed quotes, priced from two that are: `a / b` for a ratio
+/// (ETH/BTC from ETH/USDT and BTC/USDT), `a - b` for a difference.
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct Synthetic {
+    pub legs: [String; 2],
+    pub measure: SpreadMeasure,
+}
+
+impl Synthetic {
+    /// Parses the right-hand side of a `SYNTHETIC_PAIRS` entry, `A / B` or
+    /// `A - B`. The operator needs spaces around it, since symbols
+    /// contain `/`.
+    pub fn parse(spec: &str) -> Option<Self> {
+        let tokens: Vec<&str> = spec.split_whitespace().collect();
+        let [a, op, b] = tokens[..] else {
+            return None;
+        };
+        let measure = match op {
+            "/" => SpreadMeasure::Ratio,
+            "-" => SpreadMeasure::Difference,
+            _ => return None,
+        };
+        Some(Self {
+            legs: [a.to_string(), b.to_string()],
+            measure,
+        })
+    }
+
+    /// The synthetic price from the legs' prices; `None` for a ratio over
+    /// zero. A bid comes from the first leg's bid and the second's ask,
+    /// and an ask the other way round, as both measures rise with the
+    /// first leg and fall with the second.
+    pub fn price(&self, a: Decimal, b: Decimal) -> Option<Decimal> {
+        match self.measure {
+            SpreadMeasure::Ratio if b.is_zero() => None,
+            SpreadMeasure::Ratio => Some(a / b),
+            SpreadMeasure::Difference => Some(a - b),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn specs_need_two_legs_around_an_operator() {
+        let s = Synthetic::parse(" ETH/USDT / BTC/USDT ").unwrap();
+        assert_eq!(s.legs, ["ETH/USDT".to_string(), "BTC/USDT".to_string()]);
+        assert_eq!(s.price(dec!(3000), dec!(60000)), Some(dec!(0.05)));
+        assert_eq!(s.price(dec!(1), dec!(0)), None);
+        let d = Synthetic::parse("ETH/USDT - ETH/USDC").unwrap();
+        assert_eq!(d.price(dec!(3001), dec!(3000)), Some(dec!(1)));
+        for bad in ["ETH/USDT/BTC/USDT", "ETH/USDT * BTC/USDT", "ETH/USDT /"] {
+            assert!(Synthetic::parse(bad).is_none(), "{bad}");
+        }
+    }
+}
@@ -1,9 +1,18 @@
+pub mod accounts;
+#[cfg(feature = "client")]
+pub mod client;
 pub mod engine;
 pub mod entities;
 pub mod errors;
+pub mod flags;
 pub mod handlers;
+pub mod index;
+pub mod instruments;
+pub mod limits;
+pub mod metrics;
 pub mod oracle_service;
 pub mod repositories;
 pub mod routes;
+pub mod selfcheck;
 pub mod state;
 pub mod utils;
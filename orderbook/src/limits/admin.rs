@@ -0,0 +1,102 @@
+use actix_web::{
+    body::MessageBody,
+    dev::{ServiceRequest, ServiceResponse},
+    http::header::{HeaderValue, AUTHORIZATION, WWW_AUTHENTICATE},
+    middleware::Next,
+    web, Error,
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use std::sync::Arc;
+use subtle::ConstantTimeEq;
+
+use crate::errors::ApiError;
+
+/// Header admin clients send the token in.
+pub const ADMIN_TOKEN_HEADER: &str = "X-Admin-Token";
+
+/// Who may call the `/admin` scope: callers presenting `token`, in
+/// [`ADMIN_TOKEN_HEADER`] or as the password of HTTP Basic auth, which is
+/// what a browser sends for the admin UI. Without a token the scope is
+/// closed to everyone.
+#[derive(Clone, Default)]
+pub struct AdminAuth {
+    token: Option<Arc<str>>,
+}
+
+impl AdminAuth {
+    pub fn new(token: &str) -> Self {
+        Self {
+            token: Some(Arc::from(token)).filter(|t: &Arc<str>| !t.is_empty()),
+        }
+    }
+
+    /// `ADMIN_TOKEN`; closed if it is unset or empty.
+    pub fn from_env() -> Self {
+        std::env::var("ADMIN_TOKEN").map_or_else(|_| Self::default(), |t| Self::new(t.trim()))
+    }
+
+    /// Whether a token is set; without one every request is refused.
+    pub fn is_configured(&self) -> bool {
+        self.token.is_some()
+    }
+
+    fn admits(&self, presented: &str) -> bool {
+        self.token
+            .as_ref()
+            .is_some_and(|t| bool::from(t.as_bytes().ct_eq(presented.as_bytes())))
+    }
+}
+
+/// The token a request presents, if any.
+fn presented(req: &ServiceRequest) -> Option<String> {
+    let h = req.headers();
+    if let Some(t) = h.get(ADMIN_TOKEN_HEADER).and_then(|v| v.to_str().ok()) {
+        return Some(t.trim().to_string());
+    }
+    let basic = h
+        .get(AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())?
+        .strip_prefix("Basic ")?;
+    let decoded = String::from_utf8(STANDARD.decode(basic.trim()).ok()?).ok()?;
+    decoded.split_once(':').map(|(_, pass)| pass.to_string())
+}
+
+/// Applies [`AdminAuth`] to the scope it wraps: **401** with a Basic
+/// challenge unless the request presents the admin token. Refuses
+/// everything when no [`AdminAuth`] is registered.
+pub async fn admin_gate(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse, Error> {
+    let admitted = req
+        .app_data::<web::Data<AdminAuth>>()
+        .zip(presented(&req))
+        .is_some_and(|(auth, token)| auth.admits(&token));
+    if !admitted {
+        let mut resp = req.error_response(ApiError::Unauthorized);
+        resp.headers_mut().insert(
+            WWW_AUTHENTICATE,
+            HeaderValue::from_static("Basic realm=\"orderbook admin\""),
+        );
+        return Ok(resp);
+    }
+    Ok(next.call(req).await?.map_into_boxed_body())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_the_configured_token_is_admitted() {
+        let auth = AdminAuth::new("s3cret");
+        assert!(auth.is_configured());
+        assert!(auth.admits("s3cret"));
+        assert!(!auth.admits("s3cre"));
+        assert!(!auth.admits(""));
+        for closed in [AdminAuth::default(), AdminAuth::new("")] {
+            assert!(!closed.is_configured());
+            assert!(!closed.admits(""));
+        }
+    }
+}
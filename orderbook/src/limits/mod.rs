@@ -0,0 +1,226 @@
+use actix_web::{
+    body::MessageBody,
+    dev::{Payload, ServiceRequest, ServiceResponse},
+    middleware::Next,
+    web, Error, HttpMessage,
+};
+use futures_util::StreamExt;
+use std::time::Duration;
+
+use crate::errors::ApiError;
+
+pub mod admin;
+pub mod public;
+
+/// Bounds on what a single request may ask the service to parse or do.
+#[derive(Debug, Clone, Copy)]
+pub struct Limits {
+    pub max_body_bytes: usize,
+    /// Deepest allowed nesting of JSON objects and arrays.
+    pub max_json_depth: usize,
+    /// Most entries accepted by batch endpoints.
+    pub max_batch: usize,
+    /// Deadline for requests that do not send `x-request-timeout-ms`.
+    pub default_timeout_ms: u64,
+    /// Upper bound on what `x-request-timeout-ms` may ask for.
+    pub max_timeout_ms: u64,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_body_bytes: 256 * 1024,
+            max_json_depth: 16,
+            max_batch: 1_000,
+            default_timeout_ms: 10_000,
+            max_timeout_ms: 30_000,
+        }
+    }
+}
+
+impl Limits {
+    pub fn from_env() -> Self {
+        let d = Self::default();
+        fn var<T: std::str::FromStr>(k: &str, default: T) -> T {
+            std::env::var(k)
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(default)
+        }
+        Self {
+            max_body_bytes: var("MAX_BODY_BYTES", d.max_body_bytes),
+            max_json_depth: var("MAX_JSON_DEPTH", d.max_json_depth),
+            max_batch: var("MAX_BATCH_SIZE", d.max_batch),
+            default_timeout_ms: var("REQUEST_TIMEOUT_MS", d.default_timeout_ms),
+            max_timeout_ms: var("MAX_REQUEST_TIMEOUT_MS", d.max_timeout_ms),
+        }
+    }
+
+    /// The deadline a request asked for, capped at `max_timeout_ms`.
+    pub fn timeout_for(&self, header: Option<&str>) -> Result<u64, ApiError> {
+        let Some(raw) = header else {
+            return Ok(self.default_timeout_ms.min(self.max_timeout_ms));
+        };
+        match raw.trim().parse::<u64>() {
+            Ok(ms) if ms > 0 => Ok(ms.min(self.max_timeout_ms)),
+            _ => Err(ApiError::BadRequest(format!(
+                "{TIMEOUT_HEADER} must be a positive number of milliseconds"
+            ))),
+        }
+    }
+
+    pub fn check_batch(&self, len: usize) -> Result<(), ApiError> {
+        if len > self.max_batch {
+            return Err(ApiError::BadRequest(format!(
+                "batch of {len} exceeds the limit of {}",
+                self.max_batch
+            )));
+        }
+        Ok(())
+    }
+}
+
+pub const TIMEOUT_HEADER: &str = "x-request-timeout-ms";
+
+/// Maximum nesting of `{`/`[` outside string literals. Malformed input is
+/// scanned as far as it goes; the JSON parser rejects it afterwards.
+pub fn json_depth(body: &[u8]) -> usize {
+    let (mut depth, mut max, mut in_str, mut escaped) = (0usize, 0usize, false, false);
+    for &b in body {
+        if in_str {
+            match b {
+                _ if escaped => escaped = false,
+                b'\\' => escaped = true,
+                b'"' => in_str = false,
+                _ => {}
+            }
+            continue;
+        }
+        match b {
+            b'"' => in_str = true,
+            b'{' | b'[' => {
+                depth += 1;
+                max = max.max(depth);
+            }
+            b'}' | b']' => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+    }
+    max
+}
+
+/// Buffers the request body up to `max_body_bytes` and rejects it if too
+/// large or nested too deeply, before any handler deserializes it. Upgrade
+/// requests pass untouched: their "body" is the upgraded connection.
+pub async fn guard_body(
+    mut req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse, Error> {
+    if req.head().upgrade() {
+        return Ok(next.call(req).await?.map_into_boxed_body());
+    }
+    let limits = req
+        .app_data::<web::Data<Limits>>()
+        .map(|l| *l.get_ref())
+        .unwrap_or_default();
+    let mut payload = req.take_payload();
+    let mut body = web::BytesMut::new();
+    while let Some(chunk) = payload.next().await {
+        let chunk = chunk?;
+        if body.len() + chunk.len() > limits.max_body_bytes {
+            return Ok(req.error_response(ApiError::PayloadTooLarge(limits.max_body_bytes)));
+        }
+        body.extend_from_slice(&chunk);
+    }
+    let depth = json_depth(&body);
+    if depth > limits.max_json_depth {
+        return Ok(req.error_response(ApiError::BadRequest(format!(
+            "json nested {depth} deep exceeds the limit of {}",
+            limits.max_json_depth
+        ))));
+    }
+    req.set_payload(Payload::from(body.freeze()));
+    Ok(next.call(req).await?.map_into_boxed_body())
+}
+
+/// Runs the rest of the request, repository calls included, under the
+/// deadline from `x-request-timeout-ms`. Past it the handler is dropped and
+/// the caller gets **504**. Streaming bodies are not covered once their
+/// headers are sent.
+pub async fn deadline(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse, Error> {
+    let limits = req
+        .app_data::<web::Data<Limits>>()
+        .map(|l| *l.get_ref())
+        .unwrap_or_default();
+    let header = req
+        .headers()
+        .get(TIMEOUT_HEADER)
+        .map(|v| v.to_str().unwrap_or(""));
+    let ms = match limits.timeout_for(header) {
+        Ok(ms) => ms,
+        Err(e) => return Ok(req.error_response(e)),
+    };
+    let path = req.path().to_string();
+    match tokio::time::timeout(Duration::from_millis(ms), next.call(req)).await {
+        Ok(res) => Ok(res?.map_into_boxed_body()),
+        Err(_) => {
+            tracing::warn!(%path, timeout_ms = ms, "request deadline exceeded");
+            Err(ApiError::Timeout(ms).into())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{middleware::from_fn, test::TestRequest, App, HttpResponse};
+
+    #[test]
+    fn depth_ignores_brackets_inside_strings() {
+        assert_eq!(json_depth(b"1"), 0);
+        assert_eq!(json_depth(br#"{"a": [1, {"b": 2}], "c": {}}"#), 3);
+        assert_eq!(json_depth(br#"{"s": "[[[{{\"]]"}"#), 1);
+        assert_eq!(json_depth(&[b'['; 10_000]), 10_000);
+    }
+
+    #[actix_web::test]
+    async fn slow_handlers_time_out_with_504() {
+        let app = actix_web::test::init_service(
+            App::new()
+                .wrap(from_fn(deadline))
+                .app_data(web::Data::new(Limits {
+                    max_timeout_ms: 20,
+                    ..Limits::default()
+                }))
+                .route(
+                    "/slow",
+                    web::get().to(|| async {
+                        tokio::time::sleep(Duration::from_millis(200)).await;
+                        HttpResponse::Ok().finish()
+                    }),
+                ),
+        )
+        .await;
+        let call = |timeout: Option<&'static str>| {
+            let mut req = TestRequest::get().uri("/slow");
+            if let Some(t) = timeout {
+                req = req.insert_header((TIMEOUT_HEADER, t));
+            }
+            req.to_request()
+        };
+        // Asking for more than the cap still gets the cap.
+        let status = |r: Result<ServiceResponse, Error>| match r {
+            Ok(res) => res.status(),
+            Err(e) => e.error_response().status(),
+        };
+        let res = actix_web::test::try_call_service(&app, call(Some("60000"))).await;
+        assert_eq!(status(res), 504);
+        let res = actix_web::test::try_call_service(&app, call(None)).await;
+        assert_eq!(status(res), 504);
+        let res = actix_web::test::call_service(&app, call(Some("soon"))).await;
+        assert_eq!(res.status(), 400);
+    }
+}
@@ -0,0 +1,163 @@
+use actix_web::{
+    body::MessageBody,
+    dev::{ServiceRequest, ServiceResponse},
+    middleware::Next,
+    web, Error,
+};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use crate::accounts::AccountStore;
+use crate::errors::ApiError;
+use crate::handlers::accounts::authenticate;
+use crate::utils::now_ms;
+
+const WINDOW_MS: i64 = 60_000;
+/// Clients tracked before windows that ended are dropped.
+const MAX_TRACKED: usize = 10_000;
+
+/// Market data served to anyone; the rest of the non-admin API is order
+/// flow. Health checks and metrics are left alone.
+const PUBLIC_PREFIXES: [&str; 5] = ["/markets", "/book", "/index", "/stats", "/analytics"];
+const ORDER_PREFIXES: [&str; 7] = [
+    "/orders",
+    "/order-groups",
+    "/baskets",
+    "/templates",
+    "/ws/orders",
+    "/account",
+    "/accounts",
+];
+
+/// How a public demo deployment is exposed: with `enabled`, market data
+/// needs no credentials but anonymous callers get `requests_per_min` per
+/// client address, and order endpoints need an API key. Admin routes are
+/// left to [`admin_gate`](super::admin::admin_gate).
+#[derive(Clone)]
+pub struct PublicMode {
+    pub enabled: bool,
+    pub requests_per_min: u32,
+    pub limiter: PublicRateLimiter,
+}
+
+impl Default for PublicMode {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            requests_per_min: 30,
+            limiter: PublicRateLimiter::default(),
+        }
+    }
+}
+
+impl PublicMode {
+    pub fn from_env() -> Self {
+        let d = Self::default();
+        Self {
+            enabled: std::env::var("PUBLIC_READ_ONLY")
+                .is_ok_and(|v| matches!(v.trim(), "1" | "true" | "yes")),
+            requests_per_min: std::env::var("PUBLIC_RATE_LIMIT_PER_MIN")
+                .ok()
+                .and_then(|s| s.trim().parse().ok())
+                .unwrap_or(d.requests_per_min),
+            ..d
+        }
+    }
+}
+
+/// Fixed one-minute request windows per client address.
+#[derive(Clone, Default)]
+pub struct PublicRateLimiter {
+    windows: Arc<Mutex<HashMap<String, (i64, u32)>>>,
+}
+
+impl PublicRateLimiter {
+    /// Counts a request from `client`; the seconds until its window resets
+    /// when it is over `limit`.
+    pub fn check(&self, client: &str, limit: u32, now_ms: i64) -> Result<(), u64> {
+        let start = now_ms - now_ms.rem_euclid(WINDOW_MS);
+        let mut w = self.windows.lock().unwrap();
+        if w.len() >= MAX_TRACKED {
+            w.retain(|_, (s, _)| *s == start);
+        }
+        let slot = w.entry(client.to_string()).or_insert((start, 0));
+        if slot.0 != start {
+            *slot = (start, 0);
+        }
+        if slot.1 >= limit {
+            return Err(((start + WINDOW_MS - now_ms + 999) / 1000) as u64);
+        }
+        slot.1 += 1;
+        Ok(())
+    }
+}
+
+/// Whether the request carries an API key the account store knows. A
+/// made-up or revoked key is metered like no key at all.
+fn has_credentials(req: &ServiceRequest) -> bool {
+    req.app_data::<web::Data<AccountStore>>()
+        .is_some_and(|accounts| authenticate(req.request(), accounts).is_ok())
+}
+
+fn under(path: &str, prefixes: &[&str]) -> bool {
+    prefixes.iter().any(|p| {
+        path.strip_prefix(p)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+    })
+}
+
+/// Applies [`PublicMode`]: rate limits anonymous market-data reads and
+/// turns away order requests without a valid API key. A no-op unless the
+/// mode is enabled.
+pub async fn public_gate(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse, Error> {
+    let Some(mode) = req
+        .app_data::<web::Data<PublicMode>>()
+        .filter(|m| m.enabled)
+        .cloned()
+    else {
+        return Ok(next.call(req).await?.map_into_boxed_body());
+    };
+    let path = req.path();
+    if under(path, &ORDER_PREFIXES) {
+        let Some(accounts) = req.app_data::<web::Data<AccountStore>>() else {
+            return Ok(req.error_response(ApiError::Internal));
+        };
+        if let Err(e) = authenticate(req.request(), accounts) {
+            return Ok(req.error_response(e));
+        }
+    } else if under(path, &PUBLIC_PREFIXES) && !has_credentials(&req) {
+        let client = req
+            .peer_addr()
+            .map_or_else(|| "unknown".to_string(), |a| a.ip().to_string());
+        let checked = mode.limiter.check(&client, mode.requests_per_min, now_ms());
+        if let Err(retry_after) = checked {
+            return Ok(req.error_response(ApiError::RateLimited(retry_after)));
+        }
+    }
+    Ok(next.call(req).await?.map_into_boxed_body())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn windows_reset_each_minute_per_client() {
+        let l = PublicRateLimiter::default();
+        let t = 1_700_000_040_000;
+        assert!(l.check("a", 2, t).is_ok());
+        assert!(l.check("a", 2, t + 1).is_ok());
+        assert_eq!(l.check("a", 2, t + 1_000), Err(59));
+        assert!(l.check("b", 2, t + 1_000).is_ok());
+        assert!(l.check("a", 2, t + 60_000).is_ok());
+        assert!(under("/book/BTC/USDT", &PUBLIC_PREFIXES));
+        assert!(under("/orders", &ORDER_PREFIXES));
+        assert!(under("/order-groups", &ORDER_PREFIXES));
+        assert!(under("/order-groups/g1", &ORDER_PREFIXES));
+        assert!(!under("/bookmarks", &PUBLIC_PREFIXES));
+        assert!(!under("/admin/orders/x/cancel", &ORDER_PREFIXES));
+    }
+}
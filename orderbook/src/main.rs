@@ -1,20 +1,55 @@
-use actix_web::{middleware::Logger, web, App, HttpServer};
+use actix_web::{
+    middleware::{from_fn, Logger},
+    web, App, HttpServer,
+};
 use dotenvy::dotenv;
 use tracing_subscriber::{fmt::SubscriberBuilder, EnvFilter};
 
-use crate::engine::start_matchers;
-use crate::oracle_service::{OracleCache, OracleWsClient};
-use crate::repositories::in_memory::InMemoryOrderRepository;
-
-pub mod engine;
-pub mod entities;
-pub mod errors;
-pub mod handlers;
-pub mod oracle_service;
-pub mod repositories;
-pub mod routes;
-pub mod state;
-pub mod utils;
+use conditional_orderbook::accounts::purge::AccountPurger;
+use conditional_orderbook::accounts::statements::StatementLedger;
+use conditional_orderbook::accounts::usage::{AccountLimits, AccountUsage};
+use conditional_orderbook::accounts::{secrets::SecretBox, AccountStore};
+use conditional_orderbook::engine::baskets::{start_basket_watcher, BasketMarkets};
+use conditional_orderbook::engine::bots::{start_liquidity_bots, BotConfig, LiquidityBots};
+use conditional_orderbook::engine::callbacks::{start_callbacks, OrderCallbacks};
+use conditional_orderbook::engine::deadletter::DeadLetters;
+use conditional_orderbook::engine::delist::PairDelister;
+use conditional_orderbook::engine::downtime::{MissedTriggerPolicy, MissedTriggers};
+use conditional_orderbook::engine::expiry::start_expiry_sweep;
+use conditional_orderbook::engine::groups::{start_group_budgets, OrderGroups};
+use conditional_orderbook::engine::halts::{HaltRegistry, ResumePolicy};
+use conditional_orderbook::engine::housekeeping::{start_housekeeping, Housekeeper};
+use conditional_orderbook::engine::interpolate::Interpolation;
+use conditional_orderbook::engine::ledger::ExecutionLedger;
+use conditional_orderbook::engine::orphans::start_orphan_sweep;
+use conditional_orderbook::engine::rename::PairRenamer;
+use conditional_orderbook::engine::retry::{ErrorBudgetConfig, RetryPolicy};
+use conditional_orderbook::engine::throttle::FillThrottle;
+use conditional_orderbook::engine::timers::{start_order_timers, OrderTimers};
+use conditional_orderbook::engine::{start_matchers, Controls, EngineConfig, Matchers};
+use conditional_orderbook::flags::FeatureFlags;
+use conditional_orderbook::instruments::InstrumentRegistry;
+use conditional_orderbook::limits::admin::AdminAuth;
+use conditional_orderbook::limits::public::{public_gate, PublicMode};
+use conditional_orderbook::limits::{deadline, guard_body, Limits};
+use conditional_orderbook::metrics::placement::{PlacementSlo, PlacementTimings};
+use conditional_orderbook::metrics::Metrics;
+use conditional_orderbook::oracle_service::deadman::{start_dead_man, DeadMan};
+use conditional_orderbook::oracle_service::{OracleCache, OracleWsClient};
+use conditional_orderbook::repositories::audit::AuditLog;
+use conditional_orderbook::repositories::baskets::BasketStore;
+use conditional_orderbook::repositories::changes::{ChangeStream, Observed};
+use conditional_orderbook::repositories::in_memory::InMemoryOrderRepository;
+use conditional_orderbook::repositories::journal::{
+    start_journal_compaction, Journal, JournalRetention,
+};
+use conditional_orderbook::repositories::open_orders::OpenOrdersView;
+use conditional_orderbook::repositories::search::OrderSearch;
+use conditional_orderbook::repositories::stats::{FillQuality, TradingStats, TriggerLatency};
+use conditional_orderbook::repositories::tape::TradeTape;
+use conditional_orderbook::repositories::templates::TemplateStore;
+use conditional_orderbook::repositories::vacuum::{start_vacuum, VacuumConfig};
+use conditional_orderbook::{oracle_service, routes, selfcheck, state, utils};
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
@@ -25,34 +60,473 @@ async fn main() -> std::io::Result<()> {
         .with_target(false)
         .init();
 
-    let cache = OracleCache::default();
-    OracleWsClient::default().spawn(cache.clone());
+    // `--check [--capture <journal.ndjson>]` validates the deployment and
+    // exits instead of serving.
+    let args: Vec<String> = std::env::args().collect();
+    if args.iter().any(|a| a == "--check") {
+        let capture = args
+            .iter()
+            .position(|a| a == "--capture")
+            .and_then(|i| args.get(i + 1));
+        let report = selfcheck::run(capture.map(String::as_str)).await;
+        println!("{report}");
+        std::process::exit(if report.passed() { 0 } else { 1 });
+    }
+
+    if let Some(f) = std::env::var("DECIMAL_JSON")
+        .ok()
+        .and_then(|s| utils::decimal::DecimalFormat::parse(&s))
+    {
+        utils::decimal::set_format(f);
+    }
+    if let Some(scheme) = std::env::var("ID_SCHEME")
+        .ok()
+        .and_then(|s| utils::ids::IdScheme::parse(&s))
+    {
+        let node = std::env::var("SNOWFLAKE_NODE")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(0);
+        utils::ids::set_generator(scheme.build(node));
+    }
+
+    let vault = match std::env::var("MASTER_KEYS") {
+        Ok(spec) => SecretBox::from_keyring(&spec).expect("MASTER_KEYS"),
+        Err(_) => {
+            tracing::warn!("MASTER_KEYS unset; webhook secrets sealed with an ephemeral key");
+            SecretBox::ephemeral()
+        }
+    };
+    let usage = AccountUsage::new(AccountLimits::from_env());
+    let statements = StatementLedger::default();
+    let accounts_data = web::Data::new(
+        AccountStore::new(vault)
+            .with_usage(usage.clone())
+            .with_statements(statements.clone()),
+    );
+
+    let limits = Limits::from_env();
+    let limits_data = web::Data::new(limits);
+    let public = PublicMode::from_env();
+    if public.enabled {
+        tracing::warn!(
+            requests_per_min = public.requests_per_min,
+            "public read-only mode: market data is open, order endpoints need an API key"
+        );
+    }
+    let public_data = web::Data::new(public);
+    let admin = AdminAuth::from_env();
+    if !admin.is_configured() {
+        tracing::warn!("ADMIN_TOKEN unset; every /admin request is refused");
+    }
+    let admin_data = web::Data::new(admin);
+
+    let instruments = InstrumentRegistry::from_env();
+    let instruments_data = web::Data::new(instruments.clone());
+
+    let stale_after_ms: u64 = std::env::var("ORACLE_STALE_MS")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(5_000);
+    let metrics = Metrics::default();
+    let sources = std::env::var("ORACLE_SOURCES")
+        .map(|s| {
+            s.split(',')
+                .map(|v| v.trim().to_string())
+                .filter(|v| !v.is_empty())
+                .collect()
+        })
+        .unwrap_or_default();
+    let cache = OracleCache::with_stale_after(std::time::Duration::from_millis(stale_after_ms))
+        .with_metrics(metrics.clone())
+        .with_max_future(std::time::Duration::from_millis(
+            std::env::var("ORACLE_MAX_FUTURE_MS")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(oracle_service::DEFAULT_MAX_FUTURE.as_millis() as u64),
+        ))
+        .with_sources(sources)
+        .with_synthetics(instruments.synthetics())
+        .with_history(
+            std::env::var("ORACLE_HISTORY_TICKS")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(oracle_service::history::DEFAULT_CAPACITY),
+        )
+        .with_dead_man(DeadMan::new(
+            optional_count("ORACLE_DEAD_MAN_SECS", Some(30))?.map(std::time::Duration::from_secs),
+            utils::now_ms(),
+        ));
+    start_dead_man(
+        cache.clone(),
+        metrics.clone(),
+        std::time::Duration::from_secs(1),
+    );
+    let oracle_client = OracleWsClient::from_env(instruments.clone());
+    // Volume conditions read prints from a second connection.
+    if std::env::var("ORACLE_WS_TRADES").is_ok_and(|v| matches!(v.trim(), "1" | "true" | "yes")) {
+        OracleWsClient {
+            channel: Some("trades".into()),
+            ..oracle_client.clone()
+        }
+        .spawn(cache.clone());
+    }
+    oracle_client.spawn(cache.clone());
     let cache_data = web::Data::new(cache.clone());
 
-    let repo = InMemoryOrderRepository::default();
+    let metrics_data = web::Data::new(metrics.clone());
+    let placement_data = web::Data::new(PlacementTimings::new(
+        PlacementSlo::from_env(),
+        metrics.clone(),
+    ));
+
+    let store = InMemoryOrderRepository::default();
+    let changes = ChangeStream::default();
+    let open_orders = OpenOrdersView::default();
+    open_orders
+        .attach(&store, &changes)
+        .await
+        .expect("seed open orders view");
+    let open_orders_data = web::Data::new(open_orders);
+    let search = OrderSearch::default();
+    search
+        .attach(&store, &changes)
+        .await
+        .expect("seed order search index");
+    let search_data = web::Data::new(search);
+    let defaults = JournalRetention::default();
+    let journal = Journal::with_retention(JournalRetention {
+        max_age_ms: optional_count("JOURNAL_RETENTION_MS", defaults.max_age_ms)?,
+        max_entries: optional_count("JOURNAL_MAX_ENTRIES", defaults.max_entries)?,
+    });
+    journal.attach(&changes);
+    let compact_every_secs = period("JOURNAL_COMPACT_INTERVAL_SECS", 60)?;
+    start_journal_compaction(
+        journal.clone(),
+        metrics.clone(),
+        std::time::Duration::from_secs(compact_every_secs),
+    );
+    let journal_data = web::Data::new(journal);
+    let trading_stats = TradingStats::default();
+    trading_stats.attach(&changes);
+    usage
+        .attach(&store, &changes)
+        .await
+        .expect("seed account usage");
+    statements
+        .attach(&store, &changes)
+        .await
+        .expect("seed account statements");
+    let trading_stats_data = web::Data::new(trading_stats);
+    let latency = TriggerLatency::default();
+    latency.attach(&changes);
+    let latency_data = web::Data::new(latency);
+    let fill_quality = FillQuality::default();
+    fill_quality
+        .attach(&store, &changes)
+        .await
+        .expect("seed fill quality");
+    let fill_quality_data = web::Data::new(fill_quality);
+    let timers = OrderTimers::default();
+    timers
+        .attach(&store, &changes)
+        .await
+        .expect("seed order timers");
+    let groups = OrderGroups::default();
+    groups.follow(&changes);
+    let callbacks = OrderCallbacks::default();
+    callbacks.follow(&changes);
+    let tape = match std::env::var("TRADE_TAPE_DIR") {
+        Ok(dir) => TradeTape::open(dir).expect("TRADE_TAPE_DIR"),
+        Err(_) => TradeTape::default(),
+    };
+    tape.follow(&changes);
+    let tape_data = web::Data::new(tape);
+    let changes_data = web::Data::new(changes.clone());
+    let store_handle = store.clone();
+    let repo = Observed::new(store, changes);
+    let mut vacuum_cfg = VacuumConfig::from_env()
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))?;
+    vacuum_cfg.every = std::time::Duration::from_secs(period(
+        "REPO_VACUUM_INTERVAL_SECS",
+        vacuum_cfg.every.as_secs(),
+    )?);
+    let housekeeper = Housekeeper::new(
+        std::sync::Arc::new(repo.clone()),
+        open_orders_data.get_ref().clone(),
+        search_data.get_ref().clone(),
+        vacuum_cfg.archive.clone(),
+        Housekeeper::policies_from_env(),
+    );
+    start_housekeeping(housekeeper.clone());
+    let housekeeper_data = web::Data::new(housekeeper);
+    start_vacuum(store_handle, repo.clone(), metrics.clone(), vacuum_cfg);
+    start_order_timers(timers, repo.clone());
+    start_group_budgets(groups.clone(), repo.clone());
+    let groups_data = web::Data::new(groups);
+    start_callbacks(callbacks.clone(), accounts_data.get_ref().clone());
+    let callbacks_data = web::Data::new(callbacks);
     let state = state::AppState::new(repo.clone());
+    let templates_data = web::Data::new(TemplateStore::default());
+    let audit_data = web::Data::new(AuditLog::default());
+
+    let halts = HaltRegistry::with_default_policy(
+        std::env::var("HALT_RESUME_POLICY")
+            .ok()
+            .and_then(|s| ResumePolicy::parse(&s))
+            .unwrap_or_default(),
+    );
+    let halts_data = web::Data::new(halts.clone());
+    let dead_letters = DeadLetters::with_max_failures(
+        std::env::var("DEAD_LETTER_AFTER")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(5),
+    );
+    let dead_letters_data = web::Data::new(dead_letters.clone());
+    let missed = MissedTriggers::default();
+    let missed_data = web::Data::new(missed.clone());
+    let ledger = ExecutionLedger::default().for_owner(
+        &std::env::var("ENGINE_INSTANCE_ID")
+            .unwrap_or_else(|_| format!("engine-{}", uuid::Uuid::new_v4())),
+    );
+    let ledger_data = web::Data::new(ledger.clone());
+
+    let flags = FeatureFlags::from_env().expect("parse FEATURE_FLAGS");
+    let flags_data = web::Data::new(flags.clone());
 
-    let assets = vec![
-        "BTC/USDT".to_string(),
-        "ETH/USDT".to_string(),
-        "SOL/USDT".to_string(),
-    ];
+    let assets = instruments.symbols();
 
+    let engine_cfg = EngineConfig {
+        policy: std::env::var("MATCH_POLICY")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or_default(),
+        circuit_breaker_bps: std::env::var("CIRCUIT_BREAKER_BPS")
+            .ok()
+            .and_then(|s| s.parse().ok()),
+        fair_band_bps: decimal("FAIR_BAND_BPS")?,
+        retry: RetryPolicy {
+            attempts: std::env::var("ENGINE_RETRY_ATTEMPTS")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(RetryPolicy::default().attempts),
+            ..RetryPolicy::default()
+        },
+        error_budget: ErrorBudgetConfig {
+            max_error_rate: std::env::var("ERROR_BUDGET_RATE")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(ErrorBudgetConfig::default().max_error_rate),
+            pause: std::env::var("ERROR_BUDGET_PAUSE_SECS")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .map(std::time::Duration::from_secs)
+                .unwrap_or(ErrorBudgetConfig::default().pause),
+            ..ErrorBudgetConfig::default()
+        },
+        missed_trigger: std::env::var("MISSED_TRIGGER_POLICY")
+            .ok()
+            .and_then(|s| MissedTriggerPolicy::parse(&s))
+            .unwrap_or_default(),
+        downtime_gap: std::env::var("DOWNTIME_GAP_SECS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .map(std::time::Duration::from_secs)
+            .unwrap_or(EngineConfig::default().downtime_gap),
+        interpolation: std::env::var("ORACLE_INTERPOLATION")
+            .ok()
+            .and_then(|s| Interpolation::parse(&s))
+            .unwrap_or_default(),
+        eval_threads: std::env::var("ENGINE_EVAL_THREADS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(EngineConfig::default().eval_threads),
+        bulk_fill_threshold: std::env::var("BULK_FILL_THRESHOLD")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(EngineConfig::default().bulk_fill_threshold),
+        ..EngineConfig::default()
+    };
+    let matchers = Matchers::default();
     start_matchers(
         assets,
         repo.clone(),
         cache.clone(),
-        std::time::Duration::from_secs(1),
+        instruments.clone(),
+        Controls {
+            halts: halts.clone(),
+            dead_letters,
+            events: changes_data.get_ref().clone(),
+            missed,
+            ledger,
+            flags,
+            matchers: matchers.clone(),
+            throttle: FillThrottle::new(optional_count("MAX_FILLS_PER_SEC", None)?),
+        },
+        engine_cfg.clone(),
+    );
+    let engine_data = web::Data::new(engine_cfg);
+
+    let orphan_min_age_hours: i64 = std::env::var("ORPHAN_MIN_AGE_HOURS")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(24);
+    let orphan_min_age_ms = orphan_min_age_hours.checked_mul(3_600_000).ok_or_else(|| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("ORPHAN_MIN_AGE_HOURS={orphan_min_age_hours}: too large"),
+        )
+    })?;
+    start_orphan_sweep(
+        repo.clone(),
+        cache.clone(),
+        metrics.clone(),
+        orphan_min_age_ms,
+        std::time::Duration::from_secs(60),
+    );
+    start_expiry_sweep(
+        repo.clone(),
+        metrics.clone(),
+        std::time::Duration::from_millis(period("EXPIRY_SWEEP_INTERVAL_MS", 1_000)?),
+    );
+
+    let baskets = BasketStore::default();
+    start_basket_watcher(
+        baskets.clone(),
+        repo.clone(),
+        BasketMarkets {
+            oracle: cache.clone(),
+            instruments: instruments.clone(),
+            halts: halts.clone(),
+        },
+        EngineConfig::default().tick_every,
     );
+    if let Some(mut cfg) = BotConfig::from_env() {
+        cfg.refresh = std::time::Duration::from_millis(period(
+            "LIQUIDITY_BOT_REFRESH_MS",
+            cfg.refresh.as_millis() as u64,
+        )?);
+        tracing::info!(?cfg, "liquidity bots quoting every pair");
+        start_liquidity_bots(
+            LiquidityBots::new(cfg, cache.clone(), instruments.clone()),
+            repo.clone(),
+        );
+    }
+    let renamer_data = web::Data::new(PairRenamer::new(
+        instruments.clone(),
+        state.orders.clone(),
+        journal_data.get_ref().clone(),
+        cache.clone(),
+        halts.clone(),
+        baskets.clone(),
+        changes_data.get_ref().clone(),
+    ));
+    let delister_data = web::Data::new(PairDelister::new(
+        instruments.clone(),
+        state.orders.clone(),
+        cache.clone(),
+        halts.clone(),
+        matchers,
+        changes_data.get_ref().clone(),
+    ));
+    let purger_data = web::Data::new(AccountPurger::new(
+        state.orders.clone(),
+        journal_data.get_ref().clone(),
+        audit_data.get_ref().clone(),
+        baskets.clone(),
+    ));
+    let baskets_data = web::Data::new(baskets);
 
     HttpServer::new(move || {
         App::new()
+            .wrap(from_fn(deadline))
+            .wrap(from_fn(guard_body))
+            .wrap(from_fn(public_gate))
             .wrap(Logger::default())
             .app_data(state.clone())
             .app_data(cache_data.clone())
+            .app_data(metrics_data.clone())
+            .app_data(placement_data.clone())
+            .app_data(instruments_data.clone())
+            .app_data(halts_data.clone())
+            .app_data(dead_letters_data.clone())
+            .app_data(missed_data.clone())
+            .app_data(ledger_data.clone())
+            .app_data(templates_data.clone())
+            .app_data(audit_data.clone())
+            .app_data(baskets_data.clone())
+            .app_data(groups_data.clone())
+            .app_data(callbacks_data.clone())
+            .app_data(tape_data.clone())
+            .app_data(renamer_data.clone())
+            .app_data(delister_data.clone())
+            .app_data(flags_data.clone())
+            .app_data(purger_data.clone())
+            .app_data(open_orders_data.clone())
+            .app_data(search_data.clone())
+            .app_data(journal_data.clone())
+            .app_data(accounts_data.clone())
+            .app_data(limits_data.clone())
+            .app_data(public_data.clone())
+            .app_data(admin_data.clone())
+            .app_data(changes_data.clone())
+            .app_data(trading_stats_data.clone())
+            .app_data(latency_data.clone())
+            .app_data(fill_quality_data.clone())
+            .app_data(engine_data.clone())
+            .app_data(housekeeper_data.clone())
+            .app_data(web::JsonConfig::default().limit(limits.max_body_bytes))
             .configure(routes::config)
     })
     .bind(std::env::var("SERVER_ADDR").unwrap_or_else(|_| "127.0.0.1:8080".into()))?
     .run()
     .await
 }
+
+/// A timer period from `name`, or `default` when it is unset. Anything
+/// but a whole number above 0 is a config error, since a zero period
+/// would panic the timer's task long after boot.
+fn period(name: &str, default: u64) -> std::io::Result<u64> {
+    match std::env::var(name) {
+        Err(_) => Ok(default),
+        Ok(v) => v.trim().parse().ok().filter(|&n| n > 0).ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("{name}={v:?}: expected a whole number above 0"),
+            )
+        }),
+    }
+}
+
+/// An optional count from `name`: `default` when it is unset and `None`
+/// for `off`. Anything else that is not a whole number is a config error.
+fn optional_count<T: std::str::FromStr>(
+    name: &str,
+    default: Option<T>,
+) -> std::io::Result<Option<T>> {
+    match std::env::var(name) {
+        Err(_) => Ok(default),
+        Ok(v) if v.trim() == "off" => Ok(None),
+        Ok(v) => v.trim().parse().map(Some).map_err(|_| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("{name}={v:?}: expected a whole number or `off`"),
+            )
+        }),
+    }
+}
+
+/// An optional decimal from `name`. A value that does not parse is a
+/// config error rather than the setting quietly switching off.
+fn decimal(name: &str) -> std::io::Result<Option<rust_decimal::Decimal>> {
+    match std::env::var(name) {
+        Err(_) => Ok(None),
+        Ok(v) => v.trim().parse().map(Some).map_err(|_| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("{name}={v:?}: expected a decimal"),
+            )
+        }),
+    }
+}
@@ -0,0 +1,92 @@
+pub mod placement;
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::{Arc, Mutex};
+
+/// Process-wide counters and gauges, rendered in the Prometheus text format
+/// on `GET /metrics`. Series are keyed by name plus an ordered label set.
+#[derive(Clone, Default)]
+pub struct Metrics {
+    inner: Arc<Mutex<BTreeMap<String, f64>>>,
+}
+
+fn series(name: &str, labels: &[(&str, &str)]) -> String {
+    if labels.is_empty() {
+        return name.to_string();
+    }
+    let body = labels
+        .iter()
+        .map(|(k, v)| format!("{k}=\"{v}\""))
+        .collect::<Vec<_>>()
+        .join(",");
+    format!("{name}{{{body}}}")
+}
+
+impl Metrics {
+    pub fn incr(&self, name: &str, labels: &[(&str, &str)], by: f64) {
+        let mut m = self.inner.lock().unwrap();
+        *m.entry(series(name, labels)).or_insert(0.0) += by;
+    }
+
+    pub fn set(&self, name: &str, labels: &[(&str, &str)], value: f64) {
+        let mut m = self.inner.lock().unwrap();
+        m.insert(series(name, labels), value);
+    }
+
+    /// Drops every series of `name` so a gauge family can be rebuilt from
+    /// scratch (e.g. per-pair counts where pairs may disappear).
+    pub fn clear(&self, name: &str) {
+        let mut m = self.inner.lock().unwrap();
+        m.retain(|k, _| k != name && !k.starts_with(&format!("{name}{{")));
+    }
+
+    pub fn get(&self, name: &str, labels: &[(&str, &str)]) -> Option<f64> {
+        let m = self.inner.lock().unwrap();
+        m.get(&series(name, labels)).copied()
+    }
+
+    pub fn render(&self) -> String {
+        let m = self.inner.lock().unwrap();
+        let mut out = String::new();
+        for (k, v) in m.iter() {
+            let _ = writeln!(out, "{k} {v}");
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn incr_and_set_are_keyed_by_labels() {
+        let m = Metrics::default();
+        m.incr("fills_total", &[("pair", "BTC/USDT")], 1.0);
+        m.incr("fills_total", &[("pair", "BTC/USDT")], 2.0);
+        m.set("open_orders", &[], 7.0);
+        assert_eq!(m.get("fills_total", &[("pair", "BTC/USDT")]), Some(3.0));
+        assert_eq!(m.get("fills_total", &[("pair", "ETH/USDT")]), None);
+        assert_eq!(m.get("open_orders", &[]), Some(7.0));
+    }
+
+    #[test]
+    fn clear_drops_whole_family_only() {
+        let m = Metrics::default();
+        m.set("orphaned_orders", &[], 2.0);
+        m.set("orphaned_orders", &[("pair", "X/Y")], 2.0);
+        m.set("orphaned_orders_cancelled_total", &[], 1.0);
+        m.clear("orphaned_orders");
+        assert_eq!(m.get("orphaned_orders", &[]), None);
+        assert_eq!(m.get("orphaned_orders", &[("pair", "X/Y")]), None);
+        assert_eq!(m.get("orphaned_orders_cancelled_total", &[]), Some(1.0));
+    }
+
+    #[test]
+    fn render_is_prometheus_text() {
+        let m = Metrics::default();
+        m.set("a", &[("pair", "BTC/USDT")], 1.5);
+        assert_eq!(m.render(), "a{pair=\"BTC/USDT\"} 1.5\n");
+    }
+}
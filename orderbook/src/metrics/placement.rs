@@ -0,0 +1,293 @@
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::Serialize;
+
+use crate::metrics::Metrics;
+use crate::utils::percentile;
+
+/// Most recent placements kept for the SLO summary.
+const PLACEMENT_SAMPLES: usize = 10_000;
+
+/// Where the create path spends its time.
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Stage {
+    /// Symbol, shape, condition and precision checks.
+    Validation,
+    /// Oracle freshness and trading session checks.
+    Risk,
+    /// The repository `create` call.
+    Write,
+    /// Handing the new order to change listeners and subscribers.
+    Publish,
+}
+
+impl Stage {
+    pub const ALL: [Stage; 4] = [Stage::Validation, Stage::Risk, Stage::Write, Stage::Publish];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Stage::Validation => "validation",
+            Stage::Risk => "risk",
+            Stage::Write => "write",
+            Stage::Publish => "publish",
+        }
+    }
+}
+
+/// Time charged to each stage of one placement.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StageTimes([Duration; 4]);
+
+impl StageTimes {
+    pub fn get(&self, stage: Stage) -> Duration {
+        self.0[stage as usize]
+    }
+
+    pub fn total(&self) -> Duration {
+        self.0.iter().sum()
+    }
+}
+
+tokio::task_local! {
+    static CLOCK: RefCell<StageTimes>;
+}
+
+/// Runs a placement, collecting what its stages charge along the way.
+pub async fn timed<F: Future>(f: F) -> (F::Output, StageTimes) {
+    CLOCK
+        .scope(RefCell::new(StageTimes::default()), async {
+            let out = f.await;
+            (out, CLOCK.with(|c| *c.borrow()))
+        })
+        .await
+}
+
+/// Charges `d` to `stage` of the placement running on this task. Outside
+/// [`timed`] it does nothing, so shared code paths may call it freely.
+pub fn charge(stage: Stage, d: Duration) {
+    let _ = CLOCK.try_with(|c| c.borrow_mut().0[stage as usize] += d);
+}
+
+/// Charges the time `f` takes to `stage`, less whatever other stages were
+/// charged meanwhile (the publish inside a repository write, say).
+pub async fn measure<F: Future>(stage: Stage, f: F) -> F::Output {
+    let charged = || CLOCK.try_with(|c| c.borrow().total()).unwrap_or_default();
+    let before = charged();
+    let started = Instant::now();
+    let out = f.await;
+    let nested = charged().saturating_sub(before);
+    charge(stage, started.elapsed().saturating_sub(nested));
+    out
+}
+
+/// Placement latency objective: `objective` of placements complete within
+/// `target`.
+#[derive(Debug, Clone, Copy)]
+pub struct PlacementSlo {
+    pub target: Duration,
+    pub objective: Decimal,
+}
+
+impl Default for PlacementSlo {
+    fn default() -> Self {
+        Self {
+            target: Duration::from_millis(50),
+            objective: dec!(0.99),
+        }
+    }
+}
+
+impl PlacementSlo {
+    /// `PLACEMENT_SLO_MS` and `PLACEMENT_SLO_OBJECTIVE` override the
+    /// defaults.
+    pub fn from_env() -> Self {
+        let d = Self::default();
+        Self {
+            target: std::env::var("PLACEMENT_SLO_MS")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .map_or(d.target, Duration::from_millis),
+            objective: std::env::var("PLACEMENT_SLO_OBJECTIVE")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(d.objective),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct Distribution {
+    pub p50_us: i64,
+    pub p90_us: i64,
+    pub p99_us: i64,
+    pub max_us: i64,
+    pub mean_us: i64,
+}
+
+impl Distribution {
+    fn of(mut samples: Vec<i64>) -> Option<Self> {
+        if samples.is_empty() {
+            return None;
+        }
+        samples.sort_unstable();
+        Some(Self {
+            p50_us: percentile(&samples, 50),
+            p90_us: percentile(&samples, 90),
+            p99_us: percentile(&samples, 99),
+            max_us: samples[samples.len() - 1],
+            mean_us: samples.iter().sum::<i64>() / samples.len() as i64,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct StageSummary {
+    pub stage: Stage,
+    #[serde(flatten)]
+    pub latency: Distribution,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct SloSummary {
+    pub target_ms: u64,
+    pub objective: Decimal,
+    pub samples: usize,
+    pub within_target: usize,
+    /// Share of samples within target; absent before the first placement.
+    pub compliance: Option<Decimal>,
+    pub met: bool,
+    pub total: Option<Distribution>,
+    pub stages: Vec<StageSummary>,
+}
+
+/// Per-stage timings of recent successful placements, checked against the
+/// placement SLO and mirrored into `/metrics`.
+#[derive(Clone)]
+pub struct PlacementTimings {
+    slo: PlacementSlo,
+    metrics: Metrics,
+    samples: Arc<Mutex<VecDeque<StageTimes>>>,
+}
+
+impl Default for PlacementTimings {
+    fn default() -> Self {
+        Self::new(PlacementSlo::default(), Metrics::default())
+    }
+}
+
+impl PlacementTimings {
+    pub fn new(slo: PlacementSlo, metrics: Metrics) -> Self {
+        Self {
+            slo,
+            metrics,
+            samples: Arc::new(Mutex::new(VecDeque::new())),
+        }
+    }
+
+    pub fn record(&self, t: StageTimes) {
+        for stage in Stage::ALL {
+            self.metrics.incr(
+                "order_placement_stage_seconds_total",
+                &[("stage", stage.name())],
+                t.get(stage).as_secs_f64(),
+            );
+        }
+        self.metrics.incr("order_placements_total", &[], 1.0);
+        if t.total() > self.slo.target {
+            self.metrics
+                .incr("order_placement_slo_breaches_total", &[], 1.0);
+        }
+        let mut q = self.samples.lock().unwrap();
+        if q.len() == PLACEMENT_SAMPLES {
+            q.pop_front();
+        }
+        q.push_back(t);
+    }
+
+    pub fn summary(&self) -> SloSummary {
+        let q = self.samples.lock().unwrap();
+        let us = |d: Duration| d.as_micros() as i64;
+        let within_target = q.iter().filter(|t| t.total() <= self.slo.target).count();
+        let compliance = (!q.is_empty())
+            .then(|| (Decimal::from(within_target) / Decimal::from(q.len())).round_dp(4));
+        SloSummary {
+            target_ms: self.slo.target.as_millis() as u64,
+            objective: self.slo.objective,
+            samples: q.len(),
+            within_target,
+            compliance,
+            met: compliance.is_none_or(|c| c >= self.slo.objective),
+            total: Distribution::of(q.iter().map(|t| us(t.total())).collect()),
+            stages: Stage::ALL
+                .into_iter()
+                .filter_map(|stage| {
+                    let latency = Distribution::of(q.iter().map(|t| us(t.get(stage))).collect())?;
+                    Some(StageSummary { stage, latency })
+                })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn nested_stages_are_not_double_counted() {
+        let ((), t) = timed(async {
+            charge(Stage::Validation, Duration::from_millis(2));
+            measure(Stage::Write, async {
+                tokio::time::sleep(Duration::from_millis(5)).await;
+                charge(Stage::Publish, Duration::from_millis(3));
+            })
+            .await;
+        })
+        .await;
+        assert_eq!(t.get(Stage::Validation), Duration::from_millis(2));
+        assert_eq!(t.get(Stage::Publish), Duration::from_millis(3));
+        let write = t.get(Stage::Write);
+        assert!(write >= Duration::from_millis(2) && write < Duration::from_millis(100));
+        assert_eq!(t.get(Stage::Risk), Duration::ZERO);
+
+        // Outside a placement, charging is a no-op.
+        charge(Stage::Risk, Duration::from_secs(1));
+    }
+
+    #[test]
+    fn summary_reports_compliance_against_the_target() {
+        let metrics = Metrics::default();
+        let timings = PlacementTimings::new(
+            PlacementSlo {
+                target: Duration::from_millis(10),
+                objective: dec!(0.75),
+            },
+            metrics.clone(),
+        );
+        assert!(timings.summary().met);
+        for ms in [1, 2, 3, 20] {
+            let mut t = StageTimes::default();
+            t.0[Stage::Write as usize] = Duration::from_millis(ms);
+            timings.record(t);
+        }
+        let s = timings.summary();
+        assert_eq!(s.samples, 4);
+        assert_eq!(s.within_target, 3);
+        assert_eq!(s.compliance, Some(dec!(0.75)));
+        assert!(s.met);
+        assert_eq!(s.total.unwrap().max_us, 20_000);
+        assert_eq!(s.stages.len(), 4);
+        assert_eq!(
+            metrics.get("order_placement_slo_breaches_total", &[]),
+            Some(1.0)
+        );
+        assert_eq!(metrics.get("order_placements_total", &[]), Some(4.0));
+    }
+}
@@ -0,0 +1,139 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use serde::Serialize;
+use tokio::time::{interval, MissedTickBehavior};
+use tracing::{info, warn};
+
+use crate::metrics::Metrics;
+use crate::oracle_service::OracleCache;
+use crate::utils::now_ms;
+
+/// Where the dead-man switch stands, as served on `/health/ready`.
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct DeadManStatus {
+    pub cancel_only: bool,
+    /// `None` when the switch is disabled.
+    pub timeout_ms: Option<i64>,
+    /// When the last tick for any pair arrived; `None` before the first.
+    pub last_tick_ms: Option<i64>,
+    pub since_ms: Option<i64>,
+}
+
+struct Inner {
+    timeout_ms: Option<i64>,
+    armed_ms: i64,
+    last_tick_ms: Option<i64>,
+    since_ms: Option<i64>,
+}
+
+/// Fail-safe for total oracle loss: once no pair has ticked for `timeout`
+/// the service goes cancel-only. Placement and amends are refused, matchers
+/// stop evaluating and cancels still go through, until a tick arrives
+/// again. Without a first tick the timeout counts from when the switch was
+/// armed.
+#[derive(Clone)]
+pub struct DeadMan {
+    inner: Arc<Mutex<Inner>>,
+}
+
+impl Default for DeadMan {
+    /// Disabled: never trips.
+    fn default() -> Self {
+        Self::new(None, now_ms())
+    }
+}
+
+impl DeadMan {
+    pub fn new(timeout: Option<Duration>, armed_ms: i64) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(Inner {
+                timeout_ms: timeout.map(|t| t.as_millis() as i64),
+                armed_ms,
+                last_tick_ms: None,
+                since_ms: None,
+            })),
+        }
+    }
+
+    /// Re-evaluates the switch given when the last tick arrived. Returns
+    /// the new cancel-only state when it changed.
+    pub fn check(&self, last_tick_ms: Option<i64>, now_ms: i64) -> Option<bool> {
+        let mut g = self.inner.lock().unwrap();
+        g.last_tick_ms = last_tick_ms;
+        let timeout = g.timeout_ms?;
+        let silent = now_ms - last_tick_ms.unwrap_or(g.armed_ms);
+        match (silent > timeout, g.since_ms) {
+            (true, None) => {
+                g.since_ms = Some(now_ms);
+                Some(true)
+            }
+            (false, Some(_)) => {
+                g.since_ms = None;
+                Some(false)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn is_cancel_only(&self) -> bool {
+        self.inner.lock().unwrap().since_ms.is_some()
+    }
+
+    pub fn status(&self) -> DeadManStatus {
+        let g = self.inner.lock().unwrap();
+        DeadManStatus {
+            cancel_only: g.since_ms.is_some(),
+            timeout_ms: g.timeout_ms,
+            last_tick_ms: g.last_tick_ms,
+            since_ms: g.since_ms,
+        }
+    }
+}
+
+/// Checks the cache's switch against its last tick every `every`, logging
+/// transitions and keeping the `cancel_only` gauge current.
+pub fn start_dead_man(oracle: OracleCache, metrics: Metrics, every: Duration) {
+    tokio::spawn(async move {
+        let dead_man = oracle.dead_man();
+        let mut t = interval(every);
+        t.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            t.tick().await;
+            let last = oracle.last_received_ms();
+            match dead_man.check(last, now_ms()) {
+                Some(true) => {
+                    warn!(last_tick_ms = ?last, "no oracle ticks for any pair; entering cancel-only mode")
+                }
+                Some(false) => info!("oracle ticks resumed; leaving cancel-only mode"),
+                None => {}
+            }
+            let gauge = if dead_man.is_cancel_only() { 1.0 } else { 0.0 };
+            metrics.set("cancel_only", &[], gauge);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trips_on_silence_and_recovers_on_the_next_tick() {
+        let d = DeadMan::new(Some(Duration::from_secs(10)), 1_000);
+        // Never ticked: counts from when it was armed.
+        assert_eq!(d.check(None, 5_000), None);
+        assert_eq!(d.check(None, 11_001), Some(true));
+        assert!(d.is_cancel_only());
+        assert_eq!(d.check(None, 12_000), None);
+        assert_eq!(d.status().since_ms, Some(11_001));
+
+        assert_eq!(d.check(Some(12_500), 13_000), Some(false));
+        assert!(!d.is_cancel_only());
+        assert_eq!(d.check(Some(12_500), 22_501), Some(true));
+
+        let off = DeadMan::default();
+        assert_eq!(off.check(None, i64::MAX), None);
+        assert!(!off.is_cancel_only());
+    }
+}
@@ -0,0 +1,406 @@
+//! Client side of permessage-deflate (RFC 7692) for the oracle feed.
+//! tungstenite fails any frame with RSV1 set, so when the server agrees to
+//! compress, frames are read and inflated here; when it does not, the
+//! socket goes to tungstenite after our handshake.
+
+use flate2::{Decompress, FlushDecompress};
+use futures_util::{SinkExt, StreamExt};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio_tungstenite::tungstenite::handshake::{client::generate_key, derive_accept_key};
+use tokio_tungstenite::tungstenite::protocol::{Message, Role};
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+
+/// We never compress what we send, so the client window is irrelevant.
+const OFFER: &str = "permessage-deflate; client_no_context_takeover";
+const SYNC_TAIL: [u8; 4] = [0x00, 0x00, 0xff, 0xff];
+const MAX_HANDSHAKE: usize = 16 * 1024;
+/// Largest message accepted, compressed or inflated.
+const MAX_MESSAGE: usize = 16 << 20;
+
+/// An open oracle connection, compressed or not.
+pub enum Feed {
+    Plain(WebSocketStream<MaybeTlsStream<TcpStream>>),
+    Deflated(Inflating<TcpStream>),
+}
+
+impl Feed {
+    pub fn is_deflated(&self) -> bool {
+        matches!(self, Feed::Deflated(_))
+    }
+
+    /// Sends `text` uncompressed.
+    pub async fn send_text(&mut self, text: String) -> Result<(), String> {
+        match self {
+            Feed::Plain(ws) => ws
+                .send(Message::text(text))
+                .await
+                .map_err(|e| e.to_string()),
+            Feed::Deflated(f) => f.send(0x1, text.as_bytes()).await,
+        }
+    }
+
+    pub async fn next(&mut self) -> Option<Result<Message, String>> {
+        match self {
+            Feed::Plain(ws) => ws.next().await.map(|r| r.map_err(|e| e.to_string())),
+            Feed::Deflated(f) => f.next_message().await.transpose(),
+        }
+    }
+}
+
+/// Opens `url` offering permessage-deflate, with `token` as a bearer
+/// credential. Plain `ws://` only.
+pub async fn connect(url: &str, token: Option<&str>) -> Result<Feed, String> {
+    let u = url::Url::parse(url).map_err(|e| e.to_string())?;
+    if u.scheme() != "ws" {
+        return Err(format!(
+            "compression needs a ws:// endpoint, not {}",
+            u.scheme()
+        ));
+    }
+    let host = u.host_str().ok_or("endpoint has no host")?;
+    let port = u.port_or_known_default().unwrap_or(80);
+    let mut stream = TcpStream::connect((host, port))
+        .await
+        .map_err(|e| e.to_string())?;
+    let key = generate_key();
+    let target = match u.query() {
+        Some(q) => format!("{}?{q}", u.path()),
+        None => u.path().to_string(),
+    };
+    if token.is_some_and(|t| t.contains(['\r', '\n'])) {
+        return Err("token must be a single line".into());
+    }
+    let auth = token.map_or_else(String::new, |t| format!("Authorization: Bearer {t}\r\n"));
+    let request = format!(
+        "GET {target} HTTP/1.1\r\nHost: {host}:{port}\r\nUpgrade: websocket\r\n\
+         Connection: Upgrade\r\nSec-WebSocket-Key: {key}\r\nSec-WebSocket-Version: 13\r\n\
+         Sec-WebSocket-Extensions: {OFFER}\r\n{auth}\r\n"
+    );
+    stream
+        .write_all(request.as_bytes())
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let mut buf = Vec::new();
+    let end = loop {
+        if let Some(i) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+            break i + 4;
+        }
+        if buf.len() > MAX_HANDSHAKE {
+            return Err("handshake response too large".into());
+        }
+        let mut chunk = [0u8; 1024];
+        let n = stream.read(&mut chunk).await.map_err(|e| e.to_string())?;
+        if n == 0 {
+            return Err("connection closed during handshake".into());
+        }
+        buf.extend_from_slice(&chunk[..n]);
+    };
+    let head = std::str::from_utf8(&buf[..end]).map_err(|e| e.to_string())?;
+    let rest = buf[end..].to_vec();
+    Ok(match accepted(head, &key)? {
+        Some(reset) => Feed::Deflated(Inflating::new(stream, rest, reset)),
+        None => Feed::Plain(
+            WebSocketStream::from_partially_read(
+                MaybeTlsStream::Plain(stream),
+                rest,
+                Role::Client,
+                None,
+            )
+            .await,
+        ),
+    })
+}
+
+/// Checks the upgrade response to our offer. `None` if the server does
+/// not compress, otherwise whether it restarts its window per message.
+fn accepted(head: &str, key: &str) -> Result<Option<bool>, String> {
+    let mut lines = head.split("\r\n");
+    let status = lines.next().unwrap_or_default();
+    if status.split_whitespace().nth(1) != Some("101") {
+        return Err(format!("upgrade refused: {status}"));
+    }
+    let (mut accept, mut extensions) = (None, None);
+    for (name, value) in lines.filter_map(|l| l.split_once(':')) {
+        if name.eq_ignore_ascii_case("sec-websocket-accept") {
+            accept = Some(value.trim());
+        } else if name.eq_ignore_ascii_case("sec-websocket-extensions") {
+            extensions = Some(value.trim());
+        }
+    }
+    if accept != Some(derive_accept_key(key.as_bytes()).as_str()) {
+        return Err("bad Sec-WebSocket-Accept".into());
+    }
+    let Some(ext) = extensions else {
+        return Ok(None);
+    };
+    let mut params = ext.split(';').map(str::trim);
+    if params.next() != Some("permessage-deflate") {
+        return Err(format!("unexpected extension: {ext}"));
+    }
+    let mut reset = false;
+    for p in params {
+        match p.split('=').next().unwrap_or_default().trim() {
+            "server_no_context_takeover" => reset = true,
+            // Any smaller server window inflates fine with ours.
+            "server_max_window_bits" | "client_no_context_takeover" | "client_max_window_bits" => {}
+            other => return Err(format!("unexpected extension parameter: {other}")),
+        }
+    }
+    Ok(Some(reset))
+}
+
+/// Reads messages off a socket with permessage-deflate negotiated. Pings
+/// are answered; nothing is ever sent compressed.
+pub struct Inflating<S> {
+    stream: S,
+    buf: Vec<u8>,
+    inflate: Decompress,
+    /// `server_no_context_takeover`: each message starts a fresh window.
+    reset: bool,
+    /// Opcode, whether compressed, and payload of a fragmented message.
+    message: Option<(u8, bool, Vec<u8>)>,
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> Inflating<S> {
+    /// `buf` is whatever arrived after the handshake response.
+    pub fn new(stream: S, buf: Vec<u8>, reset: bool) -> Self {
+        Self {
+            stream,
+            buf,
+            inflate: Decompress::new(false),
+            reset,
+            message: None,
+        }
+    }
+
+    /// The next text, binary or close message; `None` when the socket ends
+    /// between frames.
+    pub async fn next_message(&mut self) -> Result<Option<Message>, String> {
+        loop {
+            let Some((first, payload)) = self.frame().await? else {
+                return Ok(None);
+            };
+            let (fin, compressed, opcode) = (first & 0x80 != 0, first & 0x40 != 0, first & 0x0f);
+            match opcode {
+                0x8 => return Ok(Some(Message::Close(None))),
+                0x9 => {
+                    self.send(0xa, &payload).await?;
+                    continue;
+                }
+                0xa => continue,
+                0x1 | 0x2 if self.message.is_some() => {
+                    return Err("new message inside a fragmented one".into())
+                }
+                0x1 | 0x2 => self.message = Some((opcode, compressed, payload)),
+                0x0 => {
+                    let Some((_, _, data)) = &mut self.message else {
+                        return Err("continuation without a message".into());
+                    };
+                    if data.len() + payload.len() > MAX_MESSAGE {
+                        return Err("message too large".into());
+                    }
+                    data.extend_from_slice(&payload);
+                }
+                op => return Err(format!("unknown opcode {op}")),
+            }
+            if !fin {
+                continue;
+            }
+            let Some((opcode, compressed, data)) = self.message.take() else {
+                continue;
+            };
+            let data = if compressed {
+                self.inflate(data)?
+            } else {
+                data
+            };
+            return Ok(Some(if opcode == 0x1 {
+                Message::text(String::from_utf8(data).map_err(|e| e.to_string())?)
+            } else {
+                Message::binary(data)
+            }));
+        }
+    }
+
+    /// First header byte and payload of the next frame.
+    async fn frame(&mut self) -> Result<Option<(u8, Vec<u8>)>, String> {
+        if !self.fill(2).await? {
+            return Ok(None);
+        }
+        if self.buf[1] & 0x80 != 0 {
+            return Err("masked frame from server".into());
+        }
+        let header = match self.buf[1] & 0x7f {
+            126 => 4,
+            127 => 10,
+            _ => 2,
+        };
+        self.fill(header).await?;
+        let len = match header {
+            4 => u16::from_be_bytes([self.buf[2], self.buf[3]]) as usize,
+            10 => {
+                let mut n = [0u8; 8];
+                n.copy_from_slice(&self.buf[2..10]);
+                u64::from_be_bytes(n) as usize
+            }
+            _ => (self.buf[1] & 0x7f) as usize,
+        };
+        if len > MAX_MESSAGE {
+            return Err("frame too large".into());
+        }
+        self.fill(header + len).await?;
+        let first = self.buf[0];
+        let payload = self.buf[header..header + len].to_vec();
+        self.buf.drain(..header + len);
+        Ok(Some((first, payload)))
+    }
+
+    /// Reads until `n` bytes are buffered; false if the socket ended with
+    /// nothing buffered.
+    async fn fill(&mut self, n: usize) -> Result<bool, String> {
+        while self.buf.len() < n {
+            let mut chunk = [0u8; 4096];
+            let read = self
+                .stream
+                .read(&mut chunk)
+                .await
+                .map_err(|e| e.to_string())?;
+            if read == 0 {
+                return match self.buf.is_empty() {
+                    true => Ok(false),
+                    false => Err("connection closed mid-frame".into()),
+                };
+            }
+            self.buf.extend_from_slice(&chunk[..read]);
+        }
+        Ok(true)
+    }
+
+    fn inflate(&mut self, mut data: Vec<u8>) -> Result<Vec<u8>, String> {
+        data.extend_from_slice(&SYNC_TAIL);
+        let mut out = Vec::with_capacity(data.len() * 4);
+        let start = self.inflate.total_in();
+        loop {
+            let consumed = (self.inflate.total_in() - start) as usize;
+            if out.len() == out.capacity() {
+                if out.len() >= MAX_MESSAGE {
+                    return Err("inflated message too large".into());
+                }
+                out.reserve(out.len().max(1024));
+            }
+            let before = (consumed, out.len());
+            self.inflate
+                .decompress_vec(&data[consumed..], &mut out, FlushDecompress::Sync)
+                .map_err(|e| e.to_string())?;
+            let consumed = (self.inflate.total_in() - start) as usize;
+            if consumed == data.len() && out.len() < out.capacity() {
+                break;
+            }
+            if (consumed, out.len()) == before {
+                return Err("inflate made no progress".into());
+            }
+        }
+        if self.reset {
+            self.inflate.reset(false);
+        }
+        Ok(out)
+    }
+
+    /// A masked frame, as clients must send; control payloads are cut to
+    /// 125 bytes.
+    async fn send(&mut self, opcode: u8, payload: &[u8]) -> Result<(), String> {
+        let payload = match opcode & 0x8 {
+            0 => payload,
+            _ => &payload[..payload.len().min(125)],
+        };
+        let mask: [u8; 4] = rand::random();
+        let mut f = vec![0x80 | opcode];
+        match payload.len() {
+            n @ 0..=125 => f.push(0x80 | n as u8),
+            n @ 126..=0xffff => {
+                f.push(0x80 | 126);
+                f.extend_from_slice(&(n as u16).to_be_bytes());
+            }
+            n => {
+                f.push(0x80 | 127);
+                f.extend_from_slice(&(n as u64).to_be_bytes());
+            }
+        }
+        f.extend_from_slice(&mask);
+        f.extend(payload.iter().enumerate().map(|(i, b)| b ^ mask[i % 4]));
+        self.stream.write_all(&f).await.map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::{Compress, Compression, FlushCompress};
+
+    fn deflated(c: &mut Compress, text: &str) -> Vec<u8> {
+        let mut out = Vec::with_capacity(text.len() + 64);
+        c.compress_vec(text.as_bytes(), &mut out, FlushCompress::Sync)
+            .unwrap();
+        assert!(out.ends_with(&SYNC_TAIL));
+        out.truncate(out.len() - SYNC_TAIL.len());
+        out
+    }
+
+    fn frame(first: u8, payload: &[u8]) -> Vec<u8> {
+        let mut f = vec![first, payload.len() as u8];
+        f.extend_from_slice(payload);
+        f
+    }
+
+    #[test]
+    fn handshake_accepts_only_what_was_offered() {
+        let key = "dGhlIHNhbXBsZSBub25jZQ==";
+        let ok = "HTTP/1.1 101 Switching Protocols\r\n\
+                  Sec-WebSocket-Accept: s3pPLMBiTxaQ9kYGzzhZRbK+xOo=\r\n";
+        assert_eq!(accepted(&format!("{ok}\r\n"), key), Ok(None));
+        let h = format!(
+            "{ok}Sec-WebSocket-Extensions: permessage-deflate; server_no_context_takeover\r\n\r\n"
+        );
+        assert_eq!(accepted(&h, key), Ok(Some(true)));
+        let h = format!("{ok}Sec-WebSocket-Extensions: x-other\r\n\r\n");
+        assert!(accepted(&h, key).is_err());
+        assert!(accepted("HTTP/1.1 401 Unauthorized\r\n\r\n", key).is_err());
+    }
+
+    #[tokio::test]
+    async fn compressed_and_fragmented_messages_are_reassembled() {
+        let tick = r#"{"pair":"BTC/USDT","price":"100","ts_ms":1}"#;
+        let mut c = Compress::new(Compression::default(), false);
+        let mut wire = frame(0xc1, &deflated(&mut c, tick));
+        wire.extend(frame(0x89, b"hi"));
+        // Shares the window with the first.
+        wire.extend(frame(0xc1, &deflated(&mut c, tick)));
+        wire.extend(frame(0x01, b"plain "));
+        wire.extend(frame(0x80, b"text"));
+        wire.extend(frame(0x88, &[]));
+
+        let (client, mut server) = tokio::io::duplex(4096);
+        let mut feed = Inflating::new(client, wire[..3].to_vec(), false);
+        server.write_all(&wire[3..]).await.unwrap();
+
+        for want in [tick, tick, "plain text"] {
+            let got = feed.next_message().await.unwrap().unwrap();
+            assert_eq!(got, Message::text(want));
+        }
+        assert!(matches!(
+            feed.next_message().await,
+            Ok(Some(Message::Close(None)))
+        ));
+
+        let mut pong = [0u8; 8];
+        server.read_exact(&mut pong).await.unwrap();
+        assert_eq!(pong[..2], [0x8a, 0x82]);
+        let unmasked: Vec<u8> = (0..2).map(|i| pong[6 + i] ^ pong[2 + i]).collect();
+        assert_eq!(unmasked, b"hi");
+
+        drop(server);
+        assert_eq!(feed.next_message().await, Ok(None));
+    }
+}
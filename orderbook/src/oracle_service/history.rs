@@ -0,0 +1,130 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+
+use tokio::sync::RwLock;
+
+use super::Tick;
+use crate::instruments::symbol::Symbol;
+
+/// A day of one-second ticks per pair.
+pub const DEFAULT_CAPACITY: usize = 86_400;
+
+/// The latest accepted aggregate ticks per pair, oldest first, kept so
+/// orders can be re-evaluated against what the feed said. At most
+/// `capacity` per pair; 0 keeps nothing.
+#[derive(Clone)]
+pub struct TickHistory {
+    capacity: usize,
+    inner: Arc<RwLock<HashMap<Symbol, VecDeque<Tick>>>>,
+}
+
+impl Default for TickHistory {
+    fn default() -> Self {
+        Self::with_capacity(DEFAULT_CAPACITY)
+    }
+}
+
+impl TickHistory {
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            capacity,
+            inner: Arc::default(),
+        }
+    }
+
+    /// Appends `t`, which must be newer than the pair's last tick.
+    pub async fn record(&self, t: &Tick) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut w = self.inner.write().await;
+        let q = w.entry(t.pair.clone()).or_default();
+        q.push_back(t.clone());
+        if q.len() > self.capacity {
+            q.pop_front();
+        }
+    }
+
+    /// Ticks with `from_ms <= ts_ms <= to_ms`.
+    pub async fn range(&self, pair: &str, from_ms: i64, to_ms: i64) -> Vec<Tick> {
+        let r = self.inner.read().await;
+        r.get(pair).map_or_else(Vec::new, |q| {
+            q.iter()
+                .filter(|t| (from_ms..=to_ms).contains(&t.ts_ms))
+                .cloned()
+                .collect()
+        })
+    }
+
+    /// Up to `n` ticks right before `ts_ms`, oldest first.
+    pub async fn before(&self, pair: &str, ts_ms: i64, n: usize) -> Vec<Tick> {
+        let r = self.inner.read().await;
+        let Some(q) = r.get(pair) else {
+            return Vec::new();
+        };
+        let end = q.partition_point(|t| t.ts_ms < ts_ms);
+        q.range(end.saturating_sub(n)..end).cloned().collect()
+    }
+
+    /// Refiles `from`'s ticks under `to`, ahead of any `to` already has.
+    /// Returns how many moved.
+    pub async fn rename(&self, from: &str, to: &Symbol) -> usize {
+        let mut w = self.inner.write().await;
+        let Some(mut moved) = w.remove(from) else {
+            return 0;
+        };
+        for t in moved.iter_mut() {
+            t.pair = to.clone();
+        }
+        let n = moved.len();
+        let q = w.entry(to.clone()).or_default();
+        moved.append(q);
+        while moved.len() > self.capacity {
+            moved.pop_front();
+        }
+        *q = moved;
+        n
+    }
+
+    /// Drops `pair`'s ticks and returns how many there were.
+    pub async fn forget(&self, pair: &str) -> usize {
+        self.inner.write().await.remove(pair).map_or(0, |q| q.len())
+    }
+
+    /// How many ticks are kept for `pair`.
+    pub async fn len(&self, pair: &str) -> usize {
+        self.inner.read().await.get(pair).map_or(0, VecDeque::len)
+    }
+
+    /// When the oldest kept tick for `pair` was quoted.
+    pub async fn oldest(&self, pair: &str) -> Option<i64> {
+        let r = self.inner.read().await;
+        r.get(pair).and_then(|q| q.front()).map(|t| t.ts_ms)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal::Decimal;
+
+    #[tokio::test]
+    async fn keeps_the_latest_ticks_per_pair_and_serves_ranges() {
+        let h = TickHistory::with_capacity(3);
+        for ts in 1..=5 {
+            h.record(&Tick::new("BTC/USDT", Decimal::from(ts), ts))
+                .await;
+        }
+        h.record(&Tick::new("ETH/USDT", Decimal::ONE, 1)).await;
+        let ts = |v: Vec<Tick>| v.iter().map(|t| t.ts_ms).collect::<Vec<_>>();
+        assert_eq!(h.oldest("BTC/USDT").await, Some(3));
+        assert_eq!(ts(h.range("BTC/USDT", 4, 10).await), vec![4, 5]);
+        assert_eq!(ts(h.before("BTC/USDT", 5, 10).await), vec![3, 4]);
+        assert_eq!(ts(h.before("BTC/USDT", 5, 1).await), vec![4]);
+        assert!(h.range("SOL/USDT", 0, 10).await.is_empty());
+
+        let off = TickHistory::with_capacity(0);
+        off.record(&Tick::new("BTC/USDT", Decimal::ONE, 1)).await;
+        assert_eq!(off.oldest("BTC/USDT").await, None);
+    }
+}
@@ -0,0 +1,143 @@
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+
+use super::trades::TradePrint;
+use super::Tick;
+use crate::instruments::symbol::Symbol;
+
+/// Messages buffered per pair for each subscriber.
+pub const TOPIC_CAPACITY: usize = 4_096;
+
+/// One message on a pair's topic: a tick the cache accepted into the
+/// aggregate, or a trade print.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum MarketData {
+    Tick(Tick),
+    Trade(TradePrint),
+}
+
+impl MarketData {
+    pub fn pair(&self) -> &str {
+        match self {
+            MarketData::Tick(t) => &t.pair,
+            MarketData::Trade(t) => &t.pair,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            MarketData::Tick(_) => "tick",
+            MarketData::Trade(_) => "trade",
+        }
+    }
+}
+
+/// In-process pub/sub for market data, one bounded topic per pair. The
+/// oracle cache publishes and every consumer subscribes to the pairs it
+/// reads, so none of them calls another. A subscriber that falls more than
+/// the topic's capacity behind skips the oldest messages and is told how
+/// many; publishers never wait.
+#[derive(Clone)]
+pub struct MarketHub {
+    capacity: usize,
+    topics: Arc<RwLock<HashMap<Symbol, broadcast::Sender<MarketData>>>>,
+}
+
+impl Default for MarketHub {
+    fn default() -> Self {
+        Self::with_capacity(TOPIC_CAPACITY)
+    }
+}
+
+impl MarketHub {
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            topics: Arc::default(),
+        }
+    }
+
+    /// Sends `data` to its pair's subscribers and returns how many there
+    /// were. Nobody listening is fine.
+    pub fn publish(&self, data: MarketData) -> usize {
+        let r = self.topics.read().unwrap();
+        r.get(data.pair())
+            .map_or(0, |tx| tx.send(data).unwrap_or(0))
+    }
+
+    pub fn subscribe(&self, pair: &str) -> broadcast::Receiver<MarketData> {
+        if let Some(tx) = self.topics.read().unwrap().get(pair) {
+            return tx.subscribe();
+        }
+        let mut w = self.topics.write().unwrap();
+        w.entry(Symbol::intern(pair))
+            .or_insert_with(|| broadcast::channel(self.capacity).0)
+            .subscribe()
+    }
+
+    pub fn subscribers(&self, pair: &str) -> usize {
+        let r = self.topics.read().unwrap();
+        r.get(pair).map_or(0, broadcast::Sender::receiver_count)
+    }
+
+    /// Drops `pair`'s topic; its subscribers see it closed.
+    pub fn close(&self, pair: &str) {
+        self.topics.write().unwrap().remove(pair);
+    }
+
+    /// Moves `from`'s topic, with its subscribers, to `to` unless `to`
+    /// already has one; subscribers of the old name then resubscribe.
+    pub fn rename(&self, from: &str, to: &Symbol) {
+        let mut w = self.topics.write().unwrap();
+        if let Some(tx) = w.remove(from) {
+            w.entry(to.clone()).or_insert(tx);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::oracle_service::trades::Aggressor;
+    use rust_decimal::Decimal;
+    use tokio::sync::broadcast::error::TryRecvError;
+
+    fn print(pair: &str, ts_ms: i64) -> MarketData {
+        MarketData::Trade(TradePrint {
+            pair: pair.into(),
+            price: Decimal::ONE,
+            size: Decimal::ONE,
+            side: Aggressor::Buy,
+            ts_ms,
+            seq: None,
+        })
+    }
+
+    #[test]
+    fn subscribers_only_see_their_pair_and_lag_instead_of_blocking() {
+        let hub = MarketHub::with_capacity(2);
+        assert_eq!(hub.publish(print("BTC/USDT", 1)), 0);
+        let mut btc = hub.subscribe("BTC/USDT");
+        let mut eth = hub.subscribe("ETH/USDT");
+        assert_eq!(hub.publish(print("BTC/USDT", 2)), 1);
+        assert_eq!(btc.try_recv().unwrap().pair(), "BTC/USDT");
+        assert!(matches!(eth.try_recv(), Err(TryRecvError::Empty)));
+
+        for ts in 3..=5 {
+            hub.publish(print("BTC/USDT", ts));
+        }
+        assert!(matches!(btc.try_recv(), Err(TryRecvError::Lagged(1))));
+        let MarketData::Trade(t) = btc.try_recv().unwrap() else {
+            panic!("expected a print");
+        };
+        assert_eq!(t.ts_ms, 4);
+
+        hub.rename("BTC/USDT", &Symbol::intern("XBT/USDT"));
+        assert_eq!(hub.subscribers("XBT/USDT"), 1);
+        assert_eq!(hub.publish(print("XBT/USDT", 6)), 1);
+    }
+}
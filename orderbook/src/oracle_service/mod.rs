@@ -1,26 +1,399 @@
-use futures_util::StreamExt;
 use rust_decimal::Decimal;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicI64, Ordering};
 use std::{collections::HashMap, sync::Arc, time::Duration};
 use tokio::{sync::RwLock, time::sleep};
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::handshake::client::Request;
+use tokio_tungstenite::tungstenite::http::{header::AUTHORIZATION, HeaderValue};
 use tokio_tungstenite::{connect_async, tungstenite::protocol::Message};
 
-#[derive(Debug, Clone, Deserialize)]
+use crate::index::{IndexCache, IndexPrice};
+use crate::instruments::symbol::Symbol;
+use crate::instruments::{Instrument, InstrumentRegistry};
+use crate::metrics::Metrics;
+use deadman::DeadMan;
+use deflate::Feed;
+use history::TickHistory;
+use hub::{MarketData, MarketHub};
+use sequence::{resend_request, SeqCheck, SeqTracker};
+use skew::ClockSkew;
+use trades::{FeedMessage, TradePrint};
+
+pub mod deadman;
+pub mod deflate;
+pub mod history;
+pub mod hub;
+pub mod sequence;
+pub mod skew;
+pub mod trades;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Tick {
-    pub pair: String,
+    pub pair: Symbol,
     pub price: Decimal,
+    #[serde(default)]
+    pub bid: Option<Decimal>,
+    #[serde(default)]
+    pub ask: Option<Decimal>,
     pub ts_ms: i64,
+    /// The venue that quoted the tick; absent on a single-feed setup.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    /// Position in the connection's stream, when the feed numbers ticks.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seq: Option<u64>,
+    /// When the cache accepted the tick, by the local clock.
+    #[serde(skip)]
+    pub received_ms: Option<i64>,
+    /// How far ahead of the local clock the source's clock was estimated
+    /// to run when the tick arrived; see [`ClockSkew`].
+    #[serde(skip)]
+    pub skew_ms: i64,
+}
+
+impl Tick {
+    pub fn new(pair: impl Into<Symbol>, price: Decimal, ts_ms: i64) -> Self {
+        Self {
+            pair: pair.into(),
+            price,
+            bid: None,
+            ask: None,
+            ts_ms,
+            source: None,
+            seq: None,
+            received_ms: None,
+            skew_ms: 0,
+        }
+    }
+
+    /// `ts_ms` by the local clock.
+    pub fn local_ts_ms(&self) -> i64 {
+        self.ts_ms - self.skew_ms
+    }
+}
+
+pub const DEFAULT_STALE_AFTER: Duration = Duration::from_secs(5);
+
+/// How far ahead of the local clock a tick may be stamped.
+pub const DEFAULT_MAX_FUTURE: Duration = Duration::from_secs(1);
+
+/// What [`OracleCache::set`] did with a tick.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TickOutcome {
+    Accepted,
+    /// Same `ts_ms` as the cached tick; dropped.
+    Duplicate,
+    /// Older than the cached tick; dropped.
+    OutOfOrder,
+    /// Stamped further ahead of the local clock than the cache tolerates;
+    /// dropped.
+    Future,
+}
+
+impl TickOutcome {
+    pub fn name(&self) -> &'static str {
+        match self {
+            TickOutcome::Accepted => "accepted",
+            TickOutcome::Duplicate => "duplicate",
+            TickOutcome::OutOfOrder => "out_of_order",
+            TickOutcome::Future => "future",
+        }
+    }
+}
+
+/// Moves the tick at `old` to `new`, renamed to `pair`, unless `new`
+/// holds a tick at least as recent. Returns whether there was one.
+fn refile<K: Eq + std::hash::Hash>(
+    w: &mut HashMap<K, Tick>,
+    old: K,
+    new: K,
+    pair: &Symbol,
+) -> bool {
+    let Some(mut t) = w.remove(&old) else {
+        return false;
+    };
+    t.pair = pair.clone();
+    if w.get(&new).is_none_or(|cur| cur.ts_ms < t.ts_ms) {
+        w.insert(new, t);
+    }
+    true
 }
 
-#[derive(Clone, Default)]
+fn order_of(cached: Option<&Tick>, t: &Tick) -> TickOutcome {
+    match cached {
+        Some(cur) if t.ts_ms == cur.ts_ms => TickOutcome::Duplicate,
+        Some(cur) if t.ts_ms < cur.ts_ms => TickOutcome::OutOfOrder,
+        _ => TickOutcome::Accepted,
+    }
+}
+
+/// Latest tick per pair, plus per venue for the configured sources. The
+/// per-pair entry is the aggregate: the newest tick from any venue.
+/// Synthetic pairs get theirs derived from their legs' aggregates.
+#[derive(Clone)]
 pub struct OracleCache {
-    inner: Arc<RwLock<HashMap<String, Tick>>>,
+    inner: Arc<RwLock<HashMap<Symbol, Tick>>>,
+    venues: Arc<RwLock<HashMap<(Symbol, String), Tick>>>,
+    sources: Arc<Vec<String>>,
+    synthetics: Arc<Vec<Instrument>>,
+    /// Wall-clock arrival of the last accepted tick, 0 before the first.
+    last_received_ms: Arc<AtomicI64>,
+    dead_man: DeadMan,
+    index: IndexCache,
+    hub: MarketHub,
+    history: TickHistory,
+    skew: ClockSkew,
+    stale_after_ms: i64,
+    max_future_ms: i64,
+    metrics: Metrics,
+}
+
+impl Default for OracleCache {
+    fn default() -> Self {
+        Self::with_stale_after(DEFAULT_STALE_AFTER)
+    }
 }
 
 impl OracleCache {
-    pub async fn set(&self, t: Tick) {
+    pub fn with_stale_after(stale_after: Duration) -> Self {
+        Self {
+            inner: Arc::default(),
+            venues: Arc::default(),
+            sources: Arc::default(),
+            synthetics: Arc::default(),
+            last_received_ms: Arc::default(),
+            dead_man: DeadMan::default(),
+            index: IndexCache::default(),
+            hub: MarketHub::default(),
+            history: TickHistory::default(),
+            skew: ClockSkew::default(),
+            stale_after_ms: stale_after.as_millis() as i64,
+            max_future_ms: DEFAULT_MAX_FUTURE.as_millis() as i64,
+            metrics: Metrics::default(),
+        }
+    }
+
+    /// Drops ticks stamped more than `max_future` ahead of the local
+    /// clock. Skew within it is estimated per source and taken out of
+    /// staleness checks.
+    pub fn with_max_future(mut self, max_future: Duration) -> Self {
+        self.max_future_ms = max_future.as_millis() as i64;
+        self
+    }
+
+    /// Counts dropped ticks into `metrics` as `oracle_ticks_dropped_total`.
+    pub fn with_metrics(mut self, metrics: Metrics) -> Self {
+        self.metrics = metrics;
+        self
+    }
+
+    /// Whether `t` is inside the staleness window, timed by the local
+    /// clock, so a feed whose clock runs ahead still goes stale.
+    pub fn is_fresh(&self, t: &Tick, now: i64) -> bool {
+        now - t.local_ts_ms() <= self.stale_after_ms
+    }
+
+    /// Whether `pair` has a tick inside the staleness window.
+    pub async fn has_fresh_price(&self, pair: &str, now: i64) -> bool {
+        match self.get_tick(pair).await {
+            Some(t) => self.is_fresh(&t, now),
+            None => false,
+        }
+    }
+
+    pub fn clock_skew(&self) -> &ClockSkew {
+        &self.skew
+    }
+
+    /// Arms the fail-safe for total oracle loss; see [`DeadMan`].
+    pub fn with_dead_man(mut self, dead_man: DeadMan) -> Self {
+        self.dead_man = dead_man;
+        self
+    }
+
+    /// Keeps up to `capacity` accepted ticks per pair for re-evaluation.
+    pub fn with_history(mut self, capacity: usize) -> Self {
+        self.history = TickHistory::with_capacity(capacity);
+        self
+    }
+
+    pub fn history(&self) -> &TickHistory {
+        &self.history
+    }
+
+    pub fn dead_man(&self) -> &DeadMan {
+        &self.dead_man
+    }
+
+    /// Venues orders may name as their `price_source`. Ticks from other
+    /// sources only feed the aggregate.
+    pub fn with_sources(mut self, sources: Vec<String>) -> Self {
+        self.sources = Arc::new(sources);
+        self
+    }
+
+    pub fn sources(&self) -> &[String] {
+        &self.sources
+    }
+
+    /// Pairs whose ticks are derived from their legs'; see
+    /// [`Instrument::synthetic`].
+    pub fn with_synthetics(mut self, synthetics: Vec<Instrument>) -> Self {
+        self.synthetics = Arc::new(synthetics);
+        self
+    }
+
+    /// `Ok` for the aggregate or a configured venue.
+    pub fn check_source(&self, source: Option<&str>) -> Result<(), String> {
+        match source {
+            Some(s) if !self.sources.iter().any(|c| c == s) => Err(format!(
+                "unknown price_source '{s}'; configured: [{}]",
+                self.sources.join(", ")
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Caches `t` unless the pair (or, for a configured venue, that venue)
+    /// already holds a tick at least as new, so several feeds or a replay
+    /// writing the same pair never move it back. A venue tick that is new
+    /// for its venue but behind another venue is kept for the venue only.
+    /// Only ticks that advance the aggregate reach the index, the history
+    /// and the pair's topic on the hub. Ticks from too far in the future
+    /// are dropped first.
+    pub async fn set(&self, mut t: Tick) -> TickOutcome {
+        let now = crate::utils::now_ms();
+        t.received_ms = Some(now);
+        if t.ts_ms - now > self.max_future_ms {
+            return self.dropped(&t, TickOutcome::Future);
+        }
+        let source = t.source.as_deref().unwrap_or("default");
+        t.skew_ms = self.skew.observe(source, t.ts_ms, now);
+        self.metrics.set(
+            "oracle_clock_skew_ms",
+            &[("source", source)],
+            t.skew_ms as f64,
+        );
+        let venue = t
+            .source
+            .clone()
+            .filter(|s| self.sources.contains(s))
+            .map(|s| (t.pair.clone(), s));
+        if let Some(key) = venue.clone() {
+            let mut v = self.venues.write().await;
+            let outcome = order_of(v.get(&key), &t);
+            if outcome != TickOutcome::Accepted {
+                return self.dropped(&t, outcome);
+            }
+            v.insert(key, t.clone());
+            self.received();
+        }
+        let outcome = self.advance(&t).await;
+        if outcome != TickOutcome::Accepted {
+            if venue.is_some() {
+                return TickOutcome::Accepted;
+            }
+            return self.dropped(&t, outcome);
+        }
+        outcome
+    }
+
+    /// Makes `t` its pair's aggregate unless that is at least as new, and
+    /// passes it on to the index, the history, the hub and the synthetic
+    /// pairs it is a leg of.
+    async fn advance(&self, t: &Tick) -> TickOutcome {
         let mut w = self.inner.write().await;
-        w.insert(t.pair.clone(), t);
+        let outcome = order_of(w.get(&t.pair), t);
+        if outcome != TickOutcome::Accepted {
+            return outcome;
+        }
+        self.index.observe(t).await;
+        self.history.record(t).await;
+        w.insert(t.pair.clone(), t.clone());
+        drop(w);
+        self.received();
+        self.hub.publish(MarketData::Tick(t.clone()));
+        self.derive(&t.pair).await;
+        outcome
+    }
+
+    /// Re-prices the synthetic pairs `leg` feeds. Each gets a tick while
+    /// both its legs are fresh, at its own price precision. It is stamped
+    /// with the newer leg's `ts_ms`, or 1 ms past the pair's last tick when
+    /// that is not newer, so a move of the older leg still lands.
+    async fn derive(&self, leg: &Symbol) {
+        let now = crate::utils::now_ms();
+        for i in self.synthetics.iter() {
+            let Some(s) = i
+                .synthetic
+                .as_ref()
+                .filter(|s| s.legs.iter().any(|l| l == leg))
+            else {
+                continue;
+            };
+            let (Some(a), Some(b)) = (
+                self.get_tick(&s.legs[0]).await,
+                self.get_tick(&s.legs[1]).await,
+            ) else {
+                continue;
+            };
+            if !self.is_fresh(&a, now) || !self.is_fresh(&b, now) {
+                continue;
+            }
+            let px = |x: Decimal, y: Decimal| s.price(x, y).map(|p| p.round_dp(i.price_scale));
+            let Some(price) = px(a.price, b.price) else {
+                continue;
+            };
+            let quote = |x: Option<Decimal>, y: Option<Decimal>| px(x?, y?);
+            let last = self.get_tick(&i.symbol).await.map_or(i64::MIN, |t| t.ts_ms);
+            let ts_ms = a.ts_ms.max(b.ts_ms).max(last.saturating_add(1));
+            let t = Tick {
+                bid: quote(a.bid, b.ask),
+                ask: quote(a.ask, b.bid),
+                received_ms: Some(now),
+                skew_ms: a.skew_ms.max(b.skew_ms),
+                ..Tick::new(i.symbol.as_str(), price, ts_ms)
+            };
+            Box::pin(self.advance(&t)).await;
+        }
+    }
+
+    fn received(&self) {
+        self.last_received_ms
+            .store(crate::utils::now_ms(), Ordering::Relaxed);
+    }
+
+    /// When a tick for any pair was last accepted, by the local clock.
+    pub fn last_received_ms(&self) -> Option<i64> {
+        Some(self.last_received_ms.load(Ordering::Relaxed)).filter(|ms| *ms > 0)
+    }
+
+    fn dropped(&self, t: &Tick, outcome: TickOutcome) -> TickOutcome {
+        self.metrics.incr(
+            "oracle_ticks_dropped_total",
+            &[("pair", &t.pair), ("reason", outcome.name())],
+            1.0,
+        );
+        outcome
+    }
+
+    pub async fn get_index(&self, pair: &str) -> Option<IndexPrice> {
+        self.index.get(pair).await
+    }
+
+    pub fn index(&self) -> &IndexCache {
+        &self.index
+    }
+
+    /// Publishes a trade print on its pair's topic; prints are not cached.
+    pub fn record_trade(&self, t: TradePrint) {
+        self.hub.publish(MarketData::Trade(t));
+    }
+
+    /// Where accepted ticks and trade prints are published, per pair.
+    pub fn hub(&self) -> &MarketHub {
+        &self.hub
     }
 
     pub async fn get_price(&self, pair: &str) -> Option<(Decimal, i64)> {
@@ -28,16 +401,79 @@ impl OracleCache {
         r.get(pair).map(|t| (t.price, t.ts_ms))
     }
 
-    pub async fn pairs(&self) -> Vec<String> {
+    pub async fn get_tick(&self, pair: &str) -> Option<Tick> {
+        let r = self.inner.read().await;
+        r.get(pair).cloned()
+    }
+
+    /// The latest tick `source` quoted for `pair`.
+    pub async fn get_venue_tick(&self, pair: &str, source: &str) -> Option<Tick> {
+        let r = self.venues.read().await;
+        r.get(&(Symbol::intern(pair), source.to_string())).cloned()
+    }
+
+    pub async fn pairs(&self) -> Vec<Symbol> {
         let r = self.inner.read().await;
         r.keys().cloned().collect()
     }
+
+    /// How many ticks the cache holds for `pair`: the latest, per venue
+    /// and in the history.
+    pub async fn cached_ticks(&self, pair: &str) -> usize {
+        let latest = usize::from(self.inner.read().await.contains_key(pair));
+        let venues = self.venues.read().await;
+        let venues = venues.keys().filter(|(p, _)| p == pair).count();
+        latest + venues + self.history.len(pair).await
+    }
+
+    /// Refiles everything cached under `from` (latest ticks, venue ticks,
+    /// index and history) under `to`, so the pair keeps its price across
+    /// a rename. Where `to` already has a newer tick, that one is kept.
+    /// Returns how many ticks moved.
+    pub async fn rename_pair(&self, from: &str, to: &Symbol) -> usize {
+        let mut inner = self.inner.write().await;
+        let mut moved = usize::from(refile(&mut inner, Symbol::intern(from), to.clone(), to));
+        drop(inner);
+        let mut venues = self.venues.write().await;
+        let keys: Vec<_> = venues.keys().filter(|(p, _)| p == from).cloned().collect();
+        for (pair, source) in keys {
+            let new = (to.clone(), source.clone());
+            moved += usize::from(refile(&mut venues, (pair, source), new, to));
+        }
+        drop(venues);
+        self.index.rename(from, to).await;
+        self.hub.rename(from, to);
+        moved + self.history.rename(from, to).await
+    }
+
+    /// Drops everything cached for `pair` and closes its topic, for a
+    /// delisted pair. Returns how many ticks went.
+    pub async fn forget_pair(&self, pair: &str) -> usize {
+        let mut dropped = usize::from(self.inner.write().await.remove(pair).is_some());
+        let mut venues = self.venues.write().await;
+        let before = venues.len();
+        venues.retain(|(p, _), _| p != pair);
+        dropped += before - venues.len();
+        drop(venues);
+        self.index.forget(pair).await;
+        self.hub.close(pair);
+        dropped + self.history.forget(pair).await
+    }
 }
 
+#[derive(Clone)]
 pub struct OracleWsClient {
     pub endpoint: String,
     pub pair: Option<String>,
+    /// The feed's `channel` (`trades` for prints); absent for ticks.
+    pub channel: Option<String>,
     pub reconnect_backoff: Duration,
+    pub instruments: InstrumentRegistry,
+    /// Offer permessage-deflate; servers that decline it are read plain.
+    pub compression: bool,
+    /// Sent as `Authorization: Bearer` on every connect. A rejected token
+    /// is retried on the usual backoff.
+    pub auth_token: Option<String>,
 }
 
 impl Default for OracleWsClient {
@@ -45,7 +481,11 @@ impl Default for OracleWsClient {
         Self {
             endpoint: "ws://127.0.0.1:9001/ws".into(),
             pair: None,
+            channel: None,
             reconnect_backoff: Duration::from_secs(2),
+            instruments: InstrumentRegistry::default(),
+            compression: false,
+            auth_token: None,
         }
     }
 }
@@ -55,20 +495,51 @@ impl OracleWsClient {
         tokio::spawn(async move {
             let mut backoff = self.reconnect_backoff;
             loop {
-                let url = build_url(&self.endpoint, self.pair.as_deref());
-                tracing::info!("oracle-ws: connecting to {}", url);
-
-                match connect_async(&url).await {
-                    Ok((ws_stream, _resp)) => {
-                        tracing::info!("oracle-ws: connected");
+                tracing::info!("oracle-ws: connecting to {}", self.url());
+                match self.connect().await {
+                    Ok(mut feed) => {
+                        tracing::info!(deflate = feed.is_deflated(), "oracle-ws: connected");
                         backoff = self.reconnect_backoff;
 
-                        let (_, mut read) = ws_stream.split();
-                        while let Some(msg) = read.next().await {
+                        let mut seqs = SeqTracker::default();
+                        while let Some(msg) = feed.next().await {
                             match msg {
                                 Ok(Message::Text(txt)) => {
-                                    match serde_json::from_str::<Tick>(&txt) {
-                                        Ok(tick) => cache.set(tick).await,
+                                    match serde_json::from_str::<FeedMessage>(&txt) {
+                                        Ok(msg) => {
+                                            if let Some(seq) = msg.seq() {
+                                                let metrics = &cache.metrics;
+                                                if !sequenced(&mut feed, &mut seqs, metrics, seq)
+                                                    .await
+                                                {
+                                                    continue;
+                                                }
+                                            }
+                                            let mut tick = match msg {
+                                                FeedMessage::Tick(t) => t,
+                                                FeedMessage::Trade(t) => {
+                                                    self.trade(&cache, t);
+                                                    continue;
+                                                }
+                                            };
+                                            match self.instruments.normalize(&tick.pair) {
+                                                Ok(pair) => {
+                                                    tick.pair = pair.into();
+                                                    let ts_ms = tick.ts_ms;
+                                                    let outcome = cache.set(tick).await;
+                                                    if outcome != TickOutcome::Accepted {
+                                                        tracing::debug!(
+                                                            ts_ms,
+                                                            "oracle-ws: dropping {} tick",
+                                                            outcome.name()
+                                                        );
+                                                    }
+                                                }
+                                                Err(e) => {
+                                                    tracing::debug!("oracle-ws: dropping tick: {e}")
+                                                }
+                                            }
+                                        }
                                         Err(e) => {
                                             tracing::warn!("oracle-ws: bad json: {e}; raw={txt}")
                                         }
@@ -104,14 +575,295 @@ impl OracleWsClient {
     }
 }
 
-fn build_url(base: &str, pair: Option<&str>) -> String {
+impl OracleWsClient {
+    /// The default endpoint with `ORACLE_WS_DEFLATE` and `ORACLE_WS_TOKEN`
+    /// applied.
+    pub fn from_env(instruments: InstrumentRegistry) -> Self {
+        Self {
+            instruments,
+            compression: std::env::var("ORACLE_WS_DEFLATE")
+                .is_ok_and(|v| matches!(v.trim(), "1" | "true" | "yes")),
+            auth_token: std::env::var("ORACLE_WS_TOKEN")
+                .ok()
+                .filter(|t| !t.is_empty()),
+            ..Self::default()
+        }
+    }
+
+    /// The endpoint with this client's pair and channel, as connected to.
+    pub fn url(&self) -> String {
+        build_url(
+            &self.endpoint,
+            self.pair.as_deref(),
+            self.channel.as_deref(),
+        )
+    }
+
+    async fn connect(&self) -> Result<Feed, String> {
+        let (url, token) = (self.url(), self.auth_token.as_deref());
+        if self.compression {
+            return deflate::connect(&url, token).await;
+        }
+        let req = client_request(&url, token)?;
+        connect_async(req)
+            .await
+            .map(|(ws_stream, _resp)| Feed::Plain(ws_stream))
+            .map_err(|e| e.to_string())
+    }
+
+    /// Opens one connection and drops it, for a start-up check. Returns
+    /// whether the feed agreed to compress.
+    pub async fn probe(&self, timeout: Duration) -> Result<bool, String> {
+        match tokio::time::timeout(timeout, self.connect()).await {
+            Ok(feed) => feed.map(|f| f.is_deflated()),
+            Err(_) => Err(format!("no answer within {timeout:?}")),
+        }
+    }
+
+    fn trade(&self, cache: &OracleCache, mut t: TradePrint) {
+        match self.instruments.normalize(&t.pair) {
+            Ok(pair) => {
+                t.pair = pair;
+                cache.record_trade(t);
+            }
+            Err(e) => tracing::debug!("oracle-ws: dropping trade: {e}"),
+        }
+    }
+}
+
+/// Checks `seq` against the connection's stream and asks the feed to
+/// resend what it skipped; false for a tick to drop. Resent ticks go to the
+/// cache like any other, which keeps them only if nothing newer arrived.
+async fn sequenced(feed: &mut Feed, seqs: &mut SeqTracker, metrics: &Metrics, seq: u64) -> bool {
+    match seqs.observe(seq) {
+        SeqCheck::InOrder => true,
+        SeqCheck::Gap { from, to } => {
+            tracing::warn!(from, to, "oracle-ws: sequence gap; requesting resend");
+            metrics.incr("oracle_seq_gaps_total", &[], 1.0);
+            metrics.incr("oracle_seq_missing_total", &[], (to - from + 1) as f64);
+            if let Err(e) = feed.send_text(resend_request(from, to)).await {
+                tracing::warn!("oracle-ws: resend request failed: {e}");
+            }
+            true
+        }
+        SeqCheck::Recovered => {
+            metrics.incr("oracle_seq_recovered_total", &[], 1.0);
+            true
+        }
+        SeqCheck::Duplicate => {
+            tracing::debug!(seq, "oracle-ws: dropping repeated tick");
+            false
+        }
+    }
+}
+
+/// The upgrade request for `url`, carrying `token` as a bearer credential.
+fn client_request(url: &str, token: Option<&str>) -> Result<Request, String> {
+    let mut req = url.into_client_request().map_err(|e| e.to_string())?;
+    if let Some(t) = token {
+        let value = HeaderValue::from_str(&format!("Bearer {t}")).map_err(|e| e.to_string())?;
+        req.headers_mut().insert(AUTHORIZATION, value);
+    }
+    Ok(req)
+}
+
+fn build_url(base: &str, pair: Option<&str>, channel: Option<&str>) -> String {
+    if pair.is_none() && channel.is_none() {
+        return base.to_string();
+    }
+    let mut u = url::Url::parse(base).expect("invalid ws endpoint");
+    let mut q = u.query_pairs_mut();
     if let Some(p) = pair {
-        let mut u = url::Url::parse(base).expect("invalid ws endpoint");
-        let mut q = u.query_pairs_mut();
         q.append_pair("pair", p);
-        drop(q);
-        u.to_string()
-    } else {
-        base.to_string()
+    }
+    if let Some(c) = channel {
+        q.append_pair("channel", c);
+    }
+    drop(q);
+    u.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn connects_carry_the_bearer_token_when_configured() {
+        let url = "ws://127.0.0.1:9001/ws";
+        let req = client_request(url, Some("s3cret")).unwrap();
+        assert_eq!(req.headers()[AUTHORIZATION], "Bearer s3cret");
+        let req = client_request(url, None).unwrap();
+        assert!(!req.headers().contains_key(AUTHORIZATION));
+        assert!(client_request(url, Some("bad\ntoken")).is_err());
+    }
+
+    #[tokio::test]
+    async fn prints_and_accepted_ticks_reach_the_pairs_topic() {
+        let cache = OracleCache::default();
+        let mut rx = cache.hub().subscribe("BTC/USDT");
+        let raw = r#"{"pair":"BTC/USDT","price":100,"size":2,"side":"sell","ts_ms":5}"#;
+        let Ok(FeedMessage::Trade(t)) = serde_json::from_str(raw) else {
+            panic!("not a print");
+        };
+        cache.record_trade(t);
+        let Ok(MarketData::Trade(got)) = rx.try_recv() else {
+            panic!("expected the print");
+        };
+        assert_eq!((got.pair.as_str(), got.size), ("BTC/USDT", dec!(2)));
+        cache.set(Tick::new("BTC/USDT", dec!(100), 6)).await;
+        cache.set(Tick::new("BTC/USDT", dec!(90), 6)).await;
+        assert_eq!(rx.try_recv().unwrap().name(), "tick");
+        assert!(rx.try_recv().is_err());
+        assert_eq!(
+            build_url("ws://127.0.0.1:9001/ws", None, Some("trades")),
+            "ws://127.0.0.1:9001/ws?channel=trades"
+        );
+    }
+
+    #[tokio::test]
+    async fn older_and_duplicate_ticks_never_overwrite_newer_ones() {
+        let metrics = Metrics::default();
+        let cache = OracleCache::default().with_metrics(metrics.clone());
+        assert_eq!(
+            cache.set(Tick::new("BTC/USDT", dec!(100), 10)).await,
+            TickOutcome::Accepted
+        );
+        assert_eq!(
+            cache.set(Tick::new("BTC/USDT", dec!(90), 10)).await,
+            TickOutcome::Duplicate
+        );
+        assert_eq!(
+            cache.set(Tick::new("BTC/USDT", dec!(80), 5)).await,
+            TickOutcome::OutOfOrder
+        );
+        assert_eq!(cache.get_price("BTC/USDT").await, Some((dec!(100), 10)));
+        assert!(cache.last_received_ms().is_some());
+        let received = cache.get_tick("BTC/USDT").await.unwrap().received_ms;
+        assert!(received.is_some_and(|ms| ms > 10));
+        assert_eq!(cache.get_index("BTC/USDT").await.unwrap().samples, 1);
+
+        // Pairs are ordered independently.
+        assert_eq!(
+            cache.set(Tick::new("ETH/USDT", dec!(5), 1)).await,
+            TickOutcome::Accepted
+        );
+        assert_eq!(
+            cache.set(Tick::new("BTC/USDT", dec!(101), 11)).await,
+            TickOutcome::Accepted
+        );
+        let dropped = |reason| {
+            metrics.get(
+                "oracle_ticks_dropped_total",
+                &[("pair", "BTC/USDT"), ("reason", reason)],
+            )
+        };
+        assert_eq!(dropped("duplicate"), Some(1.0));
+        assert_eq!(dropped("out_of_order"), Some(1.0));
+    }
+
+    #[tokio::test]
+    async fn a_fast_feed_clock_is_corrected_and_far_future_ticks_dropped() {
+        let metrics = Metrics::default();
+        let cache = OracleCache::default().with_metrics(metrics.clone());
+        let now = crate::utils::now_ms();
+        assert_eq!(
+            cache
+                .set(Tick::new("BTC/USDT", dec!(100), now + 10_000))
+                .await,
+            TickOutcome::Future
+        );
+        assert!(cache.get_tick("BTC/USDT").await.is_none());
+
+        // 900ms ahead: kept, but aged from when it arrived.
+        assert_eq!(
+            cache.set(Tick::new("BTC/USDT", dec!(100), now + 900)).await,
+            TickOutcome::Accepted
+        );
+        let t = cache.get_tick("BTC/USDT").await.unwrap();
+        assert!(t.skew_ms > 400 && t.local_ts_ms() < now + 500);
+        assert!(cache.is_fresh(&t, now + 4_000));
+        assert!(!cache.is_fresh(&t, now + 5_500));
+        assert!(!cache.has_fresh_price("BTC/USDT", now + 5_500).await);
+        assert_eq!(cache.clock_skew().estimate("default"), t.skew_ms);
+        assert!(metrics
+            .get("oracle_clock_skew_ms", &[("source", "default")])
+            .is_some_and(|ms| ms > 400.0));
+        assert_eq!(
+            metrics.get(
+                "oracle_ticks_dropped_total",
+                &[("pair", "BTC/USDT"), ("reason", "future")],
+            ),
+            Some(1.0)
+        );
+    }
+
+    #[tokio::test]
+    async fn synthetic_pairs_are_priced_from_fresh_legs() {
+        let reg = InstrumentRegistry::default();
+        reg.add_synthetic("ETH/BTC:4:4", "ETH/USDT / BTC/USDT")
+            .unwrap();
+        let cache = OracleCache::with_stale_after(Duration::from_millis(500))
+            .with_synthetics(reg.synthetics());
+        let mut ticks = cache.hub().subscribe("ETH/BTC");
+        let now = crate::utils::now_ms();
+        cache.set(Tick::new("ETH/USDT", dec!(3000), now)).await;
+        assert!(cache.get_tick("ETH/BTC").await.is_none());
+        cache
+            .set(Tick {
+                bid: Some(dec!(59990)),
+                ask: Some(dec!(60010)),
+                ..Tick::new("BTC/USDT", dec!(60000), now + 5)
+            })
+            .await;
+        let t = cache.get_tick("ETH/BTC").await.unwrap();
+        assert_eq!((t.price, t.ts_ms, t.bid), (dec!(0.05), now + 5, None));
+        assert!(matches!(ticks.try_recv(), Ok(MarketData::Tick(t)) if t.pair == "ETH/BTC"));
+
+        // The older leg moving still re-prices the pair.
+        cache.set(Tick::new("ETH/USDT", dec!(3300), now + 1)).await;
+        let t = cache.get_tick("ETH/BTC").await.unwrap();
+        assert_eq!((t.price, t.ts_ms), (dec!(0.055), now + 6));
+        assert_eq!(cache.history().len("ETH/BTC").await, 2);
+
+        // A stale leg stops the derivation.
+        tokio::time::sleep(Duration::from_millis(600)).await;
+        let later = crate::utils::now_ms();
+        cache.set(Tick::new("ETH/USDT", dec!(3000), later)).await;
+        let t = cache.get_tick("ETH/BTC").await.unwrap();
+        assert_eq!(t.price, dec!(0.055));
+    }
+
+    #[tokio::test]
+    async fn configured_venues_are_kept_apart_from_the_aggregate() {
+        let cache = OracleCache::default().with_sources(vec!["a".into(), "b".into()]);
+        let at = |px, ts, source: &str| Tick {
+            source: Some(source.to_string()),
+            ..Tick::new("BTC/USDT", px, ts)
+        };
+        assert_eq!(
+            cache.set(at(dec!(100), 10, "a")).await,
+            TickOutcome::Accepted
+        );
+        // Behind venue a, but new for venue b.
+        assert_eq!(cache.set(at(dec!(99), 9, "b")).await, TickOutcome::Accepted);
+        assert_eq!(
+            cache.set(at(dec!(98), 8, "b")).await,
+            TickOutcome::OutOfOrder
+        );
+        // Unconfigured venues only feed the aggregate.
+        assert_eq!(
+            cache.set(at(dec!(101), 11, "c")).await,
+            TickOutcome::Accepted
+        );
+
+        assert_eq!(cache.get_price("BTC/USDT").await, Some((dec!(101), 11)));
+        let b = cache.get_venue_tick("BTC/USDT", "b").await.unwrap();
+        assert_eq!((b.price, b.ts_ms), (dec!(99), 9));
+        assert!(cache.get_venue_tick("BTC/USDT", "c").await.is_none());
+
+        assert!(cache.check_source(None).is_ok());
+        assert!(cache.check_source(Some("a")).is_ok());
+        assert!(cache.check_source(Some("c")).is_err());
     }
 }
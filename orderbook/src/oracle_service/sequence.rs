@@ -0,0 +1,92 @@
+use std::collections::BTreeSet;
+
+use serde_json::json;
+
+/// Widest gap asked to be resent; older missing ticks are given up on.
+pub const MAX_RESEND: u64 = 1_000;
+
+/// What an incoming `seq` means for the connection's stream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SeqCheck {
+    InOrder,
+    /// `from..=to` were skipped; this tick follows them.
+    Gap {
+        from: u64,
+        to: u64,
+    },
+    /// A tick that was missing, delivered late.
+    Recovered,
+    /// Seen already, or given up on; dropped.
+    Duplicate,
+}
+
+/// Tracks one connection's tick sequence numbers: detects skips and
+/// remembers what is still missing until a resend fills it.
+#[derive(Debug, Default)]
+pub struct SeqTracker {
+    next: Option<u64>,
+    missing: BTreeSet<u64>,
+}
+
+impl SeqTracker {
+    pub fn observe(&mut self, seq: u64) -> SeqCheck {
+        let next = self.next.unwrap_or(seq);
+        if seq < next {
+            return match self.missing.remove(&seq) {
+                true => SeqCheck::Recovered,
+                false => SeqCheck::Duplicate,
+            };
+        }
+        self.next = Some(seq + 1);
+        if seq == next {
+            return SeqCheck::InOrder;
+        }
+        let from = next.max(seq.saturating_sub(MAX_RESEND));
+        self.missing.extend(from..seq);
+        while self.missing.len() as u64 > MAX_RESEND {
+            self.missing.pop_first();
+        }
+        SeqCheck::Gap { from, to: seq - 1 }
+    }
+
+    /// Ticks still missing.
+    pub fn missing(&self) -> usize {
+        self.missing.len()
+    }
+}
+
+/// The resend request the oracle answers by replaying `from..=to`.
+pub fn resend_request(from: u64, to: u64) -> String {
+    json!({ "resend": { "from": from, "to": to } }).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gaps_are_reported_once_and_filled_by_late_ticks() {
+        let mut t = SeqTracker::default();
+        assert_eq!(t.observe(7), SeqCheck::InOrder);
+        assert_eq!(t.observe(8), SeqCheck::InOrder);
+        assert_eq!(t.observe(11), SeqCheck::Gap { from: 9, to: 10 });
+        assert_eq!(t.missing(), 2);
+        assert_eq!(t.observe(12), SeqCheck::InOrder);
+        assert_eq!(t.observe(10), SeqCheck::Recovered);
+        assert_eq!(t.observe(10), SeqCheck::Duplicate);
+        assert_eq!(t.observe(9), SeqCheck::Recovered);
+        assert_eq!(t.missing(), 0);
+        assert_eq!(t.observe(8), SeqCheck::Duplicate);
+
+        // A long outage only asks back for the newest ticks.
+        assert_eq!(
+            t.observe(5_013),
+            SeqCheck::Gap {
+                from: 4_013,
+                to: 5_012
+            }
+        );
+        assert_eq!(t.missing() as u64, MAX_RESEND);
+        assert_eq!(resend_request(9, 10), r#"{"resend":{"from":9,"to":10}}"#);
+    }
+}
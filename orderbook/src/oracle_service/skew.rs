@@ -0,0 +1,78 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+
+/// How many recent ticks per source the estimate looks back over.
+pub const SKEW_WINDOW: usize = 64;
+
+/// How far ahead of each feed's clock is of ours, per source. A tick
+/// arrives `latency - skew` after its `ts_ms`, and latency is never
+/// negative, so the largest `ts_ms - received_ms` over the recent ticks is
+/// the closest estimate. Only a clock running ahead is corrected: a feed
+/// that looks behind may simply be late, and correcting for that would
+/// hide it.
+#[derive(Clone)]
+pub struct ClockSkew {
+    window: usize,
+    inner: Arc<Mutex<HashMap<String, VecDeque<i64>>>>,
+}
+
+impl Default for ClockSkew {
+    fn default() -> Self {
+        Self::with_window(SKEW_WINDOW)
+    }
+}
+
+impl ClockSkew {
+    pub fn with_window(window: usize) -> Self {
+        Self {
+            window: window.max(1),
+            inner: Arc::default(),
+        }
+    }
+
+    /// Records a tick `source` stamped `ts_ms` that arrived at
+    /// `received_ms` and returns the source's estimate with it.
+    pub fn observe(&self, source: &str, ts_ms: i64, received_ms: i64) -> i64 {
+        let mut w = self.inner.lock().unwrap();
+        let q = w.entry(source.to_string()).or_default();
+        q.push_back(ts_ms - received_ms);
+        if q.len() > self.window {
+            q.pop_front();
+        }
+        ahead(q)
+    }
+
+    /// The current estimate for `source`, 0 before its first tick.
+    pub fn estimate(&self, source: &str) -> i64 {
+        let r = self.inner.lock().unwrap();
+        r.get(source).map_or(0, ahead)
+    }
+}
+
+fn ahead(offsets: &VecDeque<i64>) -> i64 {
+    offsets.iter().copied().max().unwrap_or(0).max(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn estimates_how_far_ahead_each_source_runs() {
+        let skew = ClockSkew::with_window(3);
+        // Fast by 800ms, arriving after 20 to 100ms on the wire.
+        assert_eq!(skew.observe("a", 1_780, 1_000), 780);
+        assert_eq!(skew.observe("a", 2_700, 2_000), 780);
+        assert_eq!(skew.observe("a", 3_790, 3_000), 790);
+        // A slow clock is left alone.
+        assert_eq!(skew.observe("b", 900, 1_000), 0);
+        assert_eq!(skew.estimate("a"), 790);
+        assert_eq!(skew.estimate("c"), 0);
+
+        // Old samples age out of the window.
+        for ts in [4_000, 5_000, 6_000] {
+            skew.observe("a", ts + 100, ts);
+        }
+        assert_eq!(skew.estimate("a"), 100);
+    }
+}
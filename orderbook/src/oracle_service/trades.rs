@@ -0,0 +1,67 @@
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+use super::Tick;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Aggressor {
+    Buy,
+    Sell,
+}
+
+/// One execution on the pair's market, as streamed on the oracle's
+/// `trades` channel.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TradePrint {
+    pub pair: String,
+    pub price: Decimal,
+    pub size: Decimal,
+    pub side: Aggressor,
+    pub ts_ms: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seq: Option<u64>,
+}
+
+/// A text frame from the feed. Prints are tried first: they carry `size`
+/// and `side`, and would otherwise also read as a tick.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum FeedMessage {
+    Trade(TradePrint),
+    Tick(Tick),
+}
+
+impl FeedMessage {
+    pub fn seq(&self) -> Option<u64> {
+        match self {
+            FeedMessage::Trade(t) => t.seq,
+            FeedMessage::Tick(t) => t.seq,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn prints_and_ticks_are_told_apart() {
+        let print =
+            r#"{"pair":"BTC/USDT","price":100.5,"size":0.25,"side":"buy","ts_ms":7,"seq":3}"#;
+        match serde_json::from_str::<FeedMessage>(print).unwrap() {
+            FeedMessage::Trade(t) => {
+                assert_eq!(
+                    (t.size, t.side, t.seq),
+                    (dec!(0.25), Aggressor::Buy, Some(3))
+                )
+            }
+            m => panic!("read as {m:?}"),
+        }
+        let tick = r#"{"pair":"BTC/USDT","price":100.5,"bid":100,"ask":101,"ts_ms":7,"seq":4}"#;
+        let m = serde_json::from_str::<FeedMessage>(tick).unwrap();
+        assert!(matches!(m, FeedMessage::Tick(_)));
+        assert_eq!(m.seq(), Some(4));
+    }
+}
@@ -0,0 +1,99 @@
+use std::sync::{Arc, RwLock};
+
+use serde::{Deserialize, Serialize};
+
+use crate::accounts::purge::PurgeMode;
+use crate::entities::order::{Order, OrderStatus};
+use crate::instruments::symbol::Symbol;
+use crate::utils::now_ms;
+
+/// What an operator did to an order.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditAction {
+    Cancel,
+    Suspend,
+    Transition,
+    /// A status set outside the lifecycle.
+    Force,
+}
+
+/// One operator intervention, recorded after the write went through.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct AuditEntry {
+    pub seq: u64,
+    pub ts_ms: i64,
+    pub action: AuditAction,
+    pub order_id: String,
+    pub pair: Symbol,
+    /// The order's owner; the operator does not act as it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub account_id: Option<String>,
+    pub from: OrderStatus,
+    pub to: OrderStatus,
+    pub reason: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub operator: Option<String>,
+}
+
+/// Append-only, in-memory trail of admin interventions on orders.
+#[derive(Clone, Default)]
+pub struct AuditLog {
+    inner: Arc<RwLock<Vec<AuditEntry>>>,
+}
+
+impl AuditLog {
+    /// Records that `action` moved `before` to `to`.
+    pub fn record(
+        &self,
+        action: AuditAction,
+        before: &Order,
+        to: OrderStatus,
+        reason: &str,
+        operator: Option<&str>,
+    ) -> AuditEntry {
+        let mut w = self.inner.write().unwrap();
+        let entry = AuditEntry {
+            seq: w.last().map_or(1, |e| e.seq + 1),
+            ts_ms: now_ms(),
+            action,
+            order_id: before.id.clone(),
+            pair: before.pair.clone(),
+            account_id: before.account_id.clone(),
+            from: before.status.clone(),
+            to,
+            reason: reason.to_string(),
+            operator: operator.map(str::to_string),
+        };
+        w.push(entry.clone());
+        entry
+    }
+
+    /// Entries newest first, optionally for one order only.
+    pub fn list(&self, order_id: Option<&str>) -> Vec<AuditEntry> {
+        let r = self.inner.read().unwrap();
+        r.iter()
+            .rev()
+            .filter(|e| order_id.is_none_or(|id| e.order_id == id))
+            .cloned()
+            .collect()
+    }
+
+    /// Drops `account_id`'s entries or, anonymizing, keeps them without
+    /// the account. Returns how many changed.
+    pub fn forget_account(&self, account_id: &str, mode: PurgeMode) -> usize {
+        let mut w = self.inner.write().unwrap();
+        let before = w.len();
+        match mode {
+            PurgeMode::Purge => {
+                w.retain(|e| e.account_id.as_deref() != Some(account_id));
+                before - w.len()
+            }
+            PurgeMode::Anonymize => w
+                .iter_mut()
+                .filter(|e| e.account_id.as_deref() == Some(account_id))
+                .map(|e| e.account_id = None)
+                .count(),
+        }
+    }
+}
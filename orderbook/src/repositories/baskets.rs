@@ -0,0 +1,110 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use tokio::sync::RwLock;
+
+use crate::accounts::purge::PurgeMode;
+use crate::entities::basket::{Basket, BasketStatus};
+use crate::utils::now_ms;
+
+fn pending_on(b: &Basket, pair: &str) -> bool {
+    b.status == BasketStatus::Pending
+        && (b.trigger.pair == pair || b.legs.iter().any(|l| l.pair == pair))
+}
+
+/// In-memory store for basket orders.
+#[derive(Clone, Default)]
+pub struct BasketStore {
+    inner: Arc<RwLock<HashMap<String, Basket>>>,
+}
+
+impl BasketStore {
+    pub async fn create(&self, b: Basket) -> Basket {
+        self.inner.write().await.insert(b.id.clone(), b.clone());
+        b
+    }
+
+    pub async fn get(&self, id: &str) -> Option<Basket> {
+        self.inner.read().await.get(id).cloned()
+    }
+
+    /// Baskets oldest first, optionally only those in `status`.
+    pub async fn list(&self, status: Option<BasketStatus>) -> Vec<Basket> {
+        let r = self.inner.read().await;
+        let mut v: Vec<Basket> = r
+            .values()
+            .filter(|b| status.is_none_or(|s| b.status == s))
+            .cloned()
+            .collect();
+        v.sort_by_key(|b| b.created);
+        v
+    }
+
+    /// Stores `b` if the stored copy is still `Pending`, so a cancel that
+    /// raced the watcher wins. Returns whether it was stored.
+    pub async fn settle(&self, mut b: Basket) -> bool {
+        let mut w = self.inner.write().await;
+        match w.get(&b.id) {
+            Some(cur) if cur.status == BasketStatus::Pending => {
+                b.updated = now_ms();
+                w.insert(b.id.clone(), b);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// How many pending baskets trigger on or trade `pair`.
+    pub async fn references(&self, pair: &str) -> usize {
+        let r = self.inner.read().await;
+        r.values().filter(|b| pending_on(b, pair)).count()
+    }
+
+    /// Points pending baskets at `to` wherever they name `from`, so their
+    /// triggers read the renamed pair and their legs are placed on it.
+    /// Returns how many changed.
+    pub async fn rename_pair(&self, from: &str, to: &str) -> usize {
+        let mut w = self.inner.write().await;
+        let mut renamed = 0;
+        for b in w.values_mut().filter(|b| pending_on(b, from)) {
+            let pairs =
+                std::iter::once(&mut b.trigger.pair).chain(b.legs.iter_mut().map(|l| &mut l.pair));
+            for p in pairs.filter(|p| *p == from) {
+                *p = to.to_string();
+            }
+            b.updated = now_ms();
+            renamed += 1;
+        }
+        renamed
+    }
+
+    /// Drops `account_id`'s baskets, pending ones included, or,
+    /// anonymizing, keeps them without the account. Returns how many
+    /// changed.
+    pub async fn forget_account(&self, account_id: &str, mode: PurgeMode) -> usize {
+        let mut w = self.inner.write().await;
+        let before = w.len();
+        match mode {
+            PurgeMode::Purge => {
+                w.retain(|_, b| b.account_id.as_deref() != Some(account_id));
+                before - w.len()
+            }
+            PurgeMode::Anonymize => w
+                .values_mut()
+                .filter(|b| b.account_id.as_deref() == Some(account_id))
+                .map(|b| b.account_id = None)
+                .count(),
+        }
+    }
+
+    /// Cancels a pending basket; the stored basket either way.
+    pub async fn cancel(&self, id: &str) -> Option<Basket> {
+        let mut w = self.inner.write().await;
+        let b = w.get_mut(id)?;
+        if b.status == BasketStatus::Pending {
+            b.status = BasketStatus::Cancelled;
+            b.updated = now_ms();
+        }
+        Some(b.clone())
+    }
+}
@@ -0,0 +1,315 @@
+use std::sync::{Arc, RwLock};
+use std::time::Instant;
+
+use async_trait::async_trait;
+use futures_util::stream::BoxStream;
+use rust_decimal::Decimal;
+use tokio::sync::broadcast;
+
+use crate::entities::events::{DomainEvent, Envelope, MarketEvent, OrderEvent};
+use crate::entities::order::{Fill, NewOrder, Order, OrderStatus};
+use crate::instruments::symbol::Symbol;
+use crate::metrics::placement::{self, Stage};
+use crate::repositories::{ListOrdersQuery, OrderRepository};
+use crate::utils::now_ms;
+
+const BROADCAST_CAPACITY: usize = 1024;
+
+/// One successful write to an order.
+pub type OrderChange = Envelope<OrderEvent>;
+
+/// Synchronous consumer of the change stream, called in write order before
+/// the write returns.
+pub trait ChangeListener: Send + Sync {
+    fn on_change(&self, change: &OrderChange);
+}
+
+/// Fan-out of domain events. Listeners see every order change
+/// synchronously; [`ChangeStream::subscribe`] gives async consumers a lossy
+/// broadcast of order and market events alike.
+#[derive(Clone)]
+pub struct ChangeStream {
+    listeners: Arc<RwLock<Vec<Arc<dyn ChangeListener>>>>,
+    tx: broadcast::Sender<Envelope<DomainEvent>>,
+}
+
+impl Default for ChangeStream {
+    fn default() -> Self {
+        Self {
+            listeners: Arc::default(),
+            tx: broadcast::channel(BROADCAST_CAPACITY).0,
+        }
+    }
+}
+
+impl ChangeStream {
+    pub fn listen(&self, l: Arc<dyn ChangeListener>) {
+        self.listeners.write().unwrap().push(l);
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<Envelope<DomainEvent>> {
+        self.tx.subscribe()
+    }
+
+    pub fn publish(&self, event: OrderEvent) {
+        let change = Envelope::new(event, now_ms());
+        for l in self.listeners.read().unwrap().iter() {
+            l.on_change(&change);
+        }
+        // No receivers is fine.
+        let _ = self.tx.send(change.into());
+    }
+
+    pub fn publish_market(&self, event: MarketEvent) {
+        let _ = self
+            .tx
+            .send(Envelope::new(DomainEvent::Market(event), now_ms()));
+    }
+}
+
+/// Repository decorator publishing every successful write to a
+/// [`ChangeStream`].
+#[derive(Clone)]
+pub struct Observed<R> {
+    inner: R,
+    stream: ChangeStream,
+}
+
+impl<R> Observed<R> {
+    pub fn new(inner: R, stream: ChangeStream) -> Self {
+        Self { inner, stream }
+    }
+
+    pub fn stream(&self) -> &ChangeStream {
+        &self.stream
+    }
+
+    fn emit(
+        &self,
+        event: fn(Order) -> OrderEvent,
+        r: Result<Order, String>,
+    ) -> Result<Order, String> {
+        if let Ok(o) = &r {
+            let started = Instant::now();
+            self.stream.publish(event(o.clone()));
+            placement::charge(Stage::Publish, started.elapsed());
+        }
+        r
+    }
+}
+
+fn created(order: Order) -> OrderEvent {
+    OrderEvent::Created { order }
+}
+
+fn updated(order: Order) -> OrderEvent {
+    OrderEvent::Updated { order }
+}
+
+#[async_trait]
+impl<R: OrderRepository + Clone + 'static> OrderRepository for Observed<R> {
+    async fn create(&self, new: NewOrder) -> Result<Order, String> {
+        self.emit(created, self.inner.create(new).await)
+    }
+
+    async fn get_by_id(&self, id: &str) -> Result<Order, String> {
+        self.inner.get_by_id(id).await
+    }
+
+    async fn list(&self, q: ListOrdersQuery) -> Result<Vec<Order>, String> {
+        self.inner.list(q).await
+    }
+
+    async fn set_status(&self, id: &str, status: OrderStatus) -> Result<Order, String> {
+        self.emit(updated, self.inner.set_status(id, status).await)
+    }
+
+    async fn record_fill(&self, id: &str, exec_price: Decimal) -> Result<Order, String> {
+        self.emit(updated, self.inner.record_fill(id, exec_price).await)
+    }
+
+    async fn record_fills(&self, id: &str, fills: &[Fill]) -> Result<Order, String> {
+        self.emit(updated, self.inner.record_fills(id, fills).await)
+    }
+
+    async fn set_fill(
+        &self,
+        id: &str,
+        status: OrderStatus,
+        filled_quantity: Decimal,
+    ) -> Result<Order, String> {
+        let r = self.inner.set_fill(id, status, filled_quantity).await;
+        self.emit(updated, r)
+    }
+
+    async fn cancel(&self, id: &str, reason: &str) -> Result<Order, String> {
+        self.emit(updated, self.inner.cancel(id, reason).await)
+    }
+
+    async fn amend(&self, id: &str, price: Decimal, quantity: Decimal) -> Result<Order, String> {
+        self.emit(updated, self.inner.amend(id, price, quantity).await)
+    }
+
+    async fn delete(&self, id: &str) -> Result<(), String> {
+        let before = self.inner.get_by_id(id).await;
+        self.inner.delete(id).await?;
+        if let Ok(o) = before {
+            self.stream.publish(OrderEvent::Deleted { order: o });
+        }
+        Ok(())
+    }
+
+    async fn rename_pair(&self, from: &str, to: &Symbol) -> Result<Vec<Order>, String> {
+        let renamed = self.inner.rename_pair(from, to).await?;
+        for o in &renamed {
+            self.stream.publish(updated(o.clone()));
+        }
+        Ok(renamed)
+    }
+
+    async fn replace(&self, id: &str, new: NewOrder) -> Result<(Order, Order), String> {
+        let (old, order) = self.inner.replace(id, new).await?;
+        self.stream.publish(updated(old.clone()));
+        self.stream.publish(created(order.clone()));
+        Ok((old, order))
+    }
+
+    async fn cross(&self, fills: &[(String, Vec<Fill>)]) -> Result<Vec<Order>, String> {
+        let crossed = self.inner.cross(fills).await?;
+        for o in &crossed {
+            self.stream.publish(updated(o.clone()));
+        }
+        Ok(crossed)
+    }
+
+    async fn activate_stop(&self, id: &str) -> Result<Order, String> {
+        self.emit(updated, self.inner.activate_stop(id).await)
+    }
+
+    async fn create_bracket(
+        &self,
+        entry: NewOrder,
+        children: Vec<NewOrder>,
+    ) -> Result<(Order, Vec<Order>), String> {
+        let (entry, children) = self.inner.create_bracket(entry, children).await?;
+        for o in std::iter::once(&entry).chain(&children) {
+            self.stream.publish(created(o.clone()));
+        }
+        Ok((entry, children))
+    }
+
+    async fn fill_oco(&self, id: &str, fills: &[Fill]) -> Result<(Order, Vec<Order>), String> {
+        let (leg, cancelled) = self.inner.fill_oco(id, fills).await?;
+        for o in std::iter::once(&leg).chain(&cancelled) {
+            self.stream.publish(updated(o.clone()));
+        }
+        Ok((leg, cancelled))
+    }
+
+    async fn trail_stop(
+        &self,
+        id: &str,
+        watermark: Decimal,
+        trigger_price: Decimal,
+    ) -> Result<Order, String> {
+        let r = self.inner.trail_stop(id, watermark, trigger_price).await;
+        self.emit(updated, r)
+    }
+
+    async fn expire(&self, id: &str, now_ms: i64) -> Result<Order, String> {
+        self.emit(updated, self.inner.expire(id, now_ms).await)
+    }
+
+    async fn anonymize(&self, id: &str) -> Result<Order, String> {
+        self.emit(updated, self.inner.anonymize(id).await)
+    }
+
+    fn replication_lag_ms(&self) -> Option<i64> {
+        self.inner.replication_lag_ms()
+    }
+
+    fn stream(self: Arc<Self>, q: ListOrdersQuery) -> BoxStream<'static, Result<Order, String>> {
+        Arc::new(self.inner.clone()).stream(q)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entities::order::{Condition, OrderSide, OrderType, TimeInForce, TriggerPriceType};
+    use crate::repositories::in_memory::InMemoryOrderRepository;
+    use rust_decimal_macros::dec;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct Recorder(Mutex<Vec<(&'static str, OrderStatus)>>);
+
+    impl ChangeListener for Recorder {
+        fn on_change(&self, c: &OrderChange) {
+            let kind = match c.event {
+                OrderEvent::Created { .. } => "created",
+                OrderEvent::Updated { .. } => "updated",
+                OrderEvent::Deleted { .. } => "deleted",
+            };
+            self.0
+                .lock()
+                .unwrap()
+                .push((kind, c.event.order().status.clone()));
+        }
+    }
+
+    #[tokio::test]
+    async fn successful_writes_reach_listeners_and_subscribers() {
+        let stream = ChangeStream::default();
+        let rec = Arc::new(Recorder::default());
+        stream.listen(rec.clone());
+        let mut rx = stream.subscribe();
+        let repo = Observed::new(InMemoryOrderRepository::default(), stream);
+
+        let o = repo
+            .create(NewOrder {
+                pair: "BTC/USDT".into(),
+                side: OrderSide::Buy,
+                order_type: OrderType::Limit,
+                price: dec!(1),
+                quantity: dec!(1),
+                trigger_price_type: TriggerPriceType::Last,
+                condition: Condition::Price,
+                sustained_for_ms: None,
+                time_in_force: TimeInForce::Gtc,
+                max_slippage_bps: None,
+                exec_pricing: None,
+                price_source: None,
+                account_id: None,
+                quantity_quote: None,
+                cancel_after_ms: None,
+                valid_for_after_armed_ms: None,
+                expires_at: None,
+                tags: Vec::new(),
+                metadata: Default::default(),
+                callback_url: None,
+                callback_template: None,
+                trigger_price: None,
+                trailing: None,
+                oco_id: None,
+            })
+            .await
+            .unwrap();
+        repo.record_fill(&o.id, dec!(1)).await.unwrap();
+        assert!(repo.cancel("missing", "x").await.is_err());
+        repo.delete(&o.id).await.unwrap();
+
+        assert_eq!(
+            *rec.0.lock().unwrap(),
+            vec![
+                ("created", OrderStatus::New),
+                ("updated", OrderStatus::Filled),
+                ("deleted", OrderStatus::Filled),
+            ]
+        );
+        let DomainEvent::Order(first) = rx.recv().await.unwrap().event else {
+            panic!("expected an order event");
+        };
+        assert!(matches!(*first, OrderEvent::Created { .. }));
+    }
+}
@@ -1,8 +1,11 @@
-use crate::entities::order::{NewOrder, Order, OrderStatus};
-use crate::repositories::{ListOrdersQuery, OrderRepository};
+use crate::entities::order::{Fill, NewOrder, Order, OrderStatus, CANCEL_OCO, CANCEL_REPLACED};
+use crate::instruments::symbol::Symbol;
+use crate::repositories::{ListOrdersQuery, OrderRepository, STREAM_PAGE};
 use crate::utils::now_ms;
 use async_trait::async_trait;
-use std::collections::HashMap;
+use futures_util::stream::{self, BoxStream, StreamExt};
+use rust_decimal::Decimal;
+use std::collections::{HashMap, VecDeque};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
@@ -11,11 +14,71 @@ pub struct InMemoryOrderRepository {
     inner: Arc<RwLock<HashMap<String, Order>>>,
 }
 
+/// How much the store holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Footprint {
+    pub orders: usize,
+    /// A rough estimate: each order's struct and fills plus the text it
+    /// owns, not allocator overhead or spare capacity.
+    pub approx_bytes: usize,
+}
+
+fn approx_bytes(id: &str, o: &Order) -> usize {
+    let opt = |s: &Option<String>| s.as_ref().map_or(0, String::len);
+    let fills: usize = o
+        .fills
+        .iter()
+        .map(|f| {
+            size_of::<Fill>()
+                + f.legs.len() * size_of::<crate::entities::order::LegPrice>()
+                + f.fair_sources
+                    .iter()
+                    .map(|s| size_of_val(s) + s.source.len())
+                    .sum::<usize>()
+                + opt(&f.counterparty)
+        })
+        .sum();
+    size_of::<Order>()
+        + id.len() * 2
+        + fills
+        + o.tags
+            .iter()
+            .map(|t| size_of::<String>() + t.len())
+            .sum::<usize>()
+        + o.metadata
+            .iter()
+            .map(|(k, v)| k.len() + v.len())
+            .sum::<usize>()
+        + opt(&o.account_id)
+        + opt(&o.price_source)
+        + opt(&o.cancel_reason)
+        + opt(&o.replaced_by)
+        + opt(&o.replaces)
+        + opt(&o.callback_url)
+        + o.callback_template
+            .as_ref()
+            .map_or(0, |t| t.to_string().len())
+}
+
+impl InMemoryOrderRepository {
+    pub async fn footprint(&self) -> Footprint {
+        let map = self.inner.read().await;
+        Footprint {
+            orders: map.len(),
+            approx_bytes: map.iter().map(|(id, o)| approx_bytes(id, o)).sum(),
+        }
+    }
+}
+
+fn matches(q: &ListOrdersQuery, o: &Order) -> bool {
+    q.pair.as_ref().is_none_or(|p| &o.pair == p) && q.status.as_ref().is_none_or(|s| &o.status == s)
+}
+
 #[async_trait]
 impl OrderRepository for InMemoryOrderRepository {
     async fn create(&self, new: NewOrder) -> Result<Order, String> {
         let mut map = self.inner.write().await;
-        let order = Order::new(new.pair, new.side, new.price, new.quantity);
+        let order = Order::from_new(new);
         map.insert(order.id.clone(), order.clone());
         Ok(order)
     }
@@ -27,14 +90,7 @@ impl OrderRepository for InMemoryOrderRepository {
 
     async fn list(&self, q: ListOrdersQuery) -> Result<Vec<Order>, String> {
         let map = self.inner.read().await;
-        let mut items: Vec<Order> = map.values().cloned().collect();
-
-        if let Some(pair) = q.pair {
-            items.retain(|o| o.pair == pair);
-        }
-        if let Some(status) = q.status {
-            items.retain(|o| o.status == status);
-        }
+        let items: Vec<Order> = map.values().filter(|o| matches(&q, o)).cloned().collect();
 
         let start = q.offset.unwrap_or(0).max(0) as usize;
         let end = q
@@ -53,16 +109,268 @@ impl OrderRepository for InMemoryOrderRepository {
 
     async fn set_status(&self, id: &str, status: OrderStatus) -> Result<Order, String> {
         let mut map = self.inner.write().await;
-        let o = map.get_mut(id).ok_or_else(|| "not found")?;
+        let o = map.get_mut(id).ok_or("not found")?;
         o.status = status;
         o.updated = now_ms();
         Ok(o.clone())
     }
 
+    async fn record_fill(&self, id: &str, exec_price: Decimal) -> Result<Order, String> {
+        let mut map = self.inner.write().await;
+        let o = map.get_mut(id).ok_or("not found")?;
+        o.status = OrderStatus::Filled;
+        o.filled_quantity = o.quantity;
+        o.exec_price = Some(exec_price);
+        o.updated = now_ms();
+        o.triggered_after_ms = Some(o.updated - o.created);
+        Ok(o.clone())
+    }
+
+    async fn record_fills(&self, id: &str, fills: &[Fill]) -> Result<Order, String> {
+        let mut map = self.inner.write().await;
+        let o = map.get_mut(id).ok_or("not found")?;
+        o.apply_fills(fills);
+        o.updated = now_ms();
+        if o.status == OrderStatus::Filled {
+            o.triggered_after_ms = Some(o.updated - o.created);
+        }
+        Ok(o.clone())
+    }
+
+    async fn set_fill(
+        &self,
+        id: &str,
+        status: OrderStatus,
+        filled_quantity: Decimal,
+    ) -> Result<Order, String> {
+        let mut map = self.inner.write().await;
+        let o = map.get_mut(id).ok_or("not found")?;
+        o.status = status;
+        o.filled_quantity = filled_quantity;
+        o.updated = now_ms();
+        Ok(o.clone())
+    }
+
+    async fn cancel(&self, id: &str, reason: &str) -> Result<Order, String> {
+        let mut map = self.inner.write().await;
+        let o = map.get_mut(id).ok_or("not found")?;
+        o.status = OrderStatus::Cancelled;
+        o.cancel_reason = Some(reason.to_string());
+        o.updated = now_ms();
+        Ok(o.clone())
+    }
+
+    async fn amend(&self, id: &str, price: Decimal, quantity: Decimal) -> Result<Order, String> {
+        let mut map = self.inner.write().await;
+        let o = map.get_mut(id).ok_or("not found")?;
+        o.price = price;
+        o.quantity = quantity;
+        o.updated = now_ms();
+        Ok(o.clone())
+    }
+
+    async fn rename_pair(&self, from: &str, to: &Symbol) -> Result<Vec<Order>, String> {
+        let mut map = self.inner.write().await;
+        let now = now_ms();
+        let mut renamed = Vec::new();
+        for o in map.values_mut().filter(|o| o.pair == from) {
+            o.pair = to.clone();
+            o.updated = now;
+            renamed.push(o.clone());
+        }
+        Ok(renamed)
+    }
+
+    async fn replace(&self, id: &str, new: NewOrder) -> Result<(Order, Order), String> {
+        let mut map = self.inner.write().await;
+        let old = map.get_mut(id).ok_or("not found")?;
+        if !old.status.is_active() {
+            return Err(format!("order is {:?}", old.status).to_lowercase());
+        }
+        let order = Order {
+            replaces: Some(old.id.clone()),
+            ..Order::from_new(new)
+        };
+        old.status = OrderStatus::Cancelled;
+        old.cancel_reason = Some(CANCEL_REPLACED.to_string());
+        old.replaced_by = Some(order.id.clone());
+        old.updated = order.created;
+        let old = old.clone();
+        map.insert(order.id.clone(), order.clone());
+        Ok((old, order))
+    }
+
+    async fn cross(&self, fills: &[(String, Vec<Fill>)]) -> Result<Vec<Order>, String> {
+        let mut map = self.inner.write().await;
+        for (id, _) in fills {
+            let o = map.get(id).ok_or("not found")?;
+            if !o.status.is_active() {
+                return Err(format!("order {id} is {:?}", o.status).to_lowercase());
+            }
+        }
+        let now = now_ms();
+        let mut crossed = Vec::new();
+        for (id, f) in fills {
+            let o = map.get_mut(id).ok_or("not found")?;
+            o.apply_fills(f);
+            o.updated = now;
+            if o.status == OrderStatus::Filled {
+                o.triggered_after_ms = Some(now - o.created);
+            }
+            crossed.push(o.clone());
+        }
+        Ok(crossed)
+    }
+
+    async fn activate_stop(&self, id: &str) -> Result<Order, String> {
+        let mut map = self.inner.write().await;
+        let o = map.get_mut(id).ok_or("not found")?;
+        if !o.status.is_active() || !o.order_type.is_stop() {
+            return Err(format!("order {id} is not an active stop order"));
+        }
+        let now = now_ms();
+        o.order_type = o.order_type.activated();
+        o.stop_triggered_ms = Some(now);
+        o.updated = now;
+        Ok(o.clone())
+    }
+
+    async fn create_bracket(
+        &self,
+        entry: NewOrder,
+        children: Vec<NewOrder>,
+    ) -> Result<(Order, Vec<Order>), String> {
+        let mut map = self.inner.write().await;
+        let entry = Order::from_new(entry);
+        let children: Vec<Order> = children
+            .into_iter()
+            .map(|c| Order {
+                status: OrderStatus::Pending,
+                parent_id: Some(entry.id.clone()),
+                ..Order::from_new(c)
+            })
+            .collect();
+        for o in std::iter::once(&entry).chain(&children) {
+            map.insert(o.id.clone(), o.clone());
+        }
+        Ok((entry, children))
+    }
+
+    async fn fill_oco(&self, id: &str, fills: &[Fill]) -> Result<(Order, Vec<Order>), String> {
+        let mut map = self.inner.write().await;
+        let o = map.get_mut(id).ok_or("not found")?;
+        if !o.status.is_active() {
+            return Err(format!("order {id} is {:?}", o.status).to_lowercase());
+        }
+        let now = now_ms();
+        o.apply_fills(fills);
+        o.updated = now;
+        if o.status == OrderStatus::Filled {
+            o.triggered_after_ms = Some(now - o.created);
+        }
+        let (leg, link) = (o.clone(), o.oco_id.clone());
+        let mut cancelled = Vec::new();
+        for o in map.values_mut() {
+            if o.id != leg.id && o.oco_id.is_some() && o.oco_id == link && o.status.is_active() {
+                o.status = OrderStatus::Cancelled;
+                o.cancel_reason = Some(CANCEL_OCO.to_string());
+                o.updated = now;
+                cancelled.push(o.clone());
+            }
+        }
+        Ok((leg, cancelled))
+    }
+
+    async fn trail_stop(
+        &self,
+        id: &str,
+        watermark: Decimal,
+        trigger_price: Decimal,
+    ) -> Result<Order, String> {
+        let mut map = self.inner.write().await;
+        let o = map.get_mut(id).ok_or("not found")?;
+        if !o.status.is_active() || !o.order_type.is_stop() || o.trailing.is_none() {
+            return Err(format!("order {id} is not an active trailing stop"));
+        }
+        o.watermark = Some(watermark);
+        o.trigger_price = Some(trigger_price);
+        o.updated = now_ms();
+        Ok(o.clone())
+    }
+
+    async fn expire(&self, id: &str, now_ms: i64) -> Result<Order, String> {
+        let mut map = self.inner.write().await;
+        let o = map.get_mut(id).ok_or("not found")?;
+        let due = o.expires_at.is_some_and(|at| at <= now_ms);
+        if !due || !o.status.can_transition_to(&OrderStatus::Expired) {
+            return Err(format!("order {id} is not due to expire"));
+        }
+        o.status = OrderStatus::Expired;
+        o.updated = crate::utils::now_ms();
+        Ok(o.clone())
+    }
+
+    async fn anonymize(&self, id: &str) -> Result<Order, String> {
+        let mut map = self.inner.write().await;
+        let o = map.get_mut(id).ok_or("not found")?;
+        o.anonymize();
+        o.updated = now_ms();
+        Ok(o.clone())
+    }
+
     async fn delete(&self, id: &str) -> Result<(), String> {
         let mut map = self.inner.write().await;
         map.remove(id).map(|_| ()).ok_or_else(|| "not found".into())
     }
+
+    /// Snapshots the matching ids, oldest first, then clones orders a page
+    /// at a time. Orders deleted meanwhile are skipped; the rest are
+    /// returned as they are when their page is read.
+    fn stream(self: Arc<Self>, q: ListOrdersQuery) -> BoxStream<'static, Result<Order, String>> {
+        let map = self.inner.clone();
+        let ids = async move {
+            let mut ids: Vec<(i64, String)> = map
+                .read()
+                .await
+                .values()
+                .filter(|o| matches(&q, o))
+                .map(|o| (o.created, o.id.clone()))
+                .collect();
+            ids.sort();
+            let start = q.offset.unwrap_or(0).max(0) as usize;
+            let take = q
+                .limit
+                .filter(|&l| l > 0)
+                .map_or(usize::MAX, |l| l as usize);
+            let ids: VecDeque<String> = ids
+                .into_iter()
+                .skip(start)
+                .take(take)
+                .map(|(_, id)| id)
+                .collect();
+            (map, ids)
+        };
+        stream::once(ids)
+            .flat_map(|state| {
+                stream::unfold(state, |(map, mut ids)| async move {
+                    if ids.is_empty() {
+                        return None;
+                    }
+                    let page: Vec<String> =
+                        ids.drain(..ids.len().min(STREAM_PAGE as usize)).collect();
+                    let r = map.read().await;
+                    let orders: Vec<Result<Order, String>> = page
+                        .iter()
+                        .filter_map(|id| r.get(id).cloned())
+                        .map(Ok)
+                        .collect();
+                    drop(r);
+                    Some((stream::iter(orders), (map, ids)))
+                })
+                .flatten()
+            })
+            .boxed()
+    }
 }
 
 #[cfg(test)]
@@ -75,13 +383,14 @@ mod tests {
     fn sample_order(id: &str, pair: &str) -> Order {
         Order {
             id: id.to_string(),
-            pair: pair.to_string(),
+            pair: pair.into(),
             side: OrderSide::Buy,
             price: dec!(100.0),
             quantity: dec!(1.0),
             status: OrderStatus::New,
             created: 1_700_000_000_000,
             updated: 1_700_000_000_000,
+            ..Order::new(pair.into(), OrderSide::Buy, dec!(100.0), dec!(1.0))
         }
     }
 
@@ -119,6 +428,32 @@ mod tests {
         assert!(after.updated >= before.updated);
     }
 
+    #[tokio::test]
+    async fn record_fill_sets_status_and_exec_price() {
+        let repo = InMemoryOrderRepository::default();
+        seed(&repo, &[sample_order("f", "BTC/USDT")]).await;
+
+        let filled = repo.record_fill("f", dec!(99.5)).await.unwrap();
+        assert_eq!(filled.status, OrderStatus::Filled);
+        assert_eq!(filled.exec_price, Some(dec!(99.5)));
+        assert_eq!(filled.filled_quantity, filled.quantity);
+        assert_eq!(
+            filled.triggered_after_ms,
+            Some(filled.updated - filled.created)
+        );
+        assert!(repo.record_fill("nope", dec!(1)).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn cancel_records_reason() {
+        let repo = InMemoryOrderRepository::default();
+        seed(&repo, &[sample_order("c", "BTC/USDT")]).await;
+
+        let cancelled = repo.cancel("c", "fok_unfillable").await.unwrap();
+        assert_eq!(cancelled.status, OrderStatus::Cancelled);
+        assert_eq!(cancelled.cancel_reason.as_deref(), Some("fok_unfillable"));
+    }
+
     #[tokio::test]
     async fn delete_removes_order() {
         let repo = InMemoryOrderRepository::default();
@@ -137,4 +472,39 @@ mod tests {
         let err = repo.delete("nope").await.unwrap_err();
         assert!(!err.is_empty());
     }
+
+    #[tokio::test]
+    async fn stream_yields_matches_oldest_first_and_skips_deleted() {
+        let repo = Arc::new(InMemoryOrderRepository::default());
+        let orders: Vec<Order> = (0..1_203)
+            .map(|i| Order {
+                created: i,
+                ..sample_order(
+                    &format!("o{i:04}"),
+                    if i % 3 == 0 { "ETH/USDT" } else { "BTC/USDT" },
+                )
+            })
+            .collect();
+        seed(&repo, &orders).await;
+
+        let q = ListOrdersQuery {
+            pair: Some("BTC/USDT".into()),
+            offset: Some(1),
+            ..ListOrdersQuery::default()
+        };
+        let mut s = repo.clone().stream(q);
+        let first = s.next().await.unwrap().unwrap();
+        assert_eq!(first.id, "o0002");
+        repo.delete("o1000").await.unwrap();
+        let rest: Vec<Order> = s.map(Result::unwrap).collect().await;
+        assert_eq!(rest.len(), 799);
+        assert!(rest.windows(2).all(|w| w[0].created < w[1].created));
+        assert!(rest.iter().all(|o| o.pair == "BTC/USDT" && o.id != "o1000"));
+
+        let limited = repo.clone().stream(ListOrdersQuery {
+            limit: Some(3),
+            ..ListOrdersQuery::default()
+        });
+        assert_eq!(limited.count().await, 3);
+    }
 }
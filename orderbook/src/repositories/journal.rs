@@ -0,0 +1,450 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use tokio::time::{interval, MissedTickBehavior};
+use tracing::info;
+
+use crate::accounts::purge::PurgeMode;
+use crate::entities::events::{Envelope, OrderEvent, SCHEMA_VERSION};
+use crate::entities::migrations;
+use crate::entities::order::Order;
+use crate::instruments::symbol::Symbol;
+use crate::metrics::Metrics;
+use crate::repositories::changes::{ChangeListener, ChangeStream, OrderChange};
+use crate::repositories::open_orders::{Book, OpenOrdersView};
+use crate::utils::now_ms;
+
+/// How much raw history compaction keeps. Entries older than `max_age_ms`
+/// and, past that, the oldest beyond `max_entries` are folded into the
+/// snapshot.
+#[derive(Debug, Clone, Copy)]
+pub struct JournalRetention {
+    pub max_age_ms: Option<i64>,
+    pub max_entries: Option<usize>,
+}
+
+impl Default for JournalRetention {
+    fn default() -> Self {
+        Self {
+            max_age_ms: Some(24 * 3_600_000),
+            max_entries: Some(100_000),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct CompactionReport {
+    pub folded: usize,
+    pub entries: usize,
+    pub snapshot_orders: usize,
+    pub horizon_ms: Option<i64>,
+}
+
+/// Result of loading a capture. `upgraded` counts records written with an
+/// older schema version.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ImportReport {
+    pub entries: usize,
+    pub snapshot_orders: usize,
+    pub upgraded: usize,
+}
+
+/// First line of a capture of a compacted journal.
+#[derive(Serialize, Deserialize)]
+struct SnapshotRecord {
+    schema_version: u32,
+    horizon_ms: Option<i64>,
+    orders: Vec<Order>,
+}
+
+#[derive(Default)]
+struct Inner {
+    entries: VecDeque<OrderChange>,
+    /// Every non-deleted order as of `horizon_ms`, i.e. all entries up to
+    /// and including the last one folded.
+    snapshot: HashMap<String, Order>,
+    /// History before this is only available through the snapshot, so
+    /// earlier `as_of` queries are refused.
+    horizon_ms: Option<i64>,
+}
+
+impl Inner {
+    fn check_horizon(&self, as_of: i64) -> Result<(), String> {
+        match self.horizon_ms {
+            Some(h) if as_of < h => Err(format!("journal history is compacted before {h}")),
+            _ => Ok(()),
+        }
+    }
+
+    fn upto(&self, as_of: i64) -> impl Iterator<Item = &OrderChange> {
+        self.entries.iter().take_while(move |c| c.ts_ms <= as_of)
+    }
+}
+
+/// Append-only in-memory record of the change stream, periodically
+/// compacted into a snapshot. Used to reconstruct past state.
+#[derive(Clone, Default)]
+pub struct Journal {
+    retention: JournalRetention,
+    inner: Arc<RwLock<Inner>>,
+}
+
+impl Journal {
+    pub fn with_retention(retention: JournalRetention) -> Self {
+        Self {
+            retention,
+            inner: Arc::default(),
+        }
+    }
+
+    pub fn attach(&self, stream: &ChangeStream) {
+        stream.listen(Arc::new(self.clone()));
+    }
+
+    /// The order as it was at `as_of`, `Ok(None)` if it did not exist then.
+    pub fn order_as_of(&self, id: &str, as_of: i64) -> Result<Option<Order>, String> {
+        let r = self.inner.read().unwrap();
+        r.check_horizon(as_of)?;
+        let Some(last) = r.upto(as_of).filter(|c| c.order().id == id).last() else {
+            return Ok(r.snapshot.get(id).cloned());
+        };
+        Ok((!last.is_delete()).then(|| last.order().clone()))
+    }
+
+    /// Replays the snapshot and the journal up to `as_of` into a scratch
+    /// view and takes the book from it.
+    pub fn book_as_of(&self, pair: &str, as_of: i64) -> Result<Book, String> {
+        let r = self.inner.read().unwrap();
+        r.check_horizon(as_of)?;
+        let view = OpenOrdersView::default();
+        for o in r.snapshot.values().filter(|o| o.pair == pair) {
+            view.on_change(&Envelope::new(
+                OrderEvent::Created { order: o.clone() },
+                o.created,
+            ));
+        }
+        for c in r.upto(as_of).filter(|c| c.order().pair == pair) {
+            view.on_change(c);
+        }
+        Ok(view.book(pair))
+    }
+
+    /// Folds entries outside the retention window into the snapshot.
+    pub fn compact(&self, now_ms: i64) -> CompactionReport {
+        let mut w = self.inner.write().unwrap();
+        let cutoff = self.retention.max_age_ms.map(|age| now_ms - age);
+        let mut folded = 0;
+        while let Some(front) = w.entries.front() {
+            let too_old = cutoff.is_some_and(|c| front.ts_ms < c);
+            let too_many = self
+                .retention
+                .max_entries
+                .is_some_and(|max| w.entries.len() > max);
+            if !too_old && !too_many {
+                break;
+            }
+            let c = w.entries.pop_front().unwrap();
+            w.horizon_ms = Some(c.ts_ms);
+            match c.event {
+                OrderEvent::Deleted { order } => w.snapshot.remove(&order.id),
+                OrderEvent::Created { order } | OrderEvent::Updated { order } => {
+                    w.snapshot.insert(order.id.clone(), order)
+                }
+            };
+            folded += 1;
+        }
+        CompactionReport {
+            folded,
+            entries: w.entries.len(),
+            snapshot_orders: w.snapshot.len(),
+            horizon_ms: w.horizon_ms,
+        }
+    }
+
+    /// Newline-delimited JSON: the snapshot, if any, then every entry.
+    pub fn export(&self) -> String {
+        let r = self.inner.read().unwrap();
+        let mut lines = Vec::with_capacity(r.entries.len() + 1);
+        if r.horizon_ms.is_some() || !r.snapshot.is_empty() {
+            let snapshot = SnapshotRecord {
+                schema_version: SCHEMA_VERSION,
+                horizon_ms: r.horizon_ms,
+                orders: r.snapshot.values().cloned().collect(),
+            };
+            lines.push(serde_json::to_string(&snapshot).unwrap_or_default());
+        }
+        for c in &r.entries {
+            lines.push(serde_json::to_string(c).unwrap_or_default());
+        }
+        lines.join("\n")
+    }
+
+    /// Replaces the journal with a capture made by [`Journal::export`],
+    /// upgrading records written by older versions. Nothing changes if any
+    /// line fails to load.
+    pub fn import(&self, ndjson: &str) -> Result<ImportReport, String> {
+        let mut fresh = Inner::default();
+        let mut report = ImportReport::default();
+        for (n, line) in ndjson.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let fail = |e: String| format!("line {}: {e}", n + 1);
+            let v: serde_json::Value =
+                serde_json::from_str(line).map_err(|e| fail(e.to_string()))?;
+            let from = if v.get("orders").is_some() {
+                let (s, from): (SnapshotRecord, u32) = migrations::load(v).map_err(fail)?;
+                fresh.horizon_ms = s.horizon_ms;
+                fresh.snapshot = s.orders.into_iter().map(|o| (o.id.clone(), o)).collect();
+                from
+            } else {
+                let (c, from): (OrderChange, u32) = migrations::load(v).map_err(fail)?;
+                fresh.entries.push_back(c);
+                from
+            };
+            if from < SCHEMA_VERSION {
+                report.upgraded += 1;
+            }
+        }
+        // Point-in-time lookups scan in time order; captures may be
+        // concatenated out of order.
+        fresh.entries.make_contiguous().sort_by_key(|c| c.ts_ms);
+        report.entries = fresh.entries.len();
+        report.snapshot_orders = fresh.snapshot.len();
+        *self.inner.write().unwrap() = fresh;
+        Ok(report)
+    }
+
+    /// How many entries and snapshot orders refer to `pair`.
+    pub fn references(&self, pair: &str) -> usize {
+        let r = self.inner.read().unwrap();
+        let entries = r.entries.iter().filter(|c| c.order().pair == pair);
+        let snapshot = r.snapshot.values().filter(|o| o.pair == pair);
+        entries.count() + snapshot.count()
+    }
+
+    /// Rewrites history so orders once on `from` read as on `to`, as if
+    /// the pair had always been called that. Returns how many entries and
+    /// snapshot orders changed.
+    pub fn rename_pair(&self, from: &str, to: &Symbol) -> usize {
+        let mut w = self.inner.write().unwrap();
+        let Inner {
+            entries, snapshot, ..
+        } = &mut *w;
+        let orders = entries
+            .iter_mut()
+            .map(|c| match &mut c.event {
+                OrderEvent::Created { order }
+                | OrderEvent::Updated { order }
+                | OrderEvent::Deleted { order } => order,
+            })
+            .chain(snapshot.values_mut());
+        let mut renamed = 0;
+        for o in orders.filter(|o| o.pair == from) {
+            o.pair = to.clone();
+            renamed += 1;
+        }
+        renamed
+    }
+
+    /// Removes every entry and snapshot order of `account_id`'s orders
+    /// or, anonymizing, strips them (see [`Order::anonymize`]). Returns
+    /// how many changed.
+    pub fn forget_account(&self, account_id: &str, mode: PurgeMode) -> usize {
+        let owned = |o: &Order| o.account_id.as_deref() == Some(account_id);
+        let mut w = self.inner.write().unwrap();
+        let Inner {
+            entries, snapshot, ..
+        } = &mut *w;
+        let before = entries.len() + snapshot.len();
+        match mode {
+            PurgeMode::Purge => {
+                entries.retain(|c| !owned(c.order()));
+                snapshot.retain(|_, o| !owned(o));
+                before - entries.len() - snapshot.len()
+            }
+            PurgeMode::Anonymize => entries
+                .iter_mut()
+                .map(|c| match &mut c.event {
+                    OrderEvent::Created { order }
+                    | OrderEvent::Updated { order }
+                    | OrderEvent::Deleted { order } => order,
+                })
+                .chain(snapshot.values_mut())
+                .filter(|o| owned(o))
+                .map(|o| o.anonymize())
+                .count(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.read().unwrap().entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl ChangeListener for Journal {
+    fn on_change(&self, change: &OrderChange) {
+        self.inner
+            .write()
+            .unwrap()
+            .entries
+            .push_back(change.clone());
+    }
+}
+
+fn record_compaction(metrics: &Metrics, report: &CompactionReport, took: Duration) {
+    metrics.set("journal_entries", &[], report.entries as f64);
+    metrics.set(
+        "journal_snapshot_orders",
+        &[],
+        report.snapshot_orders as f64,
+    );
+    metrics.set(
+        "journal_compaction_duration_seconds",
+        &[],
+        took.as_secs_f64(),
+    );
+    metrics.incr("journal_compactions_total", &[], 1.0);
+    metrics.incr("journal_entries_folded_total", &[], report.folded as f64);
+}
+
+/// Periodically compacts the journal and refreshes its gauges.
+pub fn start_journal_compaction(journal: Journal, metrics: Metrics, every: Duration) {
+    tokio::spawn(async move {
+        let mut t = interval(every);
+        t.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            t.tick().await;
+            let started = Instant::now();
+            let report = journal.compact(now_ms());
+            record_compaction(&metrics, &report, started.elapsed());
+            if report.folded > 0 {
+                info!(
+                    folded = report.folded,
+                    entries = report.entries,
+                    "journal compacted"
+                );
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entities::order::{OrderSide, OrderStatus};
+    use rust_decimal_macros::dec;
+
+    fn change(event: fn(Order) -> OrderEvent, order: &Order, ts_ms: i64) -> OrderChange {
+        Envelope::new(event(order.clone()), ts_ms)
+    }
+
+    fn created(order: Order) -> OrderEvent {
+        OrderEvent::Created { order }
+    }
+
+    fn updated(order: Order) -> OrderEvent {
+        OrderEvent::Updated { order }
+    }
+
+    fn deleted(order: Order) -> OrderEvent {
+        OrderEvent::Deleted { order }
+    }
+
+    #[test]
+    fn reconstructs_order_and_book_at_a_point_in_time() {
+        let j = Journal::default();
+        let o = Order::new("BTC/USDT".into(), OrderSide::Buy, dec!(100), dec!(1));
+        let filled = Order {
+            status: OrderStatus::Filled,
+            ..o.clone()
+        };
+        j.on_change(&change(created, &o, 10));
+        j.on_change(&change(updated, &filled, 20));
+        j.on_change(&change(deleted, &filled, 30));
+
+        assert_eq!(j.order_as_of(&o.id, 5).unwrap().map(|o| o.status), None);
+        assert_eq!(
+            j.order_as_of(&o.id, 15).unwrap().map(|o| o.status),
+            Some(OrderStatus::New)
+        );
+        assert_eq!(
+            j.order_as_of(&o.id, 25).unwrap().map(|o| o.status),
+            Some(OrderStatus::Filled)
+        );
+        assert!(j.order_as_of(&o.id, 35).unwrap().is_none());
+
+        assert_eq!(j.book_as_of("BTC/USDT", 15).unwrap().bids.len(), 1);
+        assert!(j.book_as_of("BTC/USDT", 25).unwrap().bids.is_empty());
+    }
+
+    #[test]
+    fn compaction_folds_old_entries_into_the_snapshot() {
+        let j = Journal::with_retention(JournalRetention {
+            max_age_ms: Some(100),
+            max_entries: Some(2),
+        });
+        let a = Order::new("BTC/USDT".into(), OrderSide::Buy, dec!(100), dec!(1));
+        let b = Order::new("BTC/USDT".into(), OrderSide::Buy, dec!(99), dec!(1));
+        let c = Order::new("BTC/USDT".into(), OrderSide::Sell, dec!(101), dec!(1));
+        j.on_change(&change(created, &a, 10));
+        j.on_change(&change(created, &b, 150));
+        j.on_change(&change(deleted, &b, 160));
+        j.on_change(&change(created, &c, 170));
+
+        // `a` is past the age window; `b`'s creation is over the size cap.
+        let report = j.compact(200);
+        assert_eq!(report.folded, 2);
+        assert_eq!(report.entries, 2);
+        assert_eq!(report.snapshot_orders, 2);
+        assert_eq!(report.horizon_ms, Some(150));
+
+        assert!(j.order_as_of(&a.id, 149).is_err());
+        assert!(j.order_as_of(&a.id, 150).unwrap().is_some());
+        assert!(j.order_as_of(&b.id, 165).unwrap().is_none());
+        let book = j.book_as_of("BTC/USDT", 200).unwrap();
+        assert_eq!(book.bids.len(), 1);
+        assert_eq!(book.asks.len(), 1);
+
+        assert_eq!(j.compact(200).folded, 0);
+    }
+
+    #[test]
+    fn capture_round_trips_and_upgrades_legacy_lines() {
+        let j = Journal::with_retention(JournalRetention {
+            max_age_ms: Some(100),
+            max_entries: None,
+        });
+        let a = Order::new("BTC/USDT".into(), OrderSide::Buy, dec!(100), dec!(1));
+        let b = Order::new("BTC/USDT".into(), OrderSide::Buy, dec!(99), dec!(1));
+        j.on_change(&change(created, &a, 10));
+        j.on_change(&change(created, &b, 150));
+        j.compact(200);
+
+        let legacy = serde_json::json!({"kind": "deleted", "order": b, "ts_ms": 160});
+        let capture = format!("{}\n{legacy}\n", j.export());
+        let restored = Journal::default();
+        let report = restored.import(&capture).unwrap();
+        assert_eq!(
+            report,
+            ImportReport {
+                entries: 2,
+                snapshot_orders: 1,
+                upgraded: 1,
+            }
+        );
+        assert!(restored.order_as_of(&a.id, 5).is_err());
+        assert!(restored.order_as_of(&a.id, 50).unwrap().is_some());
+        assert!(restored.order_as_of(&b.id, 155).unwrap().is_some());
+        assert!(restored.order_as_of(&b.id, 165).unwrap().is_none());
+
+        assert!(restored.import("{\"schema_version\": 99}").is_err());
+        assert_eq!(restored.len(), 2);
+    }
+}
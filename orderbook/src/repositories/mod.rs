@@ -1,8 +1,23 @@
+pub mod audit;
+pub mod baskets;
+pub mod changes;
 pub mod in_memory;
+pub mod journal;
+pub mod open_orders;
+pub mod search;
+pub mod stats;
+pub mod tape;
+pub mod templates;
+pub mod vacuum;
+
+use std::sync::Arc;
 
 use async_trait::async_trait;
+use futures_util::stream::{self, BoxStream, StreamExt};
+use rust_decimal::Decimal;
 
-use crate::entities::order::{NewOrder, Order, OrderStatus};
+use crate::entities::order::{Fill, NewOrder, Order, OrderStatus};
+use crate::instruments::symbol::Symbol;
 
 #[derive(Debug, Clone, Default)]
 pub struct ListOrdersQuery {
@@ -12,11 +27,255 @@ pub struct ListOrdersQuery {
     pub offset: Option<i64>,
 }
 
+/// Orders fetched per round trip by the default [`OrderRepository::stream`].
+pub const STREAM_PAGE: i64 = 500;
+
 #[async_trait]
 pub trait OrderRepository: Send + Sync {
     async fn create(&self, new: NewOrder) -> Result<Order, String>;
     async fn get_by_id(&self, id: &str) -> Result<Order, String>;
     async fn list(&self, q: ListOrdersQuery) -> Result<Vec<Order>, String>;
     async fn set_status(&self, id: &str, status: OrderStatus) -> Result<Order, String>;
+    /// Marks the order `Filled` and records the execution price.
+    async fn record_fill(&self, id: &str, exec_price: Decimal) -> Result<Order, String>;
+    /// Appends executions to the order; see [`Order::apply_fills`].
+    async fn record_fills(&self, id: &str, fills: &[Fill]) -> Result<Order, String>;
+    /// Overwrites status and filled quantity, e.g. from a settlement
+    /// system. Callers enforce the lifecycle rules.
+    async fn set_fill(
+        &self,
+        id: &str,
+        status: OrderStatus,
+        filled_quantity: Decimal,
+    ) -> Result<Order, String>;
+    /// Marks the order `Cancelled` and records why.
+    async fn cancel(&self, id: &str, reason: &str) -> Result<Order, String>;
+    /// Replaces price and quantity. Callers check the order may change.
+    async fn amend(&self, id: &str, price: Decimal, quantity: Decimal) -> Result<Order, String>;
     async fn delete(&self, id: &str) -> Result<(), String>;
+
+    /// Moves every order on `from` to `to` in one write, returning them as
+    /// written. Stores that cannot do that atomically refuse.
+    async fn rename_pair(&self, from: &str, to: &Symbol) -> Result<Vec<Order>, String> {
+        let _ = (from, to);
+        Err("this store cannot rename pairs".into())
+    }
+
+    /// Cancels `id` with [`CANCEL_REPLACED`](crate::entities::order::CANCEL_REPLACED)
+    /// and creates `new` in its place in one write, linked through
+    /// `replaced_by` and `replaces`; returns both as written. Errs, with
+    /// nothing changed, if `id` is no longer active. Stores that cannot do
+    /// both at once refuse.
+    async fn replace(&self, id: &str, new: NewOrder) -> Result<(Order, Order), String> {
+        let _ = (id, new);
+        Err("this store cannot replace orders".into())
+    }
+
+    /// Appends each order's fills in one write, returning the orders as
+    /// written, in the same order. Errs, with nothing changed, if any of
+    /// them is no longer active. Stores that cannot do it atomically
+    /// refuse.
+    async fn cross(&self, fills: &[(String, Vec<Fill>)]) -> Result<Vec<Order>, String> {
+        let _ = fills;
+        Err("this store cannot cross orders".into())
+    }
+
+    /// Turns an active stop or stop-limit order into the market or limit
+    /// order it becomes once its trigger price is reached (see
+    /// [`crate::entities::order::OrderType::activated`]). Stores that cannot rewrite orders refuse.
+    async fn activate_stop(&self, id: &str) -> Result<Order, String> {
+        let _ = id;
+        Err("this store cannot activate stop orders".into())
+    }
+
+    /// Stores a bracket in one write: `entry` as a new order and each of
+    /// `children` as a [`OrderStatus::Pending`] order whose `parent_id` is
+    /// the entry. Returns the entry, then the children in order. Stores
+    /// that cannot do it atomically refuse.
+    async fn create_bracket(
+        &self,
+        entry: NewOrder,
+        children: Vec<NewOrder>,
+    ) -> Result<(Order, Vec<Order>), String> {
+        let _ = (entry, children);
+        Err("this store cannot create brackets".into())
+    }
+
+    /// Records `fills` on one leg of an OCO pair and, in the same write,
+    /// cancels the other active orders sharing its `oco_id` with reason
+    /// [`crate::entities::order::CANCEL_OCO`]. Errs, with nothing changed, if the leg is
+    /// no longer active. Returns the leg as written and the orders
+    /// cancelled. Stores that cannot do it atomically refuse.
+    async fn fill_oco(&self, id: &str, fills: &[Fill]) -> Result<(Order, Vec<Order>), String> {
+        let _ = (id, fills);
+        Err("this store cannot fill OCO orders".into())
+    }
+
+    /// Moves a dormant trailing stop's watermark and trigger price (see
+    /// [`Order::trail`]). Stores that cannot rewrite orders refuse.
+    async fn trail_stop(
+        &self,
+        id: &str,
+        watermark: Decimal,
+        trigger_price: Decimal,
+    ) -> Result<Order, String> {
+        let _ = (id, watermark, trigger_price);
+        Err("this store cannot move trailing stops".into())
+    }
+
+    /// Moves `id` to [`OrderStatus::Expired`] if it is active or suspended
+    /// and its `expires_at` is at or before `now_ms`. Errs, with nothing
+    /// changed, otherwise. Stores that cannot rewrite orders refuse.
+    async fn expire(&self, id: &str, now_ms: i64) -> Result<Order, String> {
+        let _ = (id, now_ms);
+        Err("this store cannot expire orders".into())
+    }
+
+    /// Strips the order of its account, tags and metadata (see
+    /// [`Order::anonymize`]). Stores that cannot rewrite orders refuse.
+    async fn anonymize(&self, id: &str) -> Result<Order, String> {
+        let _ = id;
+        Err("this store cannot anonymize orders".into())
+    }
+
+    /// How far behind its primary this handle is, when it is a replica
+    /// that can tell.
+    fn replication_lag_ms(&self) -> Option<i64> {
+        None
+    }
+
+    /// Orders matching `q`, one at a time, for result sets too large to
+    /// collect. The default pages through `list` by offset; backends that
+    /// can hold a cursor or a consistent snapshot should override it.
+    fn stream(self: Arc<Self>, q: ListOrdersQuery) -> BoxStream<'static, Result<Order, String>>
+    where
+        Self: 'static,
+    {
+        let start = q.offset.unwrap_or(0).max(0);
+        let pages = stream::unfold(
+            (self, start, q.limit, false),
+            move |(repo, offset, remaining, done)| {
+                let q = q.clone();
+                async move {
+                    if done || remaining.is_some_and(|r| r <= 0) {
+                        return None;
+                    }
+                    let page = remaining.map_or(STREAM_PAGE, |r| r.min(STREAM_PAGE));
+                    let r = repo
+                        .list(ListOrdersQuery {
+                            limit: Some(page),
+                            offset: Some(offset),
+                            ..q
+                        })
+                        .await;
+                    let (items, done) = match r {
+                        Ok(v) => {
+                            let short = (v.len() as i64) < page;
+                            (v.into_iter().map(Ok).collect(), short)
+                        }
+                        Err(e) => (vec![Err(e)], true),
+                    };
+                    let n = items.len() as i64;
+                    let next = (repo, offset + n, remaining.map(|r| r - n), done);
+                    Some((stream::iter(items), next))
+                }
+            },
+        );
+        pages.flatten().boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repositories::in_memory::InMemoryOrderRepository;
+    use rust_decimal_macros::dec;
+    use std::collections::HashSet;
+
+    /// Only delegates the required methods, so it streams through the
+    /// default paging.
+    struct Paged(InMemoryOrderRepository);
+
+    #[async_trait]
+    impl OrderRepository for Paged {
+        async fn create(&self, new: NewOrder) -> Result<Order, String> {
+            self.0.create(new).await
+        }
+        async fn get_by_id(&self, id: &str) -> Result<Order, String> {
+            self.0.get_by_id(id).await
+        }
+        async fn list(&self, q: ListOrdersQuery) -> Result<Vec<Order>, String> {
+            self.0.list(q).await
+        }
+        async fn set_status(&self, id: &str, status: OrderStatus) -> Result<Order, String> {
+            self.0.set_status(id, status).await
+        }
+        async fn record_fill(&self, id: &str, px: Decimal) -> Result<Order, String> {
+            self.0.record_fill(id, px).await
+        }
+        async fn record_fills(&self, id: &str, fills: &[Fill]) -> Result<Order, String> {
+            self.0.record_fills(id, fills).await
+        }
+        async fn set_fill(&self, id: &str, s: OrderStatus, q: Decimal) -> Result<Order, String> {
+            self.0.set_fill(id, s, q).await
+        }
+        async fn cancel(&self, id: &str, reason: &str) -> Result<Order, String> {
+            self.0.cancel(id, reason).await
+        }
+        async fn amend(&self, id: &str, px: Decimal, qty: Decimal) -> Result<Order, String> {
+            self.0.amend(id, px, qty).await
+        }
+        async fn delete(&self, id: &str) -> Result<(), String> {
+            self.0.delete(id).await
+        }
+    }
+
+    #[tokio::test]
+    async fn default_stream_pages_through_list() {
+        let repo = Arc::new(Paged(InMemoryOrderRepository::default()));
+        for _ in 0..(2 * STREAM_PAGE + 7) {
+            repo.create(NewOrder {
+                pair: "BTC/USDT".into(),
+                side: crate::entities::order::OrderSide::Buy,
+                order_type: Default::default(),
+                price: dec!(1),
+                quantity: dec!(1),
+                trigger_price_type: Default::default(),
+                condition: Default::default(),
+                sustained_for_ms: None,
+                time_in_force: Default::default(),
+                max_slippage_bps: None,
+                exec_pricing: None,
+                price_source: None,
+                account_id: None,
+                quantity_quote: None,
+                cancel_after_ms: None,
+                valid_for_after_armed_ms: None,
+                expires_at: None,
+                tags: Vec::new(),
+                metadata: Default::default(),
+                callback_url: None,
+                callback_template: None,
+                trigger_price: None,
+                trailing: None,
+                oco_id: None,
+            })
+            .await
+            .unwrap();
+        }
+        let all: HashSet<String> = repo
+            .clone()
+            .stream(ListOrdersQuery::default())
+            .map(|r| r.unwrap().id)
+            .collect()
+            .await;
+        assert_eq!(all.len() as i64, 2 * STREAM_PAGE + 7);
+
+        let window = repo.clone().stream(ListOrdersQuery {
+            limit: Some(STREAM_PAGE + 1),
+            offset: Some(STREAM_PAGE),
+            ..ListOrdersQuery::default()
+        });
+        assert_eq!(window.count().await as i64, STREAM_PAGE + 1);
+    }
 }
@@ -0,0 +1,532 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+
+use rust_decimal::{Decimal, RoundingStrategy};
+use serde::Serialize;
+
+use crate::entities::events::OrderEvent;
+use crate::entities::order::{Condition, Order, OrderSide, OrderStatus, OrderType};
+use crate::instruments::symbol::Symbol;
+use crate::repositories::changes::{ChangeListener, ChangeStream, OrderChange};
+use crate::repositories::{ListOrdersQuery, OrderRepository};
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BookLevel {
+    #[serde(serialize_with = "crate::utils::decimal::serialize")]
+    pub price: Decimal,
+    #[serde(serialize_with = "crate::utils::decimal::serialize")]
+    pub quantity: Decimal,
+    pub orders: usize,
+}
+
+/// Resting price-triggered limit orders aggregated by price (bids best
+/// first, asks best first). Orders triggering on anything else have no
+/// meaningful level and are only counted.
+#[derive(Debug, Clone, Serialize)]
+pub struct Book {
+    pub pair: String,
+    pub bids: Vec<BookLevel>,
+    pub asks: Vec<BookLevel>,
+    pub other_orders: usize,
+}
+
+/// Where a pair's resting orders trigger relative to `price`, the last
+/// oracle price: buy levels below it and sell levels above, nearest first.
+/// Quantities are what is left unfilled, summed per level.
+#[derive(Debug, Clone, Serialize)]
+pub struct TriggerLevels {
+    pub pair: String,
+    #[serde(serialize_with = "crate::utils::decimal::serialize_opt")]
+    pub price: Option<Decimal>,
+    pub below: Vec<BookLevel>,
+    pub above: Vec<BookLevel>,
+    /// Orders the price has already reached, held back by something else
+    /// such as a halt or a sustain window.
+    pub due: usize,
+    /// Orders whose condition has no price level, such as indicators.
+    pub other_orders: usize,
+}
+
+impl Book {
+    /// The book with its levels merged into buckets `step` wide: bids
+    /// down to a multiple of `step` and asks up to one, so a bucket never
+    /// shows a better price than its orders.
+    pub fn bucketed(self, step: Decimal) -> Book {
+        let merge = |levels: Vec<BookLevel>, strategy| {
+            let mut out: Vec<BookLevel> = Vec::new();
+            for l in levels {
+                let price =
+                    ((l.price / step).round_dp_with_strategy(0, strategy) * step).normalize();
+                match out.last_mut() {
+                    Some(last) if last.price == price => {
+                        last.quantity += l.quantity;
+                        last.orders += l.orders;
+                    }
+                    _ => out.push(BookLevel { price, ..l }),
+                }
+            }
+            out
+        };
+        Book {
+            bids: merge(self.bids, RoundingStrategy::ToNegativeInfinity),
+            asks: merge(self.asks, RoundingStrategy::ToPositiveInfinity),
+            ..self
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Level {
+    quantity: Decimal,
+    orders: usize,
+}
+
+/// A pair's active orders and the book levels they add up to. Orders are
+/// keyed by id and carry an insertion sequence so listings are stable for
+/// orders created in the same millisecond. The levels are kept as orders
+/// come and go, so a book read walks levels rather than orders.
+#[derive(Default)]
+struct PairOrders {
+    orders: HashMap<String, (u64, Order)>,
+    bids: BTreeMap<Decimal, Level>,
+    asks: BTreeMap<Decimal, Level>,
+    other_orders: usize,
+}
+
+impl PairOrders {
+    fn put(&mut self, o: Order, seq: impl FnOnce() -> u64) {
+        let seq = match self.remove(&o.id) {
+            Some((seq, _)) => seq,
+            None => seq(),
+        };
+        self.tally(&o, true);
+        self.orders.insert(o.id.clone(), (seq, o));
+    }
+
+    fn remove(&mut self, id: &str) -> Option<(u64, Order)> {
+        let gone = self.orders.remove(id)?;
+        self.tally(&gone.1, false);
+        Some(gone)
+    }
+
+    /// Adds `o` to its level, or takes it off.
+    fn tally(&mut self, o: &Order, add: bool) {
+        if o.order_type != OrderType::Limit || o.condition != Condition::Price {
+            if add {
+                self.other_orders += 1;
+            } else {
+                self.other_orders -= 1;
+            }
+            return;
+        }
+        let side = match o.side {
+            OrderSide::Buy => &mut self.bids,
+            OrderSide::Sell => &mut self.asks,
+        };
+        let left = o.quantity - o.filled_quantity;
+        let level = side.entry(o.price).or_default();
+        if add {
+            level.quantity += left;
+            level.orders += 1;
+        } else {
+            level.quantity -= left;
+            level.orders -= 1;
+            if level.orders == 0 {
+                side.remove(&o.price);
+            }
+        }
+    }
+}
+
+async fn active_orders<R: OrderRepository + ?Sized>(repo: &R) -> Result<Vec<Order>, String> {
+    let mut out = Vec::new();
+    for status in OrderStatus::ACTIVE {
+        out.extend(
+            repo.list(ListOrdersQuery {
+                status: Some(status),
+                ..ListOrdersQuery::default()
+            })
+            .await?,
+        );
+    }
+    Ok(out)
+}
+
+#[derive(Default)]
+struct Inner {
+    live: AtomicBool,
+    seq: AtomicU64,
+    by_pair: RwLock<HashMap<Symbol, PairOrders>>,
+}
+
+/// Active orders per pair, kept in memory from the change stream so the
+/// hottest reads never scan the store. Only answers once attached.
+#[derive(Clone, Default)]
+pub struct OpenOrdersView {
+    inner: Arc<Inner>,
+}
+
+impl OpenOrdersView {
+    /// Subscribes the view to `stream`, seeds it from `repo` and marks it
+    /// live.
+    pub async fn attach<R: OrderRepository + ?Sized>(
+        &self,
+        repo: &R,
+        stream: &ChangeStream,
+    ) -> Result<(), String> {
+        stream.listen(Arc::new(self.clone()));
+        for o in active_orders(repo).await? {
+            self.upsert(o);
+        }
+        self.inner.live.store(true, Ordering::Release);
+        Ok(())
+    }
+
+    /// Re-seeds the view from `repo`, for when it may have drifted from
+    /// the store, and returns how many orders it then holds. The store is
+    /// read twice: once to build the new view, swapped in whole, and once
+    /// more to catch what changed under the first read.
+    pub async fn rebuild<R: OrderRepository + ?Sized>(&self, repo: &R) -> Result<usize, String> {
+        let started = crate::utils::now_ms();
+        let fresh = OpenOrdersView::default();
+        for o in active_orders(repo).await? {
+            fresh.upsert(o);
+        }
+        let fresh = std::mem::take(&mut *fresh.inner.by_pair.write().unwrap());
+        *self.inner.by_pair.write().unwrap() = fresh;
+        let stored = active_orders(repo).await?;
+        let ids: HashSet<&str> = stored.iter().map(|o| o.id.as_str()).collect();
+        {
+            let mut w = self.inner.by_pair.write().unwrap();
+            for m in w.values_mut() {
+                let gone: Vec<String> = m
+                    .orders
+                    .keys()
+                    .filter(|id| !ids.contains(id.as_str()))
+                    .cloned()
+                    .collect();
+                for id in gone {
+                    m.remove(&id);
+                }
+            }
+        }
+        let count = stored.len();
+        for o in stored.into_iter().filter(|o| o.updated >= started) {
+            self.upsert(o);
+        }
+        Ok(count)
+    }
+
+    /// Like [`attach`](Self::attach) for a store known to be empty.
+    pub fn follow(&self, stream: &ChangeStream) {
+        stream.listen(Arc::new(self.clone()));
+        self.inner.live.store(true, Ordering::Release);
+    }
+
+    pub fn is_live(&self) -> bool {
+        self.inner.live.load(Ordering::Acquire)
+    }
+
+    fn upsert(&self, o: Order) {
+        let mut w = self.inner.by_pair.write().unwrap();
+        // An order new to its pair's bucket may have moved from another
+        // pair's, when the pair was renamed.
+        if w.get(&o.pair).is_none_or(|m| !m.orders.contains_key(&o.id)) {
+            for (pair, m) in w.iter_mut() {
+                if *pair != o.pair {
+                    m.remove(&o.id);
+                }
+            }
+        }
+        if o.status.is_active() {
+            w.entry(o.pair.clone())
+                .or_default()
+                .put(o, || self.inner.seq.fetch_add(1, Ordering::Relaxed));
+        } else if let Some(m) = w.get_mut(&o.pair) {
+            m.remove(&o.id);
+        }
+    }
+
+    fn remove(&self, o: &Order) {
+        if let Some(m) = self.inner.by_pair.write().unwrap().get_mut(&o.pair) {
+            m.remove(&o.id);
+        }
+    }
+
+    /// Same contract as [`OrderRepository::list`] for active statuses,
+    /// ordered oldest first.
+    pub fn list(&self, q: &ListOrdersQuery) -> Vec<Order> {
+        let r = self.inner.by_pair.read().unwrap();
+        let mut v: Vec<&(u64, Order)> = r
+            .iter()
+            .filter(|(pair, _)| q.pair.as_ref().is_none_or(|p| p == *pair))
+            .flat_map(|(_, m)| m.orders.values())
+            .filter(|(_, o)| q.status.as_ref().is_none_or(|s| &o.status == s))
+            .collect();
+        v.sort_by_key(|(seq, o)| (o.created, *seq));
+        let start = q.offset.unwrap_or(0).max(0) as usize;
+        let mut v: Vec<Order> = v.into_iter().skip(start).map(|(_, o)| o.clone()).collect();
+        if let Some(l) = q.limit.filter(|&l| l > 0) {
+            v.truncate(l as usize);
+        }
+        v
+    }
+
+    pub fn book(&self, pair: &str) -> Book {
+        let r = self.inner.by_pair.read().unwrap();
+        let level = |(price, l): (&Decimal, &Level)| BookLevel {
+            price: *price,
+            quantity: l.quantity,
+            orders: l.orders,
+        };
+        let m = r.get(pair);
+        Book {
+            pair: pair.to_string(),
+            bids: m
+                .into_iter()
+                .flat_map(|m| m.bids.iter().rev().map(level))
+                .collect(),
+            asks: m
+                .into_iter()
+                .flat_map(|m| m.asks.iter().map(level))
+                .collect(),
+            other_orders: m.map_or(0, |m| m.other_orders),
+        }
+    }
+}
+
+impl OpenOrdersView {
+    /// The `depth` nearest trigger levels each side of `price`, levels
+    /// rounded to `scale` decimals. Without a price every buy level counts
+    /// as below and every sell level as above.
+    pub fn triggers(
+        &self,
+        pair: &str,
+        price: Option<Decimal>,
+        scale: u32,
+        depth: usize,
+    ) -> TriggerLevels {
+        let r = self.inner.by_pair.read().unwrap();
+        let mut below: BTreeMap<Decimal, BookLevel> = BTreeMap::new();
+        let mut above: BTreeMap<Decimal, BookLevel> = BTreeMap::new();
+        let (mut due, mut other_orders) = (0, 0);
+        for (_, o) in r.get(pair).into_iter().flat_map(|m| m.orders.values()) {
+            let Some(level) = o.trigger_level() else {
+                other_orders += 1;
+                continue;
+            };
+            let level = level.round_dp(scale);
+            let (side, reached) = match o.side {
+                OrderSide::Buy => (&mut below, price.is_some_and(|px| px <= level)),
+                OrderSide::Sell => (&mut above, price.is_some_and(|px| px >= level)),
+            };
+            if reached {
+                due += 1;
+                continue;
+            }
+            let l = side.entry(level).or_insert(BookLevel {
+                price: level,
+                quantity: Decimal::ZERO,
+                orders: 0,
+            });
+            l.quantity += o.quantity - o.filled_quantity;
+            l.orders += 1;
+        }
+        TriggerLevels {
+            pair: pair.to_string(),
+            price,
+            below: below.into_values().rev().take(depth).collect(),
+            above: above.into_values().take(depth).collect(),
+            due,
+            other_orders,
+        }
+    }
+}
+
+impl ChangeListener for OpenOrdersView {
+    fn on_change(&self, c: &OrderChange) {
+        match &c.event {
+            OrderEvent::Created { order } | OrderEvent::Updated { order } => {
+                self.upsert(order.clone())
+            }
+            OrderEvent::Deleted { order } => self.remove(order),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repositories::changes::Observed;
+    use crate::repositories::in_memory::InMemoryOrderRepository;
+    use rust_decimal_macros::dec;
+
+    fn order(side: OrderSide, price: Decimal, qty: Decimal) -> Order {
+        Order::new("BTC/USDT".into(), side, price, qty)
+    }
+
+    #[test]
+    fn book_aggregates_levels_and_drops_inactive_orders() {
+        let view = OpenOrdersView::default();
+        let a = order(OrderSide::Buy, dec!(100), dec!(1));
+        let b = Order {
+            filled_quantity: dec!(0.5),
+            status: OrderStatus::PartiallyFilled,
+            ..order(OrderSide::Buy, dec!(100), dec!(2))
+        };
+        let c = order(OrderSide::Buy, dec!(101), dec!(1));
+        let d = order(OrderSide::Sell, dec!(105), dec!(3));
+        let m = Order {
+            order_type: OrderType::Market,
+            ..order(OrderSide::Sell, Decimal::ZERO, dec!(1))
+        };
+        for o in [&a, &b, &c, &d, &m] {
+            view.upsert(o.clone());
+        }
+        view.upsert(Order {
+            status: OrderStatus::Filled,
+            ..c.clone()
+        });
+
+        let book = view.book("BTC/USDT");
+        assert_eq!(book.bids.len(), 1);
+        assert_eq!(book.bids[0].quantity, dec!(2.5));
+        assert_eq!(book.bids[0].orders, 2);
+        assert_eq!(book.asks[0].price, dec!(105));
+        assert_eq!(book.other_orders, 1);
+        assert!(view.book("ETH/USDT").bids.is_empty());
+
+        // Cancelling takes the order off its level, and an emptied level
+        // is gone.
+        view.upsert(Order {
+            status: OrderStatus::Cancelled,
+            ..b.clone()
+        });
+        view.upsert(Order {
+            price: dec!(104.5),
+            ..d.clone()
+        });
+        let book = view.book("BTC/USDT");
+        assert_eq!((book.bids[0].quantity, book.bids[0].orders), (dec!(1), 1));
+        assert_eq!(book.asks.len(), 1);
+        assert_eq!(book.asks[0].price, dec!(104.5));
+    }
+
+    #[test]
+    fn buckets_round_bids_down_and_asks_up() {
+        let view = OpenOrdersView::default();
+        for (side, px, qty) in [
+            (OrderSide::Buy, dec!(100.4), dec!(1)),
+            (OrderSide::Buy, dec!(100), dec!(2)),
+            (OrderSide::Buy, dec!(99.5), dec!(1)),
+            (OrderSide::Sell, dec!(100.6), dec!(3)),
+            (OrderSide::Sell, dec!(101), dec!(1)),
+        ] {
+            view.upsert(order(side, px, qty));
+        }
+        let book = view.book("BTC/USDT").bucketed(dec!(0.5));
+        let levels = |v: &[BookLevel]| {
+            v.iter()
+                .map(|l| (l.price, l.quantity, l.orders))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            levels(&book.bids),
+            [(dec!(100), dec!(3), 2), (dec!(99.5), dec!(1), 1)]
+        );
+        assert_eq!(levels(&book.asks), [(dec!(101), dec!(4), 2)]);
+    }
+
+    #[test]
+    fn triggers_split_levels_around_the_price_nearest_first() {
+        let view = OpenOrdersView::default();
+        let notional = Order {
+            condition: Condition::Notional {
+                threshold: dec!(300),
+                quantity: None,
+            },
+            ..order(OrderSide::Buy, Decimal::ZERO, dec!(3))
+        };
+        let indicator = Order {
+            condition: Condition::Volume {
+                window_ms: 1_000,
+                min_volume: dec!(1),
+                with_price: false,
+            },
+            ..order(OrderSide::Buy, dec!(90), dec!(1))
+        };
+        for o in [
+            order(OrderSide::Buy, dec!(99), dec!(1)),
+            order(OrderSide::Buy, dec!(95), dec!(2)),
+            order(OrderSide::Buy, dec!(90), dec!(1)),
+            notional,
+            order(OrderSide::Buy, dec!(101), dec!(1)),
+            order(OrderSide::Sell, dec!(102), dec!(1)),
+            order(OrderSide::Sell, dec!(104), dec!(4)),
+            indicator,
+        ] {
+            view.upsert(o);
+        }
+
+        let t = view.triggers("BTC/USDT", Some(dec!(100)), 2, 2);
+        let levels = |v: &[BookLevel]| v.iter().map(|l| (l.price, l.quantity)).collect::<Vec<_>>();
+        // The notional order triggers at 300 / 3 = 100, already reached,
+        // like the buy at 101.
+        assert_eq!(levels(&t.below), [(dec!(99), dec!(1)), (dec!(95), dec!(2))]);
+        assert_eq!(
+            levels(&t.above),
+            [(dec!(102), dec!(1)), (dec!(104), dec!(4))]
+        );
+        assert_eq!((t.due, t.other_orders), (2, 1));
+
+        let t = view.triggers("BTC/USDT", None, 2, 10);
+        assert_eq!(t.below[0].price, dec!(101));
+        assert_eq!((t.below.len(), t.due), (5, 0));
+    }
+
+    #[tokio::test]
+    async fn attach_seeds_from_store_then_follows_changes() {
+        let base = InMemoryOrderRepository::default();
+        let stream = ChangeStream::default();
+        let repo = Observed::new(base.clone(), stream.clone());
+        let seeded = repo
+            .create(crate::entities::order::NewOrder {
+                pair: "BTC/USDT".into(),
+                side: OrderSide::Buy,
+                order_type: OrderType::Limit,
+                price: dec!(1),
+                quantity: dec!(1),
+                trigger_price_type: Default::default(),
+                condition: Condition::Price,
+                sustained_for_ms: None,
+                time_in_force: Default::default(),
+                max_slippage_bps: None,
+                exec_pricing: None,
+                price_source: None,
+                account_id: None,
+                quantity_quote: None,
+                cancel_after_ms: None,
+                valid_for_after_armed_ms: None,
+                expires_at: None,
+                tags: Vec::new(),
+                metadata: Default::default(),
+                callback_url: None,
+                callback_template: None,
+                trigger_price: None,
+                trailing: None,
+                oco_id: None,
+            })
+            .await
+            .unwrap();
+
+        let view = OpenOrdersView::default();
+        assert!(!view.is_live());
+        view.attach(&base, &stream).await.unwrap();
+        assert!(view.is_live());
+        assert_eq!(view.list(&ListOrdersQuery::default()).len(), 1);
+
+        repo.cancel(&seeded.id, "user").await.unwrap();
+        assert!(view.list(&ListOrdersQuery::default()).is_empty());
+    }
+}
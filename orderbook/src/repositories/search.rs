@@ -0,0 +1,346 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::{Arc, RwLock};
+
+use crate::entities::events::OrderEvent;
+use crate::entities::order::Order;
+use crate::repositories::changes::{ChangeListener, ChangeStream, OrderChange};
+use crate::repositories::{ListOrdersQuery, OrderRepository};
+
+const EXACT_ID: u32 = 100;
+const ID_PREFIX: u32 = 10;
+const EXACT_TAG: u32 = 5;
+const TAG_PREFIX: u32 = 2;
+const FILTER: u32 = 1;
+
+/// A parsed search: bare terms, each matched against order IDs and tags,
+/// and `key:value` metadata filters that every hit must satisfy. Matching
+/// ignores case.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SearchQuery {
+    pub terms: Vec<String>,
+    pub filters: Vec<(String, String)>,
+}
+
+impl SearchQuery {
+    pub fn parse(q: &str) -> Result<Self, String> {
+        let mut out = Self::default();
+        for token in q.split_whitespace() {
+            let token = token.to_lowercase();
+            match token.split_once(':') {
+                Some((k, v)) if !k.is_empty() && !v.is_empty() => {
+                    out.filters.push((k.to_string(), v.to_string()))
+                }
+                Some(_) => return Err(format!("filter {token:?} must be key:value")),
+                None => out.terms.push(token),
+            }
+        }
+        if out.terms.is_empty() && out.filters.is_empty() {
+            return Err("q must have at least one term".into());
+        }
+        Ok(out)
+    }
+}
+
+/// An order found by a search, higher `score` first.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchHit {
+    pub order_id: String,
+    pub score: u32,
+}
+
+/// What an order is found by, lowercased, kept to unindex it.
+struct Doc {
+    id: String,
+    tags: Vec<String>,
+    metadata: Vec<(String, String)>,
+    updated: i64,
+}
+
+#[derive(Default)]
+struct Inner {
+    docs: HashMap<String, Doc>,
+    /// Lowercased ID to ID, sorted for prefix scans.
+    ids: BTreeMap<String, String>,
+    tags: BTreeMap<String, HashSet<String>>,
+    metadata: HashMap<(String, String), HashSet<String>>,
+}
+
+impl Inner {
+    fn insert(&mut self, o: &Order) {
+        self.remove(&o.id);
+        let doc = Doc {
+            id: o.id.to_lowercase(),
+            tags: o.tags.iter().map(|t| t.to_lowercase()).collect(),
+            metadata: o
+                .metadata
+                .iter()
+                .map(|(k, v)| (k.to_lowercase(), v.to_lowercase()))
+                .collect(),
+            updated: o.updated,
+        };
+        self.ids.insert(doc.id.clone(), o.id.clone());
+        for t in &doc.tags {
+            self.tags.entry(t.clone()).or_default().insert(o.id.clone());
+        }
+        for kv in &doc.metadata {
+            self.metadata
+                .entry(kv.clone())
+                .or_default()
+                .insert(o.id.clone());
+        }
+        self.docs.insert(o.id.clone(), doc);
+    }
+
+    fn remove(&mut self, id: &str) {
+        let Some(doc) = self.docs.remove(id) else {
+            return;
+        };
+        self.ids.remove(&doc.id);
+        for t in &doc.tags {
+            if let Some(s) = self.tags.get_mut(t) {
+                s.remove(id);
+                if s.is_empty() {
+                    self.tags.remove(t);
+                }
+            }
+        }
+        for kv in &doc.metadata {
+            if let Some(s) = self.metadata.get_mut(kv) {
+                s.remove(id);
+                if s.is_empty() {
+                    self.metadata.remove(kv);
+                }
+            }
+        }
+    }
+
+    /// Orders a bare term can match: IDs and tags it is a prefix of.
+    fn candidates(&self, term: &str, out: &mut HashSet<String>) {
+        let ids = self.ids.range(term.to_string()..);
+        for (_, id) in ids.take_while(|(lower, _)| lower.starts_with(term)) {
+            out.insert(id.clone());
+        }
+        let tags = self.tags.range(term.to_string()..);
+        for (_, s) in tags.take_while(|(t, _)| t.starts_with(term)) {
+            out.extend(s.iter().cloned());
+        }
+    }
+}
+
+/// Score of one bare term against a document, 0 if it matches nothing.
+fn term_score(doc: &Doc, term: &str) -> u32 {
+    let id = if doc.id == term {
+        EXACT_ID
+    } else if doc.id.starts_with(term) {
+        ID_PREFIX
+    } else {
+        0
+    };
+    let tag = doc
+        .tags
+        .iter()
+        .map(|t| {
+            if t == term {
+                EXACT_TAG
+            } else if t.starts_with(term) {
+                TAG_PREFIX
+            } else {
+                0
+            }
+        })
+        .max()
+        .unwrap_or(0);
+    id.max(tag)
+}
+
+/// An inverted index of orders by ID, tag and metadata pair, kept from the
+/// change stream. Covers every order the store holds, in any status.
+#[derive(Clone, Default)]
+pub struct OrderSearch {
+    inner: Arc<RwLock<Inner>>,
+}
+
+impl OrderSearch {
+    /// Subscribes the index to `stream` and seeds it from `repo`.
+    pub async fn attach<R: OrderRepository + ?Sized>(
+        &self,
+        repo: &R,
+        stream: &ChangeStream,
+    ) -> Result<(), String> {
+        stream.listen(Arc::new(self.clone()));
+        let orders = repo.list(ListOrdersQuery::default()).await?;
+        let mut w = self.inner.write().unwrap();
+        for o in &orders {
+            w.insert(o);
+        }
+        Ok(())
+    }
+
+    /// Like [`attach`](Self::attach) for a store known to be empty.
+    pub fn follow(&self, stream: &ChangeStream) {
+        stream.listen(Arc::new(self.clone()));
+    }
+
+    /// Re-indexes every order in `repo` and returns how many there are,
+    /// reading the store twice as [`OpenOrdersView::rebuild`] does.
+    ///
+    /// [`OpenOrdersView::rebuild`]: crate::repositories::open_orders::OpenOrdersView::rebuild
+    pub async fn rebuild<R: OrderRepository + ?Sized>(&self, repo: &R) -> Result<usize, String> {
+        let started = crate::utils::now_ms();
+        let mut fresh = Inner::default();
+        for o in &repo.list(ListOrdersQuery::default()).await? {
+            fresh.insert(o);
+        }
+        *self.inner.write().unwrap() = fresh;
+        let stored = repo.list(ListOrdersQuery::default()).await?;
+        let ids: HashSet<&str> = stored.iter().map(|o| o.id.as_str()).collect();
+        let mut w = self.inner.write().unwrap();
+        let gone: Vec<String> = w
+            .docs
+            .keys()
+            .filter(|id| !ids.contains(id.as_str()))
+            .cloned()
+            .collect();
+        for id in gone {
+            w.remove(&id);
+        }
+        for o in stored.iter().filter(|o| o.updated >= started) {
+            w.insert(o);
+        }
+        Ok(stored.len())
+    }
+
+    /// Up to `limit` orders matching `q`, best first, then most recently
+    /// updated. Every filter must hold and, if there are bare terms, at
+    /// least one must match an ID or tag exactly or by prefix.
+    pub fn search(&self, q: &SearchQuery, limit: usize) -> Vec<SearchHit> {
+        let r = self.inner.read().unwrap();
+        let mut pool: HashSet<String> = HashSet::new();
+        if let Some(smallest) = q
+            .filters
+            .iter()
+            .map(|kv| r.metadata.get(kv).map_or(0, |s| s.len()))
+            .min()
+        {
+            if smallest == 0 {
+                return Vec::new();
+            }
+            pool = q
+                .filters
+                .iter()
+                .filter_map(|kv| r.metadata.get(kv))
+                .find(|s| s.len() == smallest)
+                .cloned()
+                .unwrap_or_default();
+        } else {
+            for t in &q.terms {
+                r.candidates(t, &mut pool);
+            }
+        }
+
+        let mut hits: Vec<(SearchHit, i64)> = pool
+            .into_iter()
+            .filter_map(|id| {
+                let doc = r.docs.get(&id)?;
+                if !q.filters.iter().all(|kv| doc.metadata.contains(kv)) {
+                    return None;
+                }
+                let terms: u32 = q.terms.iter().map(|t| term_score(doc, t)).sum();
+                if !q.terms.is_empty() && terms == 0 {
+                    return None;
+                }
+                let score = terms + FILTER * q.filters.len() as u32;
+                Some((
+                    SearchHit {
+                        order_id: id,
+                        score,
+                    },
+                    doc.updated,
+                ))
+            })
+            .collect();
+        hits.sort_by(|(a, at), (b, bt)| {
+            (b.score, bt)
+                .cmp(&(a.score, at))
+                .then_with(|| a.order_id.cmp(&b.order_id))
+        });
+        hits.truncate(limit);
+        hits.into_iter().map(|(h, _)| h).collect()
+    }
+}
+
+impl ChangeListener for OrderSearch {
+    fn on_change(&self, c: &OrderChange) {
+        let mut w = self.inner.write().unwrap();
+        match &c.event {
+            OrderEvent::Created { order } | OrderEvent::Updated { order } => w.insert(order),
+            OrderEvent::Deleted { order } => w.remove(&order.id),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entities::test_support::OrderBuilder;
+
+    fn tagged(id: &str, tags: &[&str], metadata: &[(&str, &str)], updated: i64) -> Order {
+        Order {
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            metadata: metadata
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            updated,
+            ..OrderBuilder::buy("BTC/USDT").id(id).build()
+        }
+    }
+
+    #[test]
+    fn ranks_id_and_tag_matches_under_metadata_filters() {
+        let idx = OrderSearch::default();
+        {
+            let mut w = idx.inner.write().unwrap();
+            w.insert(&tagged("abc123", &["hedge"], &[("desk", "macro")], 1));
+            w.insert(&tagged(
+                "abd999",
+                &["Hedging", "btc"],
+                &[("desk", "macro")],
+                2,
+            ));
+            w.insert(&tagged("zzz000", &["abc"], &[("desk", "flow")], 3));
+        }
+        let ids = |q: &str| {
+            idx.search(&SearchQuery::parse(q).unwrap(), 10)
+                .into_iter()
+                .map(|h| (h.order_id, h.score))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            ids("abc"),
+            vec![("abc123".into(), ID_PREFIX), ("zzz000".into(), EXACT_TAG)]
+        );
+        assert_eq!(ids("ABC123"), vec![("abc123".into(), EXACT_ID)]);
+        // Prefix tag matches rank below exact ones; ties go to the newest.
+        assert_eq!(
+            ids("hedg desk:macro"),
+            vec![
+                ("abd999".into(), TAG_PREFIX + FILTER),
+                ("abc123".into(), TAG_PREFIX + FILTER)
+            ]
+        );
+        assert_eq!(ids("desk:flow"), vec![("zzz000".into(), FILTER)]);
+        assert!(ids("btc desk:flow").is_empty());
+        assert!(ids("nothing").is_empty());
+
+        // Re-indexing an order replaces its old terms.
+        idx.inner
+            .write()
+            .unwrap()
+            .insert(&tagged("zzz000", &[], &[], 4));
+        assert_eq!(ids("abc").len(), 1);
+        assert!(SearchQuery::parse("desk:").is_err());
+        assert!(SearchQuery::parse("  ").is_err());
+    }
+}
@@ -0,0 +1,740 @@
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::sync::{Arc, RwLock};
+
+use rust_decimal::Decimal;
+use serde::Serialize;
+
+use crate::entities::events::OrderEvent;
+use crate::entities::order::{Fill, Order, OrderSide, OrderStatus, OrderType};
+use crate::instruments::symbol::Symbol;
+use crate::repositories::changes::{ChangeListener, ChangeStream, OrderChange};
+use crate::repositories::{ListOrdersQuery, OrderRepository};
+use crate::utils::percentile;
+
+const BUCKET_MS: i64 = 60_000;
+const HOUR_MS: i64 = 3_600_000;
+const DAY_MS: i64 = 24 * HOUR_MS;
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Bucket {
+    created: u64,
+    filled: u64,
+    cancelled: u64,
+    fill_time_ms: i64,
+    buy_volume: Decimal,
+    sell_volume: Decimal,
+}
+
+impl Bucket {
+    fn add(&mut self, b: &Bucket) {
+        self.created += b.created;
+        self.filled += b.filled;
+        self.cancelled += b.cancelled;
+        self.fill_time_ms += b.fill_time_ms;
+        self.buy_volume += b.buy_volume;
+        self.sell_volume += b.sell_volume;
+    }
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct WindowStats {
+    pub orders_created: u64,
+    pub orders_filled: u64,
+    pub orders_cancelled: u64,
+    /// Filled share of orders that reached a terminal state in the window.
+    #[serde(serialize_with = "crate::utils::decimal::serialize_opt")]
+    pub fill_rate: Option<Decimal>,
+    pub avg_time_to_fill_ms: Option<i64>,
+    #[serde(serialize_with = "crate::utils::decimal::serialize")]
+    pub buy_volume: Decimal,
+    #[serde(serialize_with = "crate::utils::decimal::serialize")]
+    pub sell_volume: Decimal,
+}
+
+impl From<Bucket> for WindowStats {
+    fn from(b: Bucket) -> Self {
+        let terminal = b.filled + b.cancelled;
+        Self {
+            orders_created: b.created,
+            orders_filled: b.filled,
+            orders_cancelled: b.cancelled,
+            fill_rate: (terminal > 0).then(|| {
+                (Decimal::from(b.filled) / Decimal::from(terminal))
+                    .round_dp(4)
+                    .normalize()
+            }),
+            avg_time_to_fill_ms: (b.filled > 0).then(|| b.fill_time_ms / b.filled as i64),
+            buy_volume: b.buy_volume,
+            sell_volume: b.sell_volume,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct TradingStatsReport {
+    pub pair: Option<String>,
+    #[serde(rename = "1h")]
+    pub last_hour: WindowStats,
+    #[serde(rename = "24h")]
+    pub last_day: WindowStats,
+}
+
+#[derive(Default)]
+struct Inner {
+    /// Per pair, minute buckets keyed by bucket start, oldest first.
+    buckets: HashMap<String, VecDeque<(i64, Bucket)>>,
+    /// Last seen status, filled quantity and change time per order, so
+    /// each update contributes only its delta. Finished orders are kept for
+    /// a day to absorb late writes.
+    seen: HashMap<String, (OrderStatus, Decimal, i64)>,
+}
+
+impl Inner {
+    fn bucket(&mut self, pair: &str, ts_ms: i64) -> &mut Bucket {
+        let start = ts_ms - ts_ms.rem_euclid(BUCKET_MS);
+        let q = self.buckets.entry(pair.to_string()).or_default();
+        while q.front().is_some_and(|(s, _)| *s <= start - DAY_MS) {
+            q.pop_front();
+        }
+        if q.back().is_none_or(|(s, _)| *s < start) {
+            q.push_back((start, Bucket::default()));
+            self.seen
+                .retain(|_, (status, _, ts)| status.is_active() || *ts > start - DAY_MS);
+        }
+        let q = self.buckets.get_mut(pair).unwrap();
+        // Changes arrive in write order, so the newest bucket is the one.
+        &mut q.back_mut().unwrap().1
+    }
+}
+
+/// Rolling 1h/24h trading counters per pair, folded incrementally from the
+/// change stream in one-minute buckets.
+#[derive(Clone, Default)]
+pub struct TradingStats {
+    inner: Arc<RwLock<Inner>>,
+}
+
+impl TradingStats {
+    pub fn attach(&self, stream: &ChangeStream) {
+        stream.listen(Arc::new(self.clone()));
+    }
+
+    fn window(&self, pair: Option<&str>, now_ms: i64, span_ms: i64) -> WindowStats {
+        let r = self.inner.read().unwrap();
+        let since = now_ms - span_ms;
+        let mut total = Bucket::default();
+        for (p, q) in &r.buckets {
+            if pair.is_some_and(|want| want != p) {
+                continue;
+            }
+            for (_, b) in q.iter().filter(|(start, _)| start + BUCKET_MS > since) {
+                total.add(b);
+            }
+        }
+        total.into()
+    }
+
+    /// Stats for one pair, or across all pairs when `pair` is `None`.
+    pub fn report(&self, pair: Option<&str>, now_ms: i64) -> TradingStatsReport {
+        TradingStatsReport {
+            pair: pair.map(str::to_string),
+            last_hour: self.window(pair, now_ms, HOUR_MS),
+            last_day: self.window(pair, now_ms, DAY_MS),
+        }
+    }
+}
+
+impl ChangeListener for TradingStats {
+    fn on_change(&self, c: &OrderChange) {
+        let o = c.order();
+        let created = matches!(c.event, OrderEvent::Created { .. });
+        let mut w = self.inner.write().unwrap();
+        if c.is_delete() {
+            w.seen.remove(&o.id);
+            return;
+        }
+        let (prev_status, prev_filled) = if created {
+            (None, Decimal::ZERO)
+        } else {
+            w.seen
+                .get(&o.id)
+                .map(|(s, f, _)| (Some(s.clone()), *f))
+                .unwrap_or((None, Decimal::ZERO))
+        };
+        let filled = if o.status == OrderStatus::Filled {
+            o.quantity
+        } else {
+            o.filled_quantity
+        };
+        let b = w.bucket(&o.pair, c.ts_ms);
+        if created {
+            b.created += 1;
+        }
+        let delta = filled - prev_filled;
+        if delta > Decimal::ZERO {
+            match o.side {
+                OrderSide::Buy => b.buy_volume += delta,
+                OrderSide::Sell => b.sell_volume += delta,
+            }
+        }
+        if prev_status.as_ref() != Some(&o.status) {
+            match o.status {
+                OrderStatus::Filled => {
+                    b.filled += 1;
+                    b.fill_time_ms += (c.ts_ms - o.created).max(0);
+                }
+                OrderStatus::Cancelled => b.cancelled += 1,
+                _ => {}
+            }
+        }
+        w.seen
+            .insert(o.id.clone(), (o.status.clone(), filled, c.ts_ms));
+    }
+}
+
+/// Most recent samples kept per (pair, condition type).
+const LATENCY_SAMPLES: usize = 10_000;
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct Percentiles {
+    pub count: usize,
+    pub min_ms: i64,
+    pub p50_ms: i64,
+    pub p90_ms: i64,
+    pub p99_ms: i64,
+    pub max_ms: i64,
+    pub mean_ms: i64,
+}
+
+impl Percentiles {
+    /// `None` without samples.
+    fn of(samples: impl Iterator<Item = i64>) -> Option<Self> {
+        let mut sorted: Vec<i64> = samples.collect();
+        if sorted.is_empty() {
+            return None;
+        }
+        sorted.sort_unstable();
+        Some(Self {
+            count: sorted.len(),
+            min_ms: sorted[0],
+            p50_ms: percentile(&sorted, 50),
+            p90_ms: percentile(&sorted, 90),
+            p99_ms: percentile(&sorted, 99),
+            max_ms: sorted[sorted.len() - 1],
+            mean_ms: sorted.iter().sum::<i64>() / sorted.len() as i64,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct LatencyDistribution {
+    pub pair: Symbol,
+    pub condition: &'static str,
+    #[serde(flatten)]
+    pub ms: Percentiles,
+}
+
+/// Where the time went on a pair's recent engine fills; see
+/// [`Fill`](crate::entities::order::Fill) for what each leg measures.
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct FillLatency {
+    pub pair: Symbol,
+    pub clock_skew: Percentiles,
+    pub processing: Percentiles,
+    pub end_to_end: Percentiles,
+}
+
+/// One engine decision's timings: clock skew, processing, end to end.
+type FillTiming = (i64, i64, i64);
+
+#[derive(Default)]
+struct LatencyInner {
+    samples: BTreeMap<(Symbol, &'static str), VecDeque<i64>>,
+    /// Orders already sampled, oldest first, so repeated writes to a
+    /// filled order are not counted again.
+    counted: HashSet<String>,
+    counted_order: VecDeque<String>,
+    timings: BTreeMap<Symbol, VecDeque<FillTiming>>,
+    /// Fills already timed per order, oldest order first.
+    fills_seen: HashMap<String, usize>,
+    fills_seen_order: VecDeque<String>,
+}
+
+impl LatencyInner {
+    /// Times the fills `o` gained since the last change. They come from a
+    /// single engine decision, so they make one sample.
+    fn time_fills(&mut self, o: &crate::entities::order::Order) {
+        let seen = match self.fills_seen.insert(o.id.clone(), o.fills.len()) {
+            Some(n) => n,
+            None => {
+                self.fills_seen_order.push_back(o.id.clone());
+                if self.fills_seen_order.len() > LATENCY_SAMPLES {
+                    if let Some(old) = self.fills_seen_order.pop_front() {
+                        self.fills_seen.remove(&old);
+                    }
+                }
+                0
+            }
+        };
+        let Some(f) = o.fills.get(seen..).and_then(|new| new.last()) else {
+            return;
+        };
+        let (Some(skew), Some(processing), Some(e2e)) =
+            (f.clock_skew_ms(), f.processing_ms(), f.end_to_end_ms())
+        else {
+            return;
+        };
+        let q = self.timings.entry(o.pair.clone()).or_default();
+        q.push_back((skew, processing, e2e));
+        if q.len() > LATENCY_SAMPLES {
+            q.pop_front();
+        }
+    }
+}
+
+/// Creation-to-fill latency samples per pair and condition type, taken
+/// from each fill's `triggered_after_ms`, and the timestamps each engine
+/// fill carries, per pair.
+#[derive(Clone, Default)]
+pub struct TriggerLatency {
+    inner: Arc<RwLock<LatencyInner>>,
+}
+
+impl TriggerLatency {
+    pub fn attach(&self, stream: &ChangeStream) {
+        stream.listen(Arc::new(self.clone()));
+    }
+
+    pub fn distributions(&self, pair: Option<&str>) -> Vec<LatencyDistribution> {
+        let r = self.inner.read().unwrap();
+        r.samples
+            .iter()
+            .filter(|((p, _), q)| pair.is_none_or(|want| want == p) && !q.is_empty())
+            .filter_map(|((p, kind), q)| {
+                Some(LatencyDistribution {
+                    pair: p.clone(),
+                    condition: kind,
+                    ms: Percentiles::of(q.iter().copied())?,
+                })
+            })
+            .collect()
+    }
+
+    /// Skew, processing and end-to-end latency over the latest
+    /// [`LATENCY_SAMPLES`] engine decisions per pair.
+    pub fn fill_latency(&self, pair: Option<&str>) -> Vec<FillLatency> {
+        let r = self.inner.read().unwrap();
+        r.timings
+            .iter()
+            .filter(|(p, _)| pair.is_none_or(|want| want == p.as_str()))
+            .filter_map(|(p, q)| {
+                Some(FillLatency {
+                    pair: p.clone(),
+                    clock_skew: Percentiles::of(q.iter().map(|t| t.0))?,
+                    processing: Percentiles::of(q.iter().map(|t| t.1))?,
+                    end_to_end: Percentiles::of(q.iter().map(|t| t.2))?,
+                })
+            })
+            .collect()
+    }
+}
+
+impl ChangeListener for TriggerLatency {
+    fn on_change(&self, c: &OrderChange) {
+        let o = c.order();
+        if c.is_delete() {
+            return;
+        }
+        let mut w = self.inner.write().unwrap();
+        if !o.fills.is_empty() {
+            w.time_fills(o);
+        }
+        let Some(ms) = o.triggered_after_ms else {
+            return;
+        };
+        if o.status != OrderStatus::Filled {
+            return;
+        }
+        if !w.counted.insert(o.id.clone()) {
+            return;
+        }
+        w.counted_order.push_back(o.id.clone());
+        if w.counted_order.len() > LATENCY_SAMPLES {
+            if let Some(old) = w.counted_order.pop_front() {
+                w.counted.remove(&old);
+            }
+        }
+        let q = w
+            .samples
+            .entry((o.pair.clone(), o.condition.kind()))
+            .or_default();
+        q.push_back(ms);
+        if q.len() > LATENCY_SAMPLES {
+            q.pop_front();
+        }
+    }
+}
+
+/// Most recent fill-quality samples kept per (pair, order type).
+const SLIPPAGE_SAMPLES: usize = 10_000;
+
+/// Slippage percentiles in basis points. Positive is worse for the order
+/// than its trigger price: a buy paying more, a sell receiving less.
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct SlippageDistribution {
+    pub pair: Symbol,
+    pub order_type: OrderType,
+    pub count: usize,
+    #[serde(serialize_with = "crate::utils::decimal::serialize")]
+    pub min_bps: Decimal,
+    #[serde(serialize_with = "crate::utils::decimal::serialize")]
+    pub p50_bps: Decimal,
+    #[serde(serialize_with = "crate::utils::decimal::serialize")]
+    pub p90_bps: Decimal,
+    #[serde(serialize_with = "crate::utils::decimal::serialize")]
+    pub p99_bps: Decimal,
+    #[serde(serialize_with = "crate::utils::decimal::serialize")]
+    pub max_bps: Decimal,
+    #[serde(serialize_with = "crate::utils::decimal::serialize")]
+    pub mean_bps: Decimal,
+}
+
+/// How far one engine decision's fills landed from the trigger price, in
+/// basis points; `None` for fills without a trigger price.
+fn slippage_bps(side: &OrderSide, fills: &[Fill]) -> Option<Decimal> {
+    let trigger = fills.first()?.trigger_price.filter(|p| !p.is_zero())?;
+    let qty: Decimal = fills.iter().map(|f| f.quantity).sum();
+    if qty.is_zero() {
+        return None;
+    }
+    let vwap = fills.iter().map(|f| f.price * f.quantity).sum::<Decimal>() / qty;
+    let diff = match side {
+        OrderSide::Buy => vwap - trigger,
+        OrderSide::Sell => trigger - vwap,
+    };
+    Some((diff / trigger * Decimal::from(10_000)).round_dp(2))
+}
+
+#[derive(Default)]
+struct QualityInner {
+    samples: BTreeMap<(Symbol, OrderType), VecDeque<Decimal>>,
+    /// Fills already sampled per order, oldest order first.
+    fills_seen: HashMap<String, usize>,
+    fills_seen_order: VecDeque<String>,
+}
+
+impl QualityInner {
+    /// Samples the fills `o` gained since it was last seen, one sample per
+    /// engine decision (the fills of one tick).
+    fn sample(&mut self, o: &Order) {
+        let seen = match self.fills_seen.insert(o.id.clone(), o.fills.len()) {
+            Some(n) => n,
+            None => {
+                self.fills_seen_order.push_back(o.id.clone());
+                if self.fills_seen_order.len() > SLIPPAGE_SAMPLES {
+                    if let Some(old) = self.fills_seen_order.pop_front() {
+                        self.fills_seen.remove(&old);
+                    }
+                }
+                0
+            }
+        };
+        let Some(new) = o.fills.get(seen..) else {
+            return;
+        };
+        for decision in new.chunk_by(|a, b| a.ts_ms == b.ts_ms) {
+            let Some(bps) = slippage_bps(&o.side, decision) else {
+                continue;
+            };
+            let q = self
+                .samples
+                .entry((o.pair.clone(), o.order_type))
+                .or_default();
+            q.push_back(bps);
+            if q.len() > SLIPPAGE_SAMPLES {
+                q.pop_front();
+            }
+        }
+    }
+}
+
+/// Execution price against trigger price per pair and order type, over
+/// the latest [`SLIPPAGE_SAMPLES`] engine decisions. Fills are kept on
+/// their orders, so the samples are rebuilt from the store at boot.
+#[derive(Clone, Default)]
+pub struct FillQuality {
+    inner: Arc<RwLock<QualityInner>>,
+}
+
+impl FillQuality {
+    /// Subscribes to `stream` and samples the fills already in `repo`.
+    pub async fn attach<R: OrderRepository + ?Sized>(
+        &self,
+        repo: &R,
+        stream: &ChangeStream,
+    ) -> Result<(), String> {
+        stream.listen(Arc::new(self.clone()));
+        let mut orders = repo.list(ListOrdersQuery::default()).await?;
+        orders.sort_by_key(|o| o.updated);
+        let mut w = self.inner.write().unwrap();
+        for o in orders.iter().filter(|o| !o.fills.is_empty()) {
+            w.sample(o);
+        }
+        Ok(())
+    }
+
+    /// Like [`attach`](Self::attach) for a store known to be empty.
+    pub fn follow(&self, stream: &ChangeStream) {
+        stream.listen(Arc::new(self.clone()));
+    }
+
+    pub fn distributions(&self, pair: Option<&str>) -> Vec<SlippageDistribution> {
+        let r = self.inner.read().unwrap();
+        r.samples
+            .iter()
+            .filter(|((p, _), _)| pair.is_none_or(|want| want == p.as_str()))
+            .filter_map(|((p, order_type), q)| {
+                let mut sorted: Vec<Decimal> = q.iter().copied().collect();
+                sorted.sort_unstable();
+                let (min, max) = (*sorted.first()?, *sorted.last()?);
+                let mean = sorted.iter().sum::<Decimal>() / Decimal::from(sorted.len());
+                Some(SlippageDistribution {
+                    pair: p.clone(),
+                    order_type: *order_type,
+                    count: sorted.len(),
+                    min_bps: min,
+                    p50_bps: percentile(&sorted, 50),
+                    p90_bps: percentile(&sorted, 90),
+                    p99_bps: percentile(&sorted, 99),
+                    max_bps: max,
+                    mean_bps: mean.round_dp(2),
+                })
+            })
+            .collect()
+    }
+}
+
+impl ChangeListener for FillQuality {
+    fn on_change(&self, c: &OrderChange) {
+        let o = c.order();
+        if c.is_delete() || o.fills.is_empty() {
+            return;
+        }
+        self.inner.write().unwrap().sample(o);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entities::events::Envelope;
+    use crate::entities::order::Order;
+    use rust_decimal_macros::dec;
+
+    fn emit(s: &dyn ChangeListener, event: fn(Order) -> OrderEvent, order: &Order, ts_ms: i64) {
+        s.on_change(&Envelope::new(event(order.clone()), ts_ms));
+    }
+
+    fn created(order: Order) -> OrderEvent {
+        OrderEvent::Created { order }
+    }
+
+    fn updated(order: Order) -> OrderEvent {
+        OrderEvent::Updated { order }
+    }
+
+    #[test]
+    fn counts_each_transition_once_and_ages_out_of_windows() {
+        let s = TradingStats::default();
+        let base = 10 * DAY_MS;
+        let a = Order {
+            created: base,
+            ..Order::new("BTC/USDT".into(), OrderSide::Buy, dec!(100), dec!(2))
+        };
+        let b = Order {
+            created: base,
+            ..Order::new("ETH/USDT".into(), OrderSide::Sell, dec!(10), dec!(5))
+        };
+        emit(&s, created, &a, base);
+        emit(&s, created, &b, base);
+        let partial = Order {
+            status: OrderStatus::PartiallyFilled,
+            filled_quantity: dec!(0.5),
+            ..a.clone()
+        };
+        emit(&s, updated, &partial, base + 1_000);
+        let filled = Order {
+            status: OrderStatus::Filled,
+            filled_quantity: dec!(2),
+            ..a.clone()
+        };
+        emit(&s, updated, &filled, base + 3_000);
+        emit(&s, updated, &filled, base + 3_500);
+        let cancelled = Order {
+            status: OrderStatus::Cancelled,
+            ..b.clone()
+        };
+        emit(&s, updated, &cancelled, 2 * HOUR_MS + base);
+
+        let btc = s.report(Some("BTC/USDT"), base + 5_000).last_hour;
+        assert_eq!(btc.orders_created, 1);
+        assert_eq!(btc.orders_filled, 1);
+        assert_eq!(btc.buy_volume, dec!(2));
+        assert_eq!(btc.avg_time_to_fill_ms, Some(3_000));
+        assert_eq!(btc.fill_rate, Some(dec!(1)));
+
+        let all = s.report(None, 2 * HOUR_MS + base);
+        assert_eq!(all.last_hour.orders_created, 0);
+        assert_eq!(all.last_hour.orders_cancelled, 1);
+        assert_eq!(all.last_day.orders_created, 2);
+        assert_eq!(all.last_day.fill_rate, Some(dec!(0.5)));
+        assert_eq!(all.last_day.sell_volume, Decimal::ZERO);
+
+        assert_eq!(s.report(None, base + 2 * DAY_MS).last_day.orders_created, 0);
+    }
+
+    #[test]
+    fn latency_percentiles_group_by_pair_and_condition() {
+        let l = TriggerLatency::default();
+        for ms in 1..=100 {
+            let o = Order {
+                status: OrderStatus::Filled,
+                triggered_after_ms: Some(ms * 10),
+                ..Order::new("BTC/USDT".into(), OrderSide::Buy, dec!(1), dec!(1))
+            };
+            emit(&l, updated, &o, 0);
+            // A second write to the same filled order is not a new sample.
+            emit(&l, updated, &o, 0);
+        }
+        let notional = Order {
+            status: OrderStatus::Filled,
+            triggered_after_ms: Some(7),
+            condition: crate::entities::order::Condition::Notional {
+                threshold: dec!(1),
+                quantity: None,
+            },
+            ..Order::new("BTC/USDT".into(), OrderSide::Buy, dec!(0), dec!(1))
+        };
+        emit(&l, updated, &notional, 0);
+
+        let d = l.distributions(Some("BTC/USDT"));
+        assert_eq!(d.len(), 2);
+        let price = d.iter().find(|d| d.condition == "price").unwrap();
+        assert_eq!(price.ms.count, 100);
+        assert_eq!(
+            (
+                price.ms.min_ms,
+                price.ms.p50_ms,
+                price.ms.p90_ms,
+                price.ms.p99_ms
+            ),
+            (10, 500, 900, 990)
+        );
+        assert_eq!(price.ms.mean_ms, 505);
+        assert!(l.distributions(Some("ETH/USDT")).is_empty());
+    }
+
+    #[test]
+    fn each_engine_decision_is_timed_once() {
+        use crate::entities::order::Fill;
+
+        let l = TriggerLatency::default();
+        let fill = |ts_ms, received_ms, decided_ms| Fill {
+            price: dec!(1),
+            quantity: dec!(1),
+            ts_ms,
+            received_ms,
+            decided_ms,
+            recovered_trigger: false,
+            interpolated_trigger: false,
+            legs: Vec::new(),
+            trigger_price: None,
+            fair_sources: Vec::new(),
+            counterparty: None,
+        };
+        let mut o = Order::new("BTC/USDT".into(), OrderSide::Buy, dec!(1), dec!(3));
+        // A sweep of two levels on one tick.
+        o.fills = vec![
+            fill(100, Some(130), Some(1_130)),
+            fill(100, Some(130), Some(1_130)),
+        ];
+        emit(&l, updated, &o, 0);
+        emit(&l, updated, &o, 0);
+        // An older fill without timings adds nothing.
+        o.fills.push(fill(2_000, None, None));
+        emit(&l, updated, &o, 0);
+        o.fills.push(fill(3_000, Some(2_990), Some(3_190)));
+        emit(&l, updated, &o, 0);
+
+        let d = l.fill_latency(Some("BTC/USDT"));
+        assert_eq!(d.len(), 1);
+        let d = &d[0];
+        assert_eq!(d.end_to_end.count, 2);
+        assert_eq!((d.clock_skew.min_ms, d.clock_skew.max_ms), (-10, 30));
+        assert_eq!((d.processing.min_ms, d.processing.max_ms), (200, 1_000));
+        assert_eq!((d.end_to_end.min_ms, d.end_to_end.max_ms), (190, 1_030));
+        assert!(l.fill_latency(Some("ETH/USDT")).is_empty());
+    }
+
+    #[tokio::test]
+    async fn slippage_is_sampled_per_decision_and_rebuilt_from_the_store() {
+        use crate::entities::test_support::OrderBuilder;
+        use crate::repositories::changes::Observed;
+        use crate::repositories::in_memory::InMemoryOrderRepository;
+
+        let fill = |price, ts_ms| Fill {
+            price,
+            quantity: dec!(1),
+            ts_ms,
+            received_ms: None,
+            decided_ms: None,
+            recovered_trigger: false,
+            interpolated_trigger: false,
+            legs: Vec::new(),
+            trigger_price: Some(dec!(100)),
+            fair_sources: Vec::new(),
+            counterparty: None,
+        };
+        let changes = ChangeStream::default();
+        let live = FillQuality::default();
+        live.follow(&changes);
+        let repo = Observed::new(InMemoryOrderRepository::default(), changes);
+        let buy = repo
+            .create(OrderBuilder::buy("BTC/USDT").limit(101).qty(3).new_order())
+            .await
+            .unwrap();
+        // One tick sweeping two levels, then a fill on a later tick.
+        repo.record_fills(&buy.id, &[fill(dec!(100.1), 1), fill(dec!(100.3), 1)])
+            .await
+            .unwrap();
+        repo.record_fills(&buy.id, &[fill(dec!(99.9), 2)])
+            .await
+            .unwrap();
+        let sell = repo
+            .create(OrderBuilder::sell("BTC/USDT").market().new_order())
+            .await
+            .unwrap();
+        repo.record_fills(&sell.id, &[fill(dec!(99.5), 3)])
+            .await
+            .unwrap();
+
+        let dists = live.distributions(Some("BTC/USDT"));
+        assert_eq!(dists.len(), 2);
+        let limit = &dists[0];
+        assert_eq!((limit.order_type, limit.count), (OrderType::Limit, 2));
+        assert_eq!((limit.min_bps, limit.max_bps), (dec!(-10), dec!(20)));
+        assert_eq!(limit.mean_bps, dec!(5));
+        assert_eq!(
+            (dists[1].order_type, dists[1].p50_bps),
+            (OrderType::Market, dec!(50))
+        );
+        assert!(live.distributions(Some("ETH/USDT")).is_empty());
+
+        let rebuilt = FillQuality::default();
+        rebuilt
+            .attach(&repo, &ChangeStream::default())
+            .await
+            .unwrap();
+        assert_eq!(rebuilt.distributions(None), dists);
+    }
+}
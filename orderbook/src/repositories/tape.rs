@@ -0,0 +1,318 @@
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+use tracing::warn;
+
+use crate::entities::events::OrderEvent;
+use crate::entities::order::{Order, OrderSide};
+use crate::repositories::changes::{ChangeListener, ChangeStream, OrderChange};
+
+/// Live entries buffered per subscriber before it lags.
+const LIVE_CAPACITY: usize = 4096;
+
+/// One fill on a pair's tape.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TapeEntry {
+    /// Position on the pair's tape: 1 for the first fill, with no gaps.
+    pub seq: u64,
+    pub pair: String,
+    pub order_id: String,
+    /// Which of the order's fills this is, from 0.
+    pub fill_index: usize,
+    pub side: OrderSide,
+    #[serde(serialize_with = "crate::utils::decimal::serialize")]
+    pub price: Decimal,
+    #[serde(serialize_with = "crate::utils::decimal::serialize")]
+    pub quantity: Decimal,
+    pub ts_ms: i64,
+}
+
+#[derive(Default)]
+struct Inner {
+    tapes: HashMap<String, Vec<TapeEntry>>,
+    /// Fills already on the tape, per order.
+    taped: HashMap<String, usize>,
+    dir: Option<PathBuf>,
+    files: HashMap<String, File>,
+}
+
+impl Inner {
+    fn push(&mut self, e: TapeEntry) -> Result<(), String> {
+        let tape = self.tapes.entry(e.pair.clone()).or_default();
+        let expected = tape.len() as u64 + 1;
+        if e.seq != expected {
+            return Err(format!("{} seq {} where {expected} was due", e.pair, e.seq));
+        }
+        let taped = self.taped.entry(e.order_id.clone()).or_default();
+        *taped = (*taped).max(e.fill_index + 1);
+        tape.push(e);
+        Ok(())
+    }
+
+    /// Appends `e` to its pair's file, when the tape is kept on disk.
+    fn persist(&mut self, e: &TapeEntry) -> Result<(), String> {
+        let Some(dir) = &self.dir else {
+            return Ok(());
+        };
+        let file = match self.files.entry(e.pair.clone()) {
+            std::collections::hash_map::Entry::Occupied(f) => f.into_mut(),
+            std::collections::hash_map::Entry::Vacant(slot) => slot.insert(
+                OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(dir.join(file_name(&e.pair)))
+                    .map_err(|e| e.to_string())?,
+            ),
+        };
+        // Amounts are stored as strings whatever `DECIMAL_FORMAT` says, so
+        // the file stays exact.
+        let mut v = serde_json::to_value(e).map_err(|e| e.to_string())?;
+        v["price"] = e.price.to_string().into();
+        v["quantity"] = e.quantity.to_string().into();
+        writeln!(file, "{v}").map_err(|e| e.to_string())
+    }
+}
+
+fn file_name(pair: &str) -> String {
+    let safe: String = pair
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    format!("{safe}.ndjson")
+}
+
+/// Every fill the engine makes, per pair, in the order the store recorded
+/// them, each numbered by its place on the pair's tape. Entries are only
+/// ever appended, so a range of sequence numbers reads the same every
+/// time. Optionally kept on disk as one NDJSON file per pair, appended as
+/// fills land and read back on start, so numbering carries on across
+/// restarts. The whole tape is also held in memory.
+#[derive(Clone)]
+pub struct TradeTape {
+    inner: Arc<Mutex<Inner>>,
+    live: broadcast::Sender<TapeEntry>,
+}
+
+impl Default for TradeTape {
+    fn default() -> Self {
+        Self {
+            inner: Arc::default(),
+            live: broadcast::channel(LIVE_CAPACITY).0,
+        }
+    }
+}
+
+impl TradeTape {
+    /// A tape kept under `dir`, loading what earlier runs wrote. A torn last
+    /// line from a crash mid-write is cut off; any other unreadable line,
+    /// or a gap in a pair's numbering, is an error.
+    pub fn open(dir: impl AsRef<Path>) -> Result<Self, String> {
+        let dir = dir.as_ref();
+        fs::create_dir_all(dir).map_err(|e| format!("{}: {e}", dir.display()))?;
+        let tape = Self::default();
+        let mut w = tape.inner.lock().unwrap();
+        let mut paths: Vec<PathBuf> = fs::read_dir(dir)
+            .map_err(|e| format!("{}: {e}", dir.display()))?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|p| p.extension().is_some_and(|x| x == "ndjson"))
+            .collect();
+        paths.sort();
+        for path in paths {
+            let text = fs::read_to_string(&path).map_err(|e| format!("{}: {e}", path.display()))?;
+            let mut kept = 0;
+            for line in text.split_inclusive('\n') {
+                match serde_json::from_str::<TapeEntry>(line.trim_end()) {
+                    Ok(e) => w.push(e).map_err(|e| format!("{}: {e}", path.display()))?,
+                    Err(_) if !line.ends_with('\n') => {
+                        warn!(path = %path.display(), "dropping torn last tape line");
+                        let f = OpenOptions::new()
+                            .write(true)
+                            .open(&path)
+                            .map_err(|e| e.to_string())?;
+                        f.set_len(kept as u64).map_err(|e| e.to_string())?;
+                        break;
+                    }
+                    Err(e) => return Err(format!("{}: {e}", path.display())),
+                }
+                kept += line.len();
+            }
+        }
+        w.dir = Some(dir.to_path_buf());
+        drop(w);
+        Ok(tape)
+    }
+
+    pub fn follow(&self, stream: &ChangeStream) {
+        stream.listen(Arc::new(self.clone()));
+    }
+
+    /// Entries of `pair` from `from_seq` up to `to_seq`, both inclusive,
+    /// at most `limit` of them.
+    pub fn range(
+        &self,
+        pair: &str,
+        from_seq: u64,
+        to_seq: Option<u64>,
+        limit: usize,
+    ) -> Vec<TapeEntry> {
+        let r = self.inner.lock().unwrap();
+        let Some(tape) = r.tapes.get(pair) else {
+            return Vec::new();
+        };
+        let start = from_seq.max(1) as usize - 1;
+        tape.iter()
+            .skip(start)
+            .take_while(|e| to_seq.is_none_or(|to| e.seq <= to))
+            .take(limit)
+            .cloned()
+            .collect()
+    }
+
+    /// The latest sequence number of `pair`, 0 before its first fill.
+    pub fn head(&self, pair: &str) -> u64 {
+        let r = self.inner.lock().unwrap();
+        r.tapes.get(pair).map_or(0, |t| t.len() as u64)
+    }
+
+    /// Entries as they are appended, for every pair.
+    pub fn subscribe(&self) -> broadcast::Receiver<TapeEntry> {
+        self.live.subscribe()
+    }
+
+    fn append(&self, order: &Order) {
+        let mut w = self.inner.lock().unwrap();
+        let from = w.taped.get(&order.id).copied().unwrap_or(0);
+        for (i, fill) in order.fills.iter().enumerate().skip(from) {
+            let e = TapeEntry {
+                seq: w
+                    .tapes
+                    .get(order.pair.as_ref())
+                    .map_or(0, |t| t.len() as u64)
+                    + 1,
+                pair: order.pair.to_string(),
+                order_id: order.id.clone(),
+                fill_index: i,
+                side: order.side.clone(),
+                price: fill.price,
+                quantity: fill.quantity,
+                ts_ms: fill.ts_ms,
+            };
+            if let Err(err) = w.persist(&e) {
+                warn!(pair = %e.pair, seq = e.seq, %err, "trade tape write failed");
+            }
+            // In sequence by construction.
+            let _ = w.push(e.clone());
+            let _ = self.live.send(e);
+        }
+    }
+}
+
+impl ChangeListener for TradeTape {
+    fn on_change(&self, c: &OrderChange) {
+        match &c.event {
+            OrderEvent::Created { order } | OrderEvent::Updated { order } => {
+                if !order.fills.is_empty() {
+                    self.append(order);
+                }
+            }
+            OrderEvent::Deleted { .. } => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entities::events::Envelope;
+    use crate::entities::order::Fill;
+    use crate::entities::test_support::OrderBuilder;
+    use crate::repositories::changes::Observed;
+    use crate::repositories::in_memory::InMemoryOrderRepository;
+    use crate::repositories::OrderRepository;
+    use rust_decimal_macros::dec;
+
+    fn fill(price: Decimal, ts_ms: i64) -> Fill {
+        Fill {
+            price,
+            quantity: dec!(0.5),
+            ts_ms,
+            received_ms: None,
+            decided_ms: None,
+            recovered_trigger: false,
+            interpolated_trigger: false,
+            legs: Vec::new(),
+            trigger_price: None,
+            fair_sources: Vec::new(),
+            counterparty: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn fills_are_numbered_per_pair_and_survive_a_restart() {
+        let dir = std::env::temp_dir().join(format!("tape-{}", crate::utils::ids::next_id()));
+        let changes = ChangeStream::default();
+        let tape = TradeTape::open(&dir).unwrap();
+        tape.follow(&changes);
+        let mut live = tape.subscribe();
+        let repo = Observed::new(InMemoryOrderRepository::default(), changes);
+        let btc = repo
+            .create(OrderBuilder::buy("BTC/USDT").limit(100).qty(1).new_order())
+            .await
+            .unwrap();
+        let eth = repo
+            .create(OrderBuilder::sell("ETH/USDT").limit(10).qty(1).new_order())
+            .await
+            .unwrap();
+        repo.record_fills(&btc.id, &[fill(dec!(100), 1)])
+            .await
+            .unwrap();
+        repo.record_fills(&eth.id, &[fill(dec!(10), 2)])
+            .await
+            .unwrap();
+        repo.record_fills(&btc.id, &[fill(dec!(99), 3)])
+            .await
+            .unwrap();
+
+        let seqs: Vec<_> = tape
+            .range("BTC/USDT", 1, None, 10)
+            .iter()
+            .map(|e| (e.seq, e.fill_index, e.price))
+            .collect();
+        assert_eq!(seqs, vec![(1, 0, dec!(100)), (2, 1, dec!(99))]);
+        assert_eq!(tape.head("ETH/USDT"), 1);
+        assert_eq!(tape.range("BTC/USDT", 2, Some(2), 10).len(), 1);
+        assert_eq!(live.recv().await.unwrap().pair, "BTC/USDT");
+
+        // A crash mid-write leaves a torn line; it is cut off on reopen.
+        let path = dir.join("BTC_USDT.ndjson");
+        let mut f = OpenOptions::new().append(true).open(&path).unwrap();
+        write!(f, "{{\"seq\": 3, \"pa").unwrap();
+        let reopened = TradeTape::open(&dir).unwrap();
+        assert_eq!(
+            reopened.range("BTC/USDT", 1, None, 10),
+            tape.range("BTC/USDT", 1, None, 10)
+        );
+        assert!(fs::read_to_string(&path).unwrap().ends_with('\n'));
+
+        // Numbering carries on, and fills already taped are not taped again.
+        let updated = |o: Order| Envelope::new(OrderEvent::Updated { order: o }, 5);
+        reopened.on_change(&updated(repo.get_by_id(&btc.id).await.unwrap()));
+        assert_eq!(reopened.head("BTC/USDT"), 2);
+        repo.record_fills(&eth.id, &[fill(dec!(11), 4)])
+            .await
+            .unwrap();
+        reopened.on_change(&updated(repo.get_by_id(&eth.id).await.unwrap()));
+        let eth_tape = reopened.range("ETH/USDT", 1, None, 10);
+        assert_eq!(
+            eth_tape.iter().map(|e| e.seq).collect::<Vec<_>>(),
+            vec![1, 2]
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,72 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use tokio::sync::RwLock;
+
+use crate::entities::template::{NewTemplate, OrderTemplate};
+
+/// In-memory store for order templates.
+#[derive(Clone, Default)]
+pub struct TemplateStore {
+    inner: Arc<RwLock<HashMap<String, OrderTemplate>>>,
+}
+
+impl TemplateStore {
+    pub async fn create(&self, new: NewTemplate) -> OrderTemplate {
+        let t = OrderTemplate::from_new(new);
+        self.inner.write().await.insert(t.id.clone(), t.clone());
+        t
+    }
+
+    pub async fn get(&self, id: &str) -> Option<OrderTemplate> {
+        self.inner.read().await.get(id).cloned()
+    }
+
+    /// Templates oldest first, optionally only those carrying `tag`.
+    pub async fn list(&self, tag: Option<&str>) -> Vec<OrderTemplate> {
+        let r = self.inner.read().await;
+        let mut v: Vec<OrderTemplate> = r
+            .values()
+            .filter(|t| tag.is_none_or(|tag| t.tags.iter().any(|x| x == tag)))
+            .cloned()
+            .collect();
+        v.sort_by_key(|t| t.created);
+        v
+    }
+
+    pub async fn delete(&self, id: &str) -> bool {
+        self.inner.write().await.remove(id).is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entities::order::OrderSide;
+
+    fn new(tags: &[&str]) -> NewTemplate {
+        serde_json::from_value(serde_json::json!({
+            "pair": "BTC/USDT",
+            "side": "buy",
+            "tags": tags,
+        }))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn create_list_by_tag_and_delete() {
+        let store = TemplateStore::default();
+        let a = store.create(new(&["scalp"])).await;
+        store.create(new(&["swing"])).await;
+
+        assert_eq!(store.list(None).await.len(), 2);
+        let scalp = store.list(Some("scalp")).await;
+        assert_eq!(scalp.len(), 1);
+        assert_eq!(scalp[0].id, a.id);
+        assert_eq!(scalp[0].side, OrderSide::Buy);
+
+        assert!(store.delete(&a.id).await);
+        assert!(!store.delete(&a.id).await);
+        assert!(store.get(&a.id).await.is_none());
+    }
+}
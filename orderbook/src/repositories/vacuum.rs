@@ -0,0 +1,248 @@
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use tokio::time::{interval, MissedTickBehavior};
+use tracing::{info, warn};
+
+use crate::entities::order::{Order, OrderStatus};
+use crate::metrics::Metrics;
+use crate::repositories::in_memory::InMemoryOrderRepository;
+use crate::repositories::{ListOrdersQuery, OrderRepository};
+use crate::utils::now_ms;
+
+/// How the store is kept in check. `retention_ms` is how long a filled or
+/// cancelled order stays after its last update; `None` keeps every order.
+/// Each vacuumed order is first appended to `archive` as a JSON line, when
+/// set, and is only deleted once that write succeeded.
+#[derive(Debug, Clone)]
+pub struct VacuumConfig {
+    pub retention_ms: Option<i64>,
+    pub every: Duration,
+    pub archive: Option<PathBuf>,
+}
+
+impl Default for VacuumConfig {
+    fn default() -> Self {
+        Self {
+            retention_ms: None,
+            every: Duration::from_secs(60),
+            archive: None,
+        }
+    }
+}
+
+impl VacuumConfig {
+    /// Reads `REPO_VACUUM_RETENTION_MS` and `REPO_VACUUM_ARCHIVE`; the
+    /// interval is left at the default for the caller to set. A retention
+    /// that is not a whole number of milliseconds is an error, not a store
+    /// that is never vacuumed.
+    pub fn from_env() -> Result<Self, String> {
+        let retention_ms = match std::env::var("REPO_VACUUM_RETENTION_MS") {
+            Err(_) => None,
+            Ok(v) => Some(
+                v.trim()
+                    .parse::<u64>()
+                    .ok()
+                    .and_then(|ms| i64::try_from(ms).ok())
+                    .ok_or_else(|| {
+                        format!("REPO_VACUUM_RETENTION_MS={v:?}: expected a whole number")
+                    })?,
+            ),
+        };
+        Ok(Self {
+            retention_ms,
+            archive: std::env::var_os("REPO_VACUUM_ARCHIVE").map(PathBuf::from),
+            ..Self::default()
+        })
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct VacuumReport {
+    /// Terminal orders past retention.
+    pub expired: usize,
+    pub vacuumed: usize,
+    /// Orders left in place because archiving or deleting them failed.
+    pub failed: usize,
+}
+
+fn archive(path: &Path, o: &Order) -> Result<(), String> {
+    let line = serde_json::to_string(o).map_err(|e| e.to_string())?;
+    let mut f = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| format!("{}: {e}", path.display()))?;
+    writeln!(f, "{line}").map_err(|e| format!("{}: {e}", path.display()))
+}
+
+/// Deletes the filled, cancelled and expired orders last updated more than
+/// `retention_ms` before `now_ms`, archiving each first when `archive` is
+/// set. Deletes go through `repo`, so listeners on its change stream, the
+/// journal among them, see them like any other.
+pub async fn vacuum<R: OrderRepository + ?Sized>(
+    repo: &R,
+    retention_ms: i64,
+    archive_to: Option<&Path>,
+    now_ms: i64,
+) -> Result<VacuumReport, String> {
+    let statuses = [
+        OrderStatus::Filled,
+        OrderStatus::Cancelled,
+        OrderStatus::Expired,
+    ];
+    sweep(repo, &statuses, None, now_ms - retention_ms, archive_to).await
+}
+
+/// Like [`vacuum`] for the filled orders of one pair, or of every pair when
+/// `pair` is `None`, last updated more than `older_than_ms` ago.
+pub async fn archive_filled<R: OrderRepository + ?Sized>(
+    repo: &R,
+    pair: Option<&str>,
+    older_than_ms: i64,
+    archive_to: Option<&Path>,
+    now_ms: i64,
+) -> Result<VacuumReport, String> {
+    let cutoff = now_ms - older_than_ms;
+    sweep(repo, &[OrderStatus::Filled], pair, cutoff, archive_to).await
+}
+
+async fn sweep<R: OrderRepository + ?Sized>(
+    repo: &R,
+    statuses: &[OrderStatus],
+    pair: Option<&str>,
+    cutoff: i64,
+    archive_to: Option<&Path>,
+) -> Result<VacuumReport, String> {
+    let mut report = VacuumReport::default();
+    for status in statuses {
+        let expired: Vec<Order> = repo
+            .list(ListOrdersQuery {
+                pair: pair.map(str::to_string),
+                status: Some(status.clone()),
+                ..ListOrdersQuery::default()
+            })
+            .await?
+            .into_iter()
+            .filter(|o| o.updated < cutoff)
+            .collect();
+        report.expired += expired.len();
+        for o in expired {
+            let done = match archive_to {
+                Some(path) => archive(path, &o),
+                None => Ok(()),
+            };
+            match done {
+                Ok(()) => match repo.delete(&o.id).await {
+                    Ok(()) => report.vacuumed += 1,
+                    Err(err) => {
+                        warn!(order_id = %o.id, %err, "vacuum: delete failed");
+                        report.failed += 1;
+                    }
+                },
+                Err(err) => {
+                    warn!(order_id = %o.id, %err, "vacuum: archive failed; order kept");
+                    report.failed += 1;
+                }
+            }
+        }
+    }
+    Ok(report)
+}
+
+/// Refreshes the store's size gauges every `cfg.every` and, with a
+/// retention set, vacuums through `repo` (the observed view of `store`).
+pub fn start_vacuum<R: OrderRepository + 'static>(
+    store: InMemoryOrderRepository,
+    repo: R,
+    metrics: Metrics,
+    cfg: VacuumConfig,
+) {
+    tokio::spawn(async move {
+        let mut t = interval(cfg.every);
+        t.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            t.tick().await;
+            if let Some(retention_ms) = cfg.retention_ms {
+                match vacuum(&repo, retention_ms, cfg.archive.as_deref(), now_ms()).await {
+                    Ok(report) => {
+                        metrics.incr("repository_vacuumed_total", &[], report.vacuumed as f64);
+                        metrics.incr(
+                            "repository_vacuum_failures_total",
+                            &[],
+                            report.failed as f64,
+                        );
+                        if report.vacuumed > 0 {
+                            info!(
+                                vacuumed = report.vacuumed,
+                                failed = report.failed,
+                                "repository vacuumed"
+                            );
+                        }
+                    }
+                    Err(err) => warn!(%err, "vacuum: listing orders failed"),
+                }
+            }
+            let size = store.footprint().await;
+            metrics.set("repository_orders", &[], size.orders as f64);
+            metrics.set("repository_approx_bytes", &[], size.approx_bytes as f64);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entities::test_support::OrderBuilder;
+
+    #[tokio::test]
+    async fn only_terminal_orders_past_retention_are_archived_and_dropped() {
+        let store = InMemoryOrderRepository::default();
+        let mut ids = Vec::new();
+        for status in [
+            OrderStatus::Filled,
+            OrderStatus::Cancelled,
+            OrderStatus::Open,
+            OrderStatus::Suspended,
+        ] {
+            let o = store
+                .create(OrderBuilder::buy("BTC/USDT").limit(100).qty(1).new_order())
+                .await
+                .unwrap();
+            store.set_status(&o.id, status).await.unwrap();
+            ids.push(o.id);
+        }
+        let before = store.footprint().await;
+        assert_eq!(before.orders, 4);
+
+        let path =
+            std::env::temp_dir().join(format!("vacuum-{}.ndjson", crate::utils::ids::next_id()));
+        let now = now_ms();
+        let report = vacuum(&store, 60_000, Some(&path), now).await.unwrap();
+        assert_eq!(report, VacuumReport::default());
+        let report = vacuum(&store, 60_000, Some(&path), now + 120_000)
+            .await
+            .unwrap();
+        assert_eq!((report.expired, report.vacuumed), (2, 2));
+
+        for (id, kept) in ids.iter().zip([false, false, true, true]) {
+            assert_eq!(store.get_by_id(id).await.is_ok(), kept, "{id}");
+        }
+        let archived: Vec<Order> = std::fs::read_to_string(&path)
+            .unwrap()
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        let mut archived: Vec<_> = archived.into_iter().map(|o| o.id).collect();
+        archived.sort();
+        let mut gone = ids[..2].to_vec();
+        gone.sort();
+        assert_eq!(archived, gone);
+        let after = store.footprint().await;
+        assert_eq!(after.orders, 2);
+        assert!(after.approx_bytes < before.approx_bytes);
+        std::fs::remove_file(path).unwrap();
+    }
+}
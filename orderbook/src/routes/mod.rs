@@ -1,17 +1,267 @@
 use crate::handlers;
+use crate::limits::admin::admin_gate;
+use actix_web::middleware::from_fn;
 use actix_web::web::{self, ServiceConfig};
 
 pub fn config(cfg: &mut ServiceConfig) {
-    cfg.service(web::scope("/health").route("", web::get().to(handlers::health::ping)))
-        .service(
-            web::scope("/orders")
-                .route("", web::post().to(handlers::orders::create_order))
-                .route("", web::get().to(handlers::orders::list_orders))
-                .route("/{id}", web::get().to(handlers::orders::get_order))
-                .route(
-                    "/{id}/status",
-                    web::put().to(handlers::orders::update_status),
-                )
-                .route("/{id}", web::delete().to(handlers::orders::delete_order)),
-        );
+    cfg.service(
+        web::scope("/health")
+            .route("", web::get().to(handlers::health::ping))
+            .route("/ready", web::get().to(handlers::health::ready)),
+    )
+    .service(
+        web::scope("/orders")
+            .route("", web::post().to(handlers::orders::create_order))
+            .route("", web::get().to(handlers::orders::list_orders))
+            .route("/oco", web::post().to(handlers::orders::create_oco))
+            .route("/bracket", web::post().to(handlers::orders::create_bracket))
+            .route("/stream", web::get().to(handlers::orders::stream_orders))
+            .route("/search", web::get().to(handlers::orders::search_orders))
+            .route("/{id}", web::get().to(handlers::orders::get_order))
+            .route("/{id}", web::patch().to(handlers::orders::amend))
+            .route("/{id}/cancel", web::post().to(handlers::orders::cancel))
+            .route("/{id}/replace", web::post().to(handlers::orders::replace))
+            .route(
+                "/{id}/callbacks",
+                web::get().to(handlers::orders::callback_deliveries),
+            )
+            .route("/{id}", web::delete().to(handlers::orders::delete_order)),
+    )
+    .service(
+        web::scope("/baskets")
+            .route("", web::post().to(handlers::baskets::create_basket))
+            .route("", web::get().to(handlers::baskets::list_baskets))
+            .route("/{id}", web::get().to(handlers::baskets::get_basket))
+            .route(
+                "/{id}/cancel",
+                web::post().to(handlers::baskets::cancel_basket),
+            ),
+    )
+    .service(
+        web::scope("/order-groups")
+            .route("", web::post().to(handlers::groups::create_group))
+            .route("", web::get().to(handlers::groups::list_groups))
+            .route("/{id}", web::get().to(handlers::groups::get_group)),
+    )
+    .service(
+        web::scope("/templates")
+            .route("", web::post().to(handlers::templates::create_template))
+            .route("", web::get().to(handlers::templates::list_templates))
+            .route("/{id}", web::get().to(handlers::templates::get_template))
+            .route(
+                "/{id}",
+                web::delete().to(handlers::templates::delete_template),
+            )
+            .route(
+                "/{id}/instantiate",
+                web::post().to(handlers::templates::instantiate_template),
+            ),
+    )
+    .service(
+        web::scope("/index")
+            .route("", web::get().to(handlers::index::list_index))
+            .route("/{pair:.*}", web::get().to(handlers::index::get_index)),
+    )
+    .route("/stats", web::get().to(handlers::stats::get_stats))
+    .route(
+        "/analytics/time-to-trigger",
+        web::get().to(handlers::stats::time_to_trigger),
+    )
+    .route(
+        "/analytics/fill-latency",
+        web::get().to(handlers::stats::fill_latency),
+    )
+    .route(
+        "/analytics/slippage",
+        web::get().to(handlers::stats::slippage),
+    )
+    .route("/account", web::get().to(handlers::accounts::whoami))
+    .route(
+        "/accounts/{id}/usage",
+        web::get().to(handlers::accounts::usage),
+    )
+    .route(
+        "/accounts/{id}/statements",
+        web::get().to(handlers::accounts::statements),
+    )
+    .route("/ws/orders", web::get().to(handlers::ws::orders))
+    .route("/book/{pair:.*}", web::get().to(handlers::book::get_book))
+    .service(
+        web::scope("/markets")
+            .route("", web::get().to(handlers::markets::list_markets))
+            .route(
+                "/{pair:.*}/stream",
+                web::get().to(handlers::markets::stream_market),
+            )
+            .route("/{pair:.*}/tape/ws", web::get().to(handlers::ws::tape))
+            .route(
+                "/{pair:.*}/tape",
+                web::get().to(handlers::markets::get_tape),
+            )
+            .route(
+                "/{pair:.*}/triggers",
+                web::get().to(handlers::markets::get_triggers),
+            )
+            .route("/{pair:.*}", web::get().to(handlers::markets::get_market)),
+    )
+    .service(
+        web::scope("/admin")
+            .wrap(from_fn(admin_gate))
+            .route(
+                "/orders/orphaned",
+                web::get().to(handlers::admin::orphaned_orders),
+            )
+            .route(
+                "/orders/orphaned/cancel",
+                web::post().to(handlers::admin::cancel_orphaned_orders),
+            )
+            .route(
+                "/orders/deadletter",
+                web::get().to(handlers::admin::dead_letters),
+            )
+            .route(
+                "/orders/missed-triggers",
+                web::get().to(handlers::admin::missed_triggers),
+            )
+            .route(
+                "/orders/{id}/release",
+                web::post().to(handlers::admin::release_missed_trigger),
+            )
+            .route(
+                "/executions/unresolved",
+                web::get().to(handlers::admin::unresolved_executions),
+            )
+            .route(
+                "/executions/{order_id}/{trigger_seq}/resolve",
+                web::post().to(handlers::admin::resolve_execution),
+            )
+            .route(
+                "/orders/reconcile",
+                web::post().to(handlers::admin::reconcile_orders),
+            )
+            .route(
+                "/reevaluate",
+                web::post().to(handlers::admin::reevaluate_orders),
+            )
+            .route(
+                "/orders/{id}/cancel",
+                web::post().to(handlers::admin::admin_cancel_order),
+            )
+            .route(
+                "/orders/{id}/suspend",
+                web::post().to(handlers::admin::admin_suspend_order),
+            )
+            .route(
+                "/orders/{id}/transition",
+                web::post().to(handlers::admin::admin_transition_order),
+            )
+            .route(
+                "/orders/{id}/status",
+                web::put().to(handlers::admin::admin_force_status),
+            )
+            .route("/audit", web::get().to(handlers::admin::audit_log))
+            .route(
+                "/journal/export",
+                web::get().to(handlers::admin::export_journal),
+            )
+            .route(
+                "/journal/import",
+                web::post().to(handlers::admin::import_journal),
+            )
+            .route(
+                "/pairs/{pair:.*}/halt",
+                web::post().to(handlers::admin::halt_pair),
+            )
+            .route(
+                "/pairs/{pair:.*}/resume",
+                web::post().to(handlers::admin::resume_pair),
+            )
+            .route(
+                "/pairs/{pair:.*}/rename",
+                web::post().to(handlers::admin::rename_pair),
+            )
+            .route(
+                "/markets/delisted",
+                web::get().to(handlers::admin::delisted_pairs),
+            )
+            .route(
+                "/markets/{pair:.*}/delist",
+                web::post().to(handlers::admin::delist_pair),
+            )
+            .route(
+                "/delistings/{id}",
+                web::get().to(handlers::admin::delist_job),
+            )
+            .route("/stats", web::get().to(handlers::admin::stats))
+            .route("/flags", web::get().to(handlers::admin::feature_flags))
+            .route(
+                "/flags/{feature}",
+                web::put().to(handlers::admin::set_feature_flag),
+            )
+            .route(
+                "/flags/{feature}",
+                web::delete().to(handlers::admin::clear_feature_flag),
+            )
+            .route(
+                "/slo/placement",
+                web::get().to(handlers::admin::placement_slo),
+            )
+            .route(
+                "/housekeeping",
+                web::get().to(handlers::admin::housekeeping_policies),
+            )
+            .route(
+                "/housekeeping/runs",
+                web::get().to(handlers::admin::housekeeping_runs),
+            )
+            .route(
+                "/housekeeping/{name}/run",
+                web::post().to(handlers::admin::run_housekeeping),
+            )
+            .route(
+                "/accounts",
+                web::post().to(handlers::accounts::create_account),
+            )
+            .route(
+                "/accounts/{id}",
+                web::get().to(handlers::accounts::get_account),
+            )
+            .route(
+                "/accounts/{id}/keys",
+                web::post().to(handlers::accounts::issue_key),
+            )
+            .route(
+                "/accounts/{id}/keys/{key_id}/rotate",
+                web::post().to(handlers::accounts::rotate_key),
+            )
+            .route(
+                "/accounts/{id}/keys/{key_id}",
+                web::delete().to(handlers::accounts::revoke_key),
+            )
+            .route(
+                "/accounts/{id}/webhook",
+                web::put().to(handlers::accounts::set_webhook),
+            )
+            .route(
+                "/accounts/{id}/webhook/rotate",
+                web::post().to(handlers::accounts::rotate_webhook_secret),
+            )
+            .route(
+                "/accounts/{id}/data",
+                web::delete().to(handlers::accounts::purge_account_data),
+            )
+            .route(
+                "/accounts/{id}/data/jobs/{job_id}",
+                web::get().to(handlers::accounts::purge_job),
+            )
+            .route(
+                "/secrets/reseal",
+                web::post().to(handlers::accounts::reseal_secrets),
+            )
+            .route("/ui", web::get().to(handlers::ui::index))
+            .route("/ui/", web::get().to(handlers::ui::index))
+            .route("/ui/app.js", web::get().to(handlers::ui::app_js))
+            .route("/ui/events", web::get().to(handlers::ui::events)),
+    )
+    .route("/metrics", web::get().to(handlers::metrics::render));
 }
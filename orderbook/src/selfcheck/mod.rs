@@ -0,0 +1,496 @@
+use std::fmt;
+use std::time::Duration;
+
+use rust_decimal::Decimal;
+use serde_json::json;
+
+use crate::accounts::secrets::SecretBox;
+use crate::engine::bots::BotConfig;
+use crate::engine::downtime::MissedTriggerPolicy;
+use crate::engine::halts::ResumePolicy;
+use crate::engine::interpolate::Interpolation;
+use crate::engine::policy::PolicyKind;
+use crate::entities::events::{Envelope, OrderEvent, SCHEMA_VERSION};
+use crate::entities::migrations;
+use crate::entities::order::{Order, OrderSide};
+use crate::flags::FeatureFlags;
+use crate::instruments::{Instrument, InstrumentRegistry, TickPolicy};
+use crate::oracle_service::OracleWsClient;
+use crate::repositories::in_memory::InMemoryOrderRepository;
+use crate::repositories::journal::Journal;
+use crate::repositories::OrderRepository;
+use crate::utils::decimal::DecimalFormat;
+use crate::utils::ids::IdScheme;
+
+/// How long the oracle probe waits for a connection.
+pub const ORACLE_PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Variables that must hold a non-negative whole number when set.
+const COUNTS: [&str; 21] = [
+    "ORACLE_STALE_MS",
+    "ORACLE_MAX_FUTURE_MS",
+    "ORACLE_HISTORY_TICKS",
+    "BULK_FILL_THRESHOLD",
+    "ENGINE_EVAL_THREADS",
+    "SNOWFLAKE_NODE",
+    "DEAD_LETTER_AFTER",
+    "ENGINE_RETRY_ATTEMPTS",
+    "ERROR_BUDGET_PAUSE_SECS",
+    "DOWNTIME_GAP_SECS",
+    "ACCOUNT_MAX_ORDERS_PER_MIN",
+    "ACCOUNT_MAX_OPEN_ORDERS",
+    "ACCOUNT_MIN_RESTING_MS",
+    "MAX_BODY_BYTES",
+    "MAX_JSON_DEPTH",
+    "MAX_BATCH_SIZE",
+    "REQUEST_TIMEOUT_MS",
+    "MAX_REQUEST_TIMEOUT_MS",
+    "PUBLIC_RATE_LIMIT_PER_MIN",
+    "PLACEMENT_SLO_MS",
+    "REPO_VACUUM_RETENTION_MS",
+];
+
+/// Timer periods, which must be a whole number above 0 when set.
+const PERIODS: [&str; 4] = [
+    "JOURNAL_COMPACT_INTERVAL_SECS",
+    "REPO_VACUUM_INTERVAL_SECS",
+    "LIQUIDITY_BOT_REFRESH_MS",
+    "EXPIRY_SWEEP_INTERVAL_MS",
+];
+
+/// Counts that `off` disables.
+const OPTIONAL_COUNTS: [&str; 4] = [
+    "ORACLE_DEAD_MAN_SECS",
+    "JOURNAL_RETENTION_MS",
+    "JOURNAL_MAX_ENTRIES",
+    "MAX_FILLS_PER_SEC",
+];
+
+const DECIMALS: [&str; 4] = [
+    "CIRCUIT_BREAKER_BPS",
+    "FAIR_BAND_BPS",
+    "ACCOUNT_MAX_OPEN_NOTIONAL",
+    "ACCOUNT_MAX_CANCEL_FILL_RATIO",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckStatus {
+    Ok,
+    /// Runs, but probably not as meant for a deployment.
+    Warn,
+    Fail,
+}
+
+impl fmt::Display for CheckStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(match self {
+            Self::Ok => "ok",
+            Self::Warn => "warn",
+            Self::Fail => "FAIL",
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CheckResult {
+    pub name: String,
+    pub status: CheckStatus,
+    pub detail: String,
+}
+
+impl CheckResult {
+    fn new(name: impl Into<String>, status: CheckStatus, detail: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            status,
+            detail: detail.into(),
+        }
+    }
+}
+
+/// What `--check` found. It passes unless a check failed; warnings are
+/// printed but do not block a deploy.
+#[derive(Debug, Clone, Default)]
+pub struct CheckReport {
+    pub checks: Vec<CheckResult>,
+}
+
+impl CheckReport {
+    pub fn passed(&self) -> bool {
+        self.checks.iter().all(|c| c.status != CheckStatus::Fail)
+    }
+}
+
+impl fmt::Display for CheckReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let width = self.checks.iter().map(|c| c.name.len()).max().unwrap_or(0);
+        for c in &self.checks {
+            writeln!(f, "[{:>4}] {:width$}  {}", c.status, c.name, c.detail)?;
+        }
+        let failed = self
+            .checks
+            .iter()
+            .filter(|c| c.status == CheckStatus::Fail)
+            .count();
+        match failed {
+            0 => write!(f, "self-check passed"),
+            n => write!(f, "self-check failed: {n} of {} checks", self.checks.len()),
+        }
+    }
+}
+
+/// Runs every check against the process environment. `capture` is a
+/// journal export to load as a schema check.
+pub async fn run(capture: Option<&str>) -> CheckReport {
+    let mut checks = check_config(|k| std::env::var(k).ok());
+    checks.push(check_repository(&InMemoryOrderRepository::default()).await);
+    checks.push(check_schema());
+    if let Some(path) = capture {
+        checks.push(match std::fs::read_to_string(path) {
+            Ok(ndjson) => check_capture(path, &ndjson),
+            Err(e) => CheckResult::new("schema.capture", CheckStatus::Fail, format!("{path}: {e}")),
+        });
+    }
+    let client = OracleWsClient::from_env(InstrumentRegistry::from_env());
+    checks.push(check_oracle(&client, ORACLE_PROBE_TIMEOUT).await);
+    CheckReport { checks }
+}
+
+/// Checks the settings read at boot, which otherwise fall back to their
+/// defaults on a typo. One failure per bad variable, or one `config` line
+/// if all are fine.
+pub fn check_config(env: impl Fn(&str) -> Option<String>) -> Vec<CheckResult> {
+    let mut checks = Vec::new();
+    let mut seen = 0;
+    let mut check = |name: &str, ok: &dyn Fn(&str) -> bool, expected: &str| {
+        if let Some(v) = env(name) {
+            seen += 1;
+            if !ok(v.trim()) {
+                checks.push(CheckResult::new(
+                    format!("config.{name}"),
+                    CheckStatus::Fail,
+                    format!("{v:?} is not {expected}"),
+                ));
+            }
+        }
+    };
+
+    for name in COUNTS {
+        check(name, &|v| v.parse::<u64>().is_ok(), "a whole number");
+    }
+    for name in PERIODS {
+        check(
+            name,
+            &|v| v.parse::<u64>().is_ok_and(|n| n > 0),
+            "a whole number above 0",
+        );
+    }
+    for name in OPTIONAL_COUNTS {
+        check(
+            name,
+            &|v| v == "off" || v.parse::<u64>().is_ok(),
+            "a whole number or `off`",
+        );
+    }
+    check(
+        "ORPHAN_MIN_AGE_HOURS",
+        &|v| {
+            v.parse::<i64>()
+                .is_ok_and(|h| h.checked_mul(3_600_000).is_some())
+        },
+        "a whole number of hours that fits in milliseconds",
+    );
+    for name in ["ERROR_BUDGET_RATE", "PLACEMENT_SLO_OBJECTIVE"] {
+        check(
+            name,
+            &|v| v.parse::<f64>().is_ok_and(|r| (0.0..=1.0).contains(&r)),
+            "a share between 0 and 1",
+        );
+    }
+    for name in DECIMALS {
+        check(name, &|v| v.parse::<Decimal>().is_ok(), "a decimal");
+    }
+    check(
+        "SERVER_ADDR",
+        &|v| v.parse::<std::net::SocketAddr>().is_ok(),
+        "a host:port address",
+    );
+    check(
+        "DECIMAL_JSON",
+        &|v| DecimalFormat::parse(v).is_some(),
+        "`string` or `number`",
+    );
+    check(
+        "ID_SCHEME",
+        &|v| IdScheme::parse(v).is_some(),
+        "`uuidv7`, `uuidv4` or `snowflake`",
+    );
+    check(
+        "HALT_RESUME_POLICY",
+        &|v| ResumePolicy::parse(v).is_some(),
+        "a resume policy",
+    );
+    check(
+        "MISSED_TRIGGER_POLICY",
+        &|v| MissedTriggerPolicy::parse(v).is_some(),
+        "a missed-trigger policy",
+    );
+    check(
+        "ORACLE_INTERPOLATION",
+        &|v| Interpolation::parse(v).is_some(),
+        "`none` or `linear`",
+    );
+    check(
+        "MATCH_POLICY",
+        &|v| v.parse::<PolicyKind>().is_ok(),
+        "a match policy",
+    );
+    check(
+        "TICK_POLICY",
+        &|v| TickPolicy::parse(v).is_some(),
+        "`reject`, `nearest` or `passive`",
+    );
+    check(
+        "OFF_SESSION_POLICY",
+        &|v| matches!(v, "queue" | "reject"),
+        "`queue` or `reject`",
+    );
+    check(
+        "LIQUIDITY_BOTS",
+        &|v| v == "off" || BotConfig::parse(v).is_some(),
+        "`on`, `off` or levels:step_bps:quantity",
+    );
+    check(
+        "FEATURE_FLAGS",
+        &|v| FeatureFlags::parse(v).is_ok(),
+        "a flag list",
+    );
+    check(
+        "INSTRUMENTS",
+        &|v| v.split(',').all(|s| Instrument::parse(s).is_some()),
+        "a list of BASE/QUOTE[:price_scale:qty_scale]",
+    );
+    check(
+        "MASTER_KEYS",
+        &|v| SecretBox::from_keyring(v).is_ok(),
+        "a keyring of id=<64 hex>",
+    );
+
+    if env("MASTER_KEYS").is_none() {
+        checks.push(CheckResult::new(
+            "config.MASTER_KEYS",
+            CheckStatus::Warn,
+            "unset; secrets at rest would be sealed with a per-process key",
+        ));
+    }
+    if env("ADMIN_TOKEN").is_none_or(|t| t.trim().is_empty()) {
+        checks.push(CheckResult::new(
+            "config.ADMIN_TOKEN",
+            CheckStatus::Warn,
+            "unset; every /admin request would be refused",
+        ));
+    }
+    if checks.iter().all(|c| c.status != CheckStatus::Fail) {
+        checks.insert(
+            0,
+            CheckResult::new("config", CheckStatus::Ok, format!("{seen} settings valid")),
+        );
+    }
+    checks
+}
+
+/// Writes, reads back and deletes a scratch order. The store is in
+/// process memory, so there is no server to reach; this proves the
+/// backend this build runs on works at all.
+pub async fn check_repository(repo: &dyn OrderRepository) -> CheckResult {
+    let name = "repository";
+    let probe = Order::new(
+        "CHECK/CHECK".into(),
+        OrderSide::Buy,
+        Decimal::ONE,
+        Decimal::ONE,
+    );
+    let round_trip = async {
+        let created = repo.create(probe.to_new()).await?;
+        let read = repo.get_by_id(&created.id).await?;
+        repo.delete(&read.id).await
+    };
+    match round_trip.await {
+        Ok(()) => CheckResult::new(
+            name,
+            CheckStatus::Ok,
+            "in-memory store, write/read/delete ok",
+        ),
+        Err(e) => CheckResult::new(name, CheckStatus::Fail, e),
+    }
+}
+
+/// Checks that the migration chain still reaches [`SCHEMA_VERSION`] from
+/// the oldest records, by loading a version-0 change.
+pub fn check_schema() -> CheckResult {
+    let name = "schema";
+    let legacy = json!({
+        "kind": "created",
+        "ts_ms": 0,
+        "order": {
+            "id": "check", "pair": "BTC/USDT", "side": "buy", "price": "1",
+            "quantity": "1", "status": "open", "created": 0, "updated": 0
+        }
+    });
+    match migrations::load::<Envelope<OrderEvent>>(legacy) {
+        Ok((e, 0)) if e.schema_version == SCHEMA_VERSION => CheckResult::new(
+            name,
+            CheckStatus::Ok,
+            format!("version {SCHEMA_VERSION}, upgrades from 0"),
+        ),
+        Ok((_, from)) => CheckResult::new(
+            name,
+            CheckStatus::Fail,
+            format!("legacy record read as version {from}"),
+        ),
+        Err(e) => CheckResult::new(name, CheckStatus::Fail, format!("legacy record: {e}")),
+    }
+}
+
+/// Loads a journal export as `/admin/journal/import` would, so a capture
+/// made by an older or newer build is caught before deploy.
+pub fn check_capture(path: &str, ndjson: &str) -> CheckResult {
+    let name = "schema.capture";
+    match Journal::default().import(ndjson) {
+        Ok(r) => CheckResult::new(
+            name,
+            CheckStatus::Ok,
+            format!(
+                "{path}: {} entries, {} snapshot orders, {} upgraded",
+                r.entries, r.snapshot_orders, r.upgraded
+            ),
+        ),
+        Err(e) => CheckResult::new(name, CheckStatus::Fail, format!("{path}: {e}")),
+    }
+}
+
+/// Connects to the oracle once, as the feed client would.
+pub async fn check_oracle(client: &OracleWsClient, timeout: Duration) -> CheckResult {
+    let name = "oracle";
+    let url = client.url();
+    match client.probe(timeout).await {
+        Ok(deflated) => CheckResult::new(
+            name,
+            CheckStatus::Ok,
+            format!("{url} connected (deflate {deflated})"),
+        ),
+        Err(e) => CheckResult::new(name, CheckStatus::Fail, format!("{url}: {e}")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn config(vars: &[(&str, &str)]) -> Vec<CheckResult> {
+        let env: HashMap<String, String> = vars
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        check_config(|k| env.get(k).cloned())
+    }
+
+    #[test]
+    fn config_reports_each_bad_setting() {
+        let ok = config(&[
+            ("ORACLE_STALE_MS", "5000"),
+            ("JOURNAL_MAX_ENTRIES", "off"),
+            ("MATCH_POLICY", "bid_ask"),
+            ("MASTER_KEYS", &format!("k1={}", "ab".repeat(32))),
+            ("ADMIN_TOKEN", "s3cret"),
+        ]);
+        assert_eq!(ok.len(), 1);
+        assert_eq!(ok[0].status, CheckStatus::Ok);
+        assert_eq!(ok[0].detail, "4 settings valid");
+
+        let bad = config(&[
+            ("ORACLE_STALE_MS", "5s"),
+            ("ERROR_BUDGET_RATE", "2"),
+            ("FAIR_BAND_BPS", "25bps"),
+            ("TICK_POLICY", "round"),
+            ("INSTRUMENTS", "BTC/USDT,ETH"),
+        ]);
+        let failed: Vec<_> = bad
+            .iter()
+            .filter(|c| c.status == CheckStatus::Fail)
+            .map(|c| c.name.as_str())
+            .collect();
+        assert_eq!(
+            failed,
+            [
+                "config.ORACLE_STALE_MS",
+                "config.ERROR_BUDGET_RATE",
+                "config.FAIR_BAND_BPS",
+                "config.TICK_POLICY",
+                "config.INSTRUMENTS"
+            ]
+        );
+        // Unset keys are only a warning.
+        let report = CheckReport { checks: bad };
+        assert!(!report.passed());
+        assert!(report.checks.iter().any(|c| c.status == CheckStatus::Warn));
+        assert!(report.to_string().ends_with("failed: 5 of 7 checks"));
+    }
+
+    #[test]
+    fn timer_periods_must_be_above_zero() {
+        for name in PERIODS {
+            let zero = config(&[(name, "0")]);
+            assert_eq!(zero[0].name, format!("config.{name}"));
+            assert_eq!(zero[0].status, CheckStatus::Fail);
+            assert_eq!(config(&[(name, "1")])[0].status, CheckStatus::Ok);
+        }
+    }
+
+    #[test]
+    fn optional_counts_take_a_number_or_off() {
+        for name in OPTIONAL_COUNTS {
+            for ok in ["0", "30", "off"] {
+                assert_eq!(config(&[(name, ok)])[0].status, CheckStatus::Ok);
+            }
+            let typo = config(&[(name, "30s")]);
+            assert_eq!(typo[0].name, format!("config.{name}"));
+            assert_eq!(typo[0].status, CheckStatus::Fail);
+        }
+    }
+
+    #[tokio::test]
+    async fn repository_schema_and_capture_checks() {
+        let repo = InMemoryOrderRepository::default();
+        assert_eq!(check_repository(&repo).await.status, CheckStatus::Ok);
+        assert_eq!(check_schema().status, CheckStatus::Ok);
+
+        let journal = Journal::default();
+        assert_eq!(
+            check_capture("j", &journal.export()).status,
+            CheckStatus::Ok
+        );
+        let newer = json!({ "schema_version": SCHEMA_VERSION + 1, "type": "order_created" });
+        let c = check_capture("j", &newer.to_string());
+        assert_eq!(c.status, CheckStatus::Fail);
+        assert!(c.detail.contains("newer than supported"), "{}", c.detail);
+    }
+
+    #[tokio::test]
+    async fn oracle_check_fails_when_nothing_listens() {
+        // Bind and drop to find a port nobody is on.
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let client = OracleWsClient {
+            endpoint: format!("ws://127.0.0.1:{port}/ws"),
+            ..OracleWsClient::default()
+        };
+        let c = check_oracle(&client, Duration::from_secs(2)).await;
+        assert_eq!(c.status, CheckStatus::Fail);
+        assert!(c.detail.starts_with(&format!("ws://127.0.0.1:{port}/ws")));
+    }
+}
@@ -2,15 +2,34 @@ use crate::repositories::OrderRepository;
 use actix_web::web::Data;
 use std::sync::Arc;
 
+/// `orders` is the primary handle used for every write and by the engine;
+/// `reads` serves the API's list/get endpoints and may be a replica.
 #[derive(Clone)]
 pub struct AppState {
     pub orders: Arc<dyn OrderRepository>,
+    pub reads: Arc<dyn OrderRepository>,
+    pub reads_from_replica: bool,
 }
 
 impl AppState {
     pub fn new<R: OrderRepository + 'static>(orders: R) -> Data<Self> {
+        let orders: Arc<dyn OrderRepository> = Arc::new(orders);
         Data::new(Self {
-            orders: Arc::new(orders),
+            reads: orders.clone(),
+            orders,
+            reads_from_replica: false,
+        })
+    }
+
+    pub fn with_replica<P, R>(primary: P, replica: R) -> Data<Self>
+    where
+        P: OrderRepository + 'static,
+        R: OrderRepository + 'static,
+    {
+        Data::new(Self {
+            orders: Arc::new(primary),
+            reads: Arc::new(replica),
+            reads_from_replica: true,
         })
     }
 }
@@ -0,0 +1,39 @@
+use std::sync::{Arc, OnceLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Tells the time for everything stamped in milliseconds: order and fill
+/// timestamps, freshness checks, deadlines.
+pub trait Clock: Send + Sync {
+    fn now_ms(&self) -> i64;
+}
+
+/// The system's wall clock.
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now_ms(&self) -> i64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as i64)
+            .unwrap_or(0)
+    }
+}
+
+/// Set at most once, so reading it on the hot path is a plain load.
+static CLOCK: OnceLock<Arc<dyn Clock>> = OnceLock::new();
+
+/// Installs the process-wide clock in place of the system's. Meant for
+/// tests that need a frozen time, and only once per process: a second call
+/// panics. Tokio timers and latency measurements keep their own clocks.
+pub fn set_clock(c: Arc<dyn Clock>) {
+    if CLOCK.set(c).is_err() {
+        panic!("the process clock is already set");
+    }
+}
+
+pub fn now_ms() -> i64 {
+    match CLOCK.get() {
+        Some(c) => c.now_ms(),
+        None => SystemClock.now_ms(),
+    }
+}
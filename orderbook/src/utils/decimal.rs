@@ -0,0 +1,88 @@
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use serde::Serializer;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// How `Decimal` prices and quantities are written in API responses.
+/// `String` (the default) is lossless; `Number` is for clients that cannot
+/// handle string amounts and accept f64 rounding. Input always accepts both.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecimalFormat {
+    String,
+    Number,
+}
+
+impl DecimalFormat {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "string" => Some(Self::String),
+            "number" => Some(Self::Number),
+            _ => None,
+        }
+    }
+}
+
+static AS_NUMBER: AtomicBool = AtomicBool::new(false);
+
+pub fn set_format(f: DecimalFormat) {
+    AS_NUMBER.store(f == DecimalFormat::Number, Ordering::Relaxed);
+}
+
+pub fn format() -> DecimalFormat {
+    if AS_NUMBER.load(Ordering::Relaxed) {
+        DecimalFormat::Number
+    } else {
+        DecimalFormat::String
+    }
+}
+
+pub fn serialize_as<S: Serializer>(d: &Decimal, f: DecimalFormat, s: S) -> Result<S::Ok, S::Error> {
+    match f {
+        DecimalFormat::String => s.serialize_str(&d.to_string()),
+        DecimalFormat::Number => s.serialize_f64(d.to_f64().unwrap_or_default()),
+    }
+}
+
+/// `serialize_with` target honouring the process-wide [`DecimalFormat`].
+pub fn serialize<S: Serializer>(d: &Decimal, s: S) -> Result<S::Ok, S::Error> {
+    serialize_as(d, format(), s)
+}
+
+pub fn serialize_opt<S: Serializer>(d: &Option<Decimal>, s: S) -> Result<S::Ok, S::Error> {
+    match d {
+        Some(d) => serialize(d, s),
+        None => s.serialize_none(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+    use serde_json::json;
+
+    fn render(d: Decimal, f: DecimalFormat) -> serde_json::Value {
+        serialize_as(&d, f, serde_json::value::Serializer).unwrap()
+    }
+
+    #[test]
+    fn string_format_is_lossless() {
+        assert_eq!(
+            render(dec!(0.123456789012345678), DecimalFormat::String),
+            json!("0.123456789012345678")
+        );
+        assert_eq!(render(dec!(100.50), DecimalFormat::String), json!("100.50"));
+    }
+
+    #[test]
+    fn number_format_emits_json_numbers() {
+        assert_eq!(render(dec!(25000.5), DecimalFormat::Number), json!(25000.5));
+    }
+
+    #[test]
+    fn parse_format_names() {
+        assert_eq!(DecimalFormat::parse("Number"), Some(DecimalFormat::Number));
+        assert_eq!(DecimalFormat::parse("string"), Some(DecimalFormat::String));
+        assert_eq!(DecimalFormat::parse("float"), None);
+    }
+}
@@ -0,0 +1,206 @@
+//! Just enough HTTP/1.1 for the client and order callbacks: one plain-text
+//! request per connection, with a body framed by `Content-Length`, chunked
+//! encoding or the connection closing.
+
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+
+/// The most a response head may take.
+const MAX_HEAD: usize = 64 * 1024;
+
+pub(crate) struct Head {
+    pub status: u16,
+    headers: Vec<(String, String)>,
+}
+
+impl Head {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+enum Framing {
+    Length(usize),
+    Chunked { left: usize, done: bool },
+    UntilClose,
+}
+
+/// A response body read as it arrives.
+pub(crate) struct Body {
+    reader: BufReader<TcpStream>,
+    framing: Framing,
+}
+
+impl Body {
+    /// The next piece of the body; `None` once it has all arrived.
+    pub async fn next(&mut self) -> Result<Option<Vec<u8>>, String> {
+        let io = |e: std::io::Error| e.to_string();
+        match &mut self.framing {
+            Framing::Length(0) => Ok(None),
+            Framing::Length(left) => {
+                let mut buf = vec![0; (*left).min(8 * 1024)];
+                let n = self.reader.read(&mut buf).await.map_err(io)?;
+                if n == 0 {
+                    return Err("connection closed mid-body".into());
+                }
+                *left -= n;
+                buf.truncate(n);
+                Ok(Some(buf))
+            }
+            Framing::Chunked { done: true, .. } => Ok(None),
+            Framing::Chunked { left, done } => {
+                if *left == 0 {
+                    let size = read_line(&mut self.reader).await?;
+                    let size = size.split(';').next().unwrap_or("").trim();
+                    *left = usize::from_str_radix(size, 16)
+                        .map_err(|_| format!("bad chunk size {size:?}"))?;
+                    if *left == 0 {
+                        // Trailers, then the blank line that ends them.
+                        while !read_line(&mut self.reader).await?.is_empty() {}
+                        *done = true;
+                        return Ok(None);
+                    }
+                }
+                let mut buf = vec![0; (*left).min(8 * 1024)];
+                let n = self.reader.read(&mut buf).await.map_err(io)?;
+                if n == 0 {
+                    return Err("connection closed mid-chunk".into());
+                }
+                *left -= n;
+                buf.truncate(n);
+                if *left == 0 {
+                    read_line(&mut self.reader).await?;
+                }
+                Ok(Some(buf))
+            }
+            Framing::UntilClose => {
+                let mut buf = vec![0; 8 * 1024];
+                let n = self.reader.read(&mut buf).await.map_err(io)?;
+                buf.truncate(n);
+                Ok((n > 0).then_some(buf))
+            }
+        }
+    }
+
+    pub async fn read_all(mut self) -> Result<Vec<u8>, String> {
+        let mut out = Vec::new();
+        while let Some(piece) = self.next().await? {
+            out.extend(piece);
+        }
+        Ok(out)
+    }
+}
+
+async fn read_line(r: &mut BufReader<TcpStream>) -> Result<String, String> {
+    let mut line = String::new();
+    let n = r.read_line(&mut line).await.map_err(|e| e.to_string())?;
+    if n == 0 {
+        return Err("connection closed".into());
+    }
+    Ok(line.trim_end_matches(['\r', '\n']).to_string())
+}
+
+/// Sends one request to `host` (`addr:port`) and reads the response head;
+/// the body is left to the caller.
+pub(crate) async fn send(
+    host: &str,
+    method: &str,
+    path: &str,
+    headers: &[(&str, String)],
+    body: Option<&[u8]>,
+) -> Result<(Head, Body), String> {
+    let mut stream = TcpStream::connect(host)
+        .await
+        .map_err(|e| format!("connecting to {host}: {e}"))?;
+    let mut req = format!("{method} {path} HTTP/1.1\r\nHost: {host}\r\nConnection: close\r\n");
+    for (k, v) in headers {
+        req.push_str(&format!("{k}: {v}\r\n"));
+    }
+    if let Some(b) = body {
+        req.push_str(&format!(
+            "Content-Type: application/json\r\nContent-Length: {}\r\n",
+            b.len()
+        ));
+    }
+    req.push_str("\r\n");
+    stream
+        .write_all(req.as_bytes())
+        .await
+        .map_err(|e| e.to_string())?;
+    if let Some(b) = body {
+        stream.write_all(b).await.map_err(|e| e.to_string())?;
+    }
+
+    let mut reader = BufReader::new(stream);
+    let status_line = read_line(&mut reader).await?;
+    let status = status_line
+        .split(' ')
+        .nth(1)
+        .and_then(|s| s.parse().ok())
+        .ok_or_else(|| format!("bad status line {status_line:?}"))?;
+    let (mut headers, mut size) = (Vec::new(), status_line.len());
+    loop {
+        let line = read_line(&mut reader).await?;
+        if line.is_empty() {
+            break;
+        }
+        size += line.len();
+        if size > MAX_HEAD {
+            return Err("response head too large".into());
+        }
+        if let Some((k, v)) = line.split_once(':') {
+            headers.push((k.trim().to_string(), v.trim().to_string()));
+        }
+    }
+    let head = Head { status, headers };
+    let framing = if head
+        .header("transfer-encoding")
+        .is_some_and(|v| v.eq_ignore_ascii_case("chunked"))
+    {
+        Framing::Chunked {
+            left: 0,
+            done: false,
+        }
+    } else if let Some(n) = head.header("content-length") {
+        Framing::Length(n.parse().map_err(|_| format!("bad content-length {n:?}"))?)
+    } else {
+        Framing::UntilClose
+    };
+    Ok((head, Body { reader, framing }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    /// Serves `raw` as the response to one connection.
+    async fn serve(raw: &'static str) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        tokio::spawn(async move {
+            let (mut s, _) = listener.accept().await.unwrap();
+            let mut buf = [0; 1024];
+            let _ = s.read(&mut buf).await.unwrap();
+            s.write_all(raw.as_bytes()).await.unwrap();
+        });
+        addr
+    }
+
+    #[tokio::test]
+    async fn reads_length_chunked_and_close_delimited_bodies() {
+        for raw in [
+            "HTTP/1.1 200 OK\r\nContent-Length: 11\r\n\r\nhello world",
+            "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nhello\r\n6;x=y\r\n world\r\n0\r\n\r\n",
+            "HTTP/1.1 200 OK\r\n\r\nhello world",
+        ] {
+            let host = serve(raw).await;
+            let (head, body) = send(&host, "GET", "/", &[], None).await.unwrap();
+            assert_eq!(head.status, 200);
+            assert_eq!(body.read_all().await.unwrap(), b"hello world", "{raw:?}");
+        }
+    }
+}
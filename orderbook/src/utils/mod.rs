@@ -1,7 +1,15 @@
+pub mod clock;
+pub mod decimal;
+pub(crate) mod http;
+pub mod ids;
+
+/// Nearest-rank percentile of sorted, non-empty samples.
+pub fn percentile<T: Copy>(sorted: &[T], p: usize) -> T {
+    let rank = (p * sorted.len()).div_ceil(100).max(1);
+    sorted[rank - 1]
+}
+
+/// The time on the process-wide [`clock::Clock`].
 pub fn now_ms() -> i64 {
-    use std::time::{SystemTime, UNIX_EPOCH};
-    SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .map(|d| d.as_millis() as i64)
-        .unwrap_or(0)
+    clock::now_ms()
 }
@@ -0,0 +1,83 @@
+// Polls the JSON API for pair state and follows /admin/ui/events for fills.
+const REFRESH_MS = 2000;
+const MAX_FILLS = 50;
+
+const text = (tag, value, cls) => {
+  const el = document.createElement(tag);
+  el.textContent = value ?? "";
+  if (cls) el.className = cls;
+  return el;
+};
+
+const row = (cells) => {
+  const tr = document.createElement("tr");
+  cells.forEach((c) => tr.appendChild(c));
+  return tr;
+};
+
+async function getJson(url) {
+  const r = await fetch(url);
+  if (!r.ok) throw new Error(`${url}: ${r.status}`);
+  return r.json();
+}
+
+function activeCount(book) {
+  const levels = [...book.bids, ...book.asks];
+  return levels.reduce((n, l) => n + l.orders, 0) + book.other_orders;
+}
+
+async function refreshPairs() {
+  const [markets, stats] = await Promise.all([
+    getJson("/markets"),
+    getJson("/admin/stats"),
+  ]);
+  const halted = new Set(stats.halts.halted.map((h) => h.pair));
+  const now = Date.now();
+  const rows = await Promise.all(
+    markets.map(async (m) => {
+      let active = "–";
+      try {
+        active = activeCount(await getJson(`/book/${m.symbol}`));
+      } catch (_) {}
+      const age = m.last_ts_ms == null ? null : now - m.last_ts_ms;
+      return row([
+        text("td", m.symbol),
+        text("td", m.session_state),
+        text("td", halted.has(m.symbol) ? "yes" : ""),
+        text("td", m.last_price, "num"),
+        text("td", age == null ? "" : `${(age / 1000).toFixed(1)}s`, age > 5000 ? "num stale" : "num"),
+        text("td", active, "num"),
+      ]);
+    }),
+  );
+  document.getElementById("pairs").replaceChildren(...rows);
+}
+
+function followFills() {
+  const status = document.getElementById("status");
+  const fills = document.getElementById("fills");
+  const events = new EventSource("/admin/ui/events");
+  events.onopen = () => (status.textContent = "live");
+  events.onerror = () => (status.textContent = "reconnecting…");
+  events.addEventListener("order", (e) => {
+    const { order, ts_ms } = JSON.parse(e.data);
+    if (order.status !== "filled" && order.status !== "partially_filled") return;
+    fills.prepend(
+      row([
+        text("td", new Date(ts_ms).toLocaleTimeString()),
+        text("td", order.pair),
+        text("td", order.side),
+        text("td", order.filled_quantity, "num"),
+        text("td", order.exec_price, "num"),
+        text("td", order.id),
+      ]),
+    );
+    while (fills.children.length > MAX_FILLS) fills.lastChild.remove();
+  });
+}
+
+const tick = () =>
+  refreshPairs().catch((e) => (document.getElementById("status").textContent = e.message));
+tick();
+setInterval(tick, REFRESH_MS);
+followFills();
@@ -1,15 +1,63 @@
+use std::time::Duration;
+
 use actix_web::test::{self, TestRequest};
-use actix_web::{http::StatusCode, App};
+use actix_web::{http::StatusCode, middleware::from_fn, web, App};
 use rust_decimal_macros::dec;
 use serde_json::json;
 
 use conditional_orderbook::{
-    entities::order::{Order, OrderStatus},
-    repositories::in_memory::InMemoryOrderRepository,
+    accounts::{
+        purge::AccountPurger,
+        secrets::SecretBox,
+        statements::StatementLedger,
+        usage::{AccountLimits, AccountUsage},
+        AccountStore,
+    },
+    engine::callbacks::OrderCallbacks,
+    engine::deadletter::DeadLetters,
+    engine::delist::PairDelister,
+    engine::downtime::MissedTriggers,
+    engine::groups::OrderGroups,
+    engine::halts::HaltRegistry,
+    engine::housekeeping::{Housekeeper, HousekeepingPolicy},
+    engine::ledger::ExecutionLedger,
+    engine::rename::PairRenamer,
+    engine::{EngineConfig, Matchers},
+    entities::order::{Fill, NewOrder, Order, OrderStatus},
+    entities::test_support::ScriptedOracle,
+    flags::FeatureFlags,
+    instruments::{
+        session::{MaintenanceWindow, OffSessionPolicy, TradingSession},
+        InstrumentRegistry, TickPolicy,
+    },
+    limits::{
+        admin::{AdminAuth, ADMIN_TOKEN_HEADER},
+        deadline, guard_body,
+        public::{public_gate, PublicMode},
+        Limits,
+    },
+    metrics::{placement::PlacementTimings, Metrics},
+    oracle_service::{deadman::DeadMan, OracleCache, Tick},
+    repositories::{
+        audit::AuditLog,
+        baskets::BasketStore,
+        changes::{ChangeStream, Observed},
+        in_memory::InMemoryOrderRepository,
+        journal::Journal,
+        open_orders::OpenOrdersView,
+        search::OrderSearch,
+        stats::{FillQuality, TradingStats, TriggerLatency},
+        tape::TradeTape,
+        templates::TemplateStore,
+        ListOrdersQuery, OrderRepository,
+    },
     routes,
     state::AppState,
 };
 
+/// The admin token every test app accepts.
+const ADMIN: (&str, &str) = (ADMIN_TOKEN_HEADER, "test-admin-token");
+
 fn test_app() -> actix_web::App<
     impl actix_web::dev::ServiceFactory<
         actix_web::dev::ServiceRequest,
@@ -19,8 +67,169 @@ fn test_app() -> actix_web::App<
         InitError = (),
     >,
 > {
-    let state = AppState::new(InMemoryOrderRepository::default());
-    App::new().app_data(state).configure(routes::config)
+    test_app_with_cache(OracleCache::default())
+}
+
+fn test_app_with_cache(
+    cache: OracleCache,
+) -> actix_web::App<
+    impl actix_web::dev::ServiceFactory<
+        actix_web::dev::ServiceRequest,
+        Config = (),
+        Response = actix_web::dev::ServiceResponse,
+        Error = actix_web::Error,
+        InitError = (),
+    >,
+> {
+    test_app_with(cache, InstrumentRegistry::default())
+}
+
+fn test_app_with(
+    cache: OracleCache,
+    instruments: InstrumentRegistry,
+) -> actix_web::App<
+    impl actix_web::dev::ServiceFactory<
+        actix_web::dev::ServiceRequest,
+        Config = (),
+        Response = actix_web::dev::ServiceResponse,
+        Error = actix_web::Error,
+        InitError = (),
+    >,
+> {
+    let changes = ChangeStream::default();
+    let view = OpenOrdersView::default();
+    view.follow(&changes);
+    let journal = Journal::default();
+    journal.attach(&changes);
+    let state = AppState::new(Observed::new(
+        InMemoryOrderRepository::default(),
+        changes.clone(),
+    ));
+    test_app_with_state(state, changes, view, journal, cache, instruments)
+}
+
+fn test_app_with_state(
+    state: web::Data<AppState>,
+    changes: ChangeStream,
+    view: OpenOrdersView,
+    journal: Journal,
+    cache: OracleCache,
+    instruments: InstrumentRegistry,
+) -> actix_web::App<
+    impl actix_web::dev::ServiceFactory<
+        actix_web::dev::ServiceRequest,
+        Config = (),
+        Response = actix_web::dev::ServiceResponse,
+        Error = actix_web::Error,
+        InitError = (),
+    >,
+> {
+    let trading_stats = TradingStats::default();
+    trading_stats.attach(&changes);
+    let search = OrderSearch::default();
+    search.follow(&changes);
+    let tape = TradeTape::default();
+    tape.follow(&changes);
+    let latency = TriggerLatency::default();
+    latency.attach(&changes);
+    let fill_quality = FillQuality::default();
+    fill_quality.follow(&changes);
+    let usage = AccountUsage::new(AccountLimits {
+        max_orders_per_min: Some(10),
+        max_open_orders: Some(5),
+        max_open_notional: Some(dec!(1000000)),
+        max_cancel_fill_ratio: Some(dec!(2)),
+        ..AccountLimits::default()
+    });
+    usage.follow(&changes);
+    let statements = StatementLedger::default();
+    statements.follow(&changes);
+    let groups = OrderGroups::default();
+    groups.follow(&changes);
+    let (halts, baskets, audit) = (
+        HaltRegistry::default(),
+        BasketStore::default(),
+        AuditLog::default(),
+    );
+    let purger = AccountPurger::new(
+        state.orders.clone(),
+        journal.clone(),
+        audit.clone(),
+        baskets.clone(),
+    );
+    let delister = PairDelister::new(
+        instruments.clone(),
+        state.orders.clone(),
+        cache.clone(),
+        halts.clone(),
+        Matchers::default(),
+        changes.clone(),
+    );
+    let renamer = PairRenamer::new(
+        instruments.clone(),
+        state.orders.clone(),
+        journal.clone(),
+        cache.clone(),
+        halts.clone(),
+        baskets.clone(),
+        changes.clone(),
+    );
+    let housekeeper = Housekeeper::new(
+        state.orders.clone(),
+        view.clone(),
+        search.clone(),
+        None,
+        [
+            "stale|*|0 3 * * *|cancel_older_than=30d",
+            "reindex|*|0 0 * * 0|rebuild_indexes",
+        ]
+        .iter()
+        .map(|p| HousekeepingPolicy::parse(p).unwrap())
+        .collect(),
+    );
+    App::new()
+        .wrap(from_fn(deadline))
+        .wrap(from_fn(guard_body))
+        .wrap(from_fn(public_gate))
+        .app_data(web::Data::new(AdminAuth::new(ADMIN.1)))
+        .app_data(state)
+        .app_data(web::Data::new(cache))
+        .app_data(web::Data::new(Metrics::default()))
+        .app_data(web::Data::new(PlacementTimings::default()))
+        .app_data(web::Data::new(instruments))
+        .app_data(web::Data::new(halts))
+        .app_data(web::Data::new(DeadLetters::default()))
+        .app_data(web::Data::new(MissedTriggers::default()))
+        .app_data(web::Data::new(ExecutionLedger::default()))
+        .app_data(web::Data::new(TemplateStore::default()))
+        .app_data(web::Data::new(audit))
+        .app_data(web::Data::new(baskets))
+        .app_data(web::Data::new(groups))
+        .app_data(web::Data::new(OrderCallbacks::default()))
+        .app_data(web::Data::new(renamer))
+        .app_data(web::Data::new(delister))
+        .app_data(web::Data::new(purger))
+        .app_data(web::Data::new(view))
+        .app_data(web::Data::new(search))
+        .app_data(web::Data::new(tape))
+        .app_data(web::Data::new(journal))
+        .app_data(web::Data::new(trading_stats))
+        .app_data(web::Data::new(latency))
+        .app_data(web::Data::new(fill_quality))
+        .app_data(web::Data::new(EngineConfig::default()))
+        .app_data(web::Data::new(housekeeper))
+        .app_data(web::Data::new(FeatureFlags::default()))
+        .app_data(web::Data::new(changes))
+        .app_data(web::Data::new(
+            AccountStore::new(SecretBox::ephemeral())
+                .with_usage(usage)
+                .with_statements(statements),
+        ))
+        .app_data(web::Data::new(Limits {
+            max_batch: 2,
+            ..Limits::default()
+        }))
+        .configure(routes::config)
 }
 
 #[actix_web::test]
@@ -33,6 +242,67 @@ async fn health_ok() {
     assert!(resp.status().is_success());
 }
 
+#[actix_web::test]
+async fn oracle_loss_turns_the_service_cancel_only_until_ticks_resume() {
+    let cache =
+        OracleCache::default().with_dead_man(DeadMan::new(Some(Duration::from_secs(10)), 0));
+    let app = test::init_service(test_app_with_cache(cache.clone())).await;
+    let ready = || TestRequest::get().uri("/health/ready").to_request();
+    let order = json!({"pair": "BTC/USDT", "side": "buy", "price": "100", "quantity": "1"});
+
+    assert_eq!(
+        test::call_service(&app, ready()).await.status(),
+        StatusCode::OK
+    );
+    let req = TestRequest::post()
+        .uri("/orders")
+        .set_json(&order)
+        .to_request();
+    let placed: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    let id = placed["id"].as_str().unwrap().to_string();
+
+    let now = conditional_orderbook::utils::now_ms();
+    assert_eq!(cache.dead_man().check(None, now), Some(true));
+    let resp = test::call_service(&app, ready()).await;
+    assert_eq!(resp.status(), StatusCode::SERVICE_UNAVAILABLE);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["cancel_only"], true);
+
+    let req = TestRequest::post()
+        .uri("/orders")
+        .set_json(&order)
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::SERVICE_UNAVAILABLE);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["code"], "cancel_only");
+    let req = TestRequest::patch()
+        .uri(&format!("/orders/{id}"))
+        .set_json(json!({"price": "99"}))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::SERVICE_UNAVAILABLE);
+    let req = TestRequest::post()
+        .uri(&format!("/orders/{id}/cancel"))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    cache.set(Tick::new("BTC/USDT", dec!(100), now)).await;
+    let last = cache.last_received_ms();
+    assert_eq!(cache.dead_man().check(last, now), Some(false));
+    assert_eq!(
+        test::call_service(&app, ready()).await.status(),
+        StatusCode::OK
+    );
+    let req = TestRequest::post()
+        .uri("/orders")
+        .set_json(&order)
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::CREATED);
+}
+
 #[actix_web::test]
 async fn orders_create_and_get() {
     let app = test::init_service(test_app()).await;
@@ -67,10 +337,24 @@ async fn orders_create_and_get() {
     let fetched: Order = test::read_body_json(resp).await;
     assert_eq!(fetched.id, created.id);
     assert_eq!(fetched.pair, "BTC/USDT");
+
+    let req = TestRequest::get()
+        .uri("/admin/slo/placement")
+        .insert_header(ADMIN)
+        .to_request();
+    let slo: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(slo["samples"], 1);
+    let stages: Vec<_> = slo["stages"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|s| s["stage"].as_str().unwrap())
+        .collect();
+    assert_eq!(stages, ["validation", "risk", "write", "publish"]);
 }
 
 #[actix_web::test]
-async fn orders_list_then_update_status_then_delete() {
+async fn orders_list_then_force_status_then_delete() {
     let app = test::init_service(test_app()).await;
 
     let payload = json!({
@@ -95,12 +379,19 @@ async fn orders_list_then_update_status_then_delete() {
 
     let req = TestRequest::put()
         .uri(&format!("/orders/{}/status", created.id))
-        .set_json(&json!({ "status": "open" }))
+        .set_json(json!({ "status": "open" }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    let req = TestRequest::put()
+        .uri(&format!("/admin/orders/{}/status?force=true", created.id))
+        .insert_header(ADMIN)
+        .set_json(json!({ "status": "open", "reason": "test" }))
         .to_request();
     let resp = test::call_service(&app, req).await;
     assert_eq!(resp.status(), StatusCode::OK);
-    let updated: Order = test::read_body_json(resp).await;
-    assert_eq!(updated.status, OrderStatus::Open);
+    let updated: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(updated["order"]["status"], "open");
 
     let req = TestRequest::delete()
         .uri(&format!("/orders/{}", created.id))
@@ -111,6 +402,2709 @@ async fn orders_list_then_update_status_then_delete() {
     let req = TestRequest::get()
         .uri(&format!("/orders/{}", created.id))
         .to_request();
+    let order: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(order["status"], "cancelled");
+    assert_eq!(order["cancel_reason"], "owner_request");
+    let req = TestRequest::delete()
+        .uri(&format!("/orders/{}", created.id))
+        .to_request();
+    assert_eq!(
+        test::call_service(&app, req).await.status(),
+        StatusCode::CONFLICT
+    );
+}
+
+#[actix_web::test]
+async fn orders_are_found_by_id_prefix_tags_and_metadata() {
+    let app = test::init_service(test_app()).await;
+
+    let mut ids = Vec::new();
+    for (tags, desk) in [(json!(["hedge"]), "macro"), (json!(["scalp"]), "flow")] {
+        let req = TestRequest::post()
+            .uri("/orders")
+            .set_json(json!({
+                "pair": "BTC/USDT",
+                "side": "buy",
+                "price": 100,
+                "quantity": 1,
+                "tags": tags,
+                "metadata": { "desk": desk }
+            }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::CREATED);
+        let o: Order = test::read_body_json(resp).await;
+        ids.push(o.id);
+    }
+
+    let search = |q: &str| {
+        TestRequest::get()
+            .uri(&format!("/orders/search?q={q}"))
+            .to_request()
+    };
+    let hits: serde_json::Value = test::call_and_read_body_json(&app, search("HEDGE")).await;
+    assert_eq!(hits.as_array().unwrap().len(), 1);
+    assert_eq!(hits[0]["order"]["id"], ids[0].as_str());
+    assert_eq!(hits[0]["order"]["tags"], json!(["hedge"]));
+
+    let hits: serde_json::Value =
+        test::call_and_read_body_json(&app, search(&format!("{}+desk:flow", &ids[1][..8]))).await;
+    assert_eq!(hits[0]["order"]["id"], ids[1].as_str());
+    assert_eq!(hits[0]["score"], 11);
+
+    let hits: serde_json::Value = test::call_and_read_body_json(&app, search("desk:none")).await;
+    assert!(hits.as_array().unwrap().is_empty());
+    let resp = test::call_service(&app, search("desk:")).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+    let req = TestRequest::post()
+        .uri("/orders")
+        .set_json(json!({
+            "pair": "BTC/USDT",
+            "side": "buy",
+            "price": 100,
+            "quantity": 1,
+            "tags": ["two words"]
+        }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+}
+
+#[actix_web::test]
+async fn quote_sized_orders_are_estimated_at_their_limit() {
+    let app = test::init_service(test_app()).await;
+    let place = |body: serde_json::Value| {
+        TestRequest::post()
+            .uri("/orders")
+            .set_json(body)
+            .to_request()
+    };
+
+    let resp = test::call_service(
+        &app,
+        place(json!({"pair": "BTC/USDT", "side": "buy", "price": 300, "quantity_quote": 1000})),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::CREATED);
+    let o: Order = test::read_body_json(resp).await;
+    assert_eq!(o.quantity_quote, Some(dec!(1000)));
+    assert_eq!(o.quantity, dec!(3.33333333));
+
+    let req = TestRequest::patch()
+        .uri(&format!("/orders/{}", o.id))
+        .set_json(json!({ "price": 250 }))
+        .to_request();
+    let amended: Order = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(amended.quantity, dec!(4));
+    let req = TestRequest::patch()
+        .uri(&format!("/orders/{}", o.id))
+        .set_json(json!({ "quantity": 1 }))
+        .to_request();
+    assert_eq!(
+        test::call_service(&app, req).await.status(),
+        StatusCode::BAD_REQUEST
+    );
+
+    for bad in [
+        json!({"pair": "BTC/USDT", "side": "buy", "price": 300, "quantity": 1, "quantity_quote": 1000}),
+        json!({"pair": "BTC/USDT", "side": "buy", "order_type": "market", "quantity_quote": 1000}),
+        json!({"pair": "BTC/USDT", "side": "buy", "price": 300, "quantity_quote": 0}),
+    ] {
+        let resp = test::call_service(&app, place(bad)).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+}
+
+#[actix_web::test]
+async fn spread_conditions_take_canonical_legs() {
+    let app = test::init_service(test_app()).await;
+    let place = |legs: serde_json::Value| {
+        TestRequest::post()
+            .uri("/orders")
+            .set_json(json!({
+                "pair": "ETH/USDT",
+                "side": "buy",
+                "quantity": 1,
+                "condition": {"type": "spread", "legs": legs, "below": "0.05"}
+            }))
+            .to_request()
+    };
+    let resp = test::call_service(&app, place(json!(["eth-usdt", "BTCUSDT"]))).await;
+    assert_eq!(resp.status(), StatusCode::CREATED);
+    let o: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(o["condition"]["legs"], json!(["ETH/USDT", "BTC/USDT"]));
+    assert_eq!(o["condition"]["measure"], "ratio");
+
+    for legs in [json!(["ETH/USDT", "eth-usdt"]), json!(["ETH/USDT", "NOPE"])] {
+        let resp = test::call_service(&app, place(legs)).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+}
+
+#[actix_web::test]
+async fn trigger_levels_aggregate_resting_orders_around_the_price() {
+    let cache = OracleCache::default();
+    ScriptedOracle::starting_at(conditional_orderbook::utils::now_ms())
+        .then("BTC/USDT", 100)
+        .play(&cache)
+        .await;
+    let app = test::init_service(test_app_with_cache(cache)).await;
+    for (side, price, qty) in [
+        ("buy", "99", "1"),
+        ("buy", "99", "2"),
+        ("buy", "95", "1"),
+        ("sell", "110", "1"),
+    ] {
+        let req = TestRequest::post()
+            .uri("/orders")
+            .set_json(json!({"pair": "BTC/USDT", "side": side, "price": price, "quantity": qty}))
+            .to_request();
+        assert_eq!(
+            test::call_service(&app, req).await.status(),
+            StatusCode::CREATED
+        );
+    }
+
+    let req = TestRequest::get()
+        .uri("/markets/btc-usdt/triggers?levels=1")
+        .to_request();
+    let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body["price"], "100");
+    assert_eq!(
+        body["below"],
+        json!([{"price": "99", "quantity": "3", "orders": 2}])
+    );
+    assert_eq!(body["above"][0]["price"], "110");
+    assert!(body["below"][0].get("id").is_none());
+    let req = TestRequest::get()
+        .uri("/markets/BTC/USDT/triggers?levels=0")
+        .to_request();
+    assert_eq!(
+        test::call_service(&app, req).await.status(),
+        StatusCode::BAD_REQUEST
+    );
+}
+
+#[actix_web::test]
+async fn the_trade_tape_pages_fills_by_sequence_number() {
+    let changes = ChangeStream::default();
+    let state = AppState::new(Observed::new(
+        InMemoryOrderRepository::default(),
+        changes.clone(),
+    ));
+    let orders = state.orders.clone();
+    let app = test::init_service(test_app_with_state(
+        state,
+        changes,
+        OpenOrdersView::default(),
+        Journal::default(),
+        OracleCache::default(),
+        InstrumentRegistry::default(),
+    ))
+    .await;
+    let tape = |q: &str| {
+        TestRequest::get()
+            .uri(&format!("/markets/btc-usdt/tape{q}"))
+            .to_request()
+    };
+    let page: serde_json::Value = test::call_and_read_body_json(&app, tape("")).await;
+    assert_eq!(page, json!({"pair": "BTC/USDT", "head": 0, "entries": []}));
+
+    let req = TestRequest::post()
+        .uri("/orders")
+        .set_json(json!({"pair": "BTC/USDT", "side": "buy", "price": "100", "quantity": "3"}))
+        .to_request();
+    let placed: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    let id = placed["id"].as_str().unwrap();
+    for (i, price) in [dec!(100), dec!(99.5), dec!(99)].into_iter().enumerate() {
+        let fill = Fill {
+            price,
+            quantity: dec!(1),
+            ts_ms: i as i64,
+            received_ms: None,
+            decided_ms: None,
+            recovered_trigger: false,
+            interpolated_trigger: false,
+            legs: Vec::new(),
+            trigger_price: None,
+            fair_sources: Vec::new(),
+            counterparty: None,
+        };
+        orders.record_fills(id, &[fill]).await.unwrap();
+    }
+
+    let page: serde_json::Value =
+        test::call_and_read_body_json(&app, tape("?from_seq=2&limit=1")).await;
+    assert_eq!(page["head"], 3);
+    assert_eq!(
+        page["entries"],
+        json!([{
+            "seq": 2, "pair": "BTC/USDT", "order_id": id, "fill_index": 1,
+            "side": "buy", "price": "99.5", "quantity": "1", "ts_ms": 1,
+        }])
+    );
+    for q in ["?limit=0", "?from_seq=3&to_seq=2"] {
+        assert_eq!(
+            test::call_service(&app, tape(q)).await.status(),
+            StatusCode::BAD_REQUEST
+        );
+    }
+    let req = TestRequest::get().uri("/markets/NOPE/tape").to_request();
+    assert_eq!(
+        test::call_service(&app, req).await.status(),
+        StatusCode::NOT_FOUND
+    );
+}
+
+#[actix_web::test]
+async fn index_price_is_served_per_pair() {
+    let cache = OracleCache::default();
+    ScriptedOracle::starting_at(1)
+        .then("BTC/USDT", 100)
+        .then("BTC/USDT", 101)
+        .play(&cache)
+        .await;
+    let app = test::init_service(test_app_with_cache(cache)).await;
+
+    let req = TestRequest::get().uri("/index/BTC/USDT").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["pair"], "BTC/USDT");
+    assert_eq!(body["last_price"], "101");
+    assert_eq!(body["index_price"], "100.2");
+
+    let req = TestRequest::get().uri("/index/ETH/USDT").to_request();
     let resp = test::call_service(&app, req).await;
     assert_eq!(resp.status(), StatusCode::NOT_FOUND);
 }
+
+#[actix_web::test]
+async fn orders_trigger_on_a_configured_price_source_or_the_aggregate() {
+    let cache = OracleCache::default().with_sources(vec!["binance".into()]);
+    let app = test::init_service(test_app_with_cache(cache)).await;
+
+    let req = TestRequest::post()
+        .uri("/orders")
+        .set_json(json!({"pair": "BTC/USDT", "side": "buy", "price": "100", "quantity": "1", "price_source": "binance"}))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::CREATED);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["price_source"], "binance");
+
+    let req = TestRequest::post()
+        .uri("/orders")
+        .set_json(json!({"pair": "BTC/USDT", "side": "buy", "price": "100", "quantity": "1"}))
+        .to_request();
+    let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert!(body.get("price_source").is_none());
+
+    let req = TestRequest::post()
+        .uri("/orders")
+        .set_json(json!({"pair": "BTC/USDT", "side": "buy", "price": "100", "quantity": "1", "price_source": "kraken"}))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+    let req = TestRequest::post()
+        .uri("/templates")
+        .set_json(json!({"pair": "BTC/USDT", "side": "buy", "price_source": "kraken"}))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+}
+
+#[actix_web::test]
+async fn orphaned_orders_report_and_bulk_cancel() {
+    let cache = OracleCache::default();
+    cache.set(Tick::new("BTC/USDT", dec!(100), 1)).await;
+    let app = test::init_service(test_app_with_cache(cache)).await;
+
+    for pair in ["BTC/USDT", "SOL/USDT"] {
+        let req = TestRequest::post()
+            .uri("/orders")
+            .set_json(json!({"pair": pair, "side": "buy", "price": 1, "quantity": 1}))
+            .to_request();
+        assert_eq!(
+            test::call_service(&app, req).await.status(),
+            StatusCode::CREATED
+        );
+    }
+
+    let req = TestRequest::get()
+        .uri("/admin/orders/orphaned?min_age_hours=0")
+        .insert_header(ADMIN)
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["count"], 1);
+    assert_eq!(body["orders"][0]["pair"], "SOL/USDT");
+
+    let req = TestRequest::get().uri("/metrics").to_request();
+    let body = test::call_and_read_body(&app, req).await;
+    let text = String::from_utf8(body.to_vec()).unwrap();
+    assert!(text.contains("orphaned_orders{pair=\"SOL/USDT\"} 1"));
+
+    let req = TestRequest::post()
+        .uri("/admin/orders/orphaned/cancel?min_age_hours=0")
+        .insert_header(ADMIN)
+        .to_request();
+    let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body["result"]["cancelled"].as_array().unwrap().len(), 1);
+
+    let req = TestRequest::get()
+        .uri("/orders?status=cancelled")
+        .to_request();
+    let list: Vec<Order> = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(list.len(), 1);
+    assert_eq!(list[0].pair, "SOL/USDT");
+
+    for hours in ["-1", &i64::MAX.to_string()] {
+        let req = TestRequest::get()
+            .uri(&format!("/admin/orders/orphaned?min_age_hours={hours}"))
+            .insert_header(ADMIN)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+}
+
+#[actix_web::test]
+async fn orders_stream_as_ndjson() {
+    let app = test::init_service(test_app()).await;
+    for pair in ["BTC/USDT", "ETH/USDT", "BTC/USDT"] {
+        let req = TestRequest::post()
+            .uri("/orders")
+            .set_json(json!({"pair": pair, "side": "buy", "price": "1", "quantity": "1"}))
+            .to_request();
+        test::call_service(&app, req).await;
+    }
+    let req = TestRequest::get()
+        .uri("/orders/stream?pair=btc-usdt")
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(
+        resp.headers().get("content-type").unwrap(),
+        "application/x-ndjson"
+    );
+    let body = test::read_body(resp).await;
+    let orders: Vec<Order> = std::str::from_utf8(&body)
+        .unwrap()
+        .lines()
+        .map(|l| serde_json::from_str(l).unwrap())
+        .collect();
+    assert_eq!(orders.len(), 2);
+    assert!(orders.iter().all(|o| o.pair == "BTC/USDT"));
+}
+
+#[actix_web::test]
+async fn orders_normalize_pair_spellings_and_reject_unknown() {
+    let app = test::init_service(test_app()).await;
+
+    let req = TestRequest::post()
+        .uri("/orders")
+        .set_json(json!({"pair": "btc-usdt", "side": "buy", "price": 1, "quantity": 1}))
+        .to_request();
+    let created: Order = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(created.pair, "BTC/USDT");
+
+    let req = TestRequest::get().uri("/orders?pair=BTCUSDT").to_request();
+    let list: Vec<Order> = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(list.len(), 1);
+
+    let req = TestRequest::post()
+        .uri("/orders")
+        .set_json(json!({"pair": "BTCUSD", "side": "buy", "price": 1, "quantity": 1}))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert!(body["error"]
+        .as_str()
+        .unwrap()
+        .contains("did you mean BTC/USDT"));
+}
+
+#[actix_web::test]
+async fn orders_accept_string_and_number_amounts_and_enforce_precision() {
+    let app = test::init_service(test_app()).await;
+
+    let req = TestRequest::post()
+        .uri("/orders")
+        .set_json(
+            json!({"pair": "BTC/USDT", "side": "buy", "price": "100.12345678", "quantity": 0.5}),
+        )
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::CREATED);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["price"], "100.12345678");
+    assert_eq!(body["quantity"], "0.5");
+
+    let req = TestRequest::post()
+        .uri("/orders")
+        .set_json(
+            json!({"pair": "BTC/USDT", "side": "buy", "price": "100.123456789", "quantity": 1}),
+        )
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+}
+
+#[actix_web::test]
+async fn market_orders_require_a_fresh_feed_and_limit_orders_a_price() {
+    let cache = OracleCache::default();
+    cache
+        .set(Tick::new(
+            "BTC/USDT",
+            dec!(100),
+            conditional_orderbook::utils::now_ms(),
+        ))
+        .await;
+    cache.set(Tick::new("ETH/USDT", dec!(100), 1)).await;
+    let app = test::init_service(test_app_with_cache(cache)).await;
+
+    let req = TestRequest::post()
+        .uri("/orders")
+        .set_json(json!({"pair": "BTC/USDT", "side": "buy", "order_type": "market", "quantity": 1}))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::CREATED);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["order_type"], "market");
+    assert_eq!(body["exec_price"], serde_json::Value::Null);
+
+    let req = TestRequest::post()
+        .uri("/orders")
+        .set_json(json!({"pair": "ETH/USDT", "side": "buy", "order_type": "market", "quantity": 1}))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+    let req = TestRequest::post()
+        .uri("/orders")
+        .set_json(json!({"pair": "BTC/USDT", "side": "buy", "quantity": 1}))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+    let req = TestRequest::post()
+        .uri("/orders")
+        .set_json(json!({"pair": "BTC/USDT", "side": "buy", "price": "90", "quantity": 1, "exec_pricing": "limit"}))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::CREATED);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["exec_pricing"], "limit");
+
+    let req = TestRequest::post()
+        .uri("/orders")
+        .set_json(json!({"pair": "BTC/USDT", "side": "buy", "order_type": "market", "quantity": 1, "exec_pricing": "better_of"}))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+}
+
+#[actix_web::test]
+async fn stop_orders_need_a_trigger_price_and_stop_limits_a_limit() {
+    let app = test::init_service(test_app()).await;
+    let place = |body: serde_json::Value| {
+        TestRequest::post()
+            .uri("/orders")
+            .set_json(body)
+            .to_request()
+    };
+    let o: serde_json::Value = test::call_and_read_body_json(
+        &app,
+        place(json!({"pair": "BTC/USDT", "side": "buy", "order_type": "stop", "trigger_price": "105", "quantity": 1})),
+    )
+    .await;
+    assert_eq!(
+        (o["order_type"].as_str(), o["trigger_price"].as_str()),
+        (Some("stop"), Some("105"))
+    );
+    let o: serde_json::Value = test::call_and_read_body_json(
+        &app,
+        place(json!({"pair": "BTC/USDT", "side": "sell", "order_type": "stop_limit", "price": "94", "trigger_price": "95", "quantity": 1})),
+    )
+    .await;
+    assert_eq!(o["price"], "94");
+    assert!(o.get("stop_triggered_ms").is_none());
+    for bad in [
+        json!({"pair": "BTC/USDT", "side": "buy", "order_type": "stop", "quantity": 1}),
+        json!({"pair": "BTC/USDT", "side": "buy", "order_type": "stop", "trigger_price": "0", "quantity": 1}),
+        json!({"pair": "BTC/USDT", "side": "buy", "order_type": "stop_limit", "trigger_price": "95", "quantity": 1}),
+        json!({"pair": "BTC/USDT", "side": "buy", "price": "100", "trigger_price": "95", "quantity": 1}),
+    ] {
+        let resp = test::call_service(&app, place(bad.clone())).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST, "{bad}");
+    }
+}
+
+#[actix_web::test]
+async fn trailing_stops_take_an_amount_or_percent_and_need_no_trigger_price() {
+    let app = test::init_service(test_app()).await;
+    let place = |body: serde_json::Value| {
+        TestRequest::post()
+            .uri("/orders")
+            .set_json(body)
+            .to_request()
+    };
+    let o: serde_json::Value = test::call_and_read_body_json(
+        &app,
+        place(json!({"pair": "BTC/USDT", "side": "sell", "order_type": "stop", "trailing": {"percent": "2.5"}, "quantity": 1})),
+    )
+    .await;
+    assert_eq!(o["trailing"], json!({"percent": "2.5"}));
+    assert!(o.get("trigger_price").is_none() && o.get("watermark").is_none());
+    let o: serde_json::Value = test::call_and_read_body_json(
+        &app,
+        place(json!({"pair": "BTC/USDT", "side": "buy", "order_type": "stop", "trailing": {"amount": "50"}, "trigger_price": "105", "quantity": 1})),
+    )
+    .await;
+    assert_eq!(
+        (
+            o["trailing"]["amount"].as_str(),
+            o["trigger_price"].as_str()
+        ),
+        (Some("50"), Some("105"))
+    );
+    for bad in [
+        json!({"pair": "BTC/USDT", "side": "sell", "order_type": "stop", "trailing": {"amount": "0"}, "quantity": 1}),
+        json!({"pair": "BTC/USDT", "side": "sell", "order_type": "stop", "trailing": {"percent": "100"}, "quantity": 1}),
+        json!({"pair": "BTC/USDT", "side": "sell", "order_type": "stop_limit", "price": "94", "trailing": {"amount": "5"}, "quantity": 1}),
+        json!({"pair": "BTC/USDT", "side": "sell", "price": "94", "trailing": {"amount": "5"}, "quantity": 1}),
+    ] {
+        let resp = test::call_service(&app, place(bad.clone())).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST, "{bad}");
+    }
+}
+
+#[actix_web::test]
+async fn oco_places_two_linked_legs_on_one_pair() {
+    let app = test::init_service(test_app()).await;
+    let place = |legs: serde_json::Value| {
+        TestRequest::post()
+            .uri("/orders/oco")
+            .set_json(json!({ "legs": legs }))
+            .to_request()
+    };
+    let resp = test::call_service(
+        &app,
+        place(json!([
+            {"pair": "BTC/USDT", "side": "sell", "price": "110", "quantity": 1},
+            {"pair": "BTC/USDT", "side": "sell", "order_type": "stop", "trigger_price": "95", "quantity": 1},
+        ])),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::CREATED);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    let oco_id = body["oco_id"].as_str().unwrap();
+    assert_eq!(body["orders"].as_array().unwrap().len(), 2);
+    for o in body["orders"].as_array().unwrap() {
+        assert_eq!(o["oco_id"], oco_id);
+        let id = o["id"].as_str().unwrap();
+        let req = TestRequest::get()
+            .uri(&format!("/orders/{id}"))
+            .to_request();
+        let stored: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(stored["oco_id"], oco_id);
+    }
+
+    for bad in [
+        json!([{"pair": "BTC/USDT", "side": "sell", "price": "110", "quantity": 1}]),
+        json!([
+            {"pair": "BTC/USDT", "side": "sell", "price": "110", "quantity": 1},
+            {"pair": "ETH/USDT", "side": "sell", "price": "110", "quantity": 1},
+        ]),
+        json!([
+            {"pair": "BTC/USDT", "side": "sell", "price": "110", "quantity": 1},
+            {"pair": "BTC/USDT", "side": "sell", "order_type": "stop", "quantity": 1},
+        ]),
+    ] {
+        let resp = test::call_service(&app, place(bad.clone())).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST, "{bad}");
+    }
+    let all: Vec<serde_json::Value> =
+        test::call_and_read_body_json(&app, TestRequest::get().uri("/orders").to_request()).await;
+    assert_eq!(all.len(), 2, "a refused pair stores neither leg");
+}
+
+#[actix_web::test]
+async fn a_bracket_parks_its_exits_until_the_entry_fills() {
+    let app = test::init_service(test_app()).await;
+    let place = |body: serde_json::Value| {
+        TestRequest::post()
+            .uri("/orders/bracket")
+            .set_json(body)
+            .to_request()
+    };
+    let entry = json!({"pair": "BTC/USDT", "side": "buy", "price": "100", "quantity": "2"});
+    let resp = test::call_service(
+        &app,
+        place(json!({
+            "entry": entry,
+            "take_profit": {"price": "110"},
+            "stop_loss": {"trigger_price": "95", "price": "94"},
+        })),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::CREATED);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    let entry_id = body["entry"]["id"].as_str().unwrap();
+    assert_eq!(body["entry"]["status"], "new");
+    let (tp, sl) = (&body["take_profit"], &body["stop_loss"]);
+    assert_eq!(
+        (
+            &tp["order_type"],
+            &tp["side"],
+            &tp["price"],
+            &tp["quantity"]
+        ),
+        (&json!("limit"), &json!("sell"), &json!("110"), &json!("2"))
+    );
+    assert_eq!(
+        (&sl["order_type"], &sl["trigger_price"], &sl["price"]),
+        (&json!("stop_limit"), &json!("95"), &json!("94"))
+    );
+    for child in [tp, sl] {
+        assert_eq!(child["status"], "pending");
+        assert_eq!(child["parent_id"], entry_id);
+        assert_eq!(child["oco_id"], tp["oco_id"]);
+    }
+    let id = tp["id"].as_str().unwrap();
+    let req = TestRequest::patch()
+        .uri(&format!("/orders/{id}"))
+        .set_json(json!({"price": "111"}))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(
+        resp.status(),
+        StatusCode::CONFLICT,
+        "a pending exit cannot be amended"
+    );
+
+    for bad in [
+        json!({"entry": entry, "take_profit": {"price": "90"}, "stop_loss": {"trigger_price": "95"}}),
+        json!({"entry": entry, "take_profit": {"price": "110"}}),
+        json!({
+            "entry": {"pair": "BTC/USDT", "side": "buy", "price": "100", "quantity_quote": "200"},
+            "take_profit": {"price": "110"},
+            "stop_loss": {"trigger_price": "95"},
+        }),
+    ] {
+        let resp = test::call_service(&app, place(bad.clone())).await;
+        assert!(resp.status().is_client_error(), "{bad}");
+    }
+    let all: Vec<serde_json::Value> =
+        test::call_and_read_body_json(&app, TestRequest::get().uri("/orders").to_request()).await;
+    assert_eq!(all.len(), 3, "a refused bracket stores nothing");
+}
+
+#[actix_web::test]
+async fn cancel_after_is_stored_and_must_be_positive() {
+    let app = test::init_service(test_app()).await;
+    let place = |after: i64| {
+        TestRequest::post()
+            .uri("/orders")
+            .set_json(json!({"pair": "BTC/USDT", "side": "buy", "price": "100", "quantity": "1", "cancel_after_ms": after}))
+            .to_request()
+    };
+    let o: serde_json::Value = test::call_and_read_body_json(&app, place(30_000)).await;
+    assert_eq!(o["cancel_after_ms"], 30_000);
+    let resp = test::call_service(&app, place(0)).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+}
+
+#[actix_web::test]
+async fn gtd_needs_a_future_expiry_and_ioc_cannot_be_sustained() {
+    let app = test::init_service(test_app()).await;
+    let place = |extra: serde_json::Value| {
+        let mut body = json!({"pair": "BTC/USDT", "side": "buy", "price": "100", "quantity": "1"});
+        body.as_object_mut()
+            .unwrap()
+            .extend(extra.as_object().unwrap().clone());
+        TestRequest::post()
+            .uri("/orders")
+            .set_json(body)
+            .to_request()
+    };
+    let at = conditional_orderbook::utils::now_ms() + 60_000;
+    let o: serde_json::Value = test::call_and_read_body_json(
+        &app,
+        place(json!({"time_in_force": "gtd", "expires_at": at})),
+    )
+    .await;
+    assert_eq!(
+        (&o["time_in_force"], &o["expires_at"]),
+        (&json!("gtd"), &json!(at))
+    );
+    let o: serde_json::Value =
+        test::call_and_read_body_json(&app, place(json!({"time_in_force": "ioc"}))).await;
+    assert_eq!(o["time_in_force"], "ioc");
+
+    for bad in [
+        json!({"time_in_force": "gtd"}),
+        json!({"time_in_force": "gtd", "expires_at": 1}),
+        json!({"time_in_force": "gtc", "expires_at": at}),
+        json!({"time_in_force": "ioc", "sustained_for_ms": 1_000}),
+        json!({"time_in_force": "ioc", "valid_for_after_armed_ms": 1_000}),
+    ] {
+        let resp = test::call_service(&app, place(bad.clone())).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST, "{bad}");
+    }
+}
+
+#[actix_web::test]
+async fn valid_for_after_armed_is_stored_and_rejected_on_fok() {
+    let app = test::init_service(test_app()).await;
+    let place = |ms: i64, tif: &str| {
+        TestRequest::post()
+            .uri("/orders")
+            .set_json(json!({"pair": "BTC/USDT", "side": "buy", "price": "100", "quantity": "1", "time_in_force": tif, "valid_for_after_armed_ms": ms}))
+            .to_request()
+    };
+    let o: serde_json::Value = test::call_and_read_body_json(&app, place(5_000, "gtc")).await;
+    assert_eq!(o["valid_for_after_armed_ms"], 5_000);
+    assert!(o.get("armed_ms").is_none());
+    for (ms, tif) in [(0, "gtc"), (5_000, "fok")] {
+        let resp = test::call_service(&app, place(ms, tif)).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+}
+
+#[actix_web::test]
+async fn order_prices_snap_to_the_tick_size_or_are_rejected() {
+    let instruments = InstrumentRegistry::default();
+    instruments.set_tick_size("BTC/USDT", dec!(0.5), TickPolicy::Passive);
+    instruments.set_tick_size("ETH/USDT", dec!(0.5), TickPolicy::Reject);
+    let app = test::init_service(test_app_with(OracleCache::default(), instruments)).await;
+    let place = |pair: &str, side: &str, price: &str| {
+        TestRequest::post()
+            .uri("/orders")
+            .set_json(json!({"pair": pair, "side": side, "price": price, "quantity": "1"}))
+            .to_request()
+    };
+
+    let buy: serde_json::Value =
+        test::call_and_read_body_json(&app, place("BTC/USDT", "buy", "100.3")).await;
+    assert_eq!(buy["price"], "100");
+    let sell: serde_json::Value =
+        test::call_and_read_body_json(&app, place("BTC/USDT", "sell", "100.3")).await;
+    assert_eq!(sell["price"], "100.5");
+    let resp = test::call_service(&app, place("ETH/USDT", "buy", "100.3")).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    let resp = test::call_service(&app, place("ETH/USDT", "buy", "100.5")).await;
+    assert_eq!(resp.status(), StatusCode::CREATED);
+
+    let req = TestRequest::patch()
+        .uri(&format!("/orders/{}", buy["id"].as_str().unwrap()))
+        .set_json(json!({"price": "101.2"}))
+        .to_request();
+    let amended: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(amended["price"], "101");
+}
+
+#[actix_web::test]
+async fn market_stream_relays_the_pairs_ticks() {
+    use actix_web::body::MessageBody;
+
+    let cache = OracleCache::default();
+    let app = test::init_service(test_app_with_cache(cache.clone())).await;
+    let req = TestRequest::get()
+        .uri("/markets/DOGE/USDT/stream")
+        .to_request();
+    assert_eq!(
+        test::call_service(&app, req).await.status(),
+        StatusCode::NOT_FOUND
+    );
+
+    let req = TestRequest::get()
+        .uri("/markets/btc-usdt/stream")
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    cache.set(Tick::new("ETH/USDT", dec!(5), 1)).await;
+    cache.set(Tick::new("BTC/USDT", dec!(100), 1)).await;
+    let mut body = Box::pin(resp.into_body());
+    let frame = std::future::poll_fn(|cx| body.as_mut().poll_next(cx))
+        .await
+        .unwrap()
+        .unwrap();
+    let frame = String::from_utf8(frame.to_vec()).unwrap();
+    assert!(frame.starts_with("event: tick\n"));
+    assert!(frame.contains(r#""pair":"BTC/USDT""#));
+}
+
+#[actix_web::test]
+async fn markets_expose_session_state_and_reject_off_session_orders() {
+    let instruments = InstrumentRegistry::default();
+    let now = conditional_orderbook::utils::now_ms();
+    instruments.set_session(
+        "ETH/USDT",
+        TradingSession {
+            maintenance: vec![MaintenanceWindow {
+                start_ms: now - 60_000,
+                end_ms: now + 3_600_000,
+            }],
+            off_session: OffSessionPolicy::Reject,
+            ..TradingSession::default()
+        },
+    );
+    instruments.add_maintenance(
+        "SOL/USDT",
+        MaintenanceWindow {
+            start_ms: now - 60_000,
+            end_ms: now + 3_600_000,
+        },
+    );
+    let cache = OracleCache::default();
+    cache.set(Tick::new("BTC/USDT", dec!(100), now)).await;
+    let app = test::init_service(test_app_with(cache, instruments)).await;
+
+    let req = TestRequest::get().uri("/markets/BTC/USDT").to_request();
+    let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body["symbol"], "BTC/USDT");
+    assert_eq!(body["session_state"], "open");
+    assert_eq!(body["last_price"], "100");
+
+    let req = TestRequest::get().uri("/markets/eth-usdt").to_request();
+    let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body["session_state"], "maintenance");
+
+    let req = TestRequest::post()
+        .uri("/orders")
+        .set_json(json!({"pair": "ETH/USDT", "side": "buy", "price": 1, "quantity": 1}))
+        .to_request();
+    assert_eq!(
+        test::call_service(&app, req).await.status(),
+        StatusCode::CONFLICT
+    );
+
+    let req = TestRequest::post()
+        .uri("/orders")
+        .set_json(json!({"pair": "SOL/USDT", "side": "buy", "price": 1, "quantity": 1}))
+        .to_request();
+    assert_eq!(
+        test::call_service(&app, req).await.status(),
+        StatusCode::CREATED
+    );
+
+    let req = TestRequest::get().uri("/markets/DOGE/USDT").to_request();
+    assert_eq!(
+        test::call_service(&app, req).await.status(),
+        StatusCode::NOT_FOUND
+    );
+}
+
+#[actix_web::test]
+async fn admin_halts_and_resumes_pairs_and_reports_stats() {
+    let app = test::init_service(test_app()).await;
+
+    let req = TestRequest::post()
+        .uri("/admin/pairs/btc-usdt/halt")
+        .insert_header(ADMIN)
+        .set_json(json!({"reason": "exchange outage"}))
+        .to_request();
+    let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body["pair"], "BTC/USDT");
+    assert_eq!(body["source"], "admin");
+
+    let req = TestRequest::post()
+        .uri("/admin/pairs/BTC/USDT/halt")
+        .insert_header(ADMIN)
+        .to_request();
+    assert_eq!(
+        test::call_service(&app, req).await.status(),
+        StatusCode::CONFLICT
+    );
+
+    let req = TestRequest::get()
+        .uri("/admin/stats")
+        .insert_header(ADMIN)
+        .to_request();
+    let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body["halts"]["halted"][0]["reason"], "exchange outage");
+    assert_eq!(body["halts"]["default_policy"], "require_fresh_crossing");
+
+    let req = TestRequest::post()
+        .uri("/admin/pairs/BTC/USDT/resume?policy=trigger_immediately")
+        .insert_header(ADMIN)
+        .to_request();
+    let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body["policy"], "trigger_immediately");
+
+    let req = TestRequest::post()
+        .uri("/admin/pairs/BTC/USDT/resume")
+        .insert_header(ADMIN)
+        .to_request();
+    assert_eq!(
+        test::call_service(&app, req).await.status(),
+        StatusCode::CONFLICT
+    );
+
+    let req = TestRequest::post()
+        .uri("/admin/pairs/DOGE/USDT/halt")
+        .insert_header(ADMIN)
+        .to_request();
+    assert_eq!(
+        test::call_service(&app, req).await.status(),
+        StatusCode::NOT_FOUND
+    );
+}
+
+#[actix_web::test]
+async fn admin_toggles_feature_flags_per_pair_and_account() {
+    let app = test::init_service(test_app()).await;
+    let set = |body: serde_json::Value| {
+        TestRequest::put()
+            .uri("/admin/flags/partial_fill")
+            .insert_header(ADMIN)
+            .set_json(body)
+            .to_request()
+    };
+
+    let flag: serde_json::Value =
+        test::call_and_read_body_json(&app, set(json!({"enabled": true, "pair": "btc-usdt"})))
+            .await;
+    assert_eq!(flag["pairs"]["BTC/USDT"], true);
+    assert_eq!(flag["enabled"], false);
+    let flag: serde_json::Value =
+        test::call_and_read_body_json(&app, set(json!({"enabled": false, "account": "desk-1"})))
+            .await;
+    assert_eq!(flag["accounts"]["desk-1"], false);
+
+    for (req, status) in [
+        (
+            set(json!({"enabled": true, "pair": "DOGE/USDT"})),
+            StatusCode::NOT_FOUND,
+        ),
+        (
+            set(json!({"enabled": true, "pair": "BTC/USDT", "account": "desk-1"})),
+            StatusCode::BAD_REQUEST,
+        ),
+        (
+            TestRequest::put()
+                .uri("/admin/flags/event_driven")
+                .insert_header(ADMIN)
+                .set_json(json!({"enabled": true}))
+                .to_request(),
+            StatusCode::NOT_FOUND,
+        ),
+    ] {
+        assert_eq!(test::call_service(&app, req).await.status(), status);
+    }
+
+    let clear = || {
+        TestRequest::delete()
+            .uri("/admin/flags/partial_fill?pair=BTC/USDT")
+            .insert_header(ADMIN)
+            .to_request()
+    };
+    let flag: serde_json::Value = test::call_and_read_body_json(&app, clear()).await;
+    assert!(flag.get("pairs").is_none());
+    assert_eq!(
+        test::call_service(&app, clear()).await.status(),
+        StatusCode::NOT_FOUND
+    );
+
+    let req = TestRequest::get()
+        .uri("/admin/flags")
+        .insert_header(ADMIN)
+        .to_request();
+    let flags: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(flags[0]["feature"], "partial_fill");
+    assert_eq!(flags[1]["feature"], "bid_ask");
+}
+
+#[actix_web::test]
+async fn admin_lists_housekeeping_policies_and_runs_them_on_demand() {
+    let app = test::init_service(test_app()).await;
+    let req = TestRequest::post()
+        .uri("/orders")
+        .set_json(json!({"pair": "BTC/USDT", "side": "buy", "price": "100", "quantity": "1"}))
+        .to_request();
+    assert_eq!(
+        test::call_service(&app, req).await.status(),
+        StatusCode::CREATED
+    );
+
+    let req = TestRequest::get()
+        .uri("/admin/housekeeping")
+        .insert_header(ADMIN)
+        .to_request();
+    let policies: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(policies[0]["name"], "stale");
+    assert_eq!(policies[0]["schedule"], "0 3 * * *");
+    assert_eq!(policies[0]["action"], "cancel_older_than");
+    assert_eq!(policies[0]["older_than_ms"], 30 * 86_400_000i64);
+    assert!(policies[1]["next_run_ms"].as_i64().unwrap() > 0);
+    assert!(policies[1]["last_run"].is_null());
+
+    for (name, affected) in [("stale", 0), ("reindex", 1)] {
+        let req = TestRequest::post()
+            .uri(&format!("/admin/housekeeping/{name}/run"))
+            .insert_header(ADMIN)
+            .to_request();
+        let run: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(
+            (&run["status"], &run["trigger"], &run["affected"]),
+            (&json!("completed"), &json!("manual"), &json!(affected)),
+            "{name}"
+        );
+    }
+    let req = TestRequest::post()
+        .uri("/admin/housekeeping/nope/run")
+        .insert_header(ADMIN)
+        .to_request();
+    assert_eq!(
+        test::call_service(&app, req).await.status(),
+        StatusCode::NOT_FOUND
+    );
+
+    let req = TestRequest::get()
+        .uri("/admin/housekeeping/runs?policy=reindex")
+        .insert_header(ADMIN)
+        .to_request();
+    let runs: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(runs.as_array().unwrap().len(), 1);
+    let req = TestRequest::get()
+        .uri("/admin/housekeeping")
+        .insert_header(ADMIN)
+        .to_request();
+    let policies: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(policies[1]["last_run"]["id"], runs[0]["id"]);
+    let req = TestRequest::get()
+        .uri("/orders?status=new&pair=BTC/USDT")
+        .to_request();
+    let open: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(open.as_array().unwrap().len(), 1);
+}
+
+#[actix_web::test]
+async fn admin_renames_a_pair_after_a_dry_run() {
+    let app = test::init_service(test_app()).await;
+    let req = TestRequest::post()
+        .uri("/orders")
+        .set_json(json!({"pair": "BTC/USDT", "side": "buy", "price": "100", "quantity": "1"}))
+        .to_request();
+    let order: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    let id = order["id"].as_str().unwrap().to_string();
+    let rename = |body: serde_json::Value| {
+        TestRequest::post()
+            .uri("/admin/pairs/btc-usdt/rename")
+            .insert_header(ADMIN)
+            .set_json(body)
+            .to_request()
+    };
+
+    let report: serde_json::Value =
+        test::call_and_read_body_json(&app, rename(json!({"to": "XBT/USDT", "dry_run": true})))
+            .await;
+    assert_eq!(report["from"], "BTC/USDT");
+    assert_eq!(report["active_orders"], 1);
+    let req = TestRequest::get()
+        .uri(&format!("/orders/{id}"))
+        .to_request();
+    let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body["pair"], "BTC/USDT");
+
+    for (body, status) in [
+        (json!({"to": "ETH/USDT"}), StatusCode::CONFLICT),
+        (json!({"to": "XBTUSDT"}), StatusCode::BAD_REQUEST),
+    ] {
+        assert_eq!(
+            test::call_service(&app, rename(body)).await.status(),
+            status
+        );
+    }
+
+    let report: serde_json::Value =
+        test::call_and_read_body_json(&app, rename(json!({"to": "XBT/USDT"}))).await;
+    assert_eq!(
+        (report["dry_run"].clone(), report["orders"].clone()),
+        (json!(false), json!(1))
+    );
+    let req = TestRequest::get()
+        .uri(&format!("/orders/{id}"))
+        .to_request();
+    let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body["pair"], "XBT/USDT");
+    // The old spelling still resolves, now to the new name.
+    let req = TestRequest::get().uri("/orders?pair=BTC/USDT").to_request();
+    let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body.as_array().map(Vec::len), Some(1));
+    let req = TestRequest::get().uri("/markets/XBT/USDT").to_request();
+    assert!(test::call_service(&app, req).await.status().is_success());
+}
+
+#[actix_web::test]
+async fn admin_delists_a_pair_and_reports_progress() {
+    let app = test::init_service(test_app()).await;
+    let req = TestRequest::post()
+        .uri("/orders")
+        .set_json(json!({"pair": "ETH/USDT", "side": "buy", "price": "100", "quantity": "1"}))
+        .to_request();
+    let order: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    let delist = |pair: &str| {
+        TestRequest::post()
+            .uri(&format!("/admin/markets/{pair}/delist"))
+            .insert_header(ADMIN)
+            .to_request()
+    };
+    assert_eq!(
+        test::call_service(&app, delist("DOGE/USDT")).await.status(),
+        StatusCode::NOT_FOUND
+    );
+
+    let resp = test::call_service(&app, delist("eth-usdt")).await;
+    assert_eq!(resp.status(), StatusCode::ACCEPTED);
+    let job: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(job["pair"], "ETH/USDT");
+    let uri = format!("/admin/delistings/{}", job["id"].as_str().unwrap());
+    let job = loop {
+        let req = TestRequest::get()
+            .uri(&uri)
+            .insert_header(ADMIN)
+            .to_request();
+        let job: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        if job["status"] != "running" {
+            break job;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+    };
+    assert_eq!(
+        (job["status"].clone(), job["step"].clone()),
+        (json!("completed"), json!("done"))
+    );
+    assert_eq!(job["orders_cancelled"], 1);
+
+    let req = TestRequest::get()
+        .uri(&format!("/orders/{}", order["id"].as_str().unwrap()))
+        .to_request();
+    let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(
+        (body["status"].clone(), body["cancel_reason"].clone()),
+        (json!("cancelled"), json!("delisted"))
+    );
+    let req = TestRequest::post()
+        .uri("/orders")
+        .set_json(json!({"pair": "ETH/USDT", "side": "buy", "price": "100", "quantity": "1"}))
+        .to_request();
+    assert!(test::call_service(&app, req)
+        .await
+        .status()
+        .is_client_error());
+    let req = TestRequest::get()
+        .uri("/admin/markets/delisted")
+        .insert_header(ADMIN)
+        .to_request();
+    let archived: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(archived[0]["symbol"], "ETH/USDT");
+    assert_eq!(
+        test::call_service(&app, delist("ETH/USDT")).await.status(),
+        StatusCode::CONFLICT
+    );
+}
+
+#[actix_web::test]
+async fn order_groups_share_a_budget_across_buy_orders() {
+    let app = test::init_service(test_app()).await;
+    let mut ids = Vec::new();
+    for (side, price) in [("buy", "100"), ("buy", "90"), ("sell", "110")] {
+        let req = TestRequest::post()
+            .uri("/orders")
+            .set_json(json!({"pair": "BTC/USDT", "side": side, "price": price, "quantity": "1"}))
+            .to_request();
+        let order: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        ids.push(order["id"].as_str().unwrap().to_string());
+    }
+    let group = |body: serde_json::Value| {
+        TestRequest::post()
+            .uri("/order-groups")
+            .set_json(body)
+            .to_request()
+    };
+    for (body, status) in [
+        (
+            json!({"budget": "0", "order_ids": [ids[0]]}),
+            StatusCode::BAD_REQUEST,
+        ),
+        (
+            json!({"budget": "150", "order_ids": []}),
+            StatusCode::BAD_REQUEST,
+        ),
+        (
+            json!({"budget": "150", "order_ids": [ids[0], ids[0]]}),
+            StatusCode::BAD_REQUEST,
+        ),
+        (
+            json!({"budget": "150", "order_ids": [ids[0], ids[2]]}),
+            StatusCode::BAD_REQUEST,
+        ),
+        (
+            json!({"budget": "150", "order_ids": [ids[0], "nope"]}),
+            StatusCode::NOT_FOUND,
+        ),
+    ] {
+        assert_eq!(test::call_service(&app, group(body)).await.status(), status);
+    }
+
+    let resp = test::call_service(
+        &app,
+        group(json!({"budget": "150", "order_ids": [ids[0], ids[1]]})),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::CREATED);
+    let created: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(
+        (created["status"].clone(), created["spent"].clone()),
+        (json!("active"), json!("0"))
+    );
+    assert_eq!(
+        test::call_service(&app, group(json!({"budget": "10", "order_ids": [ids[1]]})))
+            .await
+            .status(),
+        StatusCode::CONFLICT
+    );
+    let req = TestRequest::get()
+        .uri(&format!(
+            "/order-groups/{}",
+            created["id"].as_str().unwrap()
+        ))
+        .to_request();
+    let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body["members"][1]["order"]["price"], "90");
+    let req = TestRequest::get().uri("/order-groups").to_request();
+    let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body.as_array().map(Vec::len), Some(1));
+}
+
+#[actix_web::test]
+async fn orders_accept_notional_and_indicator_conditions() {
+    let app = test::init_service(test_app()).await;
+
+    let req = TestRequest::post()
+        .uri("/orders")
+        .set_json(json!({
+            "pair": "BTC/USDT",
+            "side": "buy",
+            "quantity": "0.5",
+            "condition": {"type": "notional", "threshold": "25000"}
+        }))
+        .to_request();
+    let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body["condition"]["type"], "notional");
+    assert_eq!(body["condition"]["threshold"], "25000");
+    assert_eq!(body["price"], "0");
+
+    let req = TestRequest::post()
+        .uri("/orders")
+        .set_json(json!({
+            "pair": "BTC/USDT",
+            "side": "buy",
+            "quantity": "1",
+            "condition": {
+                "type": "crossover",
+                "fast": {"kind": "ema", "period": 50},
+                "slow": {"kind": "ema", "period": 200},
+                "direction": "above"
+            }
+        }))
+        .to_request();
+    let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body["condition"]["slow"]["period"], 200);
+
+    let req = TestRequest::post()
+        .uri("/orders")
+        .set_json(
+            json!({"pair": "BTC/USDT", "side": "buy", "price": "1", "quantity": "1",
+                         "sustained_for_ms": 0}),
+        )
+        .to_request();
+    assert_eq!(
+        test::call_service(&app, req).await.status(),
+        StatusCode::BAD_REQUEST
+    );
+
+    for bad in [
+        json!({"type": "notional", "threshold": "0"}),
+        json!({"type": "notional", "threshold": "1", "quantity": "-1"}),
+        json!({"type": "level", "indicator": {"kind": "rsi", "period": 14}}),
+        json!({"type": "level", "indicator": {"kind": "rsi", "period": 0}, "below": 30}),
+        json!({"type": "crossover", "fast": {"kind": "sma", "period": 5},
+               "slow": {"kind": "sma", "period": 5}, "direction": "below"}),
+    ] {
+        let req = TestRequest::post()
+            .uri("/orders")
+            .set_json(json!({"pair": "BTC/USDT", "side": "buy", "quantity": "1", "condition": bad}))
+            .to_request();
+        assert_eq!(
+            test::call_service(&app, req).await.status(),
+            StatusCode::BAD_REQUEST
+        );
+    }
+}
+
+#[actix_web::test]
+async fn templates_store_setups_and_instantiate_orders_with_overrides() {
+    let app = test::init_service(test_app()).await;
+
+    let req = TestRequest::post()
+        .uri("/templates")
+        .set_json(json!({
+            "name": "btc dip",
+            "pair": "btcusdt",
+            "side": "buy",
+            "quantity": "0.5",
+            "trigger_price_type": "mark",
+            "tags": ["dip"]
+        }))
+        .to_request();
+    let tpl: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(tpl["pair"], "BTC/USDT");
+    let id = tpl["id"].as_str().unwrap().to_string();
+
+    let req = TestRequest::get().uri("/templates?tag=dip").to_request();
+    let list: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(list.as_array().unwrap().len(), 1);
+
+    // The template has no price, so instantiating without one is rejected.
+    let req = TestRequest::post()
+        .uri(&format!("/templates/{id}/instantiate"))
+        .to_request();
+    assert_eq!(
+        test::call_service(&app, req).await.status(),
+        StatusCode::BAD_REQUEST
+    );
+
+    let req = TestRequest::post()
+        .uri(&format!(
+            "/templates/{id}/instantiate?price=95000&quantity=0.25"
+        ))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::CREATED);
+    let order: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(order["price"], "95000");
+    assert_eq!(order["quantity"], "0.25");
+    assert_eq!(order["trigger_price_type"], "mark");
+
+    let req = TestRequest::delete()
+        .uri(&format!("/templates/{id}"))
+        .to_request();
+    assert_eq!(
+        test::call_service(&app, req).await.status(),
+        StatusCode::NO_CONTENT
+    );
+    let req = TestRequest::post()
+        .uri(&format!("/templates/{id}/instantiate?price=1"))
+        .to_request();
+    assert_eq!(
+        test::call_service(&app, req).await.status(),
+        StatusCode::NOT_FOUND
+    );
+}
+
+#[actix_web::test]
+async fn baskets_are_one_resource_with_a_shared_trigger() {
+    let app = test::init_service(test_app()).await;
+    let basket = json!({
+        "trigger": {"pair": "btc-usdt", "below": "60000"},
+        "legs": [
+            {"pair": "ETH/USDT", "side": "buy", "quantity": "2"},
+            {"pair": "SOL/USDT", "side": "buy", "quantity": "30"}
+        ]
+    });
+    let req = TestRequest::post()
+        .uri("/baskets")
+        .set_json(&basket)
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::CREATED);
+    let created: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(created["trigger"]["pair"], "BTC/USDT");
+    assert_eq!(created["mode"], "atomic");
+    assert_eq!(created["status"], "pending");
+    assert_eq!(created["legs"][1]["status"], "pending");
+    let id = created["id"].as_str().unwrap();
+
+    let req = TestRequest::get()
+        .uri("/baskets?status=pending")
+        .to_request();
+    let listed: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(listed.as_array().unwrap().len(), 1);
+
+    let req = TestRequest::post()
+        .uri(&format!("/baskets/{id}/cancel"))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let req = TestRequest::get()
+        .uri(&format!("/baskets/{id}"))
+        .to_request();
+    let got: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(got["status"], "cancelled");
+
+    for bad in [
+        json!({"trigger": {"pair": "BTC/USDT"}, "legs": basket["legs"]}),
+        json!({"trigger": basket["trigger"], "legs": []}),
+        json!({"trigger": basket["trigger"], "legs": [
+            {"pair": "ETH/USDT", "side": "buy", "quantity": "1"},
+            {"pair": "ETH/USDT", "side": "buy", "quantity": "1"},
+            {"pair": "ETH/USDT", "side": "buy", "quantity": "1"}
+        ]}),
+        json!({"trigger": basket["trigger"], "legs": [{"pair": "ETH/USDT", "side": "buy", "quantity": "0"}]}),
+    ] {
+        let req = TestRequest::post()
+            .uri("/baskets")
+            .set_json(&bad)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST, "{bad}");
+    }
+}
+
+#[actix_web::test]
+async fn admin_reconcile_applies_settled_state_and_reports_conflicts() {
+    let app = test::init_service(test_app()).await;
+
+    let req = TestRequest::post()
+        .uri("/orders")
+        .set_json(json!({"pair": "BTC/USDT", "side": "sell", "price": "1", "quantity": "4"}))
+        .to_request();
+    let order: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    let id = order["id"].as_str().unwrap();
+
+    let req = TestRequest::post()
+        .uri("/admin/orders/reconcile")
+        .insert_header(ADMIN)
+        .set_json(json!({"orders": [
+            {"order_id": id, "external_status": "partially_filled", "external_fill_qty": "1"},
+            {"order_id": "nope", "external_status": "filled", "external_fill_qty": "1"}
+        ]}))
+        .to_request();
+    let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body["corrected"][0]["to"], "partially_filled");
+    assert_eq!(body["conflicts"][0]["reason"], "unknown_order");
+
+    let req = TestRequest::get()
+        .uri(&format!("/orders/{id}"))
+        .to_request();
+    let order: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(order["filled_quantity"], "1");
+}
+
+#[actix_web::test]
+async fn missed_triggers_are_listed_and_only_flagged_orders_released() {
+    let app = test::init_service(test_app()).await;
+    let req = TestRequest::get()
+        .uri("/admin/orders/missed-triggers")
+        .insert_header(ADMIN)
+        .to_request();
+    let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body, json!({"flagged": [], "pending": 0}));
+
+    for (policy, status) in [
+        ("trigger_immediately", StatusCode::NOT_FOUND),
+        ("manual_review", StatusCode::BAD_REQUEST),
+    ] {
+        let req = TestRequest::post()
+            .uri("/admin/orders/nope/release")
+            .insert_header(ADMIN)
+            .set_json(json!({ "policy": policy }))
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), status);
+    }
+}
+
+#[actix_web::test]
+async fn unresolved_executions_resolve_from_the_order_state() {
+    let app = test::init_service(test_app()).await;
+    let req = TestRequest::get()
+        .uri("/admin/executions/unresolved")
+        .insert_header(ADMIN)
+        .to_request();
+    let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body, json!([]));
+
+    let req = TestRequest::post()
+        .uri("/orders")
+        .set_json(json!({"pair": "BTC/USDT", "side": "buy", "price": "100", "quantity": "1"}))
+        .to_request();
+    let order: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    let id = order["id"].as_str().unwrap();
+    for uri in [
+        format!("/admin/executions/{id}/0/resolve"),
+        "/admin/executions/nope/0/resolve".to_string(),
+    ] {
+        let req = TestRequest::post()
+            .uri(&uri)
+            .insert_header(ADMIN)
+            .to_request();
+        assert_eq!(
+            test::call_service(&app, req).await.status(),
+            StatusCode::NOT_FOUND
+        );
+    }
+}
+
+#[actix_web::test]
+async fn admin_reevaluate_dry_runs_active_orders_over_past_ticks() {
+    let cache = OracleCache::default();
+    let app = test::init_service(test_app_with_cache(cache.clone())).await;
+    let mut ids = Vec::new();
+    for (side, price) in [("sell", "105"), ("buy", "90")] {
+        let req = TestRequest::post()
+            .uri("/orders")
+            .set_json(json!({"pair": "BTC/USDT", "side": side, "price": price, "quantity": "1"}))
+            .to_request();
+        let order: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        ids.push(order["id"].as_str().unwrap().to_string());
+    }
+    let now = conditional_orderbook::utils::now_ms();
+    for (i, px) in [dec!(100), dec!(106), dec!(104)].into_iter().enumerate() {
+        cache
+            .set(Tick::new("BTC/USDT", px, now + 10 + i as i64))
+            .await;
+    }
+
+    let req = TestRequest::post()
+        .uri("/admin/reevaluate")
+        .insert_header(ADMIN)
+        .set_json(json!({"pair": "btc-usdt", "from_ms": now, "to_ms": now + 1_000}))
+        .to_request();
+    let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body["source"], "history");
+    assert_eq!(body["ticks"], 3);
+    assert_eq!(body["evaluated"], 2);
+    assert_eq!(body["would_trigger"].as_array().unwrap().len(), 1);
+    assert_eq!(body["would_trigger"][0]["order_id"], ids[0].as_str());
+    assert_eq!(body["would_trigger"][0]["ts_ms"], now + 11);
+    assert_eq!(body["would_trigger"][0]["exec_px"], "106");
+
+    // Captured ticks replace the history; other pairs are ignored.
+    let req = TestRequest::post()
+        .uri("/admin/reevaluate")
+        .insert_header(ADMIN)
+        .set_json(
+            json!({"pair": "BTC/USDT", "from_ms": now, "to_ms": now + 1_000, "ticks": [
+                {"pair": "BTCUSDT", "price": "89", "ts_ms": now + 20},
+                {"pair": "ETH/USDT", "price": "200", "ts_ms": now + 21}
+            ]}),
+        )
+        .to_request();
+    let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body["source"], "supplied");
+    assert_eq!(body["ticks"], 1);
+    assert_eq!(body["would_trigger"][0]["order_id"], ids[1].as_str());
+
+    // Nothing was written.
+    let req = TestRequest::get()
+        .uri(&format!("/orders/{}", ids[0]))
+        .to_request();
+    let order: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(order["status"], "new");
+
+    let req = TestRequest::post()
+        .uri("/admin/reevaluate")
+        .insert_header(ADMIN)
+        .set_json(json!({"pair": "BTC/USDT", "from_ms": 2, "to_ms": 1}))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+}
+
+/// Replica stand-in that lags by a fixed amount and never sees writes.
+#[derive(Clone, Default)]
+struct LaggingReplica(InMemoryOrderRepository);
+
+#[async_trait::async_trait]
+impl OrderRepository for LaggingReplica {
+    async fn create(&self, new: NewOrder) -> Result<Order, String> {
+        self.0.create(new).await
+    }
+    async fn get_by_id(&self, id: &str) -> Result<Order, String> {
+        self.0.get_by_id(id).await
+    }
+    async fn list(&self, q: ListOrdersQuery) -> Result<Vec<Order>, String> {
+        self.0.list(q).await
+    }
+    async fn set_status(&self, id: &str, status: OrderStatus) -> Result<Order, String> {
+        self.0.set_status(id, status).await
+    }
+    async fn record_fill(&self, id: &str, px: rust_decimal::Decimal) -> Result<Order, String> {
+        self.0.record_fill(id, px).await
+    }
+    async fn record_fills(&self, id: &str, fills: &[Fill]) -> Result<Order, String> {
+        self.0.record_fills(id, fills).await
+    }
+    async fn set_fill(
+        &self,
+        id: &str,
+        status: OrderStatus,
+        qty: rust_decimal::Decimal,
+    ) -> Result<Order, String> {
+        self.0.set_fill(id, status, qty).await
+    }
+    async fn cancel(&self, id: &str, reason: &str) -> Result<Order, String> {
+        self.0.cancel(id, reason).await
+    }
+    async fn amend(
+        &self,
+        id: &str,
+        px: rust_decimal::Decimal,
+        qty: rust_decimal::Decimal,
+    ) -> Result<Order, String> {
+        self.0.amend(id, px, qty).await
+    }
+    async fn delete(&self, id: &str) -> Result<(), String> {
+        self.0.delete(id).await
+    }
+    fn replication_lag_ms(&self) -> Option<i64> {
+        Some(250)
+    }
+}
+
+#[actix_web::test]
+async fn reads_go_to_the_replica_with_a_staleness_hint() {
+    let state = AppState::with_replica(
+        InMemoryOrderRepository::default(),
+        LaggingReplica::default(),
+    );
+    let app = test::init_service(test_app_with_state(
+        state,
+        ChangeStream::default(),
+        OpenOrdersView::default(),
+        Journal::default(),
+        OracleCache::default(),
+        InstrumentRegistry::default(),
+    ))
+    .await;
+
+    let req = TestRequest::post()
+        .uri("/orders")
+        .set_json(json!({"pair": "BTC/USDT", "side": "buy", "price": "1", "quantity": "1"}))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::CREATED);
+    assert!(resp.headers().get("X-Read-Source").is_none());
+    let created: serde_json::Value = test::read_body_json(resp).await;
+
+    // The write went to the primary, which the replica has not caught up with.
+    let req = TestRequest::get()
+        .uri(&format!("/orders/{}", created["id"].as_str().unwrap()))
+        .to_request();
+    assert_eq!(
+        test::call_service(&app, req).await.status(),
+        StatusCode::NOT_FOUND
+    );
+
+    let req = TestRequest::get().uri("/orders").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.headers().get("X-Read-Source").unwrap(), "replica");
+    assert_eq!(resp.headers().get("X-Replica-Lag-Ms").unwrap(), "250");
+    let items: serde_json::Value = test::read_body_json(resp).await;
+    assert!(items.as_array().unwrap().is_empty());
+}
+
+#[actix_web::test]
+async fn book_and_open_order_lists_are_served_from_the_view() {
+    let app = test::init_service(test_app()).await;
+
+    let mut ids = Vec::new();
+    for (side, price, qty) in [
+        ("buy", "100", "1"),
+        ("buy", "100", "2"),
+        ("buy", "99", "1"),
+        ("sell", "105", "3"),
+    ] {
+        let req = TestRequest::post()
+            .uri("/orders")
+            .set_json(json!({"pair": "BTC/USDT", "side": side, "price": price, "quantity": qty}))
+            .to_request();
+        let o: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        ids.push(o["id"].as_str().unwrap().to_string());
+    }
+    let req = TestRequest::put()
+        .uri(&format!("/admin/orders/{}/status?force=true", ids[2]))
+        .insert_header(ADMIN)
+        .set_json(json!({"status": "cancelled", "reason": "test"}))
+        .to_request();
+    test::call_service(&app, req).await;
+
+    let req = TestRequest::get().uri("/book/btc-usdt").to_request();
+    let book: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(book["bids"].as_array().unwrap().len(), 1);
+    assert_eq!(book["bids"][0]["quantity"], "3");
+    assert_eq!(book["bids"][0]["orders"], 2);
+    assert_eq!(book["asks"][0]["price"], "105");
+
+    let req = TestRequest::get().uri("/book/BTC/USDT?agg=10").to_request();
+    let book: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(book["bids"][0]["price"], "100");
+    assert_eq!(book["asks"][0]["price"], "110");
+    for agg in ["0", "0.000000001"] {
+        let req = TestRequest::get()
+            .uri(&format!("/book/BTC/USDT?agg={agg}"))
+            .to_request();
+        assert_eq!(
+            test::call_service(&app, req).await.status(),
+            StatusCode::BAD_REQUEST,
+            "{agg}"
+        );
+    }
+
+    let req = TestRequest::get()
+        .uri("/orders?status=new&pair=BTC/USDT")
+        .to_request();
+    let list: Vec<Order> = test::call_and_read_body_json(&app, req).await;
+    let got: Vec<_> = list.into_iter().map(|o| o.id).collect();
+    assert_eq!(got, vec![ids[0].clone(), ids[1].clone(), ids[3].clone()]);
+
+    let req = TestRequest::get().uri("/book/DOGE/USDT").to_request();
+    assert_eq!(
+        test::call_service(&app, req).await.status(),
+        StatusCode::NOT_FOUND
+    );
+}
+
+#[actix_web::test]
+async fn journal_exports_and_imports_upgrading_legacy_records() {
+    let app = test::init_service(test_app()).await;
+    let req = TestRequest::post()
+        .uri("/orders")
+        .set_json(json!({"pair": "BTC/USDT", "side": "buy", "price": "100", "quantity": "1"}))
+        .to_request();
+    test::call_service(&app, req).await;
+
+    let req = TestRequest::get()
+        .uri("/admin/journal/export")
+        .insert_header(ADMIN)
+        .to_request();
+    let capture = String::from_utf8(test::call_and_read_body(&app, req).await.to_vec()).unwrap();
+    let first: serde_json::Value = serde_json::from_str(capture.lines().next().unwrap()).unwrap();
+    assert_eq!(first["type"], "order_created");
+    assert_eq!(first["schema_version"], 1);
+
+    let legacy = json!({
+        "kind": "created",
+        "ts_ms": 5,
+        "order": {"id": "old", "pair": "ETH/USDT", "side": "sell", "price": "2000",
+                  "quantity": "1", "status": "open", "created": 5, "updated": 5}
+    });
+    let req = TestRequest::post()
+        .uri("/admin/journal/import")
+        .insert_header(ADMIN)
+        .set_payload(format!("{capture}\n{legacy}"))
+        .to_request();
+    let report: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(report["entries"], 2);
+    assert_eq!(report["upgraded"], 1);
+
+    let req = TestRequest::get().uri("/orders/old?as_of=10").to_request();
+    let old: Order = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(old.status, OrderStatus::Open);
+
+    let req = TestRequest::post()
+        .uri("/admin/journal/import")
+        .insert_header(ADMIN)
+        .set_payload("not json")
+        .to_request();
+    assert_eq!(
+        test::call_service(&app, req).await.status(),
+        StatusCode::BAD_REQUEST
+    );
+}
+
+#[actix_web::test]
+async fn as_of_queries_rebuild_past_order_and_book_state() {
+    let app = test::init_service(test_app()).await;
+
+    let req = TestRequest::post()
+        .uri("/orders")
+        .set_json(json!({"pair": "BTC/USDT", "side": "buy", "price": "100", "quantity": "1"}))
+        .to_request();
+    let o: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    let id = o["id"].as_str().unwrap().to_string();
+    std::thread::sleep(std::time::Duration::from_millis(5));
+    let before_cancel = conditional_orderbook::utils::now_ms();
+    std::thread::sleep(std::time::Duration::from_millis(5));
+    let req = TestRequest::put()
+        .uri(&format!("/admin/orders/{id}/status?force=true"))
+        .insert_header(ADMIN)
+        .set_json(json!({"status": "cancelled", "reason": "test"}))
+        .to_request();
+    test::call_service(&app, req).await;
+
+    let req = TestRequest::get()
+        .uri(&format!("/orders/{id}?as_of={before_cancel}"))
+        .to_request();
+    let then: Order = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(then.status, OrderStatus::New);
+
+    let req = TestRequest::get()
+        .uri(&format!("/book/BTC/USDT?as_of={before_cancel}"))
+        .to_request();
+    let book: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(book["bids"][0]["price"], "100");
+    let req = TestRequest::get().uri("/book/BTC/USDT").to_request();
+    let book: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert!(book["bids"].as_array().unwrap().is_empty());
+
+    let req = TestRequest::get()
+        .uri(&format!("/orders/{id}?as_of=1"))
+        .to_request();
+    assert_eq!(
+        test::call_service(&app, req).await.status(),
+        StatusCode::NOT_FOUND
+    );
+}
+
+#[actix_web::test]
+async fn api_keys_authenticate_until_rotated_and_secrets_stay_hidden() {
+    let app = test::init_service(test_app()).await;
+
+    let req = TestRequest::post()
+        .uri("/admin/accounts")
+        .insert_header(ADMIN)
+        .set_json(json!({"name": "desk"}))
+        .to_request();
+    let created: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    let account_id = created["account"]["id"].as_str().unwrap().to_string();
+    let key_id = created["key"]["id"].as_str().unwrap().to_string();
+    let token = created["api_key"].as_str().unwrap().to_string();
+
+    let req = TestRequest::get()
+        .uri("/account")
+        .insert_header(("Authorization", format!("Bearer {token}")))
+        .to_request();
+    let me: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(me["id"], account_id);
+    let req = TestRequest::get().uri("/account").to_request();
+    assert_eq!(
+        test::call_service(&app, req).await.status(),
+        StatusCode::UNAUTHORIZED
+    );
+
+    let req = TestRequest::post()
+        .uri(&format!(
+            "/admin/accounts/{account_id}/keys/{key_id}/rotate"
+        ))
+        .insert_header(ADMIN)
+        .to_request();
+    let rotated: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    let req = TestRequest::get()
+        .uri("/account")
+        .insert_header(("X-Api-Key", token.as_str()))
+        .to_request();
+    assert_eq!(
+        test::call_service(&app, req).await.status(),
+        StatusCode::UNAUTHORIZED
+    );
+    let req = TestRequest::get()
+        .uri("/account")
+        .insert_header(("X-Api-Key", rotated["api_key"].as_str().unwrap()))
+        .to_request();
+    assert!(test::call_service(&app, req).await.status().is_success());
+
+    let req = TestRequest::get()
+        .uri(&format!("/admin/accounts/{account_id}"))
+        .insert_header(ADMIN)
+        .to_request();
+    let body = test::call_and_read_body(&app, req).await;
+    let text = std::str::from_utf8(&body).unwrap();
+    assert!(!text.contains(&token[token.len() - 16..]));
+    assert!(!text.contains("pbkdf2"));
+
+    let req = TestRequest::put()
+        .uri(&format!("/admin/accounts/{account_id}/webhook"))
+        .insert_header(ADMIN)
+        .set_json(json!({"url": "https://example.test/hook"}))
+        .to_request();
+    let hook: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert!(hook["signing_secret"]
+        .as_str()
+        .unwrap()
+        .starts_with("whsec_"));
+    let req = TestRequest::post()
+        .uri("/admin/secrets/reseal")
+        .insert_header(ADMIN)
+        .to_request();
+    let report: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(report["resealed"], 0);
+}
+
+#[actix_web::test]
+async fn purging_account_data_runs_as_a_job_and_removes_its_orders() {
+    let app = test::init_service(test_app()).await;
+    let req = TestRequest::post()
+        .uri("/admin/accounts")
+        .insert_header(ADMIN)
+        .set_json(json!({"name": "leaver"}))
+        .to_request();
+    let created: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    let account_id = created["account"]["id"].as_str().unwrap().to_string();
+    let req = TestRequest::post()
+        .uri("/orders")
+        .insert_header(("X-Api-Key", created["api_key"].as_str().unwrap()))
+        .set_json(json!({"pair": "BTC/USDT", "side": "buy", "price": "100", "quantity": "1"}))
+        .to_request();
+    let order: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(order["account_id"], account_id);
+
+    let req = TestRequest::delete()
+        .uri(&format!("/admin/accounts/{account_id}/data?mode=purge"))
+        .insert_header(ADMIN)
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::ACCEPTED);
+    let job: serde_json::Value = test::read_body_json(resp).await;
+    let job_uri = format!(
+        "/admin/accounts/{account_id}/data/jobs/{}",
+        job["id"].as_str().unwrap()
+    );
+    let mut job = job;
+    for _ in 0..100 {
+        if job["status"] != "running" {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        let req = TestRequest::get()
+            .uri(&job_uri)
+            .insert_header(ADMIN)
+            .to_request();
+        job = test::call_and_read_body_json(&app, req).await;
+    }
+    assert_eq!(job["status"], "completed");
+    assert_eq!(
+        (job["orders_cancelled"].clone(), job["orders"].clone()),
+        (json!(1), json!(1))
+    );
+
+    let req = TestRequest::get()
+        .uri(&format!("/orders/{}", order["id"].as_str().unwrap()))
+        .to_request();
+    assert_eq!(
+        test::call_service(&app, req).await.status(),
+        StatusCode::NOT_FOUND
+    );
+    for uri in [
+        "/admin/accounts/nobody/data".to_string(),
+        format!(
+            "/admin/accounts/nobody/data/jobs/{}",
+            job["id"].as_str().unwrap()
+        ),
+    ] {
+        let req = if uri.ends_with("/data") {
+            TestRequest::delete()
+                .uri(&uri)
+                .insert_header(ADMIN)
+                .to_request()
+        } else {
+            TestRequest::get()
+                .uri(&uri)
+                .insert_header(ADMIN)
+                .to_request()
+        };
+        assert_eq!(
+            test::call_service(&app, req).await.status(),
+            StatusCode::NOT_FOUND
+        );
+    }
+}
+
+#[actix_web::test]
+async fn replacing_counts_against_the_cancel_ratio() {
+    let app = test::init_service(test_app()).await;
+    let req = TestRequest::post()
+        .uri("/admin/accounts")
+        .insert_header(ADMIN)
+        .set_json(json!({"name": "quoter"}))
+        .to_request();
+    let created: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    let key = (
+        "X-Api-Key",
+        created["api_key"].as_str().unwrap().to_string(),
+    );
+    let req = TestRequest::post()
+        .uri("/orders")
+        .insert_header(key.clone())
+        .set_json(json!({"pair": "BTC/USDT", "side": "buy", "price": "100", "quantity": "1"}))
+        .to_request();
+    let order: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    let mut id = order["id"].as_str().unwrap().to_string();
+
+    // Without fills the account gets two owner cancels; replaces are cancels.
+    for price in ["99", "98"] {
+        let req = TestRequest::post()
+            .uri(&format!("/orders/{id}/replace"))
+            .insert_header(key.clone())
+            .set_json(json!({ "price": price }))
+            .to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        id = body["order"]["id"].as_str().unwrap().to_string();
+    }
+    let req = TestRequest::post()
+        .uri(&format!("/orders/{id}/replace"))
+        .insert_header(key.clone())
+        .set_json(json!({"price": "97"}))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["code"], "cancel_rate");
+    let req = TestRequest::delete()
+        .uri(&format!("/orders/{id}"))
+        .insert_header(key)
+        .to_request();
+    assert_eq!(
+        test::call_service(&app, req).await.status(),
+        StatusCode::TOO_MANY_REQUESTS
+    );
+}
+
+#[actix_web::test]
+async fn keyed_orders_are_amended_and_cancelled_by_their_account_only() {
+    let app = test::init_service(test_app()).await;
+    let req = TestRequest::post()
+        .uri("/admin/accounts")
+        .insert_header(ADMIN)
+        .set_json(json!({"name": "bot"}))
+        .to_request();
+    let created: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    let token = created["api_key"].as_str().unwrap().to_string();
+
+    let req = TestRequest::get().uri("/ws/orders").to_request();
+    assert_eq!(
+        test::call_service(&app, req).await.status(),
+        StatusCode::UNAUTHORIZED
+    );
+
+    let req = TestRequest::post()
+        .uri("/orders")
+        .insert_header(("X-Api-Key", token.as_str()))
+        .set_json(json!({"pair": "BTC/USDT", "side": "buy", "price": "100", "quantity": "2"}))
+        .to_request();
+    let order: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(order["account_id"], created["account"]["id"]);
+    let uri = format!("/orders/{}", order["id"].as_str().unwrap());
+
+    let req = TestRequest::patch()
+        .uri(&uri)
+        .set_json(json!({"price": "101"}))
+        .to_request();
+    assert_eq!(
+        test::call_service(&app, req).await.status(),
+        StatusCode::NOT_FOUND
+    );
+    let req = TestRequest::patch()
+        .uri(&uri)
+        .insert_header(("X-Api-Key", token.as_str()))
+        .set_json(json!({}))
+        .to_request();
+    assert_eq!(
+        test::call_service(&app, req).await.status(),
+        StatusCode::BAD_REQUEST
+    );
+    let req = TestRequest::patch()
+        .uri(&uri)
+        .insert_header(("X-Api-Key", token.as_str()))
+        .set_json(json!({"price": "101", "quantity": "3"}))
+        .to_request();
+    let amended: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(amended["price"], "101");
+    assert_eq!(amended["quantity"], "3");
+
+    let req = TestRequest::delete().uri(&uri).to_request();
+    assert_eq!(
+        test::call_service(&app, req).await.status(),
+        StatusCode::NOT_FOUND
+    );
+    let req = TestRequest::post()
+        .uri(&format!("{uri}/cancel"))
+        .insert_header(("X-Api-Key", token.as_str()))
+        .to_request();
+    let cancelled: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(cancelled["status"], "cancelled");
+    assert_eq!(cancelled["cancel_reason"], "owner_request");
+}
+
+#[actix_web::test]
+async fn cancel_replace_swaps_the_order_for_a_linked_copy() {
+    let app = test::init_service(test_app()).await;
+    let req = TestRequest::post()
+        .uri("/orders")
+        .set_json(json!({"pair": "BTC/USDT", "side": "buy", "price": "100", "quantity": "2", "tags": ["mm"]}))
+        .to_request();
+    let order: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    let id = order["id"].as_str().unwrap().to_string();
+    let replace = |id: &str, body: serde_json::Value| {
+        TestRequest::post()
+            .uri(&format!("/orders/{id}/replace"))
+            .set_json(body)
+            .to_request()
+    };
+
+    let resp = test::call_service(&app, replace(&id, json!({"price": "99"}))).await;
+    assert_eq!(resp.status(), StatusCode::CREATED);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    let (old, new) = (&body["replaced"], &body["order"]);
+    assert_eq!(old["status"], "cancelled");
+    assert_eq!(old["cancel_reason"], "replaced");
+    assert_eq!(old["replaced_by"], new["id"]);
+    assert_eq!(new["replaces"], id.as_str());
+    assert_eq!(
+        (
+            new["price"].clone(),
+            new["quantity"].clone(),
+            new["tags"].clone()
+        ),
+        (json!("99"), json!("2"), json!(["mm"]))
+    );
+    let req = TestRequest::get()
+        .uri(&format!("/orders/{id}"))
+        .to_request();
+    let stored: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(stored["replaced_by"], new["id"]);
+
+    let resp = test::call_service(&app, replace(&id, json!({"price": "98"}))).await;
+    assert_eq!(resp.status(), StatusCode::CONFLICT);
+    let new_id = new["id"].as_str().unwrap();
+    let resp = test::call_service(&app, replace(new_id, json!({"quantity": "0"}))).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    let resp = test::call_service(&app, replace("missing", json!({}))).await;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+}
+
+#[actix_web::test]
+async fn accounts_get_monthly_statements_as_json_or_csv() {
+    let app = test::init_service(test_app()).await;
+    let req = TestRequest::post()
+        .uri("/admin/accounts")
+        .insert_header(ADMIN)
+        .set_json(json!({"name": "bot"}))
+        .to_request();
+    let created: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    let token = created["api_key"].as_str().unwrap().to_string();
+    let account_id = created["account"]["id"].as_str().unwrap().to_string();
+    let req = TestRequest::post()
+        .uri("/orders")
+        .insert_header(("X-Api-Key", token.as_str()))
+        .set_json(json!({"pair": "BTC/USDT", "side": "buy", "price": "100", "quantity": "2"}))
+        .to_request();
+    let order: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    let get = |query: &str| {
+        TestRequest::get()
+            .uri(&format!("/accounts/{account_id}/statements{query}"))
+            .insert_header(("X-Api-Key", token.as_str()))
+            .to_request()
+    };
+
+    let s: serde_json::Value = test::call_and_read_body_json(&app, get("")).await;
+    assert_eq!(s["account_id"], account_id.as_str());
+    assert_eq!(s["orders_placed"], 1);
+    assert_eq!(s["fills"], json!([]));
+    let resp = test::call_service(&app, get("?period=2024-06&format=csv")).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let csv = test::read_body(resp).await;
+    assert_eq!(csv, "ts_ms,event,order_id,pair,side,price,quantity\n");
+    let period = s["period"].as_str().unwrap();
+    let csv = test::read_body(
+        test::call_service(&app, get(&format!("?period={period}&format=csv"))).await,
+    )
+    .await;
+    let csv = std::str::from_utf8(&csv).unwrap();
+    assert!(csv.contains(&format!(
+        ",placed,{},BTC/USDT,buy,100,2",
+        order["id"].as_str().unwrap()
+    )));
+
+    for query in ["?period=2024-13", "?format=pdf"] {
+        let resp = test::call_service(&app, get(query)).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+    let req = TestRequest::get()
+        .uri("/accounts/someone-else/statements")
+        .insert_header(("X-Api-Key", token.as_str()))
+        .to_request();
+    assert_eq!(
+        test::call_service(&app, req).await.status(),
+        StatusCode::NOT_FOUND
+    );
+}
+
+#[actix_web::test]
+async fn accounts_see_their_usage_and_are_held_to_their_limits() {
+    let app = test::init_service(test_app()).await;
+    let mut tokens = Vec::new();
+    for name in ["bot", "other"] {
+        let req = TestRequest::post()
+            .uri("/admin/accounts")
+            .insert_header(ADMIN)
+            .set_json(json!({"name": name}))
+            .to_request();
+        let created: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        tokens.push((
+            created["account"]["id"].as_str().unwrap().to_string(),
+            created["api_key"].as_str().unwrap().to_string(),
+        ));
+    }
+    let (id, token) = &tokens[0];
+    let place = |pair: &str, price: &str| {
+        TestRequest::post()
+            .uri("/orders")
+            .insert_header(("X-Api-Key", token.as_str()))
+            .set_json(json!({"pair": pair, "side": "buy", "price": price, "quantity": "2"}))
+            .to_request()
+    };
+    for (pair, price) in [
+        ("BTC/USDT", "100"),
+        ("BTC/USDT", "50"),
+        ("ETH/USDT", "10"),
+        ("ETH/USDT", "10"),
+        ("SOL/USDT", "1"),
+    ] {
+        assert_eq!(
+            test::call_service(&app, place(pair, price)).await.status(),
+            StatusCode::CREATED
+        );
+    }
+    // The test app allows five open orders per account.
+    let resp = test::call_service(&app, place("BTC/USDT", "1")).await;
+    assert_eq!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["code"], "account_limit");
+
+    let uri = format!("/accounts/{id}/usage");
+    let req = TestRequest::get()
+        .uri(&uri)
+        .insert_header(("X-Api-Key", token.as_str()))
+        .to_request();
+    let usage: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(usage["orders_last_minute"], 5);
+    assert_eq!(usage["open_orders"], 5);
+    assert_eq!(usage["open_notional"], "342");
+    assert_eq!(usage["pairs"][0]["pair"], "BTC/USDT");
+    assert_eq!(usage["pairs"][0]["open_notional"], "300");
+    assert_eq!(usage["pairs"][1]["open_orders"], 2);
+    assert_eq!(usage["headroom"]["open_orders"], 0);
+    assert_eq!(usage["headroom"]["orders_per_min"], 5);
+    assert_eq!(usage["headroom"]["open_notional"], "999658");
+
+    let req = TestRequest::get()
+        .uri(&uri)
+        .insert_header(("X-Api-Key", tokens[1].1.as_str()))
+        .to_request();
+    assert_eq!(
+        test::call_service(&app, req).await.status(),
+        StatusCode::NOT_FOUND
+    );
+}
+
+#[actix_web::test]
+async fn operators_intervene_on_orders_with_a_reason_and_an_audit_trail() {
+    let app = test::init_service(test_app()).await;
+    let req = TestRequest::post()
+        .uri("/orders")
+        .set_json(json!({"pair": "BTC/USDT", "side": "buy", "price": "100", "quantity": "1"}))
+        .to_request();
+    let order: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    let id = order["id"].as_str().unwrap();
+    let admin = |action: &str, body: serde_json::Value| {
+        TestRequest::post()
+            .uri(&format!("/admin/orders/{id}/{action}"))
+            .insert_header(ADMIN)
+            .set_json(body)
+            .to_request()
+    };
+
+    let resp = test::call_service(&app, admin("suspend", json!({"reason": " "}))).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    let req = admin(
+        "suspend",
+        json!({"reason": "stuck behind a bad tick", "operator": "alice"}),
+    );
+    let done: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(done["order"]["status"], "suspended");
+    assert_eq!(done["audit"]["from"], "new");
+    assert_eq!(done["audit"]["operator"], "alice");
+
+    let resp = test::call_service(
+        &app,
+        admin("transition", json!({"status": "filled", "reason": "x"})),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    let req = admin(
+        "transition",
+        json!({"status": "open", "reason": "feed fixed"}),
+    );
+    let done: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(done["order"]["status"], "open");
+    assert_eq!(done["audit"]["action"], "transition");
+
+    let req = admin("cancel", json!({"reason": "client asked support"}));
+    let done: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(done["order"]["status"], "cancelled");
+    assert_eq!(done["order"]["cancel_reason"], "admin_intervention");
+    let resp = test::call_service(&app, admin("suspend", json!({"reason": "again"}))).await;
+    assert_eq!(resp.status(), StatusCode::CONFLICT);
+
+    // A raw status write skips the lifecycle, so it must be forced.
+    let force = |query: &str| {
+        TestRequest::put()
+            .uri(&format!("/admin/orders/{id}/status{query}"))
+            .insert_header(ADMIN)
+            .set_json(json!({"status": "open", "reason": "cancelled in error"}))
+            .to_request()
+    };
+    let resp = test::call_service(&app, force("")).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    let done: serde_json::Value = test::call_and_read_body_json(&app, force("?force=true")).await;
+    assert_eq!(done["order"]["status"], "open");
+    assert_eq!(done["audit"]["from"], "cancelled");
+
+    let req = TestRequest::get()
+        .uri(&format!("/admin/audit?order_id={id}"))
+        .insert_header(ADMIN)
+        .to_request();
+    let trail: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    let actions: Vec<_> = trail
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|e| e["action"].as_str().unwrap())
+        .collect();
+    assert_eq!(actions, vec!["force", "cancel", "transition", "suspend"]);
+    assert_eq!(trail[1]["reason"], "client asked support");
+}
+
+#[actix_web::test]
+async fn admin_routes_need_the_admin_token() {
+    use base64::{engine::general_purpose::STANDARD, Engine};
+
+    let app = test::init_service(test_app()).await;
+    let create = || {
+        TestRequest::post()
+            .uri("/admin/accounts")
+            .set_json(json!({"name": "desk"}))
+    };
+    for req in [
+        create(),
+        create().insert_header((ADMIN_TOKEN_HEADER, "guess")),
+        create().insert_header(("Authorization", "Bearer test-admin-token")),
+    ] {
+        let resp = test::call_service(&app, req.to_request()).await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+        assert!(resp.headers().contains_key("WWW-Authenticate"));
+    }
+    let req = create().insert_header(ADMIN).to_request();
+    assert_eq!(
+        test::call_service(&app, req).await.status(),
+        StatusCode::CREATED
+    );
+    // A browser on the admin UI sends the token as the Basic password.
+    let basic = format!("Basic {}", STANDARD.encode(format!("ops:{}", ADMIN.1)));
+    let req = TestRequest::get()
+        .uri("/admin/ui")
+        .insert_header(("Authorization", basic))
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::OK);
+
+    // Without an `AdminAuth`, or with no token, the scope is closed.
+    let closed = test::init_service(App::new().configure(routes::config)).await;
+    let req = TestRequest::get()
+        .uri("/admin/stats")
+        .insert_header(ADMIN)
+        .to_request();
+    assert_eq!(
+        test::call_service(&closed, req).await.status(),
+        StatusCode::UNAUTHORIZED
+    );
+    let closed = test::init_service(
+        App::new()
+            .app_data(web::Data::new(AdminAuth::default()))
+            .configure(routes::config),
+    )
+    .await;
+    let req = TestRequest::get()
+        .uri("/admin/stats")
+        .insert_header(ADMIN)
+        .to_request();
+    assert_eq!(
+        test::call_service(&closed, req).await.status(),
+        StatusCode::UNAUTHORIZED
+    );
+}
+
+#[actix_web::test]
+async fn public_mode_opens_market_data_with_a_rate_limit_and_guards_orders() {
+    let app = test::init_service(test_app().app_data(web::Data::new(PublicMode {
+        enabled: true,
+        requests_per_min: 2,
+        ..PublicMode::default()
+    })))
+    .await;
+    let peer = "203.0.113.7:4000".parse().unwrap();
+    let read = |uri: &str| TestRequest::get().uri(uri).peer_addr(peer).to_request();
+    for uri in ["/markets", "/book/BTC/USDT"] {
+        assert_eq!(
+            test::call_service(&app, read(uri)).await.status(),
+            StatusCode::OK
+        );
+    }
+    let resp = test::call_service(&app, read("/markets")).await;
+    assert_eq!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert!(resp.headers().contains_key("Retry-After"));
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["code"], "rate_limited");
+    // A key the service does not know is metered like none.
+    let req = TestRequest::get()
+        .uri("/markets")
+        .peer_addr(peer)
+        .insert_header(("X-Api-Key", "made-up"))
+        .to_request();
+    assert_eq!(
+        test::call_service(&app, req).await.status(),
+        StatusCode::TOO_MANY_REQUESTS
+    );
+    // Health checks are not metered.
+    assert_eq!(
+        test::call_service(&app, read("/health")).await.status(),
+        StatusCode::OK
+    );
+
+    let order = json!({"pair": "BTC/USDT", "side": "buy", "price": "100", "quantity": "1"});
+    let req = TestRequest::post()
+        .uri("/orders")
+        .set_json(&order)
+        .to_request();
+    assert_eq!(
+        test::call_service(&app, req).await.status(),
+        StatusCode::UNAUTHORIZED
+    );
+    let req = TestRequest::get().uri("/orders").to_request();
+    assert_eq!(
+        test::call_service(&app, req).await.status(),
+        StatusCode::UNAUTHORIZED
+    );
+    let req = TestRequest::post()
+        .uri("/admin/accounts")
+        .insert_header(ADMIN)
+        .set_json(json!({"name": "demo"}))
+        .to_request();
+    let created: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    let req = TestRequest::post()
+        .uri("/orders")
+        .insert_header(("X-Api-Key", created["api_key"].as_str().unwrap()))
+        .set_json(&order)
+        .to_request();
+    assert_eq!(
+        test::call_service(&app, req).await.status(),
+        StatusCode::CREATED
+    );
+    let req = TestRequest::get()
+        .uri("/markets")
+        .peer_addr(peer)
+        .insert_header(("X-Api-Key", created["api_key"].as_str().unwrap()))
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::OK);
+}
+
+#[actix_web::test]
+async fn oversized_deeply_nested_and_large_batch_payloads_are_rejected() {
+    let app = test::init_service(test_app()).await;
+
+    let big = "x".repeat(Limits::default().max_body_bytes + 1);
+    let req = TestRequest::post()
+        .uri("/orders")
+        .set_json(json!({"pair": big, "side": "buy", "quantity": "1"}))
+        .to_request();
+    assert_eq!(
+        test::call_service(&app, req).await.status(),
+        StatusCode::PAYLOAD_TOO_LARGE
+    );
+
+    let nested = format!("{}{}", "[".repeat(64), "]".repeat(64));
+    let req = TestRequest::post()
+        .uri("/orders")
+        .insert_header(("content-type", "application/json"))
+        .set_payload(format!(
+            r#"{{"pair": "BTC/USDT", "side": "buy", "quantity": "1", "x": {nested}}}"#
+        ))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert!(body["error"].as_str().unwrap().contains("nested"));
+
+    let entry = json!({"order_id": "x", "external_status": "open", "external_fill_qty": "0"});
+    let req = TestRequest::post()
+        .uri("/admin/orders/reconcile")
+        .insert_header(ADMIN)
+        .set_json(json!({"orders": [entry, entry, entry]}))
+        .to_request();
+    assert_eq!(
+        test::call_service(&app, req).await.status(),
+        StatusCode::BAD_REQUEST
+    );
+
+    let req = TestRequest::post()
+        .uri("/orders")
+        .set_json(json!({"pair": "BTC/USDT", "side": "buy", "price": "1", "quantity": "1"}))
+        .to_request();
+    assert_eq!(
+        test::call_service(&app, req).await.status(),
+        StatusCode::CREATED
+    );
+}
+
+#[actix_web::test]
+async fn admin_ui_serves_dashboard_assets_and_event_stream() {
+    let app = test::init_service(test_app()).await;
+
+    let req = TestRequest::get()
+        .uri("/admin/ui")
+        .insert_header(ADMIN)
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert!(resp
+        .headers()
+        .get("content-type")
+        .unwrap()
+        .to_str()
+        .unwrap()
+        .starts_with("text/html"));
+    let html = test::read_body(resp).await;
+    assert!(std::str::from_utf8(&html)
+        .unwrap()
+        .contains("/admin/ui/app.js"));
+
+    let req = TestRequest::get()
+        .uri("/admin/ui/app.js")
+        .insert_header(ADMIN)
+        .to_request();
+    let js = test::call_and_read_body(&app, req).await;
+    assert!(std::str::from_utf8(&js).unwrap().contains("EventSource"));
+
+    let req = TestRequest::get()
+        .uri("/admin/ui/events")
+        .insert_header(ADMIN)
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(
+        resp.headers().get("content-type").unwrap(),
+        "text/event-stream"
+    );
+}
+
+#[actix_web::test]
+async fn stats_count_created_and_filled_orders_per_pair() {
+    let app = test::init_service(test_app()).await;
+
+    let mut ids = Vec::new();
+    for pair in ["BTC/USDT", "BTC/USDT", "ETH/USDT"] {
+        let req = TestRequest::post()
+            .uri("/orders")
+            .set_json(json!({"pair": pair, "side": "buy", "price": "1", "quantity": "2"}))
+            .to_request();
+        let o: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        ids.push(o["id"].as_str().unwrap().to_string());
+    }
+    for (id, status) in [(&ids[0], "filled"), (&ids[1], "cancelled")] {
+        let req = TestRequest::put()
+            .uri(&format!("/admin/orders/{id}/status?force=true"))
+            .insert_header(ADMIN)
+            .set_json(json!({ "status": status, "reason": "test" }))
+            .to_request();
+        test::call_service(&app, req).await;
+    }
+
+    let req = TestRequest::get().uri("/stats?pair=btc-usdt").to_request();
+    let s: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(s["pair"], "BTC/USDT");
+    assert_eq!(s["1h"]["orders_created"], 2);
+    assert_eq!(s["1h"]["orders_filled"], 1);
+    assert_eq!(s["24h"]["orders_cancelled"], 1);
+    assert_eq!(s["24h"]["fill_rate"], "0.5");
+    assert_eq!(s["24h"]["buy_volume"], "2");
+
+    let req = TestRequest::get().uri("/stats").to_request();
+    let s: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(s["24h"]["orders_created"], 3);
+
+    // Status overrides are not engine fills, so they carry no latency.
+    let req = TestRequest::get()
+        .uri("/analytics/time-to-trigger?pair=BTC/USDT")
+        .to_request();
+    let d: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(d, json!([]));
+    let req = TestRequest::get()
+        .uri("/analytics/fill-latency?pair=btc-usdt")
+        .to_request();
+    let d: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(d, json!([]));
+}
+
+#[actix_web::test]
+async fn callback_urls_need_an_api_key_and_a_valid_template() {
+    let app = test::init_service(test_app()).await;
+    let req = TestRequest::post()
+        .uri("/admin/accounts")
+        .insert_header(ADMIN)
+        .set_json(json!({"name": "desk"}))
+        .to_request();
+    let created: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    let token = created["api_key"].as_str().unwrap().to_string();
+    let order = |template: serde_json::Value| {
+        json!({
+            "pair": "BTC/USDT", "side": "buy", "price": "100", "quantity": "1",
+            "callback_url": "http://hooks.test/fills", "callback_template": template,
+        })
+    };
+
+    let req = TestRequest::post()
+        .uri("/orders")
+        .set_json(order(json!({"id": "{{order_id}}"})))
+        .to_request();
+    assert_eq!(
+        test::call_service(&app, req).await.status(),
+        StatusCode::BAD_REQUEST
+    );
+    let req = TestRequest::post()
+        .uri("/orders")
+        .insert_header(("X-Api-Key", token.as_str()))
+        .set_json(order(json!({"id": "{{order}}"})))
+        .to_request();
+    let res = test::call_service(&app, req).await;
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    let err: serde_json::Value = test::read_body_json(res).await;
+    assert!(
+        err["error"].as_str().unwrap().contains("\"order\""),
+        "{err}"
+    );
+
+    let req = TestRequest::post()
+        .uri("/orders")
+        .insert_header(("X-Api-Key", token.as_str()))
+        .set_json(order(json!({"id": "{{order_id}}"})))
+        .to_request();
+    let placed: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(placed["callback_url"], "http://hooks.test/fills");
+    let uri = format!("/orders/{}/callbacks", placed["id"].as_str().unwrap());
+    let req = TestRequest::get()
+        .uri(&uri)
+        .insert_header(("X-Api-Key", token.as_str()))
+        .to_request();
+    let log: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(log, json!([]));
+    let req = TestRequest::get().uri(&uri).to_request();
+    assert_eq!(
+        test::call_service(&app, req).await.status(),
+        StatusCode::NOT_FOUND
+    );
+}
@@ -0,0 +1,283 @@
+use std::collections::HashMap;
+use std::net::{SocketAddr, TcpListener};
+use std::time::Duration;
+
+use actix_web::{middleware::from_fn, web, App, HttpServer};
+use serde_json::Value;
+
+use conditional_orderbook::{
+    accounts::{purge::AccountPurger, secrets::SecretBox, AccountStore},
+    client::Client,
+    engine::{
+        callbacks::{start_callbacks, OrderCallbacks},
+        deadletter::DeadLetters,
+        delist::PairDelister,
+        downtime::MissedTriggers,
+        groups::{start_group_budgets, OrderGroups},
+        halts::HaltRegistry,
+        ledger::ExecutionLedger,
+        rename::PairRenamer,
+        start_matchers,
+        timers::{start_order_timers, OrderTimers},
+        Controls, EngineConfig,
+    },
+    flags::FeatureFlags,
+    instruments::InstrumentRegistry,
+    limits::{admin::AdminAuth, deadline, guard_body, public::public_gate, Limits},
+    metrics::{placement::PlacementTimings, Metrics},
+    oracle_service::{OracleCache, OracleWsClient},
+    repositories::{
+        audit::AuditLog,
+        baskets::BasketStore,
+        changes::{ChangeStream, Observed},
+        in_memory::InMemoryOrderRepository,
+        journal::Journal,
+        open_orders::OpenOrdersView,
+        search::OrderSearch,
+        stats::{FillQuality, TradingStats, TriggerLatency},
+        tape::TradeTape,
+        templates::TemplateStore,
+    },
+    routes,
+    state::AppState,
+};
+use mock_oracle::scenario::Scenario;
+
+/// Mock oracle ticks this often; the matchers evaluate more often still.
+pub const ORACLE_INTERVAL: Duration = Duration::from_millis(50);
+const ENGINE_TICK: Duration = Duration::from_millis(20);
+const ADMIN_TOKEN: &str = "e2e-admin-token";
+
+/// The mock oracle playing a scenario and an orderbook fed by it over its
+/// websocket, both in this process on loopback ports, with in-memory
+/// storage. Requests go over real HTTP.
+pub struct Stack {
+    pub addr: SocketAddr,
+    pub cache: OracleCache,
+}
+
+impl Stack {
+    /// Starts both services; the scenario's pairs are the only ones quoted
+    /// and matched.
+    pub async fn start(prices: &[(&str, &[f64])]) -> Self {
+        let pairs: Vec<String> = prices.iter().map(|(p, _)| p.to_string()).collect();
+        let scenario = Scenario::new(prices.iter().map(|(p, v)| (p.to_string(), v.to_vec())));
+        let oracle_listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let oracle_addr = oracle_listener.local_addr().unwrap();
+        let oracle = mock_oracle::serve(
+            mock_oracle::Config {
+                pairs: pairs.clone(),
+                interval: ORACLE_INTERVAL,
+                bands: HashMap::new(),
+                push_token: None,
+                auth_token: None,
+                gap_every: None,
+                scenario,
+            },
+            oracle_listener,
+        )
+        .unwrap();
+        actix_web::rt::spawn(oracle);
+
+        let instruments = InstrumentRegistry::default();
+        let cache = OracleCache::default();
+        OracleWsClient {
+            endpoint: format!("ws://{oracle_addr}/ws"),
+            instruments: instruments.clone(),
+            reconnect_backoff: Duration::from_millis(50),
+            ..OracleWsClient::default()
+        }
+        .spawn(cache.clone());
+
+        let store = InMemoryOrderRepository::default();
+        let changes = ChangeStream::default();
+        let view = OpenOrdersView::default();
+        view.follow(&changes);
+        let search = OrderSearch::default();
+        search.follow(&changes);
+        let journal = Journal::default();
+        journal.attach(&changes);
+        let timers = OrderTimers::default();
+        timers.follow(&changes);
+        let repo = Observed::new(store, changes.clone());
+        start_order_timers(timers, repo.clone());
+        let groups = OrderGroups::default();
+        groups.follow(&changes);
+        start_group_budgets(groups.clone(), repo.clone());
+        let callbacks = OrderCallbacks::default();
+        callbacks.follow(&changes);
+        let tape = TradeTape::default();
+        tape.follow(&changes);
+        let flags = FeatureFlags::default();
+        let ctl = Controls {
+            events: changes.clone(),
+            flags: flags.clone(),
+            ..Controls::default()
+        };
+        start_matchers(
+            pairs,
+            repo.clone(),
+            cache.clone(),
+            instruments.clone(),
+            ctl.clone(),
+            EngineConfig {
+                tick_every: ENGINE_TICK,
+                ..EngineConfig::default()
+            },
+        );
+
+        let state = AppState::new(repo);
+        let trading_stats = TradingStats::default();
+        trading_stats.attach(&changes);
+        let latency = TriggerLatency::default();
+        latency.attach(&changes);
+        let fill_quality = FillQuality::default();
+        fill_quality.follow(&changes);
+        let renamer = PairRenamer::new(
+            instruments.clone(),
+            state.orders.clone(),
+            journal.clone(),
+            cache.clone(),
+            HaltRegistry::default(),
+            BasketStore::default(),
+            changes.clone(),
+        );
+        let delister = PairDelister::new(
+            instruments.clone(),
+            state.orders.clone(),
+            cache.clone(),
+            ctl.halts.clone(),
+            ctl.matchers.clone(),
+            changes.clone(),
+        );
+        let purger = AccountPurger::new(
+            state.orders.clone(),
+            journal.clone(),
+            AuditLog::default(),
+            BasketStore::default(),
+        );
+        let (app_cache, app_instruments) = (cache.clone(), instruments);
+        let accounts = web::Data::new(AccountStore::new(SecretBox::ephemeral()));
+        start_callbacks(callbacks.clone(), accounts.get_ref().clone());
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = HttpServer::new(move || {
+            App::new()
+                .wrap(from_fn(deadline))
+                .wrap(from_fn(guard_body))
+                .wrap(from_fn(public_gate))
+                .app_data(state.clone())
+                .app_data(web::Data::new(app_cache.clone()))
+                .app_data(web::Data::new(Metrics::default()))
+                .app_data(web::Data::new(PlacementTimings::default()))
+                .app_data(web::Data::new(app_instruments.clone()))
+                .app_data(web::Data::new(HaltRegistry::default()))
+                .app_data(web::Data::new(flags.clone()))
+                .app_data(web::Data::new(DeadLetters::default()))
+                .app_data(web::Data::new(MissedTriggers::default()))
+                .app_data(web::Data::new(ExecutionLedger::default()))
+                .app_data(web::Data::new(TemplateStore::default()))
+                .app_data(web::Data::new(AuditLog::default()))
+                .app_data(web::Data::new(BasketStore::default()))
+                .app_data(web::Data::new(renamer.clone()))
+                .app_data(web::Data::new(delister.clone()))
+                .app_data(web::Data::new(groups.clone()))
+                .app_data(web::Data::new(callbacks.clone()))
+                .app_data(web::Data::new(tape.clone()))
+                .app_data(web::Data::new(purger.clone()))
+                .app_data(web::Data::new(view.clone()))
+                .app_data(web::Data::new(search.clone()))
+                .app_data(web::Data::new(journal.clone()))
+                .app_data(web::Data::new(trading_stats.clone()))
+                .app_data(web::Data::new(latency.clone()))
+                .app_data(web::Data::new(fill_quality.clone()))
+                .app_data(web::Data::new(EngineConfig::default()))
+                .app_data(web::Data::new(changes.clone()))
+                .app_data(accounts.clone())
+                .app_data(web::Data::new(AdminAuth::new(ADMIN_TOKEN)))
+                .app_data(web::Data::new(Limits::default()))
+                .configure(routes::config)
+        })
+        .workers(1)
+        .listen(listener)
+        .unwrap()
+        .run();
+        actix_web::rt::spawn(server);
+
+        Self { addr, cache }
+    }
+
+    /// Waits until the orderbook has a tick for every pair in `pairs`.
+    pub async fn wait_for_feed(&self, pairs: &[&str], within: Duration) {
+        let cache = self.cache.clone();
+        let pairs: Vec<String> = pairs.iter().map(|p| p.to_string()).collect();
+        eventually(within, "oracle feed", || {
+            let (cache, pairs) = (cache.clone(), pairs.clone());
+            async move {
+                for p in &pairs {
+                    cache.get_tick(p).await?;
+                }
+                Some(())
+            }
+        })
+        .await
+    }
+
+    /// A client of the orderbook, without an API key.
+    pub fn client(&self) -> Client {
+        let mut c = Client::new(&format!("http://{}", self.addr)).unwrap();
+        c.reconnect_backoff = Duration::from_millis(50);
+        c
+    }
+
+    /// Sends one request and returns the status and JSON body (`Null` if
+    /// there is none).
+    pub async fn call(&self, method: &str, path: &str, body: Option<&Value>) -> (u16, Value) {
+        self.client().call(method, path, body).await.unwrap()
+    }
+
+    /// Like [`call`](Self::call), with the admin token.
+    pub async fn admin(&self, method: &str, path: &str, body: Option<&Value>) -> (u16, Value) {
+        self.client()
+            .with_admin_token(ADMIN_TOKEN)
+            .call(method, path, body)
+            .await
+            .unwrap()
+    }
+
+    /// The order once `done` holds for it, failing the test if it does not
+    /// within `within`.
+    pub async fn wait_for_order(
+        &self,
+        id: &str,
+        within: Duration,
+        done: impl Fn(&Value) -> bool,
+    ) -> Value {
+        let path = format!("/orders/{id}");
+        let done = &done;
+        let path = &path;
+        eventually(within, id, || async move {
+            let (status, order) = self.call("GET", path, None).await;
+            (status == 200 && done(&order)).then_some(order)
+        })
+        .await
+    }
+}
+
+/// Polls `check` until it returns something, panicking after `within`.
+pub async fn eventually<T, F, Fut>(within: Duration, what: &str, check: F) -> T
+where
+    F: Fn() -> Fut,
+    Fut: std::future::Future<Output = Option<T>>,
+{
+    let deadline = tokio::time::Instant::now() + within;
+    loop {
+        if let Some(v) = check().await {
+            return v;
+        }
+        if tokio::time::Instant::now() >= deadline {
+            panic!("{what}: not done within {within:?}");
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+}
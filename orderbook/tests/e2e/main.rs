@@ -0,0 +1,194 @@
+//! Runs the mock oracle and the orderbook together and drives them over
+//! HTTP, so a scripted price path has to travel the whole way: oracle
+//! websocket, tick cache, matcher, repository.
+
+mod harness;
+
+use std::time::Duration;
+
+use serde_json::{json, Value};
+
+use harness::Stack;
+
+/// Long enough for the feed to connect and play a short script several
+/// times over, short enough that a stuck path fails quickly.
+const DEADLINE: Duration = Duration::from_secs(10);
+
+fn decimal(v: &Value) -> f64 {
+    v.as_str()
+        .map_or_else(|| v.as_f64(), |s| s.parse().ok())
+        .unwrap()
+}
+
+#[actix_web::test]
+async fn a_falling_price_fills_a_resting_buy_and_leaves_a_high_sell() {
+    let path = [105.0, 104.0, 103.0, 102.0, 101.0, 100.0, 99.0];
+    let stack = Stack::start(&[("BTC/USDT", &path)]).await;
+    stack.wait_for_feed(&["BTC/USDT"], DEADLINE).await;
+
+    let (status, buy) = stack
+        .call(
+            "POST",
+            "/orders",
+            Some(&json!({"pair": "BTC/USDT", "side": "buy", "price": "101", "quantity": "1"})),
+        )
+        .await;
+    assert_eq!(status, 201, "{buy}");
+    let (status, sell) = stack
+        .call(
+            "POST",
+            "/orders",
+            Some(&json!({"pair": "BTC/USDT", "side": "sell", "price": "120", "quantity": "1"})),
+        )
+        .await;
+    assert_eq!(status, 201, "{sell}");
+
+    let filled = stack
+        .wait_for_order(buy["id"].as_str().unwrap(), DEADLINE, |o| {
+            o["status"] == "filled"
+        })
+        .await;
+    let fill = &filled["fills"][0];
+    assert!(decimal(&fill["price"]) <= 101.0, "{filled}");
+    assert_eq!(decimal(&fill["quantity"]), 1.0);
+    assert!(decimal(&fill["trigger_price"]) <= 101.0, "{filled}");
+    let (status, quality) = stack
+        .call("GET", "/analytics/slippage?pair=BTC/USDT", None)
+        .await;
+    assert_eq!(status, 200);
+    assert_eq!(quality[0]["order_type"], "limit", "{quality}");
+    assert_eq!(quality[0]["count"], 1, "{quality}");
+
+    // The script holds at its last price, which never reaches the sell.
+    tokio::time::sleep(harness::ORACLE_INTERVAL * 4).await;
+    let (status, sell) = stack
+        .call(
+            "GET",
+            &format!("/orders/{}", sell["id"].as_str().unwrap()),
+            None,
+        )
+        .await;
+    assert_eq!(status, 200);
+    assert!(sell["fills"].as_array().is_none_or(Vec::is_empty), "{sell}");
+    assert_ne!(sell["status"], "filled");
+}
+
+#[actix_web::test]
+async fn an_order_no_price_reaches_is_cancelled_after_its_timeout() {
+    let stack = Stack::start(&[("BTC/USDT", &[100.0])]).await;
+    stack.wait_for_feed(&["BTC/USDT"], DEADLINE).await;
+
+    let order = json!({"pair": "BTC/USDT", "side": "buy", "price": "50", "quantity": "1", "cancel_after_ms": 200});
+    let (status, placed) = stack.call("POST", "/orders", Some(&order)).await;
+    assert_eq!(status, 201, "{placed}");
+    let cancelled = stack
+        .wait_for_order(placed["id"].as_str().unwrap(), DEADLINE, |o| {
+            o["status"] == "cancelled"
+        })
+        .await;
+    assert_eq!(cancelled["cancel_reason"], "cancel_after");
+    let waited = cancelled["updated"].as_i64().unwrap() - cancelled["created"].as_i64().unwrap();
+    assert!(waited >= 200, "{cancelled}");
+}
+
+#[actix_web::test]
+async fn a_group_that_spends_its_budget_cancels_its_last_level() {
+    let path = [105.0, 104.0, 103.0, 102.0, 101.0, 100.0, 99.0];
+    let stack = Stack::start(&[("BTC/USDT", &path)]).await;
+    stack.wait_for_feed(&["BTC/USDT"], DEADLINE).await;
+
+    let mut ids = Vec::new();
+    for price in ["101", "100", "95"] {
+        let order = json!({"pair": "BTC/USDT", "side": "buy", "price": price, "quantity": "1"});
+        let (status, placed) = stack.call("POST", "/orders", Some(&order)).await;
+        assert_eq!(status, 201, "{placed}");
+        ids.push(placed["id"].as_str().unwrap().to_string());
+    }
+    let (status, group) = stack
+        .call(
+            "POST",
+            "/order-groups",
+            Some(&json!({"budget": "150", "order_ids": ids})),
+        )
+        .await;
+    assert_eq!(status, 201, "{group}");
+
+    // The first two levels fill past the budget; the price never reaches
+    // the third.
+    let cancelled = stack
+        .wait_for_order(&ids[2], DEADLINE, |o| o["status"] == "cancelled")
+        .await;
+    assert_eq!(cancelled["cancel_reason"], "group_budget");
+    let (status, group) = stack
+        .call(
+            "GET",
+            &format!("/order-groups/{}", group["id"].as_str().unwrap()),
+            None,
+        )
+        .await;
+    assert_eq!(status, 200);
+    assert_eq!(group["status"], "exhausted", "{group}");
+    assert!(decimal(&group["spent"]) >= 150.0, "{group}");
+}
+
+#[actix_web::test]
+async fn the_client_streams_prices_and_the_accounts_fills() {
+    use conditional_orderbook::{
+        client::OrderRequest, entities::order::OrderSide, oracle_service::hub::MarketData,
+    };
+    use rust_decimal_macros::dec;
+
+    let path = [105.0, 104.0, 103.0, 102.0, 101.0, 100.0, 99.0];
+    let stack = Stack::start(&[("BTC/USDT", &path)]).await;
+    stack.wait_for_feed(&["BTC/USDT"], DEADLINE).await;
+    let (status, account) = stack
+        .admin("POST", "/admin/accounts", Some(&json!({"name": "desk"})))
+        .await;
+    assert_eq!(status, 201, "{account}");
+    let client = stack
+        .client()
+        .with_api_key(account["api_key"].as_str().unwrap());
+
+    let mut prices = client.prices("btc-usdt");
+    let tick = tokio::time::timeout(DEADLINE, async {
+        loop {
+            if let MarketData::Tick(t) = prices.next().await.unwrap() {
+                return t;
+            }
+        }
+    })
+    .await
+    .unwrap();
+    assert_eq!(tick.pair, "BTC/USDT");
+
+    let mut events = client.order_events();
+    events.connect().await.unwrap();
+    let placed = client
+        .place_order(&OrderRequest::limit(
+            "BTC/USDT",
+            OrderSide::Buy,
+            dec!(101),
+            dec!(1),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(
+        placed.account_id,
+        account["account"]["id"].as_str().map(String::from)
+    );
+    let filled = tokio::time::timeout(DEADLINE, events.next_fill())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(filled.order.id, placed.id);
+    assert!(filled.fill.price <= dec!(101));
+
+    let err = client.cancel_order(&placed.id).await.unwrap_err();
+    assert!(
+        matches!(
+            err,
+            conditional_orderbook::client::ClientError::InvalidTransition(_)
+        ),
+        "{err}"
+    );
+}
@@ -0,0 +1,905 @@
+//! Pins the JSON the HTTP endpoints answer with, so a renamed field or a
+//! price that turns from a string into a number fails here before it
+//! reaches a client. There is one test per group of routes; each starts a
+//! fresh app at the same frozen time with IDs counting up from a seed, so
+//! every run makes the same bodies. The few values that stay random,
+//! secrets and timings, are redacted. The websockets, the server-sent
+//! event streams and the admin UI's files are not covered.
+//!
+//! Each snapshot is an `insta` file next to this one. A missing or changed
+//! snapshot fails the test; review the new bodies with `cargo insta
+//! review`, or accept them all with `INSTA_UPDATE=always`.
+
+use std::sync::{Arc, LazyLock};
+use std::time::Duration;
+
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceFactory, ServiceRequest, ServiceResponse};
+use actix_web::http::header::CONTENT_TYPE;
+use actix_web::test::{self, TestRequest};
+use actix_web::{middleware::from_fn, web, App};
+use rust_decimal_macros::dec;
+use serde_json::{json, Value};
+use tokio::sync::{Mutex, MutexGuard};
+
+use conditional_orderbook::{
+    accounts::{
+        purge::AccountPurger, secrets::SecretBox, statements::StatementLedger, usage::AccountUsage,
+        AccountStore,
+    },
+    engine::callbacks::OrderCallbacks,
+    engine::deadletter::DeadLetters,
+    engine::delist::PairDelister,
+    engine::downtime::{MissedTriggerPolicy, MissedTriggers},
+    engine::groups::OrderGroups,
+    engine::halts::HaltRegistry,
+    engine::housekeeping::{Housekeeper, HousekeepingPolicy},
+    engine::ledger::ExecutionLedger,
+    engine::rename::PairRenamer,
+    engine::{EngineConfig, Matchers},
+    entities::order::Fill,
+    entities::test_support::{CountingIds, FrozenClock},
+    flags::FeatureFlags,
+    instruments::InstrumentRegistry,
+    limits::{
+        admin::{AdminAuth, ADMIN_TOKEN_HEADER},
+        deadline, guard_body,
+        public::public_gate,
+        Limits,
+    },
+    metrics::{placement::PlacementTimings, Metrics},
+    oracle_service::{OracleCache, Tick},
+    repositories::{
+        audit::AuditLog,
+        baskets::BasketStore,
+        changes::{ChangeStream, Observed},
+        in_memory::InMemoryOrderRepository,
+        journal::Journal,
+        open_orders::OpenOrdersView,
+        search::OrderSearch,
+        stats::{FillQuality, TradingStats, TriggerLatency},
+        tape::TradeTape,
+        templates::TemplateStore,
+    },
+    routes,
+    state::AppState,
+    utils::{clock::set_clock, ids::set_generator},
+};
+
+/// Monday 2024-01-01 00:00:00 UTC.
+const FROZEN_AT: i64 = 1_704_067_200_000;
+
+const ADMIN: (&str, &str) = (ADMIN_TOKEN_HEADER, "snapshot-admin-token");
+
+/// Values that differ between runs however the clock and IDs are set:
+/// secrets, API key ids, and placement timings and what depends on them.
+/// Matched by key, or by `parent.key`; keys ending in `_us` always are.
+const REDACTED: [&str; 8] = [
+    "api_key",
+    "key.id",
+    "keys.id",
+    "webhook_secret",
+    "signing_secret",
+    "within_target",
+    "compliance",
+    "met",
+];
+
+/// The process has one clock, frozen here for every test.
+static CLOCK: LazyLock<Arc<FrozenClock>> = LazyLock::new(|| {
+    let clock = Arc::new(FrozenClock::at(FROZEN_AT));
+    set_clock(clock.clone());
+    clock
+});
+
+/// The clock and the ID generator are shared, so the tests take turns.
+static SERIAL: Mutex<()> = Mutex::const_new(());
+
+fn redact(parent: &str, v: &mut Value) {
+    match v {
+        Value::Object(m) => {
+            for (k, v) in m.iter_mut() {
+                let path = format!("{parent}.{k}");
+                if k.ends_with("_us") || REDACTED.iter().any(|r| *r == k || *r == path) {
+                    *v = json!("[redacted]");
+                } else {
+                    redact(k, v);
+                }
+            }
+        }
+        Value::Array(a) => a.iter_mut().for_each(|v| redact(parent, v)),
+        _ => {}
+    }
+}
+
+/// Compares `body`, with `status`, to the snapshot called `name`.
+fn assert_snapshot(name: &str, status: u16, mut body: Value) {
+    redact("", &mut body);
+    insta::with_settings!({
+        snapshot_path => ".",
+        prepend_module_to_snapshot => false,
+        omit_expression => true,
+    }, {
+        insta::assert_json_snapshot!(name, json!({"status": status, "body": body}));
+    });
+}
+
+/// Calls `app` with the request and compares the answer to the snapshot;
+/// evaluates to the unredacted body. NDJSON is kept as an array of its
+/// lines, and other bodies that are not JSON as a string.
+macro_rules! snap {
+    ($app:expr, $name:expr, $req:expr) => {{
+        let resp = test::call_service(&$app, $req.to_request()).await;
+        let status = resp.status().as_u16();
+        let ndjson = resp
+            .headers()
+            .get(CONTENT_TYPE)
+            .is_some_and(|v| v == "application/x-ndjson");
+        let bytes = test::read_body(resp).await;
+        let text = String::from_utf8_lossy(&bytes);
+        let body: Value = if ndjson {
+            text.lines()
+                .map(|l| serde_json::from_str::<Value>(l).unwrap())
+                .collect()
+        } else {
+            serde_json::from_str(&text).unwrap_or_else(|_| Value::String(text.into()))
+        };
+        assert_snapshot($name, status, body.clone());
+        body
+    }};
+}
+
+/// One app's worth of state, with a BTC/USDT tick at the frozen time.
+struct Stack {
+    _turn: MutexGuard<'static, ()>,
+    clock: Arc<FrozenClock>,
+    state: web::Data<AppState>,
+    cache: web::Data<OracleCache>,
+    instruments: web::Data<InstrumentRegistry>,
+    halts: web::Data<HaltRegistry>,
+    dead_letters: web::Data<DeadLetters>,
+    missed: web::Data<MissedTriggers>,
+    ledger: web::Data<ExecutionLedger>,
+    audit: web::Data<AuditLog>,
+    baskets: web::Data<BasketStore>,
+    groups: web::Data<OrderGroups>,
+    renamer: web::Data<PairRenamer>,
+    delister: web::Data<PairDelister>,
+    purger: web::Data<AccountPurger>,
+    view: web::Data<OpenOrdersView>,
+    search: web::Data<OrderSearch>,
+    tape: web::Data<TradeTape>,
+    journal: web::Data<Journal>,
+    trading_stats: web::Data<TradingStats>,
+    latency: web::Data<TriggerLatency>,
+    fill_quality: web::Data<FillQuality>,
+    housekeeper: web::Data<Housekeeper>,
+    changes: web::Data<ChangeStream>,
+    accounts: web::Data<AccountStore>,
+}
+
+impl Stack {
+    async fn new() -> Self {
+        let turn = SERIAL.lock().await;
+        let clock = CLOCK.clone();
+        clock.set(FROZEN_AT);
+        set_generator(Arc::new(CountingIds::seeded("snap", 1)));
+
+        let cache = OracleCache::default();
+        cache
+            .set(Tick {
+                bid: Some(dec!(99.5)),
+                ask: Some(dec!(100.5)),
+                ..Tick::new("BTC/USDT", dec!(100), FROZEN_AT)
+            })
+            .await;
+        let changes = ChangeStream::default();
+        let view = OpenOrdersView::default();
+        view.follow(&changes);
+        let journal = Journal::default();
+        journal.attach(&changes);
+        let search = OrderSearch::default();
+        search.follow(&changes);
+        let state = AppState::new(Observed::new(
+            InMemoryOrderRepository::default(),
+            changes.clone(),
+        ));
+        let orders = state.orders.clone();
+        let instruments = InstrumentRegistry::default();
+        let trading_stats = TradingStats::default();
+        trading_stats.attach(&changes);
+        let tape = TradeTape::default();
+        tape.follow(&changes);
+        let latency = TriggerLatency::default();
+        latency.attach(&changes);
+        let fill_quality = FillQuality::default();
+        fill_quality.follow(&changes);
+        let groups = OrderGroups::default();
+        groups.follow(&changes);
+        let usage = AccountUsage::default();
+        usage.follow(&changes);
+        let statements = StatementLedger::default();
+        statements.follow(&changes);
+        let (halts, baskets, audit) = (
+            HaltRegistry::default(),
+            BasketStore::default(),
+            AuditLog::default(),
+        );
+        let purger = AccountPurger::new(
+            orders.clone(),
+            journal.clone(),
+            audit.clone(),
+            baskets.clone(),
+        );
+        let delister = PairDelister::new(
+            instruments.clone(),
+            orders.clone(),
+            cache.clone(),
+            halts.clone(),
+            Matchers::default(),
+            changes.clone(),
+        );
+        let renamer = PairRenamer::new(
+            instruments.clone(),
+            orders.clone(),
+            journal.clone(),
+            cache.clone(),
+            halts.clone(),
+            baskets.clone(),
+            changes.clone(),
+        );
+        let housekeeper = Housekeeper::new(
+            orders,
+            view.clone(),
+            search.clone(),
+            None,
+            vec![HousekeepingPolicy::parse("stale|*|0 3 * * *|cancel_older_than=30d").unwrap()],
+        );
+        Self {
+            _turn: turn,
+            clock,
+            state,
+            cache: web::Data::new(cache),
+            instruments: web::Data::new(instruments),
+            halts: web::Data::new(halts),
+            dead_letters: web::Data::new(DeadLetters::with_max_failures(1)),
+            missed: web::Data::new(MissedTriggers::default()),
+            ledger: web::Data::new(ExecutionLedger::default()),
+            audit: web::Data::new(audit),
+            baskets: web::Data::new(baskets),
+            groups: web::Data::new(groups),
+            renamer: web::Data::new(renamer),
+            delister: web::Data::new(delister),
+            purger: web::Data::new(purger),
+            view: web::Data::new(view),
+            search: web::Data::new(search),
+            tape: web::Data::new(tape),
+            journal: web::Data::new(journal),
+            trading_stats: web::Data::new(trading_stats),
+            latency: web::Data::new(latency),
+            fill_quality: web::Data::new(fill_quality),
+            housekeeper: web::Data::new(housekeeper),
+            changes: web::Data::new(changes),
+            accounts: web::Data::new(
+                AccountStore::new(SecretBox::ephemeral())
+                    .with_usage(usage)
+                    .with_statements(statements),
+            ),
+        }
+    }
+
+    fn app(
+        &self,
+    ) -> App<
+        impl ServiceFactory<
+            ServiceRequest,
+            Config = (),
+            Response = ServiceResponse<impl MessageBody>,
+            Error = actix_web::Error,
+            InitError = (),
+        >,
+    > {
+        App::new()
+            .wrap(from_fn(deadline))
+            .wrap(from_fn(guard_body))
+            .wrap(from_fn(public_gate))
+            .app_data(web::Data::new(AdminAuth::new(ADMIN.1)))
+            .app_data(self.state.clone())
+            .app_data(self.cache.clone())
+            .app_data(web::Data::new(Metrics::default()))
+            .app_data(web::Data::new(PlacementTimings::default()))
+            .app_data(self.instruments.clone())
+            .app_data(self.halts.clone())
+            .app_data(self.dead_letters.clone())
+            .app_data(self.missed.clone())
+            .app_data(self.ledger.clone())
+            .app_data(web::Data::new(TemplateStore::default()))
+            .app_data(self.audit.clone())
+            .app_data(self.baskets.clone())
+            .app_data(self.groups.clone())
+            .app_data(web::Data::new(OrderCallbacks::default()))
+            .app_data(self.renamer.clone())
+            .app_data(self.delister.clone())
+            .app_data(self.purger.clone())
+            .app_data(self.view.clone())
+            .app_data(self.search.clone())
+            .app_data(self.tape.clone())
+            .app_data(self.journal.clone())
+            .app_data(self.trading_stats.clone())
+            .app_data(self.latency.clone())
+            .app_data(self.fill_quality.clone())
+            .app_data(web::Data::new(EngineConfig::default()))
+            .app_data(self.housekeeper.clone())
+            .app_data(web::Data::new(FeatureFlags::default()))
+            .app_data(self.changes.clone())
+            .app_data(self.accounts.clone())
+            .app_data(web::Data::new(Limits::default()))
+            .configure(routes::config)
+    }
+
+    /// A fill of one at 95.5, a second after the frozen time.
+    async fn fill(&self, id: &str) {
+        self.clock.advance(1_000);
+        self.state
+            .orders
+            .record_fills(
+                id,
+                &[Fill {
+                    price: dec!(95.5),
+                    quantity: dec!(1),
+                    ts_ms: FROZEN_AT + 1_000,
+                    received_ms: Some(FROZEN_AT + 990),
+                    decided_ms: Some(FROZEN_AT + 995),
+                    recovered_trigger: false,
+                    interpolated_trigger: false,
+                    legs: Vec::new(),
+                    trigger_price: Some(dec!(95.4)),
+                    fair_sources: Vec::new(),
+                    counterparty: None,
+                }],
+            )
+            .await
+            .unwrap();
+    }
+}
+
+fn limit_order(price: &str) -> TestRequest {
+    TestRequest::post().uri("/orders").set_json(json!({
+        "pair": "BTC/USDT", "side": "buy", "price": price, "quantity": "2",
+        "tags": ["desk"], "metadata": {"strategy": "dip"}
+    }))
+}
+
+fn admin(req: TestRequest) -> TestRequest {
+    req.insert_header(ADMIN)
+}
+
+/// Polls a background job until it leaves `running`.
+macro_rules! finished {
+    ($app:expr, $uri:expr) => {{
+        let mut job = Value::Null;
+        for _ in 0..100 {
+            let req = admin(TestRequest::get().uri($uri)).to_request();
+            job = test::call_and_read_body_json(&$app, req).await;
+            if job["status"] != "running" {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        job
+    }};
+}
+
+#[actix_web::test]
+async fn health_and_market_data_keep_their_json_shape() {
+    let stack = Stack::new().await;
+    let app = test::init_service(stack.app()).await;
+    snap!(app, "health", TestRequest::get().uri("/health"));
+    snap!(app, "health_ready", TestRequest::get().uri("/health/ready"));
+    snap!(app, "markets", TestRequest::get().uri("/markets"));
+    snap!(app, "market", TestRequest::get().uri("/markets/BTC/USDT"));
+    snap!(
+        app,
+        "triggers",
+        TestRequest::get().uri("/markets/BTC/USDT/triggers")
+    );
+    snap!(app, "book", TestRequest::get().uri("/book/BTC/USDT"));
+    snap!(app, "indexes", TestRequest::get().uri("/index"));
+    snap!(app, "index", TestRequest::get().uri("/index/BTC/USDT"));
+
+    let order = snap!(app, "order_created", limit_order("95.5"));
+    stack.fill(order["id"].as_str().unwrap()).await;
+    snap!(
+        app,
+        "tape",
+        TestRequest::get().uri("/markets/BTC/USDT/tape")
+    );
+    snap!(app, "metrics", TestRequest::get().uri("/metrics"));
+}
+
+#[actix_web::test]
+async fn orders_keep_their_json_shape() {
+    let stack = Stack::new().await;
+    let app = test::init_service(stack.app()).await;
+    let limit = snap!(app, "order_created", limit_order("95.5"));
+    let id = limit["id"].as_str().unwrap().to_string();
+    snap!(
+        app,
+        "stop_order_created",
+        TestRequest::post().uri("/orders").set_json(json!({
+            "pair": "BTC/USDT", "side": "sell", "order_type": "stop_limit",
+            "price": "89", "trigger_price": "90", "quantity": "1"
+        }))
+    );
+    snap!(
+        app,
+        "order_rejected",
+        TestRequest::post()
+            .uri("/orders")
+            .set_json(json!({"pair": "BTC/USDT", "side": "buy", "quantity": "1"}))
+    );
+    snap!(
+        app,
+        "order_not_found",
+        TestRequest::get().uri("/orders/nope")
+    );
+    snap!(app, "orders", TestRequest::get().uri("/orders?status=new"));
+    snap!(
+        app,
+        "orders_stream",
+        TestRequest::get().uri("/orders/stream?status=new")
+    );
+    snap!(
+        app,
+        "order_search",
+        TestRequest::get().uri("/orders/search?q=desk")
+    );
+
+    stack.fill(&id).await;
+    snap!(
+        app,
+        "order_partially_filled",
+        TestRequest::get().uri(&format!("/orders/{id}"))
+    );
+    snap!(
+        app,
+        "order_amended",
+        TestRequest::patch()
+            .uri(&format!("/orders/{id}"))
+            .set_json(json!({"price": "95"}))
+    );
+    let replaced = snap!(
+        app,
+        "order_replaced",
+        TestRequest::post()
+            .uri(&format!("/orders/{id}/replace"))
+            .set_json(json!({"price": "94"}))
+    );
+    let id = replaced["order"]["id"].as_str().unwrap().to_string();
+    snap!(
+        app,
+        "order_callbacks",
+        TestRequest::get().uri(&format!("/orders/{id}/callbacks"))
+    );
+    snap!(
+        app,
+        "order_cancelled",
+        TestRequest::post().uri(&format!("/orders/{id}/cancel"))
+    );
+    let other = snap!(app, "order_created_again", limit_order("93"));
+    snap!(
+        app,
+        "order_deleted",
+        TestRequest::delete().uri(&format!("/orders/{}", other["id"].as_str().unwrap()))
+    );
+    snap!(
+        app,
+        "oco_created",
+        TestRequest::post().uri("/orders/oco").set_json(json!({"legs": [
+            {"pair": "BTC/USDT", "side": "sell", "price": "110", "quantity": "1"},
+            {"pair": "BTC/USDT", "side": "sell", "order_type": "stop", "trigger_price": "95", "quantity": "1"}
+        ]}))
+    );
+    snap!(
+        app,
+        "bracket_created",
+        TestRequest::post().uri("/orders/bracket").set_json(json!({
+            "entry": {"pair": "BTC/USDT", "side": "buy", "price": "100", "quantity": "1"},
+            "take_profit": {"price": "110"},
+            "stop_loss": {"trigger_price": "95"}
+        }))
+    );
+}
+
+#[actix_web::test]
+async fn templates_baskets_and_groups_keep_their_json_shape() {
+    let stack = Stack::new().await;
+    let app = test::init_service(stack.app()).await;
+    let template = snap!(
+        app,
+        "template_created",
+        TestRequest::post().uri("/templates").set_json(json!({
+            "name": "btc dip", "pair": "BTC/USDT", "side": "buy", "price": "90",
+            "tags": ["swing"]
+        }))
+    );
+    let uri = format!("/templates/{}", template["id"].as_str().unwrap());
+    snap!(app, "templates", TestRequest::get().uri("/templates"));
+    snap!(app, "template", TestRequest::get().uri(&uri));
+    let order = snap!(
+        app,
+        "template_instantiated",
+        TestRequest::post().uri(&format!("{uri}/instantiate?quantity=3"))
+    );
+    snap!(app, "template_deleted", TestRequest::delete().uri(&uri));
+
+    let basket = snap!(
+        app,
+        "basket_created",
+        TestRequest::post().uri("/baskets").set_json(json!({
+            "trigger": {"pair": "BTC/USDT", "below": "60"},
+            "legs": [{"pair": "ETH/USDT", "side": "buy", "quantity": "2"}]
+        }))
+    );
+    let uri = format!("/baskets/{}", basket["id"].as_str().unwrap());
+    snap!(app, "baskets", TestRequest::get().uri("/baskets"));
+    snap!(app, "basket", TestRequest::get().uri(&uri));
+    snap!(
+        app,
+        "basket_cancelled",
+        TestRequest::post().uri(&format!("{uri}/cancel"))
+    );
+
+    let group = snap!(
+        app,
+        "order_group_created",
+        TestRequest::post().uri("/order-groups").set_json(json!({
+            "budget": "500", "order_ids": [order["id"]]
+        }))
+    );
+    snap!(app, "order_groups", TestRequest::get().uri("/order-groups"));
+    snap!(
+        app,
+        "order_group",
+        TestRequest::get().uri(&format!("/order-groups/{}", group["id"].as_str().unwrap()))
+    );
+}
+
+#[actix_web::test]
+async fn analytics_keep_their_json_shape() {
+    let stack = Stack::new().await;
+    let app = test::init_service(stack.app()).await;
+    let order: Value = test::call_and_read_body_json(&app, limit_order("95.5").to_request()).await;
+    stack.fill(order["id"].as_str().unwrap()).await;
+    snap!(app, "stats", TestRequest::get().uri("/stats"));
+    snap!(
+        app,
+        "time_to_trigger",
+        TestRequest::get().uri("/analytics/time-to-trigger")
+    );
+    snap!(
+        app,
+        "slippage",
+        TestRequest::get().uri("/analytics/slippage")
+    );
+    snap!(
+        app,
+        "fill_latency",
+        TestRequest::get().uri("/analytics/fill-latency")
+    );
+}
+
+#[actix_web::test]
+async fn accounts_keep_their_json_shape() {
+    let stack = Stack::new().await;
+    let app = test::init_service(stack.app()).await;
+    let created = snap!(
+        app,
+        "admin_account_created",
+        admin(TestRequest::post().uri("/admin/accounts")).set_json(json!({"name": "desk"}))
+    );
+    let account = created["account"]["id"].as_str().unwrap().to_string();
+    let key = (
+        "X-Api-Key",
+        created["api_key"].as_str().unwrap().to_string(),
+    );
+    let uri = format!("/admin/accounts/{account}");
+    snap!(app, "admin_account", admin(TestRequest::get().uri(&uri)));
+    let issued = snap!(
+        app,
+        "admin_key_issued",
+        admin(TestRequest::post().uri(&format!("{uri}/keys")))
+    );
+    let key_uri = format!("{uri}/keys/{}", issued["key"]["id"].as_str().unwrap());
+    let rotated = snap!(
+        app,
+        "admin_key_rotated",
+        admin(TestRequest::post().uri(&format!("{key_uri}/rotate")))
+    );
+    snap!(
+        app,
+        "admin_key_revoked",
+        admin(TestRequest::delete().uri(&format!(
+            "{uri}/keys/{}",
+            rotated["key"]["id"].as_str().unwrap()
+        )))
+    );
+    snap!(
+        app,
+        "admin_webhook_set",
+        admin(TestRequest::put().uri(&format!("{uri}/webhook")))
+            .set_json(json!({"url": "https://example.com/hooks"}))
+    );
+    snap!(
+        app,
+        "admin_webhook_rotated",
+        admin(TestRequest::post().uri(&format!("{uri}/webhook/rotate")))
+    );
+    snap!(
+        app,
+        "admin_secrets_resealed",
+        admin(TestRequest::post().uri("/admin/secrets/reseal"))
+    );
+
+    let order: Value = test::call_and_read_body_json(
+        &app,
+        limit_order("95.5").insert_header(key.clone()).to_request(),
+    )
+    .await;
+    stack.fill(order["id"].as_str().unwrap()).await;
+    snap!(
+        app,
+        "account",
+        TestRequest::get()
+            .uri("/account")
+            .insert_header(key.clone())
+    );
+    snap!(
+        app,
+        "account_usage",
+        TestRequest::get()
+            .uri(&format!("/accounts/{account}/usage"))
+            .insert_header(key.clone())
+    );
+    snap!(
+        app,
+        "account_statement",
+        TestRequest::get()
+            .uri(&format!("/accounts/{account}/statements?period=2024-01"))
+            .insert_header(key.clone())
+    );
+    snap!(
+        app,
+        "account_statement_csv",
+        TestRequest::get()
+            .uri(&format!(
+                "/accounts/{account}/statements?period=2024-01&format=csv"
+            ))
+            .insert_header(key)
+    );
+
+    let job = snap!(
+        app,
+        "admin_purge_started",
+        admin(TestRequest::delete().uri(&format!("{uri}/data?mode=anonymize")))
+    );
+    let job_uri = format!("{uri}/data/jobs/{}", job["id"].as_str().unwrap());
+    assert_snapshot("admin_purge_job", 200, finished!(app, &job_uri));
+}
+
+#[actix_web::test]
+async fn admin_order_tools_keep_their_json_shape() {
+    let stack = Stack::new().await;
+    let app = test::init_service(stack.app()).await;
+    let mut ids = Vec::new();
+    for price in ["95.5", "95", "94.5", "94"] {
+        let order: Value =
+            test::call_and_read_body_json(&app, limit_order(price).to_request()).await;
+        ids.push(order["id"].as_str().unwrap().to_string());
+    }
+    stack.fill(&ids[0]).await;
+    stack
+        .dead_letters
+        .record_failure(&ids[1], "BTC/USDT", "record_fills", "store down", FROZEN_AT);
+    stack.missed.mark(
+        "BTC/USDT",
+        [ids[2].as_str()],
+        MissedTriggerPolicy::ManualReview,
+        FROZEN_AT - 60_000,
+    );
+    for seq in [0, 1] {
+        stack
+            .ledger
+            .claim(&ids[0], seq, "BTC/USDT", FROZEN_AT, dec!(1), FROZEN_AT)
+            .unwrap();
+    }
+
+    snap!(
+        app,
+        "admin_orphaned",
+        admin(TestRequest::get().uri("/admin/orders/orphaned"))
+    );
+    snap!(
+        app,
+        "admin_orphaned_cancelled",
+        admin(TestRequest::post().uri("/admin/orders/orphaned/cancel?min_age_hours=1"))
+    );
+    snap!(
+        app,
+        "admin_dead_letters",
+        admin(TestRequest::get().uri("/admin/orders/deadletter"))
+    );
+    snap!(
+        app,
+        "admin_missed_triggers",
+        admin(TestRequest::get().uri("/admin/orders/missed-triggers"))
+    );
+    snap!(
+        app,
+        "admin_missed_trigger_not_flagged",
+        admin(TestRequest::post().uri(&format!("/admin/orders/{}/release", ids[2])))
+            .set_json(json!({"policy": "require_fresh_crossing"}))
+    );
+    snap!(
+        app,
+        "admin_unresolved_executions",
+        admin(TestRequest::get().uri("/admin/executions/unresolved"))
+    );
+    snap!(
+        app,
+        "admin_execution_committed",
+        admin(TestRequest::post().uri(&format!("/admin/executions/{}/0/resolve", ids[0])))
+    );
+    snap!(
+        app,
+        "admin_execution_aborted",
+        admin(TestRequest::post().uri(&format!("/admin/executions/{}/1/resolve", ids[0])))
+    );
+    snap!(
+        app,
+        "admin_reconciled",
+        admin(TestRequest::post().uri("/admin/orders/reconcile")).set_json(json!({"orders": [
+            {"order_id": ids[3], "external_status": "filled", "external_fill_qty": "2"}
+        ]}))
+    );
+    let req = admin(TestRequest::post().uri("/admin/reevaluate")).set_json(json!({
+        "pair": "BTC/USDT", "from_ms": FROZEN_AT, "to_ms": FROZEN_AT + 1_000,
+        "ticks": [{"pair": "BTC/USDT", "price": "94", "ts_ms": FROZEN_AT + 500}]
+    }));
+    let mut body: Value = test::call_and_read_body_json(&app, req.to_request()).await;
+    // Active orders come out of the store in no particular order.
+    body["would_trigger"]
+        .as_array_mut()
+        .unwrap()
+        .sort_by_key(|t| t["order_id"].to_string());
+    assert_snapshot("admin_reevaluated", 200, body);
+    snap!(
+        app,
+        "admin_order_suspended",
+        admin(TestRequest::post().uri(&format!("/admin/orders/{}/suspend", ids[1])))
+            .set_json(json!({"reason": "client asked", "operator": "ops"}))
+    );
+    snap!(
+        app,
+        "admin_order_transitioned",
+        admin(TestRequest::post().uri(&format!("/admin/orders/{}/transition", ids[1])))
+            .set_json(json!({"status": "open", "reason": "client asked"}))
+    );
+    snap!(
+        app,
+        "admin_order_cancelled",
+        admin(TestRequest::post().uri(&format!("/admin/orders/{}/cancel", ids[1])))
+            .set_json(json!({"reason": "client asked"}))
+    );
+    snap!(
+        app,
+        "admin_order_status_forced",
+        admin(TestRequest::put().uri(&format!("/admin/orders/{}/status?force=true", ids[1])))
+            .set_json(json!({"status": "open", "reason": "undo"}))
+    );
+    snap!(
+        app,
+        "admin_audit",
+        admin(TestRequest::get().uri("/admin/audit"))
+    );
+}
+
+#[actix_web::test]
+async fn admin_market_tools_keep_their_json_shape() {
+    let stack = Stack::new().await;
+    let app = test::init_service(stack.app()).await;
+    let order: Value = test::call_and_read_body_json(&app, limit_order("95.5").to_request()).await;
+    snap!(
+        app,
+        "admin_halt",
+        admin(TestRequest::post().uri("/admin/pairs/BTC/USDT/halt"))
+            .set_json(json!({"reason": "maintenance"}))
+    );
+    snap!(
+        app,
+        "admin_stats",
+        admin(TestRequest::get().uri("/admin/stats"))
+    );
+    snap!(
+        app,
+        "admin_resume",
+        admin(TestRequest::post().uri("/admin/pairs/BTC/USDT/resume"))
+    );
+    snap!(
+        app,
+        "admin_flag_set",
+        admin(TestRequest::put().uri("/admin/flags/crossing"))
+            .set_json(json!({"enabled": true, "pair": "BTC/USDT"}))
+    );
+    snap!(
+        app,
+        "admin_flags",
+        admin(TestRequest::get().uri("/admin/flags"))
+    );
+    snap!(
+        app,
+        "admin_flag_cleared",
+        admin(TestRequest::delete().uri("/admin/flags/crossing?pair=BTC/USDT"))
+    );
+    snap!(
+        app,
+        "admin_placement_slo",
+        admin(TestRequest::get().uri("/admin/slo/placement"))
+    );
+    snap!(
+        app,
+        "admin_housekeeping",
+        admin(TestRequest::get().uri("/admin/housekeeping"))
+    );
+    snap!(
+        app,
+        "admin_housekeeping_run",
+        admin(TestRequest::post().uri("/admin/housekeeping/stale/run"))
+    );
+    snap!(
+        app,
+        "admin_housekeeping_runs",
+        admin(TestRequest::get().uri("/admin/housekeeping/runs"))
+    );
+    let export = snap!(
+        app,
+        "admin_journal_export",
+        admin(TestRequest::get().uri("/admin/journal/export"))
+    );
+    snap!(
+        app,
+        "admin_journal_import",
+        admin(TestRequest::post().uri("/admin/journal/import")).set_payload(
+            export
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(Value::to_string)
+                .collect::<Vec<_>>()
+                .join("\n")
+        )
+    );
+    snap!(
+        app,
+        "admin_rename",
+        admin(TestRequest::post().uri("/admin/pairs/BTC/USDT/rename"))
+            .set_json(json!({"to": "XBT/USDT", "dry_run": true}))
+    );
+    let job = snap!(
+        app,
+        "admin_delist_started",
+        admin(TestRequest::post().uri("/admin/markets/BTC/USDT/delist"))
+    );
+    let job_uri = format!("/admin/delistings/{}", job["id"].as_str().unwrap());
+    assert_snapshot("admin_delist_job", 200, finished!(app, &job_uri));
+    snap!(
+        app,
+        "admin_delisted",
+        admin(TestRequest::get().uri("/admin/markets/delisted"))
+    );
+    snap!(
+        app,
+        "order_after_delisting",
+        TestRequest::get().uri(&format!("/orders/{}", order["id"].as_str().unwrap()))
+    );
+}